@@ -0,0 +1,244 @@
+//! Mirrors executed commands to the system log, for shared machines where
+//! an admin needs a record outside Dexter's own files. Safety overrides
+//! are also always kept in Dexter's own `audit.log`.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use dexter_plugins::command_exec::exit_code_of;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::redaction::redact_sensitive_text;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AuditPolicy {
+    /// Sends one line per executed command to syslog/journald through
+    /// `logger`, or to the Windows Application event log.
+    #[serde(default)]
+    pub system_log: bool,
+}
+
+/// One command, logged as it starts and again when it ends.
+#[derive(Debug, Clone, Copy)]
+pub struct AuditEvent<'a> {
+    pub plugin: &'a str,
+    pub command: &'a str,
+    pub cwd: &'a Path,
+    /// Remote host name for runs over SSH; `None` for local runs.
+    pub host: Option<&'a str>,
+}
+
+impl AuditPolicy {
+    /// Logs that `event`'s command is about to run, so a run that never
+    /// ends (a crash, a killed terminal) still leaves an entry.
+    pub fn record_start(&self, event: &AuditEvent) {
+        if !self.system_log {
+            return;
+        }
+        if let Err(e) = write_system_log(&start_line(event), true) {
+            tracing::warn!(error = %e, "failed to write the audit log entry");
+        }
+    }
+
+    /// Logs `event` with how it ended. Failing to reach the system log is
+    /// only traced: the command has already run.
+    pub fn record(&self, event: &AuditEvent, result: &Result<String>) {
+        if !self.system_log {
+            return;
+        }
+        if let Err(e) = write_system_log(&audit_line(event, result), result.is_ok()) {
+            tracing::warn!(error = %e, "failed to write the audit log entry");
+        }
+    }
+
+    /// Logs that `event`'s command was let past the safety rule `rule`,
+    /// with the justification the user gave, before it runs. The entry in
+    /// `audit.log` is written whatever `system_log` says, and failing to
+    /// write it is an error: the command must not run without it.
+    pub fn record_override(
+        &self,
+        event: &AuditEvent,
+        rule: &str,
+        justification: &str,
+    ) -> Result<()> {
+        let line = override_line(event, rule, justification);
+        let path = audit_file_path().context("Could not find data directory")?;
+        append_audit_file(&path, &line)?;
+        if self.system_log {
+            if let Err(e) = write_system_log(&line, false) {
+                tracing::warn!(error = %e, "failed to write the audit log entry");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `<data dir>/dexter/audit.log`: Dexter's own record of overrides.
+pub fn audit_file_path() -> Option<PathBuf> {
+    crate::paths::data_dir().map(|dir| dir.join("dexter").join("audit.log"))
+}
+
+/// Appends `line` with a timestamp. One write per line, so concurrent
+/// instances don't interleave inside it.
+fn append_audit_file(path: &Path, line: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    file.write_all(format!("{} {}\n", Utc::now().to_rfc3339(), line).as_bytes())?;
+    Ok(())
+}
+
+/// The logged line. The command comes last since it has spaces of its own.
+fn audit_line(event: &AuditEvent, result: &Result<String>) -> String {
+    let exit = match result {
+        Ok(_) => "0".to_string(),
+        Err(e) => exit_code_of(e).map_or_else(|| "-".to_string(), |code| code.to_string()),
+    };
+    format!(
+        "user={} host={} cwd={} plugin={} exit={} status={} command={}",
+        current_user(),
+        event.host.unwrap_or("local"),
+        event.cwd.display(),
+        event.plugin,
+        exit,
+        if result.is_ok() { "ok" } else { "failed" },
+        redact_sensitive_text(event.command)
+    )
+}
+
+fn start_line(event: &AuditEvent) -> String {
+    format!(
+        "user={} host={} cwd={} plugin={} status=started command={}",
+        current_user(),
+        event.host.unwrap_or("local"),
+        event.cwd.display(),
+        event.plugin,
+        redact_sensitive_text(event.command)
+    )
+}
+
+fn override_line(event: &AuditEvent, rule: &str, justification: &str) -> String {
+    format!(
+        "user={} host={} cwd={} plugin={} status=override rule={:?} justification={:?} command={}",
+        current_user(),
+        event.host.unwrap_or("local"),
+        event.cwd.display(),
+        event.plugin,
+        rule,
+        justification,
+        redact_sensitive_text(event.command)
+    )
+}
+
+fn current_user() -> String {
+    ["USER", "USERNAME", "LOGNAME"]
+        .iter()
+        .find_map(|name| std::env::var(name).ok().filter(|user| !user.is_empty()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn write_system_log(line: &str, success: bool) -> Result<()> {
+    let program = if cfg!(windows) {
+        "eventcreate"
+    } else {
+        "logger"
+    };
+    crate::replay::refuse_spawn(program)?;
+    let mut command = if cfg!(windows) {
+        let level = if success { "INFORMATION" } else { "WARNING" };
+        let mut command = Command::new(program);
+        command.args([
+            "/L",
+            "APPLICATION",
+            "/SO",
+            "Dexter",
+            "/ID",
+            "100",
+            "/T",
+            level,
+        ]);
+        command.args(["/D", line]);
+        command
+    } else {
+        let priority = if success {
+            "user.notice"
+        } else {
+            "user.warning"
+        };
+        let mut command = Command::new(program);
+        command.args(["-t", "dexter", "-p", priority, "--", line]);
+        command
+    };
+    let output = command.output()?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "{}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_carry_the_run_and_hide_secrets() {
+        let event = AuditEvent {
+            plugin: "yt-dlp",
+            command: "yt-dlp --cookies cookies.txt https://a.com/v",
+            cwd: Path::new("/srv/media"),
+            host: None,
+        };
+        let line = start_line(&event);
+        assert!(line.contains(" host=local cwd=/srv/media plugin=yt-dlp status=started "));
+        assert!(line.ends_with("command=yt-dlp --cookies [REDACTED] https://a.com/v"));
+
+        let line = audit_line(&event, &Ok(String::new()));
+        assert!(line.starts_with("user="));
+        assert!(line.contains(" host=local cwd=/srv/media plugin=yt-dlp exit=0 status=ok "));
+        assert!(line.ends_with("command=yt-dlp --cookies [REDACTED] https://a.com/v"));
+
+        let remote = AuditEvent {
+            host: Some("nas"),
+            ..event
+        };
+        let line = audit_line(&remote, &Err(anyhow!("spawn failed")));
+        assert!(line.contains(" host=nas "));
+        assert!(line.contains(" exit=- status=failed "));
+
+        let line = override_line(
+            &event,
+            "Command blocked: shell composition is not allowed",
+            "piping to \"tee\" is fine here",
+        );
+        assert!(line.contains(
+            " status=override rule=\"Command blocked: shell composition is not allowed\" \
+             justification=\"piping to \\\"tee\\\" is fine here\" command="
+        ));
+    }
+
+    #[test]
+    fn overrides_append_to_the_audit_file() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("dexter").join("audit.log");
+        append_audit_file(&path, "first")?;
+        append_audit_file(&path, "second")?;
+        let content = std::fs::read_to_string(&path)?;
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with(" first"));
+        assert!(lines[1].ends_with(" second"));
+        Ok(())
+    }
+}
@@ -0,0 +1,262 @@
+//! `dexter bench`: sends the same small set of prompts to every configured
+//! route and measures how each one answers, so the route lists can be
+//! ordered by data rather than guesswork.
+
+use std::time::{Duration, Instant};
+
+use crate::config::{Config, ModelRoute};
+use crate::llm::{CachePolicy, LlmClient};
+
+/// The standard prompt set: a routing decision, a short command and a
+/// longer one, as (system prompt, user input).
+pub const BENCH_PROMPTS: &[(&str, &str)] = &[
+    (
+        "Pick the tool for the request. Reply with JSON only: {\"plugin\": \"<name>\"}. Tools: ffmpeg, pandoc, f2, qpdf.",
+        "convert every .mkv here to mp4",
+    ),
+    (
+        "You write one shell command for ffmpeg. Reply with the command only.",
+        "Files: 1. talk.mkv 2. notes.md\nRequest: extract the audio of talk.mkv as mp3",
+    ),
+    (
+        "You write one shell command for f2, the batch renamer. Reply with the command only, then one line explaining each flag.",
+        "Files: 1. IMG_0001.jpg 2. IMG_0002.jpg 3. IMG_0003.jpg 4. scan 1.png 5. scan 2.png\nRequest: rename the jpgs to holiday-001.jpg and so on, lowercase the scans and replace spaces with dashes",
+    ),
+];
+
+/// Requests each route gets unless `--rounds` says otherwise: every prompt
+/// this many times.
+pub const DEFAULT_ROUNDS: usize = 2;
+
+/// How one route did.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchResult {
+    pub route: ModelRoute,
+    /// Latency of every answered request.
+    pub latencies: Vec<Duration>,
+    pub failures: usize,
+    /// Output tokens the provider reported across all answers.
+    pub output_tokens: u64,
+    /// Time spent on the answers that came with their usage; answers
+    /// without it don't count toward tokens per second.
+    pub reported_time: Duration,
+    pub last_error: Option<String>,
+}
+
+impl BenchResult {
+    pub fn new(route: ModelRoute) -> Self {
+        Self {
+            route,
+            latencies: Vec::new(),
+            failures: 0,
+            output_tokens: 0,
+            reported_time: Duration::ZERO,
+            last_error: None,
+        }
+    }
+
+    pub fn requests(&self) -> usize {
+        self.latencies.len() + self.failures
+    }
+
+    /// Share of requests that failed, 0.0 to 1.0.
+    pub fn failure_rate(&self) -> f64 {
+        if self.requests() == 0 {
+            return 0.0;
+        }
+        self.failures as f64 / self.requests() as f64
+    }
+
+    /// Nearest-rank percentile of the answered requests; `None` without any.
+    pub fn percentile(&self, percent: f64) -> Option<Duration> {
+        if self.latencies.is_empty() {
+            return None;
+        }
+        let mut sorted = self.latencies.clone();
+        sorted.sort();
+        let rank = (percent / 100.0 * sorted.len() as f64).ceil() as usize;
+        Some(sorted[rank.clamp(1, sorted.len()) - 1])
+    }
+
+    /// Reported output tokens per second of waiting; `None` when the
+    /// provider reported no usage.
+    pub fn tokens_per_sec(&self) -> Option<f64> {
+        (self.reported_time > Duration::ZERO)
+            .then(|| self.output_tokens as f64 / self.reported_time.as_secs_f64())
+    }
+}
+
+/// The router's and the executor's routes, each once, in the order they
+/// are tried.
+pub fn bench_routes(config: &Config) -> Vec<ModelRoute> {
+    let providers = config.llm_providers();
+    let router = LlmClient::with_routes(
+        providers.clone(),
+        config.models.router_routes.clone(),
+        config.models.router_model.clone(),
+        config.models.router_fallback_models.clone(),
+    );
+    let executor = LlmClient::with_routes(
+        providers,
+        config.models.executor_routes.clone(),
+        config.models.executor_model.clone(),
+        config.models.executor_fallback_models.clone(),
+    );
+    let mut routes: Vec<ModelRoute> = Vec::new();
+    for route in router.routes().into_iter().chain(executor.routes()) {
+        if !routes.contains(&route) {
+            routes.push(route);
+        }
+    }
+    routes
+}
+
+/// Sends every prompt of `BENCH_PROMPTS` `rounds` times to `route` alone,
+/// past the response cache, one request at a time. The requests count
+/// toward the spend in `usage.json` like any others.
+pub async fn bench_route(config: &Config, route: ModelRoute, rounds: usize) -> BenchResult {
+    let client = LlmClient::for_route(config.llm_providers(), route.clone())
+        .with_spend_tracking(config.pricing.clone());
+    let mut result = BenchResult::new(route);
+    for _ in 0..rounds {
+        for (system, input) in BENCH_PROMPTS {
+            let started = Instant::now();
+            match client
+                .completion_with_usage(system, input, CachePolicy::Bypass)
+                .await
+            {
+                Ok(reply) => {
+                    let latency = started.elapsed();
+                    result.latencies.push(latency);
+                    if let Some(tokens) = reply.output_tokens {
+                        result.output_tokens += tokens;
+                        result.reported_time += latency;
+                    }
+                }
+                Err(e) => {
+                    result.failures += 1;
+                    result.last_error = Some(e.to_string());
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Routes that answered every request, fastest median first: the order
+/// the route lists could use.
+pub fn suggested_order(results: &[BenchResult]) -> Vec<&ModelRoute> {
+    let mut reliable: Vec<&BenchResult> = results
+        .iter()
+        .filter(|result| result.failures == 0 && !result.latencies.is_empty())
+        .collect();
+    reliable.sort_by_key(|result| result.percentile(50.0));
+    reliable.into_iter().map(|result| &result.route).collect()
+}
+
+pub const TABLE_HEADER: [&str; 7] = ["ROUTE", "REQ", "P50", "P90", "P99", "FAIL", "TOK/S"];
+
+/// One table row per result, in `TABLE_HEADER`'s columns.
+pub fn table_row(result: &BenchResult) -> [String; 7] {
+    let latency = |percent| {
+        result
+            .percentile(percent)
+            .map(|d| format!("{:.2}s", d.as_secs_f64()))
+            .unwrap_or_else(|| "-".to_string())
+    };
+    [
+        format!(
+            "{} / {}",
+            result.route.provider.display_name(),
+            result.route.model
+        ),
+        result.requests().to_string(),
+        latency(50.0),
+        latency(90.0),
+        latency(99.0),
+        format!("{:.0}%", result.failure_rate() * 100.0),
+        result
+            .tokens_per_sec()
+            .map(|rate| format!("{:.1}", rate))
+            .unwrap_or_else(|| "-".to_string()),
+    ]
+}
+
+/// The results as a plain text table with aligned columns.
+pub fn format_table(results: &[BenchResult]) -> String {
+    let rows: Vec<[String; 7]> = std::iter::once(TABLE_HEADER.map(str::to_string))
+        .chain(results.iter().map(table_row))
+        .collect();
+    let widths: Vec<usize> = (0..TABLE_HEADER.len())
+        .map(|col| {
+            rows.iter()
+                .map(|row| row[col].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProviderKind;
+
+    fn result(model: &str, millis: &[u64], failures: usize) -> BenchResult {
+        BenchResult {
+            latencies: millis.iter().map(|ms| Duration::from_millis(*ms)).collect(),
+            failures,
+            output_tokens: 40 * millis.len() as u64,
+            reported_time: millis.iter().map(|ms| Duration::from_millis(*ms)).sum(),
+            ..BenchResult::new(ModelRoute {
+                provider: ProviderKind::OpenAI,
+                model: model.to_string(),
+            })
+        }
+    }
+
+    #[test]
+    fn reports_percentiles_failures_and_order() {
+        let slow = result("gpt-4o", &[900, 1100, 1000, 4000], 0);
+        assert_eq!(slow.percentile(50.0), Some(Duration::from_millis(1000)));
+        assert_eq!(slow.percentile(90.0), Some(Duration::from_millis(4000)));
+        assert_eq!(slow.tokens_per_sec(), Some(160.0 / 7.0));
+        let unreported = BenchResult {
+            output_tokens: 0,
+            reported_time: Duration::ZERO,
+            ..slow.clone()
+        };
+        assert_eq!(unreported.tokens_per_sec(), None);
+
+        let flaky = result("gpt-4o-mini", &[300, 500], 2);
+        assert_eq!(flaky.failure_rate(), 0.5);
+        let fast = result("gpt-4.1-nano", &[200, 250], 0);
+        let down = result("o3", &[], 6);
+        assert_eq!(down.percentile(50.0), None);
+
+        let results = [slow, flaky, fast, down];
+        let order: Vec<&str> = suggested_order(&results)
+            .iter()
+            .map(|route| route.model.as_str())
+            .collect();
+        assert_eq!(order, ["gpt-4.1-nano", "gpt-4o"]);
+
+        let table = format_table(&results);
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[0].starts_with("ROUTE") && lines[0].ends_with("FAIL  TOK/S"));
+        assert!(lines[2].contains("gpt-4o-mini") && lines[2].contains("50%"));
+        assert!(lines[4].ends_with("6    -      -      -      100%  -"));
+    }
+}
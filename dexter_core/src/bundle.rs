@@ -0,0 +1,708 @@
+//! Shareable bundles: recipes (intent templates), pinned commands and
+//! per-plugin presets in one TOML or JSON file, so a team can pass around
+//! the workflows it has vetted. Importing merges a bundle into config.toml
+//! and the pins; an entry that exists here with other contents is a
+//! conflict, settled one by one by the caller. Plugin settings and pandoc
+//! presets change what runs, so new ones are only added once the caller
+//! approves them, and presets that would run other programs never are.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use dexter_plugins::{default_pandoc_presets, program_running_arg, PandocPreset, PluginSettings};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::config::{Config, IntentTemplate};
+use crate::executor::{add_pins, load_pins};
+use crate::{HistoryEntry, PinnedHistoryEntry};
+
+/// Format version written into every bundle.
+pub const BUNDLE_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Bundle {
+    #[serde(default = "bundle_version")]
+    pub version: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recipes: Vec<IntentTemplate>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pins: Vec<BundledPin>,
+    /// `[plugins.<name>]` settings: default flags, prompt additions.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub plugins: BTreeMap<String, PluginSettings>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pandoc_presets: Vec<PandocPreset>,
+}
+
+fn bundle_version() -> u32 {
+    BUNDLE_VERSION
+}
+
+/// A pinned command without the history entry it was pinned on, which
+/// only exists on the machine that ran it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct BundledPin {
+    pub plugin: String,
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intent: Option<String>,
+}
+
+/// What goes into an export. Empty lists mean everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BundleSelection {
+    /// Recipes by name.
+    pub recipes: Vec<String>,
+    /// Plugins whose pins and presets are included.
+    pub plugins: Vec<String>,
+    pub pins: bool,
+}
+
+/// What happens to a bundle entry whose name exists with other contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportConflict {
+    /// Keep the local one.
+    Skip,
+    /// Take the bundle's.
+    Replace,
+    /// Keep both, the bundle's under a new name. Plugin settings have one
+    /// name per plugin and are skipped instead.
+    Rename,
+}
+
+/// One bundle entry that clashes with a local one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// `recipe Shrink video`, `plugin ffmpeg`, `pandoc preset thesis`.
+    pub key: String,
+    pub local: String,
+    pub incoming: String,
+}
+
+/// Plugin settings or a pandoc preset the bundle would add: they change
+/// the commands Dexter generates and runs, so they wait for approval.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewSetting {
+    /// `plugin ffmpeg`, `pandoc preset thesis`.
+    pub key: String,
+    /// The entry as it would be written to config.toml.
+    pub incoming: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    /// Plugin settings and presets list what they set: `plugin ffmpeg
+    /// (default flags: -hide_banner)`.
+    pub added: Vec<String>,
+    pub replaced: Vec<String>,
+    /// Conflicts that kept the local entry, and new settings not approved.
+    pub skipped: Vec<String>,
+    /// Presets that would run another program, with the argument.
+    pub rejected: Vec<String>,
+    /// Entries already here as they are in the bundle.
+    pub unchanged: Vec<String>,
+}
+
+impl ImportReport {
+    pub fn summary(&self) -> String {
+        if self.added.is_empty()
+            && self.replaced.is_empty()
+            && self.skipped.is_empty()
+            && self.rejected.is_empty()
+        {
+            return "Nothing new in the bundle.".to_string();
+        }
+        let mut out = Vec::new();
+        for (label, keys) in [
+            ("Added", &self.added),
+            ("Replaced", &self.replaced),
+            ("Skipped", &self.skipped),
+            ("Rejected", &self.rejected),
+        ] {
+            out.extend(keys.iter().map(|key| format!("{}: {}", label, key)));
+        }
+        if !self.unchanged.is_empty() {
+            out.push(format!("{} already present.", self.unchanged.len()));
+        }
+        out.join("\n")
+    }
+}
+
+impl Bundle {
+    /// The selected parts of `config` and `pins`. Pandoc presets are only
+    /// included where they differ from the built-in ones.
+    pub fn collect(
+        config: &Config,
+        pins: &[PinnedHistoryEntry],
+        selection: &BundleSelection,
+    ) -> Result<Self> {
+        if let Some(missing) = selection
+            .recipes
+            .iter()
+            .find(|name| !config.templates.iter().any(|t| &t.name == *name))
+        {
+            return Err(anyhow!("No recipe named {:?} in config.toml", missing));
+        }
+        let wanted = |plugin: &str| {
+            selection.plugins.is_empty() || selection.plugins.iter().any(|p| p == plugin)
+        };
+        let recipes = config
+            .templates
+            .iter()
+            .filter(|t| selection.recipes.is_empty() || selection.recipes.contains(&t.name))
+            .cloned()
+            .collect();
+        let mut bundled: Vec<BundledPin> = Vec::new();
+        for pin in pins
+            .iter()
+            .filter(|pin| selection.pins && wanted(&pin.plugin))
+        {
+            let pin = BundledPin {
+                plugin: pin.plugin.clone(),
+                command: pin.command.clone(),
+                intent: pin.intent.clone(),
+            };
+            if !bundled.contains(&pin) {
+                bundled.push(pin);
+            }
+        }
+        let plugins = config
+            .plugins
+            .iter()
+            .filter(|(name, settings)| wanted(name) && !settings.is_default())
+            .map(|(name, settings)| (name.clone(), settings.clone()))
+            .collect();
+        let builtin = default_pandoc_presets();
+        let pandoc_presets = config
+            .pandoc_presets
+            .iter()
+            .filter(|preset| wanted("pandoc") && !builtin.contains(preset))
+            .cloned()
+            .collect();
+        Ok(Self {
+            version: BUNDLE_VERSION,
+            recipes,
+            pins: bundled,
+            plugins,
+            pandoc_presets,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.recipes.is_empty()
+            && self.pins.is_empty()
+            && self.plugins.is_empty()
+            && self.pandoc_presets.is_empty()
+    }
+
+    /// JSON for `.json` paths, TOML for anything else.
+    pub fn render(&self, path: &Path) -> Result<String> {
+        if is_json(path) {
+            Ok(serde_json::to_string_pretty(self)?)
+        } else {
+            Ok(toml::to_string_pretty(self)?)
+        }
+    }
+
+    pub fn parse(raw: &str, path: &Path) -> Result<Self> {
+        let bundle: Self = if is_json(path) {
+            serde_json::from_str(raw).context("Invalid bundle JSON")?
+        } else {
+            toml::from_str(raw).context("Invalid bundle TOML")?
+        };
+        if bundle.version > BUNDLE_VERSION {
+            return Err(anyhow!(
+                "Bundle version {} is newer than this Dexter understands ({}); update Dexter",
+                bundle.version,
+                BUNDLE_VERSION
+            ));
+        }
+        Ok(bundle)
+    }
+
+    /// `config` with the bundle merged in, the pins to add and what
+    /// changed. `resolve` settles each conflict, and `approve` decides on
+    /// each new plugin setting and pandoc preset. New recipes are appended
+    /// in bundle order; a renamed copy sits right after the local recipe
+    /// it clashed with.
+    pub fn merge_into(
+        &self,
+        config: &Config,
+        pins: &[PinnedHistoryEntry],
+        mut resolve: impl FnMut(&Conflict) -> ImportConflict,
+        mut approve: impl FnMut(&NewSetting) -> bool,
+    ) -> Result<(Config, Vec<BundledPin>, ImportReport)> {
+        let mut config = config.clone();
+        let mut report = ImportReport::default();
+
+        for recipe in &self.recipes {
+            let key = format!("recipe {}", recipe.name);
+            let existing = config.templates.iter().position(|t| t.name == recipe.name);
+            let Some(idx) = existing else {
+                config.templates.push(recipe.clone());
+                report.added.push(key);
+                continue;
+            };
+            let local = config.templates[idx].text.clone();
+            if local == recipe.text {
+                report.unchanged.push(key);
+                continue;
+            }
+            let conflict = Conflict {
+                key: key.clone(),
+                local,
+                incoming: recipe.text.clone(),
+            };
+            match resolve(&conflict) {
+                ImportConflict::Skip => report.skipped.push(key),
+                ImportConflict::Replace => {
+                    config.templates[idx].text = recipe.text.clone();
+                    report.replaced.push(key);
+                }
+                ImportConflict::Rename => {
+                    let name = free_name(&recipe.name, |name| {
+                        config.templates.iter().any(|t| t.name == name)
+                    });
+                    report.added.push(format!("recipe {}", name));
+                    config.templates.insert(
+                        idx + 1,
+                        IntentTemplate {
+                            name,
+                            text: recipe.text.clone(),
+                        },
+                    );
+                }
+            }
+        }
+
+        for (plugin, settings) in &self.plugins {
+            let key = format!("plugin {}", plugin);
+            let local = config.plugins.get(plugin).cloned().unwrap_or_default();
+            if local == *settings {
+                report.unchanged.push(key);
+                continue;
+            }
+            let listed = format!("{} ({})", key, settings_summary(settings));
+            if local.is_default() {
+                let setting = NewSetting {
+                    key,
+                    incoming: toml::to_string(settings)?,
+                };
+                if approve(&setting) {
+                    config.plugins.insert(plugin.clone(), settings.clone());
+                    report.added.push(listed);
+                } else {
+                    report.skipped.push(setting.key);
+                }
+                continue;
+            }
+            let conflict = Conflict {
+                key: key.clone(),
+                local: toml::to_string(&local)?,
+                incoming: toml::to_string(settings)?,
+            };
+            match resolve(&conflict) {
+                ImportConflict::Replace => {
+                    config.plugins.insert(plugin.clone(), settings.clone());
+                    report.replaced.push(listed);
+                }
+                ImportConflict::Skip | ImportConflict::Rename => report.skipped.push(key),
+            }
+        }
+
+        for preset in &self.pandoc_presets {
+            let key = format!("pandoc preset {}", preset.name);
+            let existing = config
+                .pandoc_presets
+                .iter()
+                .position(|p| p.name == preset.name);
+            if existing.is_some_and(|idx| config.pandoc_presets[idx] == *preset) {
+                report.unchanged.push(key);
+                continue;
+            }
+            if let Some(arg) = program_running_arg(&preset.args) {
+                report
+                    .rejected
+                    .push(format!("{} (runs another program: {})", key, arg));
+                continue;
+            }
+            let Some(idx) = existing else {
+                let setting = NewSetting {
+                    key,
+                    incoming: toml::to_string(preset)?,
+                };
+                if approve(&setting) {
+                    config.pandoc_presets.push(preset.clone());
+                    report.added.push(preset_summary(&preset.name, preset));
+                } else {
+                    report.skipped.push(setting.key);
+                }
+                continue;
+            };
+            let conflict = Conflict {
+                key: key.clone(),
+                local: config.pandoc_presets[idx].args.join(" "),
+                incoming: preset.args.join(" "),
+            };
+            match resolve(&conflict) {
+                ImportConflict::Skip => report.skipped.push(key),
+                ImportConflict::Replace => {
+                    config.pandoc_presets[idx] = preset.clone();
+                    report.replaced.push(preset_summary(&preset.name, preset));
+                }
+                ImportConflict::Rename => {
+                    let name = free_name(&preset.name, |name| {
+                        config.pandoc_presets.iter().any(|p| p.name == name)
+                    });
+                    report.added.push(preset_summary(&name, preset));
+                    config.pandoc_presets.push(PandocPreset {
+                        name,
+                        ..preset.clone()
+                    });
+                }
+            }
+        }
+
+        // A pin is the command itself: one already pinned is never a
+        // conflict, whatever intent it was pinned with.
+        let mut new_pins = Vec::new();
+        for pin in &self.pins {
+            let key = format!("pin {}: {}", pin.plugin, pin.command);
+            let pinned = pins
+                .iter()
+                .any(|p| p.plugin == pin.plugin && p.command == pin.command)
+                || new_pins.contains(pin);
+            if pinned {
+                report.unchanged.push(key);
+            } else {
+                new_pins.push(pin.clone());
+                report.added.push(key);
+            }
+        }
+        Ok((config, new_pins, report))
+    }
+}
+
+/// What plugin settings set, for the import report.
+fn settings_summary(settings: &PluginSettings) -> String {
+    let mut parts = Vec::new();
+    if !settings.default_flags.is_empty() {
+        parts.push(format!("default flags: {}", settings.default_flags));
+    }
+    if let Some(dir) = &settings.output_dir {
+        parts.push(format!("output dir: {}", dir));
+    }
+    if settings.never_overwrite {
+        parts.push("never overwrite".to_string());
+    }
+    for rule in &settings.prompt_append {
+        parts.push(format!("prompt rule: {}", rule));
+    }
+    if let Some(prompt) = &settings.prompt_override {
+        parts.push(format!(
+            "prompt override: {}",
+            prompt.split_whitespace().collect::<Vec<_>>().join(" ")
+        ));
+    }
+    match parts.is_empty() {
+        true => "defaults".to_string(),
+        false => parts.join("; "),
+    }
+}
+
+/// `pandoc preset thesis (--toc --pdf-engine=xelatex)`.
+fn preset_summary(name: &str, preset: &PandocPreset) -> String {
+    format!("pandoc preset {} ({})", name, preset.args.join(" "))
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+/// `name (imported)`, or `name (imported 2)` and on when that is taken.
+fn free_name(name: &str, taken: impl Fn(&str) -> bool) -> String {
+    (1..)
+        .map(|n| match n {
+            1 => format!("{} (imported)", name),
+            n => format!("{} (imported {})", name, n),
+        })
+        .find(|candidate| !taken(candidate))
+        .unwrap_or_default()
+}
+
+/// Writes the selected parts of the config and pins to `path`.
+pub async fn export_bundle(path: &Path, selection: &BundleSelection) -> Result<Bundle> {
+    let config = Config::load().await?;
+    let bundle = Bundle::collect(&config, &load_pins().await?, selection)?;
+    if bundle.is_empty() {
+        return Err(anyhow!(
+            "Nothing to export: no recipes, pins or presets selected"
+        ));
+    }
+    tokio::fs::write(path, bundle.render(path)?).await?;
+    Ok(bundle)
+}
+
+/// Merges the bundle at `path` into config.toml and the pins. `dry_run`
+/// only reports what would change.
+pub async fn import_bundle(
+    path: &Path,
+    resolve: impl FnMut(&Conflict) -> ImportConflict,
+    approve: impl FnMut(&NewSetting) -> bool,
+    dry_run: bool,
+) -> Result<ImportReport> {
+    let raw = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Could not read {}", path.display()))?;
+    let bundle = Bundle::parse(&raw, path)?;
+    let config = Config::load().await?;
+    let (merged, pins, report) =
+        bundle.merge_into(&config, &load_pins().await?, resolve, approve)?;
+    if dry_run {
+        return Ok(report);
+    }
+    let config_changed = report
+        .added
+        .iter()
+        .chain(&report.replaced)
+        .any(|key| !key.starts_with("pin "));
+    if config_changed {
+        merged.save().await?;
+    }
+    let pinned_at = Utc::now().to_rfc3339();
+    let pins = pins
+        .into_iter()
+        .map(|pin| PinnedHistoryEntry {
+            timestamp: HistoryEntry::new(&pin.plugin, &pin.command).timestamp,
+            plugin: pin.plugin,
+            command: pin.command,
+            pinned_at: pinned_at.clone(),
+            intent: pin.intent,
+        })
+        .collect();
+    add_pins(pins).await?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recipe(name: &str, text: &str) -> IntentTemplate {
+        IntentTemplate {
+            name: name.to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    fn pin(plugin: &str, command: &str) -> PinnedHistoryEntry {
+        PinnedHistoryEntry {
+            timestamp: "2026-01-01T00:00:00+00:00".to_string(),
+            plugin: plugin.to_string(),
+            command: command.to_string(),
+            pinned_at: "2026-01-02T00:00:00+00:00".to_string(),
+            intent: Some("shrink it".to_string()),
+        }
+    }
+
+    #[test]
+    fn exports_the_selection_and_reads_it_back() {
+        let mut config = Config {
+            templates: vec![
+                recipe("Shrink video", "compress {video} to under {size} MB"),
+                recipe("Private", "rename my {thing}"),
+            ],
+            ..Config::default()
+        };
+        config.plugins.insert(
+            "ffmpeg".to_string(),
+            PluginSettings {
+                default_flags: "-hide_banner".to_string(),
+                ..PluginSettings::default()
+            },
+        );
+        let pins = [
+            pin("ffmpeg", "ffmpeg -i a.mkv -crf 28 a.mp4"),
+            pin("qpdf", "qpdf --linearize in.pdf out.pdf"),
+        ];
+        let selection = BundleSelection {
+            recipes: vec!["Shrink video".to_string()],
+            plugins: vec!["ffmpeg".to_string()],
+            pins: true,
+        };
+        let bundle = Bundle::collect(&config, &pins, &selection).unwrap();
+        assert_eq!(bundle.recipes.len(), 1);
+        assert_eq!(bundle.pins.len(), 1);
+        assert!(bundle.pandoc_presets.is_empty());
+        assert_eq!(bundle.plugins["ffmpeg"].default_flags, "-hide_banner");
+
+        for path in ["team.toml", "team.json"] {
+            let raw = bundle.render(Path::new(path)).unwrap();
+            assert_eq!(Bundle::parse(&raw, Path::new(path)).unwrap(), bundle);
+        }
+        let missing = BundleSelection {
+            recipes: vec!["Nope".to_string()],
+            ..selection
+        };
+        assert!(Bundle::collect(&config, &pins, &missing).is_err());
+        assert!(Bundle::parse("version = 9", Path::new("x.toml")).is_err());
+    }
+
+    #[test]
+    fn import_settles_each_conflict() {
+        let mut config = Config {
+            templates: vec![recipe("Shrink video", "compress {video}")],
+            ..Config::default()
+        };
+        config.plugins.insert(
+            "ffmpeg".to_string(),
+            PluginSettings {
+                never_overwrite: true,
+                ..PluginSettings::default()
+            },
+        );
+        let bundle = Bundle {
+            version: BUNDLE_VERSION,
+            recipes: vec![
+                recipe("Shrink video", "compress {video} to {size} MB"),
+                recipe("OCR scans", "make {pdf} searchable"),
+            ],
+            pins: vec![
+                BundledPin {
+                    plugin: "ffmpeg".to_string(),
+                    command: "ffmpeg -i a.mkv a.mp4".to_string(),
+                    intent: None,
+                },
+                BundledPin {
+                    plugin: "qpdf".to_string(),
+                    command: "qpdf --linearize in.pdf out.pdf".to_string(),
+                    intent: None,
+                },
+            ],
+            plugins: BTreeMap::from([(
+                "ffmpeg".to_string(),
+                PluginSettings {
+                    default_flags: "-hide_banner".to_string(),
+                    ..PluginSettings::default()
+                },
+            )]),
+            pandoc_presets: Vec::new(),
+        };
+        let pins = [pin("ffmpeg", "ffmpeg -i a.mkv a.mp4")];
+
+        let mut asked = Vec::new();
+        let (merged, new_pins, report) = bundle
+            .merge_into(
+                &config,
+                &pins,
+                |conflict| {
+                    asked.push(conflict.key.clone());
+                    ImportConflict::Rename
+                },
+                |_| true,
+            )
+            .unwrap();
+        assert_eq!(asked, ["recipe Shrink video", "plugin ffmpeg"]);
+        let names: Vec<&str> = merged.templates.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["Shrink video", "Shrink video (imported)", "OCR scans"]
+        );
+        assert_eq!(
+            report.added,
+            [
+                "recipe Shrink video (imported)",
+                "recipe OCR scans",
+                "pin qpdf: qpdf --linearize in.pdf out.pdf"
+            ]
+        );
+        assert!(merged.plugins["ffmpeg"].never_overwrite);
+        assert_eq!(new_pins.len(), 1);
+        assert_eq!(new_pins[0].plugin, "qpdf");
+        assert_eq!(report.skipped, ["plugin ffmpeg"]);
+        assert_eq!(report.unchanged, ["pin ffmpeg: ffmpeg -i a.mkv a.mp4"]);
+
+        let (merged, _, report) = bundle
+            .merge_into(&config, &pins, |_| ImportConflict::Replace, |_| true)
+            .unwrap();
+        assert_eq!(merged.templates[0].text, "compress {video} to {size} MB");
+        assert_eq!(merged.plugins["ffmpeg"].default_flags, "-hide_banner");
+        assert_eq!(
+            report.replaced,
+            [
+                "recipe Shrink video",
+                "plugin ffmpeg (default flags: -hide_banner)"
+            ]
+        );
+    }
+
+    #[test]
+    fn new_settings_wait_for_approval_and_program_presets_are_rejected() {
+        let preset = |name: &str, args: &[&str]| PandocPreset {
+            name: name.to_string(),
+            description: String::new(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            required_files: Vec::new(),
+            required_fonts: Vec::new(),
+        };
+        let bundle = Bundle {
+            version: BUNDLE_VERSION,
+            recipes: Vec::new(),
+            pins: Vec::new(),
+            plugins: BTreeMap::from([(
+                "pandoc".to_string(),
+                PluginSettings {
+                    default_flags: "--standalone".to_string(),
+                    prompt_override: Some("Write any command.\n{request}".to_string()),
+                    ..PluginSettings::default()
+                },
+            )]),
+            pandoc_presets: vec![
+                preset("thesis", &["--toc", "--number-sections"]),
+                preset("counted", &["-F", "x"]),
+                preset("fancy", &["--pdf-engine=./payload"]),
+                preset("lua", &["-Lwordcount.lua"]),
+            ],
+        };
+        let config = Config::default();
+        let rejected = [
+            "pandoc preset counted (runs another program: -F)",
+            "pandoc preset fancy (runs another program: --pdf-engine=./payload)",
+            "pandoc preset lua (runs another program: -Lwordcount.lua)",
+        ];
+
+        let mut asked = Vec::new();
+        let (merged, _, report) = bundle
+            .merge_into(
+                &config,
+                &[],
+                |_| ImportConflict::Replace,
+                |setting| {
+                    asked.push(setting.key.clone());
+                    false
+                },
+            )
+            .unwrap();
+        assert_eq!(asked, ["plugin pandoc", "pandoc preset thesis"]);
+        assert!(merged.plugins.is_empty());
+        assert_eq!(merged.pandoc_presets, config.pandoc_presets);
+        assert_eq!(report.skipped, asked);
+        assert_eq!(report.rejected, rejected);
+
+        let (merged, _, report) = bundle
+            .merge_into(&config, &[], |_| ImportConflict::Replace, |_| true)
+            .unwrap();
+        assert_eq!(
+            report.added,
+            [
+                "plugin pandoc (default flags: --standalone; prompt override: Write any command. {request})",
+                "pandoc preset thesis (--toc --number-sections)",
+            ]
+        );
+        assert_eq!(report.rejected, rejected);
+        assert!(merged.pandoc_presets.iter().all(|p| p.name != "counted"));
+        assert!(report.summary().contains("Rejected: pandoc preset fancy"));
+    }
+}
@@ -0,0 +1,315 @@
+use anyhow::{anyhow, Context, Result};
+use dexter_plugins::f2::{f2_batches, F2_MODE_FLAGS};
+use dexter_plugins::ExecMode;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+/// One planned rename as reported by `f2 --json`, relative to the cwd.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedRename {
+    pub source: PathBuf,
+    pub target: PathBuf,
+}
+
+/// Names that would end up sharing a path on a case-insensitive filesystem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenameCollision {
+    pub dir: PathBuf,
+    pub names: Vec<String>,
+}
+
+/// Guards f2 renames against collisions that only exist on case-insensitive
+/// filesystems (default macOS APFS, Windows NTFS, exFAT mounts), where f2's
+/// own conflict detection compares names byte-for-byte.
+pub struct CollisionChecker;
+
+impl CollisionChecker {
+    /// Simulates `cmd` with `f2 --json` in `cwd` and returns every collision.
+    /// An empty result means nothing was found; an error means the simulation
+    /// itself could not be run or understood, so the check is inconclusive.
+    pub async fn check_f2(cmd: &str, cwd: &Path) -> Result<Vec<RenameCollision>> {
+        if !is_case_insensitive_dir(cwd) {
+            return Ok(Vec::new());
+        }
+
+        let renames = Self::plan_f2(cmd, cwd).await?;
+
+        let mut existing = HashMap::new();
+        for dir in renames.iter().filter_map(|r| r.target.parent()) {
+            if existing.contains_key(dir) {
+                continue;
+            }
+            existing.insert(dir.to_path_buf(), list_dir_names(&cwd.join(dir)));
+        }
+        Ok(find_case_collisions(&renames, &existing))
+    }
+
+    /// The renames `cmd` would perform in `cwd`, from `f2 --json`, run in
+    /// the same batches as the command itself.
+    pub async fn plan_f2(cmd: &str, cwd: &Path) -> Result<Vec<PlannedRename>> {
+        let argv = f2_simulation_argv(cmd)?;
+        crate::replay::refuse_spawn(&argv[0])?;
+        let mut renames = Vec::new();
+        for batch in f2_batches(&argv, cwd) {
+            let output = Command::new(&batch[0])
+                .args(&batch[1..])
+                .current_dir(cwd)
+                .output()
+                .await
+                .context("Failed to run f2 simulation")?;
+            // f2 exits non-zero when it detects its own conflicts but still
+            // prints the plan, so only the JSON decides.
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            renames.extend(parse_f2_json(&stdout)?);
+        }
+        Ok(renames)
+    }
+}
+
+pub fn format_collisions(collisions: &[RenameCollision]) -> String {
+    let mut out = vec![format!(
+        "Rename blocked: {} case-insensitive filename collision(s) detected.",
+        collisions.len()
+    )];
+    for collision in collisions {
+        let dir = if collision.dir.as_os_str().is_empty() {
+            ".".to_string()
+        } else {
+            collision.dir.display().to_string()
+        };
+        out.push(format!("- in {}: {}", dir, collision.names.join(" <-> ")));
+    }
+    out.join("\n")
+}
+
+fn f2_simulation_argv(cmd: &str) -> Result<Vec<String>> {
+    let mut argv = shell_words::split(cmd).map_err(|e| anyhow!("Invalid command syntax: {}", e))?;
+    if argv.first().map(String::as_str) != Some("f2") {
+        return Err(anyhow!("Not an f2 command"));
+    }
+    F2_MODE_FLAGS.apply_to(&mut argv, ExecMode::Preview);
+    for flag in ["--json", "--no-color"] {
+        if !argv.iter().any(|a| a == flag) {
+            argv.push(flag.to_string());
+        }
+    }
+    Ok(argv)
+}
+
+/// Accepts both a bare change list and `{ "changes": [...] }`, and the field
+/// spellings used across f2 releases (`source`/`target`, `original`/`new`).
+fn parse_f2_json(raw: &str) -> Result<Vec<PlannedRename>> {
+    let start = raw
+        .find(['{', '['])
+        .ok_or_else(|| anyhow!("f2 produced no JSON output"))?;
+    let value: serde_json::Value = serde_json::Deserializer::from_str(&raw[start..])
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("f2 produced no JSON output"))?
+        .context("f2 JSON output is invalid")?;
+
+    let changes = match &value {
+        serde_json::Value::Array(items) => items,
+        serde_json::Value::Object(map) => match map.get("changes") {
+            Some(serde_json::Value::Array(items)) => items,
+            Some(serde_json::Value::Null) | None => return Ok(Vec::new()),
+            Some(_) => return Err(anyhow!("f2 JSON `changes` is not a list")),
+        },
+        _ => return Err(anyhow!("Unexpected f2 JSON shape")),
+    };
+
+    let field = |item: &serde_json::Value, keys: &[&str]| -> Option<String> {
+        keys.iter()
+            .find_map(|k| item.get(*k).and_then(|v| v.as_str()))
+            .map(str::to_string)
+    };
+
+    let mut out = Vec::new();
+    for item in changes {
+        let (Some(source), Some(target)) = (
+            field(item, &["source", "original", "old"]),
+            field(item, &["target", "new"]),
+        ) else {
+            return Err(anyhow!("f2 JSON change is missing source/target"));
+        };
+        let base = field(item, &["base_dir", "dir"]).unwrap_or_default();
+        let base = Path::new(&base);
+        out.push(PlannedRename {
+            source: base.join(source),
+            target: base.join(target),
+        });
+    }
+    Ok(out)
+}
+
+/// Computes the post-rename contents of every affected directory and reports
+/// names that differ only by case (plus exact duplicate targets).
+fn find_case_collisions(
+    renames: &[PlannedRename],
+    existing: &HashMap<PathBuf, Vec<String>>,
+) -> Vec<RenameCollision> {
+    let sources: HashSet<&Path> = renames.iter().map(|r| r.source.as_path()).collect();
+    let mut collisions = Vec::new();
+
+    let dirs: BTreeMap<&Path, Vec<&PlannedRename>> =
+        renames.iter().fold(BTreeMap::new(), |mut acc, r| {
+            let dir = r.target.parent().unwrap_or(Path::new(""));
+            acc.entry(dir).or_insert_with(Vec::new).push(r);
+            acc
+        });
+
+    for (dir, planned) in dirs {
+        // Folded name -> distinct final names that map onto it.
+        let mut folded: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut add = |name: String| {
+            let names = folded.entry(name.to_lowercase()).or_default();
+            names.push(name);
+        };
+
+        for name in existing.get(dir).into_iter().flatten() {
+            if !sources.contains(dir.join(name).as_path()) {
+                add(name.clone());
+            }
+        }
+        for rename in planned {
+            if let Some(name) = rename.target.file_name() {
+                add(name.to_string_lossy().into_owned());
+            }
+        }
+
+        for (_, mut names) in folded {
+            if names.len() > 1 {
+                names.sort();
+                collisions.push(RenameCollision {
+                    dir: dir.to_path_buf(),
+                    names,
+                });
+            }
+        }
+    }
+
+    collisions
+}
+
+fn list_dir_names(dir: &Path) -> Vec<String> {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| e.file_name().into_string().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Probes `dir` by looking up an existing entry with its case flipped. Falls
+/// back to the platform default when the directory has no cased names.
+fn is_case_insensitive_dir(dir: &Path) -> bool {
+    let platform_default = cfg!(any(target_os = "macos", windows));
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return platform_default;
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        let flipped: String = name
+            .chars()
+            .map(|c| {
+                if c.is_lowercase() {
+                    c.to_uppercase().next().unwrap_or(c)
+                } else {
+                    c.to_lowercase().next().unwrap_or(c)
+                }
+            })
+            .collect();
+        if flipped != name {
+            return dir.join(flipped).exists();
+        }
+    }
+    platform_default
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn rename(source: &str, target: &str) -> PlannedRename {
+        PlannedRename {
+            source: PathBuf::from(source),
+            target: PathBuf::from(target),
+        }
+    }
+
+    #[test]
+    fn simulation_argv_strips_exec_and_requests_json() {
+        assert_eq!(
+            f2_simulation_argv(r#"f2 -f "a" -r "b" -x"#).unwrap(),
+            vec!["f2", "-f", "a", "-r", "b", "--json", "--no-color"]
+        );
+        assert_eq!(
+            f2_simulation_argv(r#"f2 -f "a" -r "b" --exec=true"#).unwrap(),
+            vec!["f2", "-f", "a", "-r", "b", "--json", "--no-color"]
+        );
+        assert!(f2_simulation_argv("ffmpeg -i a.mp4").is_err());
+    }
+
+    #[test]
+    fn parses_both_f2_json_shapes() {
+        let wrapped =
+            r#"{"dry_run":true,"changes":[{"source":"A.JPG","target":"a.jpg","base_dir":"pics"}]}"#;
+        assert_eq!(
+            parse_f2_json(wrapped).unwrap(),
+            vec![rename("pics/A.JPG", "pics/a.jpg")]
+        );
+
+        let bare = r#"warning: something
+[{"original":"x.txt","new":"y.txt"}]"#;
+        assert_eq!(parse_f2_json(bare).unwrap(), vec![rename("x.txt", "y.txt")]);
+        assert!(parse_f2_json(r#"{"changes":null}"#).unwrap().is_empty());
+        assert!(parse_f2_json("no json here").is_err());
+    }
+
+    #[test]
+    fn detects_case_only_collisions() {
+        let existing = HashMap::from([(
+            PathBuf::new(),
+            vec![
+                "IMG_1.jpg".to_string(),
+                "img_2.JPG".to_string(),
+                "Notes.txt".to_string(),
+            ],
+        )]);
+        let renames = vec![
+            rename("img_2.JPG", "Img_1.jpg"),
+            rename("Notes.txt", "notes.txt"),
+        ];
+
+        let collisions = find_case_collisions(&renames, &existing);
+        assert_eq!(
+            collisions,
+            vec![RenameCollision {
+                dir: PathBuf::new(),
+                names: vec!["IMG_1.jpg".to_string(), "Img_1.jpg".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn case_change_of_the_same_file_is_not_a_collision() {
+        let existing = HashMap::from([(PathBuf::new(), vec!["Photo.JPG".to_string()])]);
+        let renames = vec![rename("Photo.JPG", "photo.jpg")];
+        assert!(find_case_collisions(&renames, &existing).is_empty());
+    }
+
+    #[test]
+    fn case_probe_matches_filesystem_behaviour() -> Result<()> {
+        let dir = tempdir()?;
+        std::fs::File::create(dir.path().join("Probe.txt"))?;
+        let expected = dir.path().join("pROBE.TXT").exists();
+        assert_eq!(is_case_insensitive_dir(dir.path()), expected);
+        Ok(())
+    }
+}
@@ -1,32 +1,183 @@
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
+use dexter_plugins::{default_pandoc_presets, PandocPreset, PluginSettings, YtDlpPolicy};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
 
+use crate::audit::AuditPolicy;
+use crate::budget::{ModelPrice, TokenBudget};
+use crate::context::DEFAULT_CONTEXT_FILES;
+use crate::network::MeteredPolicy;
+use crate::oauth::OAuthSettings;
+use crate::ocr::OcrPolicy;
+use crate::opener::OpenPolicy;
+use crate::router::DEFAULT_CLARIFY_OPTIONS;
+use crate::safety::SafetyPolicy;
+use crate::sandbox::SandboxPolicy;
+use crate::sync::SyncPolicy;
+use crate::trash::TrashPolicy;
+use crate::update::UpdatePolicy;
+use crate::verify::VerifyPolicy;
+
+mod document;
+mod migrate;
+mod validation;
+pub use migrate::{migrate_content, MigrationReport, CONFIG_VERSION};
+pub use validation::{format_issues, parse_lenient, ConfigField, ConfigIssue, THEME_NAMES};
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Config {
+    /// Schema version the file was written with; older files are upgraded
+    /// on load (see `config::migrate`).
+    #[serde(default = "default_version")]
+    pub version: u32,
     #[serde(default)]
     pub providers: Vec<ProviderConfig>,
     #[serde(default)]
-    pub api_keys: ApiKeys,
-    #[serde(default)]
     pub models: ModelPreferences,
     #[serde(default = "default_theme")]
     pub theme: String,
+    #[serde(default)]
+    pub remotes: Vec<RemoteHost>,
+    #[serde(default)]
+    pub templates: Vec<IntentTemplate>,
+    /// Favorite directories the TUI's Ctrl+B switcher moves between.
+    #[serde(default)]
+    pub bookmarks: Vec<Bookmark>,
+    /// Where the TUI opens; `dexter --history` and `--recipe` override it.
+    #[serde(default)]
+    pub startup: StartupMode,
+    #[serde(default)]
+    pub thumbnails: ThumbnailMode,
+    #[serde(default = "default_pandoc_presets")]
+    pub pandoc_presets: Vec<PandocPreset>,
+    #[serde(default)]
+    pub ytdlp: YtDlpPolicy,
+    #[serde(default)]
+    pub output_conflicts: ConflictPolicy,
+    #[serde(default)]
+    pub output_layout: OutputLayout,
+    /// How many corrected commands the TUI asks for after a failed run, each
+    /// confirmed like a fresh proposal. Off (`0`) unless set.
+    #[serde(default)]
+    pub auto_repair_attempts: u32,
+    #[serde(default)]
+    pub token_budget: TokenBudget,
+    /// Per-model prices that replace or extend the built-in table used for
+    /// cost estimates.
+    #[serde(default)]
+    pub pricing: Vec<ModelPrice>,
+    #[serde(default)]
+    pub trash: TrashPolicy,
+    /// Draws toggles, markers, borders and scrollbars with ASCII only and
+    /// turns off the spinner, conveyor belt and blinking cursor, for
+    /// terminals or locales that mis-render Unicode.
+    #[serde(default)]
+    pub plain_ui: bool,
+    /// Routes with the keyword rules alone and sends generation to local
+    /// Ollama providers only; nothing leaves the machine.
+    #[serde(default)]
+    pub offline: bool,
+    /// Whether downloads run while the connection is metered.
+    #[serde(default)]
+    pub metered: MeteredPolicy,
+    /// Language for clarify questions, critic issues and other text the
+    /// models write for the user: `auto` follows the intent's language,
+    /// anything else (`German`, `日本語`) is used as given.
+    #[serde(default = "default_language")]
+    pub language: String,
+    /// Copies every executed command to the system log.
+    #[serde(default)]
+    pub audit: AuditPolicy,
+    /// Plugins whose commands run under bubblewrap, firejail or
+    /// `sandbox-exec`, confined to the working directory.
+    #[serde(default)]
+    pub sandbox: SandboxPolicy,
+    /// Checks and checksums conversion outputs after successful runs.
+    #[serde(default)]
+    pub verify: VerifyPolicy,
+    /// Where `dexter sync` keeps settings and pins shared between machines.
+    #[serde(default)]
+    pub sync: SyncPolicy,
+    /// How many accepted runs with similar requests the executor sees as
+    /// examples, drawn from the history. `0` turns them off.
+    #[serde(default = "default_history_examples")]
+    pub history_examples: usize,
+    /// How long a preview waits for confirmation in the TUI.
+    #[serde(default)]
+    pub confirm_timeout: ConfirmTimeout,
+    /// Files a generation prompt lists by name. Larger directories are
+    /// summarized (counts per extension, examples, outliers) and only the
+    /// files that best match the request are named.
+    #[serde(default = "default_context_file_limit")]
+    pub context_file_limit: usize,
+    /// Choices a clarify question offers at most, whether the keyword rules
+    /// or the router model asks it. At least two.
+    #[serde(default = "default_clarify_options")]
+    pub clarify_options: usize,
+    /// Lines of a run's output the TUI's result view shows. Longer output
+    /// is cut there and saved whole to `<data dir>/dexter/output/`. `0`
+    /// shows everything.
+    #[serde(default = "default_output_line_limit")]
+    pub output_line_limit: usize,
+    /// CPU-only jobs `dexter batch` runs at once. Jobs on a hardware
+    /// encoder run one at a time whatever this is; `1` runs every job in
+    /// turn.
+    #[serde(default = "default_parallel_jobs")]
+    pub parallel_jobs: usize,
+    /// Whether the TUI looks for new Dexter releases.
+    #[serde(default)]
+    pub updates: UpdatePolicy,
+    /// Settings for single plugins, by plugin name: `[plugins.ffmpeg]`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub plugins: BTreeMap<String, PluginSettings>,
+    /// Reads the text in scanned images for rename requests.
+    #[serde(default)]
+    pub ocr: OcrPolicy,
+    /// The OPEN FOLDER, PLAY and COPY PATHS actions after a run.
+    #[serde(default)]
+    pub open: OpenPolicy,
+    /// Danger patterns, path containment and plugin policies for every
+    /// project.
+    #[serde(default)]
+    pub safety: SafetyPolicy,
+}
+
+fn default_version() -> u32 {
+    CONFIG_VERSION
 }
 
 fn default_theme() -> String {
     "auto".to_string()
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
-pub struct ApiKeys {
-    pub gemini: Option<String>,
-    pub deepseek: Option<String>,
-    pub base_url: Option<String>, // Override for OpenAI-compatible endpoints
+fn default_language() -> String {
+    "auto".to_string()
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+fn default_history_examples() -> usize {
+    3
+}
+
+fn default_context_file_limit() -> usize {
+    DEFAULT_CONTEXT_FILES
+}
+
+fn default_clarify_options() -> usize {
+    DEFAULT_CLARIFY_OPTIONS
+}
+
+fn default_output_line_limit() -> usize {
+    200
+}
+
+fn default_parallel_jobs() -> usize {
+    4
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
 pub enum ProviderKind {
     #[serde(rename = "openai")]
@@ -141,6 +292,10 @@ pub enum ProviderAuth {
     ApiKey,
     XApiKey,
     None,
+    /// Short-lived tokens from the provider's `oauth` gateway, signed in
+    /// with a device code and refreshed as they expire.
+    #[serde(rename = "oauth")]
+    OAuth,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -158,6 +313,18 @@ pub struct ProviderConfig {
     pub enabled: bool,
     #[serde(default)]
     pub models: Vec<String>,
+    /// `http://`, `https://`, `socks5://` or `socks5h://` proxy for this
+    /// provider, or `system` to honour `HTTPS_PROXY`/`ALL_PROXY`. Without
+    /// it requests go out directly.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// PEM file with extra root certificates, for endpoints behind a
+    /// TLS-inspecting proxy or signed by a private CA.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_cert: Option<String>,
+    /// Endpoints of the gateway that issues tokens for `auth = "oauth"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oauth: Option<OAuthSettings>,
 }
 
 fn default_enabled() -> bool {
@@ -174,6 +341,9 @@ impl ProviderConfig {
             auth: kind.default_auth(),
             enabled: true,
             models: kind.default_models(),
+            proxy: None,
+            ca_cert: None,
+            oauth: None,
         }
     }
 
@@ -205,6 +375,8 @@ impl ProviderConfig {
 
         self.api_key = clean_optional(self.api_key.take());
         self.name = clean_optional(self.name.take());
+        self.proxy = clean_optional(self.proxy.take());
+        self.ca_cert = clean_optional(self.ca_cert.take());
 
         if self.auth == ProviderAuth::Bearer {
             self.auth = self.kind.default_auth();
@@ -220,6 +392,7 @@ impl ProviderConfig {
 
         match self.auth {
             ProviderAuth::None => true,
+            ProviderAuth::OAuth => self.oauth.is_some(),
             ProviderAuth::Bearer | ProviderAuth::ApiKey | ProviderAuth::XApiKey => self
                 .api_key
                 .as_ref()
@@ -243,6 +416,38 @@ pub struct ModelPreferences {
     pub router_routes: Vec<ModelRoute>,
     #[serde(default)]
     pub executor_routes: Vec<ModelRoute>,
+    /// Model that reviews each generated command before its preview;
+    /// empty turns the review off.
+    #[serde(default)]
+    pub critic_model: String,
+    #[serde(default)]
+    pub router_strategy: RouterStrategy,
+    #[serde(default)]
+    pub routing: RoutingMode,
+}
+
+/// How the router prompt is sent to its routes. `race` sends it to the
+/// first two at once and keeps the first usable answer, trading a second
+/// request for less waiting on a slow or flaky provider.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RouterStrategy {
+    #[default]
+    Fallback,
+    Race,
+}
+
+/// How a request is matched to a plugin. `rules` and `ask` never call the
+/// router model; `hybrid` calls it only when the keyword rules don't settle
+/// on one plugin. Offline mode turns `llm` and `hybrid` into `rules`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RoutingMode {
+    #[default]
+    Llm,
+    Rules,
+    Hybrid,
+    Ask,
 }
 
 fn default_router_model() -> String {
@@ -262,6 +467,9 @@ impl Default for ModelPreferences {
             executor_fallback_models: Vec::new(),
             router_routes: Vec::new(),
             executor_routes: Vec::new(),
+            critic_model: String::new(),
+            router_strategy: RouterStrategy::default(),
+            routing: RoutingMode::default(),
         }
     }
 }
@@ -272,37 +480,271 @@ pub struct ModelRoute {
     pub model: String,
 }
 
+impl ModelRoute {
+    /// Local models and the small tiers of hosted families. They answer the
+    /// short routing prompt quickly and cheaply, so setup suggests them for
+    /// the router and leaves the larger ones to write commands.
+    pub fn is_lightweight(&self) -> bool {
+        if self.provider == ProviderKind::Ollama {
+            return true;
+        }
+        let model = self.model.to_lowercase();
+        [
+            "mini", "flash", "lite", "haiku", "nano", "small", "instant", "8b",
+        ]
+        .iter()
+        .any(|tier| model.contains(tier))
+    }
+}
+
+/// An SSH host that can be selected as the execution target. Authentication is
+/// left entirely to the user's ssh setup (agent, keys, `~/.ssh/config`).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct RemoteHost {
+    pub name: String,
+    pub host: String,
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// Remote directory used for context scanning and as the working directory
+    /// of executed commands. Defaults to the login directory.
+    #[serde(default)]
+    pub workdir: Option<String>,
+}
+
+impl RemoteHost {
+    pub fn destination(&self) -> String {
+        match clean_optional(self.user.clone()) {
+            Some(user) => format!("{}@{}", user, self.host.trim()),
+            None => self.host.trim().to_string(),
+        }
+    }
+
+    pub fn workdir(&self) -> String {
+        clean_optional(self.workdir.clone()).unwrap_or_else(|| ".".to_string())
+    }
+}
+
+/// Inline preview of the first affected media file before confirmation.
+/// `auto` picks kitty, iTerm2 or sixel graphics from the terminal's
+/// environment and falls back to ASCII art everywhere else.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ThumbnailMode {
+    #[default]
+    Auto,
+    Ascii,
+    Off,
+}
+
+/// What to do when a generated command would overwrite an existing file.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Stop at the confirmation screen and let the user choose.
+    #[default]
+    Ask,
+    /// Point the output at the first free `name-N.ext` instead.
+    AutoRename,
+    Overwrite,
+    /// Don't run the command.
+    Skip,
+}
+
+impl ConflictPolicy {
+    /// The policy for a plugin's commands: with `never_overwrite` set,
+    /// conflicts that would be overwritten or asked about are renamed.
+    pub fn for_plugin(self, settings: &PluginSettings) -> Self {
+        match self {
+            ConflictPolicy::Ask | ConflictPolicy::Overwrite if settings.never_overwrite => {
+                ConflictPolicy::AutoRename
+            }
+            policy => policy,
+        }
+    }
+}
+
+/// How the TUI output pane is arranged. The split layouts keep the live log
+/// next to (or under) the preview instead of switching between them.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputLayout {
+    #[default]
+    Single,
+    SideBySide,
+    Stacked,
+}
+
+impl OutputLayout {
+    pub fn next(self) -> Self {
+        match self {
+            OutputLayout::Single => OutputLayout::SideBySide,
+            OutputLayout::SideBySide => OutputLayout::Stacked,
+            OutputLayout::Stacked => OutputLayout::Single,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            OutputLayout::Single => "single",
+            OutputLayout::SideBySide => "side by side",
+            OutputLayout::Stacked => "stacked",
+        }
+    }
+}
+
+/// The first screen of the TUI.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StartupMode {
+    #[default]
+    Input,
+    History,
+    /// The intent template with this name, submitted right away unless it
+    /// has placeholders to fill in.
+    Recipe(String),
+}
+
+/// A reusable intent snippet. `{name}` segments in `text` are placeholders
+/// the input editor steps through with Tab.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct IntentTemplate {
+    pub name: String,
+    pub text: String,
+}
+
+/// A favorite directory. `path` may start with `~/`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Bookmark {
+    pub name: String,
+    pub path: String,
+}
+
+impl Bookmark {
+    /// The directory, with a leading `~` resolved to the home directory.
+    pub fn dir(&self) -> PathBuf {
+        let path = self.path.trim();
+        match (path.strip_prefix('~'), crate::paths::home_dir()) {
+            (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+                home.join(rest.trim_start_matches('/'))
+            }
+            _ => PathBuf::from(path),
+        }
+    }
+}
+
+/// What happens to a preview nobody confirms, so a session left open
+/// doesn't run a stale command on the next Enter.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+pub struct ConfirmTimeout {
+    /// Minutes without a keypress after which the pending command is
+    /// cancelled; `0` waits forever.
+    #[serde(default)]
+    pub minutes: u64,
+    /// Names of templates whose commands run instead of being cancelled,
+    /// unless they delete files or were edited.
+    #[serde(default)]
+    pub auto_run: Vec<String>,
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
+            version: CONFIG_VERSION,
             providers: Vec::new(),
-            api_keys: ApiKeys::default(),
             models: ModelPreferences::default(),
             theme: default_theme(),
+            remotes: Vec::new(),
+            templates: Vec::new(),
+            bookmarks: Vec::new(),
+            startup: StartupMode::default(),
+            thumbnails: ThumbnailMode::default(),
+            pandoc_presets: default_pandoc_presets(),
+            ytdlp: YtDlpPolicy::default(),
+            output_conflicts: ConflictPolicy::default(),
+            output_layout: OutputLayout::default(),
+            auto_repair_attempts: 0,
+            token_budget: TokenBudget::default(),
+            pricing: Vec::new(),
+            trash: TrashPolicy::default(),
+            plain_ui: false,
+            offline: false,
+            metered: MeteredPolicy::default(),
+            language: default_language(),
+            audit: AuditPolicy::default(),
+            sandbox: SandboxPolicy::default(),
+            verify: VerifyPolicy::default(),
+            sync: SyncPolicy::default(),
+            history_examples: default_history_examples(),
+            confirm_timeout: ConfirmTimeout::default(),
+            context_file_limit: default_context_file_limit(),
+            clarify_options: default_clarify_options(),
+            output_line_limit: default_output_line_limit(),
+            parallel_jobs: default_parallel_jobs(),
+            updates: UpdatePolicy::default(),
+            plugins: BTreeMap::new(),
+            ocr: OcrPolicy::default(),
+            open: OpenPolicy::default(),
+            safety: SafetyPolicy::default(),
         }
     }
 }
 
 impl Config {
+    /// Loads the config for non-interactive commands: anything that had to
+    /// be dropped while parsing is an error listing every problem, while
+    /// semantic issues are only logged.
     pub async fn load() -> Result<Self> {
-        let config_dir = dirs::config_dir()
+        let (config, issues) = Self::load_validated().await?;
+        if issues.iter().any(|issue| issue.dropped) {
+            return Err(anyhow!(
+                "{}\nRun `dexter` to fix them in the settings panel.",
+                format_issues(&issues)
+            ));
+        }
+        for issue in &issues {
+            tracing::warn!(target: "dexter::config", "{}", issue);
+        }
+        Ok(config)
+    }
+
+    /// Loads whatever parts of the config are valid and reports every
+    /// problem at once. When something had to be dropped, the original file
+    /// is copied to `config.toml.bak` before a later save can replace it.
+    /// A file from an older schema is upgraded first and rewritten, keeping
+    /// the original as `config.toml.v<version>.bak`.
+    pub async fn load_validated() -> Result<(Self, Vec<ConfigIssue>)> {
+        let config_dir = crate::paths::config_dir()
             .context("Could not find config directory")?
             .join("dexter");
 
         let config_path = config_dir.join("config.toml");
 
         if !config_path.exists() {
-            return Ok(Self::default());
+            return Ok((Self::default(), Vec::new()));
         }
 
-        let content = fs::read_to_string(&config_path).await?;
-        let config: Config = toml::from_str(&content)?;
-
-        Ok(config)
+        let mut content = fs::read_to_string(&config_path).await?;
+        if let Some((migrated, report)) = migrate_content(&content) {
+            let backup = config_dir.join(format!("config.toml.v{}.bak", report.from));
+            write_private(&backup, &content).await?;
+            write_private(&config_path, &migrated).await?;
+            for line in report.lines() {
+                tracing::info!(target: "dexter::config", "{}", line);
+            }
+            tracing::info!(target: "dexter::config", "Previous file kept as {}", backup.display());
+            content = migrated;
+        }
+        let (config, issues) = parse_lenient(&content);
+        if issues.iter().any(|issue| issue.dropped) {
+            write_private(&config_dir.join("config.toml.bak"), &content).await?;
+        }
+        Ok((config, issues))
     }
 
     pub async fn save(&self) -> Result<()> {
-        let config_dir = dirs::config_dir()
+        let config_dir = crate::paths::config_dir()
             .context("Could not find config directory")?
             .join("dexter");
 
@@ -310,31 +752,17 @@ impl Config {
             fs::create_dir_all(&config_dir).await?;
         }
 
+        let config = Self {
+            version: CONFIG_VERSION,
+            ..self.clone()
+        };
         let config_path = config_dir.join("config.toml");
-        let content = toml::to_string_pretty(self)?;
-
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-
-            let mut file = fs::OpenOptions::new()
-                .create(true)
-                .truncate(true)
-                .write(true)
-                .mode(0o600)
-                .open(&config_path)
-                .await?;
-            file.write_all(content.as_bytes()).await?;
-            file.flush().await?;
-            fs::set_permissions(&config_path, std::fs::Permissions::from_mode(0o600)).await?;
-        }
-
-        #[cfg(not(unix))]
-        {
-            fs::write(&config_path, content).await?;
-        }
-
-        Ok(())
+        let fresh = toml::to_string_pretty(&config)?;
+        let content = match fs::read_to_string(&config_path).await {
+            Ok(existing) => document::preserve(&existing, &fresh).unwrap_or(fresh),
+            Err(_) => fresh,
+        };
+        write_private(&config_path, &content).await
     }
 
     pub fn has_keys(&self) -> bool {
@@ -342,14 +770,9 @@ impl Config {
     }
 
     pub fn effective_providers(&self) -> Vec<ProviderConfig> {
-        let providers = if self.providers.is_empty() {
-            self.legacy_providers()
-        } else {
-            self.providers.clone()
-        };
-
-        providers
-            .into_iter()
+        self.providers
+            .iter()
+            .cloned()
             .map(ProviderConfig::normalized)
             .collect()
     }
@@ -361,45 +784,54 @@ impl Config {
             .collect()
     }
 
-    fn legacy_providers(&self) -> Vec<ProviderConfig> {
-        let mut providers = Vec::new();
+    /// Providers requests may go to: every configured one, or only the
+    /// local Ollama ones while `offline` is set.
+    pub fn llm_providers(&self) -> Vec<ProviderConfig> {
+        self.configured_providers()
+            .into_iter()
+            .filter(|p| !self.offline || p.kind == ProviderKind::Ollama)
+            .collect()
+    }
 
-        if let Some(key) = clean_optional(self.api_keys.gemini.clone()) {
-            providers.push(ProviderConfig::builtin(ProviderKind::Gemini, Some(key)));
-        }
+    /// The `[plugins.<name>]` settings, the defaults without a table.
+    pub fn plugin_settings(&self, name: &str) -> PluginSettings {
+        self.plugins.get(name).cloned().unwrap_or_default()
+    }
 
-        if let Some(key) = clean_optional(self.api_keys.deepseek.clone()) {
-            providers.push(ProviderConfig::builtin(ProviderKind::Deepseek, Some(key)));
-        }
+    /// The conflict policy for a plugin's commands, `output_conflicts` as
+    /// its `never_overwrite` setting leaves it. Read from the config, so it
+    /// holds for plugins that keep no settings of their own.
+    pub fn conflict_policy_for(&self, plugin: &str) -> ConflictPolicy {
+        self.output_conflicts
+            .for_plugin(&self.plugin_settings(plugin))
+    }
+}
 
-        if let Some(base_url) = clean_optional(self.api_keys.base_url.clone()) {
-            let api_key = clean_optional(
-                self.api_keys
-                    .deepseek
-                    .clone()
-                    .or(self.api_keys.gemini.clone()),
-            );
-
-            providers.insert(
-                0,
-                ProviderConfig {
-                    kind: ProviderKind::OpenAICompatible,
-                    name: Some("Legacy Custom Endpoint".to_string()),
-                    api_key: api_key.clone(),
-                    base_url,
-                    auth: if api_key.is_some() {
-                        ProviderAuth::Bearer
-                    } else {
-                        ProviderAuth::None
-                    },
-                    enabled: true,
-                    models: Vec::new(),
-                },
-            );
-        }
+/// Writes `content` to `path`, readable by the owner only since the config
+/// holds API keys.
+pub(crate) async fn write_private(path: &Path, content: &str) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .mode(0o600)
+            .open(path)
+            .await?;
+        file.write_all(content.as_bytes()).await?;
+        file.flush().await?;
+        fs::set_permissions(path, std::fs::Permissions::from_mode(0o600)).await?;
+    }
 
-        providers
+    #[cfg(not(unix))]
+    {
+        fs::write(path, content).await?;
     }
+
+    Ok(())
 }
 
 fn clean_optional(input: Option<String>) -> Option<String> {
@@ -0,0 +1,140 @@
+//! Writing settings back into an existing config.toml. Only the values this
+//! build changed are touched, so comments, layout, and keys it doesn't know
+//! (from a newer Dexter, or a typo the user wants to keep) survive a save.
+
+use toml_edit::{DocumentMut, Item, TableLike};
+
+use super::parse_lenient;
+
+/// Merges the freshly serialized `fresh` into the file text `existing`.
+/// A key present in the file but missing from `fresh` is removed only if
+/// this build read it in the first place; otherwise it is left alone.
+/// `None` when `existing` isn't TOML, so the caller writes `fresh` as is.
+pub fn preserve(existing: &str, fresh: &str) -> Option<String> {
+    let mut doc: DocumentMut = existing.parse().ok()?;
+    let (read, _) = parse_lenient(existing);
+    let base: DocumentMut = toml::to_string_pretty(&read).ok()?.parse().ok()?;
+    let mut new: DocumentMut = fresh.parse().ok()?;
+
+    // A file from a newer build keeps its version, since its newer keys
+    // are kept too.
+    let version = |doc: &DocumentMut| doc.get("version").and_then(Item::as_integer);
+    if let (Some(file), Some(ours)) = (version(&doc), version(&new)) {
+        if file > ours {
+            new["version"] = toml_edit::value(file);
+        }
+    }
+
+    merge_table(doc.as_table_mut(), Some(base.as_table()), new.as_table());
+    Some(doc.to_string())
+}
+
+fn merge_table(doc: &mut dyn TableLike, base: Option<&dyn TableLike>, new: &dyn TableLike) {
+    let stale: Vec<String> = doc
+        .iter()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| !new.contains_key(key) && base.is_some_and(|base| base.contains_key(key)))
+        .collect();
+    for key in stale {
+        doc.remove(&key);
+    }
+
+    for (key, item) in new.iter() {
+        let base_item = base.and_then(|base| base.get(key));
+        match doc.get_mut(key) {
+            Some(existing) => merge_item(existing, base_item, item),
+            None => {
+                doc.insert(key, item.clone());
+            }
+        }
+    }
+}
+
+fn merge_item(doc: &mut Item, base: Option<&Item>, new: &Item) {
+    match (doc, new) {
+        (Item::Value(old), Item::Value(value))
+            if old.as_inline_table().is_none() || value.as_inline_table().is_none() =>
+        {
+            if !same_value(old, value) {
+                let decor = old.decor().clone();
+                *old = value.clone();
+                *old.decor_mut() = decor;
+            }
+        }
+        (Item::ArrayOfTables(old), Item::ArrayOfTables(tables)) if old.len() == tables.len() => {
+            // Entries are matched by position, so this only holds while the
+            // list keeps its length; otherwise it is rewritten whole.
+            let base = base.and_then(Item::as_array_of_tables);
+            for (idx, table) in tables.iter().enumerate() {
+                let base_table = base
+                    .filter(|base| base.len() == tables.len())
+                    .and_then(|base| base.get(idx))
+                    .map(|table| table as &dyn TableLike);
+                if let Some(old) = old.get_mut(idx) {
+                    merge_table(old, base_table, table);
+                }
+            }
+        }
+        (doc, new) => match (doc.as_table_like_mut(), new.as_table_like()) {
+            (Some(old), Some(table)) => merge_table(old, base.and_then(Item::as_table_like), table),
+            _ => *doc = new.clone(),
+        },
+    }
+}
+
+/// Compares values as TOML data, ignoring whitespace and comments.
+fn same_value(a: &toml_edit::Value, b: &toml_edit::Value) -> bool {
+    let parse = |value: &toml_edit::Value| {
+        format!("v = {}", value.to_string().trim())
+            .parse::<toml::Table>()
+            .ok()
+    };
+    matches!((parse(a), parse(b)), (Some(a), Some(b)) if a == b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, CONFIG_VERSION};
+
+    #[test]
+    fn keeps_comments_and_unknown_keys() {
+        let existing = format!(
+            r#"# my settings
+version = {CONFIG_VERSION}
+theme = "retro" # favourite
+fancy_new_option = true
+
+[[providers]]
+# work account
+kind = "gemini"
+api_key = "old-key"
+future_field = "kept"
+"#
+        );
+        let mut config = parse_lenient(&existing).0;
+        config.theme = "light".to_string();
+        config.providers[0].api_key = None;
+        let fresh = toml::to_string_pretty(&config).unwrap();
+
+        let saved = preserve(&existing, &fresh).expect("valid TOML");
+        assert!(saved.starts_with("# my settings\n"), "{saved}");
+        assert!(saved.contains("theme = \"light\" # favourite"), "{saved}");
+        assert!(saved.contains("fancy_new_option = true"), "{saved}");
+        assert!(saved.contains("# work account"), "{saved}");
+        assert!(saved.contains("future_field = \"kept\""), "{saved}");
+        assert!(!saved.contains("old-key"), "{saved}");
+
+        let (reread, issues) = parse_lenient(&saved);
+        assert!(issues.is_empty(), "{:?}", issues);
+        assert_eq!(toml::to_string_pretty(&reread).unwrap(), fresh);
+
+        let newer = "version = 99\nsomething = 1\n";
+        let fresh = toml::to_string_pretty(&Config::default()).unwrap();
+        let saved = preserve(newer, &fresh).unwrap();
+        assert!(saved.contains("version = 99"), "{saved}");
+        assert!(saved.contains("something = 1"), "{saved}");
+
+        assert_eq!(preserve("not = [toml", &fresh), None);
+    }
+}
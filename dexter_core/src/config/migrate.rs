@@ -0,0 +1,184 @@
+//! Versioned config schema. Every file records the `version` it was written
+//! with; older files are upgraded one step at a time when they are loaded,
+//! so the rest of the code only ever sees the current shape.
+
+use super::{clean_optional, ProviderAuth, ProviderConfig, ProviderKind};
+
+/// Schema version written by this build.
+pub const CONFIG_VERSION: u32 = 2;
+
+/// Files without a `version` key predate versioning.
+const UNVERSIONED: u32 = 1;
+
+struct Migration {
+    /// Version the step upgrades from, to `from + 1`.
+    from: u32,
+    /// Rewrites the table and describes each change it made.
+    apply: fn(&mut toml::Table) -> Vec<String>,
+}
+
+const MIGRATIONS: &[Migration] = &[Migration {
+    from: 1,
+    apply: api_keys_to_providers,
+}];
+
+/// What upgrading a file changed, for the log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub from: u32,
+    pub to: u32,
+    pub changes: Vec<String>,
+}
+
+impl MigrationReport {
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "Upgraded config.toml from version {} to {}",
+            self.from, self.to
+        )];
+        lines.extend(self.changes.iter().map(|change| format!("- {}", change)));
+        lines
+    }
+}
+
+/// The schema version `table` was written with.
+pub fn file_version(table: &toml::Table) -> u32 {
+    table
+        .get("version")
+        .and_then(toml::Value::as_integer)
+        .and_then(|v| u32::try_from(v).ok())
+        .unwrap_or(UNVERSIONED)
+}
+
+/// Upgrades `table` to `CONFIG_VERSION`. `None` when it is current already,
+/// or newer than this build knows.
+pub fn migrate(table: &mut toml::Table) -> Option<MigrationReport> {
+    let from = file_version(table);
+    if from >= CONFIG_VERSION {
+        return None;
+    }
+    let mut changes = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|m| m.from >= from) {
+        changes.extend((migration.apply)(table));
+    }
+    table.insert(
+        "version".to_string(),
+        toml::Value::Integer(CONFIG_VERSION.into()),
+    );
+    Some(MigrationReport {
+        from,
+        to: CONFIG_VERSION,
+        changes,
+    })
+}
+
+/// Upgrades the text of a config file. `None` when there is nothing to do,
+/// including for text that isn't TOML, which the lenient parser reports.
+pub fn migrate_content(content: &str) -> Option<(String, MigrationReport)> {
+    let mut table: toml::Table = content.parse().ok()?;
+    let report = migrate(&mut table)?;
+    let migrated = toml::to_string_pretty(&table).ok()?;
+    Some((migrated, report))
+}
+
+/// v1 -> v2: the `[api_keys]` table (Gemini and DeepSeek keys plus an
+/// optional OpenAI-compatible `base_url`) becomes `[[providers]]` entries.
+/// Files that already list providers only lose the stale keys.
+fn api_keys_to_providers(table: &mut toml::Table) -> Vec<String> {
+    let Some(toml::Value::Table(keys)) = table.remove("api_keys") else {
+        return Vec::new();
+    };
+    let has_providers = table
+        .get("providers")
+        .and_then(toml::Value::as_array)
+        .is_some_and(|providers| !providers.is_empty());
+    if has_providers {
+        return vec!["dropped [api_keys], already covered by [[providers]]".to_string()];
+    }
+
+    let key =
+        |name: &str| clean_optional(keys.get(name).and_then(|v| v.as_str()).map(String::from));
+    let mut providers = Vec::new();
+    if let Some(base_url) = key("base_url") {
+        let api_key = key("deepseek").or_else(|| key("gemini"));
+        providers.push(ProviderConfig {
+            kind: ProviderKind::OpenAICompatible,
+            name: Some("Legacy Custom Endpoint".to_string()),
+            auth: if api_key.is_some() {
+                ProviderAuth::Bearer
+            } else {
+                ProviderAuth::None
+            },
+            api_key,
+            base_url,
+            enabled: true,
+            models: Vec::new(),
+            proxy: None,
+            ca_cert: None,
+            oauth: None,
+        });
+    }
+    if let Some(api_key) = key("gemini") {
+        providers.push(ProviderConfig::builtin(ProviderKind::Gemini, Some(api_key)));
+    }
+    if let Some(api_key) = key("deepseek") {
+        providers.push(ProviderConfig::builtin(
+            ProviderKind::Deepseek,
+            Some(api_key),
+        ));
+    }
+
+    let changes = providers
+        .iter()
+        .map(|provider| format!("added provider {} from [api_keys]", provider.display_name()))
+        .collect();
+    let providers = providers
+        .into_iter()
+        .filter_map(|provider| toml::Value::try_from(provider).ok())
+        .collect();
+    table.insert("providers".to_string(), toml::Value::Array(providers));
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::parse_lenient;
+
+    #[test]
+    fn moves_legacy_api_keys_into_providers() {
+        let legacy = r#"
+theme = "retro"
+
+[api_keys]
+gemini = " gm-key "
+base_url = "https://llm.example.com/v1"
+"#;
+        let (migrated, report) = migrate_content(legacy).expect("unversioned file");
+        assert_eq!((report.from, report.to), (1, CONFIG_VERSION));
+        assert_eq!(
+            report.lines(),
+            vec![
+                "Upgraded config.toml from version 1 to 2",
+                "- added provider Legacy Custom Endpoint from [api_keys]",
+                "- added provider GEMINI from [api_keys]",
+            ]
+        );
+        assert!(!migrated.contains("api_keys"));
+
+        let (config, issues) = parse_lenient(&migrated);
+        assert!(issues.is_empty(), "{:?}", issues);
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.theme, "retro");
+        let kinds: Vec<ProviderKind> = config.providers.iter().map(|p| p.kind).collect();
+        assert_eq!(
+            kinds,
+            [ProviderKind::OpenAICompatible, ProviderKind::Gemini]
+        );
+        assert_eq!(config.providers[0].api_key.as_deref(), Some("gm-key"));
+
+        assert_eq!(migrate_content(&migrated), None);
+        assert_eq!(migrate_content("version = 3"), None);
+        assert_eq!(migrate_content("not = [toml"), None);
+    }
+}
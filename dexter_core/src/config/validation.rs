@@ -0,0 +1,423 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use super::migrate::{file_version, migrate, CONFIG_VERSION};
+use super::{Config, ProviderAuth, ProviderKind};
+
+/// Theme names the TUI understands.
+pub const THEME_NAMES: [&str; 4] = ["auto", "dark", "retro", "light"];
+
+/// Part of the config a problem belongs to, so the settings panel can open
+/// on the screen that fixes it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigField {
+    /// The file itself could not be read as TOML.
+    File,
+    /// A provider entry; `None` when its `kind` is not one Dexter knows.
+    Provider(Option<ProviderKind>),
+    BaseUrl(ProviderKind),
+    /// A provider's `proxy` or `ca_cert`.
+    Network(ProviderKind),
+    Routes,
+    Theme,
+    Remote(usize),
+    /// Any other top-level table or key, by name.
+    Section(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+    pub field: ConfigField,
+    pub message: String,
+    /// The offending value was dropped while loading; saving the config
+    /// will remove it from the file.
+    pub dropped: bool,
+}
+
+impl ConfigIssue {
+    fn new(field: ConfigField, message: impl Into<String>) -> Self {
+        Self {
+            field,
+            message: message.into(),
+            dropped: false,
+        }
+    }
+
+    fn dropped(field: ConfigField, message: impl Into<String>) -> Self {
+        Self {
+            field,
+            message: message.into(),
+            dropped: true,
+        }
+    }
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if self.dropped {
+            write!(f, " (ignored)")?;
+        }
+        Ok(())
+    }
+}
+
+pub fn format_issues(issues: &[ConfigIssue]) -> String {
+    let mut out = vec![format!("{} problem(s) in config.toml:", issues.len())];
+    out.extend(issues.iter().map(|issue| format!("- {}", issue)));
+    out.join("\n")
+}
+
+/// Parses `content` keeping everything that is valid: a provider or route
+/// with an unknown kind, or a section with the wrong shape, is dropped and
+/// reported instead of failing the whole load. Files from an older schema
+/// are upgraded first. Semantic checks from `Config::validate` are
+/// appended.
+pub fn parse_lenient(content: &str) -> (Config, Vec<ConfigIssue>) {
+    let mut issues = Vec::new();
+    let mut table: toml::Table = match content.parse() {
+        Ok(table) => table,
+        Err(e) => {
+            issues.push(ConfigIssue::dropped(
+                ConfigField::File,
+                format!(
+                    "config.toml is not valid TOML: {}",
+                    one_line(&e.to_string())
+                ),
+            ));
+            return (Config::default(), issues);
+        }
+    };
+    migrate(&mut table);
+    let version = file_version(&table);
+    if version > CONFIG_VERSION {
+        issues.push(ConfigIssue::new(
+            ConfigField::Section("version".to_string()),
+            format!(
+                "config.toml is from a newer Dexter (version {}, this one knows {}); settings it added are ignored",
+                version, CONFIG_VERSION
+            ),
+        ));
+    }
+
+    issues.extend(retain_valid_items::<super::ProviderConfig>(
+        &mut table,
+        &[],
+        "providers",
+        |idx, e| {
+            ConfigIssue::dropped(
+                ConfigField::Provider(None),
+                format!("providers[{}]: {}", idx, e),
+            )
+        },
+    ));
+    for routes in ["router_routes", "executor_routes"] {
+        issues.extend(retain_valid_items::<super::ModelRoute>(
+            &mut table,
+            &["models"],
+            routes,
+            |idx, e| {
+                ConfigIssue::dropped(
+                    ConfigField::Routes,
+                    format!("models.{}[{}]: {}", routes, idx, e),
+                )
+            },
+        ));
+    }
+
+    // Whatever still fails is isolated one top-level key at a time.
+    let keys: Vec<String> = table.keys().cloned().collect();
+    for key in keys {
+        let mut single = toml::Table::new();
+        single.insert(key.clone(), table[&key].clone());
+        if let Err(e) = toml::Value::Table(single).try_into::<Config>() {
+            table.remove(&key);
+            issues.push(ConfigIssue::dropped(
+                ConfigField::Section(key.clone()),
+                format!("{}: {}", key, one_line(e.message())),
+            ));
+        }
+    }
+
+    let config = match toml::Value::Table(table).try_into::<Config>() {
+        Ok(config) => config,
+        Err(e) => {
+            issues.push(ConfigIssue::dropped(
+                ConfigField::File,
+                one_line(e.message()).to_string(),
+            ));
+            Config::default()
+        }
+    };
+    issues.extend(config.validate());
+    (config, issues)
+}
+
+/// Removes the entries of the array at `parents.key` that don't deserialize
+/// as `T`, reporting each through `issue`.
+fn retain_valid_items<T: serde::de::DeserializeOwned>(
+    table: &mut toml::Table,
+    parents: &[&str],
+    key: &str,
+    issue: impl Fn(usize, &str) -> ConfigIssue,
+) -> Vec<ConfigIssue> {
+    let mut current = table;
+    for parent in parents {
+        match current.get_mut(*parent) {
+            Some(toml::Value::Table(inner)) => current = inner,
+            _ => return Vec::new(),
+        }
+    }
+    let Some(toml::Value::Array(items)) = current.get_mut(key) else {
+        return Vec::new();
+    };
+    let mut issues = Vec::new();
+    let mut idx = 0;
+    items.retain(|item| {
+        let keep = match item.clone().try_into::<T>() {
+            Ok(_) => true,
+            Err(e) => {
+                issues.push(issue(idx, one_line(e.message())));
+                false
+            }
+        };
+        idx += 1;
+        keep
+    });
+    issues
+}
+
+fn one_line(message: &str) -> &str {
+    message
+        .lines()
+        .find(|l| !l.trim().is_empty())
+        .unwrap_or(message)
+        .trim()
+}
+
+impl Config {
+    /// Semantic problems in an otherwise well-formed config, all at once.
+    pub fn validate(&self) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+
+        for provider in &self.providers {
+            let url = provider.base_url.trim();
+            if !url.is_empty() && !is_valid_base_url(url) {
+                issues.push(ConfigIssue::new(
+                    ConfigField::BaseUrl(provider.kind),
+                    format!(
+                        "{}: base_url `{}` is not an http(s) URL",
+                        provider.display_name(),
+                        url
+                    ),
+                ));
+            }
+        }
+        for provider in &self.providers {
+            if provider.auth == ProviderAuth::OAuth && provider.oauth.is_none() {
+                issues.push(ConfigIssue::new(
+                    ConfigField::Provider(Some(provider.kind)),
+                    format!(
+                        "{}: auth = \"oauth\" needs an `oauth` table with device_url, token_url and client_id",
+                        provider.display_name()
+                    ),
+                ));
+            }
+            if let Some(proxy) = provider.proxy.as_deref().map(str::trim) {
+                if !proxy.is_empty() && !is_valid_proxy(proxy) {
+                    issues.push(ConfigIssue::new(
+                        ConfigField::Network(provider.kind),
+                        format!(
+                            "{}: proxy `{}` must be http(s)://, socks5(h):// or `system`",
+                            provider.display_name(),
+                            proxy
+                        ),
+                    ));
+                }
+            }
+            if let Some(path) = provider.ca_cert.as_deref().map(str::trim) {
+                if !path.is_empty() && !std::path::Path::new(path).is_file() {
+                    issues.push(ConfigIssue::new(
+                        ConfigField::Network(provider.kind),
+                        format!(
+                            "{}: ca_cert `{}` does not exist",
+                            provider.display_name(),
+                            path
+                        ),
+                    ));
+                }
+            }
+        }
+
+        let configured: HashSet<ProviderKind> =
+            self.configured_providers().iter().map(|p| p.kind).collect();
+        for (label, routes) in [
+            ("router", &self.models.router_routes),
+            ("executor", &self.models.executor_routes),
+        ] {
+            for (idx, route) in routes.iter().enumerate() {
+                if route.model.trim().is_empty() {
+                    issues.push(ConfigIssue::new(
+                        ConfigField::Routes,
+                        format!("{} route #{} has no model", label, idx + 1),
+                    ));
+                } else if !configured.is_empty() && !configured.contains(&route.provider) {
+                    issues.push(ConfigIssue::new(
+                        ConfigField::Routes,
+                        format!(
+                            "{} route #{} uses {} ({}), which is not configured",
+                            label,
+                            idx + 1,
+                            route.provider.display_name(),
+                            route.model
+                        ),
+                    ));
+                }
+            }
+        }
+        if !configured.is_empty()
+            && self.models.executor_routes.is_empty()
+            && self.models.executor_model.trim().is_empty()
+        {
+            issues.push(ConfigIssue::new(
+                ConfigField::Routes,
+                "no executor model or route is selected",
+            ));
+        }
+
+        if !THEME_NAMES
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(self.theme.trim()))
+        {
+            issues.push(ConfigIssue::new(
+                ConfigField::Theme,
+                format!(
+                    "unknown theme `{}` (expected one of: {})",
+                    self.theme,
+                    THEME_NAMES.join(", ")
+                ),
+            ));
+        }
+
+        for (idx, remote) in self.remotes.iter().enumerate() {
+            if remote.host.trim().is_empty() {
+                issues.push(ConfigIssue::new(
+                    ConfigField::Remote(idx),
+                    format!("remote `{}` has no host", remote.name),
+                ));
+            }
+        }
+        for bookmark in &self.bookmarks {
+            if bookmark.path.trim().is_empty() {
+                issues.push(ConfigIssue::new(
+                    ConfigField::Section("bookmarks".to_string()),
+                    format!("bookmark `{}` has no path", bookmark.name),
+                ));
+            }
+        }
+        for pattern in &self.safety.blocked_patterns {
+            if let Err(e) = regex::Regex::new(pattern) {
+                // The parser's last line names the problem.
+                let message = e.to_string();
+                issues.push(ConfigIssue::new(
+                    ConfigField::Section("safety".to_string()),
+                    format!(
+                        "safety.blocked_patterns: `{}` is not a valid regex ({})",
+                        pattern,
+                        message.lines().last().unwrap_or_default().trim()
+                    ),
+                ));
+            }
+        }
+        issues
+    }
+}
+
+fn is_valid_base_url(url: &str) -> bool {
+    let Some(rest) = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+    else {
+        return false;
+    };
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    !host.is_empty() && !url.chars().any(char::is_whitespace)
+}
+
+fn is_valid_proxy(proxy: &str) -> bool {
+    if proxy.eq_ignore_ascii_case("system") {
+        return true;
+    }
+    ["http://", "https://", "socks5://", "socks5h://"]
+        .iter()
+        .any(|scheme| {
+            proxy
+                .strip_prefix(scheme)
+                .is_some_and(|rest| !rest.is_empty() && !rest.contains(char::is_whitespace))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_every_problem_and_keeps_the_rest() {
+        let raw = r#"
+theme = "neon"
+
+[[providers]]
+kind = "gemini"
+api_key = "k"
+base_url = "generativelanguage.googleapis.com"
+proxy = "socks4://10.0.0.1:1080"
+
+[[providers]]
+kind = "skynet"
+api_key = "k"
+
+[models]
+executor_model = "gemini-2.5-flash"
+
+[[models.executor_routes]]
+provider = "gemini"
+model = ""
+
+[[models.executor_routes]]
+provider = "mystery"
+model = "m"
+
+[[remotes]]
+name = "nas"
+host = " "
+"#;
+        let (config, issues) = parse_lenient(raw);
+        assert_eq!(config.providers.len(), 1);
+        assert_eq!(config.models.executor_model, "gemini-2.5-flash");
+        assert_eq!(config.models.executor_routes.len(), 1);
+
+        let fields: Vec<&ConfigField> = issues.iter().map(|i| &i.field).collect();
+        assert!(fields.contains(&&ConfigField::Provider(None)));
+        assert!(fields.contains(&&ConfigField::BaseUrl(ProviderKind::Gemini)));
+        assert!(fields.contains(&&ConfigField::Network(ProviderKind::Gemini)));
+        assert_eq!(
+            fields
+                .iter()
+                .filter(|f| ***f == ConfigField::Routes)
+                .count(),
+            2
+        );
+        assert!(fields.contains(&&ConfigField::Theme));
+        assert!(fields.contains(&&ConfigField::Remote(0)));
+        assert!(issues[0].message.contains("skynet"));
+    }
+
+    #[test]
+    fn broken_toml_falls_back_to_defaults() {
+        let (config, issues) = parse_lenient("theme = \"dark\"\n[models\n");
+        assert_eq!(config.theme, "auto");
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].field, ConfigField::File);
+        assert!(parse_lenient("theme = \"dark\"").1.is_empty());
+    }
+}
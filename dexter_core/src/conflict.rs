@@ -0,0 +1,266 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::config::ConflictPolicy;
+
+/// An output path of a generated command that already exists on disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputConflict {
+    /// Output path exactly as written in the command.
+    pub path: String,
+    /// First free sibling name (`out-1.mp4`, `out-2.mp4`, ...).
+    pub suggested: String,
+    arg_index: usize,
+    arg_prefix: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConflictOutcome {
+    /// No output of the command exists yet.
+    Clear,
+    /// The policy rewrote the command (renamed outputs or forced overwrite).
+    Rewritten {
+        command: String,
+        conflicts: Vec<OutputConflict>,
+    },
+    /// The policy says not to run the command at all.
+    Skip { conflicts: Vec<OutputConflict> },
+    /// The user has to choose before the command can run.
+    Ask { conflicts: Vec<OutputConflict> },
+}
+
+/// Output argument located in a command's argv. `prefix` is kept for the
+/// `--output=path` spelling so a rename can rebuild the token.
+struct OutputArg {
+    index: usize,
+    prefix: String,
+    path: String,
+}
+
+/// Returns the outputs of `cmd` (as run by `plugin`) that already exist in
+/// `cwd`. Plugins whose outputs can't be parsed reliably report nothing.
+pub fn find_output_conflicts(plugin: &str, cmd: &str, cwd: &Path) -> Result<Vec<OutputConflict>> {
+    let argv = shell_words::split(cmd).map_err(|e| anyhow!("Invalid command syntax: {}", e))?;
+    let outputs = output_args(plugin, &argv);
+    let mut taken: HashSet<PathBuf> = outputs.iter().map(|o| cwd.join(&o.path)).collect();
+    let mut conflicts = Vec::new();
+    for output in outputs {
+        if !cwd.join(&output.path).exists() {
+            continue;
+        }
+        let suggested = free_name(&output.path, cwd, &taken);
+        taken.insert(cwd.join(&suggested));
+        conflicts.push(OutputConflict {
+            path: output.path,
+            suggested,
+            arg_index: output.index,
+            arg_prefix: output.prefix,
+        });
+    }
+    Ok(conflicts)
+}
+
+/// Applies `policy` to a command whose outputs collide with `conflicts`.
+pub fn apply_conflict_policy(
+    plugin: &str,
+    cmd: &str,
+    conflicts: Vec<OutputConflict>,
+    policy: ConflictPolicy,
+) -> Result<ConflictOutcome> {
+    if conflicts.is_empty() {
+        return Ok(ConflictOutcome::Clear);
+    }
+    let mut argv = shell_words::split(cmd).map_err(|e| anyhow!("Invalid command syntax: {}", e))?;
+    match policy {
+        ConflictPolicy::Ask => Ok(ConflictOutcome::Ask { conflicts }),
+        ConflictPolicy::Skip => Ok(ConflictOutcome::Skip { conflicts }),
+        ConflictPolicy::AutoRename => {
+            for conflict in &conflicts {
+                let slot = argv
+                    .get_mut(conflict.arg_index)
+                    .ok_or_else(|| anyhow!("Command changed since conflicts were detected"))?;
+                *slot = format!("{}{}", conflict.arg_prefix, conflict.suggested);
+            }
+            Ok(ConflictOutcome::Rewritten {
+                command: shell_words::join(&argv),
+                conflicts,
+            })
+        }
+        ConflictPolicy::Overwrite => {
+            // The other tools overwrite silently; ffmpeg would stop at its
+            // interactive prompt (and `-n` refuses outright).
+            if plugin == "ffmpeg" {
+                argv.retain(|a| a != "-n");
+                if !argv.iter().any(|a| a == "-y") {
+                    argv.insert(1, "-y".to_string());
+                }
+            }
+            Ok(ConflictOutcome::Rewritten {
+                command: shell_words::join(&argv),
+                conflicts,
+            })
+        }
+    }
+}
+
+/// Output paths of `cmd` as written, for the plugins `find_output_conflicts`
+/// understands.
+pub fn output_paths(plugin: &str, cmd: &str) -> Result<Vec<String>> {
+    let argv = shell_words::split(cmd).map_err(|e| anyhow!("Invalid command syntax: {}", e))?;
+    Ok(output_args(plugin, &argv)
+        .into_iter()
+        .map(|o| o.path)
+        .collect())
+}
+
+pub fn format_conflicts(conflicts: &[OutputConflict]) -> String {
+    let mut out = vec![format!("{} output file(s) already exist:", conflicts.len())];
+    for conflict in conflicts {
+        out.push(format!(
+            "- {} (free name: {})",
+            conflict.path, conflict.suggested
+        ));
+    }
+    out.join("\n")
+}
+
+fn output_args(plugin: &str, argv: &[String]) -> Vec<OutputArg> {
+    let last_positional = || {
+        let index = argv.len().checked_sub(1)?;
+        let path = &argv[index];
+        (index > 0 && !path.starts_with('-')).then(|| OutputArg {
+            index,
+            prefix: String::new(),
+            path: path.clone(),
+        })
+    };
+    let found = match plugin {
+        "ffmpeg" => last_positional(),
+        "qpdf" if !argv.iter().any(|a| a == "--replace-input") => last_positional(),
+        "ocrmypdf" => last_positional(),
+        "pandoc" => pandoc_output(argv),
+        "libvips" if argv.first().map(String::as_str) == Some("vips") => {
+            argv.get(3).map(|path| OutputArg {
+                index: 3,
+                prefix: String::new(),
+                path: path.clone(),
+            })
+        }
+        _ => None,
+    };
+    // Streams, URLs, devices and numbered sequences are never plain files.
+    found
+        .filter(|o| {
+            !o.path.is_empty()
+                && o.path != "-"
+                && !o.path.contains("://")
+                && !o.path.contains('%')
+                && !o.path.starts_with("/dev/")
+        })
+        .into_iter()
+        .collect()
+}
+
+fn pandoc_output(argv: &[String]) -> Option<OutputArg> {
+    for (index, arg) in argv.iter().enumerate() {
+        if arg == "-o" || arg == "--output" {
+            return argv.get(index + 1).map(|path| OutputArg {
+                index: index + 1,
+                prefix: String::new(),
+                path: path.clone(),
+            });
+        }
+        if let Some(path) = arg.strip_prefix("--output=") {
+            return Some(OutputArg {
+                index,
+                prefix: "--output=".to_string(),
+                path: path.to_string(),
+            });
+        }
+    }
+    None
+}
+
+fn free_name(path: &str, cwd: &Path, taken: &HashSet<PathBuf>) -> String {
+    let original = Path::new(path);
+    let stem = original
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let ext = original
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    let parent = original.parent().unwrap_or(Path::new(""));
+    for n in 1.. {
+        let candidate = parent.join(format!("{}-{}{}", stem, n, ext));
+        let full = cwd.join(&candidate);
+        if !full.exists() && !taken.contains(&full) {
+            return candidate.to_string_lossy().into_owned();
+        }
+    }
+    unreachable!("unbounded search always finds a free name")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn detects_existing_outputs_and_suggests_free_names() -> Result<()> {
+        let dir = tempdir()?;
+        for name in ["in.mov", "out.mp4", "out-1.mp4", "doc.pdf"] {
+            std::fs::File::create(dir.path().join(name))?;
+        }
+
+        let conflicts = find_output_conflicts("ffmpeg", "ffmpeg -i in.mov out.mp4", dir.path())?;
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].suggested, "out-2.mp4");
+
+        let pandoc = find_output_conflicts("pandoc", "pandoc a.md --output=doc.pdf", dir.path())?;
+        assert_eq!(pandoc[0].path, "doc.pdf");
+        assert!(
+            find_output_conflicts("ffmpeg", "ffmpeg -i in.mov new.mp4", dir.path())?.is_empty()
+        );
+        assert!(
+            find_output_conflicts("qpdf", "qpdf --replace-input doc.pdf", dir.path())?.is_empty()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn applies_each_policy() -> Result<()> {
+        let dir = tempdir()?;
+        std::fs::File::create(dir.path().join("doc.pdf"))?;
+        let cmd = "pandoc a.md --output=doc.pdf";
+        let conflicts = find_output_conflicts("pandoc", cmd, dir.path())?;
+
+        assert!(matches!(
+            apply_conflict_policy("pandoc", cmd, conflicts.clone(), ConflictPolicy::Ask)?,
+            ConflictOutcome::Ask { .. }
+        ));
+        assert!(matches!(
+            apply_conflict_policy("pandoc", cmd, conflicts.clone(), ConflictPolicy::Skip)?,
+            ConflictOutcome::Skip { .. }
+        ));
+        let ConflictOutcome::Rewritten { command, .. } =
+            apply_conflict_policy("pandoc", cmd, conflicts, ConflictPolicy::AutoRename)?
+        else {
+            panic!("expected a rewrite");
+        };
+        assert_eq!(command, "pandoc a.md '--output=doc-1.pdf'");
+
+        std::fs::File::create(dir.path().join("out.mp4"))?;
+        let cmd = "ffmpeg -n -i in.mov out.mp4";
+        let conflicts = find_output_conflicts("ffmpeg", cmd, dir.path())?;
+        let ConflictOutcome::Rewritten { command, .. } =
+            apply_conflict_policy("ffmpeg", cmd, conflicts, ConflictPolicy::Overwrite)?
+        else {
+            panic!("expected a rewrite");
+        };
+        assert_eq!(command, "ffmpeg -y -i in.mov out.mp4");
+        Ok(())
+    }
+}
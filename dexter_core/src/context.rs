@@ -1,63 +1,597 @@
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::fs;
 
-#[derive(Debug, Clone)]
+use crate::ocr::OcrText;
+
+/// Longest a single directory scan may take; past it the scan stops and
+/// reports what it has, marked as truncated.
+pub const SCAN_TIME_BUDGET: Duration = Duration::from_millis(750);
+
+/// Directories remembered between scans before the cache starts over.
+const SCAN_CACHE_CAPACITY: usize = 64;
+/// Piped names spelled out in the prompt; the rest are only counted.
+const PIPED_LIST_LIMIT: usize = 200;
+/// Files a prompt lists by name unless `config.context_file_limit` says
+/// otherwise; larger listings are summarized.
+pub const DEFAULT_CONTEXT_FILES: usize = 20;
+/// Extensions counted one by one in a summary; the rest are lumped together.
+const SUMMARY_EXTENSIONS: usize = 8;
+/// Names quoted as examples, and as outliers, in a summary.
+const SUMMARY_EXAMPLES: usize = 6;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FileContext {
+    /// Every file found, sorted; prompts see a summary and a subset of them
+    /// when there are too many (see `for_prompt`).
     pub files: Vec<String>,
+    /// Notes for the models ahead of the listing: a truncated scan, the
+    /// paths the listing covers, pinned project files.
     pub summary: Option<String>,
+    /// The scan hit `SCAN_TIME_BUDGET`; `files` and the counts are partial.
+    pub truncated: bool,
+    pub dirs: usize,
+    /// The user piped in exactly these files: prompts name them all, up to
+    /// `PIPED_LIST_LIMIT`, instead of summarizing.
+    pub exact: bool,
+    /// Text read from the images by `ocr::enrich`, for rename requests.
+    pub ocr: Vec<OcrText>,
+}
+
+impl FileContext {
+    /// Whether a prompt with room for `limit` names gets a summary instead
+    /// of the full listing.
+    pub fn is_summarized(&self, limit: usize) -> bool {
+        if self.exact {
+            self.files.len() > PIPED_LIST_LIMIT
+        } else {
+            self.files.len() > limit
+        }
+    }
+
+    /// The context as a prompt shows it: the notes, then either every file
+    /// or, past `limit` files, a summary of the listing and the `limit`
+    /// files that best match `intent`.
+    pub fn for_prompt(&self, intent: &str, limit: usize) -> String {
+        let mut parts: Vec<String> = self.summary.iter().cloned().collect();
+        if self.files.is_empty() {
+            if parts.is_empty() {
+                parts.push("(no visible files in current directory)".to_string());
+            }
+        } else if self.exact {
+            let mut listing = numbered(self.files.iter().take(PIPED_LIST_LIMIT));
+            if self.files.len() > PIPED_LIST_LIMIT {
+                listing.push_str(&format!(
+                    "\n... and {} more",
+                    self.files.len() - PIPED_LIST_LIMIT
+                ));
+            }
+            parts.push(listing);
+        } else if !self.is_summarized(limit) {
+            parts.push(numbered(self.files.iter()));
+        } else {
+            parts.push(self.overview());
+            let relevant = self.relevant_files(intent, limit);
+            if !relevant.is_empty() {
+                parts.push(format!(
+                    "Files most relevant to the request ({} of {}):\n{}",
+                    relevant.len(),
+                    self.files.len(),
+                    numbered(relevant.into_iter())
+                ));
+            }
+        }
+        if !self.ocr.is_empty() {
+            parts.push(self.ocr_text());
+        }
+        parts.join("\n")
+    }
+
+    /// The text read from images, one file per line, with the dates found
+    /// in it spelled out.
+    fn ocr_text(&self) -> String {
+        let lines: Vec<String> = self
+            .ocr
+            .iter()
+            .map(|found| {
+                let mut line = format!("- {}: \"{}\"", found.file, found.text);
+                if !found.dates.is_empty() {
+                    line.push_str(&format!(" (dates: {})", found.dates.join(", ")));
+                }
+                line
+            })
+            .collect();
+        format!(
+            "Text visible in the images (OCR, may contain misreadings):\n{}",
+            lines.join("\n")
+        )
+    }
+
+    /// What a large listing holds: counts per extension, a few example
+    /// names of the common ones and the files that stand out.
+    fn overview(&self) -> String {
+        let mut by_extension: HashMap<String, Vec<&str>> = HashMap::new();
+        for file in &self.files {
+            by_extension.entry(extension(file)).or_default().push(file);
+        }
+        let mut groups: Vec<(String, Vec<&str>)> = by_extension.into_iter().collect();
+        groups.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(&b.0)));
+
+        let mut counts: Vec<String> = groups
+            .iter()
+            .take(SUMMARY_EXTENSIONS)
+            .map(|(ext, names)| format!("{} {}", names.len(), ext))
+            .collect();
+        let rest: usize = groups
+            .iter()
+            .skip(SUMMARY_EXTENSIONS)
+            .map(|(_, names)| names.len())
+            .sum();
+        if rest > 0 {
+            counts.push(format!("{} other", rest));
+        }
+        let mut text = format!(
+            "Directory contains {} files and {} subdirectories.\nBy extension: {}",
+            self.files.len(),
+            self.dirs,
+            counts.join(", ")
+        );
+
+        // The first and last name of each common extension show its naming
+        // pattern; files whose extension is rare are the outliers.
+        let examples: Vec<&str> = groups
+            .iter()
+            .filter(|(_, names)| names.len() > 1)
+            .flat_map(|(_, names)| [names[0], names[names.len() - 1]])
+            .take(SUMMARY_EXAMPLES)
+            .collect();
+        if !examples.is_empty() {
+            text.push_str(&format!("\nExamples: {}", examples.join(", ")));
+        }
+        let rare = (self.files.len() / 100).max(1);
+        let outliers: Vec<&str> = groups
+            .iter()
+            .filter(|(_, names)| names.len() <= rare)
+            .flat_map(|(_, names)| names.iter().copied())
+            .collect();
+        if !outliers.is_empty() && outliers.len() < self.files.len() {
+            text.push_str(&format!(
+                "\nOutliers: {}",
+                outliers
+                    .iter()
+                    .take(SUMMARY_EXAMPLES)
+                    .copied()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+            if outliers.len() > SUMMARY_EXAMPLES {
+                text.push_str(&format!(" and {} more", outliers.len() - SUMMARY_EXAMPLES));
+            }
+        }
+        text
+    }
+
+    /// Up to `limit` files, those sharing the most words with `intent`
+    /// first and otherwise in listing order.
+    fn relevant_files(&self, intent: &str, limit: usize) -> Vec<&String> {
+        let words: Vec<String> = intent
+            .to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| word.chars().count() >= 2)
+            .map(str::to_string)
+            .collect();
+        let mut scored: Vec<(usize, &String)> = self
+            .files
+            .iter()
+            .map(|file| {
+                let name = file.to_lowercase();
+                let ext = extension(file);
+                let score = words
+                    .iter()
+                    .map(|word| {
+                        if ext.trim_start_matches('.') == word {
+                            1
+                        } else if name.contains(word.as_str()) {
+                            2
+                        } else {
+                            0
+                        }
+                    })
+                    .sum();
+                (score, file)
+            })
+            .collect();
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        scored
+            .into_iter()
+            .take(limit)
+            .map(|(_, file)| file)
+            .collect()
+    }
+}
+
+/// `.jpg` for `IMG_1.JPG`; `(no extension)` when there is none.
+fn extension(file: &str) -> String {
+    Path::new(file)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| format!(".{}", ext.to_lowercase()))
+        .unwrap_or_else(|| "(no extension)".to_string())
+}
+
+fn numbered<'a>(files: impl Iterator<Item = &'a String>) -> String {
+    files
+        .enumerate()
+        .map(|(i, f)| format!("{}. {}", i + 1, f))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Entry names with whether each is a directory, as of `modified`.
+struct CachedScan {
+    modified: Option<SystemTime>,
+    entries: HashMap<String, bool>,
+    complete: bool,
+}
+
+fn scan_cache() -> &'static Mutex<HashMap<PathBuf, CachedScan>> {
+    static CACHE: OnceLock<Mutex<HashMap<PathBuf, CachedScan>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
 }
 
 pub struct ContextScanner;
 
 impl ContextScanner {
     pub async fn scan_cwd() -> Result<FileContext> {
-        let cwd = std::env::current_dir()?;
-        let mut entries = fs::read_dir(cwd).await?;
-        let mut files = Vec::new();
-        let mut file_count = 0;
-        let mut dir_count = 0;
+        Self::scan_dir(&std::env::current_dir()?).await
+    }
 
+    pub async fn scan_dir(dir: &Path) -> Result<FileContext> {
+        Self::scan_dir_within(dir, SCAN_TIME_BUDGET).await
+    }
+
+    /// Lists `dir`, reusing the previous scan while the directory's mtime is
+    /// unchanged. When it did change, only entries not seen before are
+    /// stat'ed. Stops after `budget` with whatever was listed so far.
+    pub async fn scan_dir_within(dir: &Path, budget: Duration) -> Result<FileContext> {
+        let deadline = Instant::now() + budget;
+        let modified = fs::metadata(dir).await?.modified().ok();
+        let mut known = {
+            let mut cache = scan_cache().lock().unwrap_or_else(|e| e.into_inner());
+            match cache.remove(dir) {
+                Some(cached)
+                    if cached.complete && modified.is_some() && cached.modified == modified =>
+                {
+                    let context = Self::from_entries(&cached.entries, false);
+                    cache.insert(dir.to_path_buf(), cached);
+                    return Ok(context);
+                }
+                Some(cached) => cached.entries,
+                None => HashMap::new(),
+            }
+        };
+
+        let mut entries = fs::read_dir(dir).await?;
+        let mut listed = HashMap::with_capacity(known.len());
+        let mut truncated = false;
         while let Some(entry) = entries.next_entry().await? {
-            let file_type = entry.file_type().await?;
-            if file_type.is_file() {
-                if let Ok(name) = entry.file_name().into_string() {
-                    if !name.starts_with('.') {
-                        files.push(name);
-                        file_count += 1;
+            if Instant::now() >= deadline {
+                truncated = true;
+                break;
+            }
+            let Ok(name) = entry.file_name().into_string() else {
+                continue;
+            };
+            let is_dir = match known.remove(&name) {
+                Some(is_dir) => is_dir,
+                None => {
+                    let file_type = entry.file_type().await?;
+                    if !file_type.is_file() && !file_type.is_dir() {
+                        continue;
                     }
+                    file_type.is_dir()
+                }
+            };
+            listed.insert(name, is_dir);
+        }
+        if truncated {
+            tracing::debug!(dir = %dir.display(), entries = listed.len(), "context scan truncated");
+        }
+
+        let context = Self::from_entries(&listed, truncated);
+        let mut cache = scan_cache().lock().unwrap_or_else(|e| e.into_inner());
+        if cache.len() >= SCAN_CACHE_CAPACITY {
+            cache.clear();
+        }
+        cache.insert(
+            dir.to_path_buf(),
+            CachedScan {
+                modified,
+                entries: listed,
+                complete: !truncated,
+            },
+        );
+        Ok(context)
+    }
+
+    fn from_entries(entries: &HashMap<String, bool>, truncated: bool) -> FileContext {
+        let files = entries
+            .iter()
+            .filter(|(name, is_dir)| !**is_dir && !name.starts_with('.'))
+            .map(|(name, _)| name.clone())
+            .collect();
+        let dir_count = entries.values().filter(|is_dir| **is_dir).count();
+        let mut context = Self::from_listing(files, dir_count);
+        if truncated {
+            context.truncated = true;
+            let note = format!(
+                "Scan truncated after {} entries; the directory holds more.",
+                entries.len()
+            );
+            context.summary = Some(match context.summary.take() {
+                Some(summary) => format!("{}\n{}", note, summary),
+                None => note,
+            });
+        }
+        context
+    }
+
+    /// Lists the files and directories named in an intent instead of the
+    /// cwd. Entries are named by the path as the user wrote it (with `~`
+    /// expanded), so a command built from them works from the cwd.
+    pub async fn scan_paths(paths: &[IntentPath]) -> Result<FileContext> {
+        let mut files = Vec::new();
+        let mut dir_count = 0;
+        let mut truncated = false;
+        for path in paths {
+            if !path.resolved.is_dir() {
+                files.push(path.display.clone());
+                continue;
+            }
+            // The scan leaves the full listing, with directories, in the
+            // cache; its own context names the files without the prefix.
+            truncated |= Self::scan_dir(&path.resolved).await?.truncated;
+            let cache = scan_cache().lock().unwrap_or_else(|e| e.into_inner());
+            let Some(cached) = cache.get(&path.resolved) else {
+                continue;
+            };
+            let prefix = path.display.trim_end_matches('/');
+            for (name, is_dir) in &cached.entries {
+                if *is_dir {
+                    dir_count += 1;
+                } else if !name.starts_with('.') {
+                    files.push(format!("{}/{}", prefix, name));
                 }
-            } else if file_type.is_dir() {
-                dir_count += 1;
             }
         }
+        let mut context = Self::from_listing(files, dir_count);
+        context.truncated = truncated;
+        let names: Vec<&str> = paths.iter().map(|p| p.display.as_str()).collect();
+        let note = format!(
+            "Listing covers {} (not the working directory); refer to files by the paths shown.",
+            names.join(", ")
+        );
+        context.summary = Some(match context.summary.take() {
+            Some(summary) => format!("{}\n{}", note, summary),
+            None => note,
+        });
+        Ok(context)
+    }
 
+    /// Takes the file names piped in on stdin, one per line, as the whole
+    /// context in place of a directory scan. Every name is kept in `files`;
+    /// the prompt spells out the first `PIPED_LIST_LIMIT`.
+    pub fn from_piped(input: &str) -> FileContext {
+        let mut seen = HashSet::new();
+        let files: Vec<String> = input
+            .lines()
+            .map(str::trim)
+            .map(|line| line.strip_prefix("./").unwrap_or(line))
+            .filter(|line| !line.is_empty() && seen.insert(*line))
+            .map(str::to_string)
+            .collect();
+        FileContext {
+            summary: Some(format!(
+                "The user piped in these {} files; act on exactly these, not the rest of the working directory:",
+                files.len()
+            )),
+            files,
+            exact: true,
+            ..FileContext::default()
+        }
+    }
+
+    /// Builds the context from a raw directory listing; `for_prompt`
+    /// summarizes it when it is large.
+    pub fn from_listing(mut files: Vec<String>, dir_count: usize) -> FileContext {
         files.sort();
+        FileContext {
+            files,
+            dirs: dir_count,
+            ..FileContext::default()
+        }
+    }
+}
 
-        if files.len() > 20 {
-            // Fallback to summary
-            let summary = format!(
-                "Directory contains {} files and {} subdirectories.\nTop 5 files:\n{}",
-                file_count,
-                dir_count,
-                files
-                    .iter()
-                    .take(5)
-                    .enumerate()
-                    .map(|(i, f)| format!("{}. {}", i + 1, f))
-                    .collect::<Vec<_>>()
-                    .join("\n")
-            );
-            Ok(FileContext {
-                files: files.into_iter().take(20).collect(),
-                summary: Some(summary),
-            })
+/// A file or directory named in an intent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntentPath {
+    /// As written, with a leading `~/` expanded.
+    pub display: String,
+    pub resolved: PathBuf,
+}
+
+/// Paths the intent points at: leading words that look like existing paths
+/// (`~/Videos ./clips shrink these`) and `@path` or `@"path with spaces"`
+/// anywhere. Returns them with the intent minus the `@` markers. Words that
+/// don't resolve to something on disk, and the spacing and line breaks
+/// between words, are left as they are; only the ends are trimmed.
+pub fn intent_paths(intent: &str, cwd: &Path) -> (Vec<IntentPath>, String) {
+    let mut paths = Vec::new();
+    let mut out = String::new();
+    let mut copied = 0;
+    let mut leading = true;
+    for span in split_intent_words(intent) {
+        let word = &intent[span.clone()];
+        let (marked, raw) = match word.strip_prefix('@') {
+            Some(rest) => (true, rest.trim_matches(|c| c == '"' || c == '\'')),
+            None => (false, word),
+        };
+        let path_like = raw.contains('/') || raw.starts_with('~') || raw == "." || raw == "..";
+        let candidate = (marked || (leading && path_like))
+            .then(|| resolve_intent_path(raw, cwd))
+            .flatten();
+        leading &= candidate.is_some();
+        let Some(path) = candidate else {
+            continue;
+        };
+        out.push_str(&intent[copied..span.start]);
+        if raw.contains(' ') {
+            out.push_str(&format!("\"{}\"", path.display));
         } else {
-            Ok(FileContext {
-                files,
-                summary: None,
-            })
+            out.push_str(&path.display);
+        }
+        copied = span.end;
+        if !paths.contains(&path) {
+            paths.push(path);
         }
     }
+    out.push_str(&intent[copied..]);
+    (paths, out.trim().to_string())
+}
+
+/// Byte ranges of the whitespace-separated words, keeping `@"..."` /
+/// `@'...'` in one piece.
+fn split_intent_words(intent: &str) -> Vec<std::ops::Range<usize>> {
+    let mut words = Vec::new();
+    let mut chars = intent.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        let quote = if c == '@' {
+            chars
+                .next_if(|(_, n)| *n == '"' || *n == '\'')
+                .map(|(_, n)| n)
+        } else {
+            None
+        };
+        if let Some(quote) = quote {
+            for (_, n) in chars.by_ref() {
+                if n == quote {
+                    break;
+                }
+            }
+        }
+        while chars.next_if(|(_, n)| !n.is_whitespace()).is_some() {}
+        let end = chars.peek().map_or(intent.len(), |(idx, _)| *idx);
+        words.push(start..end);
+    }
+    words
+}
+
+fn resolve_intent_path(raw: &str, cwd: &Path) -> Option<IntentPath> {
+    if raw.is_empty() {
+        return None;
+    }
+    let display = crate::paths::expand_home(raw).display().to_string();
+    let resolved = cwd.join(&display);
+    resolved
+        .exists()
+        .then_some(IntentPath { display, resolved })
+}
+
+/// Files that appeared in or left a watched directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContextChange {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl ContextChange {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    /// `2 added, 1 removed`.
+    pub fn label(&self) -> String {
+        match (self.added.len(), self.removed.len()) {
+            (added, 0) => format!("{} added", added),
+            (0, removed) => format!("{} removed", removed),
+            (added, removed) => format!("{} added, {} removed", added, removed),
+        }
+    }
+}
+
+/// Watches a directory's listing from the moment a preview was built.
+/// Polling only re-lists it when the directory's mtime moved, which every
+/// create, delete and rename inside it does.
+pub struct DirWatch {
+    dir: PathBuf,
+    modified: Option<SystemTime>,
+    baseline: HashSet<String>,
+    change: ContextChange,
+}
+
+impl DirWatch {
+    pub fn new(dir: &Path) -> Result<Self> {
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            modified: std::fs::metadata(dir)?.modified().ok(),
+            baseline: list_names(dir)?,
+            change: ContextChange::default(),
+        })
+    }
+
+    /// What changed since the watch began; `None` while the listing
+    /// matches it again.
+    pub fn poll(&mut self) -> Option<&ContextChange> {
+        let modified = std::fs::metadata(&self.dir)
+            .ok()
+            .and_then(|meta| meta.modified().ok());
+        if modified.is_none() || modified != self.modified {
+            self.modified = modified;
+            self.relist();
+        }
+        (!self.change.is_empty()).then_some(&self.change)
+    }
+
+    /// Like `poll`, but re-lists even when the mtime hasn't moved, which
+    /// filesystems with whole-second mtimes can hide. For the last check
+    /// before a command runs.
+    pub fn rescan(&mut self) -> Option<&ContextChange> {
+        self.modified = std::fs::metadata(&self.dir)
+            .ok()
+            .and_then(|meta| meta.modified().ok());
+        self.relist();
+        (!self.change.is_empty()).then_some(&self.change)
+    }
+
+    fn relist(&mut self) {
+        let current = list_names(&self.dir).unwrap_or_default();
+        let mut added: Vec<String> = current.difference(&self.baseline).cloned().collect();
+        let mut removed: Vec<String> = self.baseline.difference(&current).cloned().collect();
+        added.sort();
+        removed.sort();
+        self.change = ContextChange { added, removed };
+    }
+}
+
+/// Non-hidden entry names in `dir`.
+fn list_names(dir: &Path) -> Result<HashSet<String>> {
+    let mut names = HashSet::new();
+    for entry in std::fs::read_dir(dir)? {
+        if let Ok(name) = entry?.file_name().into_string() {
+            if !name.starts_with('.') {
+                names.insert(name);
+            }
+        }
+    }
+    Ok(names)
 }
 
 #[cfg(test)]
@@ -82,11 +616,157 @@ mod tests {
 
         let context = ContextScanner::scan_cwd().await?;
 
-        assert_eq!(context.files.len(), 20); // Limit is 20
-        assert!(context.summary.is_some());
-        assert!(context.summary.unwrap().contains("25 files"));
+        assert_eq!(context.files.len(), 25);
+        assert!(context.is_summarized(DEFAULT_CONTEXT_FILES));
+        let prompt = context.for_prompt("rename them", DEFAULT_CONTEXT_FILES);
+        assert!(prompt.contains("25 files"));
+        assert!(prompt.contains("(20 of 25)"));
 
         std::env::set_current_dir(original_cwd)?;
         Ok(())
     }
+
+    #[tokio::test]
+    async fn rescans_only_when_the_directory_changes() -> Result<()> {
+        let dir = tempdir()?;
+        File::create(dir.path().join("a.txt"))?;
+        std::fs::create_dir(dir.path().join("sub"))?;
+        let first = ContextScanner::scan_dir(dir.path()).await?;
+        assert_eq!(first.files, vec!["a.txt"]);
+        assert!(!first.truncated);
+
+        // Same mtime: served from the cache, which is what `scan_dir` sees.
+        scan_cache()
+            .lock()
+            .unwrap()
+            .get_mut(dir.path())
+            .unwrap()
+            .entries
+            .insert("cached.txt".to_string(), false);
+        let cached = ContextScanner::scan_dir(dir.path()).await?;
+        assert_eq!(cached.files, vec!["a.txt", "cached.txt"]);
+
+        // A new entry changes the mtime; the stale cached name disappears.
+        // Coarse filesystem clocks can give both writes the same mtime, so
+        // the cached one is moved back rather than waiting for a tick.
+        File::create(dir.path().join("b.txt"))?;
+        scan_cache()
+            .lock()
+            .unwrap()
+            .get_mut(dir.path())
+            .unwrap()
+            .modified = Some(SystemTime::UNIX_EPOCH);
+        let rescanned = ContextScanner::scan_dir(dir.path()).await?;
+        assert_eq!(rescanned.files, vec!["a.txt", "b.txt"]);
+
+        let fresh = tempdir()?;
+        File::create(fresh.path().join("c.txt"))?;
+        let partial = ContextScanner::scan_dir_within(fresh.path(), Duration::ZERO).await?;
+        assert!(partial.truncated);
+        assert!(partial.files.is_empty());
+        assert!(partial.summary.unwrap().contains("truncated"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn intent_paths_pick_leading_and_marked_paths() -> Result<()> {
+        let dir = tempdir()?;
+        let cwd = dir.path();
+        std::fs::create_dir_all(cwd.join("clips"))?;
+        std::fs::create_dir_all(cwd.join("My Photos"))?;
+        File::create(cwd.join("clips/a.mp4"))?;
+        File::create(cwd.join("My Photos/b.jpg"))?;
+        File::create(cwd.join("notes.md"))?;
+
+        let (paths, intent) = intent_paths("./clips shrink these @notes.md @nope", cwd);
+        let names: Vec<_> = paths.iter().map(|p| p.display.as_str()).collect();
+        assert_eq!(names, ["./clips", "notes.md"]);
+        assert_eq!(intent, "./clips shrink these notes.md @nope");
+
+        let (paths, intent) = intent_paths(r#"rename @"My Photos" by date"#, cwd);
+        assert_eq!(paths[0].display, "My Photos");
+        assert_eq!(intent, r#"rename "My Photos" by date"#);
+        let (_, intent) = intent_paths("  rename  @notes.md\nthen \"a  b\"  ", cwd);
+        assert_eq!(intent, "rename  notes.md\nthen \"a  b\"");
+        assert!(intent_paths("compress clips/", cwd).0.is_empty());
+
+        let (paths, _) = intent_paths("./clips @notes.md", cwd);
+        let context = ContextScanner::scan_paths(&paths).await?;
+        assert_eq!(context.files, ["./clips/a.mp4", "notes.md"]);
+        assert!(context.summary.unwrap().contains("./clips, notes.md"));
+        Ok(())
+    }
+
+    #[test]
+    fn piped_names_replace_the_scan() {
+        let context = ContextScanner::from_piped("./a.mkv\n\n  b c.mkv \na.mkv\n");
+        assert_eq!(context.files, ["a.mkv", "b c.mkv"]);
+        let prompt = context.for_prompt("shrink these", 1);
+        assert!(prompt.contains("piped in these 2 files"));
+        assert!(prompt.ends_with("1. a.mkv\n2. b c.mkv"));
+
+        let many: String = (0..PIPED_LIST_LIMIT + 3)
+            .map(|i| format!("f{}.mkv\n", i))
+            .collect();
+        let context = ContextScanner::from_piped(&many);
+        assert_eq!(context.files.len(), PIPED_LIST_LIMIT + 3);
+        assert!(context
+            .for_prompt("", DEFAULT_CONTEXT_FILES)
+            .ends_with("... and 3 more"));
+    }
+
+    #[test]
+    fn large_listings_are_summarized_around_the_request() {
+        let mut files: Vec<String> = (0..40).map(|i| format!("IMG_{:04}.jpg", i)).collect();
+        files.extend((0..10).map(|i| format!("clip{}.mp4", i)));
+        files.push("invoice.pdf".to_string());
+        files.push("README".to_string());
+        let context = ContextScanner::from_listing(files, 3);
+        assert_eq!(context.files.len(), 52);
+
+        let prompt = context.for_prompt("convert the clip files to webm", 5);
+        assert!(prompt.starts_with("Directory contains 52 files and 3 subdirectories."));
+        assert!(prompt.contains("By extension: 40 .jpg, 10 .mp4, 1 (no extension), 1 .pdf"));
+        assert!(prompt.contains("Examples: IMG_0000.jpg, IMG_0039.jpg, clip0.mp4, clip9.mp4"));
+        assert!(prompt.contains("Outliers: README, invoice.pdf"));
+        assert!(prompt.ends_with(
+            "(5 of 52):\n1. clip0.mp4\n2. clip1.mp4\n3. clip2.mp4\n4. clip3.mp4\n5. clip4.mp4"
+        ));
+
+        // Under the limit, every name is listed after the notes.
+        let small = FileContext {
+            summary: Some("Pinned project files: brief.md".to_string()),
+            ..ContextScanner::from_listing(vec!["b.mkv".to_string(), "a.mkv".to_string()], 0)
+        };
+        assert_eq!(
+            small.for_prompt("x", DEFAULT_CONTEXT_FILES),
+            "Pinned project files: brief.md\n1. a.mkv\n2. b.mkv"
+        );
+        assert_eq!(
+            FileContext::default().for_prompt("x", DEFAULT_CONTEXT_FILES),
+            "(no visible files in current directory)"
+        );
+    }
+
+    #[test]
+    fn dir_watch_reports_files_added_and_removed() -> Result<()> {
+        let dir = tempdir()?;
+        File::create(dir.path().join("a.jpg"))?;
+        File::create(dir.path().join("b.jpg"))?;
+        let mut watch = DirWatch::new(dir.path())?;
+        assert_eq!(watch.poll(), None);
+
+        File::create(dir.path().join("c.jpg"))?;
+        std::fs::remove_file(dir.path().join("a.jpg"))?;
+        // Sub-second mtimes are not available everywhere; force a re-list.
+        let change = watch.rescan().cloned().unwrap();
+        assert_eq!(change.added, ["c.jpg"]);
+        assert_eq!(change.removed, ["a.jpg"]);
+        assert_eq!(change.label(), "1 added, 1 removed");
+
+        File::create(dir.path().join("a.jpg"))?;
+        std::fs::remove_file(dir.path().join("c.jpg"))?;
+        assert_eq!(watch.rescan(), None);
+        Ok(())
+    }
 }
@@ -0,0 +1,153 @@
+use anyhow::Result;
+use serde::Deserialize;
+
+use crate::context::{FileContext, DEFAULT_CONTEXT_FILES};
+use crate::language::language_section;
+use crate::llm::{JsonReply, LlmClient};
+use crate::redaction::redact_sensitive_text;
+
+const CRITIC_USER_INPUT: &str = "Review the command now. Reply with the JSON object only.";
+
+/// Most issues shown for one command; a critic listing more is rambling.
+const MAX_ISSUES: usize = 5;
+
+/// Second opinion on a generated command from a (usually cheaper) model,
+/// asked before the preview so mismatches show up next to it.
+pub struct Critic {
+    llm_client: LlmClient,
+    language: String,
+    context_file_limit: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct CriticResponse {
+    #[serde(default)]
+    issues: Vec<String>,
+}
+
+impl JsonReply for CriticResponse {
+    const KIND: &'static str = "critic review";
+
+    fn schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "required": ["issues"],
+            "properties": {
+                "issues": { "type": "array", "items": { "type": "string" } }
+            }
+        })
+    }
+}
+
+impl Critic {
+    pub fn new(llm_client: LlmClient) -> Self {
+        Self {
+            llm_client,
+            language: "auto".to_string(),
+            context_file_limit: DEFAULT_CONTEXT_FILES,
+        }
+    }
+
+    /// `config.language`: what the issues are written in.
+    pub fn with_language(mut self, language: &str) -> Self {
+        self.language = language.to_string();
+        self
+    }
+
+    /// `config.context_file_limit`: how many files the prompt lists by name.
+    pub fn with_context_file_limit(mut self, limit: usize) -> Self {
+        self.context_file_limit = limit;
+        self
+    }
+
+    pub fn llm_client(&self) -> &LlmClient {
+        &self.llm_client
+    }
+
+    /// Ways `command` may not do what `user_input` asked, one sentence
+    /// each; empty when the critic found nothing.
+    #[tracing::instrument(name = "critic", skip_all, fields(plugin = plugin_name))]
+    pub async fn review(
+        &self,
+        user_input: &str,
+        context: &FileContext,
+        plugin_name: &str,
+        command: &str,
+    ) -> Result<Vec<String>> {
+        let files = context.for_prompt(user_input, self.context_file_limit);
+        let mut system_prompt = critic_system_prompt(user_input, &files, plugin_name, command);
+        system_prompt.push_str(&language_section(&self.language, user_input));
+        let response = self
+            .llm_client
+            .json_completion::<CriticResponse>(&system_prompt, CRITIC_USER_INPUT)
+            .await?;
+        Ok(response.value.into_issues())
+    }
+}
+
+fn critic_system_prompt(user_input: &str, files: &str, plugin_name: &str, command: &str) -> String {
+    format!(
+        r#"You are the Command Critic for Dexter. Another model turned the user's request into a `{}` command. Check it before it is previewed.
+
+### HARD CONSTRAINTS (MUST FOLLOW):
+1. Only report concrete mismatches between the request, the files and the command: wrong or missing file/extension filter, files that are not in the listing, missing or wrong output path or flag, wrong format, codec, size or quality, or an operation the user did not ask for.
+2. Do NOT comment on style, flag order or equivalent spellings, and do NOT suggest improvements the user did not ask for.
+3. Each issue is one short sentence naming the part of the command that is wrong.
+4. Output ONLY JSON: {{"issues": ["..."]}}. Use {{"issues": []}} when the command matches the request.
+
+User request: {}
+
+Files:
+{}
+
+Command:
+{}
+"#,
+        plugin_name,
+        user_input,
+        files,
+        redact_sensitive_text(command)
+    )
+}
+
+impl CriticResponse {
+    fn into_issues(self) -> Vec<String> {
+        self.issues
+            .into_iter()
+            .map(|issue| issue.trim().to_string())
+            .filter(|issue| !issue.is_empty())
+            .take(MAX_ISSUES)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::parse_json_reply;
+
+    #[test]
+    fn parses_fenced_replies_and_drops_blank_issues() {
+        let issues =
+            |reply: &str| parse_json_reply::<CriticResponse>(reply).map(|r| r.into_issues());
+        let reply =
+            "```json\n{\"issues\": [\" Filter is *.png but the files are .jpg \", \"\"]}\n```";
+        assert_eq!(
+            issues(reply).unwrap(),
+            vec!["Filter is *.png but the files are .jpg"]
+        );
+        assert!(issues(r#"{"issues": []}"#).unwrap().is_empty());
+        assert!(issues("{}").unwrap().is_empty());
+        assert!(issues("Looks fine to me.").is_err());
+
+        let files = FileContext {
+            files: vec!["a.jpg".to_string()],
+            ..FileContext::default()
+        }
+        .for_prompt("convert to png", DEFAULT_CONTEXT_FILES);
+        let prompt =
+            critic_system_prompt("convert to png", &files, "libvips", "vips copy a.jpg a.png");
+        assert!(prompt.contains("1. a.jpg"));
+        assert!(prompt.contains("vips copy a.jpg a.png"));
+    }
+}
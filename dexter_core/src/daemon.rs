@@ -0,0 +1,435 @@
+use anyhow::{anyhow, Context, Result};
+use dexter_plugins::Plugin;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::sync::Mutex;
+
+use crate::config::Config;
+use crate::context::{ContextScanner, FileContext};
+use crate::executor::{load_dir_uses, Executor, RunEnvironment, RunOutcome};
+use crate::hooks::Hooks;
+use crate::llm::LlmClient;
+use crate::ocr::{self, OcrPolicy};
+use crate::router::{RouteOutcome, Router};
+use crate::safety::SafetyPolicy;
+use crate::workspace::Workspace;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DaemonRequest {
+    /// Route and generate a command for `intent` as if typed in `cwd`.
+    Propose {
+        intent: String,
+        cwd: PathBuf,
+        /// File names piped to the client; they replace the scan of `cwd`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        files: Option<String>,
+    },
+    /// Record a command the client confirmed and ran.
+    Record {
+        plugin: String,
+        command: String,
+        /// Absent from clients older than run outcomes.
+        #[serde(default)]
+        outcome: Option<RunOutcome>,
+        /// What the command was generated for, kept for few-shot examples.
+        #[serde(default)]
+        intent: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct QuickOption {
+    pub label: String,
+    pub resolved_intent: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum DaemonResponse {
+    Command {
+        plugin: String,
+        command: String,
+    },
+    Clarify {
+        question: String,
+        options: Vec<QuickOption>,
+    },
+    Unsupported {
+        reason: String,
+    },
+    Recorded,
+    Error {
+        message: String,
+    },
+}
+
+struct CachedContext {
+    modified: SystemTime,
+    context: FileContext,
+}
+
+/// Keeps the parsed config, LLM clients and per-directory context scans alive
+/// between `dexter quick` invocations. The daemon only proposes commands;
+/// confirmation and execution stay in the client, in the user's terminal.
+pub struct Daemon {
+    router: Router,
+    executor: Executor,
+    plugins: Vec<Arc<dyn Plugin>>,
+    ocr: OcrPolicy,
+    safety: SafetyPolicy,
+    contexts: Mutex<HashMap<PathBuf, CachedContext>>,
+}
+
+impl Daemon {
+    pub fn new(config: &Config, plugins: Vec<Arc<dyn Plugin>>) -> Self {
+        let providers = config.llm_providers();
+        let router_client = LlmClient::with_routes(
+            providers.clone(),
+            config.models.router_routes.clone(),
+            config.models.router_model.clone(),
+            config.models.router_fallback_models.clone(),
+        )
+        .with_spend_tracking(config.pricing.clone());
+        let executor_client = LlmClient::with_routes(
+            providers,
+            config.models.executor_routes.clone(),
+            config.models.executor_model.clone(),
+            config.models.executor_fallback_models.clone(),
+        )
+        .with_spend_tracking(config.pricing.clone());
+        Self {
+            router: Router::new(router_client)
+                .with_offline(config.offline)
+                .with_strategy(config.models.router_strategy)
+                .with_routing(config.models.routing)
+                .with_language(&config.language)
+                .with_context_file_limit(config.context_file_limit)
+                .with_clarify_options(config.clarify_options),
+            executor: Executor::new(executor_client)
+                .with_language(&config.language)
+                .with_history_examples(config.history_examples)
+                .with_context_file_limit(config.context_file_limit)
+                .with_hooks(Hooks::from_config_dir()),
+            plugins,
+            ocr: config.ocr.clone(),
+            safety: config.safety.clone(),
+            contexts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn handle(&self, request: DaemonRequest) -> DaemonResponse {
+        let result = match request {
+            DaemonRequest::Propose { intent, cwd, files } => {
+                self.propose(&intent, &cwd, files.as_deref()).await
+            }
+            DaemonRequest::Record {
+                plugin,
+                command,
+                outcome,
+                intent,
+            } => {
+                // Clients run commands on this machine, with these tools.
+                let environment = match self.plugins.iter().find(|p| p.name() == plugin) {
+                    Some(p) => Some(RunEnvironment::capture(p.as_ref()).await),
+                    None => None,
+                };
+                match outcome {
+                    Some(outcome) => {
+                        self.executor
+                            .record_run(&plugin, &command, intent.as_deref(), outcome, environment)
+                            .await
+                    }
+                    None => {
+                        self.executor
+                            .record_history(&plugin, &command, intent.as_deref(), environment)
+                            .await
+                    }
+                }
+                .map(|_| DaemonResponse::Recorded)
+            }
+        };
+        result.unwrap_or_else(|e| DaemonResponse::Error {
+            message: e.to_string(),
+        })
+    }
+
+    async fn propose(
+        &self,
+        intent: &str,
+        cwd: &Path,
+        files: Option<&str>,
+    ) -> Result<DaemonResponse> {
+        let workspace = Workspace::discover(cwd)?;
+        let mut context = match files {
+            Some(files) => ContextScanner::from_piped(files),
+            None => {
+                let mut context = self.context_for(cwd).await?;
+                workspace.pin_context(cwd, &mut context);
+                context
+            }
+        };
+        ocr::enrich(&mut context, cwd, intent, &self.ocr).await;
+        let router = self
+            .router
+            .clone()
+            .with_preferred_plugins(workspace.settings.preferred_plugins.clone())
+            .with_dir_history(load_dir_uses(cwd).await.unwrap_or_default());
+        let outcome = router.route(intent, &context, &self.plugins).await?;
+        let plugin_name = match outcome {
+            RouteOutcome::Selected { plugin, .. } => plugin,
+            RouteOutcome::Unsupported { reason } => {
+                return Ok(DaemonResponse::Unsupported { reason })
+            }
+            RouteOutcome::Clarify {
+                question, options, ..
+            } => {
+                return Ok(DaemonResponse::Clarify {
+                    question,
+                    options: options
+                        .into_iter()
+                        .map(|o| QuickOption {
+                            label: o.label,
+                            resolved_intent: o.resolved_intent,
+                        })
+                        .collect(),
+                })
+            }
+        };
+
+        let plugin = self
+            .plugins
+            .iter()
+            .find(|p| p.name() == plugin_name)
+            .ok_or_else(|| anyhow!("Plugin not found: {}", plugin_name))?;
+        let guard = workspace
+            .safety_guard()
+            .clone()
+            .with_policy(&self.safety, cwd)?;
+        guard.check_plugin(&plugin_name)?;
+        let command = self
+            .executor
+            .clone()
+            .with_safety_guard(guard)
+            .with_output_dir(workspace.output_dir(cwd))
+            .generate_command(intent, &context, plugin.as_ref())
+            .await?;
+        Ok(DaemonResponse::Command {
+            plugin: plugin_name,
+            command,
+        })
+    }
+
+    /// Reuses the previous scan of `dir` until its mtime changes, which
+    /// happens whenever an entry is added, removed or renamed. The cache
+    /// isn't locked during a scan, so requests for other directories don't
+    /// wait on it.
+    async fn context_for(&self, dir: &Path) -> Result<FileContext> {
+        let modified = tokio::fs::metadata(dir).await?.modified()?;
+        if let Some(cached) = self.contexts.lock().await.get(dir) {
+            if cached.modified == modified {
+                return Ok(cached.context.clone());
+            }
+        }
+        let context = ContextScanner::scan_dir(dir).await?;
+        self.contexts.lock().await.insert(
+            dir.to_path_buf(),
+            CachedContext {
+                modified,
+                context: context.clone(),
+            },
+        );
+        Ok(context)
+    }
+
+    /// Serves one JSON request per line until the process is stopped.
+    #[cfg(unix)]
+    pub async fn serve(self: Arc<Self>, socket: &Path) -> Result<()> {
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+        use tokio::net::{UnixListener, UnixStream};
+
+        if socket.exists() {
+            if UnixStream::connect(socket).await.is_ok() {
+                return Err(anyhow!(
+                    "A Dexter daemon is already listening on {}",
+                    socket.display()
+                ));
+            }
+            std::fs::remove_file(socket).context("Failed to remove stale daemon socket")?;
+        }
+        // Only the user may reach the socket, even before it is chmodded.
+        if let Some(parent) = socket.parent() {
+            std::fs::DirBuilder::new()
+                .recursive(true)
+                .mode(0o700)
+                .create(parent)?;
+        }
+        let listener = UnixListener::bind(socket)
+            .with_context(|| format!("Failed to bind {}", socket.display()))?;
+        std::fs::set_permissions(socket, std::fs::Permissions::from_mode(0o600))?;
+
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let daemon = self.clone();
+            tokio::spawn(async move {
+                let (read, mut write) = stream.into_split();
+                let mut lines = BufReader::new(read).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let response = match serde_json::from_str::<DaemonRequest>(&line) {
+                        Ok(request) => daemon.handle(request).await,
+                        Err(e) => DaemonResponse::Error {
+                            message: format!("Invalid request: {}", e),
+                        },
+                    };
+                    let Ok(mut payload) = serde_json::to_string(&response) else {
+                        break;
+                    };
+                    payload.push('\n');
+                    if write.write_all(payload.as_bytes()).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub async fn serve(self: Arc<Self>, _socket: &Path) -> Result<()> {
+        Err(anyhow!("Daemon mode is only supported on Unix platforms"))
+    }
+}
+
+/// Default socket location: the per-user runtime dir when the platform has
+/// one, otherwise the cache dir.
+pub fn socket_path() -> Result<PathBuf> {
+    let base = crate::paths::runtime_dir()
+        .or_else(crate::paths::cache_dir)
+        .context("Could not find a runtime or cache directory")?;
+    Ok(base.join("dexter").join("daemon.sock"))
+}
+
+#[cfg(unix)]
+pub async fn daemon_available(socket: &Path) -> bool {
+    tokio::net::UnixStream::connect(socket).await.is_ok()
+}
+
+#[cfg(not(unix))]
+pub async fn daemon_available(_socket: &Path) -> bool {
+    false
+}
+
+/// Sends one request to a running daemon over a fresh connection.
+#[cfg(unix)]
+pub async fn send_request(socket: &Path, request: &DaemonRequest) -> Result<DaemonResponse> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::UnixStream;
+
+    let stream = UnixStream::connect(socket)
+        .await
+        .with_context(|| format!("No Dexter daemon listening on {}", socket.display()))?;
+    let (read, mut write) = stream.into_split();
+    let mut payload = serde_json::to_string(request)?;
+    payload.push('\n');
+    write.write_all(payload.as_bytes()).await?;
+
+    let line = BufReader::new(read)
+        .lines()
+        .next_line()
+        .await?
+        .ok_or_else(|| anyhow!("Daemon closed the connection without replying"))?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+#[cfg(not(unix))]
+pub async fn send_request(_socket: &Path, _request: &DaemonRequest) -> Result<DaemonResponse> {
+    Err(anyhow!("Daemon mode is only supported on Unix platforms"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn protocol_round_trips_as_tagged_json() {
+        let request = DaemonRequest::Propose {
+            intent: "convert a.mov to mp4".to_string(),
+            cwd: PathBuf::from("/tmp"),
+            files: None,
+        };
+        let json = serde_json::to_string(&request).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"propose","intent":"convert a.mov to mp4","cwd":"/tmp"}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<DaemonRequest>(&json).unwrap(),
+            request
+        );
+
+        let response: DaemonResponse =
+            serde_json::from_str(r#"{"status":"unsupported","reason":"no"}"#).unwrap();
+        assert_eq!(
+            response,
+            DaemonResponse::Unsupported {
+                reason: "no".to_string()
+            }
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn serves_requests_and_caches_context_until_dir_changes() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir()?;
+        std::fs::File::create(dir.path().join("a.txt"))?;
+        let daemon = Arc::new(Daemon::new(&Config::default(), Vec::new()));
+
+        let first = daemon.context_for(dir.path()).await?;
+        assert_eq!(first.files, vec!["a.txt"]);
+        // Same mtime: the cached listing is returned as-is.
+        daemon
+            .contexts
+            .lock()
+            .await
+            .get_mut(dir.path())
+            .unwrap()
+            .context = ContextScanner::from_listing(vec!["cached.txt".to_string()], 0);
+        assert_eq!(
+            daemon.context_for(dir.path()).await?.files,
+            vec!["cached.txt"]
+        );
+
+        let socket = dir.path().join("run").join("daemon.sock");
+        let server = tokio::spawn({
+            let socket = socket.clone();
+            async move { daemon.serve(&socket).await }
+        });
+        // The server binds asynchronously; retry briefly.
+        let mut response = None;
+        for _ in 0..50 {
+            let request = DaemonRequest::Propose {
+                intent: "do something".to_string(),
+                cwd: dir.path().join("missing"),
+                files: None,
+            };
+            if let Ok(r) = send_request(&socket, &request).await {
+                response = Some(r);
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        server.abort();
+        assert!(matches!(response, Some(DaemonResponse::Error { .. })));
+        let mode = std::fs::metadata(dir.path().join("run"))?
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o700);
+        Ok(())
+    }
+}
@@ -0,0 +1,310 @@
+use crate::llm::{CachePolicy, LlmClient};
+use anyhow::{anyhow, Result};
+use dexter_plugins::health::{find_in_path, installed_binary, installed_version, major_version};
+use dexter_plugins::Plugin;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+use tokio::process::Command;
+
+/// Longest help text sent for summarizing; ffmpeg's full help runs to
+/// hundreds of kilobytes.
+const MAX_HELP_CHARS: usize = 24_000;
+
+/// Help shorter than this is topped up with the man page.
+const SHORT_HELP_CHARS: usize = 1_500;
+
+/// Which binary a cached version came from, and when it was last written.
+type BinaryStamp = Option<(PathBuf, SystemTime)>;
+
+type DetectedVersions = Mutex<HashMap<String, (BinaryStamp, Option<String>)>>;
+
+/// Version line of each plugin's installed tool, probed again once the
+/// binary is replaced, so a long-running daemon notices upgrades.
+fn detected_versions() -> &'static DetectedVersions {
+    static VERSIONS: OnceLock<DetectedVersions> = OnceLock::new();
+    VERSIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn binary_stamp(plugin: &dyn Plugin) -> BinaryStamp {
+    let path = installed_binary(plugin)?;
+    let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+    Some((path, modified))
+}
+
+/// `<config dir>/dexter/docs/`: a `<plugin>/v<major>.md` there replaces the
+/// built-in docs for that version, `<plugin>/default.md` for any version.
+pub fn docs_dir() -> Option<PathBuf> {
+    crate::paths::config_dir().map(|dir| dir.join("dexter").join("docs"))
+}
+
+/// `<cache dir>/dexter/docs/`: docs `sync_doc` summarized from each
+/// installed tool's help, as `<plugin>/<version>.md`.
+pub fn synced_docs_dir() -> Option<PathBuf> {
+    crate::paths::cache_dir().map(|dir| dir.join("dexter").join("docs"))
+}
+
+/// The executor docs matching the locally installed version of the
+/// plugin's tool: a user bundle first, then docs synced from that exact
+/// version's help, then the plugin's own bundle for its major version,
+/// then its default docs.
+pub async fn executor_doc(plugin: &dyn Plugin) -> String {
+    let version = installed_version_line(plugin).await;
+    resolve_doc(
+        plugin,
+        version.as_deref(),
+        docs_dir().as_deref(),
+        synced_docs_dir().as_deref(),
+    )
+}
+
+/// The lock only guards the map: the probe runs without it, so callers
+/// never wait on another plugin's tool.
+pub(crate) async fn installed_version_line(plugin: &dyn Plugin) -> Option<String> {
+    let stamp = binary_stamp(plugin);
+    let cached = detected_versions()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(plugin.name())
+        .filter(|(cached, _)| *cached == stamp)
+        .map(|(_, version)| version.clone());
+    if let Some(version) = cached {
+        return version;
+    }
+    let version = installed_version(plugin).await;
+    detected_versions()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(plugin.name().to_string(), (stamp, version.clone()));
+    version
+}
+
+fn resolve_doc(
+    plugin: &dyn Plugin,
+    version: Option<&str>,
+    user_dir: Option<&Path>,
+    synced_dir: Option<&Path>,
+) -> String {
+    let major = version.and_then(major_version);
+    let mut candidates = Vec::new();
+    if let Some(dir) = user_dir.map(|dir| dir.join(plugin.name())) {
+        candidates.extend(major.map(|major| dir.join(format!("v{}.md", major))));
+        candidates.push(dir.join("default.md"));
+    }
+    if let (Some(dir), Some(version)) = (synced_dir, version) {
+        candidates.push(synced_doc_path(dir, plugin, version));
+    }
+    for path in candidates {
+        match std::fs::read_to_string(&path) {
+            Ok(doc) if !doc.trim().is_empty() => return doc,
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                tracing::warn!(path = %path.display(), error = %e, "unreadable doc bundle")
+            }
+        }
+    }
+    major
+        .and_then(|major| plugin.doc_bundles().iter().find(|b| b.major == major))
+        .map(|bundle| bundle.doc.to_string())
+        .unwrap_or_else(|| plugin.get_doc_for_executor().to_string())
+}
+
+/// `<dir>/<plugin>/<version>.md`, with the version line reduced to
+/// characters safe in a file name.
+fn synced_doc_path(dir: &Path, plugin: &dyn Plugin, version: &str) -> PathBuf {
+    let key: String = version
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    dir.join(plugin.name()).join(format!("{}.md", key))
+}
+
+/// Summarizes the installed tool's `--help` (and man page, when the help
+/// is terse) into executor docs with `llm`, and caches them for that exact
+/// version so later prompts only mention flags the local build has.
+/// Returns where the docs were written.
+pub async fn sync_doc(plugin: &dyn Plugin, llm: &LlmClient) -> Result<PathBuf> {
+    if plugin.built_in() {
+        return Err(anyhow!("{} is built into Dexter", plugin.name()));
+    }
+    let dir = synced_docs_dir().ok_or_else(|| anyhow!("Could not find cache directory"))?;
+    let binary = plugin
+        .binaries()
+        .iter()
+        .copied()
+        .chain([plugin.name()])
+        .find_map(find_in_path)
+        .ok_or_else(|| anyhow!("{} is not installed", plugin.name()))?;
+    let version = installed_version(plugin)
+        .await
+        .ok_or_else(|| anyhow!("Could not read the {} version", plugin.name()))?;
+
+    let mut help = tool_help(&binary).await;
+    if help.chars().count() < SHORT_HELP_CHARS {
+        if let Some(man) = man_page(&binary).await {
+            help.push_str("\n\n");
+            help.push_str(&man);
+        }
+    }
+    if help.trim().is_empty() {
+        return Err(anyhow!("{} printed no help text", binary.display()));
+    }
+    let help: String = help.chars().take(MAX_HELP_CHARS).collect();
+
+    let doc = llm
+        .completion_with_policy(
+            &sync_system_prompt(plugin),
+            &format!("Help output of `{}`:\n{}", version, help),
+            CachePolicy::Bypass,
+        )
+        .await?;
+    let doc = doc
+        .trim()
+        .trim_start_matches("```markdown")
+        .trim_start_matches("```")
+        .trim_end_matches("```")
+        .trim()
+        .to_string();
+    if doc.is_empty() {
+        return Err(anyhow!("The model returned empty docs"));
+    }
+
+    let path = synced_doc_path(&dir, plugin, &version);
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(&path, format!("{}\n", doc)).await?;
+    detected_versions()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(
+            plugin.name().to_string(),
+            (binary_stamp(plugin), Some(version)),
+        );
+    Ok(path)
+}
+
+fn sync_system_prompt(plugin: &dyn Plugin) -> String {
+    format!(
+        "You write the usage notes another model reads before generating `{name}` commands.\n\
+         Rewrite the help output you are given into the same format as these current notes: \
+         usage lines, then examples, then numbered notes. Mention only flags that appear in the \
+         help output, keep it under 60 lines, and output only the notes.\n\n\
+         ### Current notes:\n{doc}",
+        name = plugin.name(),
+        doc = plugin.get_doc_for_executor()
+    )
+}
+
+/// `--help` output, or `-h` for tools that reject the long form.
+async fn tool_help(binary: &Path) -> String {
+    for arg in ["--help", "-h"] {
+        let Ok(output) = Command::new(binary).arg(arg).output().await else {
+            return String::new();
+        };
+        let text = format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        if output.status.success() || text.len() > SHORT_HELP_CHARS {
+            return text;
+        }
+    }
+    String::new()
+}
+
+/// The tool's man page as plain text, without overstrike formatting.
+async fn man_page(binary: &Path) -> Option<String> {
+    let name = binary.file_stem()?.to_str()?;
+    let output = Command::new("man")
+        .arg(name)
+        .env("MANPAGER", "cat")
+        .env("MANWIDTH", "100")
+        .output()
+        .await
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| strip_overstrike(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Drops the `x\bx` bold and `_\bx` underline sequences `man` emits.
+fn strip_overstrike(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c == '\u{8}' {
+            out.pop();
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dexter_plugins::F2Plugin;
+    use tempfile::tempdir;
+
+    #[test]
+    fn picks_user_then_versioned_then_default_docs() {
+        let plugin = F2Plugin::default();
+        let v1 = Some("f2 version v1.9.1");
+        let v2 = Some("f2 version v2.0.3");
+        let default = plugin.get_doc_for_executor().to_string();
+        assert_eq!(resolve_doc(&plugin, None, None, None), default);
+        assert_eq!(resolve_doc(&plugin, v1, None, None), default);
+        let bundled = resolve_doc(&plugin, v2, None, None);
+        assert!(bundled.contains("{%03d}") && !bundled.contains("{{%03d}}"));
+
+        let dir = tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("f2")).unwrap();
+        std::fs::write(dir.path().join("f2/v2.md"), "custom v2").unwrap();
+        assert_eq!(
+            resolve_doc(&plugin, v2, Some(dir.path()), None),
+            "custom v2"
+        );
+        assert_eq!(resolve_doc(&plugin, v1, Some(dir.path()), None), default);
+        std::fs::write(dir.path().join("f2/default.md"), "custom any").unwrap();
+        assert_eq!(
+            resolve_doc(&plugin, v1, Some(dir.path()), None),
+            "custom any"
+        );
+    }
+
+    #[test]
+    fn synced_docs_apply_to_their_exact_version_only() {
+        let plugin = F2Plugin::default();
+        let synced = tempdir().unwrap();
+        let path = synced_doc_path(synced.path(), &plugin, "f2 version v2.0.3");
+        assert!(path.ends_with("f2/f2_version_v2.0.3.md"));
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "synced v2.0.3").unwrap();
+
+        let v2 = Some("f2 version v2.0.3");
+        assert_eq!(
+            resolve_doc(&plugin, v2, None, Some(synced.path())),
+            "synced v2.0.3"
+        );
+        let other = resolve_doc(
+            &plugin,
+            Some("f2 version v2.1.0"),
+            None,
+            Some(synced.path()),
+        );
+        assert_ne!(other, "synced v2.0.3");
+
+        assert_eq!(strip_overstrike("N\u{8}NA\u{8}AME _\u{8}f"), "NAME f");
+    }
+}
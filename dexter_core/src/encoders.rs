@@ -0,0 +1,232 @@
+//! Which jobs of a set compete for a hardware video encoder. GPU encoders
+//! have a fixed number of sessions (consumer NVIDIA cards refuse the one
+//! past their NVENC limit outright), so jobs on one run one at a time while
+//! CPU-only jobs run side by side.
+
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// Longest `nvidia-smi` may take before the session count is given up.
+const NVIDIA_SMI_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Concurrent NVENC sessions a consumer NVIDIA card allows (driver 550 and
+/// later; older drivers allowed fewer, and they report it the same way).
+pub const NVENC_SESSION_LIMIT: u32 = 8;
+
+/// How often a held NVENC job asks `nvidia-smi` again for a free session.
+const NVENC_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A family of hardware encoders, named by the suffix ffmpeg gives its
+/// encoders (`h264_nvenc`, `hevc_qsv`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HwEncoder {
+    Nvenc,
+    Qsv,
+    Vaapi,
+    VideoToolbox,
+    Amf,
+    V4l2m2m,
+}
+
+impl HwEncoder {
+    const ALL: [HwEncoder; 6] = [
+        Self::Nvenc,
+        Self::Qsv,
+        Self::Vaapi,
+        Self::VideoToolbox,
+        Self::Amf,
+        Self::V4l2m2m,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Nvenc => "nvenc",
+            Self::Qsv => "qsv",
+            Self::Vaapi => "vaapi",
+            Self::VideoToolbox => "videotoolbox",
+            Self::Amf => "amf",
+            Self::V4l2m2m => "v4l2m2m",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Nvenc => "NVENC",
+            Self::Qsv => "Quick Sync",
+            Self::Vaapi => "VA-API",
+            Self::VideoToolbox => "VideoToolbox",
+            Self::Amf => "AMF",
+            Self::V4l2m2m => "V4L2",
+        }
+    }
+
+    /// `-hwaccel` values that decode on the same device.
+    fn hwaccels(self) -> &'static [&'static str] {
+        match self {
+            Self::Nvenc => &["cuda", "nvdec", "cuvid"],
+            Self::Qsv => &["qsv"],
+            Self::Vaapi => &["vaapi"],
+            Self::VideoToolbox => &["videotoolbox"],
+            Self::Amf | Self::V4l2m2m => &[],
+        }
+    }
+}
+
+/// Where a job runs next to the others of its set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum JobLane {
+    /// Uses the encoder: waits for the other jobs on it.
+    Hardware(HwEncoder),
+    /// CPU only: runs alongside other jobs.
+    Cpu,
+}
+
+impl JobLane {
+    /// The lane of `command`. Only ffmpeg commands use a hardware encoder,
+    /// through a `*_nvenc`-style encoder or `-hwaccel`.
+    pub fn of(command: &str) -> Self {
+        let argv = shell_words::split(command).unwrap_or_default();
+        let is_ffmpeg = argv.first().is_some_and(|program| {
+            std::path::Path::new(program)
+                .file_stem()
+                .is_some_and(|stem| stem == "ffmpeg")
+        });
+        if !is_ffmpeg {
+            return Self::Cpu;
+        }
+        for (idx, arg) in argv.iter().enumerate().skip(1) {
+            let hwaccel = (argv[idx - 1] == "-hwaccel").then_some(arg.as_str());
+            for encoder in HwEncoder::ALL {
+                if arg.ends_with(&format!("_{}", encoder.name()))
+                    || hwaccel.is_some_and(|value| encoder.hwaccels().contains(&value))
+                {
+                    return Self::Hardware(encoder);
+                }
+            }
+        }
+        Self::Cpu
+    }
+
+    /// `NVENC` or `CPU`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Hardware(encoder) => encoder.label(),
+            Self::Cpu => "CPU",
+        }
+    }
+}
+
+/// NVENC sessions open right now, across all GPUs, by `nvidia-smi`. `None`
+/// without an NVIDIA driver.
+pub async fn nvenc_sessions_in_use() -> Option<u32> {
+    let query = Command::new("nvidia-smi")
+        .args([
+            "--query-gpu=encoder.stats.sessionCount",
+            "--format=csv,noheader,nounits",
+        ])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(NVIDIA_SMI_TIMEOUT, query)
+        .await
+        .ok()?
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.trim().parse::<u32>().ok())
+        .sum()
+}
+
+/// Waits until other programs leave an NVENC session free. Called by the
+/// job holding the NVENC lane before it starts, so Dexter never opens the
+/// session the card would refuse. Returns at once without an NVIDIA
+/// driver; `on_wait` runs once, with the count, if the job has to wait.
+pub async fn wait_for_nvenc_session(mut on_wait: impl FnMut(u32)) {
+    let mut waited = false;
+    while let Some(busy) = nvenc_sessions_in_use().await {
+        if busy < NVENC_SESSION_LIMIT {
+            return;
+        }
+        if !std::mem::replace(&mut waited, true) {
+            on_wait(busy);
+        }
+        tokio::time::sleep(NVENC_POLL_INTERVAL).await;
+    }
+}
+
+/// What the scheduler decided for `lanes`, one line per lane in use:
+/// `3 CPU job(s) run up to 4 at a time`.
+pub fn describe_plan(lanes: &[JobLane], parallel: usize, nvenc_busy: Option<u32>) -> Vec<String> {
+    let mut counts: BTreeMap<JobLane, usize> = BTreeMap::new();
+    for lane in lanes {
+        *counts.entry(*lane).or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .map(|(lane, count)| match lane {
+            JobLane::Hardware(encoder) => {
+                let mut line = format!(
+                    "{} {} job(s) run one at a time: the encoder has a limited number of \
+                     sessions",
+                    count,
+                    encoder.label()
+                );
+                if let (HwEncoder::Nvenc, Some(busy)) = (encoder, nvenc_busy.filter(|n| *n > 0)) {
+                    line.push_str(&format!(" and {} are in use by other programs", busy));
+                    if busy >= NVENC_SESSION_LIMIT {
+                        line.push_str("; they wait for one to free up");
+                    }
+                }
+                line
+            }
+            JobLane::Cpu if parallel <= 1 => {
+                format!("{} CPU job(s) run one at a time", count)
+            }
+            JobLane::Cpu => format!("{} CPU job(s) run up to {} at a time", count, parallel),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hardware_encoded_ffmpeg_commands_get_their_encoder_lane() {
+        assert_eq!(
+            JobLane::of("ffmpeg -i a.mkv -c:v hevc_nvenc out_qsv.mp4"),
+            JobLane::Hardware(HwEncoder::Nvenc)
+        );
+        assert_eq!(
+            JobLane::of("ffmpeg -hwaccel vaapi -i a.mkv -c:v libx264 a.mp4"),
+            JobLane::Hardware(HwEncoder::Vaapi)
+        );
+        assert_eq!(
+            JobLane::of("ffmpeg -i a.mkv -c:v libx264 a_nvenc.mp4"),
+            JobLane::Cpu
+        );
+        assert_eq!(JobLane::of("f2 -f h264_nvenc -r x"), JobLane::Cpu);
+
+        let lanes = [
+            JobLane::Cpu,
+            JobLane::Hardware(HwEncoder::Nvenc),
+            JobLane::Cpu,
+        ];
+        assert_eq!(
+            describe_plan(&lanes, 4, Some(2)),
+            [
+                "1 NVENC job(s) run one at a time: the encoder has a limited number of \
+                 sessions and 2 are in use by other programs",
+                "2 CPU job(s) run up to 4 at a time",
+            ]
+        );
+        assert!(describe_plan(&lanes, 4, Some(NVENC_SESSION_LIMIT))[0]
+            .ends_with("in use by other programs; they wait for one to free up"));
+    }
+}
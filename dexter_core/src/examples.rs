@@ -0,0 +1,151 @@
+//! Few-shot examples from the user's own history: the accepted runs whose
+//! requests read most like the new one, shown to the executor so generated
+//! commands follow the naming and flag habits of commands that were kept.
+
+use std::collections::HashSet;
+
+use crate::executor::HistoryEntry;
+
+/// Word overlap (Jaccard) a past request needs to count as similar.
+const MIN_SIMILARITY: f32 = 0.25;
+
+/// Words that say nothing about which command fits.
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "all", "as", "at", "by", "every", "for", "from", "in", "into", "it", "me",
+    "my", "of", "on", "please", "the", "them", "these", "this", "those", "to", "with",
+];
+
+/// Up to `limit` successful `plugin` runs from `history` (oldest first)
+/// whose recorded intents are most like `intent`, best first. Newer runs
+/// win ties, and each intent and each command is used once.
+pub fn similar_runs<'a>(
+    history: &'a [HistoryEntry],
+    plugin: &str,
+    intent: &str,
+    limit: usize,
+) -> Vec<&'a HistoryEntry> {
+    let wanted = words(intent);
+    if limit == 0 || wanted.is_empty() {
+        return Vec::new();
+    }
+    let mut seen = HashSet::new();
+    let mut scored: Vec<(f32, &HistoryEntry)> = history
+        .iter()
+        .rev()
+        .filter(|entry| entry.plugin == plugin)
+        .filter(|entry| entry.outcome.is_some_and(|outcome| outcome.success))
+        .filter_map(|entry| {
+            let past = entry.intent.as_deref()?;
+            let score = similarity(&wanted, &words(past));
+            (score >= MIN_SIMILARITY).then_some((score, entry))
+        })
+        .filter(|(_, entry)| {
+            let new_intent = seen.insert(entry.intent.clone().unwrap_or_default());
+            let new_command = seen.insert(entry.command.clone());
+            new_intent && new_command
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(_, entry)| entry)
+        .collect()
+}
+
+fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|word| !word.is_empty() && !STOP_WORDS.contains(&word.as_str()))
+        .collect()
+}
+
+fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f32 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f32 / union as f32
+}
+
+/// Executor prompt section listing `examples`; empty without any.
+pub(crate) fn examples_section(examples: &[&HistoryEntry]) -> String {
+    if examples.is_empty() {
+        return String::new();
+    }
+    let listed = examples
+        .iter()
+        .filter_map(|entry| {
+            Some(format!(
+                "Intent: {}\nCommand: {}",
+                entry.intent.as_deref()?,
+                entry.command
+            ))
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    format!(
+        "\n### Accepted Earlier By This User:\n{}\nThese similar requests ran as shown. Follow their naming conventions, output locations and flag choices where they fit this intent; the file names and paths must still come from the current context.\n",
+        listed
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::RunOutcome;
+
+    fn run(intent: &str, command: &str, success: bool) -> HistoryEntry {
+        HistoryEntry {
+            outcome: Some(RunOutcome {
+                success,
+                exit_code: Some(if success { 0 } else { 1 }),
+                duration_ms: 10,
+                output_bytes: 0,
+            }),
+            ..HistoryEntry::new("ffmpeg", command).with_intent(intent)
+        }
+    }
+
+    #[test]
+    fn picks_the_closest_accepted_runs() {
+        let history = vec![
+            run(
+                "convert talk.mkv to webm",
+                "ffmpeg -i talk.mkv talk.webm",
+                true,
+            ),
+            run(
+                "convert the mkv files to webm for the web",
+                "ffmpeg -i a.mkv -c:v libvpx-vp9 web/a.webm",
+                true,
+            ),
+            run(
+                "convert a.mkv to webm",
+                "ffmpeg -i a.mkv broken.webm",
+                false,
+            ),
+            run(
+                "extract the audio as mp3",
+                "ffmpeg -i a.mp4 -vn a.mp3",
+                true,
+            ),
+            HistoryEntry::new("ffmpeg", "ffmpeg -i b.mkv b.webm"),
+        ];
+        let picked = similar_runs(&history, "ffmpeg", "convert lecture.mkv to webm", 3);
+        let commands: Vec<&str> = picked.iter().map(|e| e.command.as_str()).collect();
+        assert_eq!(
+            commands,
+            [
+                "ffmpeg -i talk.mkv talk.webm",
+                "ffmpeg -i a.mkv -c:v libvpx-vp9 web/a.webm"
+            ]
+        );
+        assert!(similar_runs(&history, "f2", "convert talk.mkv to webm", 3).is_empty());
+        assert!(similar_runs(&history, "ffmpeg", "convert talk.mkv to webm", 0).is_empty());
+
+        let section = examples_section(&picked[..1]);
+        assert!(section.contains("Intent: convert talk.mkv to webm\nCommand: ffmpeg"));
+        assert_eq!(examples_section(&[]), "");
+    }
+}
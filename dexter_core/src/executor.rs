@@ -1,20 +1,56 @@
-use crate::context::FileContext;
-use crate::llm::LlmClient;
+use crate::config::ConflictPolicy;
+use crate::conflict::{apply_conflict_policy, find_output_conflicts, ConflictOutcome};
+use crate::context::{FileContext, DEFAULT_CONTEXT_FILES};
+use crate::docs::{executor_doc, installed_version_line};
+use crate::examples::{examples_section, similar_runs};
+use crate::hooks::Hooks;
+use crate::language::language_section;
+use crate::llm::{truncate_error, Attachment, BatchProgress, BatchRequest, LlmClient};
 use crate::redaction::redact_sensitive_text;
-use crate::safety::SafetyGuard;
+use crate::safety::{SafetyGuard, SafetyViolation};
+use crate::session::session_section;
 use crate::CachePolicy;
 use anyhow::{Context, Result};
 use chrono::Utc;
+use dexter_plugins::command_exec::exit_code_of;
 use dexter_plugins::Plugin;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
 use tokio::fs::{self, create_dir_all, OpenOptions};
 use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
 
+const EXECUTOR_USER_INPUT: &str =
+    "Please generate the exact command based on the instructions above.";
+
+/// Lets the model answer with an argument list instead of a command line,
+/// so file names never need shell quoting. The object then is the command
+/// the plugin prompts' OUTPUT ONLY rule asks for.
+const STRUCTURED_REPLY_NOTE: &str = "\n\n### Reply Format:\n\
+Instead of a command line you may output only a JSON object, without Markdown; it counts as \
+the command: {\"program\": \"...\", \"args\": [\"...\"], \"inputs\": [\"...\"], \"outputs\": [\"...\"]}, \
+where args are the command's arguments without shell quoting and inputs and outputs list the \
+file arguments the command reads and writes. To split a long command across lines, put an \
+argument of just \"\\n\" where a new line starts.";
+
+/// Tail of the error output kept when asking for a repair; the end of
+/// stderr is where tools put the reason they gave up.
+const REPAIR_ERROR_CHARS: usize = 2000;
+
+#[derive(Clone)]
 pub struct Executor {
     llm_client: LlmClient,
     safety_guard: SafetyGuard,
+    output_dir: Option<String>,
+    session: Option<String>,
+    language: String,
+    history_examples: usize,
+    context_file_limit: usize,
+    hooks: Hooks,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -22,6 +58,102 @@ pub struct HistoryEntry {
     pub timestamp: String,
     pub plugin: String,
     pub command: String,
+    /// Filled in once the command ends; missing for runs that never
+    /// finished and for entries from older versions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<RunOutcome>,
+    /// What the user asked for; missing for runs recorded without one and
+    /// for entries from older versions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intent: Option<String>,
+    /// What the command ran with; missing for remote runs and for entries
+    /// from older versions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<RunEnvironment>,
+}
+
+/// The versions a recorded run depended on, so a command that worked last
+/// month can be told apart from one that broke with a tool upgrade.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RunEnvironment {
+    pub dexter_version: String,
+    /// `Ubuntu 24.04.1 LTS (x86_64)`; the bare OS name where the release
+    /// can't be read.
+    pub os: String,
+    /// First line of the plugin tool's `--version`; `None` when it isn't
+    /// installed or doesn't say.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_version: Option<String>,
+}
+
+impl RunEnvironment {
+    /// This machine's environment for a run of `plugin`. The tool version
+    /// is probed once per process.
+    pub async fn capture(plugin: &dyn Plugin) -> Self {
+        Self {
+            dexter_version: env!("CARGO_PKG_VERSION").to_string(),
+            os: os_description().to_string(),
+            tool_version: installed_version_line(plugin).await,
+        }
+    }
+}
+
+fn os_description() -> &'static str {
+    static OS: OnceLock<String> = OnceLock::new();
+    OS.get_or_init(|| {
+        let release = os_release().unwrap_or_else(|| std::env::consts::OS.to_string());
+        format!("{} ({})", release, std::env::consts::ARCH)
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn os_release() -> Option<String> {
+    let raw = std::fs::read_to_string("/etc/os-release").ok()?;
+    raw.lines()
+        .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+        .map(|name| name.trim_matches('"').to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn os_release() -> Option<String> {
+    let output = std::process::Command::new("sw_vers")
+        .arg("-productVersion")
+        .output()
+        .ok()?;
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!version.is_empty()).then(|| format!("macOS {}", version))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn os_release() -> Option<String> {
+    None
+}
+
+/// How one recorded run went.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RunOutcome {
+    pub success: bool,
+    /// `None` when the tool reported none: killed by a signal, or failed
+    /// before it started.
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    /// Size of the output (or error text) the run produced.
+    pub output_bytes: u64,
+}
+
+impl RunOutcome {
+    pub fn from_result(elapsed: Duration, result: &Result<String>) -> Self {
+        let (exit_code, output_bytes) = match result {
+            Ok(output) => (Some(0), output.len()),
+            Err(e) => (exit_code_of(e), e.to_string().len()),
+        };
+        Self {
+            success: result.is_ok(),
+            exit_code,
+            duration_ms: elapsed.as_millis().try_into().unwrap_or(u64::MAX),
+            output_bytes: output_bytes as u64,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -30,6 +162,40 @@ pub struct PinnedHistoryEntry {
     pub plugin: String,
     pub command: String,
     pub pinned_at: String,
+    /// What the user asked for, when the command was pinned from its preview.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intent: Option<String>,
+}
+
+/// Size and modification time of the history and the pins file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistoryStamp([Option<(SystemTime, u64)>; 2]);
+
+impl HistoryEntry {
+    /// An entry stamped now, with secrets redacted from the command.
+    pub fn new(plugin_name: &str, command: &str) -> Self {
+        Self {
+            timestamp: Utc::now().to_rfc3339(),
+            plugin: plugin_name.to_string(),
+            command: redact_sensitive_text(command),
+            outcome: None,
+            intent: None,
+            environment: None,
+        }
+    }
+
+    /// The same entry recording `intent`, with secrets redacted; a blank
+    /// intent records none.
+    pub fn with_intent(mut self, intent: &str) -> Self {
+        let intent = intent.trim();
+        self.intent = (!intent.is_empty()).then(|| redact_sensitive_text(intent));
+        self
+    }
+
+    pub fn with_environment(mut self, environment: Option<RunEnvironment>) -> Self {
+        self.environment = environment;
+        self
+    }
 }
 
 impl Executor {
@@ -37,9 +203,71 @@ impl Executor {
         Self {
             llm_client,
             safety_guard: SafetyGuard::default(),
+            output_dir: None,
+            session: None,
+            language: "auto".to_string(),
+            history_examples: 0,
+            context_file_limit: DEFAULT_CONTEXT_FILES,
+            hooks: Hooks::default(),
         }
     }
 
+    /// Earlier runs the model may use to resolve follow-up intents.
+    pub fn with_session(mut self, transcript: Option<String>) -> Self {
+        self.session = transcript;
+        self
+    }
+
+    /// `config.language`: what any note for the user is written in.
+    pub fn with_language(mut self, language: &str) -> Self {
+        self.language = language.to_string();
+        self
+    }
+
+    /// `config.history_examples`: how many similar accepted runs from the
+    /// history each generation prompt shows as examples.
+    pub fn with_history_examples(mut self, limit: usize) -> Self {
+        self.history_examples = limit;
+        self
+    }
+
+    /// `config.context_file_limit`: how many files each generation prompt lists
+    /// by name before it summarizes the directory instead.
+    pub fn with_context_file_limit(mut self, limit: usize) -> Self {
+        self.context_file_limit = limit;
+        self
+    }
+
+    /// Directory the project's `.dexter.toml` sends new output files to.
+    pub fn with_output_dir(mut self, output_dir: Option<String>) -> Self {
+        self.output_dir = output_dir;
+        self
+    }
+
+    /// The rules generated and hook-rewritten commands must pass; the
+    /// built-in ones by default.
+    pub fn with_safety_guard(mut self, guard: SafetyGuard) -> Self {
+        self.safety_guard = guard;
+        self
+    }
+
+    /// Hook scripts run on each request once it is routed and on each
+    /// command once it is generated.
+    pub fn with_hooks(mut self, hooks: Hooks) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    pub fn hooks(&self) -> &Hooks {
+        &self.hooks
+    }
+
+    /// Sends generation to `llm_client` instead, for a one-off model pick.
+    pub fn with_llm_client(mut self, llm_client: LlmClient) -> Self {
+        self.llm_client = llm_client;
+        self
+    }
+
     pub fn llm_client(&self) -> &LlmClient {
         &self.llm_client
     }
@@ -54,6 +282,27 @@ impl Executor {
             .await
     }
 
+    /// Full text `generate_command` would send to the model.
+    pub async fn prompt_for(
+        &self,
+        user_input: &str,
+        context: &FileContext,
+        plugin: &dyn Plugin,
+    ) -> String {
+        let history = self.example_history().await;
+        format!(
+            "{}\n{}",
+            self.system_prompt(user_input, context, plugin, &history)
+                .await,
+            EXECUTOR_USER_INPUT
+        )
+    }
+
+    #[tracing::instrument(
+        name = "generation",
+        skip_all,
+        fields(plugin = plugin.name(), cache = ?cache_policy)
+    )]
     pub async fn generate_command_with_policy(
         &self,
         user_input: &str,
@@ -61,37 +310,225 @@ impl Executor {
         plugin: &dyn Plugin,
         cache_policy: CachePolicy,
     ) -> Result<String> {
-        let context_str = if let Some(summary) = &context.summary {
-            summary.clone()
-        } else {
-            context
-                .files
-                .iter()
-                .enumerate()
-                .map(|(i, f)| format!("{}. {}", i + 1, f))
-                .collect::<Vec<_>>()
-                .join("\n")
-        };
-
-        let system_prompt = plugin.get_executor_prompt(&context_str, user_input);
+        let user_input = &self.hooks.rewrite_intent(user_input, plugin.name()).await;
+        let history = self.example_history().await;
+        let system_prompt = self
+            .system_prompt(user_input, context, plugin, &history)
+            .await;
+        let attachments: Vec<Attachment> = Attachment::file_list(context, self.context_file_limit)
+            .into_iter()
+            .collect();
 
         let command = self
             .llm_client
-            .completion_with_policy(
+            .completion_with_attachments(
                 &system_prompt,
-                "Please generate the exact command based on the instructions above.",
+                EXECUTOR_USER_INPUT,
+                &attachments,
                 cache_policy,
             )
             .await?;
-        let clean_command = command
-            .trim()
-            .replace("```bash", "")
-            .replace("```", "")
-            .trim()
-            .to_string();
+        let command = self.finalize_command(&command, plugin).await?;
+        Ok(self.hooked_command(user_input, &command, plugin).await)
+    }
 
-        // Safety Check
-        self.safety_guard.check(&clean_command)?;
+    /// Asks for a corrected command after `failed_command` exited with
+    /// `error_output`. The fix passes the same safety and plugin checks as a
+    /// fresh command and must differ from the one that failed.
+    #[tracing::instrument(name = "repair", skip_all, fields(plugin = plugin.name()))]
+    pub async fn repair_command(
+        &self,
+        user_input: &str,
+        context: &FileContext,
+        plugin: &dyn Plugin,
+        failed_command: &str,
+        error_output: &str,
+    ) -> Result<String> {
+        let request = repair_user_input(failed_command, error_output);
+        self.corrected_command(user_input, context, plugin, failed_command, &request)
+            .await
+    }
+
+    /// Asks for another command after the safety rules refused
+    /// `blocked_command` for `reason`, telling the model to stay within
+    /// the rule. The answer is checked like any other.
+    #[tracing::instrument(name = "safety_retry", skip_all, fields(plugin = plugin.name()))]
+    pub async fn constrained_command(
+        &self,
+        user_input: &str,
+        context: &FileContext,
+        plugin: &dyn Plugin,
+        blocked_command: &str,
+        reason: &str,
+    ) -> Result<String> {
+        let request = safety_user_input(blocked_command, reason);
+        self.corrected_command(user_input, context, plugin, blocked_command, &request)
+            .await
+    }
+
+    /// A new command for the intent, asked for with `request` in place of
+    /// the usual closing instruction; it must differ from `previous`.
+    async fn corrected_command(
+        &self,
+        user_input: &str,
+        context: &FileContext,
+        plugin: &dyn Plugin,
+        previous: &str,
+        request: &str,
+    ) -> Result<String> {
+        let user_input = &self.hooks.rewrite_intent(user_input, plugin.name()).await;
+        let history = self.example_history().await;
+        let system_prompt = self
+            .system_prompt(user_input, context, plugin, &history)
+            .await;
+        let attachments: Vec<Attachment> = Attachment::file_list(context, self.context_file_limit)
+            .into_iter()
+            .collect();
+        let command = self
+            .llm_client
+            .completion_with_attachments(&system_prompt, request, &attachments, CachePolicy::Bypass)
+            .await?;
+        let command = self.finalize_command(&command, plugin).await?;
+        let command = self.hooked_command(user_input, &command, plugin).await;
+        if command.trim() == previous.trim() {
+            return Err(anyhow::anyhow!("The model returned the same command again"));
+        }
+        Ok(command)
+    }
+
+    /// Generates one command per `(intent, context)` job with a single
+    /// provider batch, reporting progress while the batch completes. Each job
+    /// gets its own result so one rejected command doesn't sink the rest.
+    #[tracing::instrument(
+        name = "batch_generation",
+        skip_all,
+        fields(plugin = plugin.name(), jobs = jobs.len())
+    )]
+    pub async fn generate_commands_batch(
+        &self,
+        jobs: &[(String, FileContext)],
+        plugin: &dyn Plugin,
+        progress: mpsc::Sender<BatchProgress>,
+    ) -> Result<Vec<Result<String>>> {
+        let history = self.example_history().await;
+        let mut intents = Vec::with_capacity(jobs.len());
+        for (user_input, _) in jobs {
+            intents.push(self.hooks.rewrite_intent(user_input, plugin.name()).await);
+        }
+        let mut requests = Vec::with_capacity(jobs.len());
+        for (user_input, (_, context)) in intents.iter().zip(jobs) {
+            requests.push(BatchRequest {
+                system_prompt: self
+                    .system_prompt(user_input, context, plugin, &history)
+                    .await,
+                user_input: EXECUTOR_USER_INPUT.to_string(),
+            });
+        }
+        let responses = self
+            .llm_client
+            .batch_completion(&requests, progress)
+            .await?;
+        let mut commands = Vec::with_capacity(responses.len());
+        for (response, user_input) in responses.into_iter().zip(&intents) {
+            let command = match response {
+                Ok(reply) => self.finalize_command(&reply, plugin).await,
+                Err(e) => Err(e),
+            };
+            commands.push(match command {
+                Ok(command) => Ok(self.hooked_command(user_input, &command, plugin).await),
+                Err(e) => Err(e),
+            });
+        }
+        Ok(commands)
+    }
+
+    /// The history to draw examples from; empty when they are off or the
+    /// history can't be read.
+    async fn example_history(&self) -> Vec<HistoryEntry> {
+        if self.history_examples == 0 {
+            return Vec::new();
+        }
+        self.load_history_entries().await.unwrap_or_else(|e| {
+            tracing::debug!(error = %e, "history unavailable for examples");
+            Vec::new()
+        })
+    }
+
+    async fn system_prompt(
+        &self,
+        user_input: &str,
+        context: &FileContext,
+        plugin: &dyn Plugin,
+        history: &[HistoryEntry],
+    ) -> String {
+        let doc = executor_doc(plugin).await;
+        let context = context.for_prompt(user_input, self.context_file_limit);
+        let mut settings = plugin.settings().clone();
+        let mut prompt = match settings.override_prompt(&doc, &context, user_input) {
+            Some(prompt) => {
+                tracing::debug!(
+                    plugin = plugin.name(),
+                    "executor prompt overridden by config"
+                );
+                prompt
+            }
+            None => plugin.get_executor_prompt_with_doc(&doc, &context, user_input),
+        };
+        if let Some(dir) = &self.output_dir {
+            prompt.push_str(&format!(
+                "\n\nProject rule: write new output files into `{}/` unless the user names another location.",
+                dir
+            ));
+            // The project's directory wins over the plugin's.
+            settings.output_dir = None;
+        }
+        prompt.push_str(&settings.prompt_rules());
+        let examples = similar_runs(history, plugin.name(), user_input, self.history_examples);
+        if !examples.is_empty() {
+            tracing::debug!(examples = examples.len(), "added examples from history");
+        }
+        prompt.push_str(&examples_section(&examples));
+        prompt.push_str(&session_section(self.session.as_deref()));
+        prompt.push_str(&language_section(&self.language, user_input));
+        prompt.push_str(STRUCTURED_REPLY_NOTE);
+        prompt
+    }
+
+    /// The command line in `reply`, from a JSON command object or, for
+    /// models that answer with a plain line, the line itself. A JSON reply
+    /// that can't be read is dropped from the cache and refused.
+    async fn finalize_command(&self, reply: &str, plugin: &dyn Plugin) -> Result<String> {
+        let (clean_command, checked) = match plugin.parse_structured(reply) {
+            Some(structured) => (
+                structured.to_command_line(),
+                self.safety_guard.check_structured(&structured),
+            ),
+            None => {
+                let clean_command = reply
+                    .trim()
+                    .replace("```bash", "")
+                    .replace("```", "")
+                    .trim()
+                    .to_string();
+                let body = clean_command.strip_prefix("json").unwrap_or(&clean_command);
+                if body.trim_start().starts_with('{') {
+                    self.llm_client.forget_cached(reply).await;
+                    return Err(anyhow::anyhow!(
+                        "The model's JSON command could not be read: {}",
+                        truncate_error(reply)
+                    ));
+                }
+                let checked = self.safety_guard.check(&clean_command);
+                (clean_command, checked)
+            }
+        };
+        if let Err(e) = checked {
+            return Err(SafetyViolation {
+                command: clean_command,
+                reason: e.to_string(),
+            }
+            .into());
+        }
 
         // Plugin specific validation
         if !plugin.validate_command(&clean_command) {
@@ -101,34 +538,102 @@ impl Executor {
         Ok(clean_command)
     }
 
-    pub async fn record_history(&self, plugin_name: &str, command: &str) -> Result<()> {
-        let history_dir = history_dir()?;
-        if !history_dir.exists() {
-            create_dir_all(&history_dir).await?;
+    /// `command` after the generate hook; a rewrite must pass the same
+    /// safety and plugin checks as the model's command.
+    async fn hooked_command(&self, user_input: &str, command: &str, plugin: &dyn Plugin) -> String {
+        self.hooks
+            .rewrite_command(user_input, plugin.name(), command, |rewritten| {
+                self.safety_guard.check(rewritten)?;
+                if !plugin.validate_command(rewritten) {
+                    return Err(anyhow::anyhow!("Command failed plugin validation logic"));
+                }
+                Ok(())
+            })
+            .await
+    }
+
+    /// Checks whether `cmd` would overwrite existing files in `cwd` and
+    /// applies `policy`: a rewritten command, a skip, or a question for the
+    /// user. Run as part of the dry run, before anything is previewed.
+    pub fn resolve_output_conflicts(
+        &self,
+        plugin_name: &str,
+        cmd: &str,
+        cwd: &Path,
+        policy: ConflictPolicy,
+    ) -> Result<ConflictOutcome> {
+        let conflicts = find_output_conflicts(plugin_name, cmd, cwd)?;
+        if conflicts.is_empty() {
+            return Ok(ConflictOutcome::Clear);
         }
+        tracing::info!(
+            plugin = plugin_name,
+            conflicts = conflicts.len(),
+            policy = ?policy,
+            "generated command would overwrite existing files"
+        );
+        apply_conflict_policy(plugin_name, cmd, conflicts, policy)
+    }
+
+    /// Records a command about to run, with the intent it was generated
+    /// for and the environment it runs in; `record_outcome` completes the
+    /// entry once it ends.
+    pub async fn record_history(
+        &self,
+        plugin_name: &str,
+        command: &str,
+        intent: Option<&str>,
+        environment: Option<RunEnvironment>,
+    ) -> Result<HistoryEntry> {
+        let entry = HistoryEntry::new(plugin_name, command)
+            .with_intent(intent.unwrap_or_default())
+            .with_environment(environment);
+        append_history_entry(&history_path()?, &entry).await?;
+        Ok(entry)
+    }
 
-        let history_path = history_dir.join("history.jsonl");
-        let redacted_command = redact_sensitive_text(command);
+    /// Records a command that already ran.
+    pub async fn record_run(
+        &self,
+        plugin_name: &str,
+        command: &str,
+        intent: Option<&str>,
+        outcome: RunOutcome,
+        environment: Option<RunEnvironment>,
+    ) -> Result<HistoryEntry> {
         let entry = HistoryEntry {
-            timestamp: Utc::now().to_rfc3339(),
-            plugin: plugin_name.to_string(),
-            command: redacted_command,
+            outcome: Some(outcome),
+            ..HistoryEntry::new(plugin_name, command)
+                .with_intent(intent.unwrap_or_default())
+                .with_environment(environment)
         };
+        append_history_entry(&history_path()?, &entry).await?;
+        Ok(entry)
+    }
 
-        let mut file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(history_path)
-            .await?;
+    /// Changes whenever this or another instance writes the history or the
+    /// pins; the History view compares it to refresh.
+    pub fn history_stamp(&self) -> HistoryStamp {
+        let stamp = |path: Result<PathBuf>| {
+            let meta = std::fs::metadata(path.ok()?).ok()?;
+            Some((meta.modified().ok()?, meta.len()))
+        };
+        HistoryStamp([stamp(history_path()), stamp(pin_path())])
+    }
 
-        let line = serde_json::to_string(&entry)?;
-        file.write_all(format!("{}\n", line).as_bytes()).await?;
-        Ok(())
+    pub async fn record_outcome(&self, entry: &HistoryEntry, outcome: RunOutcome) -> Result<()> {
+        record_outcome_in_path(&history_path()?, entry, outcome).await
     }
 
     pub async fn load_history_entries(&self) -> Result<Vec<HistoryEntry>> {
+        self.load_recent_history_entries(usize::MAX).await
+    }
+
+    /// The newest `limit` entries, oldest first. The file is read through,
+    /// but older entries aren't kept.
+    pub async fn load_recent_history_entries(&self, limit: usize) -> Result<Vec<HistoryEntry>> {
         let path = history_path()?;
-        let (entries, skipped_lines) = load_history_entries_from_path(&path).await?;
+        let (entries, skipped_lines) = load_recent_entries_from_path(&path, limit).await?;
         if skipped_lines > 0 {
             tracing::warn!(
                 "Skipped {} invalid history line(s) in {}",
@@ -144,9 +649,11 @@ impl Executor {
         load_pinned_entries_from_path(&path).await
     }
 
-    pub async fn set_pin(&self, entry: &HistoryEntry) -> Result<()> {
+    /// Pins `entry`, which need not be in the history yet: a command pinned
+    /// from its preview is listed as a favorite before it ever runs.
+    pub async fn set_pin(&self, entry: &HistoryEntry, intent: Option<&str>) -> Result<()> {
         let path = pin_path()?;
-        set_pin_in_path(&path, entry).await
+        set_pin_in_path(&path, entry, intent).await
     }
 
     pub async fn unset_pin(&self, entry: &HistoryEntry) -> Result<()> {
@@ -155,8 +662,151 @@ impl Executor {
     }
 }
 
+fn repair_user_input(failed_command: &str, error_output: &str) -> String {
+    let error = error_output.trim();
+    let skip = error.chars().count().saturating_sub(REPAIR_ERROR_CHARS);
+    let tail: String = error.chars().skip(skip).collect();
+    format!(
+        "This command was run and failed:\n{}\n\nError output{}:\n{}\n\n\
+         Generate a corrected command that achieves the instructions above. \
+         Output only the command.",
+        redact_sensitive_text(failed_command),
+        if skip > 0 { " (last lines)" } else { "" },
+        redact_sensitive_text(&tail)
+    )
+}
+
+fn safety_user_input(blocked_command: &str, reason: &str) -> String {
+    format!(
+        "Dexter's safety rules refused this command:\n{}\n\nReason: {}\n\n\
+         Generate a different command that achieves the instructions above \
+         without breaking that rule. Output only the command.",
+        redact_sensitive_text(blocked_command),
+        reason.trim()
+    )
+}
+
+/// Remembers that `plugin_name` just finished a command successfully. The
+/// history can't tell: the TUI writes it before the command runs.
+pub async fn record_plugin_success(plugin_name: &str) -> Result<()> {
+    record_plugin_success_in_path(&successes_path()?, plugin_name).await
+}
+
+/// RFC 3339 time of each plugin's last successful run.
+pub async fn load_plugin_successes() -> Result<HashMap<String, String>> {
+    load_plugin_successes_from_path(&successes_path()?).await
+}
+
+async fn record_plugin_success_in_path(path: &Path, plugin_name: &str) -> Result<()> {
+    let _lock = lock_store(path).await?;
+    let mut successes = load_plugin_successes_from_path(path)
+        .await
+        .unwrap_or_default();
+    successes.insert(plugin_name.to_string(), Utc::now().to_rfc3339());
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent).await?;
+    }
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, serde_json::to_string_pretty(&successes)?).await?;
+    fs::rename(&tmp_path, path).await?;
+    Ok(())
+}
+
+async fn load_plugin_successes_from_path(path: &Path) -> Result<HashMap<String, String>> {
+    match fs::read_to_string(path).await {
+        Ok(raw) => Ok(serde_json::from_str(&raw)?),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(HashMap::new()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Directories `dir_usage.json` keeps; the ones used longest ago go first.
+const DIR_USAGE_LIMIT: usize = 500;
+
+/// How often each plugin ran successfully in one directory.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct DirUsage {
+    plugins: HashMap<String, u32>,
+    /// RFC 3339 time of the latest run.
+    last_used: String,
+}
+
+/// Counts a successful run of `plugin_name` in `dir`, for the router's
+/// hint about what this directory usually needs.
+pub async fn record_dir_use(dir: &Path, plugin_name: &str) -> Result<()> {
+    record_dir_use_in_path(&dir_usage_path()?, dir, plugin_name).await
+}
+
+/// The plugins that ran in `dir`, most runs first.
+pub async fn load_dir_uses(dir: &Path) -> Result<Vec<(String, u32)>> {
+    load_dir_uses_from_path(&dir_usage_path()?, dir).await
+}
+
+async fn record_dir_use_in_path(path: &Path, dir: &Path, plugin_name: &str) -> Result<()> {
+    let _lock = lock_store(path).await?;
+    let mut usage = load_dir_usage_from_path(path).await.unwrap_or_default();
+    let entry = usage.entry(dir.display().to_string()).or_default();
+    *entry.plugins.entry(plugin_name.to_string()).or_default() += 1;
+    entry.last_used = Utc::now().to_rfc3339();
+    if usage.len() > DIR_USAGE_LIMIT {
+        let mut by_age: Vec<(String, String)> = usage
+            .iter()
+            .map(|(dir, entry)| (entry.last_used.clone(), dir.clone()))
+            .collect();
+        by_age.sort();
+        for (_, dir) in by_age.into_iter().take(usage.len() - DIR_USAGE_LIMIT) {
+            usage.remove(&dir);
+        }
+    }
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, serde_json::to_string_pretty(&usage)?).await?;
+    fs::rename(&tmp_path, path).await?;
+    Ok(())
+}
+
+async fn load_dir_uses_from_path(path: &Path, dir: &Path) -> Result<Vec<(String, u32)>> {
+    let mut usage = load_dir_usage_from_path(path).await?;
+    let mut uses: Vec<(String, u32)> = usage
+        .remove(&dir.display().to_string())
+        .map(|entry| entry.plugins.into_iter().collect())
+        .unwrap_or_default();
+    uses.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(uses)
+}
+
+async fn load_dir_usage_from_path(path: &Path) -> Result<HashMap<String, DirUsage>> {
+    match fs::read_to_string(path).await {
+        Ok(raw) => Ok(serde_json::from_str(&raw)?),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(HashMap::new()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// The pins as stored, for `sync`.
+pub(crate) async fn load_pins() -> Result<Vec<PinnedHistoryEntry>> {
+    load_pinned_entries_from_path(&pin_path()?).await
+}
+
+/// Adds `new` to the pins, for `bundle` imports.
+pub(crate) async fn add_pins(new: Vec<PinnedHistoryEntry>) -> Result<()> {
+    if new.is_empty() {
+        return Ok(());
+    }
+    let path = pin_path()?;
+    let _lock = lock_store(&path).await?;
+    let mut pins = load_pinned_entries_from_path(&path).await?;
+    pins.extend(new);
+    write_pinned_entries_atomic(&path, &pins).await
+}
+
+pub(crate) async fn save_pins(pins: &[PinnedHistoryEntry]) -> Result<()> {
+    let path = pin_path()?;
+    let _lock = lock_store(&path).await?;
+    write_pinned_entries_atomic(&path, pins).await
+}
+
 fn history_dir() -> Result<PathBuf> {
-    Ok(dirs::data_dir()
+    Ok(crate::paths::data_dir()
         .context("Could not find data directory")?
         .join("dexter"))
 }
@@ -169,18 +819,106 @@ fn pin_path() -> Result<PathBuf> {
     Ok(history_dir()?.join("history_pins.json"))
 }
 
+fn successes_path() -> Result<PathBuf> {
+    Ok(history_dir()?.join("plugin_successes.json"))
+}
+
+fn dir_usage_path() -> Result<PathBuf> {
+    Ok(history_dir()?.join("dir_usage.json"))
+}
+
+/// Takes the exclusive lock on `<path>.lock`, which every Dexter holds
+/// while it writes `path`, so concurrent instances never interleave a line
+/// or rewrite the file without each other's entries. The lock sits beside
+/// the file because rewrites replace the file itself. Released on drop.
+pub(crate) async fn lock_store(path: &Path) -> Result<std::fs::File> {
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent).await?;
+    }
+    let lock_path = path.with_extension("lock");
+    let file = tokio::task::spawn_blocking(move || {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)?;
+        file.lock()?;
+        Ok::<_, std::io::Error>(file)
+    })
+    .await??;
+    Ok(file)
+}
+
+async fn append_history_entry(path: &Path, entry: &HistoryEntry) -> Result<()> {
+    let _lock = lock_store(path).await?;
+    append_history_line(path, entry).await
+}
+
+async fn append_history_line(path: &Path, entry: &HistoryEntry) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    let line = serde_json::to_string(entry)?;
+    file.write_all(format!("{}\n", line).as_bytes()).await?;
+    Ok(())
+}
+
+/// Rewrites the newest line matching `entry` with `outcome` attached, or
+/// appends the entry when it is no longer in the file. The file is read
+/// under the lock, so lines other instances appended meanwhile are kept.
+async fn record_outcome_in_path(
+    path: &Path,
+    entry: &HistoryEntry,
+    outcome: RunOutcome,
+) -> Result<()> {
+    let _lock = lock_store(path).await?;
+    let updated = HistoryEntry {
+        outcome: Some(outcome),
+        ..entry.clone()
+    };
+    let content = match fs::read_to_string(path).await {
+        Ok(content) => content,
+        Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err.into()),
+    };
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let matching = lines.iter().rposition(|line| {
+        serde_json::from_str::<HistoryEntry>(line).is_ok_and(|e| {
+            e.timestamp == entry.timestamp && e.plugin == entry.plugin && e.command == entry.command
+        })
+    });
+    let Some(idx) = matching else {
+        return append_history_line(path, &updated).await;
+    };
+    lines[idx] = serde_json::to_string(&updated)?;
+    let tmp_path = path.with_extension("jsonl.tmp");
+    fs::write(&tmp_path, format!("{}\n", lines.join("\n"))).await?;
+    fs::rename(&tmp_path, path).await?;
+    Ok(())
+}
+
 fn is_same_history_entry(entry: &HistoryEntry, pin: &PinnedHistoryEntry) -> bool {
     entry.timestamp == pin.timestamp && entry.plugin == pin.plugin && entry.command == pin.command
 }
 
+#[cfg(test)]
 async fn load_history_entries_from_path(path: &Path) -> Result<(Vec<HistoryEntry>, usize)> {
+    load_recent_entries_from_path(path, usize::MAX).await
+}
+
+async fn load_recent_entries_from_path(
+    path: &Path,
+    limit: usize,
+) -> Result<(Vec<HistoryEntry>, usize)> {
     let content = match fs::read_to_string(path).await {
         Ok(content) => content,
         Err(err) if err.kind() == ErrorKind::NotFound => return Ok((Vec::new(), 0)),
         Err(err) => return Err(err.into()),
     };
 
-    let mut entries = Vec::new();
+    let mut entries = VecDeque::new();
     let mut skipped_lines = 0usize;
     for raw_line in content.lines() {
         let line = raw_line.trim();
@@ -188,12 +926,17 @@ async fn load_history_entries_from_path(path: &Path) -> Result<(Vec<HistoryEntry
             continue;
         }
         match serde_json::from_str::<HistoryEntry>(line) {
-            Ok(entry) => entries.push(entry),
+            Ok(entry) => {
+                entries.push_back(entry);
+                if entries.len() > limit {
+                    entries.pop_front();
+                }
+            }
             Err(_) => skipped_lines += 1,
         }
     }
 
-    Ok((entries, skipped_lines))
+    Ok((entries.into(), skipped_lines))
 }
 
 async fn load_pinned_entries_from_path(path: &Path) -> Result<Vec<PinnedHistoryEntry>> {
@@ -225,7 +968,8 @@ async fn write_pinned_entries_atomic(path: &Path, entries: &[PinnedHistoryEntry]
     Ok(())
 }
 
-async fn set_pin_in_path(path: &Path, entry: &HistoryEntry) -> Result<()> {
+async fn set_pin_in_path(path: &Path, entry: &HistoryEntry, intent: Option<&str>) -> Result<()> {
+    let _lock = lock_store(path).await?;
     let mut pins = match load_pinned_entries_from_path(path).await {
         Ok(entries) => entries,
         Err(err) => {
@@ -244,12 +988,14 @@ async fn set_pin_in_path(path: &Path, entry: &HistoryEntry) -> Result<()> {
         plugin: entry.plugin.clone(),
         command: entry.command.clone(),
         pinned_at: Utc::now().to_rfc3339(),
+        intent: intent.map(str::to_string),
     });
 
     write_pinned_entries_atomic(path, &pins).await
 }
 
 async fn unset_pin_in_path(path: &Path, entry: &HistoryEntry) -> Result<()> {
+    let _lock = lock_store(path).await?;
     let path_exists = path.exists();
     let mut pins = match load_pinned_entries_from_path(path).await {
         Ok(entries) => entries,
@@ -287,6 +1033,24 @@ mod tests {
         assert!(redacted.contains("token=[REDACTED]"));
     }
 
+    #[test]
+    fn repair_prompt_carries_command_and_error_tail() {
+        let long_error = format!("{}Invalid argument: codec xyz", "noise ".repeat(1000));
+        let prompt = repair_user_input("yt-dlp --cookies cookies.txt -f xyz url", &long_error);
+        assert!(prompt.contains("--cookies [REDACTED] -f xyz url"));
+        assert!(prompt.contains("(last lines)"));
+        assert!(prompt.contains("Invalid argument: codec xyz"));
+        assert!(prompt.len() < long_error.len());
+
+        let prompt = safety_user_input(
+            "yt-dlp --cookies cookies.txt url; rm -rf out",
+            "Command blocked: shell composition is not allowed\n",
+        );
+        assert!(prompt.contains("--cookies [REDACTED] url; rm -rf out"));
+        assert!(prompt.contains("Reason: Command blocked: shell composition is not allowed\n"));
+        assert!(prompt.contains("without breaking that rule"));
+    }
+
     #[tokio::test]
     async fn load_history_entries_skips_invalid_lines() {
         let tmp = tempdir().unwrap();
@@ -295,11 +1059,17 @@ mod tests {
             timestamp: "2026-02-08T10:00:00Z".to_string(),
             plugin: "f2".to_string(),
             command: "f2 -f old new".to_string(),
+            outcome: None,
+            intent: None,
+            environment: None,
         };
         let valid_b = HistoryEntry {
             timestamp: "2026-02-08T11:00:00Z".to_string(),
             plugin: "ffmpeg".to_string(),
             command: "ffmpeg -i a.mp4 b.mp3".to_string(),
+            outcome: None,
+            intent: None,
+            environment: None,
         };
         let payload = format!(
             "{}\n{{broken json}}\n{}\n",
@@ -309,8 +1079,113 @@ mod tests {
         fs::write(&history_path, payload).await.unwrap();
 
         let (entries, skipped) = load_history_entries_from_path(&history_path).await.unwrap();
-        assert_eq!(entries, vec![valid_a, valid_b]);
+        assert_eq!(entries, vec![valid_a, valid_b.clone()]);
         assert_eq!(skipped, 1);
+
+        let (entries, skipped) = load_recent_entries_from_path(&history_path, 1)
+            .await
+            .unwrap();
+        assert_eq!(entries, vec![valid_b]);
+        assert_eq!(skipped, 1);
+    }
+
+    #[tokio::test]
+    async fn record_outcome_rewrites_the_matching_entry() {
+        let tmp = tempdir().unwrap();
+        let path = tmp.path().join("history.jsonl");
+        let first = HistoryEntry::new("f2", "f2 -f a -r b -x");
+        let second = HistoryEntry::new("qpdf", "qpdf --decrypt in.pdf out.pdf");
+        append_history_entry(&path, &first).await.unwrap();
+        append_history_entry(&path, &second).await.unwrap();
+
+        let failed: Result<String> = Err(dexter_plugins::command_exec::exit_failure(
+            exit_status(2),
+            "qpdf error: bad password",
+        ));
+        let outcome = RunOutcome::from_result(Duration::from_millis(1500), &failed);
+        assert_eq!(outcome.exit_code, Some(2));
+        assert_eq!(
+            outcome.output_bytes,
+            "qpdf error: bad password".len() as u64
+        );
+        record_outcome_in_path(&path, &second, outcome)
+            .await
+            .unwrap();
+
+        let (entries, skipped) = load_history_entries_from_path(&path).await.unwrap();
+        assert_eq!(skipped, 0);
+        assert_eq!(entries[0], first);
+        assert_eq!(entries[1].outcome, Some(outcome));
+        assert!(!entries[1].outcome.unwrap().success);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_writers_keep_every_entry_and_pin() {
+        let tmp = tempdir().unwrap();
+        let path = tmp.path().join("history.jsonl");
+        let pins_path = tmp.path().join("history_pins.json");
+        let writers: Vec<_> = (0..16)
+            .map(|i| {
+                let (path, pins_path) = (path.clone(), pins_path.clone());
+                tokio::spawn(async move {
+                    let entry = HistoryEntry::new("f2", &format!("f2 -f a{} -r b -x", i));
+                    append_history_entry(&path, &entry).await.unwrap();
+                    let outcome = RunOutcome::from_result(Duration::ZERO, &Ok(String::new()));
+                    record_outcome_in_path(&path, &entry, outcome)
+                        .await
+                        .unwrap();
+                    set_pin_in_path(&pins_path, &entry, None).await.unwrap();
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.await.unwrap();
+        }
+
+        let (entries, skipped) = load_history_entries_from_path(&path).await.unwrap();
+        assert_eq!((entries.len(), skipped), (16, 0));
+        assert!(entries.iter().all(|entry| entry.outcome.is_some()));
+        let pins = load_pinned_entries_from_path(&pins_path).await.unwrap();
+        assert_eq!(pins.len(), 16);
+    }
+
+    #[tokio::test]
+    async fn record_outcome_keeps_the_run_environment() {
+        let tmp = tempdir().unwrap();
+        let path = tmp.path().join("history.jsonl");
+        let environment = RunEnvironment {
+            dexter_version: "0.1.0".to_string(),
+            os: "Ubuntu 24.04.1 LTS (x86_64)".to_string(),
+            tool_version: Some("ffmpeg version 6.1.1-3ubuntu5".to_string()),
+        };
+        let entry = HistoryEntry::new("ffmpeg", "ffmpeg -i a.mkv a.mp4")
+            .with_environment(Some(environment.clone()));
+        append_history_entry(&path, &entry).await.unwrap();
+        let outcome = RunOutcome::from_result(Duration::from_millis(40), &Ok(String::new()));
+        record_outcome_in_path(&path, &entry, outcome)
+            .await
+            .unwrap();
+
+        let (entries, _) = load_history_entries_from_path(&path).await.unwrap();
+        assert_eq!(entries[0].environment, Some(environment));
+        // Entries from before environments were recorded still load.
+        let old: HistoryEntry = serde_json::from_str(
+            r#"{"timestamp":"2026-02-08T10:00:00Z","plugin":"f2","command":"f2 -f a -r b"}"#,
+        )
+        .unwrap();
+        assert_eq!(old.environment, None);
+    }
+
+    #[cfg(unix)]
+    fn exit_status(code: i32) -> std::process::ExitStatus {
+        use std::os::unix::process::ExitStatusExt;
+        std::process::ExitStatus::from_raw(code << 8)
+    }
+
+    #[cfg(windows)]
+    fn exit_status(code: i32) -> std::process::ExitStatus {
+        use std::os::windows::process::ExitStatusExt;
+        std::process::ExitStatus::from_raw(code as u32)
     }
 
     #[tokio::test]
@@ -321,11 +1196,21 @@ mod tests {
             timestamp: "2026-02-08T10:00:00Z".to_string(),
             plugin: "f2".to_string(),
             command: "f2 -f old new".to_string(),
+            outcome: None,
+            intent: None,
+            environment: None,
         };
 
-        set_pin_in_path(&pins_path, &entry).await.unwrap();
+        set_pin_in_path(&pins_path, &entry, None).await.unwrap();
+        set_pin_in_path(&pins_path, &entry, Some("rename old to new"))
+            .await
+            .unwrap();
         let pins_after_set = load_pinned_entries_from_path(&pins_path).await.unwrap();
         assert_eq!(pins_after_set.len(), 1);
+        assert_eq!(
+            pins_after_set[0].intent.as_deref(),
+            Some("rename old to new")
+        );
         assert_eq!(pins_after_set[0].timestamp, entry.timestamp);
         assert_eq!(pins_after_set[0].plugin, entry.plugin);
         assert_eq!(pins_after_set[0].command, entry.command);
@@ -335,4 +1220,60 @@ mod tests {
         let pins_after_unset = load_pinned_entries_from_path(&pins_path).await.unwrap();
         assert!(pins_after_unset.is_empty());
     }
+
+    #[tokio::test]
+    async fn plugin_successes_keep_the_latest_run_per_plugin() {
+        let tmp = tempdir().unwrap();
+        let path = tmp.path().join("plugin_successes.json");
+        assert!(load_plugin_successes_from_path(&path)
+            .await
+            .unwrap()
+            .is_empty());
+
+        record_plugin_success_in_path(&path, "ffmpeg")
+            .await
+            .unwrap();
+        let first = load_plugin_successes_from_path(&path).await.unwrap()["ffmpeg"].clone();
+        record_plugin_success_in_path(&path, "qpdf").await.unwrap();
+        record_plugin_success_in_path(&path, "ffmpeg")
+            .await
+            .unwrap();
+
+        let successes = load_plugin_successes_from_path(&path).await.unwrap();
+        assert_eq!(successes.len(), 2);
+        assert!(successes["ffmpeg"] >= first);
+    }
+
+    #[tokio::test]
+    async fn dir_uses_count_runs_per_directory() {
+        let tmp = tempdir().unwrap();
+        let path = tmp.path().join("dir_usage.json");
+        let videos = Path::new("/home/ada/Videos");
+        assert!(load_dir_uses_from_path(&path, videos)
+            .await
+            .unwrap()
+            .is_empty());
+
+        for plugin in ["f2", "ffmpeg", "ffmpeg", "qpdf"] {
+            record_dir_use_in_path(&path, videos, plugin).await.unwrap();
+        }
+        record_dir_use_in_path(&path, Path::new("/srv/docs"), "pandoc")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            load_dir_uses_from_path(&path, videos).await.unwrap(),
+            [
+                ("ffmpeg".to_string(), 2),
+                ("f2".to_string(), 1),
+                ("qpdf".to_string(), 1)
+            ]
+        );
+        assert_eq!(
+            load_dir_uses_from_path(&path, Path::new("/srv/docs"))
+                .await
+                .unwrap(),
+            [("pandoc".to_string(), 1)]
+        );
+    }
 }
@@ -0,0 +1,341 @@
+//! User hook scripts for power users: rhai scripts in
+//! `<config dir>/dexter/hooks/` that can rewrite a request once it is routed
+//! and each command once it is generated, e.g. to add `-map_metadata 0` to
+//! every ffmpeg command.
+//!
+//! A hook is named after its point, `route.rhai` or `generate.rhai`; on Unix
+//! it and the hooks directory must belong to the user and be writable by
+//! nobody else. The script runs in an embedded engine with no access to
+//! files or programs. It sees the request as variables (`hook`, `intent`,
+//! `plugin`, `cwd` and, for `generate`, `command`) and returns the
+//! replacement; returning nothing keeps the value. A hook that fails, times
+//! out or returns a command the checks refuse leaves the value as it was,
+//! and the error is kept for the log.
+
+use anyhow::{anyhow, Context, Result};
+use rhai::{Dynamic, Engine, Scope};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long a hook may run before it is stopped.
+const HOOK_TIMEOUT: Duration = Duration::from_secs(5);
+/// The longest string a hook may build.
+const MAX_STRING_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookPoint {
+    /// After routing picked a plugin: may rewrite the intent the command is
+    /// generated from.
+    Route,
+    /// After a command was generated: may rewrite the command.
+    Generate,
+}
+
+impl HookPoint {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Route => "route",
+            Self::Generate => "generate",
+        }
+    }
+
+    fn file_name(self) -> String {
+        format!("{}.rhai", self.name())
+    }
+}
+
+/// `<config dir>/dexter/hooks/`.
+pub fn hooks_dir() -> Option<PathBuf> {
+    crate::paths::config_dir().map(|dir| dir.join("dexter").join("hooks"))
+}
+
+/// The hook scripts of one directory. Clones share the errors not yet taken.
+#[derive(Debug, Clone, Default)]
+pub struct Hooks {
+    dir: Option<PathBuf>,
+    errors: Arc<Mutex<Vec<String>>>,
+}
+
+impl Hooks {
+    /// Hooks from `dir`; `None` runs none.
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        Self {
+            dir,
+            errors: Arc::default(),
+        }
+    }
+
+    /// Hooks from the config dir.
+    pub fn from_config_dir() -> Self {
+        Self::new(hooks_dir())
+    }
+
+    /// The script for `point`, looked up on each call so a new one applies
+    /// without a restart. An error names a script that is there but unsafe
+    /// to run.
+    pub fn script(&self, point: HookPoint) -> Result<Option<PathBuf>> {
+        match self.dir.as_deref() {
+            Some(dir) => find_script(dir, &point.file_name()),
+            None => Ok(None),
+        }
+    }
+
+    /// Hook errors since the last call, oldest first.
+    pub fn take_errors(&self) -> Vec<String> {
+        std::mem::take(&mut *self.errors.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// The intent the route hook makes of `intent` for `plugin`.
+    pub async fn rewrite_intent(&self, intent: &str, plugin: &str) -> String {
+        let vars = vec![
+            ("intent", intent.to_string()),
+            ("plugin", plugin.to_string()),
+        ];
+        self.apply(HookPoint::Route, vars, intent, |_| Ok(())).await
+    }
+
+    /// The command the generate hook makes of `command`. `check` is what
+    /// the generated command passed; a rewrite that fails it is dropped.
+    pub async fn rewrite_command(
+        &self,
+        intent: &str,
+        plugin: &str,
+        command: &str,
+        check: impl Fn(&str) -> Result<()>,
+    ) -> String {
+        let vars = vec![
+            ("intent", intent.to_string()),
+            ("plugin", plugin.to_string()),
+            ("command", command.to_string()),
+        ];
+        self.apply(HookPoint::Generate, vars, command, check).await
+    }
+
+    async fn apply(
+        &self,
+        point: HookPoint,
+        vars: Vec<(&'static str, String)>,
+        value: &str,
+        check: impl Fn(&str) -> Result<()>,
+    ) -> String {
+        let script = match self.script(point) {
+            Ok(Some(script)) => script,
+            Ok(None) => return value.to_string(),
+            Err(e) => {
+                self.record_error(format!("{} hook: {:#}", point.name(), e));
+                return value.to_string();
+            }
+        };
+        let result = run_script(&script, point, vars)
+            .await
+            .and_then(|output| match output {
+                Some(rewritten) => check(&rewritten)
+                    .map(|()| rewritten)
+                    .context("the rewritten value was refused"),
+                None => Ok(value.to_string()),
+            });
+        match result {
+            Ok(rewritten) => {
+                if rewritten != value {
+                    tracing::info!(
+                        target: "dexter::hooks",
+                        hook = point.name(),
+                        from = value,
+                        to = %rewritten,
+                        "hook rewrote the value"
+                    );
+                }
+                rewritten
+            }
+            Err(e) => {
+                self.record_error(format!(
+                    "{} hook ({}): {:#}",
+                    point.name(),
+                    script.display(),
+                    e
+                ));
+                value.to_string()
+            }
+        }
+    }
+
+    fn record_error(&self, message: String) {
+        tracing::warn!(target: "dexter::hooks", "{}", message);
+        self.errors
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(message);
+    }
+}
+
+/// `dir/<name>`, or `None` when there is no such file. A file that others
+/// could have changed is refused.
+fn find_script(dir: &Path, name: &str) -> Result<Option<PathBuf>> {
+    let path = dir.join(name);
+    let Ok(metadata) = std::fs::metadata(&path) else {
+        return Ok(None);
+    };
+    if !metadata.is_file() {
+        return Err(anyhow!("{} is not a file", path.display()));
+    }
+    check_owner_only(dir, &std::fs::metadata(dir)?)?;
+    check_owner_only(&path, &metadata)?;
+    Ok(Some(path))
+}
+
+/// Refuses `path` unless the user owns it and no one else may write to it.
+#[cfg(unix)]
+fn check_owner_only(path: &Path, metadata: &std::fs::Metadata) -> Result<()> {
+    use std::os::unix::fs::MetadataExt;
+    // SAFETY: geteuid has no preconditions and can't fail.
+    let uid = unsafe { libc::geteuid() };
+    if metadata.uid() != uid {
+        return Err(anyhow!("{} belongs to another user", path.display()));
+    }
+    if metadata.mode() & 0o022 != 0 {
+        return Err(anyhow!(
+            "{} is writable by other users; run chmod go-w on it",
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_owner_only(_path: &Path, _metadata: &std::fs::Metadata) -> Result<()> {
+    Ok(())
+}
+
+fn current_dir() -> String {
+    std::env::current_dir()
+        .map(|dir| dir.display().to_string())
+        .unwrap_or_default()
+}
+
+/// Runs `script` with `vars` in scope: the string it returns, trimmed, or
+/// `None` when it returned nothing or an empty string.
+async fn run_script(
+    script: &Path,
+    point: HookPoint,
+    vars: Vec<(&'static str, String)>,
+) -> Result<Option<String>> {
+    let source = std::fs::read_to_string(script).context("could not read it")?;
+    let cwd = current_dir();
+    tokio::task::spawn_blocking(move || {
+        let mut scope = Scope::new();
+        scope.push_constant("hook", point.name().to_string());
+        scope.push_constant("cwd", cwd);
+        for (name, value) in vars {
+            scope.push(name, value);
+        }
+        let returned = hook_engine()
+            .eval_with_scope::<Dynamic>(&mut scope, &source)
+            .map_err(|e| anyhow!("{}", e))?;
+        if returned.is_unit() {
+            return Ok(None);
+        }
+        let type_name = returned.type_name();
+        let text = returned
+            .into_string()
+            .map_err(|_| anyhow!("returned {}, not a string", type_name))?;
+        let text = text.trim().to_string();
+        Ok((!text.is_empty()).then_some(text))
+    })
+    .await?
+}
+
+/// An engine that stops a script after `HOOK_TIMEOUT` and sends what it
+/// prints to the log.
+fn hook_engine() -> Engine {
+    let mut engine = Engine::new();
+    let started = Instant::now();
+    engine.on_progress(move |_| (started.elapsed() > HOOK_TIMEOUT).then_some(Dynamic::UNIT));
+    engine.set_max_string_size(MAX_STRING_SIZE);
+    engine.on_print(|text| tracing::info!(target: "dexter::hooks", "{}", text));
+    engine.on_debug(|text, _, _| tracing::debug!(target: "dexter::hooks", "{}", text));
+    engine
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    /// The README's example hook.
+    const MAP_METADATA: &str = r#"
+if plugin == "ffmpeg" {
+    // before the output file: ffmpeg ignores options after it
+    let output = command.split(" ").pop();
+    command.sub_string(0, command.len() - output.len()) + "-map_metadata 0 " + output
+}
+"#;
+
+    fn write_hook(dir: &Path, name: &str, body: &str) {
+        std::fs::write(dir.join(name), body).unwrap();
+    }
+
+    #[tokio::test]
+    async fn rewrites_values_and_keeps_them_when_a_hook_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::set_permissions(dir.path(), std::fs::Permissions::from_mode(0o700)).unwrap();
+        let hooks = Hooks::new(Some(dir.path().to_path_buf()));
+        assert_eq!(
+            hooks.rewrite_intent("shrink a.mp4", "ffmpeg").await,
+            "shrink a.mp4"
+        );
+
+        write_hook(dir.path(), "generate.rhai", MAP_METADATA);
+        let command = hooks
+            .rewrite_command("shrink", "ffmpeg", "ffmpeg -i a.mp4 b.mp4", |_| Ok(()))
+            .await;
+        assert_eq!(command, "ffmpeg -i a.mp4 -map_metadata 0 b.mp4");
+        let other = hooks
+            .rewrite_command("shrink", "magick", "magick a.png b.jpg", |_| Ok(()))
+            .await;
+        assert_eq!(other, "magick a.png b.jpg");
+        let refused = hooks
+            .rewrite_command("shrink", "ffmpeg", "ffmpeg -i a.mp4 b.mp4", |_| {
+                Err(anyhow!("blocked"))
+            })
+            .await;
+        assert_eq!(refused, "ffmpeg -i a.mp4 b.mp4");
+
+        write_hook(dir.path(), "route.rhai", r#"throw "broken""#);
+        assert_eq!(
+            hooks.rewrite_intent("shrink a.mp4", "ffmpeg").await,
+            "shrink a.mp4"
+        );
+        write_hook(dir.path(), "route.rhai", "42");
+        assert_eq!(
+            hooks.rewrite_intent("shrink a.mp4", "ffmpeg").await,
+            "shrink a.mp4"
+        );
+        let errors = hooks.take_errors();
+        assert_eq!(errors.len(), 3);
+        assert!(errors[1].contains("broken"));
+        assert!(errors[2].contains("not a string"));
+        assert!(hooks.take_errors().is_empty());
+    }
+
+    #[test]
+    fn only_private_rhai_files_are_hooks() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::set_permissions(dir.path(), std::fs::Permissions::from_mode(0o700)).unwrap();
+        write_hook(dir.path(), "generate", "()");
+        assert!(find_script(dir.path(), "generate.rhai").unwrap().is_none());
+
+        write_hook(dir.path(), "generate.rhai", "()");
+        assert!(find_script(dir.path(), "generate.rhai").unwrap().is_some());
+
+        let script = dir.path().join("generate.rhai");
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o666)).unwrap();
+        let err = find_script(dir.path(), "generate.rhai").unwrap_err();
+        assert!(err.to_string().contains("writable by other users"));
+
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o644)).unwrap();
+        std::fs::set_permissions(dir.path(), std::fs::Permissions::from_mode(0o777)).unwrap();
+        let err = find_script(dir.path(), "generate.rhai").unwrap_err();
+        assert!(err.to_string().contains("writable by other users"));
+    }
+}
@@ -0,0 +1,258 @@
+//! Pre-flight check for files another process has open, so a batch isn't
+//! left half done when a video that is playing can't be renamed or a PDF
+//! open in a viewer can't be replaced.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::collision::CollisionChecker;
+
+/// Longest `lsof` may take before the check is given up.
+#[cfg(all(unix, not(target_os = "linux")))]
+const LSOF_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// A file the command touches that is open elsewhere.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileInUse {
+    /// As the command names it, relative to the cwd when it can be.
+    pub path: PathBuf,
+    /// `vlc (pid 4242)`; empty where the platform doesn't say who.
+    pub processes: Vec<String>,
+}
+
+/// The files `command` would read, rename or overwrite in `cwd` that
+/// another process has open. Empty when nothing is, and when the platform
+/// gives no way to tell.
+pub async fn files_in_use(plugin: &str, command: &str, cwd: &Path) -> Vec<FileInUse> {
+    let files = touched_files(plugin, command, cwd).await;
+    if files.is_empty() {
+        return Vec::new();
+    }
+    let absolute: Vec<PathBuf> = files
+        .iter()
+        .filter_map(|file| cwd.join(file).canonicalize().ok())
+        .collect();
+    let open = match open_elsewhere(absolute).await {
+        Ok(open) => open,
+        Err(e) => {
+            tracing::debug!(error = %e, "open file check unavailable");
+            return Vec::new();
+        }
+    };
+    files
+        .into_iter()
+        .filter_map(|path| {
+            let absolute = cwd.join(&path).canonicalize().ok()?;
+            let processes = open.get(&absolute)?.clone();
+            Some(FileInUse { path, processes })
+        })
+        .collect()
+}
+
+pub fn format_in_use(files: &[FileInUse]) -> String {
+    let mut out = vec![format!(
+        "{} file(s) are open in another process:",
+        files.len()
+    )];
+    for file in files {
+        if file.processes.is_empty() {
+            out.push(format!("- {}", file.path.display()));
+        } else {
+            out.push(format!(
+                "- {} ({})",
+                file.path.display(),
+                file.processes.join(", ")
+            ));
+        }
+    }
+    out.join("\n")
+}
+
+/// Existing files the command names: the f2 plan's sources and the
+/// targets it would replace, otherwise every argument that is a file.
+async fn touched_files(plugin: &str, command: &str, cwd: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if plugin == "f2" {
+        if let Ok(renames) = CollisionChecker::plan_f2(command, cwd).await {
+            for rename in renames.into_iter().filter(|r| r.source != r.target) {
+                files.push(rename.source);
+                files.push(rename.target);
+            }
+        }
+    } else if let Ok(argv) = shell_words::split(command) {
+        files.extend(argv.into_iter().skip(1).map(PathBuf::from));
+    }
+    let mut seen = HashSet::new();
+    files.retain(|file| cwd.join(file).is_file() && seen.insert(file.clone()));
+    files
+}
+
+/// Which of `files` (canonical paths) other processes hold open, and by
+/// whom: `/proc` on Linux.
+#[cfg(target_os = "linux")]
+async fn open_elsewhere(files: Vec<PathBuf>) -> anyhow::Result<BTreeMap<PathBuf, Vec<String>>> {
+    Ok(tokio::task::spawn_blocking(move || scan_proc(&files.into_iter().collect())).await?)
+}
+
+#[cfg(target_os = "linux")]
+fn scan_proc(files: &HashSet<PathBuf>) -> BTreeMap<PathBuf, Vec<String>> {
+    let me = std::process::id().to_string();
+    let mut open: BTreeMap<PathBuf, Vec<String>> = BTreeMap::new();
+    let Ok(procs) = std::fs::read_dir("/proc") else {
+        return open;
+    };
+    for entry in procs.flatten() {
+        let pid = entry.file_name().to_string_lossy().into_owned();
+        if pid == me || !pid.bytes().all(|b| b.is_ascii_digit()) {
+            continue;
+        }
+        // Other users' processes can't be read, and are left out.
+        let Ok(fds) = std::fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        for fd in fds.flatten() {
+            let Ok(target) = std::fs::read_link(fd.path()) else {
+                continue;
+            };
+            if !files.contains(&target) {
+                continue;
+            }
+            let name = std::fs::read_to_string(entry.path().join("comm")).unwrap_or_default();
+            let process = format!("{} (pid {})", name.trim(), pid);
+            let holders = open.entry(target).or_default();
+            if !holders.contains(&process) {
+                holders.push(process);
+            }
+        }
+    }
+    open
+}
+
+/// `lsof` elsewhere on Unix; without it nothing is reported.
+#[cfg(all(unix, not(target_os = "linux")))]
+async fn open_elsewhere(files: Vec<PathBuf>) -> anyhow::Result<BTreeMap<PathBuf, Vec<String>>> {
+    if dexter_plugins::health::find_in_path("lsof").is_none() || crate::replay::is_replaying() {
+        return Ok(BTreeMap::new());
+    }
+    let output = tokio::process::Command::new("lsof")
+        .args(["-F", "pcn", "--"])
+        .args(&files)
+        .stdin(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    // lsof exits 1 when none of the files is open.
+    let output = tokio::time::timeout(LSOF_TIMEOUT, output).await??;
+    Ok(parse_lsof(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// `lsof -F pcn` output: a `p<pid>` and `c<command>` line per process,
+/// then an `n<path>` line per file it has open.
+#[cfg_attr(target_os = "linux", allow(dead_code))]
+fn parse_lsof(output: &str) -> BTreeMap<PathBuf, Vec<String>> {
+    let me = std::process::id().to_string();
+    let mut open: BTreeMap<PathBuf, Vec<String>> = BTreeMap::new();
+    let (mut pid, mut command) = ("", "");
+    for line in output.lines() {
+        let (tag, value) = line.split_at(line.len().min(1));
+        match tag {
+            "p" => pid = value,
+            "c" => command = value,
+            "n" if pid != me => {
+                let process = format!("{} (pid {})", command, pid);
+                let holders = open.entry(PathBuf::from(value)).or_default();
+                if !holders.contains(&process) {
+                    holders.push(process);
+                }
+            }
+            _ => {}
+        }
+    }
+    open
+}
+
+/// Windows: a file another process has open without sharing can't be
+/// opened exclusively; who holds it isn't known.
+#[cfg(windows)]
+async fn open_elsewhere(files: Vec<PathBuf>) -> anyhow::Result<BTreeMap<PathBuf, Vec<String>>> {
+    use std::os::windows::fs::OpenOptionsExt;
+
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+    Ok(files
+        .into_iter()
+        .filter(|file| {
+            std::fs::OpenOptions::new()
+                .read(true)
+                .share_mode(0)
+                .open(file)
+                .is_err_and(|e| e.raw_os_error() == Some(ERROR_SHARING_VIOLATION))
+        })
+        .map(|file| (file, Vec::new()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_lsof_output_and_formats_the_report() {
+        let open = parse_lsof(
+            "p4242\ncvlc\nn/home/me/talk.mkv\np77\ncmpv\nn/home/me/talk.mkv\nn/home/me/b.mkv\n",
+        );
+        assert_eq!(
+            open.get(Path::new("/home/me/talk.mkv")).unwrap(),
+            &["vlc (pid 4242)", "mpv (pid 77)"]
+        );
+        assert_eq!(
+            open.get(Path::new("/home/me/b.mkv")).unwrap(),
+            &["mpv (pid 77)"]
+        );
+
+        let report = format_in_use(&[
+            FileInUse {
+                path: PathBuf::from("talk.mkv"),
+                processes: vec!["vlc (pid 4242)".to_string()],
+            },
+            FileInUse {
+                path: PathBuf::from("notes.pdf"),
+                processes: Vec::new(),
+            },
+        ]);
+        assert_eq!(
+            report,
+            "2 file(s) are open in another process:\n- talk.mkv (vlc (pid 4242))\n- notes.pdf"
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn finds_a_file_another_process_holds_open() {
+        use tokio::io::AsyncBufReadExt;
+
+        let dir = std::env::temp_dir().join(format!("dexter-in-use-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("talk.mkv"), b"x").unwrap();
+        std::fs::write(dir.join("idle.mkv"), b"x").unwrap();
+        let mut holder = tokio::process::Command::new("sh")
+            .args(["-c", "exec 3<talk.mkv; echo open; sleep 5"])
+            .current_dir(&dir)
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        // The shell says when it holds the file.
+        let mut ready = String::new();
+        tokio::io::BufReader::new(holder.stdout.take().unwrap())
+            .read_line(&mut ready)
+            .await
+            .unwrap();
+        assert_eq!(ready, "open\n");
+
+        let found = files_in_use("ffmpeg", "ffmpeg -i talk.mkv -i idle.mkv out.mp4", &dir).await;
+        holder.kill().await.unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].path, PathBuf::from("talk.mkv"));
+        assert!(found[0].processes[0].starts_with("sh (pid "));
+    }
+}
@@ -0,0 +1,161 @@
+//! The process a confirmed command actually starts: the plugin's own
+//! argv changes, the sandbox wrapper or the SSH hop, the directory it runs
+//! in and what it gets of the environment. Shown before running, so no
+//! change to the command goes unseen.
+
+use anyhow::Result;
+use dexter_plugins::{ExecMode, Plugin};
+use std::path::Path;
+
+use crate::config::RemoteHost;
+use crate::remote::remote_command_argv;
+use crate::sandbox::SandboxPolicy;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invocation {
+    /// Program and arguments exactly as spawned.
+    pub argv: Vec<String>,
+    /// Working directory, on the remote host for remote runs.
+    pub cwd: String,
+    /// Environment changes, one `NAME=value` or description per line;
+    /// empty when the process inherits Dexter's environment unchanged.
+    pub env: Vec<String>,
+    /// How `argv` differs from the command as shown.
+    pub changes: Vec<String>,
+    /// What runs next when `argv` fails in a way the plugin recovers from.
+    pub retry: Option<Vec<String>>,
+}
+
+impl Invocation {
+    /// How `plugin` runs `cmd` in `cwd`, or on `remote` when set.
+    pub fn plan(
+        plugin: &dyn Plugin,
+        cmd: &str,
+        cwd: &Path,
+        sandbox: &SandboxPolicy,
+        remote: Option<&RemoteHost>,
+    ) -> Result<Self> {
+        let shown = shell_words::split(cmd).unwrap_or_default();
+        if let Some(host) = remote {
+            let argv = remote_command_argv(host, cmd)?;
+            return Ok(Self {
+                changes: vec![format!(
+                    "runs over ssh on {}; plugin flags are not added",
+                    host.name
+                )],
+                argv,
+                cwd: format!("{}:{}", host.name, host.workdir()),
+                env: vec!["the remote login environment".to_string()],
+                retry: None,
+            });
+        }
+
+        let argv = plugin.command_argv(cmd, ExecMode::Apply)?;
+        let mut changes = argv_changes(&shown, &argv);
+        let mut retry = plugin.retry_argv(&argv);
+        if let Some(retry) = &retry {
+            changes.push(format!(
+                "if that fails, runs again with {}",
+                argv_changes(&argv, retry).join(", ")
+            ));
+        }
+        let mut env = Vec::new();
+        let argv = match sandbox.sandbox_for(plugin, cwd)? {
+            Some(sandbox) => {
+                changes.push(format!(
+                    "wrapped in {} ({})",
+                    sandbox.backend.binary(),
+                    if sandbox.network {
+                        "network allowed"
+                    } else {
+                        "no network"
+                    }
+                ));
+                env.push("private /tmp inside the sandbox".to_string());
+                retry = retry.map(|retry| sandbox.wrap(&retry));
+                sandbox.wrap(&argv)
+            }
+            None => argv,
+        };
+        Ok(Self {
+            argv,
+            cwd: cwd.display().to_string(),
+            env,
+            changes,
+            retry,
+        })
+    }
+
+    /// `argv` quoted for a shell, one line.
+    pub fn command_line(&self) -> String {
+        shell_words::join(&self.argv)
+    }
+
+    /// `retry` quoted for a shell, one line.
+    pub fn retry_line(&self) -> Option<String> {
+        self.retry.as_ref().map(shell_words::join)
+    }
+}
+
+/// Arguments `to` adds to or drops from `from`, as `+arg` and `-arg`.
+pub fn argv_changes(from: &[String], to: &[String]) -> Vec<String> {
+    let mut remaining = from.to_vec();
+    let mut added = Vec::new();
+    for arg in to {
+        match remaining.iter().position(|a| a == arg) {
+            Some(idx) => {
+                remaining.remove(idx);
+            }
+            None => added.push(format!("added {}", arg)),
+        }
+    }
+    added.extend(remaining.iter().map(|arg| format!("dropped {}", arg)));
+    added
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dexter_plugins::{F2Plugin, FFmpegPlugin, OcrmypdfPlugin};
+
+    #[test]
+    fn shows_the_flags_plugins_add() {
+        let cwd = Path::new("/srv/photos");
+        let policy = SandboxPolicy::default();
+        let plan =
+            Invocation::plan(&F2Plugin::default(), "f2 -f a -r b", cwd, &policy, None).unwrap();
+        assert_eq!(plan.argv, ["f2", "-f", "a", "-r", "b", "-x", "--no-color"]);
+        assert_eq!(plan.changes, ["added -x", "added --no-color"]);
+        assert_eq!(plan.cwd, "/srv/photos");
+        assert!(plan.env.is_empty());
+
+        let plan = Invocation::plan(
+            &FFmpegPlugin::default(),
+            "ffmpeg -i 'a b.mov' out.mp4",
+            cwd,
+            &policy,
+            None,
+        )
+        .unwrap();
+        assert!(plan.changes.is_empty());
+        assert_eq!(plan.retry, None);
+        assert_eq!(plan.command_line(), "ffmpeg -i 'a b.mov' out.mp4");
+
+        let plan = Invocation::plan(
+            &OcrmypdfPlugin::default(),
+            "ocrmypdf in.pdf out.pdf",
+            cwd,
+            &policy,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            plan.changes,
+            ["if that fails, runs again with added --skip-text"]
+        );
+        assert_eq!(
+            plan.retry.unwrap(),
+            ["ocrmypdf", "--skip-text", "in.pdf", "out.pdf"]
+        );
+    }
+}
@@ -0,0 +1,500 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+use crate::collision::CollisionChecker;
+use crate::conflict::output_paths;
+use crate::trash::restore_file;
+
+/// Files written close to the start of a run may carry an mtime slightly
+/// before it on coarse-grained filesystems (FAT, some network mounts).
+const MTIME_SLACK_SECS: i64 = 2;
+
+/// Journals this process has begun, so runs started in the same
+/// millisecond (parallel batch jobs) get files of their own.
+static BEGUN: AtomicU64 = AtomicU64::new(0);
+
+/// One file the command is expected to touch, relative to the run's cwd.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FileOp {
+    /// `existed` outputs are overwritten, so rolling back cannot restore them.
+    Create {
+        path: PathBuf,
+        existed: bool,
+    },
+    Rename {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    /// `path` was kept in the Dexter trash before the run could destroy it.
+    Stage {
+        path: PathBuf,
+        staged: PathBuf,
+    },
+}
+
+impl FileOp {
+    fn is_done(&self, cwd: &Path, started_at: DateTime<Utc>) -> bool {
+        match self {
+            FileOp::Create { path, .. } => fs::metadata(cwd.join(path))
+                .and_then(|meta| meta.modified())
+                .map(|modified| written_since(modified, started_at))
+                .unwrap_or(false),
+            FileOp::Rename { from, to } => cwd.join(to).exists() && !cwd.join(from).exists(),
+            FileOp::Stage { staged, .. } => cwd.join(staged).exists(),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            FileOp::Create { path, existed } => format!(
+                "{} {}",
+                if *existed { "overwrite" } else { "create" },
+                path.display()
+            ),
+            FileOp::Rename { from, to } => {
+                format!("rename {} -> {}", from.display(), to.display())
+            }
+            FileOp::Stage { path, staged } => {
+                format!("stage {} -> {}", path.display(), staged.display())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct JournalHeader {
+    pub id: String,
+    pub intent: String,
+    pub plugin: String,
+    pub command: String,
+    pub cwd: PathBuf,
+    pub started_at: DateTime<Utc>,
+    pub ops: Vec<FileOp>,
+    /// The Dexter process running the command. Missing in journals written
+    /// before it was recorded.
+    #[serde(default)]
+    pub pid: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum JournalRecord {
+    Begin(JournalHeader),
+    /// `ops[op]` was seen completed on disk.
+    Done {
+        op: usize,
+        at: DateTime<Utc>,
+    },
+}
+
+/// The files a local command is expected to create or rename, as far as
+/// Dexter can tell before running it: parsed outputs for the tools
+/// `find_output_conflicts` understands, and the `f2 --json` plan for f2.
+pub async fn plan_file_ops(plugin: &str, command: &str, cwd: &Path) -> Vec<FileOp> {
+    if plugin == "f2" {
+        return match CollisionChecker::plan_f2(command, cwd).await {
+            Ok(renames) => renames
+                .into_iter()
+                .filter(|r| r.source != r.target)
+                .map(|r| FileOp::Rename {
+                    from: r.source,
+                    to: r.target,
+                })
+                .collect(),
+            Err(e) => {
+                tracing::debug!(error = %e, "no f2 plan for the journal");
+                Vec::new()
+            }
+        };
+    }
+    output_paths(plugin, command)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|path| FileOp::Create {
+            existed: cwd.join(&path).exists(),
+            path: PathBuf::from(path),
+        })
+        .collect()
+}
+
+/// Append-only record of one execution, flushed to disk after every entry
+/// so a crash leaves an accurate trail. Removed once the run ends; a journal
+/// still on disk at launch belongs to a run that never finished.
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+    header: JournalHeader,
+    done: Vec<bool>,
+}
+
+impl Journal {
+    pub fn dir() -> Result<PathBuf> {
+        Ok(crate::paths::data_dir()
+            .context("Could not find data directory")?
+            .join("dexter")
+            .join("journal"))
+    }
+
+    pub fn begin(
+        intent: &str,
+        plugin: &str,
+        command: &str,
+        cwd: &Path,
+        ops: Vec<FileOp>,
+    ) -> Result<Self> {
+        Self::begin_in(&Self::dir()?, intent, plugin, command, cwd, ops)
+    }
+
+    pub fn begin_in(
+        dir: &Path,
+        intent: &str,
+        plugin: &str,
+        command: &str,
+        cwd: &Path,
+        ops: Vec<FileOp>,
+    ) -> Result<Self> {
+        fs::create_dir_all(dir)?;
+        let started_at = Utc::now();
+        let id = format!(
+            "{}-{}-{}",
+            started_at.timestamp_millis(),
+            std::process::id(),
+            BEGUN.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = dir.join(format!("{}.jsonl", id));
+        let file = OpenOptions::new()
+            .create_new(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to create journal {}", path.display()))?;
+        let header = JournalHeader {
+            id,
+            intent: intent.to_string(),
+            plugin: plugin.to_string(),
+            command: command.to_string(),
+            cwd: cwd.to_path_buf(),
+            started_at,
+            ops,
+            pid: Some(std::process::id()),
+        };
+        let mut journal = Self {
+            path,
+            file,
+            done: vec![false; header.ops.len()],
+            header,
+        };
+        journal.append(&JournalRecord::Begin(journal.header.clone()))?;
+        Ok(journal)
+    }
+
+    /// Records every planned operation that has completed since the last
+    /// check; returns how many are done in total.
+    pub fn check_progress(&mut self) -> Result<usize> {
+        for idx in 0..self.header.ops.len() {
+            if self.done[idx]
+                || !self.header.ops[idx].is_done(&self.header.cwd, self.header.started_at)
+            {
+                continue;
+            }
+            self.done[idx] = true;
+            self.append(&JournalRecord::Done {
+                op: idx,
+                at: Utc::now(),
+            })?;
+        }
+        Ok(self.done.iter().filter(|done| **done).count())
+    }
+
+    pub fn op_count(&self) -> usize {
+        self.header.ops.len()
+    }
+
+    /// The run ended (either way) while Dexter was watching; nothing is left
+    /// to recover.
+    pub fn finish(self) -> Result<()> {
+        drop(self.file);
+        fs::remove_file(&self.path)
+            .with_context(|| format!("Failed to remove journal {}", self.path.display()))
+    }
+
+    fn append(&mut self, record: &JournalRecord) -> Result<()> {
+        let line = serde_json::to_string(record)?;
+        self.file.write_all(format!("{}\n", line).as_bytes())?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+}
+
+/// A run whose journal outlived it.
+#[derive(Debug, Clone)]
+pub struct InterruptedRun {
+    pub path: PathBuf,
+    pub header: JournalHeader,
+    /// Per operation: recorded as done, or found done on disk now.
+    pub done: Vec<bool>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RollbackReport {
+    pub undone: usize,
+    /// Operations left as they are, with the reason.
+    pub kept: Vec<String>,
+}
+
+/// Journals of runs that never finished, oldest first. Runs another Dexter
+/// is still executing are left out. Unreadable journals are skipped and
+/// left in place.
+pub fn interrupted_runs() -> Result<Vec<InterruptedRun>> {
+    interrupted_runs_in(&Journal::dir()?)
+}
+
+pub fn interrupted_runs_in(dir: &Path) -> Result<Vec<InterruptedRun>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut runs = Vec::new();
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("jsonl") {
+            continue;
+        }
+        match read_journal(&path) {
+            Ok(Some(run)) => runs.push(run),
+            Ok(None) => {}
+            Err(e) => tracing::warn!(path = %path.display(), error = %e, "unreadable journal"),
+        }
+    }
+    runs.sort_by_key(|run| run.header.started_at);
+    Ok(runs)
+}
+
+fn read_journal(path: &Path) -> Result<Option<InterruptedRun>> {
+    let raw = fs::read_to_string(path)?;
+    let mut header: Option<JournalHeader> = None;
+    let mut recorded = Vec::new();
+    // A torn last line is what a crash mid-write looks like; skip it.
+    for line in raw.lines().filter(|line| !line.trim().is_empty()) {
+        match serde_json::from_str::<JournalRecord>(line) {
+            Ok(JournalRecord::Begin(begin)) => header = Some(begin),
+            Ok(JournalRecord::Done { op, .. }) => recorded.push(op),
+            Err(_) => continue,
+        }
+    }
+    let Some(header) = header else {
+        return Ok(None);
+    };
+    if header
+        .pid
+        .is_some_and(|pid| pid != std::process::id() && process_alive(pid))
+    {
+        return Ok(None);
+    }
+    let done = header
+        .ops
+        .iter()
+        .enumerate()
+        .map(|(idx, op)| recorded.contains(&idx) || op.is_done(&header.cwd, header.started_at))
+        .collect();
+    Ok(Some(InterruptedRun {
+        path: path.to_path_buf(),
+        header,
+        done,
+    }))
+}
+
+impl InterruptedRun {
+    pub fn done_count(&self) -> usize {
+        self.done.iter().filter(|done| **done).count()
+    }
+
+    /// What was being done and how far it got, for the recovery prompt.
+    pub fn summary(&self) -> String {
+        let header = &self.header;
+        let started = header
+            .started_at
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M");
+        let mut lines = vec![
+            format!("An interrupted run was found (started {}).", started),
+            format!("Intent: {}", header.intent),
+            format!("Command [{}]: {}", header.plugin, header.command),
+            format!("Directory: {}", header.cwd.display()),
+        ];
+        if header.ops.is_empty() {
+            lines.push("Which files it touched is unknown.".to_string());
+        } else {
+            lines.push(format!(
+                "{} of {} file operation(s) completed:",
+                self.done_count(),
+                header.ops.len()
+            ));
+            for (op, done) in header.ops.iter().zip(&self.done) {
+                lines.push(format!(
+                    "  [{}] {}",
+                    if *done { "done" } else { "    " },
+                    op.label()
+                ));
+            }
+        }
+        lines.join("\n")
+    }
+
+    /// Undoes the completed operations, newest first: removes outputs that
+    /// did not exist before the run, renames files back and returns staged
+    /// originals to their names. Overwritten outputs and renames or staged
+    /// files whose name is taken again are reported and left alone. The journal is removed afterwards.
+    pub fn roll_back(&self) -> Result<RollbackReport> {
+        let cwd = &self.header.cwd;
+        let mut report = RollbackReport::default();
+        for (op, done) in self.header.ops.iter().zip(&self.done).rev() {
+            if !done {
+                continue;
+            }
+            match op {
+                FileOp::Create {
+                    path,
+                    existed: false,
+                } => match fs::remove_file(cwd.join(path)) {
+                    Ok(()) => report.undone += 1,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => report.kept.push(format!("{}: {}", path.display(), e)),
+                },
+                FileOp::Create {
+                    path,
+                    existed: true,
+                } => report.kept.push(format!(
+                    "{}: overwritten, the previous version is gone",
+                    path.display()
+                )),
+                FileOp::Rename { from, to } => {
+                    if cwd.join(from).exists() {
+                        report
+                            .kept
+                            .push(format!("{}: name is taken again", from.display()));
+                        continue;
+                    }
+                    match fs::rename(cwd.join(to), cwd.join(from)) {
+                        Ok(()) => report.undone += 1,
+                        Err(e) => report.kept.push(format!("{}: {}", to.display(), e)),
+                    }
+                }
+                FileOp::Stage { path, staged } => match restore_file(cwd, path, staged) {
+                    Ok(true) => report.undone += 1,
+                    Ok(false) => {}
+                    Err(e) => report.kept.push(format!("{}: {}", path.display(), e)),
+                },
+            }
+        }
+        self.discard()?;
+        Ok(report)
+    }
+
+    /// Forgets the run, leaving the files as they are.
+    pub fn discard(&self) -> Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Whether `modified` is new enough to have been written by a run started
+/// at `started_at`.
+fn written_since(modified: SystemTime, started_at: DateTime<Utc>) -> bool {
+    DateTime::<Utc>::from(modified) >= started_at - Duration::seconds(MTIME_SLACK_SECS)
+}
+
+/// Whether a process with `pid` exists. A recycled pid reads as alive,
+/// which only postpones the recovery prompt.
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // SAFETY: signal 0 only checks that the process exists.
+    let sent = unsafe { libc::kill(pid, 0) };
+    sent == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn interrupted_runs_report_progress_and_roll_back() -> Result<()> {
+        let journals = tempdir()?;
+        let work = tempdir()?;
+        let cwd = work.path();
+        fs::write(cwd.join("a.txt"), "a")?;
+        fs::write(cwd.join("b.txt"), "b")?;
+        let ops = vec![
+            FileOp::Rename {
+                from: "a.txt".into(),
+                to: "a-1.txt".into(),
+            },
+            FileOp::Rename {
+                from: "b.txt".into(),
+                to: "b-1.txt".into(),
+            },
+            FileOp::Create {
+                path: "out.mp4".into(),
+                existed: false,
+            },
+        ];
+        let mut journal =
+            Journal::begin_in(journals.path(), "number them", "f2", "f2 -r x", cwd, ops)?;
+        fs::rename(cwd.join("a.txt"), cwd.join("a-1.txt"))?;
+        assert_eq!(journal.check_progress()?, 1);
+        fs::write(cwd.join("out.mp4"), "partial")?;
+        // The journal is not finished: this is what a crash leaves behind.
+        drop(journal);
+
+        let runs = interrupted_runs_in(journals.path())?;
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].done, vec![true, false, true]);
+        assert!(runs[0].summary().contains("2 of 3"));
+
+        let report = runs[0].roll_back()?;
+        assert_eq!(report.undone, 2);
+        assert!(cwd.join("a.txt").exists());
+        assert!(!cwd.join("out.mp4").exists());
+        assert!(cwd.join("b.txt").exists());
+        assert!(interrupted_runs_in(journals.path())?.is_empty());
+
+        let finished = Journal::begin_in(journals.path(), "x", "f2", "f2", cwd, Vec::new())?;
+        finished.finish()?;
+        assert!(interrupted_runs_in(journals.path())?.is_empty());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn runs_another_live_dexter_holds_are_not_interrupted() -> Result<()> {
+        let journals = tempdir()?;
+        let cwd = tempdir()?;
+        let mut journal =
+            Journal::begin_in(journals.path(), "x", "f2", "f2", cwd.path(), Vec::new())?;
+        journal.header.pid = Some(std::os::unix::process::parent_id());
+        fs::write(
+            &journal.path,
+            serde_json::to_string(&JournalRecord::Begin(journal.header.clone()))?,
+        )?;
+        assert!(interrupted_runs_in(journals.path())?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+/// The language an intent is written in, judged by its script. Latin text
+/// is taken as English: telling French from German needs more than a
+/// short request gives.
+pub fn detect_language(text: &str) -> &'static str {
+    let mut counts = [0usize; 9];
+    for c in text.chars() {
+        let script = match c as u32 {
+            0x3040..=0x30FF | 0x31F0..=0x31FF => 0, // kana
+            0xAC00..=0xD7AF | 0x1100..=0x11FF | 0x3130..=0x318F => 1, // hangul
+            0x4E00..=0x9FFF | 0x3400..=0x4DBF | 0xF900..=0xFAFF => 2, // han
+            0x0400..=0x04FF => 3,
+            0x0600..=0x06FF => 4,
+            0x0370..=0x03FF => 5,
+            0x0590..=0x05FF => 6,
+            0x0E00..=0x0E7F => 7,
+            0x0900..=0x097F => 8,
+            _ => continue,
+        };
+        counts[script] += 1;
+    }
+    // Japanese mixes kanji into kana, so any kana decides it.
+    if counts[0] > 0 {
+        return "Japanese";
+    }
+    const NAMES: [&str; 9] = [
+        "Japanese", "Korean", "Chinese", "Russian", "Arabic", "Greek", "Hebrew", "Thai", "Hindi",
+    ];
+    counts
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count > 0)
+        .max_by_key(|(_, count)| **count)
+        .map(|(script, _)| NAMES[script])
+        .unwrap_or("English")
+}
+
+/// The language replies should use: `preference` from the config, or the
+/// intent's own for `auto` (and an empty setting).
+pub fn reply_language<'a>(preference: &'a str, user_input: &str) -> &'a str {
+    match preference.trim() {
+        "" | "auto" => detect_language(user_input),
+        language => language,
+    }
+}
+
+/// Prompt section asking for user-facing text in the user's language;
+/// empty for English, which every prompt already produces.
+pub(crate) fn language_section(preference: &str, user_input: &str) -> String {
+    let language = reply_language(preference, user_input);
+    if language.eq_ignore_ascii_case("english") || language.eq_ignore_ascii_case("en") {
+        return String::new();
+    }
+    format!(
+        "\n### Reply Language:\nWrite every question, label, explanation and issue meant for the user in {}. Keep commands, flags, file names, JSON keys and ids exactly as they are.\n",
+        language
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_the_intent_script_and_honours_the_override() {
+        assert_eq!(detect_language("把这些视频转换成 mp4"), "Chinese");
+        assert_eq!(detect_language("この写真を日付でリネームして"), "Japanese");
+        assert_eq!(detect_language("사진 이름을 날짜로 바꿔줘"), "Korean");
+        assert_eq!(detect_language("сожми это видео"), "Russian");
+        assert_eq!(detect_language("rename IMG_【1】.jpg"), "English");
+
+        assert_eq!(language_section("auto", "convert these to mp4"), "");
+        assert!(language_section("auto", "转换成 mp4").contains("in Chinese."));
+        assert!(language_section("German", "convert these").contains("in German."));
+        assert_eq!(language_section("English", "转换成 mp4"), "");
+    }
+}
@@ -1,16 +1,95 @@
-pub use config::{Config, ModelRoute, ProviderAuth, ProviderConfig, ProviderKind};
-pub use context::{ContextScanner, FileContext};
-pub use executor::{Executor, HistoryEntry, PinnedHistoryEntry};
-pub use llm::{CachePolicy, LlmClient};
+pub use audit::{AuditEvent, AuditPolicy};
+pub use bench::BenchResult;
+pub use budget::{ModelPrice, PromptEstimate, TokenBudget};
+pub use collision::{CollisionChecker, RenameCollision};
+pub use config::{
+    Bookmark, Config, ConfigField, ConfigIssue, ConfirmTimeout, ConflictPolicy, IntentTemplate,
+    ModelRoute, OutputLayout, ProviderAuth, ProviderConfig, ProviderKind, RemoteHost,
+    RouterStrategy, RoutingMode, StartupMode, ThumbnailMode,
+};
+pub use conflict::{ConflictOutcome, OutputConflict};
+pub use context::{ContextChange, ContextScanner, DirWatch, FileContext};
+pub use critic::Critic;
+pub use daemon::{Daemon, DaemonRequest, DaemonResponse};
+pub use encoders::{HwEncoder, JobLane};
+pub use executor::{
+    Executor, HistoryEntry, HistoryStamp, PinnedHistoryEntry, RunEnvironment, RunOutcome,
+};
+pub use hooks::{HookPoint, Hooks};
+pub use in_use::FileInUse;
+pub use invocation::Invocation;
+pub use journal::{InterruptedRun, Journal};
+pub use llm::{
+    Attachment, BatchProgress, BatchRequest, CachePolicy, Completion, LlmClient, ModelListError,
+};
+pub use model_cache::{CachedModels, ModelCache};
+pub use network::{MeteredAction, MeteredPolicy, MeteredVerdict};
+pub use oauth::{DeviceCode, OAuthSettings, OAuthTokens};
+pub use ocr::{OcrPolicy, OcrText};
+pub use opener::OpenPolicy;
+pub use recycle::{TrashTool, Trashed};
 pub use redaction::redact_sensitive_text;
+pub use remote::RemoteRunner;
+pub use replay::{InputEvent, SessionEntry, SessionLog};
+pub use risk::{RiskAssessment, RiskLevel};
 pub use router::Router;
-pub use router::{ClarifyOption, ClarifySource, RouteOutcome};
-pub use safety::SafetyGuard;
+pub use router::{ClarifyOption, ClarifySource, PluginScore, RouteExplanation, RouteOutcome};
+pub use router::{RouteRequest, RoutingStrategy};
+pub use safety::{PluginPolicy, SafetyGuard, SafetyPolicy, SafetyViolation};
+pub use sandbox::{PluginSandbox, SandboxPolicy};
+pub use schedule::{ScheduledJob, Scheduler};
+pub use scope::ScopeSummary;
+pub use session::{SessionMemory, SessionTurn};
+pub use sync::{SyncPolicy, SyncReport};
+pub use trash::{DeleteMode, TrashBatch, TrashPolicy};
+pub use triage::FailureHint;
+pub use update::{Release, UpdatePolicy};
+pub use usage::{BudgetAlert, UsageTracker};
+pub use verify::{ExecutionReport, OutputCheck, VerifyPolicy};
+pub use workspace::{Workspace, WorkspaceSettings};
 
+pub mod audit;
+pub mod bench;
+pub mod budget;
+pub mod bundle;
+pub mod collision;
 pub mod config;
+pub mod conflict;
 pub mod context;
+pub mod critic;
+pub mod daemon;
+pub mod docs;
+pub mod encoders;
+pub mod examples;
 pub mod executor;
+pub mod hooks;
+pub mod in_use;
+pub mod invocation;
+pub mod journal;
+pub mod language;
 pub mod llm;
+pub mod model_cache;
+pub mod network;
+pub mod oauth;
+pub mod ocr;
+pub mod opener;
+pub mod paths;
+pub mod recycle;
 pub mod redaction;
+pub mod remote;
+pub mod replay;
+pub mod risk;
 pub mod router;
 pub mod safety;
+pub mod sandbox;
+pub mod schedule;
+pub mod scope;
+pub mod session;
+pub mod splitter;
+pub mod sync;
+pub mod trash;
+pub mod triage;
+pub mod update;
+pub mod usage;
+pub mod verify;
+pub mod workspace;
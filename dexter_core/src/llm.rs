@@ -1,11 +1,25 @@
+use crate::budget::{ModelPrice, PromptEstimate};
 use crate::config::{ModelRoute, ProviderAuth, ProviderConfig, ProviderKind};
+use crate::oauth::{self, DeviceCode, OAuthSettings};
+use crate::replay;
+use crate::usage::UsageTracker;
 use anyhow::{anyhow, Result};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
+use tracing::Instrument;
+
+mod batch;
+mod gemini;
+mod json;
+mod listing;
+pub use batch::{BatchProgress, BatchRequest};
+pub use gemini::Attachment;
+pub use json::{parse_json_reply, JsonCompletion, JsonReply};
+pub use listing::ModelListError;
 
 const DEFAULT_CACHE_CAPACITY: usize = 512;
 const ROUTER_TEMPERATURE: f32 = 0.0;
@@ -21,12 +35,21 @@ pub enum CachePolicy {
     Bypass,
 }
 
+/// Proxy and extra CA certificate a target's requests go through.
+type NetworkKey = (Option<String>, Option<String>);
+
 #[derive(Debug, Clone)]
 pub struct LlmClient {
     http_client: Client,
+    /// One client per distinct proxy/CA setting; a setting that could not be
+    /// applied keeps its error so requests fail instead of going out direct.
+    routed_clients: HashMap<NetworkKey, std::result::Result<Client, String>>,
     targets: Vec<LlmTarget>,
     cache: Arc<RwLock<HashMap<String, String>>>,
     cache_capacity: usize,
+    /// Prices the prompts sent to providers for `usage.json`; `None`
+    /// leaves them untracked.
+    spend_prices: Option<Arc<[ModelPrice]>>,
 }
 
 #[derive(Debug, Clone)]
@@ -37,12 +60,26 @@ struct LlmTarget {
     base_url: String,
     auth: ProviderAuth,
     model: String,
+    proxy: Option<String>,
+    ca_cert: Option<String>,
+    oauth: Option<OAuthSettings>,
+}
+
+impl LlmTarget {
+    fn network_key(&self) -> Option<NetworkKey> {
+        if self.proxy.is_none() && self.ca_cert.is_none() {
+            return None;
+        }
+        Some((self.proxy.clone(), self.ca_cert.clone()))
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
 struct CompletionParams {
     temperature: f32,
     max_tokens: Option<u32>,
+    /// Ask for a JSON object where the provider has a switch for it.
+    json: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -59,6 +96,8 @@ struct ChatRequest {
     temperature: f32,
     #[serde(skip_serializing_if = "Option::is_none")]
     max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -70,6 +109,12 @@ struct Choice {
 #[derive(Debug, Deserialize)]
 struct ChatResponse {
     choices: Vec<Choice>,
+    usage: Option<ChatUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatUsage {
+    completion_tokens: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -98,6 +143,29 @@ struct AnthropicContentBlock {
 struct AnthropicResponse {
     content: Option<Vec<AnthropicContentBlock>>,
     stop_reason: Option<String>,
+    usage: Option<AnthropicUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicUsage {
+    output_tokens: Option<u64>,
+}
+
+/// A reply and the output tokens the provider reports it used; `None`
+/// when the reply came from the cache or the provider reports no usage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    pub content: String,
+    pub output_tokens: Option<u64>,
+}
+
+impl Completion {
+    fn cached(content: String) -> Self {
+        Self {
+            content,
+            output_tokens: None,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -142,7 +210,8 @@ impl LlmClient {
         };
 
         Self {
-            http_client: build_http_client(),
+            http_client: build_default_http_client(),
+            routed_clients: HashMap::new(),
             targets: vec![LlmTarget {
                 provider_name: kind.display_name().to_string(),
                 kind,
@@ -150,9 +219,13 @@ impl LlmClient {
                 base_url: base_url.trim().trim_end_matches('/').to_string(),
                 auth,
                 model: model.trim().to_string(),
+                proxy: None,
+                ca_cert: None,
+                oauth: None,
             }],
             cache: Arc::new(RwLock::new(HashMap::new())),
             cache_capacity: DEFAULT_CACHE_CAPACITY,
+            spend_prices: None,
         }
     }
 
@@ -228,13 +301,46 @@ impl LlmClient {
             ));
         }
 
+        let mut routed_clients = HashMap::new();
+        for key in targets.iter().filter_map(LlmTarget::network_key) {
+            routed_clients
+                .entry(key)
+                .or_insert_with_key(|(proxy, ca_cert)| {
+                    build_http_client(proxy.as_deref(), ca_cert.as_deref())
+                        .map_err(|e| e.to_string())
+                });
+        }
+
         Self {
-            http_client: build_http_client(),
+            http_client: build_default_http_client(),
+            routed_clients,
             targets,
             cache: Arc::new(RwLock::new(HashMap::new())),
             cache_capacity: DEFAULT_CACHE_CAPACITY,
+            spend_prices: None,
         }
     }
+
+    /// A client that tries `route` and nothing else, for a one-off pick
+    /// that shouldn't quietly fall back to other models.
+    pub fn for_route(providers: Vec<ProviderConfig>, route: ModelRoute) -> Self {
+        let providers = providers
+            .into_iter()
+            .filter(|provider| provider.kind == route.provider)
+            .map(|provider| ProviderConfig {
+                models: Vec::new(),
+                ..provider
+            })
+            .collect();
+        let mut client =
+            Self::with_routes(providers, vec![route.clone()], String::new(), Vec::new());
+        // Normalizing gives a provider without models its default list,
+        // which `with_routes` would queue behind the route.
+        client
+            .targets
+            .retain(|target| target.model == route.model.trim());
+        client
+    }
 }
 
 fn build_targets_from_legacy_models(
@@ -287,10 +393,82 @@ fn build_targets_from_legacy_models(
 }
 
 impl LlmClient {
+    /// Adds the estimated cost of every prompt actually sent to a provider
+    /// to `usage.json`, pricing models from `prices` first. Cached replies
+    /// aren't counted.
+    pub fn with_spend_tracking(mut self, prices: Vec<ModelPrice>) -> Self {
+        self.spend_prices = Some(prices.into());
+        self
+    }
+
+    async fn record_spend<'a>(
+        &self,
+        target: &LlmTarget,
+        prompts: impl IntoIterator<Item = &'a str>,
+    ) {
+        let Some(prices) = &self.spend_prices else {
+            return;
+        };
+        let usd = prompts
+            .into_iter()
+            .filter_map(|prompt| {
+                PromptEstimate::with_prices(prompt, Some(target.kind), &target.model, prices).usd
+            })
+            .sum();
+        if let Err(e) = UsageTracker::load().record(usd).await {
+            tracing::warn!("Could not save usage: {}", e);
+        }
+    }
+
+    /// Provider and model the next request tries first.
+    pub fn primary_target(&self) -> Option<(ProviderKind, &str)> {
+        self.targets
+            .first()
+            .map(|target| (target.kind, target.model.as_str()))
+    }
+
+    /// Every provider and model the client tries, in order.
+    pub fn routes(&self) -> Vec<ModelRoute> {
+        self.targets
+            .iter()
+            .map(|target| ModelRoute {
+                provider: target.kind,
+                model: target.model.clone(),
+            })
+            .collect()
+    }
+
+    /// Asks the first provider's OAuth gateway for a device code, through
+    /// the provider's proxy settings.
+    pub async fn start_sign_in(&self) -> Result<DeviceCode> {
+        let target = self.sign_in_target()?;
+        oauth::start_device_flow(self.http_for(target)?, oauth_settings(target)?).await
+    }
+
+    /// Waits for the user to enter `code`, then stores the provider's tokens.
+    pub async fn finish_sign_in(&self, code: &DeviceCode) -> Result<()> {
+        let target = self.sign_in_target()?;
+        oauth::finish_device_flow(
+            self.http_for(target)?,
+            &target.provider_name,
+            oauth_settings(target)?,
+            code,
+        )
+        .await?;
+        Ok(())
+    }
+
+    fn sign_in_target(&self) -> Result<&LlmTarget> {
+        self.targets
+            .first()
+            .ok_or_else(|| anyhow!("No provider to sign in to"))
+    }
+
     pub async fn completion(&self, system_prompt: &str, user_input: &str) -> Result<String> {
         self.completion_with_policy_and_params(
             system_prompt,
             user_input,
+            &[],
             CachePolicy::Normal,
             router_completion_params(),
         )
@@ -302,10 +480,24 @@ impl LlmClient {
         system_prompt: &str,
         user_input: &str,
         cache_policy: CachePolicy,
+    ) -> Result<String> {
+        self.completion_with_attachments(system_prompt, user_input, &[], cache_policy)
+            .await
+    }
+
+    /// Executor-style completion with file data inline. Native Gemini
+    /// targets receive the attachments; the others get the prompt alone.
+    pub async fn completion_with_attachments(
+        &self,
+        system_prompt: &str,
+        user_input: &str,
+        attachments: &[Attachment],
+        cache_policy: CachePolicy,
     ) -> Result<String> {
         self.completion_with_policy_and_params(
             system_prompt,
             user_input,
+            attachments,
             cache_policy,
             executor_completion_params(),
         )
@@ -316,9 +508,47 @@ impl LlmClient {
         &self,
         system_prompt: &str,
         user_input: &str,
+        attachments: &[Attachment],
         cache_policy: CachePolicy,
         params: CompletionParams,
     ) -> Result<String> {
+        if let Some(reply) = replay::replayed_reply(system_prompt, user_input) {
+            return reply;
+        }
+        let reply = self
+            .first_completion(system_prompt, user_input, attachments, cache_policy, params)
+            .await
+            .map(|reply| reply.content);
+        replay::record_reply(system_prompt, user_input, &reply);
+        reply
+    }
+
+    /// `completion_with_policy` with the usage the provider reports, for
+    /// measuring routes; replies aren't recorded for session replay.
+    pub async fn completion_with_usage(
+        &self,
+        system_prompt: &str,
+        user_input: &str,
+        cache_policy: CachePolicy,
+    ) -> Result<Completion> {
+        self.first_completion(
+            system_prompt,
+            user_input,
+            &[],
+            cache_policy,
+            executor_completion_params(),
+        )
+        .await
+    }
+
+    async fn first_completion(
+        &self,
+        system_prompt: &str,
+        user_input: &str,
+        attachments: &[Attachment],
+        cache_policy: CachePolicy,
+        params: CompletionParams,
+    ) -> Result<Completion> {
         let mut errors = Vec::new();
         for target in &self.targets {
             match self
@@ -326,6 +556,7 @@ impl LlmClient {
                     target,
                     system_prompt,
                     user_input,
+                    attachments,
                     cache_policy,
                     params,
                 )
@@ -345,15 +576,169 @@ impl LlmClient {
         ))
     }
 
+    /// Router-style completion sent to the first two targets at once. The
+    /// first response `accept` takes wins and the other request is dropped
+    /// mid-flight; when both fail the remaining targets are tried in order.
+    pub async fn race_completion(
+        &self,
+        system_prompt: &str,
+        user_input: &str,
+        accept: impl Fn(&str) -> bool,
+    ) -> Result<String> {
+        self.race_completion_with_params(
+            system_prompt,
+            user_input,
+            router_completion_params(),
+            accept,
+        )
+        .await
+    }
+
+    async fn race_completion_with_params(
+        &self,
+        system_prompt: &str,
+        user_input: &str,
+        params: CompletionParams,
+        accept: impl Fn(&str) -> bool,
+    ) -> Result<String> {
+        if let Some(reply) = replay::replayed_reply(system_prompt, user_input) {
+            return reply;
+        }
+        let reply = self
+            .first_accepted_completion(system_prompt, user_input, params, accept)
+            .await;
+        replay::record_reply(system_prompt, user_input, &reply);
+        reply
+    }
+
+    async fn first_accepted_completion(
+        &self,
+        system_prompt: &str,
+        user_input: &str,
+        params: CompletionParams,
+        accept: impl Fn(&str) -> bool,
+    ) -> Result<String> {
+        // A reply `accept` refuses isn't kept for the next ask.
+        let accept = &accept;
+        let call = move |target| async move {
+            let result = self
+                .execute_completion_for_target(
+                    target,
+                    system_prompt,
+                    user_input,
+                    &[],
+                    CachePolicy::Normal,
+                    params,
+                )
+                .await
+                .map(|reply| reply.content);
+            if let Ok(content) = &result {
+                if !accept(content) {
+                    self.forget_cached(content).await;
+                }
+            }
+            result
+        };
+        let mut errors = Vec::new();
+        let rest = match self.targets.as_slice() {
+            [first, second, rest @ ..] => {
+                match first_accepted(call(first), call(second), &accept).await {
+                    Ok(content) => return Ok(content),
+                    Err(failures) => {
+                        for (index, e) in failures {
+                            let target = if index == 0 { first } else { second };
+                            errors.push(format!(
+                                "- [{} | {}] {}",
+                                target.provider_name, target.model, e
+                            ));
+                        }
+                    }
+                }
+                rest
+            }
+            all => all,
+        };
+        for target in rest {
+            match call(target).await {
+                Ok(content) if accept(&content) => return Ok(content),
+                Ok(content) => errors.push(format!(
+                    "- [{} | {}] {}",
+                    target.provider_name,
+                    target.model,
+                    invalid_response(&content)
+                )),
+                Err(e) => errors.push(format!(
+                    "- [{} | {}] {}",
+                    target.provider_name, target.model, e
+                )),
+            }
+        }
+
+        Err(anyhow!(
+            "All configured providers/models failed:\n{}",
+            errors.join("\n")
+        ))
+    }
+
+    /// Runs one provider call inside a `provider_call` span that records the
+    /// latency and outcome, so intermittent provider failures show up in the
+    /// log with the exact provider/model pair and timing.
     async fn execute_completion_for_target(
         &self,
         target: &LlmTarget,
         system_prompt: &str,
         user_input: &str,
+        attachments: &[Attachment],
         cache_policy: CachePolicy,
         params: CompletionParams,
-    ) -> Result<String> {
-        if is_anthropic_target(target) {
+    ) -> Result<Completion> {
+        let span = tracing::info_span!(
+            "provider_call",
+            provider = %target.provider_name,
+            model = %target.model,
+            latency_ms = tracing::field::Empty,
+            ok = tracing::field::Empty,
+        );
+        let started = Instant::now();
+        let result = self
+            .dispatch_completion_for_target(
+                target,
+                system_prompt,
+                user_input,
+                attachments,
+                cache_policy,
+                params,
+            )
+            .instrument(span.clone())
+            .await;
+        span.record("latency_ms", started.elapsed().as_millis() as u64);
+        span.record("ok", result.is_ok());
+        if let Err(e) = &result {
+            tracing::warn!(parent: &span, error = %e, "provider call failed");
+        }
+        result
+    }
+
+    async fn dispatch_completion_for_target(
+        &self,
+        target: &LlmTarget,
+        system_prompt: &str,
+        user_input: &str,
+        attachments: &[Attachment],
+        cache_policy: CachePolicy,
+        params: CompletionParams,
+    ) -> Result<Completion> {
+        if gemini::uses_native_gemini(target) {
+            self.execute_gemini_completion_for_target(
+                target,
+                system_prompt,
+                user_input,
+                attachments,
+                cache_policy,
+                params,
+            )
+            .await
+        } else if is_anthropic_target(target) {
             self.execute_anthropic_completion_for_target(
                 target,
                 system_prompt,
@@ -381,15 +766,14 @@ impl LlmClient {
         user_input: &str,
         cache_policy: CachePolicy,
         params: CompletionParams,
-    ) -> Result<String> {
+    ) -> Result<Completion> {
         let url = format!("{}/chat/completions", target.base_url.trim_end_matches('/'));
         let messages = build_openai_messages(target, system_prompt, user_input);
-        let cache_key =
-            self.build_cache_key(target, &messages, params.temperature, params.max_tokens)?;
+        let cache_key = self.build_cache_key(target, &messages, params)?;
 
         if cache_policy == CachePolicy::Normal {
             if let Some(cached) = self.cache.read().await.get(&cache_key).cloned() {
-                return Ok(cached);
+                return Ok(Completion::cached(cached));
             }
         }
 
@@ -398,55 +782,35 @@ impl LlmClient {
             messages,
             temperature: params.temperature,
             max_tokens: params.max_tokens,
+            response_format: params
+                .json
+                .then(|| serde_json::json!({ "type": "json_object" })),
         };
 
-        let mut request = self
-            .http_client
-            .post(&url)
-            .header("Content-Type", "application/json");
-        request = apply_auth_header(request, target)?;
-        let response = request.json(&request_body).send().await?;
+        let (mut status, mut text) = self.send_chat_request(target, &url, &request_body).await?;
 
-        let mut status = response.status();
-        let mut text = response.text().await?;
+        // Compatible servers without JSON mode get the prompt's own format
+        // instructions only.
+        if !status.is_success()
+            && request_body.response_format.is_some()
+            && likely_rejects_field(status.as_u16(), &text, "response_format")
+        {
+            request_body.response_format = None;
+            (status, text) = self.send_chat_request(target, &url, &request_body).await?;
+        }
 
         if !status.is_success()
             && request_body.max_tokens.is_some()
             && likely_rejects_max_tokens(status.as_u16(), &text)
         {
             request_body.max_tokens = None;
-            let mut retry = self
-                .http_client
-                .post(&url)
-                .header("Content-Type", "application/json");
-            retry = apply_auth_header(retry, target)?;
-            let retry_response = retry.json(&request_body).send().await?;
-            status = retry_response.status();
-            text = retry_response.text().await?;
+            (status, text) = self.send_chat_request(target, &url, &request_body).await?;
         }
 
         if !status.is_success() {
-            let lower = text.to_lowercase();
-            let hint = if status.as_u16() == 429
-                || lower.contains("rate limit")
-                || lower.contains("quota")
-            {
-                " (quota/rate-limit, trying fallback)"
-            } else if lower.contains("content_filter")
-                || lower.contains("safety")
-                || lower.contains("blocked")
-            {
-                " (content policy block, trying fallback)"
-            } else {
-                ""
-            };
-            return Err(anyhow!(
-                "LLM API Error (Status {}): {}{}",
-                status,
-                truncate_error(&text),
-                hint
-            ));
+            return Err(api_error(status, &text));
         }
+        self.record_spend(target, [system_prompt, user_input]).await;
 
         let chat_response: ChatResponse = serde_json::from_str(&text).map_err(|e| {
             anyhow!(
@@ -471,16 +835,16 @@ impl LlmClient {
             let content = msg.content.clone();
 
             if cache_policy == CachePolicy::Normal {
-                let mut cache = self.cache.write().await;
-                if cache.len() >= self.cache_capacity {
-                    if let Some(any_key) = cache.keys().next().cloned() {
-                        cache.remove(&any_key);
-                    }
-                }
-                cache.insert(cache_key, content.clone());
+                self.store_in_cache(cache_key, &content).await;
             }
 
-            Ok(content)
+            Ok(Completion {
+                content,
+                output_tokens: chat_response
+                    .usage
+                    .as_ref()
+                    .and_then(|usage| usage.completion_tokens),
+            })
         } else if let Some(reason) = first_choice.finish_reason.as_ref() {
             if reason.to_lowercase().contains("content_filter") {
                 Err(anyhow!(
@@ -497,6 +861,72 @@ impl LlmClient {
         }
     }
 
+    /// Posts `body` to `url`. An OAuth token the provider refuses is
+    /// refreshed and the request sent once more.
+    async fn send_chat_request<T: Serialize>(
+        &self,
+        target: &LlmTarget,
+        url: &str,
+        body: &T,
+    ) -> Result<(reqwest::StatusCode, String)> {
+        let response = self
+            .send_authorized(target, |http| {
+                http.post(url)
+                    .header("Content-Type", "application/json")
+                    .json(body)
+            })
+            .await?;
+        let status = response.status();
+        Ok((status, response.text().await?))
+    }
+
+    /// Sends the request `build` makes, authorized for `target`. An OAuth
+    /// token the provider refuses is refreshed, and the request built and
+    /// sent once more.
+    async fn send_authorized(
+        &self,
+        target: &LlmTarget,
+        build: impl Fn(&Client) -> reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response> {
+        let mut refused: Option<String> = None;
+        loop {
+            let request = build(self.http_for(target)?);
+            let (request, token) = self.authorize(request, target, refused.as_deref()).await?;
+            let response = request.send().await?;
+            if response.status() == reqwest::StatusCode::UNAUTHORIZED && refused.is_none() {
+                if let Some(token) = token {
+                    refused = Some(token);
+                    continue;
+                }
+            }
+            return Ok(response);
+        }
+    }
+
+    /// `apply_auth_header`, with the OAuth token looked up first: refreshed
+    /// when it is about to expire or when the provider `refused` it. Also
+    /// returns the OAuth token it sent.
+    async fn authorize(
+        &self,
+        request: reqwest::RequestBuilder,
+        target: &LlmTarget,
+        refused: Option<&str>,
+    ) -> Result<(reqwest::RequestBuilder, Option<String>)> {
+        if target.auth != ProviderAuth::OAuth {
+            return Ok((apply_auth_header(request, target)?, None));
+        }
+        let settings = oauth_settings(target)?;
+        let http = self.http_for(target)?;
+        let token = match refused {
+            Some(stale) => {
+                oauth::refresh_access_token(http, &target.provider_name, settings, stale).await?
+            }
+            None => oauth::access_token(http, &target.provider_name, settings).await?,
+        };
+        let request = request.header("Authorization", format!("Bearer {}", token));
+        Ok((request, Some(token)))
+    }
+
     async fn execute_anthropic_completion_for_target(
         &self,
         target: &LlmTarget,
@@ -504,7 +934,7 @@ impl LlmClient {
         user_input: &str,
         cache_policy: CachePolicy,
         params: CompletionParams,
-    ) -> Result<String> {
+    ) -> Result<Completion> {
         let url = format!("{}/messages", target.base_url.trim_end_matches('/'));
         let max_tokens = params.max_tokens.unwrap_or(EXECUTOR_MAX_TOKENS);
         let cache_key = self.build_anthropic_cache_key(
@@ -517,7 +947,7 @@ impl LlmClient {
 
         if cache_policy == CachePolicy::Normal {
             if let Some(cached) = self.cache.read().await.get(&cache_key).cloned() {
-                return Ok(cached);
+                return Ok(Completion::cached(cached));
             }
         }
 
@@ -532,38 +962,12 @@ impl LlmClient {
             max_tokens,
         };
 
-        let mut request = self
-            .http_client
-            .post(&url)
-            .header("Content-Type", "application/json");
-        request = apply_auth_header(request, target)?;
-        let response = request.json(&request_body).send().await?;
-
-        let status = response.status();
-        let text = response.text().await?;
+        let (status, text) = self.send_chat_request(target, &url, &request_body).await?;
 
         if !status.is_success() {
-            let lower = text.to_lowercase();
-            let hint = if status.as_u16() == 429
-                || lower.contains("rate limit")
-                || lower.contains("quota")
-            {
-                " (quota/rate-limit, trying fallback)"
-            } else if lower.contains("content_filter")
-                || lower.contains("safety")
-                || lower.contains("blocked")
-            {
-                " (content policy block, trying fallback)"
-            } else {
-                ""
-            };
-            return Err(anyhow!(
-                "LLM API Error (Status {}): {}{}",
-                status,
-                truncate_error(&text),
-                hint
-            ));
+            return Err(api_error(status, &text));
         }
+        self.record_spend(target, [system_prompt, user_input]).await;
 
         let parsed: AnthropicResponse = serde_json::from_str(&text).map_err(|e| {
             anyhow!(
@@ -604,29 +1008,42 @@ impl LlmClient {
         let content = chunks.join("\n\n");
 
         if cache_policy == CachePolicy::Normal {
-            let mut cache = self.cache.write().await;
-            if cache.len() >= self.cache_capacity {
-                if let Some(any_key) = cache.keys().next().cloned() {
-                    cache.remove(&any_key);
-                }
+            self.store_in_cache(cache_key, &content).await;
+        }
+
+        Ok(Completion {
+            content,
+            output_tokens: parsed.usage.and_then(|usage| usage.output_tokens),
+        })
+    }
+
+    async fn store_in_cache(&self, key: String, content: &str) {
+        let mut cache = self.cache.write().await;
+        if cache.len() >= self.cache_capacity {
+            if let Some(any_key) = cache.keys().next().cloned() {
+                cache.remove(&any_key);
             }
-            cache.insert(cache_key, content.clone());
         }
+        cache.insert(key, content.to_string());
+    }
 
-        Ok(content)
+    /// Drops every cached copy of `reply`, a reply that turned out to be
+    /// unusable, so asking again reaches the provider.
+    pub async fn forget_cached(&self, reply: &str) {
+        self.cache.write().await.retain(|_, cached| cached != reply);
     }
 
     fn build_cache_key(
         &self,
         target: &LlmTarget,
         messages: &[ChatMessage],
-        temperature: f32,
-        max_tokens: Option<u32>,
+        params: CompletionParams,
     ) -> Result<String> {
         let payload = serde_json::json!({
             "messages": messages,
-            "temperature": temperature,
-            "max_tokens": max_tokens,
+            "temperature": params.temperature,
+            "max_tokens": params.max_tokens,
+            "json": params.json,
         });
         let payload = serde_json::to_string(&payload)
             .map_err(|e| anyhow!("Failed to serialize messages for cache key: {}", e))?;
@@ -656,6 +1073,8 @@ impl LlmClient {
         ))
     }
 
+    /// Every model the configured providers list. With one provider its
+    /// `ModelListError` stays in the error chain.
     pub async fn list_models(&self) -> Result<Vec<String>> {
         let mut all = Vec::new();
         let mut errors = Vec::new();
@@ -676,10 +1095,8 @@ impl LlmClient {
             match self.fetch_models_for_target(target).await {
                 Ok(models) => all.extend(models),
                 Err(e) => {
-                    errors.push(format!(
-                        "{} @ {}: {}",
-                        target.provider_name, target.base_url, e
-                    ));
+                    errors
+                        .push(e.context(format!("{} @ {}", target.provider_name, target.base_url)));
                 }
             }
         }
@@ -688,15 +1105,37 @@ impl LlmClient {
         all.dedup();
 
         if all.is_empty() {
+            if errors.len() == 1 {
+                return Err(errors.remove(0).context("Failed to list models"));
+            }
             return Err(anyhow!(
                 "Failed to list models from configured providers:\n{}",
-                errors.join("\n")
+                errors
+                    .iter()
+                    .map(|e| format!("{:#}", e))
+                    .collect::<Vec<_>>()
+                    .join("\n")
             ));
         }
 
         Ok(all)
     }
 
+    fn http_for(&self, target: &LlmTarget) -> Result<&Client> {
+        let Some(key) = target.network_key() else {
+            return Ok(&self.http_client);
+        };
+        match self.routed_clients.get(&key) {
+            Some(Ok(client)) => Ok(client),
+            Some(Err(e)) => Err(anyhow!(
+                "{}: proxy/TLS settings not applied: {}",
+                target.provider_name,
+                e
+            )),
+            None => Ok(&self.http_client),
+        }
+    }
+
     async fn fetch_models_for_target(&self, target: &LlmTarget) -> Result<Vec<String>> {
         // Gemini exposes model discovery on its non-openai endpoint.
         if is_gemini_target(target) {
@@ -704,62 +1143,33 @@ impl LlmClient {
                 .api_key
                 .clone()
                 .ok_or_else(|| anyhow!("Gemini model listing requires API key"))?;
-            let mut base = target.base_url.trim_end_matches('/').to_string();
-            if base.ends_with("/openai") {
-                base = base.trim_end_matches("/openai").to_string();
-            }
-            let url = format!("{}/models?key={}", base, key);
-            let response = self.http_client.get(&url).send().await?;
-            let status = response.status();
-            let text = response.text().await?;
-            if !status.is_success() {
-                return Err(anyhow!(
-                    "status {} from {}: {}",
-                    status,
-                    url,
-                    truncate_error(&text)
-                ));
-            }
-            return parse_model_list(&text);
+            let url = format!("{}/models", gemini::native_base_url(&target.base_url));
+            let request = self.http_for(target)?.get(format!("{}?key={}", url, key));
+            return Ok(listing::fetch_model_list(target, &url, request).await?);
         }
 
         // OpenAI-compatible path.
         let url = format!("{}/models", target.base_url.trim_end_matches('/'));
-        let mut request = self.http_client.get(&url);
-        request = apply_auth_header(request, target)?;
-        let response = request.send().await?;
-        let status = response.status();
-        let text = response.text().await?;
-        if status.is_success() {
-            return parse_model_list(&text);
-        }
+        let (request, _) = self
+            .authorize(self.http_for(target)?.get(&url), target, None)
+            .await?;
+        let error = match listing::fetch_model_list(target, &url, request).await {
+            Ok(models) => return Ok(models),
+            Err(e) => e,
+        };
 
         // Ollama fallback to native endpoint.
-        if target.kind == ProviderKind::Ollama {
+        if target.kind == ProviderKind::Ollama && error.status.is_some() {
             let base = target.base_url.trim_end_matches('/');
             let root = base.strip_suffix("/v1").unwrap_or(base);
             let fallback_url = format!("{}/api/models", root);
-            let response = self.http_client.get(&fallback_url).send().await?;
-            let fallback_status = response.status();
-            let fallback_text = response.text().await?;
-            if fallback_status.is_success() {
-                return parse_model_list(&fallback_text);
+            let request = self.http_for(target)?.get(&fallback_url);
+            if let Ok(models) = listing::fetch_model_list(target, &fallback_url, request).await {
+                return Ok(models);
             }
-            return Err(anyhow!(
-                "status {} from {}, then status {} from {}",
-                status,
-                url,
-                fallback_status,
-                fallback_url
-            ));
         }
 
-        Err(anyhow!(
-            "status {} from {}: {}",
-            status,
-            url,
-            truncate_error(&text)
-        ))
+        Err(error.into())
     }
 }
 
@@ -804,6 +1214,7 @@ fn router_completion_params() -> CompletionParams {
     CompletionParams {
         temperature: ROUTER_TEMPERATURE,
         max_tokens: Some(ROUTER_MAX_TOKENS),
+        json: false,
     }
 }
 
@@ -811,9 +1222,16 @@ fn executor_completion_params() -> CompletionParams {
     CompletionParams {
         temperature: EXECUTOR_TEMPERATURE,
         max_tokens: Some(EXECUTOR_MAX_TOKENS),
+        json: false,
     }
 }
 
+/// A 400 that names `field`, the way servers refuse a request option they
+/// don't support.
+fn likely_rejects_field(status_code: u16, body: &str, field: &str) -> bool {
+    status_code == 400 && body.to_lowercase().contains(field)
+}
+
 fn likely_rejects_max_tokens(status_code: u16, body: &str) -> bool {
     if status_code != 400 {
         return false;
@@ -892,9 +1310,24 @@ fn apply_auth_header(
                 .header("x-api-key", key)
                 .header("anthropic-version", "2023-06-01"))
         }
+        // The stored token as is; `LlmClient::authorize` refreshes it.
+        ProviderAuth::OAuth => {
+            let token = oauth::stored_access_token(oauth_settings(target)?)
+                .ok_or_else(|| anyhow!("Not signed in to provider {}", target.provider_name))?;
+            Ok(request.header("Authorization", format!("Bearer {}", token)))
+        }
     }
 }
 
+fn oauth_settings(target: &LlmTarget) -> Result<&OAuthSettings> {
+    target.oauth.as_ref().ok_or_else(|| {
+        anyhow!(
+            "Provider {} uses auth = \"oauth\" but has no `oauth` table",
+            target.provider_name
+        )
+    })
+}
+
 fn infer_provider_kind(base_url: &str) -> ProviderKind {
     let lower = base_url.to_lowercase();
     if lower.contains("api.openai.com") {
@@ -932,21 +1365,96 @@ fn target_from_provider(provider: &ProviderConfig, model: String) -> LlmTarget {
         base_url: provider.base_url.trim().trim_end_matches('/').to_string(),
         auth: provider.auth,
         model,
+        proxy: provider.proxy.clone(),
+        ca_cert: provider.ca_cert.clone(),
+        oauth: provider.oauth.clone(),
     }
 }
 
 fn target_key(target: &LlmTarget) -> String {
     format!(
-        "{}|{}|{:?}|{}|{}",
+        "{}|{}|{:?}|{}|{}|{:?}",
         target.provider_name,
         target.base_url,
         target.auth,
         target.api_key.clone().unwrap_or_default(),
-        target.model
+        target.model,
+        target.network_key()
+    )
+}
+
+/// A provider's error response, with a hint when the fallback chain is
+/// about to move on because of quota or content policy.
+fn api_error(status: reqwest::StatusCode, text: &str) -> anyhow::Error {
+    let lower = text.to_lowercase();
+    let hint = if status.as_u16() == 429 || lower.contains("rate limit") || lower.contains("quota")
+    {
+        " (quota/rate-limit, trying fallback)"
+    } else if lower.contains("content_filter")
+        || lower.contains("safety")
+        || lower.contains("blocked")
+    {
+        " (content policy block, trying fallback)"
+    } else {
+        ""
+    };
+    anyhow!(
+        "LLM API Error (Status {}): {}{}",
+        status,
+        truncate_error(text),
+        hint
+    )
+}
+
+/// Polls both calls together and returns the first accepted response; the
+/// other call is dropped, which cancels its request. On failure, returns
+/// each call's error tagged with its position (0 or 1).
+async fn first_accepted<F>(
+    first: F,
+    second: F,
+    accept: impl Fn(&str) -> bool,
+) -> std::result::Result<String, Vec<(usize, anyhow::Error)>>
+where
+    F: std::future::Future<Output = Result<String>>,
+{
+    let check = |result: Result<String>| {
+        result.and_then(|content| {
+            if accept(&content) {
+                Ok(content)
+            } else {
+                Err(invalid_response(&content))
+            }
+        })
+    };
+    tokio::pin!(first);
+    tokio::pin!(second);
+    let (index, result) = tokio::select! {
+        result = &mut first => (0, result),
+        result = &mut second => (1, result),
+    };
+    let early = match check(result) {
+        Ok(content) => return Ok(content),
+        Err(e) => (index, e),
+    };
+    let late = if index == 0 {
+        (1, check((&mut second).await))
+    } else {
+        (0, check((&mut first).await))
+    };
+    match late {
+        (_, Ok(content)) => Ok(content),
+        (index, Err(e)) => Err(vec![early, (index, e)]),
+    }
+}
+
+fn invalid_response(content: &str) -> anyhow::Error {
+    anyhow!(
+        "Response was not in the expected format: {}",
+        truncate_error(content)
     )
 }
 
-fn truncate_error(text: &str) -> String {
+pub(crate) fn truncate_error(text: &str) -> String {
     const MAX: usize = 320;
     if text.len() > MAX {
         format!("{}...", &text[..MAX])
@@ -955,18 +1463,38 @@ fn truncate_error(text: &str) -> String {
     }
 }
 
-fn build_http_client() -> Client {
-    Client::builder()
-        .no_proxy()
+fn build_default_http_client() -> Client {
+    build_http_client(None, None).unwrap_or_else(|_| {
+        Client::builder()
+            .no_proxy()
+            .build()
+            .unwrap_or_else(|_| panic!("failed to initialize HTTP client"))
+    })
+}
+
+/// Requests go out directly unless a provider names a proxy; `system`
+/// defers to reqwest's `HTTPS_PROXY`/`ALL_PROXY` handling.
+fn build_http_client(proxy: Option<&str>, ca_cert: Option<&str>) -> Result<Client> {
+    let mut builder = Client::builder()
         .connect_timeout(Duration::from_secs(HTTP_CONNECT_TIMEOUT_SECS))
-        .timeout(Duration::from_secs(HTTP_REQUEST_TIMEOUT_SECS))
-        .build()
-        .unwrap_or_else(|_| {
-            Client::builder()
-                .no_proxy()
-                .build()
-                .unwrap_or_else(|_| panic!("failed to initialize HTTP client"))
-        })
+        .timeout(Duration::from_secs(HTTP_REQUEST_TIMEOUT_SECS));
+    match proxy {
+        Some(proxy) if proxy.eq_ignore_ascii_case("system") => {}
+        Some(proxy) => {
+            let proxy = reqwest::Proxy::all(proxy)
+                .map_err(|e| anyhow!("invalid proxy `{}`: {}", proxy, e))?;
+            builder = builder.proxy(proxy);
+        }
+        None => builder = builder.no_proxy(),
+    }
+    if let Some(path) = ca_cert {
+        let pem = std::fs::read(path)
+            .map_err(|e| anyhow!("cannot read CA certificate {}: {}", path, e))?;
+        let cert = reqwest::Certificate::from_pem(&pem)
+            .map_err(|e| anyhow!("invalid CA certificate {}: {}", path, e))?;
+        builder = builder.add_root_certificate(cert);
+    }
+    Ok(builder.build()?)
 }
 
 fn clean_optional(input: String) -> Option<String> {
@@ -985,3 +1513,54 @@ impl dexter_plugins::LlmBridge for LlmClient {
             .await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn reply(delay_ms: u64, content: Result<String>) -> Result<String> {
+        tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+        content
+    }
+
+    #[tokio::test]
+    async fn race_takes_the_first_valid_reply() {
+        let json = |text: &str| text.starts_with('{');
+        let fast = reply(5, Ok("{\"fast\":1}".to_string()));
+        let slow = reply(5_000, Ok("{\"slow\":1}".to_string()));
+        let started = Instant::now();
+        assert_eq!(
+            first_accepted(fast, slow, json).await.unwrap(),
+            "{\"fast\":1}"
+        );
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        let garbled = reply(5, Ok("sorry, I can't".to_string()));
+        let valid = reply(30, Ok("{\"slow\":1}".to_string()));
+        assert_eq!(
+            first_accepted(garbled, valid, json).await.unwrap(),
+            "{\"slow\":1}"
+        );
+
+        let down = reply(5, Err(anyhow!("503")));
+        let garbled = reply(10, Ok("nope".to_string()));
+        let failures = first_accepted(down, garbled, json).await.unwrap_err();
+        let order: Vec<usize> = failures.iter().map(|(index, _)| *index).collect();
+        assert_eq!(order, [0, 1]);
+    }
+
+    #[tokio::test]
+    async fn forgotten_replies_leave_the_cache() {
+        let client = LlmClient::new(
+            String::new(),
+            "http://localhost:1".to_string(),
+            "m".to_string(),
+        );
+        client.store_in_cache("a".to_string(), "{\"broken\"").await;
+        client.store_in_cache("b".to_string(), "ls").await;
+        client.forget_cached("{\"broken\"").await;
+        let cache = client.cache.read().await;
+        assert!(!cache.contains_key("a"));
+        assert_eq!(cache.get("b").map(String::as_str), Some("ls"));
+    }
+}
@@ -0,0 +1,569 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Semaphore};
+use tokio::task::JoinSet;
+
+use super::{
+    build_openai_messages, executor_completion_params, truncate_error, CachePolicy, LlmClient,
+    LlmTarget, EXECUTOR_MAX_TOKENS,
+};
+use crate::config::ProviderKind;
+
+const BATCH_POLL_INTERVAL: Duration = Duration::from_secs(10);
+/// How long a submitted batch may run before it is cancelled and its
+/// unfinished requests are sent one by one.
+const BATCH_DEADLINE: Duration = Duration::from_secs(60 * 60);
+const FALLBACK_CONCURRENCY: usize = 4;
+
+/// One prompt pair of a bulk generation.
+#[derive(Debug, Clone)]
+pub struct BatchRequest {
+    pub system_prompt: String,
+    pub user_input: String,
+}
+
+/// Snapshot of a running batch, sent after every poll.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchProgress {
+    /// Provider-side status (`in_progress`, `ended`, ...) or `concurrent`
+    /// when the requests are sent one by one.
+    pub status: String,
+    pub completed: usize,
+    pub failed: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BatchApi {
+    OpenAi,
+    Anthropic,
+}
+
+fn batch_api(target: &LlmTarget) -> Option<BatchApi> {
+    let host = target.base_url.to_lowercase();
+    match target.kind {
+        ProviderKind::OpenAI if host.contains("api.openai.com") => Some(BatchApi::OpenAi),
+        ProviderKind::Anthropic if host.contains("api.anthropic.com") => Some(BatchApi::Anthropic),
+        _ => None,
+    }
+}
+
+impl LlmClient {
+    /// Runs executor-style completions for all `requests`, returning one
+    /// result per request in order. The primary target's batch API is used
+    /// when it has one (OpenAI Batch, Anthropic Message Batches; both billed
+    /// at a discount); otherwise the requests go through the normal
+    /// fallback chain a few at a time. Requests the batch failed or didn't
+    /// finish within `BATCH_DEADLINE` take the fallback chain as well.
+    pub async fn batch_completion(
+        &self,
+        requests: &[BatchRequest],
+        progress: mpsc::Sender<BatchProgress>,
+    ) -> Result<Vec<Result<String>>> {
+        if requests.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut results = None;
+        if let Some(target) = self.targets.first() {
+            if let Some(api) = batch_api(target) {
+                let submitted = match api {
+                    BatchApi::OpenAi => self.run_openai_batch(target, requests, &progress).await,
+                    BatchApi::Anthropic => {
+                        self.run_anthropic_batch(target, requests, &progress).await
+                    }
+                };
+                match submitted {
+                    Ok(batch) => results = Some(batch),
+                    Err(e) => tracing::warn!(
+                        provider = %target.provider_name,
+                        error = %e,
+                        "batch API unavailable, sending requests individually"
+                    ),
+                }
+            }
+        }
+        let Some(mut results) = results else {
+            return Ok(self.run_concurrent(requests, &progress).await);
+        };
+        let resend: Vec<usize> = (0..results.len())
+            .filter(|&idx| results[idx].is_err())
+            .collect();
+        if resend.is_empty() {
+            return Ok(results);
+        }
+        tracing::info!(
+            requests = resend.len(),
+            "sending failed batch requests individually"
+        );
+        let retry: Vec<BatchRequest> = resend.iter().map(|&idx| requests[idx].clone()).collect();
+        let retried = self.run_concurrent(&retry, &progress).await;
+        for (idx, result) in resend.into_iter().zip(retried) {
+            results[idx] = result;
+        }
+        Ok(results)
+    }
+
+    async fn run_concurrent(
+        &self,
+        requests: &[BatchRequest],
+        progress: &mpsc::Sender<BatchProgress>,
+    ) -> Vec<Result<String>> {
+        let total = requests.len();
+        let limit = Arc::new(Semaphore::new(FALLBACK_CONCURRENCY));
+        let mut tasks = JoinSet::new();
+        for (idx, request) in requests.iter().cloned().enumerate() {
+            let client = self.clone();
+            let limit = limit.clone();
+            tasks.spawn(async move {
+                let _permit = limit.acquire_owned().await;
+                let result = client
+                    .completion_with_policy(
+                        &request.system_prompt,
+                        &request.user_input,
+                        CachePolicy::Normal,
+                    )
+                    .await;
+                (idx, result)
+            });
+        }
+
+        let mut results: Vec<Option<Result<String>>> = (0..total).map(|_| None).collect();
+        let (mut completed, mut failed) = (0, 0);
+        while let Some(joined) = tasks.join_next().await {
+            let Ok((idx, result)) = joined else {
+                continue;
+            };
+            if result.is_ok() {
+                completed += 1;
+            } else {
+                failed += 1;
+            }
+            results[idx] = Some(result);
+            let _ = progress
+                .send(BatchProgress {
+                    status: "concurrent".to_string(),
+                    completed,
+                    failed,
+                    total,
+                })
+                .await;
+        }
+        results
+            .into_iter()
+            .map(|r| r.unwrap_or_else(|| Err(anyhow!("Request task failed"))))
+            .collect()
+    }
+
+    async fn run_openai_batch(
+        &self,
+        target: &LlmTarget,
+        requests: &[BatchRequest],
+        progress: &mpsc::Sender<BatchProgress>,
+    ) -> Result<Vec<Result<String>>> {
+        let base = target.base_url.trim_end_matches('/');
+        let jsonl = openai_batch_jsonl(target, requests)?;
+        let boundary = format!("dexter-batch-{}", std::process::id());
+        let upload = self
+            .send_authorized(target, |http| {
+                http.post(format!("{}/files", base))
+                    .header(
+                        "Content-Type",
+                        format!("multipart/form-data; boundary={}", boundary),
+                    )
+                    .body(multipart_body(&boundary, &jsonl))
+            })
+            .await?;
+        let file: serde_json::Value = read_json(upload).await?;
+        let file_id = json_str(&file, "id")?;
+
+        let create = self
+            .send_authorized(target, |http| {
+                http.post(format!("{}/batches", base)).json(&json!({
+                    "input_file_id": file_id,
+                    "endpoint": "/v1/chat/completions",
+                    "completion_window": "24h",
+                }))
+            })
+            .await?;
+        let batch: serde_json::Value = read_json(create).await?;
+        let batch_id = json_str(&batch, "id")?;
+        tracing::info!(batch_id = %batch_id, requests = requests.len(), "openai batch submitted");
+        self.record_spend(target, prompts(requests)).await;
+
+        let started = Instant::now();
+        let finished = loop {
+            let poll = self
+                .send_authorized(target, |http| {
+                    http.get(format!("{}/batches/{}", base, batch_id))
+                })
+                .await?;
+            let batch: serde_json::Value = read_json(poll).await?;
+            let status = json_str(&batch, "status")?;
+            let counts = &batch["request_counts"];
+            let _ = progress
+                .send(BatchProgress {
+                    status: status.clone(),
+                    completed: counts["completed"].as_u64().unwrap_or(0) as usize,
+                    failed: counts["failed"].as_u64().unwrap_or(0) as usize,
+                    total: requests.len(),
+                })
+                .await;
+            match status.as_str() {
+                "completed" | "failed" | "expired" | "cancelled" => break batch,
+                _ if started.elapsed() >= BATCH_DEADLINE => {
+                    self.cancel_batch(target, format!("{}/batches/{}/cancel", base, batch_id))
+                        .await;
+                    return Ok(unfinished(requests.len()));
+                }
+                _ => tokio::time::sleep(BATCH_POLL_INTERVAL).await,
+            }
+        };
+
+        let mut lines = String::new();
+        for key in ["output_file_id", "error_file_id"] {
+            if let Some(id) = finished[key].as_str() {
+                let body = self
+                    .send_authorized(target, |http| {
+                        http.get(format!("{}/files/{}/content", base, id))
+                    })
+                    .await?
+                    .text()
+                    .await?;
+                lines.push_str(&body);
+                lines.push('\n');
+            }
+        }
+        if lines.trim().is_empty() {
+            return Err(anyhow!(
+                "Batch {} ended as `{}` without results",
+                batch_id,
+                finished["status"].as_str().unwrap_or("unknown")
+            ));
+        }
+        Ok(collect_results(
+            requests.len(),
+            parse_openai_results(&lines),
+        ))
+    }
+
+    async fn run_anthropic_batch(
+        &self,
+        target: &LlmTarget,
+        requests: &[BatchRequest],
+        progress: &mpsc::Sender<BatchProgress>,
+    ) -> Result<Vec<Result<String>>> {
+        let base = target.base_url.trim_end_matches('/');
+        let params = executor_completion_params();
+        let body = json!({
+            "requests": requests.iter().enumerate().map(|(idx, r)| json!({
+                "custom_id": custom_id(idx),
+                "params": {
+                    "model": target.model,
+                    "max_tokens": params.max_tokens.unwrap_or(EXECUTOR_MAX_TOKENS),
+                    "temperature": params.temperature,
+                    "system": r.system_prompt,
+                    "messages": [{"role": "user", "content": r.user_input}],
+                },
+            })).collect::<Vec<_>>(),
+        });
+        let create = self
+            .send_authorized(target, |http| {
+                http.post(format!("{}/messages/batches", base)).json(&body)
+            })
+            .await?;
+        let batch: serde_json::Value = read_json(create).await?;
+        let batch_id = json_str(&batch, "id")?;
+        tracing::info!(batch_id = %batch_id, requests = requests.len(), "anthropic batch submitted");
+        self.record_spend(target, prompts(requests)).await;
+
+        let started = Instant::now();
+        let results_url = loop {
+            let poll = self
+                .send_authorized(target, |http| {
+                    http.get(format!("{}/messages/batches/{}", base, batch_id))
+                })
+                .await?;
+            let batch: serde_json::Value = read_json(poll).await?;
+            let status = json_str(&batch, "processing_status")?;
+            let counts = &batch["request_counts"];
+            let count = |key: &str| counts[key].as_u64().unwrap_or(0) as usize;
+            let _ = progress
+                .send(BatchProgress {
+                    status: status.clone(),
+                    completed: count("succeeded"),
+                    failed: count("errored") + count("canceled") + count("expired"),
+                    total: requests.len(),
+                })
+                .await;
+            if status == "ended" {
+                break json_str(&batch, "results_url")?;
+            }
+            if started.elapsed() >= BATCH_DEADLINE {
+                let url = format!("{}/messages/batches/{}/cancel", base, batch_id);
+                self.cancel_batch(target, url).await;
+                return Ok(unfinished(requests.len()));
+            }
+            tokio::time::sleep(BATCH_POLL_INTERVAL).await;
+        };
+
+        let lines = self
+            .send_authorized(target, |http| http.get(&results_url))
+            .await?
+            .text()
+            .await?;
+        Ok(collect_results(
+            requests.len(),
+            parse_anthropic_results(&lines),
+        ))
+    }
+
+    /// Asks the provider to stop a batch past `BATCH_DEADLINE`, so the
+    /// requests sent again aren't billed twice. Best effort.
+    async fn cancel_batch(&self, target: &LlmTarget, url: String) {
+        let cancelled = self
+            .send_authorized(target, |http| http.post(&url))
+            .await
+            .and_then(|response| Ok(response.error_for_status()?));
+        if let Err(e) = cancelled {
+            tracing::warn!(error = %e, "could not cancel batch");
+        }
+    }
+}
+
+/// Results for a batch cancelled before it finished: every request is
+/// sent again.
+fn unfinished(total: usize) -> Vec<Result<String>> {
+    (0..total)
+        .map(|_| Err(anyhow!("Batch did not finish in time")))
+        .collect()
+}
+
+fn prompts(requests: &[BatchRequest]) -> impl Iterator<Item = &str> {
+    requests
+        .iter()
+        .flat_map(|r| [r.system_prompt.as_str(), r.user_input.as_str()])
+}
+
+fn custom_id(idx: usize) -> String {
+    format!("dexter-{}", idx)
+}
+
+fn openai_batch_jsonl(target: &LlmTarget, requests: &[BatchRequest]) -> Result<String> {
+    let params = executor_completion_params();
+    let mut out = String::new();
+    for (idx, request) in requests.iter().enumerate() {
+        let line = json!({
+            "custom_id": custom_id(idx),
+            "method": "POST",
+            "url": "/v1/chat/completions",
+            "body": {
+                "model": target.model,
+                "messages": build_openai_messages(target, &request.system_prompt, &request.user_input),
+                "temperature": params.temperature,
+                "max_tokens": params.max_tokens,
+            },
+        });
+        out.push_str(&serde_json::to_string(&line)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn multipart_body(boundary: &str, jsonl: &str) -> String {
+    format!(
+        "--{b}\r\nContent-Disposition: form-data; name=\"purpose\"\r\n\r\nbatch\r\n\
+         --{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"dexter-batch.jsonl\"\r\n\
+         Content-Type: application/jsonl\r\n\r\n{body}\r\n--{b}--\r\n",
+        b = boundary,
+        body = jsonl
+    )
+}
+
+async fn read_json(response: reqwest::Response) -> Result<serde_json::Value> {
+    let status = response.status();
+    let text = response.text().await?;
+    if !status.is_success() {
+        return Err(anyhow!(
+            "Batch API Error (Status {}): {}",
+            status,
+            truncate_error(&text)
+        ));
+    }
+    Ok(serde_json::from_str(&text)?)
+}
+
+fn json_str(value: &serde_json::Value, key: &str) -> Result<String> {
+    value[key]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("Batch API response is missing `{}`", key))
+}
+
+#[derive(Deserialize)]
+struct OpenAiResultLine {
+    custom_id: String,
+    response: Option<OpenAiResultResponse>,
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiResultResponse {
+    status_code: u16,
+    body: serde_json::Value,
+}
+
+fn parse_openai_results(jsonl: &str) -> HashMap<String, Result<String>> {
+    let mut out = HashMap::new();
+    for line in jsonl.lines().filter(|l| !l.trim().is_empty()) {
+        let Ok(parsed) = serde_json::from_str::<OpenAiResultLine>(line) else {
+            continue;
+        };
+        let result = match (&parsed.response, &parsed.error) {
+            (Some(response), _) if response.status_code == 200 => response.body["choices"][0]
+                ["message"]["content"]
+                .as_str()
+                .filter(|c| !c.trim().is_empty())
+                .map(str::to_string)
+                .ok_or_else(|| anyhow!("LLM returned empty content")),
+            (Some(response), _) => Err(anyhow!(
+                "LLM API Error (Status {}): {}",
+                response.status_code,
+                truncate_error(&response.body.to_string())
+            )),
+            (None, Some(error)) => Err(anyhow!("Batch request failed: {}", error)),
+            (None, None) => Err(anyhow!("Batch request returned nothing")),
+        };
+        out.insert(parsed.custom_id, result);
+    }
+    out
+}
+
+fn parse_anthropic_results(jsonl: &str) -> HashMap<String, Result<String>> {
+    let mut out = HashMap::new();
+    for line in jsonl.lines().filter(|l| !l.trim().is_empty()) {
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        let Some(id) = value["custom_id"].as_str() else {
+            continue;
+        };
+        let result = &value["result"];
+        let parsed = match result["type"].as_str() {
+            Some("succeeded") => {
+                let text: Vec<&str> = result["message"]["content"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter(|block| block["type"] == "text")
+                    .filter_map(|block| block["text"].as_str())
+                    .map(str::trim)
+                    .filter(|t| !t.is_empty())
+                    .collect();
+                if text.is_empty() {
+                    Err(anyhow!("LLM returned empty content"))
+                } else {
+                    Ok(text.join("\n"))
+                }
+            }
+            Some(kind) => Err(anyhow!(
+                "Batch request {}: {}",
+                kind,
+                truncate_error(&result["error"].to_string())
+            )),
+            None => Err(anyhow!("Batch request returned nothing")),
+        };
+        out.insert(id.to_string(), parsed);
+    }
+    out
+}
+
+fn collect_results(
+    total: usize,
+    mut by_id: HashMap<String, Result<String>>,
+) -> Vec<Result<String>> {
+    (0..total)
+        .map(|idx| {
+            by_id
+                .remove(&custom_id(idx))
+                .unwrap_or_else(|| Err(anyhow!("Batch returned no result for this request")))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProviderAuth;
+
+    fn target(kind: ProviderKind, base_url: &str) -> LlmTarget {
+        LlmTarget {
+            provider_name: "test".to_string(),
+            kind,
+            api_key: Some("k".to_string()),
+            base_url: base_url.to_string(),
+            auth: ProviderAuth::Bearer,
+            model: "m".to_string(),
+            proxy: None,
+            ca_cert: None,
+            oauth: None,
+        }
+    }
+
+    #[test]
+    fn only_official_endpoints_use_batch_apis() {
+        assert_eq!(
+            batch_api(&target(ProviderKind::OpenAI, "https://api.openai.com/v1")),
+            Some(BatchApi::OpenAi)
+        );
+        assert_eq!(
+            batch_api(&target(
+                ProviderKind::Anthropic,
+                "https://api.anthropic.com/v1"
+            )),
+            Some(BatchApi::Anthropic)
+        );
+        assert_eq!(
+            batch_api(&target(
+                ProviderKind::OpenAICompatible,
+                "http://localhost:8080/v1"
+            )),
+            None
+        );
+
+        let jsonl = openai_batch_jsonl(
+            &target(ProviderKind::OpenAI, "https://api.openai.com/v1"),
+            &[BatchRequest {
+                system_prompt: "sys".to_string(),
+                user_input: "go".to_string(),
+            }],
+        )
+        .unwrap();
+        let line: serde_json::Value = serde_json::from_str(jsonl.trim()).unwrap();
+        assert_eq!(line["custom_id"], "dexter-0");
+        assert_eq!(line["body"]["messages"][0]["content"], "sys");
+    }
+
+    #[test]
+    fn results_are_matched_back_by_custom_id() {
+        let openai = r#"{"custom_id":"dexter-1","response":{"status_code":200,"body":{"choices":[{"message":{"role":"assistant","content":"ffmpeg -i b.mov b.mp4"}}]}},"error":null}
+{"custom_id":"dexter-0","response":{"status_code":429,"body":{"error":"slow down"}},"error":null}"#;
+        let results = collect_results(3, parse_openai_results(openai));
+        assert!(results[0].as_ref().unwrap_err().to_string().contains("429"));
+        assert_eq!(results[1].as_ref().unwrap(), "ffmpeg -i b.mov b.mp4");
+        assert!(results[2].is_err());
+
+        let anthropic = r#"{"custom_id":"dexter-0","result":{"type":"succeeded","message":{"content":[{"type":"text","text":" qpdf --decrypt a.pdf b.pdf "}]}}}
+{"custom_id":"dexter-1","result":{"type":"errored","error":{"type":"overloaded_error"}}}"#;
+        let results = collect_results(2, parse_anthropic_results(anthropic));
+        assert_eq!(results[0].as_ref().unwrap(), "qpdf --decrypt a.pdf b.pdf");
+        assert!(results[1]
+            .as_ref()
+            .unwrap_err()
+            .to_string()
+            .contains("errored"));
+    }
+}
@@ -0,0 +1,370 @@
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use super::{
+    api_error, truncate_error, CachePolicy, Completion, CompletionParams, LlmClient, LlmTarget,
+};
+use crate::config::ProviderKind;
+use crate::context::FileContext;
+
+/// File data sent inline with a prompt. Only the native Gemini API takes
+/// it; other targets get the prompt alone, which already describes the
+/// same context in text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attachment {
+    pub name: String,
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+impl Attachment {
+    /// CSV of every file name, for when the prompt only carries a summary
+    /// of a directory larger than `limit` files.
+    pub fn file_list(context: &FileContext, limit: usize) -> Option<Self> {
+        if !context.is_summarized(limit) {
+            return None;
+        }
+        let mut csv = String::from("name\n");
+        for name in &context.files {
+            csv.push_str(&csv_field(name));
+            csv.push('\n');
+        }
+        Some(Self {
+            name: "files.csv".to_string(),
+            mime_type: "text/csv".to_string(),
+            data: csv.into_bytes(),
+        })
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiRequest {
+    system_instruction: GeminiContent,
+    contents: Vec<GeminiContent>,
+    generation_config: GenerationConfig,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GeminiContent {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    role: Option<String>,
+    #[serde(default)]
+    parts: Vec<GeminiPart>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiPart {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    inline_data: Option<InlineData>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InlineData {
+    mime_type: String,
+    data: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerationConfig {
+    temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_output_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_mime_type: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiResponse {
+    #[serde(default)]
+    candidates: Vec<Candidate>,
+    prompt_feedback: Option<PromptFeedback>,
+    usage_metadata: Option<UsageMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UsageMetadata {
+    candidates_token_count: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Candidate {
+    content: Option<GeminiContent>,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PromptFeedback {
+    block_reason: Option<String>,
+}
+
+/// Gemini targets on Google's endpoint use `generateContent` directly; a
+/// Gemini-kind target pointed elsewhere stays on the OpenAI-compatible path.
+pub(super) fn uses_native_gemini(target: &LlmTarget) -> bool {
+    target.kind == ProviderKind::Gemini
+        && target
+            .base_url
+            .contains("generativelanguage.googleapis.com")
+}
+
+/// The API root without the `/openai` compatibility suffix.
+pub(super) fn native_base_url(base_url: &str) -> &str {
+    let base = base_url.trim_end_matches('/');
+    base.strip_suffix("/openai").unwrap_or(base)
+}
+
+fn build_request(
+    system_prompt: &str,
+    user_input: &str,
+    attachments: &[Attachment],
+    params: CompletionParams,
+) -> GeminiRequest {
+    let mut parts = vec![GeminiPart {
+        text: Some(user_input.to_string()),
+        ..GeminiPart::default()
+    }];
+    for attachment in attachments {
+        parts.push(GeminiPart {
+            text: Some(format!("Attached file: {}", attachment.name)),
+            ..GeminiPart::default()
+        });
+        parts.push(GeminiPart {
+            inline_data: Some(InlineData {
+                mime_type: attachment.mime_type.clone(),
+                data: base64::engine::general_purpose::STANDARD.encode(&attachment.data),
+            }),
+            ..GeminiPart::default()
+        });
+    }
+    GeminiRequest {
+        system_instruction: GeminiContent {
+            role: None,
+            parts: vec![GeminiPart {
+                text: Some(system_prompt.to_string()),
+                ..GeminiPart::default()
+            }],
+        },
+        contents: vec![GeminiContent {
+            role: Some("user".to_string()),
+            parts,
+        }],
+        generation_config: GenerationConfig {
+            temperature: params.temperature,
+            max_output_tokens: params.max_tokens,
+            response_mime_type: params.json.then(|| "application/json".to_string()),
+        },
+    }
+}
+
+fn parse_response(text: &str) -> Result<Completion> {
+    let parsed: GeminiResponse = serde_json::from_str(text).map_err(|e| {
+        anyhow!(
+            "Failed to parse LLM response: {} | Raw response snippet: {}",
+            e,
+            truncate_error(text)
+        )
+    })?;
+    if let Some(reason) = parsed.prompt_feedback.and_then(|f| f.block_reason) {
+        return Err(anyhow!(
+            "content filter triggered by current provider/model ({}); trying fallback",
+            reason
+        ));
+    }
+    let candidate = parsed
+        .candidates
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("No candidates returned from Gemini"))?;
+    let chunks: Vec<String> = candidate
+        .content
+        .map(|content| content.parts)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|part| part.text)
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+        .collect();
+    if !chunks.is_empty() {
+        return Ok(Completion {
+            content: chunks.join("\n\n"),
+            output_tokens: parsed
+                .usage_metadata
+                .and_then(|usage| usage.candidates_token_count),
+        });
+    }
+    match candidate.finish_reason {
+        Some(reason) if matches!(reason.as_str(), "SAFETY" | "PROHIBITED_CONTENT") => Err(anyhow!(
+            "content filter triggered by current provider/model; trying fallback"
+        )),
+        Some(reason) => Err(anyhow!("LLM stopped execution. Reason: {}", reason)),
+        None => Err(anyhow!("No text content in Gemini response")),
+    }
+}
+
+impl LlmClient {
+    pub(super) async fn execute_gemini_completion_for_target(
+        &self,
+        target: &LlmTarget,
+        system_prompt: &str,
+        user_input: &str,
+        attachments: &[Attachment],
+        cache_policy: CachePolicy,
+        params: CompletionParams,
+    ) -> Result<Completion> {
+        let key = target
+            .api_key
+            .as_ref()
+            .ok_or_else(|| anyhow!("Missing API key for provider {}", target.provider_name))?;
+        let url = format!(
+            "{}/models/{}:generateContent",
+            native_base_url(&target.base_url),
+            target.model.trim_start_matches("models/")
+        );
+        let cache_key = gemini_cache_key(target, system_prompt, user_input, attachments, params);
+
+        if cache_policy == CachePolicy::Normal {
+            if let Some(cached) = self.cache.read().await.get(&cache_key).cloned() {
+                return Ok(Completion::cached(cached));
+            }
+        }
+
+        let request_body = build_request(system_prompt, user_input, attachments, params);
+        let response = self
+            .http_for(target)?
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .header("x-goog-api-key", key)
+            .json(&request_body)
+            .send()
+            .await?;
+        let status = response.status();
+        let text = response.text().await?;
+        if !status.is_success() {
+            return Err(api_error(status, &text));
+        }
+        self.record_spend(target, [system_prompt, user_input]).await;
+
+        let reply = parse_response(&text)?;
+        if cache_policy == CachePolicy::Normal {
+            self.store_in_cache(cache_key, &reply.content).await;
+        }
+        Ok(reply)
+    }
+}
+
+fn gemini_cache_key(
+    target: &LlmTarget,
+    system_prompt: &str,
+    user_input: &str,
+    attachments: &[Attachment],
+    params: CompletionParams,
+) -> String {
+    let mut hasher = DefaultHasher::new();
+    for attachment in attachments {
+        attachment.name.hash(&mut hasher);
+        attachment.mime_type.hash(&mut hasher);
+        attachment.data.hash(&mut hasher);
+    }
+    let payload = serde_json::json!({
+        "system": system_prompt,
+        "user": user_input,
+        "attachments": format!("{}:{:x}", attachments.len(), hasher.finish()),
+        "temperature": params.temperature,
+        "max_tokens": params.max_tokens,
+        "json": params.json,
+    });
+    format!(
+        "{}|{}|{}|gemini|{}",
+        target.provider_name, target.base_url, target.model, payload
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::ContextScanner;
+
+    #[test]
+    fn request_carries_system_instruction_and_inline_csv() {
+        let context = ContextScanner::from_listing(
+            (0..25)
+                .map(|i| format!("clip {}, take {}.mkv", i, i))
+                .collect(),
+            0,
+        );
+        let attachment = Attachment::file_list(&context, 20).unwrap();
+        let request = build_request(
+            "system",
+            "user",
+            &[attachment],
+            CompletionParams {
+                temperature: 0.1,
+                max_tokens: Some(64),
+                json: true,
+            },
+        );
+        let body = serde_json::to_value(&request).unwrap();
+        assert_eq!(body["systemInstruction"]["parts"][0]["text"], "system");
+        assert_eq!(body["contents"][0]["role"], "user");
+        assert_eq!(body["contents"][0]["parts"][0]["text"], "user");
+        assert_eq!(body["generationConfig"]["maxOutputTokens"], 64);
+        assert_eq!(
+            body["generationConfig"]["responseMimeType"],
+            "application/json"
+        );
+        let inline = &body["contents"][0]["parts"][2]["inlineData"];
+        assert_eq!(inline["mimeType"], "text/csv");
+        let csv = base64::engine::general_purpose::STANDARD
+            .decode(inline["data"].as_str().unwrap())
+            .unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.starts_with("name\n\"clip 0, take 0.mkv\"\n"));
+
+        let small = ContextScanner::from_listing(vec!["a.mkv".to_string()], 0);
+        assert_eq!(Attachment::file_list(&small, 20), None);
+    }
+
+    #[test]
+    fn response_text_parts_are_joined_and_blocks_reported() {
+        let ok = r#"{"candidates":[{"content":{"role":"model","parts":[{"text":" ffmpeg -i a.mkv a.mp4 "}]},"finishReason":"STOP"}],"usageMetadata":{"promptTokenCount":30,"candidatesTokenCount":9}}"#;
+        assert_eq!(
+            parse_response(ok).unwrap(),
+            Completion {
+                content: "ffmpeg -i a.mkv a.mp4".to_string(),
+                output_tokens: Some(9),
+            }
+        );
+
+        let blocked = r#"{"candidates":[],"promptFeedback":{"blockReason":"SAFETY"}}"#;
+        assert!(parse_response(blocked)
+            .unwrap_err()
+            .to_string()
+            .contains("content filter"));
+
+        let empty = r#"{"candidates":[{"content":{"parts":[]},"finishReason":"MAX_TOKENS"}]}"#;
+        assert!(parse_response(empty)
+            .unwrap_err()
+            .to_string()
+            .contains("MAX_TOKENS"));
+    }
+}
@@ -0,0 +1,275 @@
+use anyhow::{anyhow, Result};
+use serde::de::DeserializeOwned;
+
+use super::{
+    executor_completion_params, router_completion_params, truncate_error, Attachment, CachePolicy,
+    CompletionParams, LlmClient,
+};
+
+/// Times a reply that doesn't fit its schema is sent back for repair.
+const MAX_REPAIRS: usize = 1;
+
+/// A reply the model must give as one JSON object.
+pub trait JsonReply: DeserializeOwned {
+    /// What the reply is, for error messages ("router decision").
+    const KIND: &'static str;
+
+    /// JSON Schema of the object, shown to the model when it has to repair
+    /// a reply.
+    fn schema() -> serde_json::Value;
+
+    /// Checks serde can't express, such as a value range.
+    fn validate(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A parsed reply with the text it came from.
+#[derive(Debug, Clone)]
+pub struct JsonCompletion<T> {
+    pub value: T,
+    pub raw: String,
+}
+
+/// Parses `reply` as `T`, tolerating a Markdown fence or prose around the
+/// object.
+pub fn parse_json_reply<T: JsonReply>(reply: &str) -> Result<T> {
+    let clean = reply
+        .trim()
+        .trim_start_matches("```json")
+        .trim_start_matches("```")
+        .trim_end_matches("```")
+        .trim();
+
+    let parsed = match serde_json::from_str::<T>(clean) {
+        Ok(value) => Ok(value),
+        Err(e) => match extract_first_json_object(reply) {
+            Some(candidate) => serde_json::from_str::<T>(&candidate).map_err(|_| e),
+            None => Err(e),
+        },
+    };
+    let value = parsed.map_err(|e| {
+        anyhow!(
+            "Failed to parse {} JSON ({}). Raw response snippet: {}",
+            T::KIND,
+            e,
+            truncate_error(reply)
+        )
+    })?;
+    value
+        .validate()
+        .map_err(|e| anyhow!("Invalid {}: {}", T::KIND, e))?;
+    Ok(value)
+}
+
+pub(crate) fn extract_first_json_object(input: &str) -> Option<String> {
+    let mut start = None;
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (idx, ch) in input.char_indices() {
+        if in_string {
+            if escaped {
+                escaped = false;
+                continue;
+            }
+            if ch == '\\' {
+                escaped = true;
+                continue;
+            }
+            if ch == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+
+        match ch {
+            '"' => in_string = true,
+            '{' => {
+                if depth == 0 {
+                    start = Some(idx);
+                }
+                depth += 1;
+            }
+            '}' => {
+                if depth == 0 {
+                    continue;
+                }
+                depth -= 1;
+                if depth == 0 {
+                    if let Some(s) = start {
+                        return Some(input[s..=idx].to_string());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Follow-up turn asking the model to fix `reply`.
+fn repair_prompt<T: JsonReply>(user_input: &str, reply: &str, error: &anyhow::Error) -> String {
+    format!(
+        "{}\n\n### Your previous reply could not be used:\n{}\n\n### Previous reply:\n{}\n\n\
+         Reply again with only one JSON object matching this schema, without Markdown or \
+         commentary:\n{}",
+        user_input,
+        error,
+        truncate_error(reply),
+        serde_json::to_string_pretty(&T::schema()).unwrap_or_default()
+    )
+}
+
+impl LlmClient {
+    /// Router-style completion that must come back as a `T`.
+    pub async fn json_completion<T: JsonReply>(
+        &self,
+        system_prompt: &str,
+        user_input: &str,
+    ) -> Result<JsonCompletion<T>> {
+        let params = json_params(router_completion_params());
+        let reply = self
+            .completion_with_policy_and_params(
+                system_prompt,
+                user_input,
+                &[],
+                CachePolicy::Normal,
+                params,
+            )
+            .await?;
+        self.finish_json(system_prompt, user_input, &[], params, reply)
+            .await
+    }
+
+    /// Executor-style completion with file data inline that must come back
+    /// as a `T`.
+    pub async fn json_completion_with_attachments<T: JsonReply>(
+        &self,
+        system_prompt: &str,
+        user_input: &str,
+        attachments: &[Attachment],
+        cache_policy: CachePolicy,
+    ) -> Result<JsonCompletion<T>> {
+        let params = json_params(executor_completion_params());
+        let reply = self
+            .completion_with_policy_and_params(
+                system_prompt,
+                user_input,
+                attachments,
+                cache_policy,
+                params,
+            )
+            .await?;
+        self.finish_json(system_prompt, user_input, attachments, params, reply)
+            .await
+    }
+
+    /// `json_completion` raced across the first two targets; a reply that
+    /// doesn't parse loses the race.
+    pub async fn json_race_completion<T: JsonReply>(
+        &self,
+        system_prompt: &str,
+        user_input: &str,
+    ) -> Result<JsonCompletion<T>> {
+        let params = json_params(router_completion_params());
+        let reply = self
+            .race_completion_with_params(system_prompt, user_input, params, |text| {
+                parse_json_reply::<T>(text).is_ok()
+            })
+            .await?;
+        self.finish_json(system_prompt, user_input, &[], params, reply)
+            .await
+    }
+
+    /// Parses `reply`, sending it back with the error and the schema while
+    /// it doesn't fit.
+    async fn finish_json<T: JsonReply>(
+        &self,
+        system_prompt: &str,
+        user_input: &str,
+        attachments: &[Attachment],
+        params: CompletionParams,
+        mut reply: String,
+    ) -> Result<JsonCompletion<T>> {
+        let mut repairs = 0;
+        loop {
+            let error = match parse_json_reply::<T>(&reply) {
+                Ok(value) => return Ok(JsonCompletion { value, raw: reply }),
+                Err(e) => e,
+            };
+            self.forget_cached(&reply).await;
+            if repairs == MAX_REPAIRS {
+                return Err(error);
+            }
+            repairs += 1;
+            tracing::debug!(kind = T::KIND, error = %error, "asking the model to repair its reply");
+            reply = self
+                .completion_with_policy_and_params(
+                    system_prompt,
+                    &repair_prompt::<T>(user_input, &reply, &error),
+                    attachments,
+                    CachePolicy::Bypass,
+                    params,
+                )
+                .await?;
+        }
+    }
+}
+
+fn json_params(params: CompletionParams) -> CompletionParams {
+    CompletionParams {
+        json: true,
+        ..params
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    struct Pick {
+        choice: u8,
+    }
+
+    impl JsonReply for Pick {
+        const KIND: &'static str = "pick";
+
+        fn schema() -> serde_json::Value {
+            serde_json::json!({
+                "type": "object",
+                "required": ["choice"],
+                "properties": { "choice": { "type": "integer", "maximum": 3 } }
+            })
+        }
+
+        fn validate(&self) -> Result<()> {
+            if self.choice > 3 {
+                return Err(anyhow!("choice {} is out of range", self.choice));
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn parses_fenced_or_embedded_objects_and_validates_them() {
+        let fenced = "```json\n{\"choice\": 2}\n```";
+        assert_eq!(parse_json_reply::<Pick>(fenced).unwrap().choice, 2);
+        let noisy = "Sure! {\"choice\": 1, \"note\": \"a } in a string\"} Thanks.";
+        assert_eq!(parse_json_reply::<Pick>(noisy).unwrap().choice, 1);
+
+        let err = parse_json_reply::<Pick>("{\"choice\": 7}").unwrap_err();
+        assert_eq!(err.to_string(), "Invalid pick: choice 7 is out of range");
+        let err = parse_json_reply::<Pick>(&"x".repeat(500)).unwrap_err();
+        assert!(err.to_string().starts_with("Failed to parse pick JSON"));
+        assert!(err.to_string().ends_with("..."));
+
+        let prompt = repair_prompt::<Pick>("Pick one.", "{}", &err);
+        assert!(prompt.starts_with("Pick one.\n"));
+        assert!(prompt.contains("\"maximum\": 3"));
+    }
+}
@@ -0,0 +1,221 @@
+//! Why a provider's model list could not be fetched: the HTTP status, what
+//! the provider answered and a hint at the usual cause (a rejected key, a
+//! wrong base URL, a region block), for the setup wizard to show.
+
+use reqwest::{RequestBuilder, StatusCode};
+use std::fmt;
+
+use super::{parse_model_list, truncate_error, LlmTarget};
+use crate::config::ProviderKind;
+
+/// Words providers use when refusing a request for where it comes from,
+/// including Anthropic's bare "Request not allowed" and the Chinese
+/// wording of Moonshot and DeepSeek.
+const REGION_MARKERS: &[&str] = &[
+    "unsupported_country",
+    "unsupported country",
+    "location is not supported",
+    "not available in your",
+    "request not allowed",
+    "region",
+    "country",
+    "territory",
+    "geograph",
+    "sanction",
+    "地区",
+    "国家",
+    "地區",
+    "國家",
+];
+
+/// Words providers use when refusing a key.
+const KEY_MARKERS: &[&str] = &[
+    "api key",
+    "api_key",
+    "apikey",
+    "invalid key",
+    "unauthorized",
+];
+
+const BAD_KEY: &str =
+    "The API key was rejected. Check it was pasted whole, is still active and belongs to this provider.";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelListError {
+    pub provider: String,
+    pub kind: ProviderKind,
+    /// The endpoint asked, without the API key.
+    pub url: String,
+    /// `None` when no response came back.
+    pub status: Option<u16>,
+    /// The provider's answer, or why there was none.
+    pub body: String,
+}
+
+impl ModelListError {
+    fn new(target: &LlmTarget, url: &str, status: Option<u16>, body: String) -> Self {
+        Self {
+            provider: target.provider_name.clone(),
+            kind: target.kind,
+            url: url.to_string(),
+            status,
+            body,
+        }
+    }
+
+    /// `401 Unauthorized`, or `no response`.
+    pub fn status_line(&self) -> String {
+        match self.status {
+            Some(code) => {
+                let reason = StatusCode::from_u16(code)
+                    .ok()
+                    .and_then(|status| status.canonical_reason());
+                match reason {
+                    Some(reason) => format!("{} {}", code, reason),
+                    None => code.to_string(),
+                }
+            }
+            None => "no response".to_string(),
+        }
+    }
+
+    /// The usual cause of this failure and what to change.
+    pub fn hint(&self) -> Option<&'static str> {
+        let body = self.body.to_lowercase();
+        let mentions = |markers: &[&str]| markers.iter().any(|marker| body.contains(marker));
+        let hint = match self.status {
+            None if self.kind == ProviderKind::Ollama => {
+                "Ollama isn't answering. Start it with `ollama serve`, or check its base_url in config.toml."
+            }
+            None => {
+                "Nothing answered. Check the base_url in config.toml, the network, and the proxy and CA certificate on step 2."
+            }
+            Some(400 | 403 | 451) if mentions(REGION_MARKERS) => {
+                "The provider refuses requests from your region. Set a proxy in a supported region on step 2, or use another provider."
+            }
+            Some(401 | 403) => BAD_KEY,
+            Some(400) if mentions(KEY_MARKERS) => BAD_KEY,
+            Some(404 | 405) => {
+                "No model list at this URL. Check the base_url in config.toml; OpenAI-compatible APIs usually end in /v1."
+            }
+            Some(200..=299) => {
+                "The URL answered, but not with a model list. Check the base_url in config.toml."
+            }
+            Some(429) => "Rate limited or out of quota. Retry in a moment, or check the account's billing.",
+            Some(500..=599) => "The provider is having trouble. Retry in a minute.",
+            Some(_) => return None,
+        };
+        Some(hint)
+    }
+}
+
+impl fmt::Display for ModelListError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.status {
+            Some(_) => write!(
+                f,
+                "status {} from {}: {}",
+                self.status_line(),
+                self.url,
+                self.body
+            ),
+            None => write!(f, "{}: {}", self.url, self.body),
+        }
+    }
+}
+
+impl std::error::Error for ModelListError {}
+
+/// Sends `request` for `target`'s model list; `url` is the endpoint as shown.
+pub(super) async fn fetch_model_list(
+    target: &LlmTarget,
+    url: &str,
+    request: RequestBuilder,
+) -> Result<Vec<String>, ModelListError> {
+    let unreachable = |e: reqwest::Error| ModelListError::new(target, url, None, describe(e));
+    let response = request.send().await.map_err(unreachable)?;
+    let status = response.status();
+    let text = response.text().await.map_err(unreachable)?;
+    if !status.is_success() {
+        return Err(ModelListError::new(
+            target,
+            url,
+            Some(status.as_u16()),
+            truncate_error(&text),
+        ));
+    }
+    parse_model_list(&text)
+        .map_err(|e| ModelListError::new(target, url, Some(status.as_u16()), e.to_string()))
+}
+
+/// `e` with its causes, leaving out the URL, which can carry the key.
+fn describe(e: reqwest::Error) -> String {
+    let e = e.without_url();
+    let mut text = e.to_string();
+    let mut source = std::error::Error::source(&e);
+    while let Some(cause) = source {
+        text.push_str(": ");
+        text.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure(kind: ProviderKind, status: Option<u16>, body: &str) -> ModelListError {
+        ModelListError {
+            provider: "test".to_string(),
+            kind,
+            url: "https://api.example.com/v1/models".to_string(),
+            status,
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn hints_at_the_usual_cause() {
+        let hint = |status, body| failure(ProviderKind::OpenAI, status, body).hint();
+        let bad_key = hint(Some(401), "{\"error\":{\"code\":\"invalid_api_key\"}}").unwrap();
+        assert!(bad_key.contains("API key"));
+        assert_eq!(
+            hint(Some(400), "API key not valid. Please pass a valid API key."),
+            Some(bad_key)
+        );
+        let region = hint(
+            Some(403),
+            "{\"error\":{\"code\":\"unsupported_country_region_territory\"}}",
+        )
+        .unwrap();
+        assert!(region.contains("region"));
+        assert_eq!(
+            hint(Some(400), "User location is not supported for the API use."),
+            Some(region)
+        );
+        for body in [
+            "{\"type\":\"forbidden\",\"message\":\"Request not allowed\"}",
+            "This service is not available in your territory.",
+            "Unavailable due to geographic restrictions",
+            "当前地区暂不支持该服务",
+        ] {
+            assert_eq!(hint(Some(403), body), Some(region), "{body}");
+        }
+        assert!(hint(Some(404), "Not Found").unwrap().contains("base_url"));
+        assert!(hint(Some(200), "<html>").unwrap().contains("base_url"));
+        assert!(hint(Some(429), "").unwrap().contains("quota"));
+        assert_eq!(hint(Some(418), ""), None);
+        assert!(failure(ProviderKind::Ollama, None, "connection refused")
+            .hint()
+            .unwrap()
+            .contains("ollama serve"));
+
+        let e = failure(ProviderKind::OpenAI, Some(401), "bad key");
+        assert_eq!(e.status_line(), "401 Unauthorized");
+        assert_eq!(
+            e.to_string(),
+            "status 401 Unauthorized from https://api.example.com/v1/models: bad key"
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! Model lists fetched from each provider, kept between sessions so the
+//! settings panel doesn't call slow or rate-limited listing endpoints every
+//! time it opens.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::config::ProviderConfig;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CachedModels {
+    pub fetched_at: DateTime<Utc>,
+    pub models: Vec<String>,
+}
+
+impl CachedModels {
+    pub fn age(&self) -> String {
+        format_age(self.fetched_at, Utc::now())
+    }
+}
+
+/// `<cache dir>/dexter/models.json`, keyed by provider, endpoint and a
+/// hash of the API key, since accounts on one endpoint can see different
+/// models. API keys are not stored.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelCache {
+    #[serde(default)]
+    providers: HashMap<String, CachedModels>,
+}
+
+impl ModelCache {
+    pub fn path() -> Option<PathBuf> {
+        crate::paths::cache_dir().map(|dir| dir.join("dexter").join("models.json"))
+    }
+
+    /// The stored lists; a missing or unreadable file is an empty cache.
+    pub fn load() -> Self {
+        Self::path()
+            .map(|path| Self::load_from(&path))
+            .unwrap_or_default()
+    }
+
+    pub fn load_from(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        match Self::path() {
+            Some(path) => self.save_to(&path),
+            None => Ok(()),
+        }
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, provider: &ProviderConfig) -> Option<&CachedModels> {
+        self.providers.get(&cache_key(provider))
+    }
+
+    /// Stores `models` as fetched now and returns the entry.
+    pub fn insert(&mut self, provider: &ProviderConfig, models: Vec<String>) -> &CachedModels {
+        let key = cache_key(provider);
+        self.providers.insert(
+            key.clone(),
+            CachedModels {
+                fetched_at: Utc::now(),
+                models,
+            },
+        );
+        &self.providers[&key]
+    }
+}
+
+fn cache_key(provider: &ProviderConfig) -> String {
+    let account = provider
+        .api_key
+        .as_deref()
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(|key| {
+            Sha256::digest(key.as_bytes())[..8]
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect::<String>()
+        })
+        .unwrap_or_default();
+    format!(
+        "{:?}|{}|{}|{}",
+        provider.kind,
+        provider.display_name(),
+        provider.base_url.trim().trim_end_matches('/'),
+        account
+    )
+}
+
+/// How long ago `at` was, coarsely: `just now`, `12m ago`, `3h ago`,
+/// `5d ago`.
+pub fn format_age(at: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let minutes = (now - at).num_minutes().max(0);
+    match minutes {
+        0 => "just now".to_string(),
+        1..=59 => format!("{}m ago", minutes),
+        60..=1439 => format!("{}h ago", minutes / 60),
+        _ => format!("{}d ago", minutes / 1440),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProviderKind;
+    use chrono::Duration;
+
+    #[test]
+    fn keeps_lists_per_endpoint_across_loads() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("models.json");
+        let ollama = ProviderConfig::builtin(ProviderKind::Ollama, None);
+        let remote_ollama = ProviderConfig {
+            base_url: "http://gpu-box:11434/v1/".to_string(),
+            ..ollama.clone()
+        };
+
+        let mut cache = ModelCache::load_from(&path);
+        assert!(cache.get(&ollama).is_none());
+        cache.insert(&remote_ollama, vec!["qwen3:8b".to_string()]);
+        cache.save_to(&path).unwrap();
+
+        let cache = ModelCache::load_from(&path);
+        assert!(cache.get(&ollama).is_none());
+        assert_eq!(cache.get(&remote_ollama).unwrap().models, ["qwen3:8b"]);
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("api_key"));
+    }
+
+    #[test]
+    fn accounts_on_one_endpoint_get_their_own_lists() {
+        let work = ProviderConfig::builtin(ProviderKind::OpenAI, Some("sk-work".to_string()));
+        let personal = ProviderConfig {
+            api_key: Some("sk-personal".to_string()),
+            ..work.clone()
+        };
+        let mut cache = ModelCache::default();
+        cache.insert(&work, vec!["gpt-4.1".to_string()]);
+        assert!(cache.get(&personal).is_none());
+        assert!(!cache_key(&work).contains("sk-work"));
+    }
+
+    #[test]
+    fn ages_are_coarse() {
+        let now = Utc::now();
+        assert_eq!(format_age(now, now), "just now");
+        assert_eq!(format_age(now - Duration::minutes(12), now), "12m ago");
+        assert_eq!(format_age(now - Duration::hours(3), now), "3h ago");
+        assert_eq!(format_age(now - Duration::days(5), now), "5d ago");
+    }
+}
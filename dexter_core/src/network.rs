@@ -0,0 +1,131 @@
+use dexter_plugins::command_exec::preview_offline;
+use dexter_plugins::{Capability, Plugin};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::process::Command;
+
+/// What to do with downloads while the connection is metered (a phone
+/// hotspot, a capped mobile plan).
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MeteredAction {
+    Allow,
+    /// Log a warning and run.
+    #[default]
+    Warn,
+    /// Hold the command until the user confirms it a second time.
+    Block,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MeteredPolicy {
+    #[serde(default)]
+    pub action: MeteredAction,
+    /// Skips detection: `true` always treats the connection as metered,
+    /// `false` never does. Unset asks NetworkManager, which only exists on
+    /// Linux; elsewhere the connection counts as unmetered.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metered: Option<bool>,
+}
+
+/// The outcome of `MeteredPolicy::check` for one command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeteredVerdict {
+    Clear,
+    Warn,
+    Block,
+}
+
+impl MeteredPolicy {
+    /// Only plugins that download are held back; detection runs only for
+    /// them.
+    pub fn check(&self, plugin: &dyn Plugin) -> MeteredVerdict {
+        if self.action == MeteredAction::Allow
+            || !plugin.capabilities().contains(&Capability::Downloading)
+            || !self.is_metered()
+        {
+            return MeteredVerdict::Clear;
+        }
+        match self.action {
+            MeteredAction::Block => MeteredVerdict::Block,
+            _ => MeteredVerdict::Warn,
+        }
+    }
+
+    /// Runs `preview`, a dry run of a `plugin` command, without the network
+    /// fetches that only enrich it whenever `check` doesn't clear the plugin.
+    pub async fn preview<F: Future>(&self, plugin: &dyn Plugin, preview: F) -> F::Output {
+        let offline = self.check(plugin) != MeteredVerdict::Clear;
+        preview_offline(offline, preview).await
+    }
+
+    pub fn is_metered(&self) -> bool {
+        self.metered.unwrap_or_else(detect_metered)
+    }
+}
+
+/// Asks NetworkManager over D-Bus whether the primary connection is
+/// metered. Anything that fails counts as unmetered.
+pub fn detect_metered() -> bool {
+    if !cfg!(target_os = "linux") {
+        return false;
+    }
+    Command::new("busctl")
+        .args([
+            "get-property",
+            "org.freedesktop.NetworkManager",
+            "/org/freedesktop/NetworkManager",
+            "org.freedesktop.NetworkManager",
+            "Metered",
+        ])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .is_some_and(|output| parse_nm_metered(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// `busctl` prints the NMMetered value as `u 1`: 1 is yes and 3 a guessed
+/// yes; 0, 2 and 4 are unknown, no and a guessed no.
+fn parse_nm_metered(output: &str) -> bool {
+    matches!(
+        output.trim().strip_prefix("u ").map(str::trim),
+        Some("1") | Some("3")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dexter_plugins::{DownloadPlugin, F2Plugin};
+
+    #[test]
+    fn only_downloads_on_metered_connections_are_held() {
+        assert!(parse_nm_metered("u 1\n"));
+        assert!(parse_nm_metered("u 3"));
+        assert!(!parse_nm_metered("u 4"));
+        assert!(!parse_nm_metered(""));
+
+        let block = MeteredPolicy {
+            action: MeteredAction::Block,
+            metered: Some(true),
+        };
+        assert_eq!(
+            block.check(&DownloadPlugin::default()),
+            MeteredVerdict::Block
+        );
+        assert_eq!(block.check(&F2Plugin::default()), MeteredVerdict::Clear);
+        let warn = MeteredPolicy {
+            metered: Some(true),
+            ..MeteredPolicy::default()
+        };
+        assert_eq!(warn.check(&DownloadPlugin::default()), MeteredVerdict::Warn);
+        let unmetered = MeteredPolicy {
+            metered: Some(false),
+            ..block
+        };
+        assert_eq!(
+            unmetered.check(&DownloadPlugin::default()),
+            MeteredVerdict::Clear
+        );
+    }
+}
@@ -0,0 +1,397 @@
+//! Short-lived OAuth tokens for providers behind an enterprise gateway:
+//! the device code sign-in (RFC 8628), a token store next to the config,
+//! and the refresh that keeps the access token current.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::config::write_private;
+use crate::executor::lock_store;
+use crate::llm::truncate_error;
+
+/// Tokens this close to expiry are refreshed before they are sent.
+const EXPIRY_MARGIN_SECS: i64 = 60;
+const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// A provider's `[providers.oauth]` table: where its gateway signs users
+/// in and hands out tokens.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct OAuthSettings {
+    /// Device authorization endpoint, which hands out the code to enter.
+    pub device_url: String,
+    /// Token endpoint, for the sign-in's poll and for refreshes.
+    pub token_url: String,
+    pub client_id: String,
+    /// Space-separated scopes, e.g. `openid offline_access`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+}
+
+impl OAuthSettings {
+    /// Where this gateway's tokens sit in `tokens.json`: its token endpoint
+    /// and client, which stay put when the provider is renamed.
+    pub fn store_key(&self) -> String {
+        format!("{} {}", self.token_url, self.client_id)
+    }
+}
+
+/// One provider's tokens as stored in `tokens.json`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct OAuthTokens {
+    pub access_token: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    /// `None` when the gateway didn't say; such tokens are used until a
+    /// request is refused.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl OAuthTokens {
+    /// Whether the access token still has `EXPIRY_MARGIN_SECS` to go.
+    pub fn is_fresh(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at
+            .is_none_or(|at| at - ChronoDuration::seconds(EXPIRY_MARGIN_SECS) > now)
+    }
+}
+
+/// The token endpoint's answer, successful or not.
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+    expires_in: Option<i64>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+impl TokenResponse {
+    fn into_tokens(self, now: DateTime<Utc>) -> Option<OAuthTokens> {
+        Some(OAuthTokens {
+            access_token: self.access_token?,
+            refresh_token: self.refresh_token,
+            expires_at: self
+                .expires_in
+                .map(|secs| now + ChronoDuration::seconds(secs)),
+        })
+    }
+
+    fn error_message(&self) -> String {
+        match (&self.error, &self.error_description) {
+            (Some(error), Some(description)) => format!("{}: {}", error, description),
+            (Some(error), None) => error.clone(),
+            _ => "no access token in the answer".to_string(),
+        }
+    }
+}
+
+/// The code the user enters at `verification_uri` to sign in.
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeviceCode {
+    device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    /// The URI with the code filled in, when the gateway offers one.
+    #[serde(default)]
+    pub verification_uri_complete: Option<String>,
+    pub expires_in: u64,
+    /// Seconds between polls.
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+}
+
+fn default_interval() -> u64 {
+    5
+}
+
+/// Asks the gateway for a device code, the first step of signing in.
+pub async fn start_device_flow(http: &Client, settings: &OAuthSettings) -> Result<DeviceCode> {
+    let mut form = vec![("client_id", settings.client_id.as_str())];
+    if let Some(scope) = settings.scope.as_deref() {
+        form.push(("scope", scope));
+    }
+    let response = http
+        .post(&settings.device_url)
+        .form(&form)
+        .send()
+        .await
+        .with_context(|| format!("Could not reach {}", settings.device_url))?;
+    let status = response.status();
+    let text = response.text().await?;
+    if !status.is_success() {
+        return Err(anyhow!(
+            "Device authorization failed (status {}): {}",
+            status,
+            truncate_error(&text)
+        ));
+    }
+    serde_json::from_str(&text).map_err(|e| {
+        anyhow!(
+            "Failed to parse the device code: {} | Raw response snippet: {}",
+            e,
+            truncate_error(&text)
+        )
+    })
+}
+
+/// Polls the token endpoint until the user has signed in with `code`,
+/// then stores the tokens for `provider`'s gateway.
+pub async fn finish_device_flow(
+    http: &Client,
+    provider: &str,
+    settings: &OAuthSettings,
+    code: &DeviceCode,
+) -> Result<OAuthTokens> {
+    let deadline = Utc::now() + ChronoDuration::seconds(code.expires_in as i64);
+    let mut interval = code.interval.max(1);
+    loop {
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+        if Utc::now() > deadline {
+            return Err(anyhow!("The sign-in code expired. Start again."));
+        }
+        let answer = request_tokens(
+            http,
+            settings,
+            &[
+                ("grant_type", DEVICE_CODE_GRANT),
+                ("device_code", code.device_code.as_str()),
+                ("client_id", settings.client_id.as_str()),
+            ],
+        )
+        .await?;
+        match answer.error.as_deref() {
+            Some("authorization_pending") => continue,
+            Some("slow_down") => interval += 5,
+            Some(_) => return Err(anyhow!("Sign-in failed: {}", answer.error_message())),
+            None => {
+                let message = answer.error_message();
+                let tokens = answer
+                    .into_tokens(Utc::now())
+                    .ok_or_else(|| anyhow!("Sign-in failed: {}", message))?;
+                let _lock = TokenStore::lock().await?;
+                TokenStore::load()
+                    .insert(&settings.store_key(), tokens.clone())
+                    .await?;
+                tracing::debug!(provider, "signed in with OAuth");
+                return Ok(tokens);
+            }
+        }
+    }
+}
+
+/// `provider`'s access token, refreshed first when it is about to expire.
+pub async fn access_token(
+    http: &Client,
+    provider: &str,
+    settings: &OAuthSettings,
+) -> Result<String> {
+    let tokens = TokenStore::load()
+        .get(&settings.store_key())
+        .ok_or_else(|| not_signed_in(provider))?;
+    if tokens.is_fresh(Utc::now()) {
+        return Ok(tokens.access_token);
+    }
+    refresh_access_token(http, provider, settings, &tokens.access_token).await
+}
+
+/// Trades `provider`'s refresh token for a new access token and stores
+/// both, for a `stale` token that expired or that the provider refused.
+/// The store stays locked throughout and is read again first: when another
+/// Dexter has refreshed in the meantime, its token is used rather than
+/// spending the refresh token twice.
+pub async fn refresh_access_token(
+    http: &Client,
+    provider: &str,
+    settings: &OAuthSettings,
+    stale: &str,
+) -> Result<String> {
+    let key = settings.store_key();
+    let _lock = TokenStore::lock().await?;
+    let mut store = TokenStore::load();
+    let old = store.get(&key).ok_or_else(|| not_signed_in(provider))?;
+    if old.access_token != stale && old.is_fresh(Utc::now()) {
+        return Ok(old.access_token);
+    }
+    let refresh_token = old.refresh_token.clone().ok_or_else(|| {
+        anyhow!(
+            "The token for {} expired and can't be refreshed. Sign in again from the settings panel.",
+            provider
+        )
+    })?;
+    let answer = request_tokens(
+        http,
+        settings,
+        &[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token.as_str()),
+            ("client_id", settings.client_id.as_str()),
+        ],
+    )
+    .await?;
+    let message = answer.error_message();
+    let mut tokens = answer.into_tokens(Utc::now()).ok_or_else(|| {
+        anyhow!(
+            "Refreshing the token for {} failed: {}. Sign in again from the settings panel.",
+            provider,
+            message
+        )
+    })?;
+    // Gateways that don't rotate refresh tokens leave it out.
+    if tokens.refresh_token.is_none() {
+        tokens.refresh_token = Some(refresh_token);
+    }
+    store.insert(&key, tokens.clone()).await?;
+    tracing::debug!(provider, "refreshed OAuth token");
+    Ok(tokens.access_token)
+}
+
+/// The gateway's stored access token as is, for callers that can't
+/// refresh.
+pub fn stored_access_token(settings: &OAuthSettings) -> Option<String> {
+    TokenStore::load()
+        .get(&settings.store_key())
+        .map(|tokens| tokens.access_token)
+}
+
+/// Whether the user has signed in to the gateway.
+pub fn is_signed_in(settings: &OAuthSettings) -> bool {
+    TokenStore::load().get(&settings.store_key()).is_some()
+}
+
+fn not_signed_in(provider: &str) -> anyhow::Error {
+    anyhow!(
+        "Not signed in to {}. Sign in from the settings panel (`dexter --setup`).",
+        provider
+    )
+}
+
+/// Posts `form` to the token endpoint. OAuth errors come back as 400 with
+/// an `error` field, so only answers without one fail here.
+async fn request_tokens(
+    http: &Client,
+    settings: &OAuthSettings,
+    form: &[(&str, &str)],
+) -> Result<TokenResponse> {
+    let response = http
+        .post(&settings.token_url)
+        .form(form)
+        .send()
+        .await
+        .with_context(|| format!("Could not reach {}", settings.token_url))?;
+    let status = response.status();
+    let text = response.text().await?;
+    match serde_json::from_str::<TokenResponse>(&text) {
+        Ok(answer) if status.is_success() || answer.error.is_some() => Ok(answer),
+        _ => Err(anyhow!(
+            "Token request failed (status {}): {}",
+            status,
+            truncate_error(&text)
+        )),
+    }
+}
+
+/// `tokens.json` in the config directory, by `OAuthSettings::store_key`.
+/// Written with the same 0600 permissions as the config that holds the API
+/// keys.
+#[derive(Debug, Default)]
+pub struct TokenStore {
+    path: Option<PathBuf>,
+    tokens: HashMap<String, OAuthTokens>,
+}
+
+impl TokenStore {
+    fn default_path() -> Option<PathBuf> {
+        crate::paths::config_dir().map(|dir| dir.join("dexter").join("tokens.json"))
+    }
+
+    pub fn load() -> Self {
+        Self::load_from(Self::default_path())
+    }
+
+    /// Held while tokens are refreshed or stored, across every Dexter.
+    async fn lock() -> Result<Option<std::fs::File>> {
+        match Self::default_path() {
+            Some(path) => Ok(Some(lock_store(&path).await?)),
+            None => Ok(None),
+        }
+    }
+
+    fn load_from(path: Option<PathBuf>) -> Self {
+        let tokens = path
+            .as_deref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default();
+        Self { path, tokens }
+    }
+
+    pub fn get(&self, provider: &str) -> Option<OAuthTokens> {
+        self.tokens.get(provider).cloned()
+    }
+
+    pub async fn insert(&mut self, key: &str, tokens: OAuthTokens) -> Result<()> {
+        self.tokens.insert(key.to_string(), tokens);
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let tmp_path = path.with_extension("json.tmp");
+        write_private(&tmp_path, &serde_json::to_string_pretty(&self.tokens)?)
+            .await
+            .with_context(|| format!("Could not write {}", tmp_path.display()))?;
+        tokio::fs::rename(&tmp_path, path).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn keeps_tokens_and_refreshes_them_before_expiry() {
+        let now = Utc::now();
+        let answer: TokenResponse = serde_json::from_str(
+            r#"{"access_token": "at-1", "refresh_token": "rt-1", "expires_in": 3600, "token_type": "Bearer"}"#,
+        )
+        .unwrap();
+        let tokens = answer.into_tokens(now).unwrap();
+        assert_eq!(tokens.expires_at, Some(now + ChronoDuration::seconds(3600)));
+        assert!(tokens.is_fresh(now));
+        assert!(!tokens.is_fresh(now + ChronoDuration::seconds(3550)));
+        assert!(OAuthTokens {
+            expires_at: None,
+            ..tokens.clone()
+        }
+        .is_fresh(now));
+
+        let pending: TokenResponse =
+            serde_json::from_str(r#"{"error": "authorization_pending"}"#).unwrap();
+        assert_eq!(pending.error_message(), "authorization_pending");
+        assert!(pending.into_tokens(now).is_none());
+
+        let path = std::env::temp_dir().join(format!("dexter-tokens-{}.json", std::process::id()));
+        TokenStore::load_from(Some(path.clone()))
+            .insert("https://sso.example.com/token dexter", tokens.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            TokenStore::load_from(Some(path.clone())).get("https://sso.example.com/token dexter"),
+            Some(tokens)
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,276 @@
+//! Reads the text visible in scans and screenshots with tesseract, so a
+//! request like "rename these scans by the invoice number visible in them"
+//! has the numbers to work with. Off unless `ocr.enabled`, and only rename
+//! requests pay for it.
+
+use dexter_plugins::health::find_in_path;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, SystemTime};
+use tokio::process::Command;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::context::FileContext;
+use crate::redaction::redact_sensitive_text;
+
+/// Longest tesseract may take on one image before it is skipped.
+const FILE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Longest the whole request waits for text; images not read by then are
+/// listed without it.
+const TOTAL_TIMEOUT: Duration = Duration::from_secs(15);
+/// Images read at once.
+const PARALLEL_FILES: usize = 4;
+/// Characters of recognized text kept per file.
+const TEXT_LIMIT: usize = 300;
+/// Extensions tesseract reads directly; PDFs have to be images first.
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "tif", "tiff", "bmp", "gif", "webp", "pnm",
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct OcrPolicy {
+    /// Runs tesseract over the images in the context of rename requests.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Images read per request; the rest are listed without their text.
+    #[serde(default = "default_max_files")]
+    pub max_files: usize,
+    /// Tesseract's `-l` languages, e.g. `eng+deu`.
+    #[serde(default = "default_languages")]
+    pub languages: String,
+}
+
+fn default_max_files() -> usize {
+    20
+}
+
+fn default_languages() -> String {
+    "eng".to_string()
+}
+
+impl Default for OcrPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_files: default_max_files(),
+            languages: default_languages(),
+        }
+    }
+}
+
+/// What tesseract read in one file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OcrText {
+    /// The file as the context names it.
+    pub file: String,
+    /// The recognized text on one line, cut at `TEXT_LIMIT` characters.
+    pub text: String,
+    /// Dates found in the text, as written.
+    pub dates: Vec<String>,
+}
+
+/// Whether `intent` asks to rename files, the only requests that get OCR.
+pub fn wants_ocr(intent: &str) -> bool {
+    let intent = intent.to_lowercase();
+    intent
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|word| word.starts_with("renam"))
+        || ["name them", "name each", "name these", "name the files"]
+            .iter()
+            .any(|phrase| intent.contains(phrase))
+}
+
+fn is_image(file: &str) -> bool {
+    Path::new(file)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// Reads the images in `context`, relative to `cwd`, and stores their text
+/// in `context.ocr`. Does nothing unless `policy` is on, `intent` is a
+/// rename and tesseract is installed. Images named in `intent` come first,
+/// then the newest. They are read in background tasks, several at once,
+/// until `TOTAL_TIMEOUT`; images it can't read in time are left out.
+pub async fn enrich(context: &mut FileContext, cwd: &Path, intent: &str, policy: &OcrPolicy) {
+    if !policy.enabled || !wants_ocr(intent) || find_in_path("tesseract").is_none() {
+        return;
+    }
+    let images = pick_images(&context.files, cwd, intent, policy.max_files).await;
+    let permits = Arc::new(Semaphore::new(PARALLEL_FILES));
+    let mut tasks = JoinSet::new();
+    for (index, file) in images.into_iter().enumerate() {
+        let path = cwd.join(&file);
+        let languages = policy.languages.clone();
+        let permits = Arc::clone(&permits);
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await.ok()?;
+            let text = recognize(&path, &languages).await;
+            Some((index, file, text))
+        });
+    }
+    let mut found = Vec::new();
+    let deadline = tokio::time::Instant::now() + TOTAL_TIMEOUT;
+    loop {
+        let next = match tokio::time::timeout_at(deadline, tasks.join_next()).await {
+            Ok(Some(next)) => next,
+            Ok(None) => break,
+            Err(_) => {
+                tracing::debug!(left = tasks.len(), "OCR ran out of time");
+                break;
+            }
+        };
+        match next {
+            Ok(Some((index, file, Some(text)))) if !text.is_empty() => {
+                let dates = find_dates(&text);
+                found.push((index, OcrText { file, text, dates }));
+            }
+            Ok(Some((_, file, _))) => tracing::debug!(file = %file, "no text recognized"),
+            _ => {}
+        }
+    }
+    // Dropping the set kills the tesseract runs still going.
+    drop(tasks);
+    found.sort_by_key(|(index, _)| *index);
+    context.ocr.extend(found.into_iter().map(|(_, text)| text));
+}
+
+/// Up to `limit` images of `files`: those `intent` names first, then the
+/// most recently modified.
+async fn pick_images(files: &[String], cwd: &Path, intent: &str, limit: usize) -> Vec<String> {
+    let intent = intent.to_lowercase();
+    let mut images: Vec<(bool, Option<SystemTime>, &String)> = Vec::new();
+    for file in files.iter().filter(|file| is_image(file)) {
+        let path = cwd.join(file);
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let named = intent.contains(&name) || intent.contains(&file.to_lowercase());
+        let modified = tokio::fs::metadata(&path)
+            .await
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        images.push((named, modified, file));
+    }
+    images.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)));
+    images
+        .into_iter()
+        .take(limit)
+        .map(|(_, _, file)| file.clone())
+        .collect()
+}
+
+/// `tesseract <image> stdout`, collapsed to one line and cut to length.
+async fn recognize(path: &Path, languages: &str) -> Option<String> {
+    crate::replay::refuse_spawn("tesseract").ok()?;
+    let output = Command::new("tesseract")
+        .arg(path)
+        .arg("stdout")
+        .args(["-l", languages])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(FILE_TIMEOUT, output)
+        .await
+        .ok()?
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = clean_text(&String::from_utf8_lossy(&output.stdout));
+    // Scans of letters and screenshots can show keys and tokens.
+    Some(redact_sensitive_text(&text))
+}
+
+fn clean_text(raw: &str) -> String {
+    let text = raw.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(TEXT_LIMIT) {
+        Some((end, _)) => format!("{}...", text[..end].trim_end()),
+        None => text,
+    }
+}
+
+/// `2024-03-01`, `01.03.2024`, `3/1/24` and `1 March 2024` style dates.
+fn find_dates(text: &str) -> Vec<String> {
+    static DATE: OnceLock<Regex> = OnceLock::new();
+    let date = DATE.get_or_init(|| {
+        Regex::new(
+            r"(?i)\b(\d{4}-\d{1,2}-\d{1,2}|\d{1,2}[./-]\d{1,2}[./-]\d{2,4}|\d{1,2}\.? (jan|feb|mar|apr|may|jun|jul|aug|sep|oct|nov|dec)[a-z]*\.? \d{4})\b",
+        )
+        .expect("date pattern")
+    });
+    let mut dates: Vec<String> = Vec::new();
+    for found in date.find_iter(text) {
+        if !dates.iter().any(|d| d == found.as_str()) {
+            dates.push(found.as_str().to_string());
+        }
+    }
+    dates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_rename_requests_read_images() {
+        assert!(wants_ocr(
+            "Rename these scans by the invoice number in them"
+        ));
+        assert!(wants_ocr("name them after the date printed on each"));
+        assert!(!wants_ocr("convert the scans to pdf"));
+        assert!(is_image("scans/IMG_01.JPG") && !is_image("invoice.pdf"));
+
+        let text =
+            clean_text("INVOICE  No. 4711\n\nDate: 2024-03-01\nDue 15.04.2024, 1 March 2024\n");
+        assert_eq!(
+            text,
+            "INVOICE No. 4711 Date: 2024-03-01 Due 15.04.2024, 1 March 2024"
+        );
+        assert_eq!(
+            find_dates(&text),
+            ["2024-03-01", "15.04.2024", "1 March 2024"]
+        );
+        assert!(clean_text(&"x ".repeat(400)).ends_with("x..."));
+
+        let context = FileContext {
+            ocr: vec![OcrText {
+                file: "scan1.png".to_string(),
+                dates: find_dates(&text),
+                text,
+            }],
+            ..crate::ContextScanner::from_listing(vec!["scan1.png".to_string()], 0)
+        };
+        assert!(context.for_prompt("rename", 20).ends_with(
+            "1. scan1.png\nText visible in the images (OCR, may contain misreadings):\n- scan1.png: \"INVOICE No. 4711 Date: 2024-03-01 Due 15.04.2024, 1 March 2024\" (dates: 2024-03-01, 15.04.2024, 1 March 2024)"
+        ));
+    }
+
+    #[tokio::test]
+    async fn named_images_come_first_then_the_newest() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = std::time::SystemTime::now() - Duration::from_secs(3600);
+        for name in ["old.png", "new.png", "invoice.jpg", "notes.txt"] {
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+        for name in ["old.png", "invoice.jpg"] {
+            std::fs::File::options()
+                .write(true)
+                .open(dir.path().join(name))
+                .unwrap()
+                .set_modified(old)
+                .unwrap();
+        }
+        let files: Vec<String> = ["invoice.jpg", "new.png", "notes.txt", "old.png"]
+            .map(String::from)
+            .to_vec();
+        let picked = pick_images(&files, dir.path(), "rename Invoice.jpg by its number", 2).await;
+        assert_eq!(picked, ["invoice.jpg", "new.png"]);
+    }
+}
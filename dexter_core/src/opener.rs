@@ -0,0 +1,228 @@
+//! Hands a finished run's results to the desktop: its folder to the file
+//! manager, its first audio or video output to the default player, and
+//! its output paths to the clipboard.
+
+use anyhow::{anyhow, Result};
+use dexter_plugins::health::find_in_path;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::conflict::output_paths;
+
+const PLAYABLE_EXTENSIONS: &[&str] = &[
+    "mp4", "mkv", "mov", "avi", "webm", "m4v", "wmv", "flv", "mpg", "mpeg", "ts", "mp3", "m4a",
+    "aac", "flac", "wav", "ogg", "opus", "wma",
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct OpenPolicy {
+    /// Offers OPEN FOLDER, PLAY and COPY PATHS after a successful local run.
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Program given the folder or file instead of the platform's opener
+    /// (`xdg-open`, `open`, `explorer.exe`), e.g. `"mpv --force-window"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub opener: Option<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Default for OpenPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            opener: None,
+        }
+    }
+}
+
+impl OpenPolicy {
+    /// Whether anything can be opened here. Without an `opener`, Linux and
+    /// the BSDs need a desktop session: over plain SSH there is nothing to
+    /// show a folder on.
+    pub fn can_open(&self) -> bool {
+        self.enabled && (self.opener.is_some() || has_desktop(|key| std::env::var_os(key)))
+    }
+
+    /// Opens `target` with the opener and returns without waiting for it.
+    pub fn open(&self, target: &Path) -> Result<()> {
+        let argv = self.opener_argv(target)?;
+        crate::replay::refuse_spawn(&argv[0])?;
+        let mut child = Command::new(&argv[0])
+            .args(&argv[1..])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| anyhow!("Could not start {}: {}", argv[0], e))?;
+        // Reaped in the background; the opener usually exits right away.
+        tokio::spawn(async move {
+            let _ = child.wait().await;
+        });
+        Ok(())
+    }
+
+    fn opener_argv(&self, target: &Path) -> Result<Vec<String>> {
+        let target = target.to_string_lossy().into_owned();
+        let mut argv = match &self.opener {
+            Some(opener) => {
+                shell_words::split(opener).map_err(|e| anyhow!("Invalid open.opener: {}", e))?
+            }
+            None => vec![platform_opener(std::env::consts::OS).to_string()],
+        };
+        if argv.is_empty() {
+            return Err(anyhow!("open.opener is empty"));
+        }
+        argv.push(target);
+        Ok(argv)
+    }
+}
+
+/// The program that opens a file or folder on `os`. Windows gets
+/// `explorer.exe` rather than `cmd /C start`, which would read `&` and `^`
+/// in the name as shell syntax.
+fn platform_opener(os: &str) -> &'static str {
+    match os {
+        "windows" => "explorer.exe",
+        "macos" => "open",
+        _ => "xdg-open",
+    }
+}
+
+fn has_desktop(var: impl Fn(&str) -> Option<std::ffi::OsString>) -> bool {
+    if cfg!(any(windows, target_os = "macos")) {
+        return true;
+    }
+    ["DISPLAY", "WAYLAND_DISPLAY"]
+        .iter()
+        .any(|key| var(key).is_some_and(|value| !value.is_empty()))
+}
+
+/// The outputs `command` names that exist in `cwd` after the run, as
+/// absolute paths.
+pub fn result_outputs(plugin: &str, command: &str, cwd: &Path) -> Vec<PathBuf> {
+    output_paths(plugin, command)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|path| cwd.join(path))
+        .filter(|path| path.exists())
+        .collect()
+}
+
+/// The first audio or video file among `outputs`.
+pub fn playable(outputs: &[PathBuf]) -> Option<&PathBuf> {
+    outputs.iter().find(|path| {
+        path.is_file()
+            && path
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+                .is_some_and(|ext| PLAYABLE_EXTENSIONS.contains(&ext.as_str()))
+    })
+}
+
+/// Where the outputs went: the folder of the first one, else `cwd`.
+pub fn output_folder(outputs: &[PathBuf], cwd: &Path) -> PathBuf {
+    outputs
+        .first()
+        .map(|path| {
+            if path.is_dir() {
+                path.clone()
+            } else {
+                path.parent().unwrap_or(cwd).to_path_buf()
+            }
+        })
+        .unwrap_or_else(|| cwd.to_path_buf())
+}
+
+/// Puts `text` on the system clipboard with the first clipboard tool
+/// found, and names it. Fails when there is none, as over SSH; the caller
+/// can still ask the terminal.
+pub async fn copy_to_clipboard(text: &str) -> Result<&'static str> {
+    let tools: &[(&'static str, &[&str])] = if cfg!(windows) {
+        &[("clip", &[])]
+    } else if cfg!(target_os = "macos") {
+        &[("pbcopy", &[])]
+    } else {
+        &[
+            ("wl-copy", &[]),
+            ("xclip", &["-selection", "clipboard"]),
+            ("xsel", &["--clipboard", "--input"]),
+        ]
+    };
+    if !has_desktop(|key| std::env::var_os(key)) {
+        return Err(anyhow!("No desktop session to copy to"));
+    }
+    let (tool, args) = tools
+        .iter()
+        .find(|(tool, _)| find_in_path(tool).is_some())
+        .ok_or_else(|| anyhow!("No clipboard tool found"))?;
+    crate::replay::refuse_spawn(tool)?;
+    let mut child = Command::new(tool)
+        .args(*args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes()).await?;
+    }
+    let status = child.wait().await?;
+    if !status.success() {
+        return Err(anyhow!("{} failed ({})", tool, status));
+    }
+    Ok(tool)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_opener_folder_and_player() {
+        let policy = OpenPolicy {
+            opener: Some("mpv --force-window".to_string()),
+            ..OpenPolicy::default()
+        };
+        assert_eq!(
+            policy.opener_argv(Path::new("out/a b.mp4")).unwrap(),
+            ["mpv", "--force-window", "out/a b.mp4"]
+        );
+        assert!(policy.can_open());
+        assert_eq!(platform_opener("windows"), "explorer.exe");
+        let name = r"out\a & calc.mp4";
+        let argv = OpenPolicy::default().opener_argv(Path::new(name)).unwrap();
+        assert_eq!(argv.len(), 2);
+        assert_eq!(argv[1], name);
+        assert!(!OpenPolicy {
+            enabled: false,
+            ..policy
+        }
+        .can_open());
+
+        let dir = std::env::temp_dir().join(format!("dexter-opener-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("out")).unwrap();
+        std::fs::write(dir.join("out/talk.srt"), b"1").unwrap();
+        std::fs::write(dir.join("out/talk.mp3"), b"x").unwrap();
+        let outputs = result_outputs("ffmpeg", "ffmpeg -i talk.mkv out/talk.mp3", &dir);
+        assert_eq!(outputs, [dir.join("out/talk.mp3")]);
+        assert_eq!(playable(&outputs), Some(&dir.join("out/talk.mp3")));
+        assert_eq!(playable(&[dir.join("out/talk.srt")]), None);
+        assert_eq!(output_folder(&outputs, &dir), dir.join("out"));
+        assert_eq!(output_folder(&[], &dir), dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn needs_a_desktop_session_without_an_opener() {
+        assert!(!has_desktop(|_| None));
+        assert!(has_desktop(
+            |key| (key == "WAYLAND_DISPLAY").then(|| "wayland-0".into())
+        ));
+    }
+}
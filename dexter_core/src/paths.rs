@@ -0,0 +1,52 @@
+//! Where Dexter keeps its files: the platform directories, or the same
+//! layout under a throwaway home for `dexter replay`. The home is handed in
+//! once at startup rather than through `HOME` and `XDG_*`, which other
+//! threads may be reading by then.
+
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+static HOME: OnceLock<PathBuf> = OnceLock::new();
+
+/// Puts every directory below under `home` for the rest of the process.
+pub fn set_home(home: &Path) -> Result<()> {
+    HOME.set(home.to_path_buf())
+        .map_err(|_| anyhow!("Dexter's home directory is already set"))
+}
+
+fn under_home(dir: &str, platform: fn() -> Option<PathBuf>) -> Option<PathBuf> {
+    match HOME.get() {
+        Some(home) => Some(home.join(dir)),
+        None => platform(),
+    }
+}
+
+pub fn home_dir() -> Option<PathBuf> {
+    HOME.get().cloned().or_else(dirs::home_dir)
+}
+
+/// `path` with a leading `~` resolved against `home_dir`.
+pub fn expand_home(path: &str) -> PathBuf {
+    dexter_plugins::command_exec::expand_home(path, home_dir().as_deref())
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+    under_home(".config", dirs::config_dir)
+}
+
+pub fn data_dir() -> Option<PathBuf> {
+    under_home(".local/share", dirs::data_dir)
+}
+
+pub fn cache_dir() -> Option<PathBuf> {
+    under_home(".cache", dirs::cache_dir)
+}
+
+/// `None` under a throwaway home, which has no runtime directory.
+pub fn runtime_dir() -> Option<PathBuf> {
+    match HOME.get() {
+        Some(_) => None,
+        None => dirs::runtime_dir(),
+    }
+}
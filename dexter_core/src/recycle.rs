@@ -0,0 +1,385 @@
+//! Deletes that can be undone. Instead of letting a tool remove files
+//! itself (jdupes `-d`, `cleanup --delete`), Dexter runs it as a scan and
+//! moves the files it would have removed to the system trash: trash-cli,
+//! `gio trash`, the macOS `trash` command or Finder, or the Windows
+//! Recycle Bin.
+
+use anyhow::{anyhow, Result};
+use dexter_plugins::cleanup::{self, parse_cleanup_command};
+use dexter_plugins::health::find_in_path;
+use dexter_plugins::{Plugin, Progress};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::sync::mpsc;
+
+use crate::sandbox::SandboxPolicy;
+
+/// Paths handed to one trash command.
+const CHUNK: usize = 200;
+
+/// Flags that make jdupes delete instead of list.
+const DELETE_FLAGS: &[&str] = &["-d", "--delete", "-N", "--no-prompt"];
+/// Flags that change the listing away from one path per line.
+const LISTING_FLAGS: &[&str] = &[
+    "-m",
+    "--summarize",
+    "-M",
+    "--print-summarize",
+    "-S",
+    "--size",
+    "-j",
+    "--json",
+    "-u",
+    "--print-unique",
+    "-1",
+    "--one-line",
+    "-0",
+    "--print-null",
+];
+
+/// Sends each path read from stdin to the Recycle Bin. `DeleteFile` throws
+/// on a directory, such as one `cleanup empty-dirs` removes, so those go
+/// through `DeleteDirectory`.
+const RECYCLE_SCRIPT: &str = "Add-Type -AssemblyName Microsoft.VisualBasic; \
+     $input | ForEach-Object { $path = (Resolve-Path -LiteralPath $_).Path; \
+     if (Test-Path -LiteralPath $path -PathType Container) { \
+     [Microsoft.VisualBasic.FileIO.FileSystem]::DeleteDirectory(\
+     $path, 'OnlyErrorDialogs', 'SendToRecycleBin') } else { \
+     [Microsoft.VisualBasic.FileIO.FileSystem]::DeleteFile(\
+     $path, 'OnlyErrorDialogs', 'SendToRecycleBin') } }";
+
+/// Has Finder move each argument to the trash, so it can be put back.
+const FINDER_SCRIPT: &[&str] = &[
+    "on run argv",
+    "repeat with p in argv",
+    "tell application \"Finder\" to delete (POSIX file (p as text))",
+    "end repeat",
+    "end run",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrashTool {
+    /// `trash-put` from trash-cli.
+    TrashCli,
+    /// `gio trash` from GLib.
+    Gio,
+    /// The `trash` command on macOS.
+    MacTrash,
+    /// Finder, through `osascript`, when macOS has no `trash` command.
+    Finder,
+    /// PowerShell and the Visual Basic file API.
+    RecycleBin,
+}
+
+impl TrashTool {
+    /// The first one available on this machine.
+    pub fn detect() -> Option<Self> {
+        if cfg!(windows) {
+            return find_in_path("powershell.exe").map(|_| Self::RecycleBin);
+        }
+        if cfg!(target_os = "macos") {
+            // trash-cli and gio would use the XDG trash, which Finder never
+            // shows, so macOS always goes to ~/.Trash.
+            return if find_in_path("trash").is_some() {
+                Some(Self::MacTrash)
+            } else {
+                find_in_path("osascript").map(|_| Self::Finder)
+            };
+        }
+        if find_in_path("trash-put").is_some() {
+            Some(Self::TrashCli)
+        } else if find_in_path("gio").is_some() {
+            Some(Self::Gio)
+        } else {
+            None
+        }
+    }
+
+    /// Where trashed files end up. Files on another drive go to that
+    /// drive's own trash directory.
+    pub fn location(&self) -> String {
+        match self {
+            Self::TrashCli | Self::Gio => crate::paths::data_dir()
+                .map(|dir| dir.join("Trash").display().to_string())
+                .unwrap_or_else(|| "~/.local/share/Trash".to_string()),
+            Self::MacTrash | Self::Finder => crate::paths::home_dir()
+                .map(|dir| dir.join(".Trash").display().to_string())
+                .unwrap_or_else(|| "~/.Trash".to_string()),
+            Self::RecycleBin => "the Recycle Bin".to_string(),
+        }
+    }
+
+    fn program(&self) -> Vec<&'static str> {
+        match self {
+            Self::TrashCli => vec!["trash-put", "--"],
+            Self::Gio => vec!["gio", "trash", "--"],
+            Self::MacTrash => vec!["trash"],
+            Self::Finder => {
+                let mut program = vec!["osascript"];
+                for line in FINDER_SCRIPT {
+                    program.extend(["-e", line]);
+                }
+                program
+            }
+            Self::RecycleBin => vec!["powershell.exe", "-NoProfile", "-Command", RECYCLE_SCRIPT],
+        }
+    }
+
+    /// Moves `paths` to the trash.
+    pub async fn move_to_trash(&self, paths: &[PathBuf], cwd: &Path) -> Result<()> {
+        for chunk in paths.chunks(CHUNK) {
+            let program = self.program();
+            crate::replay::refuse_spawn(program[0])?;
+            let mut command = Command::new(program[0]);
+            command
+                .args(&program[1..])
+                .current_dir(cwd)
+                .stdout(Stdio::null())
+                .stderr(Stdio::piped());
+            if *self == Self::RecycleBin {
+                command.stdin(Stdio::piped());
+            } else if *self == Self::Finder {
+                // Finder resolves POSIX paths from /, not from `cwd`.
+                command
+                    .args(chunk.iter().map(|path| cwd.join(path)))
+                    .stdin(Stdio::null());
+            } else {
+                command.args(chunk).stdin(Stdio::null());
+            }
+            let mut child = command.spawn()?;
+            if let Some(mut stdin) = child.stdin.take() {
+                let list: Vec<String> = chunk.iter().map(|p| p.display().to_string()).collect();
+                stdin.write_all(list.join("\n").as_bytes()).await?;
+            }
+            let output = child.wait_with_output().await?;
+            if !output.status.success() {
+                return Err(anyhow!(
+                    "{} failed: {}",
+                    program[0],
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Files a run moved to the trash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trashed {
+    pub location: String,
+    pub files: Vec<PathBuf>,
+}
+
+/// Whether `cmd` has `plugin` delete files.
+pub fn deletes_files(plugin: &str, cmd: &str) -> bool {
+    match plugin {
+        "jdupes" => shell_words::split(cmd)
+            .unwrap_or_default()
+            .iter()
+            .any(|arg| arg == "--delete" || short_cluster(arg).is_some_and(|c| c.contains('d'))),
+        "cleanup" => cleanup::removes_files(cmd),
+        _ => false,
+    }
+}
+
+/// The letters of a combined short flag such as `-rdN`.
+fn short_cluster(arg: &str) -> Option<&str> {
+    let letters = arg.strip_prefix('-')?;
+    (!letters.is_empty() && letters.chars().all(|c| c.is_ascii_alphabetic())).then_some(letters)
+}
+
+/// `cmd` listing the duplicate sets instead of deleting from them.
+fn scan_command(cmd: &str) -> Result<String> {
+    let argv = shell_words::split(cmd)?;
+    let mut scan = Vec::with_capacity(argv.len());
+    for arg in argv {
+        if DELETE_FLAGS.contains(&arg.as_str()) || LISTING_FLAGS.contains(&arg.as_str()) {
+            continue;
+        }
+        match short_cluster(&arg) {
+            Some(letters) if letters.len() > 1 => {
+                let kept: String = letters
+                    .chars()
+                    .filter(|c| !matches!(c, 'd' | 'N' | 'm' | 'M' | 'S' | 'j' | 'u'))
+                    .collect();
+                if !kept.is_empty() {
+                    scan.push(format!("-{}", kept));
+                }
+            }
+            _ => scan.push(arg),
+        }
+    }
+    Ok(shell_words::join(scan))
+}
+
+/// Every file after the first of each blank-line separated set, the ones
+/// `jdupes -d -N` removes.
+fn duplicates(listing: &str) -> Vec<&str> {
+    listing
+        .split("\n\n")
+        .flat_map(|set| {
+            set.lines()
+                .map(str::trim_end)
+                .filter(|line| !line.is_empty())
+                .skip(1)
+        })
+        .collect()
+}
+
+/// Runs `cmd` with `plugin` like `SandboxPolicy::execute`. With `trash` set
+/// the command runs as a scan, and the files it would delete are moved to
+/// the trash instead.
+pub async fn execute(
+    trash: Option<TrashTool>,
+    sandbox: &SandboxPolicy,
+    plugin: &dyn Plugin,
+    cmd: &str,
+    cwd: &Path,
+    progress_tx: mpsc::Sender<Progress>,
+) -> Result<(String, Option<Trashed>)> {
+    let Some(tool) = trash else {
+        let output = sandbox.execute(plugin, cmd, cwd, progress_tx).await?;
+        return Ok((output, None));
+    };
+    if plugin.name() == "cleanup" {
+        return trash_cleanup(tool, cmd, cwd, progress_tx).await;
+    }
+    let listing = sandbox
+        .execute(plugin, &scan_command(cmd)?, cwd, progress_tx.clone())
+        .await?
+        .replace("\r\n", "\n");
+    let sets = listing
+        .split("\n\n")
+        .filter(|set| !set.trim().is_empty())
+        .count();
+    let files: Vec<PathBuf> = duplicates(&listing)
+        .into_iter()
+        .map(PathBuf::from)
+        .filter(|path| cwd.join(path).is_file())
+        .collect();
+    let location = tool.location();
+    if !files.is_empty() {
+        let _ = progress_tx
+            .send(Progress {
+                percentage: None,
+                message: format!("Moving {} file(s) to {}...", files.len(), location),
+            })
+            .await;
+        tool.move_to_trash(&files, cwd).await?;
+    }
+    let output = format!(
+        "Moved {} duplicate file(s) to {}; kept the first of each of {} set(s).\n{}",
+        files.len(),
+        location,
+        sets,
+        files
+            .iter()
+            .map(|path| format!("  {}", path.display()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    );
+    Ok((output, Some(Trashed { location, files })))
+}
+
+/// Moves what `cleanup --delete` previewed to the trash, each entry only
+/// while it is still empty; a folder goes with the empty folders inside it.
+async fn trash_cleanup(
+    tool: TrashTool,
+    cmd: &str,
+    cwd: &Path,
+    progress_tx: mpsc::Sender<Progress>,
+) -> Result<(String, Option<Trashed>)> {
+    let command = parse_cleanup_command(cmd)?;
+    let (owned, root) = (cmd.to_string(), cwd.to_path_buf());
+    let planned = tokio::task::spawn_blocking(move || {
+        let planned = cleanup::removals_to_run(&owned, &root)?;
+        Ok::<_, anyhow::Error>(
+            planned
+                .into_iter()
+                .filter(|path| cleanup::still_empty(path, &command))
+                .collect::<Vec<_>>(),
+        )
+    })
+    .await??;
+    let files: Vec<PathBuf> = cleanup::outermost(&planned)
+        .into_iter()
+        .map(|path| {
+            path.strip_prefix(cwd)
+                .map(Path::to_path_buf)
+                .unwrap_or(path)
+        })
+        .collect();
+    let location = tool.location();
+    if !files.is_empty() {
+        let _ = progress_tx
+            .send(Progress {
+                percentage: None,
+                message: format!("Moving {} item(s) to {}...", files.len(), location),
+            })
+            .await;
+        tool.move_to_trash(&files, cwd).await?;
+    }
+    let mut lines = vec![format!(
+        "Moved {} empty item(s) to {}.",
+        files.len(),
+        location
+    )];
+    lines.extend(files.iter().map(|path| format!("  {}", path.display())));
+    Ok((lines.join("\n"), Some(Trashed { location, files })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deletes_become_a_scan_of_the_duplicates() {
+        assert!(deletes_files("jdupes", "jdupes -r -d -N ."));
+        assert!(deletes_files("jdupes", "jdupes -rdN ."));
+        assert!(deletes_files("jdupes", "jdupes --delete --no-prompt ."));
+        assert!(!deletes_files("jdupes", "jdupes -r -m ."));
+        assert!(!deletes_files("f2", "f2 -f d -r e"));
+        assert!(deletes_files("cleanup", "cleanup empty-dirs --delete"));
+        assert!(!deletes_files("cleanup", "cleanup largest"));
+
+        assert_eq!(
+            scan_command("jdupes -r -d -N -m 'a b'").unwrap(),
+            "jdupes -r 'a b'"
+        );
+        assert_eq!(scan_command("jdupes -rdN .").unwrap(), "jdupes -r .");
+        assert_eq!(scan_command("jdupes -dN .").unwrap(), "jdupes .");
+
+        let listing = "./a.jpg\n./copy/a.jpg\n./b/a.jpg\n\n./x.txt\n./y.txt\n";
+        assert_eq!(
+            duplicates(listing),
+            ["./copy/a.jpg", "./b/a.jpg", "./y.txt"]
+        );
+    }
+
+    #[test]
+    fn macos_tools_use_the_finder_trash() {
+        for tool in [TrashTool::MacTrash, TrashTool::Finder] {
+            assert!(tool.location().ends_with(".Trash"), "{}", tool.location());
+        }
+        let program = TrashTool::Finder.program();
+        assert_eq!(program[0], "osascript");
+        assert_eq!(program.iter().filter(|arg| **arg == "-e").count(), 5);
+    }
+
+    #[test]
+    fn recycle_bin_sends_directories_through_delete_directory() {
+        let program = TrashTool::RecycleBin.program();
+        let script = program.last().unwrap();
+        let (directory, file) = script
+            .split_once("} else {")
+            .expect("the script branches on directories");
+        assert!(directory.contains("Test-Path -LiteralPath $path -PathType Container"));
+        assert!(
+            directory.contains("::DeleteDirectory($path, 'OnlyErrorDialogs', 'SendToRecycleBin')")
+        );
+        assert!(file.contains("::DeleteFile($path, 'OnlyErrorDialogs', 'SendToRecycleBin')"));
+    }
+}
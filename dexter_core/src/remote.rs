@@ -0,0 +1,221 @@
+use anyhow::{anyhow, Context, Result};
+use dexter_plugins::command_exec::exit_failure;
+use dexter_plugins::Progress;
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+
+use crate::config::RemoteHost;
+use crate::context::{ContextScanner, FileContext};
+
+/// Runs context scans and confirmed commands on a configured SSH host.
+///
+/// Commands are validated locally exactly as they would be for local
+/// execution; the remote side only ever receives `cd <workdir> && <argv>`
+/// with every argument re-quoted, so nothing the LLM produced is spliced into
+/// the remote shell unescaped.
+pub struct RemoteRunner;
+
+impl RemoteRunner {
+    pub async fn scan(host: &RemoteHost) -> Result<FileContext> {
+        crate::replay::refuse_spawn("ssh")?;
+        let script = format!("cd {} && ls -1Ap", quote_remote_path(&host.workdir()));
+        let output = Command::new("ssh")
+            .args(ssh_args(host, &script))
+            .stdin(Stdio::null())
+            .output()
+            .await
+            .context("Failed to launch ssh")?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "Remote scan on {} failed: {}",
+                host.name,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(parse_remote_listing(&String::from_utf8_lossy(
+            &output.stdout,
+        )))
+    }
+
+    pub async fn execute(
+        host: &RemoteHost,
+        cmd: &str,
+        progress_tx: mpsc::Sender<Progress>,
+    ) -> Result<String> {
+        let argv = remote_command_argv(host, cmd)?;
+        crate::replay::refuse_spawn(&argv[0])?;
+        let mut child = Command::new(&argv[0])
+            .args(&argv[1..])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to launch ssh")?;
+
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| anyhow!("Failed to capture stdout"))?;
+        let stderr = child
+            .stderr
+            .take()
+            .ok_or_else(|| anyhow!("Failed to capture stderr"))?;
+
+        let stdout_handle = tokio::spawn(stream_lines(stdout, progress_tx.clone()));
+        let stderr_handle = tokio::spawn(stream_lines(stderr, progress_tx));
+
+        let status = child.wait().await?;
+        let out_output = stdout_handle.await?;
+        let err_output = stderr_handle.await?;
+
+        if status.success() {
+            let combined = format!("{}\n{}", out_output, err_output);
+            Ok(if combined.trim().is_empty() {
+                format!("Command executed successfully on {} (no output)", host.name)
+            } else {
+                combined
+            })
+        } else {
+            Err(exit_failure(status, err_output))
+        }
+    }
+}
+
+/// Full argv (`ssh ... -- <destination> <script>`) used to run `cmd` on `host`.
+pub fn remote_command_argv(host: &RemoteHost, cmd: &str) -> Result<Vec<String>> {
+    let argv = shell_words::split(cmd).map_err(|e| anyhow!("Invalid command syntax: {}", e))?;
+    if argv.is_empty() {
+        return Err(anyhow!("Command is empty"));
+    }
+    let script = format!(
+        "cd {} && {}",
+        quote_remote_path(&host.workdir()),
+        shell_words::join(&argv)
+    );
+    let mut out = vec!["ssh".to_string()];
+    out.extend(ssh_args(host, &script));
+    Ok(out)
+}
+
+/// How long ssh waits for an unreachable host before giving up.
+const SSH_CONNECT_TIMEOUT_SECS: u32 = 10;
+
+fn ssh_args(host: &RemoteHost, script: &str) -> Vec<String> {
+    let mut args = vec![
+        "-o".to_string(),
+        "BatchMode=yes".to_string(),
+        "-o".to_string(),
+        format!("ConnectTimeout={}", SSH_CONNECT_TIMEOUT_SECS),
+    ];
+    if let Some(port) = host.port {
+        args.push("-p".to_string());
+        args.push(port.to_string());
+    }
+    // `--` must precede the destination: ssh treats everything after the
+    // destination as part of the remote command.
+    args.push("--".to_string());
+    args.push(host.destination());
+    args.push(script.to_string());
+    args
+}
+
+/// Quotes a remote path while keeping a leading `~` expandable.
+fn quote_remote_path(path: &str) -> String {
+    if path == "~" {
+        return "~".to_string();
+    }
+    match path.strip_prefix("~/") {
+        Some(rest) if !rest.is_empty() => format!("~/{}", shell_words::quote(rest)),
+        Some(_) => "~".to_string(),
+        None => shell_words::quote(path).into_owned(),
+    }
+}
+
+fn parse_remote_listing(listing: &str) -> FileContext {
+    let mut files = Vec::new();
+    let mut dir_count = 0;
+    for line in listing.lines() {
+        let name = line.trim_end_matches('\r');
+        if name.is_empty() || name.starts_with('.') {
+            continue;
+        }
+        if name.ends_with('/') {
+            dir_count += 1;
+        } else {
+            files.push(name.to_string());
+        }
+    }
+    ContextScanner::from_listing(files, dir_count)
+}
+
+async fn stream_lines<R>(reader: R, progress_tx: mpsc::Sender<Progress>) -> String
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    let mut lines = BufReader::new(reader).lines();
+    let mut captured = String::new();
+    while let Ok(Some(line)) = lines.next_line().await {
+        captured.push_str(&line);
+        captured.push('\n');
+        let _ = progress_tx
+            .send(Progress {
+                percentage: None,
+                message: line,
+            })
+            .await;
+    }
+    captured
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host(workdir: Option<&str>) -> RemoteHost {
+        RemoteHost {
+            name: "nas".to_string(),
+            host: "nas.local".to_string(),
+            user: Some("media".to_string()),
+            port: Some(2222),
+            workdir: workdir.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn remote_argv_requotes_command_and_keeps_tilde() {
+        let argv = remote_command_argv(
+            &host(Some("~/Movies/My Shows")),
+            "ffmpeg -i 'a b.mkv' out.mp4",
+        )
+        .unwrap();
+        assert_eq!(
+            argv,
+            vec![
+                "ssh",
+                "-o",
+                "BatchMode=yes",
+                "-o",
+                "ConnectTimeout=10",
+                "-p",
+                "2222",
+                "--",
+                "media@nas.local",
+                "cd ~/'Movies/My Shows' && ffmpeg -i 'a b.mkv' out.mp4",
+            ]
+        );
+    }
+
+    #[test]
+    fn remote_argv_rejects_unbalanced_quotes() {
+        assert!(remote_command_argv(&host(None), "ffmpeg -i 'a.mkv").is_err());
+    }
+
+    #[test]
+    fn parses_ls_listing_into_context() {
+        let context = parse_remote_listing("b.mkv\n.hidden\nShows/\na.mp4\n");
+        assert_eq!(context.files, vec!["a.mp4", "b.mkv"]);
+        assert!(context.summary.is_none());
+    }
+}
@@ -0,0 +1,394 @@
+//! Session logs for bug reports. `dexter --record-session FILE` writes a TUI
+//! session to FILE as JSON lines: the config without its secrets, every
+//! input event with the state it arrived in, and what the models, context
+//! scans, dry runs and runs returned. `dexter replay FILE` drives the UI
+//! through the same session again with all of that read back from the log,
+//! so nothing goes to a provider and nothing is scanned or run.
+//!
+//! The tape is process wide: the model clients, the scanner and the runner
+//! are built in too many places to hand it to each one.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use dexter_plugins::PreviewContent;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use crate::audit::AuditPolicy;
+use crate::config::Config;
+use crate::context::FileContext;
+use crate::opener::OpenPolicy;
+use crate::scope::ScopeSummary;
+use crate::sync::SyncPolicy;
+
+/// Format version written into every session log.
+pub const SESSION_LOG_VERSION: u32 = 1;
+
+/// Stands in for the API keys a session log leaves out, so the replayed
+/// config still counts its providers as configured.
+const REDACTED_KEY: &str = "redacted";
+
+/// A terminal event as the log keeps it, independent of the terminal
+/// library.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum InputEvent {
+    /// `ctrl+k`, `shift+BackTab`, `Enter`, `a`.
+    Key {
+        key: String,
+    },
+    Paste {
+        text: String,
+    },
+    /// `down_left`, `scroll_up`... at a cell of the screen.
+    Mouse {
+        action: String,
+        column: u16,
+        row: u16,
+    },
+}
+
+/// One line of a session log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SessionEntry {
+    Header {
+        version: u32,
+        dexter: String,
+        recorded_at: DateTime<Utc>,
+        /// The session's directory, for reference; replays don't use it.
+        cwd: String,
+        /// config.toml without keys, proxies or sync remote.
+        config: String,
+    },
+    Input {
+        /// The app state the event arrived in, which the replay waits for.
+        state: String,
+        event: InputEvent,
+    },
+    Llm {
+        /// Fingerprint of the system and user prompts.
+        key: String,
+        prompt: String,
+        reply: Result<String, String>,
+    },
+    Context {
+        context: Result<FileContext, String>,
+    },
+    DryRun {
+        preview: Result<(PreviewContent, Option<ScopeSummary>), String>,
+    },
+    Run {
+        output: Result<String, String>,
+    },
+}
+
+/// A session log as `dexter replay` reads it.
+#[derive(Debug, Clone, Default)]
+pub struct SessionLog {
+    pub version: u32,
+    /// The Dexter version that recorded it.
+    pub dexter: String,
+    pub cwd: String,
+    pub config: String,
+    /// Input events in order, with the state each one arrived in.
+    pub inputs: Vec<(String, InputEvent)>,
+    replies: Vec<(String, Result<String, String>)>,
+    contexts: VecDeque<Result<FileContext, String>>,
+    dry_runs: VecDeque<Result<(PreviewContent, Option<ScopeSummary>), String>>,
+    runs: VecDeque<Result<String, String>>,
+}
+
+impl SessionLog {
+    pub fn read(path: &Path) -> Result<Self> {
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read session log {}", path.display()))?;
+        Self::parse(&raw)
+    }
+
+    pub fn parse(raw: &str) -> Result<Self> {
+        let mut log = Self::default();
+        for (idx, line) in raw.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: SessionEntry = serde_json::from_str(line)
+                .with_context(|| format!("Session log line {} is not an entry", idx + 1))?;
+            match entry {
+                SessionEntry::Header {
+                    version,
+                    dexter,
+                    cwd,
+                    config,
+                    ..
+                } => {
+                    if version > SESSION_LOG_VERSION {
+                        return Err(anyhow!(
+                            "The session log is version {}; this Dexter reads up to {}",
+                            version,
+                            SESSION_LOG_VERSION
+                        ));
+                    }
+                    log.version = version;
+                    log.dexter = dexter;
+                    log.cwd = cwd;
+                    log.config = config;
+                }
+                SessionEntry::Input { state, event } => log.inputs.push((state, event)),
+                SessionEntry::Llm { key, reply, .. } => log.replies.push((key, reply)),
+                SessionEntry::Context { context } => log.contexts.push_back(context),
+                SessionEntry::DryRun { preview } => log.dry_runs.push_back(preview),
+                SessionEntry::Run { output } => log.runs.push_back(output),
+            }
+        }
+        if log.version == 0 {
+            return Err(anyhow!("The session log has no header line"));
+        }
+        Ok(log)
+    }
+
+    /// The reply recorded for these prompts, else the oldest one left: a
+    /// prompt can differ in a replay (history examples, dates) while the
+    /// conversation is the same.
+    fn take_reply(&mut self, key: &str) -> Result<String> {
+        let idx = self.replies.iter().position(|(k, _)| k == key);
+        if idx.is_none() && !self.replies.is_empty() {
+            tracing::warn!(
+                target: "dexter::replay",
+                key,
+                "no reply for this prompt; using the next one"
+            );
+        }
+        let (_, reply) = match idx {
+            Some(idx) => self.replies.remove(idx),
+            None if !self.replies.is_empty() => self.replies.remove(0),
+            None => return Err(anyhow!("The session log has no more model replies")),
+        };
+        reply.map_err(anyhow::Error::msg)
+    }
+}
+
+enum Tape {
+    Recording(Mutex<File>),
+    Replaying(Box<Mutex<SessionLog>>),
+}
+
+static TAPE: OnceLock<Tape> = OnceLock::new();
+
+/// Starts writing this session to `path`, beginning with `config`.
+pub fn start_recording(path: &Path, config: &Config) -> Result<()> {
+    let file = File::create(path)
+        .with_context(|| format!("Cannot create session log {}", path.display()))?;
+    let header = SessionEntry::Header {
+        version: SESSION_LOG_VERSION,
+        dexter: env!("CARGO_PKG_VERSION").to_string(),
+        recorded_at: Utc::now(),
+        cwd: std::env::current_dir()
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_default(),
+        config: toml::to_string_pretty(&redacted_config(config))?,
+    };
+    TAPE.set(Tape::Recording(Mutex::new(file)))
+        .map_err(|_| anyhow!("A session is already being recorded or replayed"))?;
+    record(|| header);
+    Ok(())
+}
+
+/// Serves everything after this from `log` instead of the providers, the
+/// filesystem and the tools.
+pub fn start_replay(log: SessionLog) -> Result<()> {
+    TAPE.set(Tape::Replaying(Box::new(Mutex::new(log))))
+        .map_err(|_| anyhow!("A session is already being recorded or replayed"))
+}
+
+pub fn is_recording() -> bool {
+    matches!(TAPE.get(), Some(Tape::Recording(_)))
+}
+
+pub fn is_replaying() -> bool {
+    matches!(TAPE.get(), Some(Tape::Replaying(_)))
+}
+
+/// Appends `entry` to the log being recorded, if any. A line that can't be
+/// written is dropped with a warning rather than failing the session.
+pub fn record(entry: impl FnOnce() -> SessionEntry) {
+    let Some(Tape::Recording(file)) = TAPE.get() else {
+        return;
+    };
+    let written = serde_json::to_string(&entry())
+        .map_err(anyhow::Error::from)
+        .and_then(|line| {
+            let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+            writeln!(file, "{}", line).map_err(anyhow::Error::from)
+        });
+    if let Err(e) = written {
+        tracing::warn!(target: "dexter::replay", error = %e, "session log line dropped");
+    }
+}
+
+fn replaying<T>(take: impl FnOnce(&mut SessionLog) -> T) -> Option<T> {
+    let Some(Tape::Replaying(log)) = TAPE.get() else {
+        return None;
+    };
+    Some(take(&mut log.lock().unwrap_or_else(|e| e.into_inner())))
+}
+
+/// The recorded reply to these prompts when replaying.
+pub fn replayed_reply(system: &str, user: &str) -> Option<Result<String>> {
+    replaying(|log| log.take_reply(&prompt_key(system, user)))
+}
+
+/// Records a model's `reply` to these prompts when recording.
+pub fn record_reply(system: &str, user: &str, reply: &Result<String>) {
+    record(|| SessionEntry::Llm {
+        key: prompt_key(system, user),
+        prompt: user.to_string(),
+        reply: error_text(reply),
+    });
+}
+
+/// The next recorded context scan when replaying.
+pub fn replayed_context() -> Option<Result<FileContext>> {
+    replaying(|log| next(&mut log.contexts, "context scans"))
+}
+
+/// The next recorded dry run when replaying.
+pub fn replayed_dry_run() -> Option<Result<(PreviewContent, Option<ScopeSummary>)>> {
+    replaying(|log| next(&mut log.dry_runs, "dry runs"))
+}
+
+/// The next recorded run output when replaying.
+pub fn replayed_run() -> Option<Result<String>> {
+    replaying(|log| next(&mut log.runs, "runs"))
+}
+
+/// `result` with its error as the text the log keeps.
+pub fn error_text<T: Clone>(result: &Result<T>) -> Result<T, String> {
+    match result {
+        Ok(value) => Ok(value.clone()),
+        Err(e) => Err(format!("{:#}", e)),
+    }
+}
+
+fn next<T>(queue: &mut VecDeque<Result<T, String>>, what: &str) -> Result<T> {
+    queue
+        .pop_front()
+        .ok_or_else(|| anyhow!("The session log has no more {}", what))?
+        .map_err(anyhow::Error::msg)
+}
+
+fn prompt_key(system: &str, user: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(system.as_bytes());
+    hasher.update([0]);
+    hasher.update(user.as_bytes());
+    hasher.finalize()[..8]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// `config` as a session log keeps it: keys replaced, and the proxies,
+/// certificates and sync remote, which can hold credentials, left out.
+pub fn redacted_config(config: &Config) -> Config {
+    let mut config = config.clone();
+    for provider in &mut config.providers {
+        if provider.api_key.is_some() {
+            provider.api_key = Some(REDACTED_KEY.to_string());
+        }
+        provider.proxy = None;
+        provider.ca_cert = None;
+    }
+    config.sync = SyncPolicy::default();
+    config
+}
+
+/// The config a replay runs with: the recorded one without what would
+/// start programs on this machine, i.e. the opener, remote hosts, the
+/// sync remote and the system log.
+pub fn replayed_config(raw: &str) -> Result<String> {
+    let mut config: Config = toml::from_str(raw).context("Invalid config in the session log")?;
+    config.open = OpenPolicy::default();
+    config.remotes.clear();
+    config.sync = SyncPolicy::default();
+    config.audit = AuditPolicy::default();
+    Ok(toml::to_string_pretty(&config)?)
+}
+
+/// Refuses to start `program` while a session is replayed: a replay only
+/// shows what happened and never acts on this machine.
+pub fn refuse_spawn(program: &str) -> Result<()> {
+    if is_replaying() {
+        return Err(anyhow!(
+            "{} is not started while replaying a session",
+            program
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replays_recorded_replies_by_prompt_then_in_order() {
+        let lines = [
+            SessionEntry::Header {
+                version: SESSION_LOG_VERSION,
+                dexter: "0.1.0".to_string(),
+                recorded_at: Utc::now(),
+                cwd: "/work".to_string(),
+                config: String::new(),
+            },
+            SessionEntry::Input {
+                state: "Input".to_string(),
+                event: InputEvent::Key {
+                    key: "Enter".to_string(),
+                },
+            },
+            SessionEntry::Llm {
+                key: prompt_key("route", "shrink a.mp4"),
+                prompt: "shrink a.mp4".to_string(),
+                reply: Ok("ffmpeg".to_string()),
+            },
+            SessionEntry::Llm {
+                key: prompt_key("generate", "shrink a.mp4"),
+                prompt: "shrink a.mp4".to_string(),
+                reply: Err("rate limited".to_string()),
+            },
+            SessionEntry::Run {
+                output: Ok("done".to_string()),
+            },
+        ];
+        let raw: String = lines
+            .iter()
+            .map(|entry| serde_json::to_string(entry).unwrap() + "\n")
+            .collect();
+        let mut log = SessionLog::parse(&raw).unwrap();
+        assert_eq!(log.inputs.len(), 1);
+
+        let generate = log.take_reply(&prompt_key("generate", "shrink a.mp4"));
+        assert_eq!(generate.unwrap_err().to_string(), "rate limited");
+        assert_eq!(log.take_reply("changed prompt").unwrap(), "ffmpeg");
+        assert!(log.take_reply("more").is_err());
+        assert_eq!(next(&mut log.runs, "runs").unwrap(), "done");
+        assert!(next(&mut log.runs, "runs").is_err());
+
+        assert!(SessionLog::parse("").is_err());
+    }
+
+    #[test]
+    fn replayed_config_leaves_the_system_log_alone() {
+        let mut config = Config::default();
+        config.audit.system_log = true;
+        let raw = toml::to_string_pretty(&config).unwrap();
+        let replayed: Config = toml::from_str(&replayed_config(&raw).unwrap()).unwrap();
+        assert!(!replayed.audit.system_log);
+    }
+}
@@ -0,0 +1,170 @@
+//! A rough 0-100 score for how much a previewed command can break, with
+//! the reasons behind it, for `dexter preview --json` and other tools that
+//! decide whether a command may run unattended.
+
+use dexter_plugins::{Capability, Plugin, PreviewContent};
+use serde::Serialize;
+
+use crate::journal::FileOp;
+use crate::recycle::deletes_files;
+use crate::trash::DeleteMode;
+
+/// Files touched above which the size of a run counts on its own.
+const MANY_FILES: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskLevel {
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RiskAssessment {
+    pub score: u8,
+    pub level: RiskLevel,
+    /// One line per factor that raised the score.
+    pub reasons: Vec<String>,
+}
+
+impl RiskAssessment {
+    /// Scores `command` from what its preview showed: the planned file
+    /// `ops`, the rename `collisions` found, and `preview`, `None` when the
+    /// dry run failed.
+    pub fn assess(
+        plugin: &dyn Plugin,
+        command: &str,
+        ops: &[FileOp],
+        preview: Option<&PreviewContent>,
+        collisions: usize,
+        deletes: DeleteMode,
+    ) -> Self {
+        let mut score = 0u32;
+        let mut reasons = Vec::new();
+        let mut raise = |points: u32, reason: String| {
+            score += points;
+            reasons.push(reason);
+        };
+
+        if deletes_files(plugin.name(), command) {
+            match deletes {
+                DeleteMode::Permanent => raise(50, "deletes files permanently".to_string()),
+                _ => raise(20, "moves files to the trash".to_string()),
+            }
+        }
+        if collisions > 0 {
+            raise(40, format!("{} rename collision(s)", collisions));
+        }
+        let overwritten = ops
+            .iter()
+            .filter(|op| matches!(op, FileOp::Create { existed: true, .. }))
+            .count();
+        if overwritten > 0 {
+            raise(30, format!("overwrites {} existing file(s)", overwritten));
+        }
+        let renamed = ops
+            .iter()
+            .filter(|op| matches!(op, FileOp::Rename { .. }))
+            .count();
+        if renamed > 0 {
+            raise(10, format!("renames {} file(s)", renamed));
+        }
+        let touched = ops.len().max(preview.map_or(0, diff_count));
+        if touched > MANY_FILES {
+            raise(10, format!("touches {} files", touched));
+        }
+        if plugin.capabilities().contains(&Capability::Downloading) {
+            raise(10, "downloads from the network".to_string());
+        }
+        if preview.is_none() {
+            raise(15, "no dry run was possible".to_string());
+        }
+
+        let score = score.min(100) as u8;
+        let level = match score {
+            0..=24 => RiskLevel::Low,
+            25..=59 => RiskLevel::Medium,
+            _ => RiskLevel::High,
+        };
+        Self {
+            score,
+            level,
+            reasons,
+        }
+    }
+}
+
+/// Diff rows in `preview`, sections included.
+fn diff_count(preview: &PreviewContent) -> usize {
+    match preview {
+        PreviewContent::DiffList(items) => items.len(),
+        PreviewContent::Sections(parts) => parts.iter().map(diff_count).sum(),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dexter_plugins::{F2Plugin, FFmpegPlugin, JdupesPlugin};
+    use std::path::PathBuf;
+
+    #[test]
+    fn scores_deletes_overwrites_and_collisions() {
+        let text = PreviewContent::Text(String::new());
+        let plain = RiskAssessment::assess(
+            &FFmpegPlugin::default(),
+            "ffmpeg -i a.mkv a.mp4",
+            &[FileOp::Create {
+                path: PathBuf::from("a.mp4"),
+                existed: false,
+            }],
+            Some(&text),
+            0,
+            DeleteMode::Trash,
+        );
+        assert_eq!((plain.score, plain.level), (0, RiskLevel::Low));
+
+        let overwrite = RiskAssessment::assess(
+            &FFmpegPlugin::default(),
+            "ffmpeg -y -i a.mkv a.mp4",
+            &[FileOp::Create {
+                path: PathBuf::from("a.mp4"),
+                existed: true,
+            }],
+            None,
+            0,
+            DeleteMode::Trash,
+        );
+        assert_eq!((overwrite.score, overwrite.level), (45, RiskLevel::Medium));
+        assert_eq!(
+            overwrite.reasons,
+            ["overwrites 1 existing file(s)", "no dry run was possible"]
+        );
+
+        let rename = FileOp::Rename {
+            from: PathBuf::from("a"),
+            to: PathBuf::from("b"),
+        };
+        let collide = RiskAssessment::assess(
+            &F2Plugin::default(),
+            "f2 -f a -r b",
+            &[rename],
+            Some(&text),
+            2,
+            DeleteMode::Trash,
+        );
+        assert_eq!((collide.score, collide.level), (50, RiskLevel::Medium));
+
+        let delete = RiskAssessment::assess(
+            &JdupesPlugin::default(),
+            "jdupes -r -d -N .",
+            &[],
+            None,
+            0,
+            DeleteMode::Permanent,
+        );
+        assert_eq!((delete.score, delete.level), (65, RiskLevel::High));
+    }
+}
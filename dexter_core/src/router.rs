@@ -1,9 +1,22 @@
-use crate::context::FileContext;
-use crate::llm::LlmClient;
+use crate::config::{RouterStrategy, RoutingMode};
+use crate::context::{FileContext, DEFAULT_CONTEXT_FILES};
+use crate::language::language_section;
+use crate::llm::{JsonReply, LlmClient};
+use crate::session::session_section;
 use anyhow::{anyhow, Result};
-use dexter_plugins::Plugin;
+use dexter_plugins::{Capability, Plugin};
 use serde::Deserialize;
 use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+mod cache;
+mod strategy;
+
+use cache::{route_key, RouteCache};
+
+pub use strategy::{
+    AskRouting, HybridRouting, ModelRouting, RouteRequest, RoutingStrategy, RuleRouting,
+};
 
 #[derive(Debug, Clone)]
 pub enum ClarifySource {
@@ -17,6 +30,34 @@ pub struct ClarifyOption {
     pub label: String,
     pub detail: String,
     pub resolved_intent: String,
+    /// Can be picked together with the other combinable options; each one
+    /// then becomes a step of its own. Alternative readings of a request
+    /// are not combinable.
+    pub combinable: bool,
+}
+
+/// The steps for a clarify answer: the resolved intents of `selected`, in
+/// the order the options were offered.
+pub fn clarify_plan(options: &[ClarifyOption], selected: &[usize]) -> Result<Vec<String>> {
+    let mut picked: Vec<&ClarifyOption> = options
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| selected.contains(idx))
+        .map(|(_, option)| option)
+        .collect();
+    if picked.is_empty() {
+        return Err(anyhow!("No option selected"));
+    }
+    if picked.len() > 1 {
+        if let Some(option) = picked.iter().find(|option| !option.combinable) {
+            return Err(anyhow!("`{}` cannot be combined with others", option.label));
+        }
+    }
+    picked.dedup_by(|a, b| a.resolved_intent == b.resolved_intent);
+    Ok(picked
+        .into_iter()
+        .map(|option| option.resolved_intent.clone())
+        .collect())
 }
 
 #[derive(Debug, Clone)]
@@ -36,12 +77,118 @@ pub enum RouteOutcome {
     },
 }
 
+/// Per-plugin fit score reported by the router model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PluginScore {
+    pub plugin: String,
+    pub score: f32,
+    pub reason: String,
+}
+
+/// Why a route was chosen, kept alongside the outcome for the debug view.
+#[derive(Debug, Clone, Default)]
+pub struct RouteExplanation {
+    /// True when a deterministic rule answered before the model was asked.
+    pub rule_based: bool,
+    pub chosen_plugin: Option<String>,
+    pub confidence: Option<f32>,
+    pub reasoning: String,
+    /// Known plugins only, highest score first.
+    pub scores: Vec<PluginScore>,
+    /// Short human-readable account of the final decision.
+    pub decision: String,
+    pub raw_response: Option<String>,
+    /// Repeats an earlier decision for the same request without asking.
+    pub cached: bool,
+}
+
 #[derive(Debug, Deserialize)]
 struct RouterResponse {
     plugin_name: Option<String>,
     confidence: Option<f32>,
     reasoning: Option<String>,
     clarify: Option<RouterClarify>,
+    #[serde(default, deserialize_with = "deserialize_scores")]
+    scores: Vec<RouterScore>,
+}
+
+impl JsonReply for RouterResponse {
+    const KIND: &'static str = "router decision";
+
+    fn schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "plugin_name": { "type": ["string", "null"] },
+                "confidence": { "type": "number", "minimum": 0, "maximum": 1 },
+                "reasoning": { "type": "string" },
+                "clarify": {
+                    "type": ["object", "null"],
+                    "properties": {
+                        "question": { "type": "string" },
+                        "options": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "required": ["label", "detail", "resolved_intent"],
+                                "properties": {
+                                    "id": { "type": "string" },
+                                    "label": { "type": "string" },
+                                    "detail": { "type": "string" },
+                                    "resolved_intent": { "type": "string" },
+                                    "combinable": { "type": "boolean" }
+                                }
+                            }
+                        }
+                    }
+                },
+                "scores": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["plugin"],
+                        "properties": {
+                            "plugin": { "type": "string" },
+                            "score": { "type": "number" },
+                            "reason": { "type": "string" }
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    fn validate(&self) -> Result<()> {
+        match self.confidence {
+            Some(confidence) if !(0.0..=1.0).contains(&confidence) => {
+                Err(anyhow!("confidence {} is outside 0 to 1", confidence))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RouterScore {
+    plugin: String,
+    score: Option<f32>,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+/// Scores are advisory, so a malformed list must never fail the whole route.
+fn deserialize_scores<'de, D>(deserializer: D) -> std::result::Result<Vec<RouterScore>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+    let Some(items) = value.as_array() else {
+        return Ok(Vec::new());
+    };
+    Ok(items
+        .iter()
+        .filter_map(|item| serde_json::from_value::<RouterScore>(item.clone()).ok())
+        .collect())
 }
 
 #[derive(Debug, Deserialize)]
@@ -58,57 +205,255 @@ struct RouterClarifyOption {
     label: String,
     detail: String,
     resolved_intent: String,
+    #[serde(default)]
+    combinable: bool,
 }
 
+const ROUTER_USER_INPUT: &str = "Which plugin should be used for this intent?";
+
+/// Choices a clarify question offers unless `config.clarify_options` says
+/// otherwise.
+pub const DEFAULT_CLARIFY_OPTIONS: usize = 4;
+
+#[derive(Clone)]
 pub struct Router {
     llm_client: LlmClient,
+    offline: bool,
+    strategy: RouterStrategy,
+    routing: RoutingMode,
+    custom: Option<Arc<dyn RoutingStrategy>>,
+    preferred: Vec<String>,
+    /// Plugin runs in the working directory, most first.
+    dir_history: Vec<(String, u32)>,
+    session: Option<String>,
+    language: String,
+    context_file_limit: usize,
+    clarify_options: usize,
+    /// Shared by clones; dropped with the router when the config changes.
+    cache: Arc<Mutex<RouteCache>>,
 }
 
 impl Router {
     pub fn new(llm_client: LlmClient) -> Self {
-        Self { llm_client }
+        Self {
+            llm_client,
+            offline: false,
+            strategy: RouterStrategy::default(),
+            routing: RoutingMode::default(),
+            custom: None,
+            preferred: Vec::new(),
+            dir_history: Vec::new(),
+            session: None,
+            language: "auto".to_string(),
+            context_file_limit: DEFAULT_CONTEXT_FILES,
+            clarify_options: DEFAULT_CLARIFY_OPTIONS,
+            cache: Arc::default(),
+        }
+    }
+
+    /// Earlier runs the model may use to resolve follow-up intents.
+    pub fn with_session(mut self, transcript: Option<String>) -> Self {
+        self.session = transcript;
+        self
+    }
+
+    /// Plugins to favour when several fit, from the project's `.dexter.toml`.
+    pub fn with_preferred_plugins(mut self, preferred: Vec<String>) -> Self {
+        self.preferred = preferred;
+        self
+    }
+
+    /// How often each plugin ran in the working directory, most first
+    /// (`load_dir_uses`), so the model leans towards what it usually needs.
+    pub fn with_dir_history(mut self, uses: Vec<(String, u32)>) -> Self {
+        self.dir_history = uses;
+        self
+    }
+
+    /// `config.language`: what clarify questions are written in.
+    pub fn with_language(mut self, language: &str) -> Self {
+        self.language = language.to_string();
+        self
+    }
+
+    /// `config.context_file_limit`: how many files the routing prompt lists
+    /// by name before it summarizes the directory instead.
+    pub fn with_context_file_limit(mut self, limit: usize) -> Self {
+        self.context_file_limit = limit;
+        self
+    }
+
+    /// `config.clarify_options`: how many choices a clarify question offers
+    /// at most. Never below two, or there would be nothing to choose.
+    pub fn with_clarify_options(mut self, limit: usize) -> Self {
+        self.clarify_options = limit.max(2);
+        self
+    }
+
+    pub fn with_strategy(mut self, strategy: RouterStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// `models.routing`: which built-in strategy picks the plugin.
+    pub fn with_routing(mut self, routing: RoutingMode) -> Self {
+        self.routing = routing;
+        self
+    }
+
+    /// Routes with `strategy` instead of the one `models.routing` names.
+    pub fn with_routing_strategy(mut self, strategy: Arc<dyn RoutingStrategy>) -> Self {
+        self.custom = Some(strategy);
+        self
+    }
+
+    /// Offline routers never ask the model: the rule precheck and the
+    /// capability keywords pick the plugin, and ambiguous requests get a
+    /// rule-based clarify listing the candidate plugins.
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
     }
 
     pub fn llm_client(&self) -> &LlmClient {
         &self.llm_client
     }
 
+    /// The strategy the next request is routed with.
+    pub fn routing_strategy(&self) -> Arc<dyn RoutingStrategy> {
+        if let Some(custom) = &self.custom {
+            return custom.clone();
+        }
+        let model = || ModelRouting::new(self.llm_client.clone(), self.strategy);
+        match self.routing {
+            RoutingMode::Ask => Arc::new(AskRouting),
+            RoutingMode::Rules => Arc::new(RuleRouting),
+            _ if self.offline => Arc::new(RuleRouting),
+            RoutingMode::Llm => Arc::new(model()),
+            RoutingMode::Hybrid => Arc::new(HybridRouting::new(model())),
+        }
+    }
+
     pub async fn route(
         &self,
         user_input: &str,
         context: &FileContext,
         plugins: &[std::sync::Arc<dyn Plugin>],
     ) -> Result<RouteOutcome> {
-        if let Some(outcome) = rule_precheck(user_input) {
-            return Ok(outcome);
+        Ok(self.route_explained(user_input, context, plugins).await?.0)
+    }
+
+    /// Full text `route` would send to the model, or `None` when the rule
+    /// precheck or the routing strategy answers without one.
+    pub fn prompt_for(
+        &self,
+        user_input: &str,
+        context: &FileContext,
+        plugins: &[std::sync::Arc<dyn Plugin>],
+    ) -> Option<String> {
+        if rule_precheck(user_input, self.clarify_options).is_some() {
+            return None;
+        }
+        let request = self.request(user_input, context, plugins);
+        if self.cached_route(&request).is_some() {
+            return None;
         }
+        self.routing_strategy().prompt(&request)
+    }
 
-        let plugin_list: Vec<String> = plugins
-            .iter()
-            .map(|p| format!("- {}: {}", p.name(), p.get_doc_for_router()))
-            .collect();
+    /// Same as `route`, additionally returning the model's per-plugin scores
+    /// and reasoning so callers can show why a plugin won.
+    #[tracing::instrument(name = "routing", skip_all, fields(plugins = plugins.len()))]
+    pub async fn route_explained(
+        &self,
+        user_input: &str,
+        context: &FileContext,
+        plugins: &[std::sync::Arc<dyn Plugin>],
+    ) -> Result<(RouteOutcome, RouteExplanation)> {
+        if let Some(outcome) = rule_precheck(user_input, self.clarify_options) {
+            let explanation = RouteExplanation {
+                rule_based: true,
+                decision: match &outcome {
+                    RouteOutcome::Clarify { .. } => {
+                        "Rule precheck found an ambiguous request; asking for clarification."
+                            .to_string()
+                    }
+                    RouteOutcome::Unsupported { reason } => {
+                        format!("Rule precheck rejected the request: {}", reason)
+                    }
+                    RouteOutcome::Selected { plugin, .. } => {
+                        format!("Rule precheck selected {}.", plugin)
+                    }
+                },
+                ..RouteExplanation::default()
+            };
+            return Ok((outcome, explanation));
+        }
+        let request = self.request(user_input, context, plugins);
+        if let Some(cached) = self.cached_route(&request) {
+            return Ok(cached);
+        }
+        let (outcome, explanation) = self.routing_strategy().route(&request).await?;
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.insert(route_key(&request), &outcome, &explanation);
+        }
+        Ok((outcome, explanation))
+    }
 
-        let context_str = if let Some(summary) = &context.summary {
-            summary.clone()
-        } else if context.files.is_empty() {
-            "(no visible files in current directory)".to_string()
-        } else {
-            context
-                .files
-                .iter()
-                .enumerate()
-                .map(|(i, f)| format!("{}. {}", i + 1, f))
-                .collect::<Vec<_>>()
-                .join("\n")
-        };
+    /// The decision made earlier for the same request, if still kept.
+    fn cached_route(&self, request: &RouteRequest<'_>) -> Option<(RouteOutcome, RouteExplanation)> {
+        self.cache.lock().ok()?.get(route_key(request))
+    }
 
-        let system_prompt = format!(
-            r#"You are the Router Agent for Dexter.
+    fn request<'a>(
+        &'a self,
+        user_input: &'a str,
+        context: &'a FileContext,
+        plugins: &'a [std::sync::Arc<dyn Plugin>],
+    ) -> RouteRequest<'a> {
+        RouteRequest {
+            user_input,
+            context,
+            plugins,
+            preferred: &self.preferred,
+            dir_history: &self.dir_history,
+            session: self.session.as_deref(),
+            language: &self.language,
+            context_file_limit: self.context_file_limit,
+            clarify_options: self.clarify_options,
+        }
+    }
+}
+
+/// The router prompt for `request`, offering `plugins`.
+fn router_system_prompt(request: &RouteRequest<'_>, plugins: &[Arc<dyn Plugin>]) -> String {
+    let user_input = request.user_input;
+    let preferred = request.preferred;
+    let mut plugin_list: Vec<String> = plugins
+        .iter()
+        .map(|p| format!("- {}: {}", p.name(), p.get_doc_for_router()))
+        .collect();
+    if !preferred.is_empty() {
+        plugin_list.push(format!(
+            "\nThis project prefers {} when it fits as well as another plugin.",
+            preferred.join(", ")
+        ));
+    }
+    if let Some(hint) = dir_history_hint(request.dir_history, plugins) {
+        plugin_list.push(hint);
+    }
+
+    let context_str = request
+        .context
+        .for_prompt(user_input, request.context_file_limit);
+
+    format!(
+        r#"You are the Router Agent for Dexter.
 Your job is to map User Intent to the best available Plugin.
 
 ### USER INTENT:
 {}
-
+{}{}
 ### Available Plugins:
 {}
 
@@ -120,6 +465,9 @@ Output Format: JSON
   "plugin_name": "exact_name_from_list or 'none'",
   "confidence": 0.0_to_1.0,
   "reasoning": "why this plugin",
+  "scores": [
+    {{ "plugin": "name_from_list", "score": 0.0_to_1.0, "reason": "short fit explanation" }}
+  ],
   "clarify": {{
     "question": "only if the intent is ambiguous or needs user choice",
     "options": [
@@ -127,7 +475,8 @@ Output Format: JSON
         "id": "stable_id",
         "label": "short button label",
         "detail": "short explanation",
-        "resolved_intent": "a single clarified instruction"
+        "resolved_intent": "a single clarified instruction",
+        "combinable": false
       }}
     ]
   }}
@@ -137,138 +486,226 @@ Rules:
 - If no plugin fits, set plugin_name to "none" and confidence to 0.0.
 - Only include "clarify" when multiple plausible interpretations exist.
 - If you include "clarify", set plugin_name to "none".
+- Include one "scores" entry for every available plugin.
 - Every clarify option must be a single operation only.
 - Do NOT propose multi-step or chained operations inside one clarify option.
+- Set "combinable" to true only for independent operations the user may want together (each runs as its own step); alternative interpretations stay false.
 "#,
-            user_input,
-            plugin_list.join("\n"),
-            context_str
-        );
-
-        let response = self
-            .llm_client
-            .completion(
-                &system_prompt,
-                "Which plugin should be used for this intent?",
-            )
-            .await?;
-
-        let router_resp: RouterResponse = parse_router_response(&response)?;
-
-        if let Some(clarify) = router_resp.clarify {
-            if let Some(outcome) = validate_llm_clarify(clarify) {
-                return Ok(outcome);
-            }
-        }
-
-        let plugin_name = router_resp
-            .plugin_name
-            .unwrap_or_else(|| "none".to_string());
-        let confidence = router_resp.confidence.unwrap_or(0.0);
-        let reasoning = router_resp.reasoning.unwrap_or_default();
-
-        let plugin_set: HashSet<String> = plugins.iter().map(|p| p.name().to_string()).collect();
-
-        if plugin_name == "none" || confidence < 0.7 || !plugin_set.contains(&plugin_name) {
-            return Ok(RouteOutcome::Unsupported {
-                reason: if reasoning.is_empty() {
-                    "No suitable plugin found for this request.".to_string()
-                } else {
-                    reasoning
-                },
-            });
-        }
+        user_input,
+        session_section(request.session),
+        language_section(request.language, user_input),
+        plugin_list.join("\n"),
+        context_str
+    )
+}
 
-        Ok(RouteOutcome::Selected {
-            plugin: plugin_name,
-            confidence,
-            reasoning,
+/// Runs a plugin needs in a directory before the router hears about them.
+const DIR_HINT_MIN_RUNS: u32 = 3;
+/// Plugins the directory hint names at most.
+const DIR_HINT_PLUGINS: usize = 3;
+
+/// "This directory previously used ffmpeg 12 times", for the plugins on
+/// offer that ran there often enough to tell an established folder apart.
+fn dir_history_hint(
+    dir_history: &[(String, u32)],
+    plugins: &[std::sync::Arc<dyn Plugin>],
+) -> Option<String> {
+    let uses: Vec<String> = dir_history
+        .iter()
+        .filter(|(name, runs)| {
+            *runs >= DIR_HINT_MIN_RUNS && plugins.iter().any(|p| p.name() == name)
         })
+        .take(DIR_HINT_PLUGINS)
+        .map(|(name, runs)| format!("{} {} time(s)", name, runs))
+        .collect();
+    if uses.is_empty() {
+        return None;
     }
+    Some(format!(
+        "\nThis directory previously used {}. For an ambiguous intent, lean towards those; a clear intent for another plugin still wins.",
+        uses.join(", ")
+    ))
 }
 
-fn parse_router_response(response: &str) -> Result<RouterResponse> {
-    let clean_json = response
-        .trim()
-        .trim_start_matches("```json")
-        .trim_start_matches("```")
-        .trim_end_matches("```")
-        .trim();
-
-    if let Ok(parsed) = serde_json::from_str::<RouterResponse>(clean_json) {
-        return Ok(parsed);
+/// Keeps the plugins whose capability tags plausibly match `user_input`,
+/// plus untagged and explicitly named ones. Falls back to every plugin when
+/// no keyword matches, so unusual phrasing never hides the right tool.
+pub fn prefilter_plugins(
+    user_input: &str,
+    plugins: &[std::sync::Arc<dyn Plugin>],
+) -> Vec<std::sync::Arc<dyn Plugin>> {
+    let wanted = requested_capabilities(user_input);
+    if wanted.is_empty() {
+        return plugins.to_vec();
     }
+    let lower = user_input.to_lowercase();
+    let kept: Vec<std::sync::Arc<dyn Plugin>> = plugins
+        .iter()
+        .filter(|p| {
+            let caps = p.capabilities();
+            caps.is_empty() || caps.iter().any(|c| wanted.contains(c)) || names(&lower, p.name())
+        })
+        .cloned()
+        .collect();
+    if kept.is_empty() {
+        plugins.to_vec()
+    } else {
+        kept
+    }
+}
 
-    if let Some(candidate) = extract_first_json_object(response) {
-        if let Ok(parsed) = serde_json::from_str::<RouterResponse>(&candidate) {
-            return Ok(parsed);
-        }
+/// The plugins the keyword rules would pick for `user_input`: those named
+/// in the request, otherwise those tagged with the requested capabilities,
+/// narrowed to the project's preferred ones when any of them is left.
+fn rule_candidates<'a>(
+    user_input: &str,
+    plugins: &'a [std::sync::Arc<dyn Plugin>],
+    preferred: &[String],
+) -> Vec<&'a str> {
+    let lower = user_input.to_lowercase();
+    let named: Vec<&str> = plugins
+        .iter()
+        .map(|p| p.name())
+        .filter(|name| names(&lower, name))
+        .collect();
+    let candidates: Vec<&str> = if named.is_empty() {
+        let wanted = requested_capabilities(user_input);
+        plugins
+            .iter()
+            .filter(|p| p.capabilities().iter().any(|c| wanted.contains(c)))
+            .map(|p| p.name())
+            .collect()
+    } else {
+        named
+    };
+    // Project preferences break ties between keyword matches.
+    let favoured: Vec<&str> = candidates
+        .iter()
+        .copied()
+        .filter(|name| preferred.iter().any(|p| p == name))
+        .collect();
+    if favoured.is_empty() {
+        candidates
+    } else {
+        favoured
     }
+}
 
-    Err(anyhow!(
-        "Failed to parse Router JSON from model response. Raw response snippet: {}",
-        truncate_router_error(response)
-    ))
+/// Whether lowercased `text` names the plugin `name` as a word of its own,
+/// or the last part of a path: "f2" is named in "run f2" and "~/bin/f2",
+/// not in "of2x".
+fn names(text: &str, name: &str) -> bool {
+    let name = name.to_lowercase();
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    text.match_indices(&name).any(|(at, _)| {
+        !is_word(text[..at].chars().next_back()) && !is_word(text[at + name.len()..].chars().next())
+    })
 }
 
-fn extract_first_json_object(input: &str) -> Option<String> {
-    let mut start = None;
-    let mut depth = 0usize;
-    let mut in_string = false;
-    let mut escaped = false;
+/// A rule-based clarify offering the first `limit` of `plugins` for
+/// `user_input`.
+fn plugin_clarify(user_input: &str, plugins: &[&str], limit: usize) -> RouteOutcome {
+    RouteOutcome::Clarify {
+        question: "Several tools fit this request. Which should Dexter use?".to_string(),
+        options: plugins
+            .iter()
+            .take(limit)
+            .map(|plugin| ClarifyOption {
+                id: plugin.to_string(),
+                label: plugin.to_string(),
+                detail: format!("Run the request with {}.", plugin),
+                resolved_intent: format!("{} (use {})", user_input.trim(), plugin),
+                combinable: false,
+            })
+            .collect(),
+        source: ClarifySource::Rule,
+    }
+}
 
-    for (idx, ch) in input.char_indices() {
-        if in_string {
-            if escaped {
-                escaped = false;
-                continue;
-            }
-            if ch == '\\' {
-                escaped = true;
-                continue;
+/// Routing without a model: a plugin named in the request wins, otherwise
+/// the plugins tagged with the requested capabilities are the candidates.
+fn offline_route(
+    user_input: &str,
+    plugins: &[std::sync::Arc<dyn Plugin>],
+    preferred: &[String],
+    clarify_options: usize,
+) -> (RouteOutcome, RouteExplanation) {
+    let candidates = rule_candidates(user_input, plugins, preferred);
+    let mut explanation = RouteExplanation {
+        rule_based: true,
+        ..RouteExplanation::default()
+    };
+
+    let outcome = match candidates.as_slice() {
+        [] => {
+            explanation.decision = "Keyword rules matched no plugin.".to_string();
+            RouteOutcome::Unsupported {
+                reason: "Rule-based routing couldn't match this request to a tool. Name the tool (e.g. \"ffmpeg\"), or let the router model decide (offline mode off, routing = \"llm\")."
+                    .to_string(),
             }
-            if ch == '"' {
-                in_string = false;
+        }
+        [plugin] => {
+            explanation.chosen_plugin = Some(plugin.to_string());
+            explanation.decision = format!("Keyword rules selected {}.", plugin);
+            RouteOutcome::Selected {
+                plugin: plugin.to_string(),
+                confidence: 1.0,
+                reasoning: "Matched by keyword rules.".to_string(),
             }
-            continue;
         }
+        several => {
+            explanation.decision = format!(
+                "Keyword rules matched {}; asking which to use.",
+                several.join(", ")
+            );
+            plugin_clarify(user_input, several, clarify_options)
+        }
+    };
+    (outcome, explanation)
+}
 
-        match ch {
-            '"' => in_string = true,
-            '{' => {
-                if depth == 0 {
-                    start = Some(idx);
-                }
-                depth += 1;
-            }
-            '}' => {
-                if depth == 0 {
-                    continue;
-                }
-                depth -= 1;
-                if depth == 0 {
-                    if let Some(s) = start {
-                        return Some(input[s..=idx].to_string());
-                    }
+pub(crate) fn requested_capabilities(user_input: &str) -> HashSet<Capability> {
+    let lower = user_input.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    let mut wanted: HashSet<Capability> = Capability::ALL
+        .into_iter()
+        .filter(|cap| {
+            cap.keywords().iter().any(|kw| {
+                if kw.contains(' ') {
+                    lower.contains(kw)
+                } else if kw.len() >= 4 {
+                    words.iter().any(|w| w.starts_with(kw))
+                } else {
+                    words.contains(kw)
                 }
-            }
-            _ => {}
-        }
+            })
+        })
+        .collect();
+    if lower.contains("://") {
+        wanted.insert(Capability::Downloading);
     }
-
-    None
+    wanted
 }
 
-fn truncate_router_error(text: &str) -> String {
-    const MAX: usize = 320;
-    if text.len() > MAX {
-        format!("{}...", &text[..MAX])
-    } else {
-        text.to_string()
-    }
+fn normalize_scores(scores: Vec<RouterScore>, plugin_set: &HashSet<String>) -> Vec<PluginScore> {
+    let mut seen = HashSet::new();
+    let mut out: Vec<PluginScore> = scores
+        .into_iter()
+        .filter(|s| plugin_set.contains(&s.plugin) && seen.insert(s.plugin.clone()))
+        .map(|s| PluginScore {
+            plugin: s.plugin,
+            score: s.score.unwrap_or(0.0).clamp(0.0, 1.0),
+            reason: s.reason.unwrap_or_default(),
+        })
+        .collect();
+    out.sort_by(|a, b| b.score.total_cmp(&a.score));
+    out
 }
 
-fn rule_precheck(user_input: &str) -> Option<RouteOutcome> {
+fn rule_precheck(user_input: &str, clarify_options: usize) -> Option<RouteOutcome> {
     let lower = user_input.to_lowercase();
     let operation_intents = detect_operation_intents(&lower);
 
@@ -276,14 +713,14 @@ fn rule_precheck(user_input: &str) -> Option<RouteOutcome> {
     if operation_intents.len() >= 2 && has_step_connector(&lower) {
         let options: Vec<ClarifyOption> = operation_intents
             .iter()
-            .take(4)
+            .take(clarify_options)
             .map(|intent| clarify_option_for_intent(*intent))
             .collect();
 
         if options.len() >= 2 {
             return Some(RouteOutcome::Clarify {
                 question:
-                    "This request mixes multiple operations. Which single operation should Dexter run first?"
+                    "This request mixes multiple operations. Which should Dexter run? Pick several to run them one after another."
                         .to_string(),
                 options,
                 source: ClarifySource::Rule,
@@ -323,6 +760,7 @@ fn rule_precheck(user_input: &str) -> Option<RouteOutcome> {
                     "rename file extension from {} to {} (rename only, no conversion)",
                     src_label, dst_label
                 ),
+                combinable: false,
             },
             ClarifyOption {
                 id: "convert_format".to_string(),
@@ -332,6 +770,7 @@ fn rule_precheck(user_input: &str) -> Option<RouteOutcome> {
                     "convert media format from {} to {}",
                     src_label, dst_label
                 ),
+                combinable: false,
             },
         ];
         return Some(RouteOutcome::Clarify {
@@ -344,18 +783,19 @@ fn rule_precheck(user_input: &str) -> Option<RouteOutcome> {
     None
 }
 
-fn validate_llm_clarify(clarify: RouterClarify) -> Option<RouteOutcome> {
+/// The model's clarify as offered to the user, cut to `limit` options.
+fn validate_llm_clarify(clarify: RouterClarify, limit: usize) -> Option<RouteOutcome> {
     let question = clarify.question?.trim().to_string();
     if question.is_empty() || question.len() > 120 {
         return None;
     }
 
-    if clarify.options.len() < 2 || clarify.options.len() > 4 {
+    if clarify.options.len() < 2 {
         return None;
     }
 
     let mut options: Vec<ClarifyOption> = Vec::new();
-    for (i, opt) in clarify.options.into_iter().enumerate() {
+    for (i, opt) in clarify.options.into_iter().take(limit).enumerate() {
         if opt.label.trim().is_empty()
             || opt.detail.trim().is_empty()
             || opt.resolved_intent.trim().is_empty()
@@ -377,6 +817,7 @@ fn validate_llm_clarify(clarify: RouterClarify) -> Option<RouteOutcome> {
             label: opt.label,
             detail: opt.detail,
             resolved_intent: opt.resolved_intent,
+            combinable: opt.combinable,
         });
     }
 
@@ -497,6 +938,7 @@ fn clarify_option_for_intent(intent: OperationIntent) -> ClarifyOption {
             resolved_intent:
                 "Rename files only (no OCR, conversion, compression, or duplicate scan)."
                     .to_string(),
+            combinable: true,
         },
         OperationIntent::Ocr => ClarifyOption {
             id: "ocr_only".to_string(),
@@ -504,6 +946,7 @@ fn clarify_option_for_intent(intent: OperationIntent) -> ClarifyOption {
             detail: "Only run OCR on PDF files.".to_string(),
             resolved_intent:
                 "Run OCR on PDF files only (no compression, renaming, or other steps).".to_string(),
+            combinable: true,
         },
         OperationIntent::Compress => ClarifyOption {
             id: "compress_only".to_string(),
@@ -512,6 +955,7 @@ fn clarify_option_for_intent(intent: OperationIntent) -> ClarifyOption {
             resolved_intent:
                 "Compress files only (no OCR, renaming, format conversion, or duplicate scan)."
                     .to_string(),
+            combinable: true,
         },
         OperationIntent::Convert => ClarifyOption {
             id: "convert_only".to_string(),
@@ -520,6 +964,7 @@ fn clarify_option_for_intent(intent: OperationIntent) -> ClarifyOption {
             resolved_intent:
                 "Convert file format only (no renaming, OCR, compression, or duplicate scan)."
                     .to_string(),
+            combinable: true,
         },
         OperationIntent::ExtractAudio => ClarifyOption {
             id: "extract_audio".to_string(),
@@ -527,6 +972,7 @@ fn clarify_option_for_intent(intent: OperationIntent) -> ClarifyOption {
             detail: "Only extract audio from media.".to_string(),
             resolved_intent:
                 "Extract audio only (no renaming, OCR, compression, or duplicate scan).".to_string(),
+            combinable: true,
         },
         OperationIntent::Dedupe => ClarifyOption {
             id: "dedupe_only".to_string(),
@@ -535,6 +981,7 @@ fn clarify_option_for_intent(intent: OperationIntent) -> ClarifyOption {
             resolved_intent:
                 "Scan duplicate files only (no renaming, OCR, compression, or conversion)."
                     .to_string(),
+            combinable: true,
         },
     }
 }
@@ -636,46 +1083,219 @@ fn image_extensions() -> HashSet<&'static str> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::llm::parse_json_reply;
+
+    #[test]
+    fn prefilter_keeps_plausible_plugins_only() {
+        use dexter_plugins::{F2Plugin, FFmpegPlugin, QpdfPlugin, YtDlpPlugin};
+        let plugins: Vec<std::sync::Arc<dyn Plugin>> = vec![
+            std::sync::Arc::new(F2Plugin::default()),
+            std::sync::Arc::new(FFmpegPlugin::default()),
+            std::sync::Arc::new(QpdfPlugin::default()),
+            std::sync::Arc::new(YtDlpPlugin::default()),
+        ];
+        let names = |input: &str| -> Vec<String> {
+            prefilter_plugins(input, &plugins)
+                .iter()
+                .map(|p| p.name().to_string())
+                .collect()
+        };
+
+        assert_eq!(names("convert these videos to mkv"), ["ffmpeg", "yt-dlp"]);
+        assert_eq!(names("Renaming photos by date"), ["f2"]);
+        assert_eq!(names("decrypt report.pdf"), ["qpdf"]);
+        assert_eq!(names("grab https://example.com/watch?v=1"), ["yt-dlp"]);
+        assert_eq!(names("use qpdf on the video"), ["ffmpeg", "qpdf", "yt-dlp"]);
+        // Nothing recognisable: every plugin stays a candidate.
+        assert_eq!(names("do the usual").len(), 4);
+    }
+
+    #[test]
+    fn dir_hint_names_established_plugins_on_offer() {
+        use dexter_plugins::{F2Plugin, FFmpegPlugin, QpdfPlugin};
+        let plugins: Vec<std::sync::Arc<dyn Plugin>> = vec![
+            std::sync::Arc::new(F2Plugin::default()),
+            std::sync::Arc::new(FFmpegPlugin::default()),
+            std::sync::Arc::new(QpdfPlugin::default()),
+        ];
+        let uses = |list: &[(&str, u32)]| -> Vec<(String, u32)> {
+            list.iter()
+                .map(|(name, runs)| (name.to_string(), *runs))
+                .collect()
+        };
+
+        let hint = dir_history_hint(
+            &uses(&[("ffmpeg", 12), ("pandoc", 9), ("f2", 3), ("qpdf", 2)]),
+            &plugins,
+        )
+        .unwrap();
+        assert!(
+            hint.starts_with("\nThis directory previously used ffmpeg 12 time(s), f2 3 time(s).")
+        );
+        assert_eq!(dir_history_hint(&uses(&[("qpdf", 2)]), &plugins), None);
+
+        let context = FileContext::default();
+        let dir_history = uses(&[("ffmpeg", 12)]);
+        let request = RouteRequest {
+            user_input: "shrink these",
+            context: &context,
+            plugins: &plugins,
+            preferred: &[],
+            dir_history: &dir_history,
+            session: None,
+            language: "auto",
+            context_file_limit: DEFAULT_CONTEXT_FILES,
+            clarify_options: DEFAULT_CLARIFY_OPTIONS,
+        };
+        let prompt = router_system_prompt(&request, &plugins);
+        assert!(prompt.contains("previously used ffmpeg 12 time(s)"));
+    }
 
     #[test]
-    fn parse_router_response_accepts_markdown_wrapped_json() {
+    fn offline_route_uses_names_and_keywords_only() {
+        use dexter_plugins::{F2Plugin, FFmpegPlugin, QpdfPlugin, YtDlpPlugin};
+        let plugins: Vec<std::sync::Arc<dyn Plugin>> = vec![
+            std::sync::Arc::new(F2Plugin::default()),
+            std::sync::Arc::new(FFmpegPlugin::default()),
+            std::sync::Arc::new(QpdfPlugin::default()),
+            std::sync::Arc::new(YtDlpPlugin::default()),
+        ];
+
+        let (outcome, explanation) = offline_route(
+            "Renaming photos by date",
+            &plugins,
+            &[],
+            DEFAULT_CLARIFY_OPTIONS,
+        );
+        assert!(matches!(outcome, RouteOutcome::Selected { ref plugin, .. } if plugin == "f2"));
+        assert!(explanation.rule_based && explanation.raw_response.is_none());
+
+        let (outcome, _) = offline_route(
+            "convert these videos to mkv",
+            &plugins,
+            &[],
+            DEFAULT_CLARIFY_OPTIONS,
+        );
+        let RouteOutcome::Clarify {
+            options, source, ..
+        } = outcome
+        else {
+            panic!("expected a clarify");
+        };
+        assert!(matches!(source, ClarifySource::Rule));
+        assert_eq!(options.len(), 2);
+        // Picking an option names the plugin, which settles the re-route.
+        let (outcome, _) = offline_route(
+            &options[0].resolved_intent,
+            &plugins,
+            &[],
+            DEFAULT_CLARIFY_OPTIONS,
+        );
+        assert!(matches!(outcome, RouteOutcome::Selected { ref plugin, .. } if plugin == "ffmpeg"));
+
+        let preferred = ["yt-dlp".to_string()];
+        let (outcome, _) = offline_route(
+            "convert these videos to mkv",
+            &plugins,
+            &preferred,
+            DEFAULT_CLARIFY_OPTIONS,
+        );
+        assert!(matches!(outcome, RouteOutcome::Selected { ref plugin, .. } if plugin == "yt-dlp"));
+
+        let (outcome, _) = offline_route("do the usual", &plugins, &[], DEFAULT_CLARIFY_OPTIONS);
+        assert!(matches!(outcome, RouteOutcome::Unsupported { .. }));
+
+        assert!(names("rename with ~/bin/f2.", "f2"));
+        assert!(!names("rename the scans of2x", "f2"));
+        assert!(!names("use qpdfium", "qpdf"));
+    }
+
+    #[test]
+    fn router_reply_accepts_markdown_wrapped_json() {
         let raw = r#"```json
 {"plugin_name":"ffmpeg","confidence":0.92,"reasoning":"media task"}
 ```"#;
-        let parsed = parse_router_response(raw).expect("should parse");
+        let parsed = parse_json_reply::<RouterResponse>(raw).expect("should parse");
         assert_eq!(parsed.plugin_name.as_deref(), Some("ffmpeg"));
+
+        // A percentage instead of a fraction goes back for repair.
+        let raw = r#"{"plugin_name":"ffmpeg","confidence":92}"#;
+        assert!(parse_json_reply::<RouterResponse>(raw).is_err());
     }
 
     #[test]
-    fn parse_router_response_extracts_json_from_noise() {
+    fn router_reply_extracts_json_from_noise() {
         let raw = r#"I think this is correct:
 {"plugin_name":"f2","confidence":0.91,"reasoning":"rename task"}
 Thanks!"#;
-        let parsed = parse_router_response(raw).expect("should parse");
+        let parsed = parse_json_reply::<RouterResponse>(raw).expect("should parse");
         assert_eq!(parsed.plugin_name.as_deref(), Some("f2"));
     }
 
     #[test]
-    fn truncate_router_error_limits_output_size() {
-        let long = "x".repeat(500);
-        let truncated = truncate_router_error(&long);
-        assert!(truncated.ends_with("..."));
-        assert!(truncated.len() <= 323);
+    fn router_reply_tolerates_malformed_scores() {
+        let raw = r#"{"plugin_name":"f2","confidence":0.8,"scores":[{"plugin":"f2","score":0.8},{"score":0.1},"bad"]}"#;
+        let parsed = parse_json_reply::<RouterResponse>(raw).expect("should parse");
+        assert_eq!(parsed.scores.len(), 1);
+
+        let raw = r#"{"plugin_name":"f2","confidence":0.8,"scores":"n/a"}"#;
+        let parsed = parse_json_reply::<RouterResponse>(raw).expect("should parse");
+        assert!(parsed.scores.is_empty());
+    }
+
+    #[test]
+    fn normalize_scores_keeps_known_plugins_sorted_by_score() {
+        let plugin_set: HashSet<String> = ["f2", "ffmpeg"].iter().map(|s| s.to_string()).collect();
+        let scores = vec![
+            RouterScore {
+                plugin: "ffmpeg".to_string(),
+                score: Some(0.4),
+                reason: Some("could transcode".to_string()),
+            },
+            RouterScore {
+                plugin: "imagemagick".to_string(),
+                score: Some(0.9),
+                reason: None,
+            },
+            RouterScore {
+                plugin: "f2".to_string(),
+                score: Some(1.7),
+                reason: None,
+            },
+            RouterScore {
+                plugin: "ffmpeg".to_string(),
+                score: Some(0.99),
+                reason: None,
+            },
+        ];
+        let normalized = normalize_scores(scores, &plugin_set);
+        assert_eq!(
+            normalized
+                .iter()
+                .map(|s| (s.plugin.as_str(), s.score))
+                .collect::<Vec<_>>(),
+            vec![("f2", 1.0), ("ffmpeg", 0.4)]
+        );
     }
 
     #[test]
     fn rule_precheck_mixed_workflow_yields_single_operation_clarify() {
         let input = "把 PDF 做 OCR 后再压缩，并把结果重命名加日期后缀";
-        let outcome = rule_precheck(input).expect("should trigger clarify");
+        let outcome =
+            rule_precheck(input, DEFAULT_CLARIFY_OPTIONS).expect("should trigger clarify");
         match outcome {
             RouteOutcome::Clarify {
                 source, options, ..
             } => {
                 assert!(matches!(source, ClarifySource::Rule));
                 assert!(options.len() >= 2);
-                for opt in options {
+                for opt in &options {
                     assert!(!looks_mixed_operation_intent(&opt.resolved_intent));
+                    assert!(opt.combinable);
                 }
+                let plan = clarify_plan(&options, &[1, 0]).unwrap();
+                assert_eq!(plan[0], options[0].resolved_intent);
+                assert_eq!(plan[1], options[1].resolved_intent);
             }
             _ => panic!("expected clarify outcome"),
         }
@@ -692,23 +1312,27 @@ Thanks!"#;
                     detail: "Do OCR and compression together".to_string(),
                     resolved_intent: "First do OCR then compress and then rename the file."
                         .to_string(),
+                    combinable: true,
                 },
                 RouterClarifyOption {
                     id: Some("ocr".to_string()),
                     label: "OCR only".to_string(),
                     detail: "Only OCR".to_string(),
                     resolved_intent: "Run OCR on PDF files only.".to_string(),
+                    combinable: true,
                 },
                 RouterClarifyOption {
                     id: Some("rename".to_string()),
                     label: "Rename only".to_string(),
                     detail: "Only rename".to_string(),
                     resolved_intent: "Rename files only.".to_string(),
+                    combinable: true,
                 },
             ],
         };
 
-        let out = validate_llm_clarify(clarify).expect("should keep non-mixed options");
+        let out = validate_llm_clarify(clarify, DEFAULT_CLARIFY_OPTIONS)
+            .expect("should keep non-mixed options");
         match out {
             RouteOutcome::Clarify { options, .. } => {
                 assert_eq!(options.len(), 2);
@@ -717,4 +1341,69 @@ Thanks!"#;
             _ => panic!("expected clarify"),
         }
     }
+
+    #[test]
+    fn clarify_plan_refuses_to_combine_alternatives() {
+        let outcome = rule_precheck("rename a.mov to a.mp4", DEFAULT_CLARIFY_OPTIONS)
+            .expect("should trigger clarify");
+        let RouteOutcome::Clarify { options, .. } = outcome else {
+            panic!("expected clarify");
+        };
+        assert!(options.iter().all(|o| !o.combinable));
+        assert_eq!(clarify_plan(&options, &[1]).unwrap().len(), 1);
+        assert!(clarify_plan(&options, &[0, 1]).is_err());
+        assert!(clarify_plan(&options, &[]).is_err());
+    }
+
+    struct CountingRouting(std::sync::atomic::AtomicUsize);
+
+    #[async_trait::async_trait]
+    impl RoutingStrategy for CountingRouting {
+        async fn route(
+            &self,
+            _request: &RouteRequest<'_>,
+        ) -> Result<(RouteOutcome, RouteExplanation)> {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            let outcome = RouteOutcome::Unsupported {
+                reason: "none fits".to_string(),
+            };
+            Ok((outcome, RouteExplanation::default()))
+        }
+    }
+
+    #[tokio::test]
+    async fn repeats_a_decision_until_the_context_changes() {
+        let strategy = Arc::new(CountingRouting(Default::default()));
+        let router = Router::new(LlmClient::new(
+            String::new(),
+            "http://127.0.0.1:9".to_string(),
+            "m".to_string(),
+        ))
+        .with_routing_strategy(strategy.clone());
+        let mut context = FileContext {
+            files: vec!["talk.mkv".to_string()],
+            ..FileContext::default()
+        };
+        let calls = || strategy.0.load(std::sync::atomic::Ordering::SeqCst);
+
+        let (_, first) = router
+            .route_explained("shrink talk.mkv", &context, &[])
+            .await
+            .unwrap();
+        assert!(!first.cached);
+        let (_, again) = router
+            .clone()
+            .route_explained("Shrink  talk.mkv", &context, &[])
+            .await
+            .unwrap();
+        assert!(again.cached);
+        assert_eq!(calls(), 1);
+
+        context.files.push("talk.mp4".to_string());
+        router
+            .route_explained("shrink talk.mkv", &context, &[])
+            .await
+            .unwrap();
+        assert_eq!(calls(), 2);
+    }
 }
@@ -0,0 +1,138 @@
+//! Recent routing decisions, so retrying a request or asking it again in
+//! other words does not ask the router model a second time. Entries are
+//! keyed on the intent with case and spacing normalized, the plugins on
+//! offer and what the model would see of the context: a changed file list,
+//! session or project preference is a different key.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+
+use super::{RouteExplanation, RouteOutcome, RouteRequest};
+
+/// Decisions kept; the least recently used one goes first.
+const ROUTE_CACHE_CAPACITY: usize = 32;
+
+#[derive(Debug, Default)]
+pub(crate) struct RouteCache {
+    entries: VecDeque<(u64, RouteOutcome, RouteExplanation)>,
+}
+
+impl RouteCache {
+    /// The decision stored for `key`, marked as cached and moved to the
+    /// front.
+    pub(crate) fn get(&mut self, key: u64) -> Option<(RouteOutcome, RouteExplanation)> {
+        let idx = self.entries.iter().position(|(k, _, _)| *k == key)?;
+        let entry = self.entries.remove(idx)?;
+        let (_, outcome, mut explanation) = entry.clone();
+        self.entries.push_back(entry);
+        explanation.cached = true;
+        Some((outcome, explanation))
+    }
+
+    pub(crate) fn insert(
+        &mut self,
+        key: u64,
+        outcome: &RouteOutcome,
+        explanation: &RouteExplanation,
+    ) {
+        self.entries.retain(|(k, _, _)| *k != key);
+        if self.entries.len() >= ROUTE_CACHE_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries
+            .push_back((key, outcome.clone(), explanation.clone()));
+    }
+}
+
+/// Key for `request`: equal for requests the router would decide alike.
+pub(crate) fn route_key(request: &RouteRequest<'_>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    normalize_intent(request.user_input).hash(&mut hasher);
+    let mut plugins: Vec<&str> = request.plugins.iter().map(|p| p.name()).collect();
+    plugins.sort_unstable();
+    plugins.hash(&mut hasher);
+    request.preferred.hash(&mut hasher);
+    request.dir_history.hash(&mut hasher);
+    request.session.hash(&mut hasher);
+    request.language.hash(&mut hasher);
+    let context = request.context;
+    context.files.hash(&mut hasher);
+    context.summary.hash(&mut hasher);
+    context.exact.hash(&mut hasher);
+    for ocr in &context.ocr {
+        ocr.text.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+fn normalize_intent(intent: &str) -> String {
+    intent
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::FileContext;
+
+    fn request<'a>(intent: &'a str, context: &'a FileContext) -> RouteRequest<'a> {
+        RouteRequest {
+            user_input: intent,
+            context,
+            plugins: &[],
+            preferred: &[],
+            dir_history: &[],
+            session: None,
+            language: "auto",
+            context_file_limit: crate::context::DEFAULT_CONTEXT_FILES,
+            clarify_options: crate::router::DEFAULT_CLARIFY_OPTIONS,
+        }
+    }
+
+    #[test]
+    fn keys_on_the_normalized_intent_and_the_context() {
+        let context = FileContext {
+            files: vec!["talk.mkv".to_string()],
+            ..FileContext::default()
+        };
+        let key = route_key(&request("Convert talk.mkv  to mp4", &context));
+        assert_eq!(
+            key,
+            route_key(&request(" convert TALK.mkv to mp4\n", &context))
+        );
+        assert_ne!(
+            key,
+            route_key(&request("convert talk.mkv to webm", &context))
+        );
+        let changed = FileContext {
+            files: vec!["talk.mkv".to_string(), "talk.mp4".to_string()],
+            ..FileContext::default()
+        };
+        assert_ne!(
+            key,
+            route_key(&request("convert talk.mkv to mp4", &changed))
+        );
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_decision() {
+        let outcome = RouteOutcome::Unsupported {
+            reason: "no".to_string(),
+        };
+        let mut cache = RouteCache::default();
+        for key in 0..ROUTE_CACHE_CAPACITY as u64 {
+            cache.insert(key, &outcome, &RouteExplanation::default());
+        }
+        assert!(cache
+            .get(0)
+            .is_some_and(|(_, explanation)| explanation.cached));
+        cache.insert(99, &outcome, &RouteExplanation::default());
+        assert!(cache.get(0).is_some());
+        assert!(cache.get(1).is_none());
+        assert!(cache.get(99).is_some());
+    }
+}
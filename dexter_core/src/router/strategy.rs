@@ -0,0 +1,351 @@
+//! How a request is matched to a plugin once the rule precheck let it
+//! through: by the router model, by the keyword rules, by the rules with the
+//! model as fallback, or by asking every time. `models.routing` picks one of
+//! these; `Router::with_routing_strategy` plugs in any other.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use dexter_plugins::Plugin;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use super::{
+    normalize_scores, offline_route, plugin_clarify, prefilter_plugins, router_system_prompt,
+    rule_candidates, validate_llm_clarify, RouteExplanation, RouteOutcome, RouterResponse,
+    ROUTER_USER_INPUT,
+};
+use crate::config::RouterStrategy;
+use crate::context::FileContext;
+use crate::llm::LlmClient;
+
+/// Everything a strategy may look at to pick a plugin.
+pub struct RouteRequest<'a> {
+    pub user_input: &'a str,
+    pub context: &'a FileContext,
+    pub plugins: &'a [Arc<dyn Plugin>],
+    /// Plugins to favour when several fit, from the project's `.dexter.toml`.
+    pub preferred: &'a [String],
+    /// Plugin runs in the working directory, most first.
+    pub dir_history: &'a [(String, u32)],
+    pub session: Option<&'a str>,
+    pub language: &'a str,
+    /// Files the prompt lists by name before it summarizes the directory.
+    pub context_file_limit: usize,
+    /// Choices a clarify question offers at most.
+    pub clarify_options: usize,
+}
+
+#[async_trait]
+pub trait RoutingStrategy: Send + Sync {
+    async fn route(&self, request: &RouteRequest<'_>) -> Result<(RouteOutcome, RouteExplanation)>;
+
+    /// The prompt `route` would send to a model; `None` when it sends none.
+    fn prompt(&self, _request: &RouteRequest<'_>) -> Option<String> {
+        None
+    }
+}
+
+/// Asks the router model, which scores every candidate plugin and may ask
+/// back when the request reads several ways.
+pub struct ModelRouting {
+    client: LlmClient,
+    strategy: RouterStrategy,
+}
+
+impl ModelRouting {
+    pub fn new(client: LlmClient, strategy: RouterStrategy) -> Self {
+        Self { client, strategy }
+    }
+}
+
+#[async_trait]
+impl RoutingStrategy for ModelRouting {
+    async fn route(&self, request: &RouteRequest<'_>) -> Result<(RouteOutcome, RouteExplanation)> {
+        let plugins = prefilter_plugins(request.user_input, request.plugins);
+        tracing::debug!(
+            candidates = ?plugins.iter().map(|p| p.name()).collect::<Vec<_>>(),
+            "router prefilter"
+        );
+        let system_prompt = system_prompt(request, &plugins);
+
+        let response = match self.strategy {
+            RouterStrategy::Fallback => {
+                self.client
+                    .json_completion::<RouterResponse>(&system_prompt, ROUTER_USER_INPUT)
+                    .await?
+            }
+            RouterStrategy::Race => {
+                self.client
+                    .json_race_completion::<RouterResponse>(&system_prompt, ROUTER_USER_INPUT)
+                    .await?
+            }
+        };
+
+        let router_resp = response.value;
+        let plugin_set: HashSet<String> = plugins.iter().map(|p| p.name().to_string()).collect();
+        let mut explanation = RouteExplanation {
+            rule_based: false,
+            chosen_plugin: router_resp.plugin_name.clone(),
+            confidence: router_resp.confidence,
+            reasoning: router_resp.reasoning.clone().unwrap_or_default(),
+            scores: normalize_scores(router_resp.scores, &plugin_set),
+            decision: String::new(),
+            raw_response: Some(response.raw),
+            cached: false,
+        };
+
+        if let Some(clarify) = router_resp.clarify {
+            if let Some(outcome) = validate_llm_clarify(clarify, request.clarify_options) {
+                explanation.decision =
+                    "Model reported multiple plausible interpretations; asking for clarification."
+                        .to_string();
+                return Ok((outcome, explanation));
+            }
+        }
+
+        let plugin_name = router_resp
+            .plugin_name
+            .unwrap_or_else(|| "none".to_string());
+        let confidence = router_resp.confidence.unwrap_or(0.0);
+        let reasoning = router_resp.reasoning.unwrap_or_default();
+
+        if plugin_name == "none" || confidence < 0.7 || !plugin_set.contains(&plugin_name) {
+            explanation.decision = if plugin_name == "none" {
+                "Model found no suitable plugin.".to_string()
+            } else if !plugin_set.contains(&plugin_name) {
+                format!("Model picked unknown plugin '{}'.", plugin_name)
+            } else {
+                format!(
+                    "Model picked {} but confidence {:.2} is below the 0.70 threshold.",
+                    plugin_name, confidence
+                )
+            };
+            return Ok((
+                RouteOutcome::Unsupported {
+                    reason: if reasoning.is_empty() {
+                        "No suitable plugin found for this request.".to_string()
+                    } else {
+                        reasoning
+                    },
+                },
+                explanation,
+            ));
+        }
+
+        explanation.decision = format!("Selected {} (confidence {:.2}).", plugin_name, confidence);
+        Ok((
+            RouteOutcome::Selected {
+                plugin: plugin_name,
+                confidence,
+                reasoning,
+            },
+            explanation,
+        ))
+    }
+
+    fn prompt(&self, request: &RouteRequest<'_>) -> Option<String> {
+        let plugins = prefilter_plugins(request.user_input, request.plugins);
+        Some(format!(
+            "{}\n{}",
+            system_prompt(request, &plugins),
+            ROUTER_USER_INPUT
+        ))
+    }
+}
+
+fn system_prompt(request: &RouteRequest<'_>, plugins: &[Arc<dyn Plugin>]) -> String {
+    router_system_prompt(request, plugins)
+}
+
+/// No model: a plugin named in the request wins, otherwise the capability
+/// keywords pick one, and several matches become a clarify listing them.
+/// The routing offline mode uses.
+pub struct RuleRouting;
+
+#[async_trait]
+impl RoutingStrategy for RuleRouting {
+    async fn route(&self, request: &RouteRequest<'_>) -> Result<(RouteOutcome, RouteExplanation)> {
+        Ok(offline_route(
+            request.user_input,
+            request.plugins,
+            request.preferred,
+            request.clarify_options,
+        ))
+    }
+}
+
+/// The keyword rules first; the router model only when they don't settle
+/// on exactly one plugin.
+pub struct HybridRouting {
+    model: ModelRouting,
+}
+
+impl HybridRouting {
+    pub fn new(model: ModelRouting) -> Self {
+        Self { model }
+    }
+}
+
+#[async_trait]
+impl RoutingStrategy for HybridRouting {
+    async fn route(&self, request: &RouteRequest<'_>) -> Result<(RouteOutcome, RouteExplanation)> {
+        let routed = offline_route(
+            request.user_input,
+            request.plugins,
+            request.preferred,
+            request.clarify_options,
+        );
+        if let (RouteOutcome::Selected { .. }, _) = routed {
+            return Ok(routed);
+        }
+        self.model.route(request).await
+    }
+
+    fn prompt(&self, request: &RouteRequest<'_>) -> Option<String> {
+        let candidates = rule_candidates(request.user_input, request.plugins, request.preferred);
+        if candidates.len() == 1 {
+            return None;
+        }
+        self.model.prompt(request)
+    }
+}
+
+/// Never guesses: unless the request names a plugin, the user picks one from
+/// the keyword matches, topped up with the preferred and then the other
+/// plugins. Picking one names it, which settles the re-route.
+pub struct AskRouting;
+
+#[async_trait]
+impl RoutingStrategy for AskRouting {
+    async fn route(&self, request: &RouteRequest<'_>) -> Result<(RouteOutcome, RouteExplanation)> {
+        let lower = request.user_input.to_lowercase();
+        let named: Vec<&str> = request
+            .plugins
+            .iter()
+            .map(|p| p.name())
+            .filter(|name| lower.contains(&name.to_lowercase()))
+            .collect();
+        let mut explanation = RouteExplanation {
+            rule_based: true,
+            ..RouteExplanation::default()
+        };
+        let mut offered = rule_candidates(request.user_input, request.plugins, request.preferred);
+        if let [plugin] = named.as_slice() {
+            offered = vec![*plugin];
+        } else {
+            let rest = request
+                .plugins
+                .iter()
+                .map(|p| p.name())
+                .filter(|name| request.preferred.iter().any(|p| p == name))
+                .chain(request.plugins.iter().map(|p| p.name()));
+            for name in rest {
+                if !offered.contains(&name) {
+                    offered.push(name);
+                }
+            }
+        }
+
+        let outcome = match offered.as_slice() {
+            [] => {
+                explanation.decision = "No plugins to offer.".to_string();
+                RouteOutcome::Unsupported {
+                    reason: "No plugins are enabled.".to_string(),
+                }
+            }
+            [plugin] => {
+                explanation.chosen_plugin = Some(plugin.to_string());
+                explanation.decision = format!("The request names {}.", plugin);
+                RouteOutcome::Selected {
+                    plugin: plugin.to_string(),
+                    confidence: 1.0,
+                    reasoning: "Named in the request.".to_string(),
+                }
+            }
+            several => {
+                explanation.decision = "Routing is set to ask; offering the plugins.".to_string();
+                plugin_clarify(request.user_input, several, request.clarify_options)
+            }
+        };
+        Ok((outcome, explanation))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dexter_plugins::{F2Plugin, FFmpegPlugin, QpdfPlugin, YtDlpPlugin};
+
+    async fn ask(
+        user_input: &str,
+        plugins: &[Arc<dyn Plugin>],
+        preferred: &[String],
+    ) -> RouteOutcome {
+        ask_for(
+            user_input,
+            plugins,
+            preferred,
+            crate::router::DEFAULT_CLARIFY_OPTIONS,
+        )
+        .await
+    }
+
+    async fn ask_for(
+        user_input: &str,
+        plugins: &[Arc<dyn Plugin>],
+        preferred: &[String],
+        clarify_options: usize,
+    ) -> RouteOutcome {
+        let context = FileContext::default();
+        let request = RouteRequest {
+            user_input,
+            context: &context,
+            plugins,
+            preferred,
+            dir_history: &[],
+            session: None,
+            language: "auto",
+            context_file_limit: crate::context::DEFAULT_CONTEXT_FILES,
+            clarify_options,
+        };
+        AskRouting.route(&request).await.unwrap().0
+    }
+
+    fn offered(outcome: RouteOutcome) -> Vec<String> {
+        match outcome {
+            RouteOutcome::Clarify { options, .. } => options.into_iter().map(|o| o.id).collect(),
+            other => panic!("expected a clarify, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn ask_routing_offers_keyword_matches_first() {
+        let plugins: Vec<Arc<dyn Plugin>> = vec![
+            Arc::new(F2Plugin::default()),
+            Arc::new(FFmpegPlugin::default()),
+            Arc::new(QpdfPlugin::default()),
+            Arc::new(YtDlpPlugin::default()),
+        ];
+        assert_eq!(
+            offered(ask("Renaming photos by date", &plugins, &[]).await),
+            ["f2", "ffmpeg", "qpdf", "yt-dlp"]
+        );
+        assert_eq!(
+            offered(ask("do the usual", &plugins, &["qpdf".to_string()]).await),
+            ["qpdf", "f2", "ffmpeg", "yt-dlp"]
+        );
+        assert_eq!(
+            offered(ask_for("do the usual", &plugins, &[], 2).await),
+            ["f2", "ffmpeg"]
+        );
+        let options = match ask("convert these videos to mkv", &plugins, &[]).await {
+            RouteOutcome::Clarify { options, .. } => options,
+            other => panic!("expected a clarify, got {:?}", other),
+        };
+        assert_eq!(options[0].id, "ffmpeg");
+        assert!(matches!(
+            ask(&options[0].resolved_intent, &plugins, &[]).await,
+            RouteOutcome::Selected { ref plugin, .. } if plugin == "ffmpeg"
+        ));
+    }
+}
@@ -1,8 +1,92 @@
 use anyhow::{anyhow, Result};
+use dexter_plugins::StructuredCommand;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
 
+use crate::recycle::{deletes_files, TrashTool};
+use crate::trash::DeleteMode;
+
+/// `[safety]`: rules on top of the built-in ones, for every project.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+pub struct SafetyPolicy {
+    /// Regexes a command must not match, next to the built-in rules and a
+    /// project's `blocked_patterns`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blocked_patterns: Vec<String>,
+    /// Blocks commands naming a path outside the working directory:
+    /// absolute paths, `~/...` and `..` that climb out of it.
+    #[serde(default)]
+    pub contain_paths: bool,
+    /// Per plugin, by name; plugins left out are allowed.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub plugins: BTreeMap<String, PluginPolicy>,
+    /// Lets the TUI run a command the patterns, shell and path rules
+    /// refused, once a justification is typed; the justification goes to
+    /// the session log and the audit log.
+    #[serde(default)]
+    pub allow_override: bool,
+}
+
+impl SafetyPolicy {
+    pub fn plugin_policy(&self, plugin: &str) -> PluginPolicy {
+        self.plugins.get(plugin).copied().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PluginPolicy {
+    #[default]
+    Allow,
+    /// Runs only after a confirmation: `confirm_timeout.auto_run` skips it.
+    Confirm,
+    /// Never runs; previews still work.
+    Block,
+}
+
+impl PluginPolicy {
+    pub fn next(self) -> Self {
+        match self {
+            PluginPolicy::Allow => PluginPolicy::Confirm,
+            PluginPolicy::Confirm => PluginPolicy::Block,
+            PluginPolicy::Block => PluginPolicy::Allow,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PluginPolicy::Allow => "allow",
+            PluginPolicy::Confirm => "confirm",
+            PluginPolicy::Block => "block",
+        }
+    }
+}
+
+/// A command the guard refused while it was generated, kept whole so the
+/// TUI can offer to edit it or send the reason back to the model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SafetyViolation {
+    pub command: String,
+    pub reason: String,
+}
+
+impl std::fmt::Display for SafetyViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.reason)
+    }
+}
+
+impl std::error::Error for SafetyViolation {}
+
+#[derive(Debug, Clone)]
 pub struct SafetyGuard {
     blacklist_patterns: Vec<Regex>,
+    read_only: bool,
+    /// Set by `safety.contain_paths`: the directory paths must stay in.
+    contain: Option<PathBuf>,
+    blocked_plugins: Vec<String>,
 }
 
 impl Default for SafetyGuard {
@@ -14,27 +98,98 @@ impl Default for SafetyGuard {
                 Regex::new(r"(?i)^dd\s+").unwrap(),
                 Regex::new(r"(?i):.*\(\s*\)\s*\{\s*:.*\|.*:.*\}\s*;.*:").unwrap(), // fork bomb
                 Regex::new(r"(?i)^sudo\s+rm").unwrap(),
+                // Deletes that skip the trash.
+                Regex::new(r"(?i)^(unlink|rmdir|shred|srm)\s+").unwrap(),
+                Regex::new(r"(?i)\s-delete(\s|$)").unwrap(),
                 Regex::new(r"(?i)>\s*/dev/sd[a-z]").unwrap(), // writing to raw device
                 Regex::new(r"(?i)mkfs").unwrap(),
             ],
+            read_only: false,
+            contain: None,
+            blocked_plugins: Vec::new(),
         }
     }
 }
 
 impl SafetyGuard {
+    /// Adds project-specific patterns; the built-in ones always stay.
+    pub fn with_blocked_patterns(mut self, patterns: &[String]) -> Result<Self> {
+        for pattern in patterns {
+            let regex =
+                Regex::new(pattern).map_err(|e| anyhow!("Invalid pattern '{}': {}", pattern, e))?;
+            self.blacklist_patterns.push(regex);
+        }
+        Ok(self)
+    }
+
+    /// Adds the config's `[safety]` rules, with paths kept inside `cwd`
+    /// when it asks for that.
+    pub fn with_policy(mut self, policy: &SafetyPolicy, cwd: &Path) -> Result<Self> {
+        self = self
+            .with_blocked_patterns(&policy.blocked_patterns)
+            .map_err(|e| anyhow!("safety.blocked_patterns: {}", e))?;
+        self.contain = policy.contain_paths.then(|| cwd.to_path_buf());
+        self.blocked_plugins = policy
+            .plugins
+            .iter()
+            .filter(|(_, policy)| **policy == PluginPolicy::Block)
+            .map(|(name, _)| name.clone())
+            .collect();
+        Ok(self)
+    }
+
+    /// Every pattern a command is checked against, built-in ones first.
+    pub fn patterns(&self) -> impl Iterator<Item = &str> {
+        self.blacklist_patterns.iter().map(Regex::as_str)
+    }
+
+    /// With `read_only`, `check_execution` rejects every command; previews
+    /// still go through `check`.
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// `check` for a command about to run.
+    pub fn check_execution(&self, cmd: &str) -> Result<()> {
+        if self.read_only {
+            return Err(anyhow!(
+                "Command blocked: Dexter is in read-only mode and runs nothing"
+            ));
+        }
+        self.check(cmd)
+    }
+
+    /// Whether `plugin` may run commands at all.
+    pub fn check_plugin(&self, plugin: &str) -> Result<()> {
+        if self.blocked_plugins.iter().any(|name| name == plugin) {
+            return Err(anyhow!(
+                "Command blocked: safety.plugins.{} is \"block\"",
+                plugin
+            ));
+        }
+        Ok(())
+    }
+
+    /// The first pattern `cmd` matches.
+    pub fn matched_pattern(&self, cmd: &str) -> Option<&str> {
+        self.blacklist_patterns
+            .iter()
+            .find(|pattern| pattern.is_match(cmd.trim()))
+            .map(Regex::as_str)
+    }
+
     pub fn check(&self, cmd: &str) -> Result<()> {
         let trimmed = cmd.trim();
         if trimmed.is_empty() {
             return Err(anyhow!("Command is empty"));
         }
 
-        for pattern in &self.blacklist_patterns {
-            if pattern.is_match(trimmed) {
-                return Err(anyhow!(
-                    "Command blocked by safety guard. Pattern matched: {}",
-                    pattern
-                ));
-            }
+        if let Some(pattern) = self.matched_pattern(trimmed) {
+            return Err(anyhow!(
+                "Command blocked by safety guard. Pattern matched: {}",
+                pattern
+            ));
         }
 
         // Additional heuristics: shell composition and risky redirection.
@@ -50,8 +205,123 @@ impl SafetyGuard {
             ));
         }
 
+        if let Some(root) = &self.contain {
+            if let Some(path) = outside_path(trimmed, root) {
+                return Err(anyhow!(
+                    "Command blocked: {} is outside the working directory (safety.contain_paths)",
+                    path
+                ));
+            }
+        }
+
         Ok(())
     }
+
+    /// `check` on the joined command line, plus checks on the declared
+    /// paths that a command line can't express.
+    pub fn check_structured(&self, cmd: &StructuredCommand) -> Result<()> {
+        self.check(&cmd.to_command_line())?;
+
+        let undeclared = cmd.undeclared_paths();
+        if !undeclared.is_empty() {
+            return Err(anyhow!(
+                "Command blocked: declared paths missing from its arguments: {}",
+                undeclared.join(", ")
+            ));
+        }
+
+        if let Some(output) = cmd
+            .outputs
+            .iter()
+            .find(|path| path.starts_with("/dev/") || path.starts_with("/sys/"))
+        {
+            return Err(anyhow!(
+                "Command blocked: writes to a device or system path: {}",
+                output
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Where the files `cmd` deletes go under `mode`: the trash tool to
+    /// move them with, or `None` when nothing is deleted or the tool may
+    /// delete them itself. Errors when the command may not run.
+    pub fn check_deletes(
+        &self,
+        plugin: &str,
+        cmd: &str,
+        mode: DeleteMode,
+    ) -> Result<Option<TrashTool>> {
+        if !deletes_files(plugin, cmd) {
+            return Ok(None);
+        }
+        match mode {
+            DeleteMode::Permanent => Ok(None),
+            DeleteMode::Block => Err(anyhow!(
+                "Command blocked: it deletes files and trash.deletes is \"block\""
+            )),
+            DeleteMode::Trash => TrashTool::detect().map(Some).ok_or_else(|| {
+                anyhow!(
+                    "Command blocked: it deletes files and no system trash was found; \
+                     install trash-cli, or set trash.deletes to \"permanent\""
+                )
+            }),
+        }
+    }
+}
+
+/// The first argument of `cmd`, value of a `--flag=value` or value
+/// attached to a short option (`-o/etc/x`), naming a path outside `root`.
+/// Paths are compared as written; symlinks are not followed.
+fn outside_path(cmd: &str, root: &Path) -> Option<String> {
+    let args = shell_words::split(cmd).ok()?;
+    args.into_iter()
+        .skip(1)
+        .map(|arg| match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with('-') => value.to_string(),
+            _ => attached_path(&arg).map(str::to_string).unwrap_or(arg),
+        })
+        .find(|arg| leaves(arg, root))
+}
+
+/// The path glued to a short option or cluster: `/abs` of `-P/abs`,
+/// `../x` of `-sSo../x`.
+fn attached_path(arg: &str) -> Option<&str> {
+    let flags = arg
+        .strip_prefix('-')
+        .filter(|rest| !rest.starts_with('-'))?;
+    (1..flags.len())
+        .filter(|idx| flags.is_char_boundary(*idx))
+        .map(|idx| &flags[idx..])
+        .find(|rest| rest.starts_with('/') || rest.starts_with("~/") || rest.starts_with(".."))
+}
+
+fn leaves(arg: &str, root: &Path) -> bool {
+    if arg.contains("://") || arg == "/dev/null" {
+        return false;
+    }
+    let path = match arg.strip_prefix("~/") {
+        Some(rest) => match crate::paths::home_dir() {
+            Some(home) => home.join(rest),
+            None => return false,
+        },
+        None => PathBuf::from(arg),
+    };
+    if !path.is_absolute() && !path.components().any(|c| c == Component::ParentDir) {
+        return false;
+    }
+    let mut resolved = PathBuf::new();
+    for component in root.join(path).components() {
+        match component {
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::CurDir => {}
+            other => resolved.push(other),
+        }
+    }
+    !resolved.starts_with(root)
 }
 
 #[cfg(test)]
@@ -69,5 +339,101 @@ mod tests {
         assert!(guard.check("cat file > /dev/sda1").is_err());
         assert!(guard.check("ffmpeg -i a.mp4 b.mp4; rm -rf /").is_err());
         assert!(guard.check("yt-dlp \"url\" && echo hacked").is_err());
+        assert!(guard.check("unlink a.txt").is_err());
+        assert!(guard.check("find . -name '*.tmp' -delete").is_err());
+        assert!(guard.check("jdupes -r --delete --no-prompt .").is_ok());
+    }
+
+    #[test]
+    fn deletes_follow_the_delete_mode() {
+        let guard = SafetyGuard::default();
+        let delete = "jdupes -r -d -N .";
+        assert!(guard
+            .check_deletes("jdupes", delete, DeleteMode::Block)
+            .is_err());
+        assert_eq!(
+            guard
+                .check_deletes("jdupes", delete, DeleteMode::Permanent)
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            guard
+                .check_deletes("jdupes", "jdupes -r .", DeleteMode::Block)
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn read_only_blocks_execution_but_not_previews() {
+        let guard = SafetyGuard::default().with_read_only(true);
+        assert!(guard.check("f2 -f a -r b").is_ok());
+        assert!(guard.check_execution("f2 -f a -r b").is_err());
+        assert!(SafetyGuard::default()
+            .check_execution("f2 -f a -r b")
+            .is_ok());
+    }
+
+    #[test]
+    fn policy_adds_patterns_containment_and_plugin_blocks() {
+        let root = Path::new("/work/project");
+        let policy = SafetyPolicy {
+            blocked_patterns: vec![r"(?i)\s-y(\s|$)".to_string()],
+            contain_paths: true,
+            plugins: BTreeMap::from([("yt-dlp".to_string(), PluginPolicy::Block)]),
+            allow_override: false,
+        };
+        let guard = SafetyGuard::default().with_policy(&policy, root).unwrap();
+
+        assert!(guard.check("ffmpeg -y -i a.mkv a.mp4").is_err());
+        assert_eq!(
+            guard.matched_pattern("ffmpeg -y -i a.mkv a.mp4"),
+            Some(r"(?i)\s-y(\s|$)")
+        );
+        assert!(guard.check("ffmpeg -i a.mkv out/a.mp4").is_ok());
+        assert!(guard.check("ffmpeg -i a.mkv ../a.mp4").is_err());
+        assert!(guard.check("ffmpeg -i /tmp/a.mkv a.mp4").is_err());
+        assert!(guard.check("ffmpeg -i a.mkv -f null /dev/null").is_ok());
+        assert!(guard.check("f2 -f a -r b out/../b").is_ok());
+        assert!(guard.check("pandoc a.md --output=/etc/a.pdf").is_err());
+        assert!(guard.check("curl -LO -o/etc/x https://a.com/x").is_err());
+        assert!(guard.check("wget -P/abs https://a.com/x").is_err());
+        assert!(guard.check("curl -sSo../x https://a.com/x").is_err());
+        assert!(guard.check("wget -P~/x https://a.com/x").is_err());
+        assert!(guard.check("curl -sSo out/x https://a.com/x").is_ok());
+        assert!(guard.check("yt-dlp https://example.com/v").is_ok());
+        assert!(guard.check_plugin("yt-dlp").is_err());
+        assert!(guard.check_plugin("ffmpeg").is_ok());
+
+        let bad = SafetyPolicy {
+            blocked_patterns: vec!["(".to_string()],
+            ..SafetyPolicy::default()
+        };
+        assert!(SafetyGuard::default().with_policy(&bad, root).is_err());
+    }
+
+    #[test]
+    fn structured_commands_check_declared_outputs() {
+        let guard = SafetyGuard::default();
+        let mut cmd = StructuredCommand {
+            program: "ffmpeg".to_string(),
+            args: vec![
+                "-i".to_string(),
+                "a b.mp4".to_string(),
+                "out.mp3".to_string(),
+            ],
+            inputs: vec!["a b.mp4".to_string()],
+            outputs: vec!["out.mp3".to_string()],
+            ..StructuredCommand::default()
+        };
+        assert!(guard.check_structured(&cmd).is_ok());
+
+        cmd.outputs = vec!["elsewhere.mp3".to_string()];
+        assert!(guard.check_structured(&cmd).is_err());
+
+        cmd.args[2] = "/dev/sda".to_string();
+        cmd.outputs = vec!["/dev/sda".to_string()];
+        assert!(guard.check_structured(&cmd).is_err());
     }
 }
@@ -0,0 +1,150 @@
+//! Which plugins run sandboxed, and with what network access. The sandbox
+//! itself lives in `dexter_plugins::sandbox`.
+
+use anyhow::Result;
+use dexter_plugins::command_exec;
+use dexter_plugins::sandbox::{self, Sandbox, SandboxBackend};
+use dexter_plugins::{Capability, LlmBridge, Plugin, PreviewContent, Progress};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+
+use crate::paths;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+pub struct SandboxPolicy {
+    #[serde(default)]
+    pub backend: SandboxBackend,
+    /// Plugins to sandbox, by name (`ffmpeg`, `yt-dlp`). Plugins not listed
+    /// run unsandboxed.
+    #[serde(default)]
+    pub plugins: BTreeMap<String, PluginSandbox>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct PluginSandbox {
+    /// `false` keeps the entry but runs the plugin unsandboxed.
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Unset allows the network only to plugins that download.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<bool>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl SandboxPolicy {
+    /// The sandbox `plugin` runs in when its commands write to `root`, or
+    /// `None` when it isn't sandboxed. Errors when it should be but no
+    /// sandbox tool is available.
+    pub fn sandbox_for(&self, plugin: &dyn Plugin, root: &Path) -> Result<Option<Sandbox>> {
+        let Some(entry) = self
+            .plugins
+            .get(plugin.name())
+            .filter(|entry| entry.enabled)
+        else {
+            return Ok(None);
+        };
+        let network = entry
+            .network
+            .unwrap_or_else(|| plugin.capabilities().contains(&Capability::Downloading));
+        Ok(Some(Sandbox {
+            backend: self.backend.resolve()?,
+            root: root.to_path_buf(),
+            network,
+            hidden: secret_dirs(root),
+        }))
+    }
+
+    /// Previews `cmd` with `plugin` in `root`, inside the same sandbox its
+    /// run would get.
+    pub async fn dry_run(
+        &self,
+        plugin: &dyn Plugin,
+        cmd: &str,
+        root: &Path,
+        llm: Option<&dyn LlmBridge>,
+    ) -> Result<PreviewContent> {
+        let sandbox = self.sandbox_for(plugin, root)?;
+        let run = plugin.dry_run(cmd, llm);
+        command_exec::in_dir(root.to_path_buf(), sandbox::scope(sandbox, run)).await
+    }
+
+    /// Runs `cmd` with `plugin` in `root`, inside its sandbox when it has
+    /// one.
+    pub async fn execute(
+        &self,
+        plugin: &dyn Plugin,
+        cmd: &str,
+        root: &Path,
+        progress_tx: mpsc::Sender<Progress>,
+    ) -> Result<String> {
+        let sandbox = self.sandbox_for(plugin, root)?;
+        let run = plugin.execute_with_progress(cmd, progress_tx);
+        command_exec::in_dir(root.to_path_buf(), sandbox::scope(sandbox, run)).await
+    }
+}
+
+/// Directories a sandboxed plugin may not read: SSH and GnuPG keys and
+/// Dexter's own config, which holds API keys. Skipped when missing, or when
+/// the target directory is inside one.
+fn secret_dirs(root: &Path) -> Vec<PathBuf> {
+    let home = paths::home_dir();
+    [
+        home.as_ref().map(|home| home.join(".ssh")),
+        home.as_ref().map(|home| home.join(".gnupg")),
+        paths::config_dir().map(|dir| dir.join("dexter")),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|dir| dir.is_dir() && !root.starts_with(dir))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dexter_plugins::{DownloadPlugin, F2Plugin};
+
+    #[test]
+    fn only_listed_plugins_are_sandboxed() {
+        let policy: SandboxPolicy = toml::from_str(
+            r#"
+backend = "firejail"
+
+[plugins.f2]
+enabled = false
+
+[plugins.download]
+"#,
+        )
+        .unwrap();
+        let root = Path::new("/srv/media");
+        assert_eq!(
+            policy.sandbox_for(&F2Plugin::default(), root).unwrap(),
+            None
+        );
+        let unlisted = SandboxPolicy::default();
+        assert_eq!(
+            unlisted
+                .sandbox_for(&DownloadPlugin::default(), root)
+                .unwrap(),
+            None
+        );
+
+        // Whether firejail is installed decides between a sandbox and an
+        // error, never an unsandboxed run.
+        match policy.sandbox_for(&DownloadPlugin::default(), root) {
+            Ok(sandbox) => {
+                let sandbox = sandbox.expect("listed plugin");
+                assert!(sandbox.network);
+                assert_eq!(sandbox.root, root);
+                assert!(sandbox.hidden.iter().all(|dir| dir.is_dir()));
+            }
+            Err(e) => assert!(e.to_string().starts_with("Sandbox unavailable")),
+        }
+    }
+}
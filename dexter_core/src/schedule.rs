@@ -0,0 +1,414 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use dexter_plugins::Plugin;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::fs;
+
+use crate::audit::AuditEvent;
+use crate::config::{Config, ConflictPolicy};
+use crate::conflict::ConflictOutcome;
+use crate::executor::{
+    record_dir_use, record_plugin_success, Executor, RunEnvironment, RunOutcome,
+};
+use crate::network::MeteredVerdict;
+use crate::recycle;
+use crate::workspace::Workspace;
+
+/// A confirmed command waiting for its start time.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ScheduledJob {
+    pub id: String,
+    pub plugin: String,
+    pub command: String,
+    /// Directory the command was previewed in; it runs there too.
+    pub cwd: PathBuf,
+    pub run_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ScheduledJob {
+    pub fn run_at_label(&self) -> String {
+        self.run_at
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    }
+
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.run_at <= now
+    }
+}
+
+/// Persists scheduled jobs as a JSON array next to the history file. The
+/// daemon checks for due jobs periodically; otherwise the next launch of
+/// `dexter` runs whatever came due in the meantime.
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    path: PathBuf,
+}
+
+impl Scheduler {
+    pub fn new() -> Result<Self> {
+        let dir = crate::paths::data_dir()
+            .context("Could not find data directory")?
+            .join("dexter");
+        Ok(Self::at(dir.join("scheduled.json")))
+    }
+
+    pub fn at(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// All pending jobs, soonest first.
+    pub async fn load(&self) -> Result<Vec<ScheduledJob>> {
+        let raw = match fs::read_to_string(&self.path).await {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        if raw.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut jobs: Vec<ScheduledJob> =
+            serde_json::from_str(&raw).context("Failed to parse scheduled jobs")?;
+        jobs.sort_by_key(|job| job.run_at);
+        Ok(jobs)
+    }
+
+    pub async fn add(
+        &self,
+        plugin: &str,
+        command: &str,
+        cwd: &Path,
+        run_at: DateTime<Utc>,
+    ) -> Result<ScheduledJob> {
+        let _lock = crate::executor::lock_store(&self.path).await?;
+        let mut jobs = self.load().await?;
+        let created_at = Utc::now();
+        let mut id = created_at.timestamp_millis();
+        while jobs.iter().any(|job| job.id == id.to_string()) {
+            id += 1;
+        }
+        let job = ScheduledJob {
+            id: id.to_string(),
+            plugin: plugin.to_string(),
+            command: command.to_string(),
+            cwd: cwd.to_path_buf(),
+            run_at,
+            created_at,
+        };
+        jobs.push(job.clone());
+        self.save(&jobs).await?;
+        Ok(job)
+    }
+
+    /// Returns whether a job with `id` was still pending.
+    pub async fn cancel(&self, id: &str) -> Result<bool> {
+        let _lock = crate::executor::lock_store(&self.path).await?;
+        let mut jobs = self.load().await?;
+        let before = jobs.len();
+        jobs.retain(|job| job.id != id);
+        if jobs.len() == before {
+            return Ok(false);
+        }
+        self.save(&jobs).await?;
+        Ok(true)
+    }
+
+    /// Removes and returns the jobs due at `now`, so each runs only once,
+    /// even with the TUI and the daemon both checking.
+    pub async fn take_due(&self, now: DateTime<Utc>) -> Result<Vec<ScheduledJob>> {
+        let _lock = crate::executor::lock_store(&self.path).await?;
+        let jobs = self.load().await?;
+        let (due, pending): (Vec<_>, Vec<_>) = jobs.into_iter().partition(|job| job.is_due(now));
+        if !due.is_empty() {
+            self.save(&pending).await?;
+        }
+        Ok(due)
+    }
+
+    /// Runs every due job in its own directory, one after another, and
+    /// records each in the history. Changes the process working directory
+    /// while a job runs, so callers must not rely on it concurrently.
+    /// Each job is checked against the rules of its directory and `config`.
+    pub async fn run_due(
+        &self,
+        plugins: &[Arc<dyn Plugin>],
+        executor: &Executor,
+        config: &Config,
+    ) -> Result<Vec<(ScheduledJob, Result<String>)>> {
+        let due = self.take_due(Utc::now()).await?;
+        if due.is_empty() {
+            return Ok(Vec::new());
+        }
+        let original_cwd = std::env::current_dir()?;
+        let mut results = Vec::new();
+        for job in due {
+            tracing::info!(id = %job.id, plugin = %job.plugin, "running scheduled job");
+            let result = run_job(&job, plugins, executor, config).await;
+            std::env::set_current_dir(&original_cwd)?;
+            if let Err(e) = &result {
+                tracing::warn!(id = %job.id, error = %e, "scheduled job failed");
+            }
+            results.push((job, result));
+        }
+        Ok(results)
+    }
+
+    async fn save(&self, jobs: &[ScheduledJob]) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(jobs)?).await?;
+        fs::rename(&tmp, &self.path).await?;
+        Ok(())
+    }
+}
+
+async fn run_job(
+    job: &ScheduledJob,
+    plugins: &[Arc<dyn Plugin>],
+    executor: &Executor,
+    config: &Config,
+) -> Result<String> {
+    let plugin = plugins
+        .iter()
+        .find(|p| p.name() == job.plugin)
+        .ok_or_else(|| anyhow!("Plugin not found: {}", job.plugin))?;
+    // Checked again at run time: the safety rules may have changed since the
+    // command was confirmed.
+    let guard = Workspace::guard_for(&job.cwd, &config.safety)?;
+    guard
+        .check_plugin(&job.plugin)
+        .and_then(|()| guard.check(&job.command))
+        .map_err(|e| anyhow!("Safety check failed: {}", e))?;
+    let trash_tool = guard
+        .check_deletes(&job.plugin, &job.command, config.trash.deletes)
+        .map_err(|e| anyhow!("Safety check failed: {}", e))?;
+    if !plugin.validate_command(&job.command) {
+        return Err(anyhow!("Command failed plugin validation logic"));
+    }
+    // Outputs may have appeared since the job was confirmed. Overwrites the
+    // user agreed to stand, except where `never_overwrite` renames them.
+    let command = if config.plugin_settings(&job.plugin).never_overwrite {
+        match executor.resolve_output_conflicts(
+            &job.plugin,
+            &job.command,
+            &job.cwd,
+            ConflictPolicy::AutoRename,
+        )? {
+            ConflictOutcome::Rewritten { command, .. } => command,
+            _ => job.command.clone(),
+        }
+    } else {
+        job.command.clone()
+    };
+    // Nobody is there to confirm a held download, so it doesn't run.
+    match config.metered.check(plugin.as_ref()) {
+        MeteredVerdict::Block => {
+            return Err(anyhow!(
+                "Not run: the connection is metered and metered.action is \"block\""
+            ));
+        }
+        MeteredVerdict::Warn => {
+            tracing::warn!(id = %job.id, "scheduled download over a metered connection")
+        }
+        MeteredVerdict::Clear => {}
+    }
+    std::env::set_current_dir(&job.cwd)
+        .with_context(|| format!("Working directory {} is gone", job.cwd.display()))?;
+    config.audit.record_start(&AuditEvent {
+        plugin: &job.plugin,
+        command: &command,
+        cwd: &job.cwd,
+        host: None,
+    });
+    let started = Instant::now();
+    let result = recycle::execute(
+        trash_tool,
+        &config.sandbox,
+        plugin.as_ref(),
+        &command,
+        &job.cwd,
+        tokio::sync::mpsc::channel(1).0,
+    )
+    .await
+    .map(|(output, _)| output);
+    let outcome = RunOutcome::from_result(started.elapsed(), &result);
+    if let Err(e) = executor
+        .record_run(
+            &job.plugin,
+            &command,
+            None,
+            outcome,
+            Some(RunEnvironment::capture(plugin.as_ref()).await),
+        )
+        .await
+    {
+        tracing::warn!(error = %e, "failed to record scheduled job in history");
+    }
+    let output = result?;
+    if let Err(e) = record_plugin_success(&job.plugin).await {
+        tracing::warn!(error = %e, "failed to record plugin success");
+    }
+    if let Err(e) = record_dir_use(&job.cwd, &job.plugin).await {
+        tracing::warn!(error = %e, "failed to record directory use");
+    }
+    Ok(output)
+}
+
+/// Parses a start time relative to `now`: `2am`, `2:30pm`, `14:30` (next
+/// occurrence), `+90m` / `in 2h` (offsets), or `2026-01-31 02:00`.
+pub fn parse_run_at(input: &str, now: DateTime<Local>) -> Result<DateTime<Local>> {
+    let text = input.trim().to_lowercase();
+    if text.is_empty() {
+        return Err(anyhow!("Enter a time such as 2am, 14:30 or +2h"));
+    }
+
+    let offset = text
+        .strip_prefix('+')
+        .or_else(|| text.strip_prefix("in "))
+        .map(str::trim);
+    if let Some(offset) = offset {
+        return Ok(now + parse_offset(offset)?);
+    }
+
+    if let Ok(naive) = NaiveDateTime::parse_from_str(&text, "%Y-%m-%d %H:%M") {
+        let at = local_from_naive(naive)?;
+        if at <= now {
+            return Err(anyhow!("{} is in the past", input.trim()));
+        }
+        return Ok(at);
+    }
+
+    let time = parse_clock(&text).ok_or_else(|| {
+        anyhow!(
+            "Unrecognized time `{}` (try 2am, 14:30 or +2h)",
+            input.trim()
+        )
+    })?;
+    let today: NaiveDate = now.date_naive();
+    let mut at = local_from_naive(today.and_time(time))?;
+    if at <= now {
+        at = local_from_naive((today + Duration::days(1)).and_time(time))?;
+    }
+    Ok(at)
+}
+
+/// `parse_run_at` against the current local time.
+pub fn resolve_run_at(input: &str) -> Result<DateTime<Utc>> {
+    Ok(parse_run_at(input, Local::now())?.with_timezone(&Utc))
+}
+
+fn parse_offset(text: &str) -> Result<Duration> {
+    let split = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (amount, unit) = text.split_at(split);
+    let amount: i64 = amount
+        .parse()
+        .map_err(|_| anyhow!("Offsets look like +90m or +2h"))?;
+    match unit.trim() {
+        "m" | "min" | "mins" | "minute" | "minutes" => Ok(Duration::minutes(amount)),
+        "h" | "hr" | "hrs" | "hour" | "hours" => Ok(Duration::hours(amount)),
+        "d" | "day" | "days" => Ok(Duration::days(amount)),
+        other => Err(anyhow!("Unknown offset unit `{}`", other)),
+    }
+}
+
+fn parse_clock(text: &str) -> Option<NaiveTime> {
+    let (body, meridiem) = if let Some(body) = text.strip_suffix("am") {
+        (body.trim(), Some(false))
+    } else if let Some(body) = text.strip_suffix("pm") {
+        (body.trim(), Some(true))
+    } else {
+        (text, None)
+    };
+    let (hour, minute) = match body.split_once(':') {
+        Some((h, m)) => (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?),
+        None => (body.parse::<u32>().ok()?, 0),
+    };
+    let hour = match meridiem {
+        Some(pm) if (1..=12).contains(&hour) => hour % 12 + if pm { 12 } else { 0 },
+        Some(_) => return None,
+        // A bare number needs a meridiem or a minute part to be a time.
+        None if !body.contains(':') => return None,
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+fn local_from_naive(naive: NaiveDateTime) -> Result<DateTime<Local>> {
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .ok_or_else(|| anyhow!("That time does not exist in the local timezone"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn parses_clock_times_offsets_and_dates() {
+        let now = Local.with_ymd_and_hms(2026, 3, 10, 22, 15, 0).unwrap();
+        let at = |s: &str| {
+            parse_run_at(s, now)
+                .unwrap()
+                .format("%m-%d %H:%M")
+                .to_string()
+        };
+
+        assert_eq!(at("2am"), "03-11 02:00");
+        assert_eq!(at("11:30pm"), "03-10 23:30");
+        assert_eq!(at("22:00"), "03-11 22:00");
+        assert_eq!(at("+90m"), "03-10 23:45");
+        assert_eq!(at("in 2h"), "03-11 00:15");
+        assert_eq!(at("2026-03-12 01:00"), "03-12 01:00");
+        assert!(parse_run_at("2", now).is_err());
+        assert!(parse_run_at("13pm", now).is_err());
+        assert!(parse_run_at("2026-03-01 01:00", now).is_err());
+    }
+
+    #[tokio::test]
+    async fn due_jobs_are_taken_once() -> Result<()> {
+        let dir = tempdir()?;
+        let scheduler = Scheduler::at(dir.path().join("scheduled.json"));
+        let now = Utc::now();
+        let later = scheduler
+            .add(
+                "ffmpeg",
+                "ffmpeg -i a.mov a.mp4",
+                dir.path(),
+                now + Duration::hours(1),
+            )
+            .await?;
+        scheduler
+            .add("qpdf", "qpdf --linearize a.pdf b.pdf", dir.path(), now)
+            .await?;
+
+        let due = scheduler.take_due(now).await?;
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].plugin, "qpdf");
+        assert!(scheduler.take_due(now).await?.is_empty());
+
+        assert!(scheduler.cancel(&later.id).await?);
+        assert!(scheduler.load().await?.is_empty());
+
+        // Another instance adding at the same time keeps both jobs.
+        let other = Scheduler::at(dir.path().join("scheduled.json"));
+        let later = now + Duration::hours(1);
+        let (a, b) = tokio::join!(
+            scheduler.add("qpdf", "qpdf --linearize a.pdf b.pdf", dir.path(), later),
+            other.add("qpdf", "qpdf --linearize c.pdf d.pdf", dir.path(), later),
+        );
+        assert_ne!(a?.id, b?.id);
+        assert_eq!(scheduler.load().await?.len(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+use dexter_plugins::PreviewContent;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::conflict::output_paths;
+
+/// How many files a previewed command reads and how large they are, shown
+/// above the confirmation prompt so the scope can be checked at a glance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScopeSummary {
+    pub files: usize,
+    pub input_bytes: u64,
+    /// Known for renames, which keep every size, and roughly for ffmpeg
+    /// stream copies; `None` when the output depends on the encoding.
+    pub output_bytes: Option<u64>,
+}
+
+impl ScopeSummary {
+    /// Renames are taken from the diff list; other commands count the
+    /// arguments that name existing files in `cwd`, outputs excluded.
+    /// Returns `None` when no local input could be identified.
+    pub fn measure(
+        plugin: &str,
+        command: &str,
+        cwd: &Path,
+        preview: &PreviewContent,
+    ) -> Option<Self> {
+        let (inputs, same_size) = match preview {
+            PreviewContent::DiffList(diffs) => (
+                diffs
+                    .iter()
+                    .filter(|diff| diff.original != diff.new)
+                    .map(|diff| diff.original.clone())
+                    .collect(),
+                true,
+            ),
+            _ => (
+                command_inputs(plugin, command, cwd),
+                is_stream_copy(plugin, command),
+            ),
+        };
+        if inputs.is_empty() {
+            return None;
+        }
+        let input_bytes = inputs
+            .iter()
+            .filter_map(|path| fs::metadata(cwd.join(path)).ok())
+            .map(|meta| meta.len())
+            .sum();
+        Some(Self {
+            files: inputs.len(),
+            input_bytes,
+            output_bytes: same_size.then_some(input_bytes),
+        })
+    }
+}
+
+fn command_inputs(plugin: &str, command: &str, cwd: &Path) -> Vec<String> {
+    let Ok(argv) = shell_words::split(command) else {
+        return Vec::new();
+    };
+    let outputs: HashSet<String> = output_paths(plugin, command)
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+    let mut seen = HashSet::new();
+    argv.into_iter()
+        .skip(1)
+        .filter(|arg| !arg.starts_with('-') && !outputs.contains(arg))
+        .filter(|arg| cwd.join(arg).is_file())
+        .filter(|arg| seen.insert(arg.clone()))
+        .collect()
+}
+
+fn is_stream_copy(plugin: &str, command: &str) -> bool {
+    if plugin != "ffmpeg" {
+        return false;
+    }
+    let argv = shell_words::split(command).unwrap_or_default();
+    argv.windows(2)
+        .any(|pair| matches!(pair[0].as_str(), "-c" | "-codec") && pair[1] == "copy")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dexter_plugins::DiffItem;
+    use tempfile::tempdir;
+
+    #[test]
+    fn counts_inputs_from_diffs_and_command_arguments() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.mkv"), vec![0u8; 300]).unwrap();
+        fs::write(dir.path().join("b.mkv"), vec![0u8; 200]).unwrap();
+        fs::write(dir.path().join("out.mp4"), vec![0u8; 50]).unwrap();
+
+        let renames = PreviewContent::DiffList(vec![
+            DiffItem {
+                original: "a.mkv".to_string(),
+                new: "1.mkv".to_string(),
+                status: None,
+            },
+            DiffItem {
+                original: "b.mkv".to_string(),
+                new: "b.mkv".to_string(),
+                status: None,
+            },
+        ]);
+        let scope = ScopeSummary::measure("f2", "f2 -f a -r 1", dir.path(), &renames).unwrap();
+        assert_eq!(
+            scope,
+            ScopeSummary {
+                files: 1,
+                input_bytes: 300,
+                output_bytes: Some(300),
+            }
+        );
+
+        let text = PreviewContent::Text(String::new());
+        let copy = "ffmpeg -i a.mkv -i b.mkv -c copy out.mp4";
+        let scope = ScopeSummary::measure("ffmpeg", copy, dir.path(), &text).unwrap();
+        assert_eq!((scope.files, scope.input_bytes), (2, 500));
+        assert_eq!(scope.output_bytes, Some(500));
+
+        let encode = "ffmpeg -i a.mkv -c:v libx264 out.mp4";
+        let scope = ScopeSummary::measure("ffmpeg", encode, dir.path(), &text).unwrap();
+        assert_eq!((scope.files, scope.output_bytes), (1, None));
+        assert_eq!(
+            ScopeSummary::measure("ytdlp", "yt-dlp https://x", dir.path(), &text),
+            None
+        );
+    }
+}
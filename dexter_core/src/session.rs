@@ -0,0 +1,111 @@
+use std::collections::VecDeque;
+
+/// Completed runs kept for follow-ups; older ones fall off.
+const SESSION_TURNS: usize = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionTurn {
+    pub intent: String,
+    pub plugin: String,
+    pub command: String,
+}
+
+/// What ran earlier in this session, so a follow-up such as "now do the
+/// same for the wav files" can be resolved against it. Lives in memory
+/// only; a new session starts empty.
+#[derive(Debug, Clone, Default)]
+pub struct SessionMemory {
+    turns: VecDeque<SessionTurn>,
+}
+
+impl SessionMemory {
+    pub fn record(&mut self, intent: &str, plugin: &str, command: &str) {
+        if self.turns.len() == SESSION_TURNS {
+            self.turns.pop_front();
+        }
+        self.turns.push_back(SessionTurn {
+            intent: intent.trim().to_string(),
+            plugin: plugin.to_string(),
+            command: command.to_string(),
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.turns.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.turns.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.turns.clear();
+    }
+
+    /// Oldest first, one numbered entry per completed run; `None` before
+    /// the first run.
+    pub fn transcript(&self) -> Option<String> {
+        if self.turns.is_empty() {
+            return None;
+        }
+        Some(
+            self.turns
+                .iter()
+                .enumerate()
+                .map(|(i, turn)| {
+                    format!(
+                        "{}. Intent: {}\n   Ran with {}: {}",
+                        i + 1,
+                        turn.intent,
+                        turn.plugin,
+                        turn.command
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )
+    }
+}
+
+/// Prompt section for the router and executor: earlier runs, to be used
+/// only for references the current intent makes to them.
+pub(crate) fn session_section(transcript: Option<&str>) -> String {
+    match transcript {
+        Some(transcript) => format!(
+            "\n### Earlier In This Session:\n{}\nUse these only to resolve references in the intent such as \"the same\", \"again\" or \"those files\"; otherwise ignore them.\n",
+            transcript
+        ),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transcript_keeps_the_latest_runs_in_order() {
+        let mut session = SessionMemory::default();
+        assert_eq!(session.transcript(), None);
+        assert_eq!(session_section(None), "");
+
+        for i in 0..7 {
+            session.record(
+                &format!("convert batch {} to mp3 ", i),
+                "ffmpeg",
+                &format!("ffmpeg -i {}.flac {}.mp3", i, i),
+            );
+        }
+        assert_eq!(session.len(), SESSION_TURNS);
+        let transcript = session.transcript().unwrap();
+        assert_eq!(transcript.lines().count(), SESSION_TURNS * 2);
+        assert!(transcript.starts_with(
+            "1. Intent: convert batch 2 to mp3\n   Ran with ffmpeg: ffmpeg -i 2.flac 2.mp3"
+        ));
+        assert!(transcript.ends_with("ffmpeg -i 6.flac 6.mp3"));
+        assert!(session_section(Some(&transcript)).contains("### Earlier In This Session:"));
+
+        session.clear();
+        assert!(session.is_empty());
+    }
+}
@@ -0,0 +1,135 @@
+//! Splits an intent that asks for several unrelated things ("convert the
+//! videos and also rename the PDFs") into sub-intents the TUI routes and
+//! generates independently.
+//!
+//! Only clear cases are split: every part has to ask for its own kind of
+//! work, and no part may refer to what another produces. Anything else is
+//! left to the router, which asks when a request mixes operations.
+
+use std::collections::HashSet;
+
+use crate::router::requested_capabilities;
+
+/// Most sub-intents one request is split into.
+pub const MAX_SUBTASKS: usize = 4;
+
+/// Joiners tried first; they rarely occur inside a single task.
+const STRONG_JOINERS: &[&str] = &["; ", ", and also ", " and also ", ", also "];
+
+/// Tried when the strong ones split nothing; `and` also joins the parts
+/// of one task ("mp4 and webm"), which the capability check then rejects.
+const WEAK_JOINERS: &[&str] = &[", and ", " and "];
+
+/// Words that make a part depend on another part's result.
+const BACK_REFERENCES: &[&str] = &[
+    "them",
+    "it",
+    "those",
+    "these",
+    "result",
+    "results",
+    "output",
+    "outputs",
+    "then",
+    "afterwards",
+];
+
+/// The sub-intents of `intent`, or an empty list when it is one task or
+/// can't be split safely.
+pub fn split_intent(intent: &str) -> Vec<String> {
+    let strong = split_on(intent, STRONG_JOINERS);
+    if independent(&strong) {
+        return strong;
+    }
+    let all: Vec<&str> = STRONG_JOINERS.iter().chain(WEAK_JOINERS).copied().collect();
+    let weak = split_on(intent, &all);
+    if independent(&weak) {
+        return weak;
+    }
+    Vec::new()
+}
+
+/// Cuts `intent` at every joiner, matched case-insensitively; the longest
+/// joiner wins where several start at the same place.
+fn split_on(intent: &str, joiners: &[&str]) -> Vec<String> {
+    let lower = intent.to_ascii_lowercase();
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i < lower.len() {
+        let joiner = joiners
+            .iter()
+            .filter(|joiner| lower[i..].starts_with(*joiner))
+            .max_by_key(|joiner| joiner.len());
+        match joiner {
+            Some(joiner) => {
+                parts.push(clean_part(&intent[start..i]));
+                i += joiner.len();
+                start = i;
+            }
+            None => i += lower[i..].chars().next().map_or(1, char::len_utf8),
+        }
+    }
+    parts.push(clean_part(&intent[start..]));
+    parts
+}
+
+fn clean_part(part: &str) -> String {
+    let part = part.trim().trim_end_matches(['.', ',']).trim();
+    let part = match part.get(..5) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("also ") => &part[5..],
+        _ => part,
+    };
+    part.trim().to_string()
+}
+
+fn independent(parts: &[String]) -> bool {
+    if parts.len() < 2 || parts.len() > MAX_SUBTASKS {
+        return false;
+    }
+    let mut claimed = HashSet::new();
+    for (i, part) in parts.iter().enumerate() {
+        let capabilities = requested_capabilities(part);
+        if capabilities.is_empty() || !capabilities.is_disjoint(&claimed) {
+            return false;
+        }
+        if i > 0 && refers_back(part) {
+            return false;
+        }
+        claimed.extend(capabilities);
+    }
+    true
+}
+
+fn refers_back(part: &str) -> bool {
+    part.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|word| BACK_REFERENCES.contains(&word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_only_clearly_unrelated_tasks() {
+        assert_eq!(
+            split_intent("Convert the videos to webm and also rename the PDFs."),
+            ["Convert the videos to webm", "rename the PDFs"]
+        );
+        assert_eq!(
+            split_intent("transcribe interview.wav; download https://a.com/f.zip"),
+            ["transcribe interview.wav", "download https://a.com/f.zip"]
+        );
+        assert_eq!(
+            split_intent("OCR the scans and extract the audio from talk.mp4"),
+            ["OCR the scans", "extract the audio from talk.mp4"]
+        );
+
+        // One task, or parts that build on each other.
+        assert!(split_intent("convert the videos to mp4 and webm").is_empty());
+        assert!(split_intent("resize the photos and crop them").is_empty());
+        assert!(split_intent("convert the videos and rename the results").is_empty());
+        assert!(split_intent("rename the photos by date").is_empty());
+    }
+}
@@ -0,0 +1,762 @@
+//! Keeps settings and pins the same on several machines through a git repo
+//! or a WebDAV file. Each side is one JSON document holding the synced
+//! config keys and the pins. The two are merged key by key against the
+//! document of the last sync, so only a key changed on both sides since
+//! then is a conflict, settled by `sync.prefer`.
+//!
+//! Providers hold API keys: they stay on each machine unless
+//! `sync.providers` is `encrypt`, which sends them encrypted with `age`.
+
+use anyhow::{anyhow, Context, Result};
+use reqwest::header::{CONTENT_TYPE, ETAG, IF_MATCH, IF_NONE_MATCH};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+use crate::config::{write_private, Config, IntentTemplate};
+use crate::executor::{load_pins, save_pins};
+use crate::PinnedHistoryEntry;
+
+/// Config keys that follow the user between machines. Remotes, the
+/// sandbox and other settings tied to one machine stay local.
+pub const SYNCED_KEYS: &[&str] = &[
+    "models",
+    "theme",
+    "templates",
+    "startup",
+    "pandoc_presets",
+    "output_layout",
+    "language",
+    "plain_ui",
+    "pricing",
+    "token_budget",
+];
+
+/// The document's name in the git repo.
+const SYNC_FILE: &str = "dexter-sync.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncBackend {
+    Git,
+    Webdav,
+}
+
+/// What happens to `providers`, which hold API keys.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderSync {
+    /// Each machine keeps its own.
+    #[default]
+    Exclude,
+    /// Sent encrypted to `age_recipient`; decrypted with `age_identity`.
+    Encrypt,
+}
+
+/// Which side wins a key changed on both since the last sync.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncPrefer {
+    #[default]
+    Local,
+    Remote,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SyncPolicy {
+    /// `git` or `webdav`; `dexter sync` refuses to run without one.
+    #[serde(default)]
+    pub backend: Option<SyncBackend>,
+    /// Git remote (`git@host:me/dexter-sync.git`) or the WebDAV URL of the
+    /// document (`https://dav.example.com/dexter/sync.json`).
+    #[serde(default)]
+    pub remote: String,
+    /// Branch of the git remote.
+    #[serde(default = "default_branch")]
+    pub branch: String,
+    /// WebDAV user; the password is read from the `password_env` variable.
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password_env: Option<String>,
+    #[serde(default)]
+    pub providers: ProviderSync,
+    /// age recipient (`age1...`) providers are encrypted to.
+    #[serde(default)]
+    pub age_recipient: Option<String>,
+    /// age identity file that decrypts them.
+    #[serde(default)]
+    pub age_identity: Option<String>,
+    #[serde(default)]
+    pub prefer: SyncPrefer,
+}
+
+fn default_branch() -> String {
+    "main".to_string()
+}
+
+impl Default for SyncPolicy {
+    fn default() -> Self {
+        Self {
+            backend: None,
+            remote: String::new(),
+            branch: default_branch(),
+            username: None,
+            password_env: None,
+            providers: ProviderSync::default(),
+            age_recipient: None,
+            age_identity: None,
+            prefer: SyncPrefer::default(),
+        }
+    }
+}
+
+/// What one sync changed, by key (`models.router_model`,
+/// `templates.Convert media`, `pins.ffmpeg: ffmpeg -i ...`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// Keys taken from the remote copy.
+    pub pulled: Vec<String>,
+    /// Keys the remote copy gets from this machine.
+    pub pushed: Vec<String>,
+    /// Keys changed on both sides, each with the side that won.
+    pub conflicts: Vec<String>,
+}
+
+impl SyncReport {
+    pub fn summary(&self) -> String {
+        if self.pulled.is_empty() && self.pushed.is_empty() && self.conflicts.is_empty() {
+            return "Already in sync.".to_string();
+        }
+        let mut out = Vec::new();
+        for (label, keys) in [
+            ("Pulled", &self.pulled),
+            ("Pushed", &self.pushed),
+            ("Conflict", &self.conflicts),
+        ] {
+            out.extend(keys.iter().map(|key| format!("{}: {}", label, key)));
+        }
+        out.join("\n")
+    }
+}
+
+/// Merges this machine's settings and pins with the remote copy, then
+/// writes the result to both. `dry_run` only reports what would change.
+pub async fn sync(config: &Config, policy: &SyncPolicy, dry_run: bool) -> Result<SyncReport> {
+    let backend = policy
+        .backend
+        .ok_or_else(|| anyhow!("Sync is off: set `sync.backend` to `git` or `webdav`"))?;
+    if policy.remote.trim().is_empty() {
+        return Err(anyhow!("Sync needs `sync.remote`"));
+    }
+    let encrypt = policy.providers == ProviderSync::Encrypt;
+    if encrypt && (policy.age_recipient.is_none() || policy.age_identity.is_none()) {
+        return Err(anyhow!(
+            "`sync.providers = \"encrypt\"` needs `sync.age_recipient` and `sync.age_identity`"
+        ));
+    }
+
+    let (raw, version) = match backend {
+        SyncBackend::Git => (git_fetch(policy).await?, RemoteVersion::Missing),
+        SyncBackend::Webdav => match webdav_get(policy).await? {
+            Some((raw, etag)) => (Some(raw), RemoteVersion::Present(etag)),
+            None => (None, RemoteVersion::Missing),
+        },
+    };
+    let mut remote = match &raw {
+        Some(raw) => Some(serde_json::from_str::<Value>(raw).context("Invalid sync document")?),
+        None => None,
+    };
+    // Another machine's encrypted providers pass through untouched when
+    // this one keeps its own.
+    let sealed = remote
+        .as_mut()
+        .and_then(Value::as_object_mut)
+        .and_then(|doc| doc.remove("providers_age"));
+    if let (true, Some(Value::String(armored))) = (encrypt, &sealed) {
+        let providers = age_decrypt(policy, armored).await?;
+        if let Some(doc) = remote.as_mut().and_then(Value::as_object_mut) {
+            doc.insert("providers".to_string(), serde_json::from_slice(&providers)?);
+        }
+    }
+
+    let local = local_document(config, &load_pins().await?, encrypt)?;
+    let base = load_base().await;
+    let merged = merge(base.as_ref(), &local, remote.as_ref(), policy.prefer);
+    let report = SyncReport {
+        pulled: changed_keys(&local, Some(&merged.value)),
+        pushed: changed_keys(&merged.value, remote.as_ref()),
+        conflicts: merged.conflicts.clone(),
+    };
+    if dry_run {
+        return Ok(report);
+    }
+
+    if !report.pulled.is_empty() {
+        apply_document(config, &merged.value)?.save().await?;
+        save_pins(&document_pins(&merged.value)).await?;
+    }
+    if remote.is_none() || !report.pushed.is_empty() {
+        let mut upload = merged.value.clone();
+        if let Some(doc) = upload.as_object_mut() {
+            let providers = doc.remove("providers");
+            match (encrypt, providers) {
+                (true, Some(providers)) => {
+                    let armored = age_encrypt(policy, &serde_json::to_vec(&providers)?).await?;
+                    doc.insert("providers_age".to_string(), Value::String(armored));
+                }
+                _ => {
+                    if let Some(sealed) = sealed {
+                        doc.insert("providers_age".to_string(), sealed);
+                    }
+                }
+            }
+        }
+        let body = serde_json::to_string_pretty(&upload)?;
+        match backend {
+            SyncBackend::Git => git_push(policy, &body).await?,
+            SyncBackend::Webdav => webdav_put(policy, &body, &version).await?,
+        }
+    }
+    save_base(&merged.value).await?;
+    Ok(report)
+}
+
+/// The synced parts of `config` and `pins`. Templates are keyed by name
+/// and pins by plugin, command and time, so each merges on its own.
+pub fn local_document(
+    config: &Config,
+    pins: &[PinnedHistoryEntry],
+    with_providers: bool,
+) -> Result<Value> {
+    let all = serde_json::to_value(config)?;
+    let mut settings = Map::new();
+    for key in SYNCED_KEYS {
+        if let Some(value) = all.get(*key) {
+            settings.insert(key.to_string(), value.clone());
+        }
+    }
+    let templates: Map<String, Value> = config
+        .templates
+        .iter()
+        .map(|t| (t.name.clone(), Value::String(t.text.clone())))
+        .collect();
+    settings.insert("templates".to_string(), Value::Object(templates));
+
+    let mut doc = Map::new();
+    doc.insert("config".to_string(), Value::Object(settings));
+    let pins: Map<String, Value> = pins
+        .iter()
+        .map(|pin| Ok((pin_key(pin), serde_json::to_value(pin)?)))
+        .collect::<Result<_>>()?;
+    doc.insert("pins".to_string(), Value::Object(pins));
+    if with_providers {
+        doc.insert(
+            "providers".to_string(),
+            serde_json::to_value(&config.providers)?,
+        );
+    }
+    Ok(Value::Object(doc))
+}
+
+fn pin_key(pin: &PinnedHistoryEntry) -> String {
+    format!("{}: {} @ {}", pin.plugin, pin.command, pin.timestamp)
+}
+
+/// `config` with the synced keys of `doc`. Templates keep their local
+/// order; new ones are added at the end.
+pub fn apply_document(config: &Config, doc: &Value) -> Result<Config> {
+    let mut all = serde_json::to_value(config)?;
+    let settings = doc.get("config").and_then(Value::as_object);
+    if let (Some(all), Some(settings)) = (all.as_object_mut(), settings) {
+        for key in SYNCED_KEYS.iter().filter(|key| **key != "templates") {
+            if let Some(value) = settings.get(*key) {
+                all.insert(key.to_string(), value.clone());
+            }
+        }
+        if let Some(providers) = doc.get("providers") {
+            all.insert("providers".to_string(), providers.clone());
+        }
+    }
+    let mut updated: Config = serde_json::from_value(all)?;
+
+    let synced: BTreeMap<String, String> = settings
+        .and_then(|settings| settings.get("templates"))
+        .and_then(Value::as_object)
+        .map(|templates| {
+            templates
+                .iter()
+                .filter_map(|(name, text)| Some((name.clone(), text.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default();
+    let mut templates: Vec<IntentTemplate> = config
+        .templates
+        .iter()
+        .filter_map(|t| {
+            Some(IntentTemplate {
+                name: t.name.clone(),
+                text: synced.get(&t.name)?.clone(),
+            })
+        })
+        .collect();
+    for (name, text) in &synced {
+        if !templates.iter().any(|t| &t.name == name) {
+            templates.push(IntentTemplate {
+                name: name.clone(),
+                text: text.clone(),
+            });
+        }
+    }
+    updated.templates = templates;
+    Ok(updated)
+}
+
+/// The pins of `doc`, oldest pin first as the pin file keeps them.
+pub fn document_pins(doc: &Value) -> Vec<PinnedHistoryEntry> {
+    let mut pins: Vec<PinnedHistoryEntry> = doc
+        .get("pins")
+        .and_then(Value::as_object)
+        .map(|pins| {
+            pins.values()
+                .filter_map(|pin| serde_json::from_value(pin.clone()).ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    pins.sort_by(|a, b| a.pinned_at.cmp(&b.pinned_at));
+    pins
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Merged {
+    pub value: Value,
+    /// Keys changed on both sides, with the side that won.
+    pub conflicts: Vec<String>,
+}
+
+/// Three-way merge of `local` and `remote` against `base`, the document
+/// of the last sync. Every leaf (a value that isn't an object) is one key:
+/// a side that left it as in `base` takes the other side's change,
+/// including a deletion. Without a base every key the sides disagree on
+/// is a conflict.
+pub fn merge(
+    base: Option<&Value>,
+    local: &Value,
+    remote: Option<&Value>,
+    prefer: SyncPrefer,
+) -> Merged {
+    let Some(remote) = remote else {
+        return Merged {
+            value: local.clone(),
+            conflicts: Vec::new(),
+        };
+    };
+    let (base, local, remote) = (
+        base.map(flatten).unwrap_or_default(),
+        flatten(local),
+        flatten(remote),
+    );
+    let keys: BTreeSet<&Vec<String>> = local.keys().chain(remote.keys()).collect();
+    let mut merged = BTreeMap::new();
+    let mut conflicts = Vec::new();
+    for key in keys {
+        let (ours, theirs, was) = (local.get(key), remote.get(key), base.get(key));
+        let value = if ours == theirs || theirs == was {
+            ours
+        } else if ours == was {
+            theirs
+        } else {
+            let (winner, side) = match prefer {
+                SyncPrefer::Local => (ours, "local"),
+                SyncPrefer::Remote => (theirs, "remote"),
+            };
+            conflicts.push(format!("{} (kept {})", key.join("."), side));
+            winner
+        };
+        if let Some(value) = value {
+            merged.insert(key.clone(), value.clone());
+        }
+    }
+    Merged {
+        value: unflatten(merged),
+        conflicts,
+    }
+}
+
+/// Leaves of `doc` by path. Empty objects are leaves too, so they survive
+/// the round trip.
+fn flatten(doc: &Value) -> BTreeMap<Vec<String>, Value> {
+    fn walk(value: &Value, path: &mut Vec<String>, out: &mut BTreeMap<Vec<String>, Value>) {
+        match value {
+            Value::Object(map) if !map.is_empty() => {
+                for (key, child) in map {
+                    path.push(key.clone());
+                    walk(child, path, out);
+                    path.pop();
+                }
+            }
+            _ => {
+                out.insert(path.clone(), value.clone());
+            }
+        }
+    }
+    let mut out = BTreeMap::new();
+    walk(doc, &mut Vec::new(), &mut out);
+    out
+}
+
+fn unflatten(leaves: BTreeMap<Vec<String>, Value>) -> Value {
+    let mut root = Value::Object(Map::new());
+    for (path, value) in leaves {
+        let mut node = &mut root;
+        for key in &path {
+            if !node.is_object() {
+                *node = Value::Object(Map::new());
+            }
+            node = node
+                .as_object_mut()
+                .expect("just made an object")
+                .entry(key.clone())
+                .or_insert(Value::Null);
+        }
+        *node = value;
+    }
+    root
+}
+
+/// Keys whose value in `to` differs from `from` (all of them without
+/// `to`), as dotted paths.
+fn changed_keys(from: &Value, to: Option<&Value>) -> Vec<String> {
+    let from = flatten(from);
+    let to = to.map(flatten).unwrap_or_default();
+    let keys: BTreeSet<&Vec<String>> = from.keys().chain(to.keys()).collect();
+    keys.into_iter()
+        .filter(|key| from.get(*key) != to.get(*key))
+        .map(|key| key.join("."))
+        .collect()
+}
+
+fn sync_dir() -> Result<PathBuf> {
+    Ok(crate::paths::data_dir()
+        .context("Could not find data directory")?
+        .join("dexter"))
+}
+
+/// The merged document of the last sync; `None` before the first one.
+async fn load_base() -> Option<Value> {
+    let raw = fs::read_to_string(sync_dir().ok()?.join("sync_base.json"))
+        .await
+        .ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+/// Saves the merged document for the next sync, readable by the owner only:
+/// with encrypted providers it holds their keys in the clear.
+async fn save_base(doc: &Value) -> Result<()> {
+    let dir = sync_dir()?;
+    fs::create_dir_all(&dir).await?;
+    let path = dir.join("sync_base.json");
+    let tmp = path.with_extension("json.tmp");
+    write_private(&tmp, &serde_json::to_string_pretty(doc)?).await?;
+    fs::rename(&tmp, &path).await?;
+    Ok(())
+}
+
+/// What the WebDAV server held when the sync started, so the upload can
+/// refuse to replace a copy another machine wrote in the meantime.
+enum RemoteVersion {
+    Missing,
+    Present(Option<String>),
+}
+
+fn webdav_request(policy: &SyncPolicy, method: reqwest::Method) -> Result<reqwest::RequestBuilder> {
+    let request = reqwest::Client::new().request(method, policy.remote.trim());
+    let Some(user) = &policy.username else {
+        return Ok(request);
+    };
+    let password = match &policy.password_env {
+        Some(var) => Some(std::env::var(var).with_context(|| format!("`{}` is not set", var))?),
+        None => None,
+    };
+    Ok(request.basic_auth(user, password))
+}
+
+async fn webdav_get(policy: &SyncPolicy) -> Result<Option<(String, Option<String>)>> {
+    let response = webdav_request(policy, reqwest::Method::GET)?
+        .send()
+        .await
+        .context("WebDAV download failed")?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let response = response.error_for_status()?;
+    let etag = response
+        .headers()
+        .get(ETAG)
+        .and_then(|tag| tag.to_str().ok())
+        .map(str::to_string);
+    Ok(Some((response.text().await?, etag)))
+}
+
+async fn webdav_put(policy: &SyncPolicy, body: &str, version: &RemoteVersion) -> Result<()> {
+    let mut request = webdav_request(policy, reqwest::Method::PUT)?
+        .header(CONTENT_TYPE, "application/json")
+        .body(body.to_string());
+    request = match version {
+        RemoteVersion::Missing => request.header(IF_NONE_MATCH, "*"),
+        RemoteVersion::Present(Some(etag)) => request.header(IF_MATCH, etag),
+        RemoteVersion::Present(None) => request,
+    };
+    let response = request.send().await.context("WebDAV upload failed")?;
+    if response.status() == StatusCode::PRECONDITION_FAILED {
+        return Err(anyhow!(
+            "The remote copy changed during the sync; run it again"
+        ));
+    }
+    response.error_for_status()?;
+    Ok(())
+}
+
+/// The local clone of the sync repo.
+fn git_dir() -> Result<PathBuf> {
+    Ok(sync_dir()?.join("sync-repo"))
+}
+
+async fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = tokio::process::Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .await
+        .context("git not found")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Clones `remote` into `dir` unless it is already a clone of it. A clone
+/// of another remote, left by an earlier `sync.remote`, is replaced: it
+/// holds nothing that isn't pushed.
+async fn ensure_clone(dir: &Path, remote: &str) -> Result<()> {
+    if dir.join(".git").exists() {
+        let origin = git(dir, &["remote", "get-url", "origin"]).await.ok();
+        if origin.as_deref().map(str::trim) == Some(remote) {
+            return Ok(());
+        }
+        fs::remove_dir_all(dir).await?;
+    }
+    let parent = dir.parent().context("sync dir has no parent")?;
+    fs::create_dir_all(parent).await?;
+    let target = dir.to_string_lossy().into_owned();
+    git(parent, &["clone", "--quiet", "--", remote, &target]).await?;
+    Ok(())
+}
+
+/// Clones the repo on first use and resets it to the remote branch; the
+/// document on it, or `None` when the branch or file doesn't exist yet.
+async fn git_fetch(policy: &SyncPolicy) -> Result<Option<String>> {
+    let dir = git_dir()?;
+    ensure_clone(&dir, policy.remote.trim()).await?;
+    let branch = policy.branch.trim();
+    if git(&dir, &["ls-remote", "--heads", "origin", branch])
+        .await?
+        .trim()
+        .is_empty()
+    {
+        return Ok(None);
+    }
+    git(&dir, &["fetch", "--quiet", "origin", branch]).await?;
+    let remote_branch = format!("origin/{}", branch);
+    git(&dir, &["checkout", "--quiet", "-B", branch, &remote_branch]).await?;
+    git(&dir, &["reset", "--quiet", "--hard", &remote_branch]).await?;
+    match fs::read_to_string(dir.join(SYNC_FILE)).await {
+        Ok(raw) => Ok(Some(raw)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+async fn git_push(policy: &SyncPolicy, body: &str) -> Result<()> {
+    let dir = git_dir()?;
+    let branch = policy.branch.trim();
+    fs::write(dir.join(SYNC_FILE), body).await?;
+    git(&dir, &["checkout", "--quiet", "-B", branch]).await?;
+    git(&dir, &["add", SYNC_FILE]).await?;
+    if git(&dir, &["status", "--porcelain"])
+        .await?
+        .trim()
+        .is_empty()
+    {
+        return Ok(());
+    }
+    // Commits need an identity; machines without one get a neutral one.
+    let mut commit = Vec::new();
+    if git(&dir, &["config", "user.email"]).await.is_err() {
+        commit.extend([
+            "-c",
+            "user.name=Dexter",
+            "-c",
+            "user.email=dexter@localhost",
+        ]);
+    }
+    commit.extend(["commit", "--quiet", "-m", "Sync Dexter settings"]);
+    git(&dir, &commit).await?;
+    let refspec = format!("HEAD:{}", branch);
+    git(&dir, &["push", "--quiet", "origin", &refspec])
+        .await
+        .context("Push rejected; another machine synced meanwhile, run it again")?;
+    Ok(())
+}
+
+async fn age(args: &[&str], input: &[u8]) -> Result<Vec<u8>> {
+    let mut child = tokio::process::Command::new("age")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("age not found; install it to sync providers encrypted")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input).await?;
+    }
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "age failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+async fn age_encrypt(policy: &SyncPolicy, plain: &[u8]) -> Result<String> {
+    let recipient = policy.age_recipient.as_deref().unwrap_or_default();
+    let sealed = age(&["--armor", "--recipient", recipient], plain).await?;
+    Ok(String::from_utf8_lossy(&sealed).into_owned())
+}
+
+async fn age_decrypt(policy: &SyncPolicy, armored: &str) -> Result<Vec<u8>> {
+    let identity = crate::paths::expand_home(policy.age_identity.as_deref().unwrap_or_default());
+    let identity = identity.to_string_lossy();
+    age(&["--decrypt", "--identity", &identity], armored.as_bytes()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn merges_key_by_key_against_the_last_sync() {
+        let base = json!({"config": {"theme": "dark", "language": "auto"}, "pins": {}});
+        let local = json!({
+            "config": {"theme": "retro", "language": "auto", "plain_ui": true},
+            "pins": {"a": 1}
+        });
+        let remote = json!({
+            "config": {"theme": "dark", "language": "German"},
+            "pins": {"b": 2}
+        });
+        let merged = merge(Some(&base), &local, Some(&remote), SyncPrefer::Local);
+        assert_eq!(
+            merged.value,
+            json!({
+                "config": {"theme": "retro", "language": "German", "plain_ui": true},
+                "pins": {"a": 1, "b": 2}
+            })
+        );
+        assert!(merged.conflicts.is_empty());
+
+        // Deleted remotely, untouched locally: gone.
+        let remote = json!({"config": {"theme": "dark"}, "pins": {}});
+        let merged = merge(Some(&base), &base, Some(&remote), SyncPrefer::Local);
+        assert_eq!(merged.value["config"].get("language"), None);
+
+        // Changed on both sides: `prefer` decides.
+        let remote = json!({"config": {"theme": "light", "language": "auto"}, "pins": {}});
+        let merged = merge(Some(&base), &local, Some(&remote), SyncPrefer::Remote);
+        assert_eq!(merged.value["config"]["theme"], "light");
+        assert_eq!(merged.conflicts, ["config.theme (kept remote)"]);
+        let merged = merge(None, &local, Some(&remote), SyncPrefer::Local);
+        assert_eq!(merged.value["config"]["theme"], "retro");
+    }
+
+    #[test]
+    fn documents_round_trip_templates_pins_and_providers() {
+        let config = Config {
+            theme: "retro".to_string(),
+            templates: vec![
+                IntentTemplate {
+                    name: "B".to_string(),
+                    text: "b".to_string(),
+                },
+                IntentTemplate {
+                    name: "A".to_string(),
+                    text: "a".to_string(),
+                },
+            ],
+            ..Config::default()
+        };
+        let pin = PinnedHistoryEntry {
+            timestamp: "2026-01-01T00:00:00Z".to_string(),
+            plugin: "f2".to_string(),
+            command: "f2 -f a -r b".to_string(),
+            pinned_at: "2026-01-02T00:00:00Z".to_string(),
+            intent: None,
+        };
+        let mut doc = local_document(&config, std::slice::from_ref(&pin), false).unwrap();
+        assert!(doc.get("providers").is_none());
+        assert!(doc["config"].get("remotes").is_none());
+        assert_eq!(doc["config"]["templates"]["A"], "a");
+
+        doc["config"]["templates"]
+            .as_object_mut()
+            .unwrap()
+            .insert("C".to_string(), json!("c"));
+        doc["config"]["templates"]
+            .as_object_mut()
+            .unwrap()
+            .remove("B");
+        doc["config"]["theme"] = json!("light");
+        let applied = apply_document(&config, &doc).unwrap();
+        assert_eq!(applied.theme, "light");
+        let names: Vec<&str> = applied.templates.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["A", "C"]);
+        assert_eq!(document_pins(&doc), [pin]);
+    }
+
+    #[tokio::test]
+    async fn reclones_when_the_remote_changes() {
+        let root = tempfile::tempdir().unwrap();
+        let remote = |name: &str| {
+            let path = root.path().join(name);
+            std::process::Command::new("git")
+                .args(["init", "--quiet"])
+                .arg(&path)
+                .status()
+                .unwrap();
+            path.to_string_lossy().into_owned()
+        };
+        let (first, second) = (remote("first"), remote("second"));
+        let dir = root.path().join("sync").join("sync-repo");
+        let origin = || async { git(&dir, &["remote", "get-url", "origin"]).await.unwrap() };
+
+        ensure_clone(&dir, &first).await.unwrap();
+        assert_eq!(origin().await.trim(), first);
+        ensure_clone(&dir, &first).await.unwrap();
+        assert_eq!(origin().await.trim(), first);
+        ensure_clone(&dir, &second).await.unwrap();
+        assert_eq!(origin().await.trim(), second);
+    }
+}
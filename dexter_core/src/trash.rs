@@ -0,0 +1,400 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::journal::FileOp;
+
+const MANIFEST: &str = "manifest.json";
+
+/// `<data dir>/dexter/trash/`: one directory per staging batch, from any
+/// working directory. Staging is a hard link, or a copy when the data dir
+/// is on another mount.
+pub fn trash_dir() -> Result<PathBuf> {
+    Ok(crate::paths::data_dir()
+        .context("Could not find data directory")?
+        .join("dexter")
+        .join("trash"))
+}
+
+/// Soft-delete staging for f2 renames that run with `--allow-overwrites`,
+/// and where commands that delete files send them.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TrashPolicy {
+    /// Keep the files such a rename would destroy in the Dexter trash
+    /// (`trash_dir`) before running it.
+    #[serde(default)]
+    pub stage_f2_overwrites: bool,
+    /// Age in days after which `dexter trash purge` removes a staging batch.
+    #[serde(default = "default_keep_days")]
+    pub keep_days: u32,
+    /// What happens to files a command deletes (jdupes `-d`, `cleanup
+    /// --delete`).
+    #[serde(default)]
+    pub deletes: DeleteMode,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DeleteMode {
+    /// Move them to the system trash; refuse to run without one.
+    #[default]
+    Trash,
+    /// Refuse to run commands that delete files.
+    Block,
+    /// Let the tool delete them for good.
+    Permanent,
+}
+
+fn default_keep_days() -> u32 {
+    30
+}
+
+impl Default for TrashPolicy {
+    fn default() -> Self {
+        Self {
+            stage_f2_overwrites: false,
+            keep_days: default_keep_days(),
+            deletes: DeleteMode::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StagedFile {
+    /// Where the file lived, relative to the run's cwd.
+    pub path: PathBuf,
+    /// Where its content is kept, under `trash_dir`.
+    pub staged: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TrashManifest {
+    pub command: String,
+    pub cwd: PathBuf,
+    pub created_at: DateTime<Utc>,
+    pub files: Vec<StagedFile>,
+}
+
+/// One run's staged originals under `<trash_dir>/<id>/`.
+#[derive(Debug, Clone)]
+pub struct TrashBatch {
+    pub id: String,
+    pub dir: PathBuf,
+    pub manifest: TrashManifest,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RestoreReport {
+    pub restored: usize,
+    /// Files left where they are, with the reason.
+    pub kept: Vec<String>,
+}
+
+/// Stages the files an f2 run planned as `ops` would destroy, when `policy`
+/// asks for it, and returns the journal entries for them so a rollback puts
+/// them back. Without `--allow-overwrites` f2 refuses such renames itself,
+/// so nothing is staged.
+pub fn stage_overwrites(
+    policy: &TrashPolicy,
+    plugin: &str,
+    command: &str,
+    cwd: &Path,
+    ops: &[FileOp],
+) -> Result<Vec<FileOp>> {
+    if !policy.stage_f2_overwrites || plugin != "f2" || !allows_overwrites(command) {
+        return Ok(Vec::new());
+    }
+    stage_overwrites_in(&trash_dir()?, command, cwd, ops)
+}
+
+fn stage_overwrites_in(
+    root: &Path,
+    command: &str,
+    cwd: &Path,
+    ops: &[FileOp],
+) -> Result<Vec<FileOp>> {
+    let at_risk = files_at_risk(ops, cwd);
+    if at_risk.is_empty() {
+        return Ok(Vec::new());
+    }
+    let batch = stage(root, cwd, command, &at_risk)?;
+    Ok(batch
+        .manifest
+        .files
+        .into_iter()
+        .map(|file| FileOp::Stage {
+            path: file.path,
+            staged: file.staged,
+        })
+        .collect())
+}
+
+fn allows_overwrites(command: &str) -> bool {
+    shell_words::split(command)
+        .map(|argv| argv.iter().any(|a| a == "--allow-overwrites"))
+        .unwrap_or(false)
+}
+
+/// Existing files a rename lands on (unless they are renamed away in the
+/// same run) and every source of a rename that shares its target with
+/// another one.
+fn files_at_risk(ops: &[FileOp], cwd: &Path) -> Vec<PathBuf> {
+    let renames: Vec<(&PathBuf, &PathBuf)> = ops
+        .iter()
+        .filter_map(|op| match op {
+            FileOp::Rename { from, to } => Some((from, to)),
+            _ => None,
+        })
+        .collect();
+    let sources: HashSet<&PathBuf> = renames.iter().map(|(from, _)| *from).collect();
+    let mut by_target: HashMap<&PathBuf, Vec<&PathBuf>> = HashMap::new();
+    for (from, to) in &renames {
+        by_target.entry(*to).or_default().push(*from);
+    }
+
+    let mut at_risk = Vec::new();
+    for (to, froms) in by_target {
+        let case_change = froms.len() == 1
+            && froms[0].to_string_lossy().to_lowercase() == to.to_string_lossy().to_lowercase();
+        if !sources.contains(to) && !case_change && cwd.join(to).is_file() {
+            at_risk.push(to.clone());
+        }
+        if froms.len() > 1 {
+            at_risk.extend(froms.into_iter().cloned());
+        }
+    }
+    at_risk.sort();
+    at_risk.dedup();
+    at_risk
+}
+
+/// Keeps `paths` (relative to `cwd`) in a new batch under `root`. The
+/// originals stay in place for the command to act on.
+pub fn stage(root: &Path, cwd: &Path, command: &str, paths: &[PathBuf]) -> Result<TrashBatch> {
+    let created_at = Utc::now();
+    let (id, dir) = new_batch_dir(root, created_at)?;
+    let mut files = Vec::new();
+    for path in paths {
+        let staged = dir.join(path);
+        if let Some(parent) = staged.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if fs::hard_link(cwd.join(path), &staged).is_err() {
+            fs::copy(cwd.join(path), &staged)
+                .with_context(|| format!("Failed to stage {}", path.display()))?;
+        }
+        files.push(StagedFile {
+            path: path.clone(),
+            staged,
+        });
+    }
+    let manifest = TrashManifest {
+        command: command.to_string(),
+        cwd: cwd.to_path_buf(),
+        created_at,
+        files,
+    };
+    fs::write(dir.join(MANIFEST), serde_json::to_string_pretty(&manifest)?)?;
+    Ok(TrashBatch { id, dir, manifest })
+}
+
+fn new_batch_dir(root: &Path, created_at: DateTime<Utc>) -> Result<(String, PathBuf)> {
+    let stamp = created_at.format("%Y%m%d-%H%M%S").to_string();
+    fs::create_dir_all(root).with_context(|| format!("Failed to create {}", root.display()))?;
+    for n in 0.. {
+        let id = if n == 0 {
+            stamp.clone()
+        } else {
+            format!("{}-{}", stamp, n)
+        };
+        let dir = root.join(&id);
+        match fs::create_dir(&dir) {
+            Ok(()) => return Ok((id, dir)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    unreachable!()
+}
+
+/// Staging batches of runs in `cwd`, newest first.
+pub fn list(cwd: &Path) -> Result<Vec<TrashBatch>> {
+    list_in(&trash_dir()?, cwd)
+}
+
+/// Directories without a readable manifest are skipped.
+fn list_in(root: &Path, cwd: &Path) -> Result<Vec<TrashBatch>> {
+    let entries = match fs::read_dir(root) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut batches = Vec::new();
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let dir = entry.path();
+        let Ok(raw) = fs::read_to_string(dir.join(MANIFEST)) else {
+            continue;
+        };
+        match serde_json::from_str::<TrashManifest>(&raw) {
+            Ok(manifest) if manifest.cwd != cwd => {}
+            Ok(manifest) => batches.push(TrashBatch {
+                id: entry.file_name().to_string_lossy().into_owned(),
+                dir,
+                manifest,
+            }),
+            Err(e) => tracing::warn!(dir = %dir.display(), error = %e, "unreadable trash manifest"),
+        }
+    }
+    batches.sort_by_key(|batch| std::cmp::Reverse(batch.manifest.created_at));
+    Ok(batches)
+}
+
+/// Removes the batches of runs in `cwd` older than `keep_days`, or all of
+/// them when `None`; returns how many were removed.
+pub fn purge(cwd: &Path, keep_days: Option<u32>) -> Result<usize> {
+    purge_in(&trash_dir()?, cwd, keep_days)
+}
+
+fn purge_in(root: &Path, cwd: &Path, keep_days: Option<u32>) -> Result<usize> {
+    let now = Utc::now();
+    let mut removed = 0;
+    for batch in list_in(root, cwd)? {
+        let age = now - batch.manifest.created_at;
+        if keep_days.is_some_and(|days| age < Duration::days(days.into())) {
+            continue;
+        }
+        batch.remove()?;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+impl TrashBatch {
+    /// `20260101-120000  2026-01-01 13:00  2 file(s)  f2 -f ...`.
+    pub fn summary(&self) -> String {
+        format!(
+            "{}  {}  {} file(s)  {}",
+            self.id,
+            self.manifest
+                .created_at
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M"),
+            self.manifest.files.len(),
+            self.manifest.command
+        )
+    }
+
+    /// Moves staged files back to their old names. Names that are taken
+    /// (typically by the renamed files, until `f2 -u -x` undoes the run)
+    /// are reported and left alone.
+    pub fn restore(&self) -> Result<RestoreReport> {
+        let cwd = &self.manifest.cwd;
+        let mut report = RestoreReport::default();
+        for file in &self.manifest.files {
+            match restore_file(cwd, &file.path, &file.staged) {
+                Ok(true) => report.restored += 1,
+                Ok(false) => {}
+                Err(e) => report.kept.push(format!("{}: {}", file.path.display(), e)),
+            }
+        }
+        Ok(report)
+    }
+
+    pub fn remove(&self) -> Result<()> {
+        fs::remove_dir_all(&self.dir)
+            .with_context(|| format!("Failed to remove {}", self.dir.display()))
+    }
+}
+
+/// `Ok(false)` when the staged copy is already gone (restored before).
+/// The trash is usually on another mount than `cwd`, so a failed rename
+/// falls back to copying the file back.
+pub(crate) fn restore_file(cwd: &Path, path: &Path, staged: &Path) -> Result<bool> {
+    if !cwd.join(staged).exists() {
+        return Ok(false);
+    }
+    if cwd.join(path).exists() {
+        return Err(anyhow!("name is taken again"));
+    }
+    if let Some(parent) = cwd.join(path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if fs::rename(cwd.join(staged), cwd.join(path)).is_err() {
+        fs::copy(cwd.join(staged), cwd.join(path))?;
+        fs::remove_file(cwd.join(staged))?;
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal::{interrupted_runs_in, Journal};
+    use tempfile::tempdir;
+
+    #[test]
+    fn stages_overwritten_and_collapsed_files_and_rolls_them_back() -> Result<()> {
+        let work = tempdir()?;
+        let journals = tempdir()?;
+        let trash = tempdir()?;
+        let cwd = work.path();
+        for name in ["a.txt", "b.txt", "keep.txt", "x1.txt", "x2.txt"] {
+            fs::write(cwd.join(name), name)?;
+        }
+        let rename = |from: &str, to: &str| FileOp::Rename {
+            from: from.into(),
+            to: to.into(),
+        };
+        let ops = vec![
+            rename("a.txt", "keep.txt"),
+            rename("x1.txt", "x.txt"),
+            rename("x2.txt", "x.txt"),
+            rename("b.txt", "B.TXT"),
+        ];
+        let command = "f2 -f a -r keep --allow-overwrites -x";
+        let policy = TrashPolicy {
+            stage_f2_overwrites: true,
+            ..TrashPolicy::default()
+        };
+        assert!(stage_overwrites(&TrashPolicy::default(), "f2", command, cwd, &ops)?.is_empty());
+        assert!(stage_overwrites(&policy, "f2", "f2 -f a -r keep -x", cwd, &ops)?.is_empty());
+
+        let staged = stage_overwrites_in(trash.path(), command, cwd, &ops)?;
+        let staged_paths: Vec<_> = staged
+            .iter()
+            .map(|op| match op {
+                FileOp::Stage { path, .. } => path.to_string_lossy().into_owned(),
+                other => panic!("unexpected op {:?}", other),
+            })
+            .collect();
+        assert_eq!(staged_paths, ["keep.txt", "x1.txt", "x2.txt"]);
+
+        let mut journal_ops = staged;
+        journal_ops.extend(ops);
+        let mut journal = Journal::begin_in(journals.path(), "x", "f2", command, cwd, journal_ops)?;
+        fs::rename(cwd.join("a.txt"), cwd.join("keep.txt"))?;
+        fs::rename(cwd.join("x1.txt"), cwd.join("x.txt"))?;
+        fs::rename(cwd.join("x2.txt"), cwd.join("x.txt"))?;
+        journal.check_progress()?;
+        drop(journal);
+
+        let runs = interrupted_runs_in(journals.path())?;
+        runs[0].roll_back()?;
+        for name in ["a.txt", "keep.txt", "x1.txt", "x2.txt"] {
+            assert_eq!(fs::read_to_string(cwd.join(name))?, name);
+        }
+
+        assert_eq!(fs::read_dir(cwd)?.count(), 5, "nothing staged in the cwd");
+        assert!(list_in(trash.path(), journals.path())?.is_empty());
+        let batches = list_in(trash.path(), cwd)?;
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].restore()?.kept, ["x2.txt: name is taken again"]);
+        assert_eq!(purge_in(trash.path(), cwd, Some(30))?, 0);
+        assert_eq!(purge_in(trash.path(), cwd, None)?, 1);
+        assert_eq!(fs::read_dir(trash.path())?.count(), 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+//! Known tool failures and what to do about them. The error view matches a
+//! failed command's output against this table and shows the fix under the
+//! raw error.
+
+use std::process::Command;
+
+/// A recognised failure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FailureHint {
+    /// What went wrong, in a few words.
+    pub problem: &'static str,
+    /// What the user can do about it.
+    pub fix: &'static str,
+    /// Command that installs what was missing, offered as a button when
+    /// its program is on `PATH`.
+    pub install: Option<&'static [&'static str]>,
+}
+
+struct Pattern {
+    /// Plugin the pattern applies to; `None` for any.
+    plugin: Option<&'static str>,
+    /// Lowercase fragments of which any one must appear in the output.
+    needles: &'static [&'static str],
+    hint: FailureHint,
+}
+
+const PATTERNS: &[Pattern] = &[
+    Pattern {
+        plugin: Some("ffmpeg"),
+        needles: &["unknown encoder", "encoder not found", "unknown decoder"],
+        hint: FailureHint {
+            problem: "This ffmpeg build is missing a codec.",
+            fix: "Install a full ffmpeg build, or ask for a codec it has (see `ffmpeg -encoders`).",
+            install: Some(&["brew", "reinstall", "ffmpeg"]),
+        },
+    },
+    Pattern {
+        plugin: Some("ffmpeg"),
+        needles: &["invalid data found when processing input", "moov atom not found"],
+        hint: FailureHint {
+            problem: "The input is not a readable media file.",
+            fix: "Check that the download or copy finished; a truncated file cannot be converted.",
+            install: None,
+        },
+    },
+    Pattern {
+        plugin: Some("yt-dlp"),
+        needles: &["http error 403", "403: forbidden"],
+        hint: FailureHint {
+            problem: "The site refused the download (HTTP 403).",
+            fix: "Update yt-dlp, since sites change often. If it persists, pass your browser's cookies with `--cookies-from-browser firefox`.",
+            install: Some(&["brew", "upgrade", "yt-dlp"]),
+        },
+    },
+    Pattern {
+        plugin: Some("yt-dlp"),
+        needles: &["sign in to confirm", "login required", "private video"],
+        hint: FailureHint {
+            problem: "The site wants you signed in.",
+            fix: "Add `--cookies-from-browser <browser>` so yt-dlp uses your logged-in session.",
+            install: None,
+        },
+    },
+    Pattern {
+        plugin: Some("yt-dlp"),
+        needles: &["ffmpeg not found", "ffprobe and ffmpeg not found"],
+        hint: FailureHint {
+            problem: "yt-dlp needs ffmpeg to merge or convert this download.",
+            fix: "Install ffmpeg, then retry.",
+            install: Some(&["brew", "install", "ffmpeg"]),
+        },
+    },
+    Pattern {
+        plugin: Some("yt-dlp"),
+        needles: &["unsupported url"],
+        hint: FailureHint {
+            problem: "yt-dlp does not know this site.",
+            fix: "Check the link, or update yt-dlp in case support was added.",
+            install: Some(&["brew", "upgrade", "yt-dlp"]),
+        },
+    },
+    Pattern {
+        plugin: Some("pandoc"),
+        needles: &["pdflatex not found", "xelatex not found", "lualatex not found"],
+        hint: FailureHint {
+            problem: "PDF output needs a LaTeX engine, and none is installed.",
+            fix: "Install a TeX distribution (BasicTeX, TeX Live), or pick another engine such as `--pdf-engine=weasyprint`.",
+            install: Some(&["brew", "install", "--cask", "basictex"]),
+        },
+    },
+    Pattern {
+        plugin: Some("pandoc"),
+        needles: &["unknown output format", "unknown input format"],
+        hint: FailureHint {
+            problem: "pandoc does not know that format name.",
+            fix: "See `pandoc --list-output-formats` for the names this version accepts.",
+            install: None,
+        },
+    },
+    Pattern {
+        plugin: Some("f2"),
+        needles: &["conflict"],
+        hint: FailureHint {
+            problem: "Several files would get the same new name.",
+            fix: "Add a counter such as `{%03d}` to the replacement, or let f2 number them with `-F`.",
+            install: None,
+        },
+    },
+    Pattern {
+        plugin: None,
+        needles: &["permission denied", "operation not permitted"],
+        hint: FailureHint {
+            problem: "The tool was not allowed to read or write a file.",
+            fix: "Check the file permissions, or write the output to a folder you own.",
+            install: None,
+        },
+    },
+    Pattern {
+        plugin: None,
+        needles: &["no space left on device"],
+        hint: FailureHint {
+            problem: "The disk is full.",
+            fix: "Free some space or write the output to another drive.",
+            install: None,
+        },
+    },
+];
+
+/// The first known failure in `output` from `plugin`.
+pub fn triage(plugin: Option<&str>, output: &str) -> Option<&'static FailureHint> {
+    let output = output.to_lowercase();
+    PATTERNS
+        .iter()
+        .filter(|pattern| pattern.plugin.is_none() || pattern.plugin == plugin)
+        .find(|pattern| pattern.needles.iter().any(|needle| output.contains(needle)))
+        .map(|pattern| &pattern.hint)
+}
+
+impl FailureHint {
+    /// The install command when its program is on `PATH`; none while
+    /// replaying a session.
+    pub fn available_install(&self) -> Option<&'static [&'static str]> {
+        self.install.filter(|argv| {
+            !crate::replay::is_replaying()
+                && Command::new(argv[0])
+                    .arg("--version")
+                    .output()
+                    .is_ok_and(|out| out.status.success())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_known_failures_for_their_plugin() {
+        let codec = triage(Some("ffmpeg"), "Unknown encoder 'libx265'").unwrap();
+        assert_eq!(codec.problem, "This ffmpeg build is missing a codec.");
+        assert!(codec.install.is_some());
+
+        let latex = triage(
+            Some("pandoc"),
+            "pdflatex not found. Please select a different --pdf-engine or install pdflatex",
+        )
+        .unwrap();
+        assert!(latex.fix.contains("--pdf-engine"));
+
+        assert!(triage(Some("yt-dlp"), "ERROR: HTTP Error 403: Forbidden").is_some());
+        // Patterns are scoped to their plugin; generic ones apply to all.
+        assert_eq!(triage(Some("f2"), "HTTP Error 403"), None);
+        assert!(triage(Some("f2"), "open a.txt: permission denied").is_some());
+        assert_eq!(triage(None, "exit status 1"), None);
+    }
+}
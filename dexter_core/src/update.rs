@@ -0,0 +1,363 @@
+//! Looks for a newer Dexter on the GitHub releases feed and replaces the
+//! running binary with it. The TUI only checks when `updates.check` is on,
+//! at most once a day; `dexter update` checks whenever it is run.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+const REPOSITORY: &str = "https://github.com/starpastoral/Dexter";
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/starpastoral/Dexter/releases/latest";
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(15);
+/// Covers a whole release binary, which can take minutes on a slow link.
+const DOWNLOAD_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(600);
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+pub struct UpdatePolicy {
+    /// Asks GitHub for the latest release when the TUI starts, at most once
+    /// a day. Off unless set; never on in offline mode.
+    #[serde(default)]
+    pub check: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Release {
+    pub tag_name: String,
+    pub html_url: String,
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+impl Release {
+    /// `0.2.0` for the tag `v0.2.0`.
+    pub fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+
+    pub fn is_newer_than(&self, current: &str) -> bool {
+        version_parts(self.version()) > version_parts(current)
+    }
+
+    /// The binary built for this machine, `dexter-<arch>-<os>` with `.exe`
+    /// on Windows, e.g. `dexter-aarch64-macos`.
+    pub fn platform_asset(&self) -> Option<&ReleaseAsset> {
+        let name = asset_name(std::env::consts::ARCH, std::env::consts::OS);
+        self.assets.iter().find(|asset| asset.name == name)
+    }
+
+    /// The published checksum of `asset`, `<name>.sha256`.
+    fn checksum_asset(&self, asset: &ReleaseAsset) -> Option<&ReleaseAsset> {
+        let name = format!("{}.sha256", asset.name);
+        self.assets.iter().find(|candidate| candidate.name == name)
+    }
+
+    /// Builds and installs the release from source, for platforms without
+    /// a published binary.
+    pub fn install_command(&self) -> String {
+        format!(
+            "cargo install --git {} --tag {} --locked dexter_tui",
+            REPOSITORY, self.tag_name
+        )
+    }
+}
+
+fn asset_name(arch: &str, os: &str) -> String {
+    let suffix = if os == "windows" { ".exe" } else { "" };
+    format!("dexter-{}-{}{}", arch, os, suffix)
+}
+
+/// `[0, 2, 1]` for `0.2.1`; a pre-release or build suffix is ignored.
+fn version_parts(version: &str) -> Vec<u64> {
+    version
+        .split(['-', '+'])
+        .next()
+        .unwrap_or("")
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+/// Where Homebrew keeps what it installed.
+const BREW_PREFIXES: [&str; 3] = ["/opt/homebrew/", "/usr/local/Cellar/", "/home/linuxbrew/"];
+/// Where system package managers install.
+const SYSTEM_PREFIXES: [&str; 6] = [
+    "/usr/bin/",
+    "/usr/sbin/",
+    "/usr/lib/",
+    "/usr/libexec/",
+    "/nix/store/",
+    "/snap/",
+];
+
+/// How to update a binary another tool installed, from the path it runs
+/// from: replacing it would leave that tool's records wrong. `None` for a
+/// binary Dexter may replace itself.
+fn managed_update(exe: &Path, cargo_bin: Option<&Path>, release: &Release) -> Option<String> {
+    if cargo_bin.is_some_and(|bin| exe.starts_with(bin)) {
+        return Some(release.install_command());
+    }
+    let path = exe.to_string_lossy();
+    if BREW_PREFIXES.iter().any(|prefix| path.starts_with(prefix)) {
+        return Some("brew upgrade dexter".to_string());
+    }
+    SYSTEM_PREFIXES
+        .iter()
+        .any(|prefix| path.starts_with(prefix))
+        .then(|| "your system's package manager".to_string())
+}
+
+/// `$CARGO_HOME/bin`, `~/.cargo/bin` by default.
+fn cargo_bin() -> Option<PathBuf> {
+    std::env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| crate::paths::home_dir().map(|home| home.join(".cargo")))
+        .map(|home| home.join("bin"))
+}
+
+/// Whether `bytes` hash to the SHA-256 at the start of `checksum`, the
+/// `sha256sum` format (`<hex>  <name>`).
+fn matches_checksum(bytes: &[u8], checksum: &str) -> bool {
+    let expected = checksum.split_whitespace().next().unwrap_or_default();
+    let actual: String = Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    expected.eq_ignore_ascii_case(&actual)
+}
+
+async fn download(client: &reqwest::Client, asset: &ReleaseAsset) -> Result<Vec<u8>> {
+    let response = client
+        .get(&asset.browser_download_url)
+        .timeout(DOWNLOAD_TIMEOUT)
+        .send()
+        .await
+        .with_context(|| format!("Couldn't download {}", asset.name))?;
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "Downloading {} failed: {}",
+            asset.name,
+            response.status()
+        ));
+    }
+    Ok(response.bytes().await?.to_vec())
+}
+
+fn client(current: &str) -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder()
+        .user_agent(format!("dexter/{}", current))
+        .timeout(REQUEST_TIMEOUT)
+        .build()?)
+}
+
+pub async fn latest_release(current: &str) -> Result<Release> {
+    let response = client(current)?
+        .get(LATEST_RELEASE_URL)
+        .header("Accept", "application/vnd.github+json")
+        .send()
+        .await
+        .context("Couldn't reach GitHub")?;
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "GitHub answered {} for the latest release",
+            response.status()
+        ));
+    }
+    Ok(response.json().await?)
+}
+
+/// The last answer from GitHub, so the TUI asks at most once a day.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedCheck {
+    checked_at: DateTime<Utc>,
+    release: Release,
+}
+
+fn cache_path() -> Option<PathBuf> {
+    crate::paths::cache_dir().map(|dir| dir.join("dexter").join("update.json"))
+}
+
+/// The TUI's startup check: the latest release when it is newer than
+/// `current`, `None` when it isn't or `policy` turns checks off.
+pub async fn check(policy: &UpdatePolicy, offline: bool, current: &str) -> Result<Option<Release>> {
+    if !policy.check || offline {
+        return Ok(None);
+    }
+    let release = match cache_path() {
+        Some(path) => cached_release(&path, current, Utc::now()).await?,
+        None => latest_release(current).await?,
+    };
+    Ok(release.is_newer_than(current).then_some(release))
+}
+
+async fn cached_release(path: &Path, current: &str, now: DateTime<Utc>) -> Result<Release> {
+    let cached = tokio::fs::read_to_string(path)
+        .await
+        .ok()
+        .and_then(|text| serde_json::from_str::<CachedCheck>(&text).ok());
+    if let Some(cached) = cached {
+        if now - cached.checked_at < Duration::days(1) {
+            return Ok(cached.release);
+        }
+    }
+    let release = latest_release(current).await?;
+    let cached = CachedCheck {
+        checked_at: now,
+        release,
+    };
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(path, serde_json::to_string_pretty(&cached)?).await?;
+    Ok(cached.release)
+}
+
+/// Downloads `release`'s binary for this platform, checks it against the
+/// published SHA-256 and puts it in place of the running one, which keeps
+/// running until it exits. Returns the path that was replaced. A binary
+/// installed by brew, cargo or a system package manager is left alone and
+/// the error names the command that updates it; without a binary for this
+/// platform the error carries the install command instead.
+pub async fn self_update(release: &Release, current: &str) -> Result<PathBuf> {
+    let exe = std::env::current_exe()?;
+    let real = std::fs::canonicalize(&exe).unwrap_or_else(|_| exe.clone());
+    if let Some(command) = managed_update(&real, cargo_bin().as_deref(), release) {
+        return Err(anyhow!(
+            "{} was installed by a package manager; update it with:\n{}",
+            real.display(),
+            command
+        ));
+    }
+    let asset = release.platform_asset().ok_or_else(|| {
+        anyhow!(
+            "No {} binary for this platform in {}. Install it with:\n{}",
+            release.tag_name,
+            release.html_url,
+            release.install_command()
+        )
+    })?;
+    let checksum = release.checksum_asset(asset).ok_or_else(|| {
+        anyhow!(
+            "{} has no {}.sha256 to check the download against; not updating",
+            release.tag_name,
+            asset.name
+        )
+    })?;
+    let client = client(current)?;
+    let bytes = download(&client, asset).await?;
+    let expected = download(&client, checksum).await?;
+    if !matches_checksum(&bytes, &String::from_utf8_lossy(&expected)) {
+        return Err(anyhow!(
+            "{} doesn't match its published SHA-256; not updating",
+            asset.name
+        ));
+    }
+    // `exe` may be a symlink into an install dir; renaming over it would
+    // leave the real binary behind and the link replaced by a copy.
+    replace_binary(&real, &bytes)
+        .with_context(|| format!("Couldn't replace {}", real.display()))?;
+    Ok(real)
+}
+
+/// Writes `bytes` next to `exe` and renames it over it. Windows can't
+/// replace a running binary but can rename it, so the old one is moved
+/// aside first.
+fn replace_binary(exe: &Path, bytes: &[u8]) -> Result<()> {
+    let staged = exe.with_extension("new");
+    std::fs::write(&staged, bytes)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o755))?;
+    }
+    if cfg!(windows) {
+        let old = exe.with_extension("old");
+        let _ = std::fs::remove_file(&old);
+        std::fs::rename(exe, &old)?;
+    }
+    if let Err(e) = std::fs::rename(&staged, exe) {
+        let _ = std::fs::remove_file(&staged);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn release(tag: &str) -> Release {
+        Release {
+            tag_name: tag.to_string(),
+            html_url: format!("{}/releases/tag/{}", REPOSITORY, tag),
+            assets: vec![ReleaseAsset {
+                name: asset_name("x86_64", "linux"),
+                browser_download_url: "https://example.com/dexter".to_string(),
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn compares_versions_and_reuses_the_daily_check() {
+        assert!(release("v0.2.0").is_newer_than("0.1.9"));
+        assert!(release("v0.10.0").is_newer_than("0.9.3"));
+        assert!(!release("v0.2.0").is_newer_than("0.2.0"));
+        assert!(!release("0.2.0-rc.1").is_newer_than("0.2.0"));
+        assert_eq!(release("v1.0.0").assets[0].name, "dexter-x86_64-linux");
+        assert_eq!(asset_name("x86_64", "windows"), "dexter-x86_64-windows.exe");
+        assert!(release("v1.0.0")
+            .install_command()
+            .ends_with("--tag v1.0.0 --locked dexter_tui"));
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("update.json");
+        let now = Utc::now();
+        let cached = CachedCheck {
+            checked_at: now - Duration::hours(2),
+            release: release("v0.3.0"),
+        };
+        std::fs::write(&path, serde_json::to_string(&cached).unwrap()).unwrap();
+        let found = cached_release(&path, "0.1.0", now).await.unwrap();
+        assert_eq!(found.version(), "0.3.0");
+
+        let exe = dir.path().join("dexter");
+        std::fs::write(&exe, b"old").unwrap();
+        replace_binary(&exe, b"new").unwrap();
+        assert_eq!(std::fs::read(&exe).unwrap(), b"new");
+        assert!(!exe.with_extension("new").exists());
+    }
+
+    #[test]
+    fn checks_downloads_and_leaves_managed_installs_alone() {
+        let sum = "11507a0e2f5e69d5dfa40a62a1bd7b6ee57e6bcd85c67c9b8431b36fff21c437";
+        assert!(matches_checksum(
+            b"new",
+            &format!("{}  dexter-x86_64-linux\n", sum)
+        ));
+        assert!(!matches_checksum(b"old", sum));
+        assert!(!matches_checksum(b"new", ""));
+
+        let release = release("v1.0.0");
+        let cargo = Path::new("/home/me/.cargo/bin");
+        let managed = |exe: &str| managed_update(Path::new(exe), Some(cargo), &release);
+        assert_eq!(
+            managed("/home/me/.cargo/bin/dexter"),
+            Some(release.install_command())
+        );
+        assert_eq!(
+            managed("/opt/homebrew/Cellar/dexter/0.1.0/bin/dexter").as_deref(),
+            Some("brew upgrade dexter")
+        );
+        assert!(managed("/usr/bin/dexter").is_some());
+        assert_eq!(managed("/home/me/bin/dexter"), None);
+        assert_eq!(managed("/usr/local/bin/dexter"), None);
+    }
+}
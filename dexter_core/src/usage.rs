@@ -0,0 +1,155 @@
+use anyhow::{Context, Result};
+use chrono::{Datelike, Local, NaiveDate};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::budget::TokenBudget;
+
+/// Estimated spend on model calls per local day, kept in `usage.json` in
+/// the data dir. Only prompts are priced, so it errs low on long replies;
+/// replies served from the response cache cost nothing.
+#[derive(Debug, Clone, Default)]
+pub struct UsageTracker {
+    path: Option<PathBuf>,
+    /// USD by `YYYY-MM-DD`.
+    daily_usd: BTreeMap<String, f64>,
+}
+
+/// A spending limit from `token_budget` that the tracked spend went over.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetAlert {
+    /// `"daily"` or `"monthly"`.
+    pub period: &'static str,
+    pub spent_usd: f64,
+    pub limit_usd: f64,
+}
+
+impl BudgetAlert {
+    /// `~$1.23 of the $1.00 daily budget`.
+    pub fn label(&self) -> String {
+        format!(
+            "~${:.2} of the ${:.2} {} budget",
+            self.spent_usd, self.limit_usd, self.period
+        )
+    }
+}
+
+impl UsageTracker {
+    /// The tracker behind `usage.json`; a missing or unreadable file
+    /// starts from nothing spent.
+    pub fn load() -> Self {
+        Self::load_from(crate::paths::data_dir().map(|dir| dir.join("dexter").join("usage.json")))
+    }
+
+    fn load_from(path: Option<PathBuf>) -> Self {
+        let daily_usd = read_daily(path.as_deref());
+        Self { path, daily_usd }
+    }
+
+    /// Picks up spend other clients and instances recorded since loading.
+    pub fn reload(&mut self) {
+        self.daily_usd = read_daily(self.path.as_deref());
+    }
+
+    /// Adds `usd` to today's spend and saves it.
+    pub async fn record(&mut self, usd: f64) -> Result<()> {
+        self.record_on(Local::now().date_naive(), usd).await
+    }
+
+    /// Re-reads the file under its lock before adding, so concurrent
+    /// instances never drop each other's spend.
+    async fn record_on(&mut self, day: NaiveDate, usd: f64) -> Result<()> {
+        if usd <= 0.0 {
+            return Ok(());
+        }
+        let Some(path) = self.path.clone() else {
+            *self.daily_usd.entry(day.to_string()).or_default() += usd;
+            return Ok(());
+        };
+        let _lock = crate::executor::lock_store(&path).await?;
+        self.reload();
+        *self.daily_usd.entry(day.to_string()).or_default() += usd;
+        let tmp_path = path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(&self.daily_usd)?)
+            .with_context(|| format!("Could not write {}", tmp_path.display()))?;
+        std::fs::rename(&tmp_path, &path)?;
+        Ok(())
+    }
+
+    pub fn spent_on(&self, day: NaiveDate) -> f64 {
+        self.daily_usd.get(&day.to_string()).copied().unwrap_or(0.0)
+    }
+
+    pub fn spent_in_month_of(&self, day: NaiveDate) -> f64 {
+        let month = format!("{:04}-{:02}-", day.year(), day.month());
+        self.daily_usd
+            .iter()
+            .filter(|(date, _)| date.starts_with(&month))
+            .map(|(_, usd)| usd)
+            .sum()
+    }
+
+    /// The first spending limit in `budget` that today's or this month's
+    /// spend is over.
+    pub fn alert(&self, budget: &TokenBudget) -> Option<BudgetAlert> {
+        self.alert_on(Local::now().date_naive(), budget)
+    }
+
+    fn alert_on(&self, day: NaiveDate, budget: &TokenBudget) -> Option<BudgetAlert> {
+        [
+            ("daily", self.spent_on(day), budget.daily_usd),
+            ("monthly", self.spent_in_month_of(day), budget.monthly_usd),
+        ]
+        .into_iter()
+        .find(|(_, spent, limit)| *limit > 0.0 && spent > limit)
+        .map(|(period, spent_usd, limit_usd)| BudgetAlert {
+            period,
+            spent_usd,
+            limit_usd,
+        })
+    }
+}
+
+fn read_daily(path: Option<&Path>) -> BTreeMap<String, f64> {
+    path.and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn alerts_once_daily_or_monthly_spend_passes_its_limit() {
+        let tmp = tempdir().unwrap();
+        let path = tmp.path().join("usage.json");
+        let mut usage = UsageTracker::load_from(Some(path.clone()));
+        let first = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        let second = NaiveDate::from_ymd_opt(2026, 3, 2).unwrap();
+        usage.record_on(first, 0.60).await.unwrap();
+        usage.record_on(second, 0.30).await.unwrap();
+        usage.record_on(second, 0.0).await.unwrap();
+
+        let budget = TokenBudget {
+            daily_usd: 0.50,
+            monthly_usd: 0.80,
+            ..TokenBudget::default()
+        };
+        assert_eq!(usage.alert_on(second, &budget).unwrap().period, "monthly");
+        assert_eq!(usage.alert_on(first, &budget).unwrap().period, "daily");
+        assert_eq!(usage.alert_on(second, &TokenBudget::default()), None);
+
+        // A second instance adds to the file instead of overwriting it.
+        let mut other = UsageTracker::load_from(Some(path.clone()));
+        other.record_on(second, 0.10).await.unwrap();
+        usage.record_on(second, 0.10).await.unwrap();
+        let reloaded = UsageTracker::load_from(Some(path));
+        assert!((reloaded.spent_in_month_of(second) - 1.10).abs() < 1e-9);
+        assert_eq!(
+            usage.alert_on(first, &budget).unwrap().label(),
+            "~$0.60 of the $0.50 daily budget"
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! Checks the files a conversion wrote once it reports success: that each
+//! output exists and isn't empty, that it still looks whole for its format,
+//! and its SHA-256 for the record.
+
+use anyhow::Result;
+use dexter_plugins::ffmpeg::probe_format;
+use dexter_plugins::health::find_in_path;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::conflict::output_paths;
+use crate::recycle::Trashed;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct VerifyPolicy {
+    /// Checks and checksums the outputs of every successful local run.
+    #[serde(default)]
+    pub outputs: bool,
+}
+
+/// One output of a finished command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputCheck {
+    /// Output path as written in the command.
+    pub path: String,
+    pub size: u64,
+    /// Hex SHA-256; `None` for missing files and directories.
+    pub sha256: Option<String>,
+    /// Why the file looks wrong, if it does.
+    pub problem: Option<String>,
+}
+
+/// What `verify_outputs` found for one run, and the files it moved to
+/// the trash.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecutionReport {
+    pub outputs: Vec<OutputCheck>,
+    pub trashed: Option<Trashed>,
+}
+
+impl ExecutionReport {
+    pub fn suspicious(&self) -> impl Iterator<Item = &OutputCheck> {
+        self.outputs.iter().filter(|check| check.problem.is_some())
+    }
+
+    /// One line per output, and where trashed files went, for the
+    /// session log.
+    pub fn summary(&self) -> String {
+        self.outputs
+            .iter()
+            .map(|check| match &check.problem {
+                Some(problem) => format!("{} SUSPICIOUS: {}", check.path, problem),
+                None => format!(
+                    "{} size={} sha256={}",
+                    check.path,
+                    check.size,
+                    check.sha256.as_deref().unwrap_or("-")
+                ),
+            })
+            .chain(self.trashed.iter().map(|trashed| {
+                format!(
+                    "trashed {} file(s) in {}",
+                    trashed.files.len(),
+                    trashed.location
+                )
+            }))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Checks the outputs of `command` (as run by `plugin`) in `cwd`. Plugins
+/// whose outputs can't be parsed from the command get an empty report.
+pub async fn verify_outputs(plugin: &str, command: &str, cwd: &Path) -> ExecutionReport {
+    let mut outputs = Vec::new();
+    for path in output_paths(plugin, command).unwrap_or_default() {
+        let full = cwd.join(&path);
+        let (blocking_path, blocking_full) = (path.clone(), full.clone());
+        let mut check =
+            tokio::task::spawn_blocking(move || check_file(blocking_path, &blocking_full))
+                .await
+                .unwrap_or_else(|e| OutputCheck {
+                    path,
+                    size: 0,
+                    sha256: None,
+                    problem: Some(format!("check failed: {}", e)),
+                });
+        // Only worth probing when the file passed the cheaper checks.
+        let probe = plugin == "ffmpeg" && check.problem.is_none() && check.sha256.is_some();
+        if probe && find_in_path("ffprobe").is_some() {
+            if let Err(e) = probe_format(&full.to_string_lossy()).await {
+                check.problem = Some(format!("ffprobe can't read it ({})", e));
+            }
+        }
+        outputs.push(check);
+    }
+    ExecutionReport {
+        outputs,
+        trashed: None,
+    }
+}
+
+fn check_file(path: String, full: &Path) -> OutputCheck {
+    let mut check = OutputCheck {
+        path,
+        size: 0,
+        sha256: None,
+        problem: None,
+    };
+    let metadata = match std::fs::metadata(full) {
+        Ok(metadata) => metadata,
+        Err(_) => {
+            check.problem = Some("missing".to_string());
+            return check;
+        }
+    };
+    if metadata.is_dir() {
+        return check;
+    }
+    check.size = metadata.len();
+    if check.size == 0 {
+        check.problem = Some("empty (0 bytes)".to_string());
+        return check;
+    }
+    match sha256_file(full) {
+        Ok(digest) => check.sha256 = Some(digest),
+        Err(e) => {
+            check.problem = Some(format!("unreadable ({})", e));
+            return check;
+        }
+    }
+    check.problem = truncation(full).unwrap_or_else(|e| Some(format!("unreadable ({})", e)));
+    check
+}
+
+fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+/// A format whose end marker shows whether the writer finished.
+struct Trailer {
+    extensions: &'static [&'static str],
+    magic: &'static [u8],
+    end: &'static [u8],
+    problem: &'static str,
+}
+
+const TRAILERS: &[Trailer] = &[
+    Trailer {
+        extensions: &["pdf"],
+        magic: b"%PDF-",
+        end: b"%%EOF",
+        problem: "truncated PDF (no %%EOF)",
+    },
+    Trailer {
+        extensions: &["docx", "pptx", "xlsx", "odt", "ods", "odp", "epub", "zip"],
+        magic: b"PK\x03\x04",
+        end: b"PK\x05\x06",
+        problem: "truncated archive (no central directory)",
+    },
+    Trailer {
+        extensions: &["png"],
+        magic: b"\x89PNG",
+        end: b"IEND",
+        problem: "truncated PNG (no IEND)",
+    },
+    Trailer {
+        extensions: &["jpg", "jpeg"],
+        magic: b"\xff\xd8",
+        end: b"\xff\xd9",
+        problem: "truncated JPEG",
+    },
+];
+
+/// Archives may end with a comment of up to 64 KiB after the marker.
+const TAIL_LEN: u64 = 64 * 1024 + 22;
+
+/// Why `path` looks cut short for its extension, if it does.
+fn truncation(path: &Path) -> Result<Option<String>> {
+    let ext = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let Some(trailer) = TRAILERS
+        .iter()
+        .find(|trailer| trailer.extensions.contains(&ext.as_str()))
+    else {
+        return Ok(None);
+    };
+    let mut file = File::open(path)?;
+    let mut head = vec![0u8; trailer.magic.len()];
+    if file.read_exact(&mut head).is_err() || head != trailer.magic {
+        return Ok(Some(format!("not a .{} file", ext)));
+    }
+    let len = file.metadata()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(TAIL_LEN)))?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail)?;
+    let found = tail.windows(trailer.end.len()).any(|w| w == trailer.end);
+    Ok((!found).then(|| trailer.problem.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn flags_empty_missing_and_truncated_outputs() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("ok.pdf"), b"%PDF-1.7\n...\n%%EOF\n").unwrap();
+        std::fs::write(dir.path().join("cut.pdf"), b"%PDF-1.7\n...").unwrap();
+        std::fs::write(dir.path().join("empty.pdf"), b"").unwrap();
+
+        let check = |cmd: &str| {
+            let cmd = cmd.to_string();
+            let cwd = dir.path().to_path_buf();
+            async move { verify_outputs("qpdf", &cmd, &cwd).await.outputs }
+        };
+        let ok = check("qpdf in.pdf ok.pdf").await;
+        assert_eq!(ok[0].problem, None);
+        assert_eq!(ok[0].size, 19);
+        assert_eq!(ok[0].sha256.as_ref().map(String::len), Some(64));
+
+        let cut = check("qpdf in.pdf cut.pdf").await;
+        assert_eq!(cut[0].problem.as_deref(), Some("truncated PDF (no %%EOF)"));
+        let empty = check("qpdf in.pdf empty.pdf").await;
+        assert_eq!(empty[0].problem.as_deref(), Some("empty (0 bytes)"));
+        let missing = check("qpdf in.pdf gone.pdf").await;
+        assert_eq!(missing[0].problem.as_deref(), Some("missing"));
+        assert!(check("qpdf --replace-input in.pdf").await.is_empty());
+    }
+}
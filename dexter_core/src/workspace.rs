@@ -0,0 +1,164 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::context::FileContext;
+use crate::safety::{SafetyGuard, SafetyPolicy};
+
+pub const WORKSPACE_FILE: &str = ".dexter.toml";
+
+/// Project settings from a `.dexter.toml`, applied on top of the global
+/// config while Dexter works inside that directory tree.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkspaceSettings {
+    /// Regexes a command must not match, in addition to the built-in rules.
+    pub blocked_patterns: Vec<String>,
+    /// Where new output files go, relative to the project root.
+    pub output_dir: Option<String>,
+    /// Files, relative to the project root, always listed in the context.
+    pub context_files: Vec<String>,
+    /// Plugins the router favours when several fit the request.
+    pub preferred_plugins: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Workspace {
+    /// Directory holding the `.dexter.toml`; `None` outside any project.
+    pub root: Option<PathBuf>,
+    pub settings: WorkspaceSettings,
+    guard: SafetyGuard,
+}
+
+impl Workspace {
+    /// The nearest `.dexter.toml` in `dir` or one of its parents. No file
+    /// means the empty workspace; a file that doesn't parse is an error.
+    pub fn discover(dir: &Path) -> Result<Self> {
+        match dir
+            .ancestors()
+            .map(|d| d.join(WORKSPACE_FILE))
+            .find(|path| path.is_file())
+        {
+            Some(path) => Self::load(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let settings: WorkspaceSettings =
+            toml::from_str(&text).with_context(|| format!("Invalid {}", path.display()))?;
+        let guard = SafetyGuard::default()
+            .with_blocked_patterns(&settings.blocked_patterns)
+            .with_context(|| format!("Invalid blocked_patterns in {}", path.display()))?;
+        Ok(Self {
+            root: path.parent().map(Path::to_path_buf),
+            settings,
+            guard,
+        })
+    }
+
+    /// The built-in rules plus the project's `blocked_patterns`.
+    pub fn safety_guard(&self) -> &SafetyGuard {
+        &self.guard
+    }
+
+    /// The guard for commands run in `dir`: the project's rules plus the
+    /// config's `[safety]` policy. A `.dexter.toml` that doesn't parse is an
+    /// error rather than a project without rules.
+    pub fn guard_for(dir: &Path, policy: &SafetyPolicy) -> Result<SafetyGuard> {
+        Self::discover(dir)?
+            .safety_guard()
+            .clone()
+            .with_policy(policy, dir)
+    }
+
+    /// The project's output directory as seen from `cwd`: relative when it
+    /// lies below `cwd`, absolute otherwise.
+    pub fn output_dir(&self, cwd: &Path) -> Option<String> {
+        let dir = self
+            .root
+            .as_ref()?
+            .join(self.settings.output_dir.as_deref()?);
+        Some(relative_to(&dir, cwd))
+    }
+
+    /// Adds the project's existing `context_files` ahead of the scanned
+    /// ones and names them in the notes, so a summarized listing keeps them.
+    pub fn pin_context(&self, cwd: &Path, context: &mut FileContext) {
+        let Some(root) = &self.root else {
+            return;
+        };
+        let pinned: Vec<String> = self
+            .settings
+            .context_files
+            .iter()
+            .map(|file| root.join(file))
+            .filter(|path| path.is_file())
+            .map(|path| relative_to(&path, cwd))
+            .filter(|name| !context.files.contains(name))
+            .collect();
+        if pinned.is_empty() {
+            return;
+        }
+        let note = format!("Pinned project files: {}", pinned.join(", "));
+        context.summary = Some(match context.summary.take() {
+            Some(summary) => format!("{}\n{}", summary, note),
+            None => note,
+        });
+        context.files.splice(0..0, pinned);
+    }
+}
+
+fn relative_to(path: &Path, cwd: &Path) -> String {
+    path.strip_prefix(cwd).unwrap_or(path).display().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::ContextScanner;
+    use tempfile::tempdir;
+
+    #[test]
+    fn nearest_project_file_applies_to_subdirectories() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("clips/raw")).unwrap();
+        std::fs::write(root.join("clips/brief.md"), "notes").unwrap();
+        std::fs::write(
+            root.join(WORKSPACE_FILE),
+            r#"
+blocked_patterns = ["(?i)-y\\b"]
+output_dir = "clips/exports"
+context_files = ["clips/brief.md", "missing.txt"]
+preferred_plugins = ["ffmpeg"]
+"#,
+        )
+        .unwrap();
+
+        let cwd = root.join("clips");
+        let workspace = Workspace::discover(&cwd.join("raw")).unwrap();
+        assert_eq!(workspace.root.as_deref(), Some(root));
+        assert_eq!(workspace.settings.preferred_plugins, ["ffmpeg"]);
+        assert_eq!(workspace.output_dir(&cwd).as_deref(), Some("exports"));
+        assert!(workspace
+            .safety_guard()
+            .check("ffmpeg -y -i a.mkv b.mp4")
+            .is_err());
+        assert!(workspace
+            .safety_guard()
+            .check("ffmpeg -i a.mkv b.mp4")
+            .is_ok());
+
+        let mut context = ContextScanner::from_listing(vec!["a.mkv".to_string()], 0);
+        workspace.pin_context(&cwd, &mut context);
+        assert_eq!(context.files, ["brief.md", "a.mkv"]);
+
+        std::fs::write(root.join(WORKSPACE_FILE), "blocked_patterns = [\"(\"]").unwrap();
+        assert!(Workspace::discover(&cwd).is_err());
+        let outside = tempdir().unwrap();
+        assert!(Workspace::discover(outside.path()).unwrap().root.is_none());
+    }
+}
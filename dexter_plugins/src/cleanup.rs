@@ -0,0 +1,664 @@
+//! Disk usage and cleanup done by Dexter itself instead of an external
+//! tool: the biggest files, the space each entry of a folder takes, and
+//! empty folders or files. `--delete` removes the empty ones the preview
+//! listed with `remove_dir` and `remove_file`, which refuse anything that
+//! has filled up since; nothing is ever handed to `rm`.
+
+use crate::command_exec::{parse_and_validate_command, working_dir};
+use crate::{Capability, DiffItem, LlmBridge, Plugin, PluginSettings, PreviewContent, Progress};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tokio::sync::mpsc;
+
+/// Rows `largest` and `usage` list without `--top`.
+const DEFAULT_TOP: usize = 20;
+
+/// Folders never walked or removed, hidden or not: version control needs
+/// its empty folders, and staged files stay where Dexter put them.
+const SKIPPED_DIRS: [&str; 4] = [".git", ".hg", ".svn", ".dexter-trash"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CleanupAction {
+    /// The biggest files, largest first.
+    Largest,
+    /// The space each entry of the folder takes, largest first.
+    Usage,
+    EmptyDirs,
+    EmptyFiles,
+}
+
+impl CleanupAction {
+    const ALL: [CleanupAction; 4] = [
+        Self::Largest,
+        Self::Usage,
+        Self::EmptyDirs,
+        Self::EmptyFiles,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Largest => "largest",
+            Self::Usage => "usage",
+            Self::EmptyDirs => "empty-dirs",
+            Self::EmptyFiles => "empty-files",
+        }
+    }
+
+    fn removes(self) -> bool {
+        matches!(self, Self::EmptyDirs | Self::EmptyFiles)
+    }
+}
+
+/// A parsed `cleanup <action> [PATH] [options]` command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CleanupCommand {
+    pub action: CleanupAction,
+    /// The folder to look in, as written; `.` when none is given.
+    pub path: PathBuf,
+    pub top: usize,
+    /// Also look at dot files and folders.
+    pub hidden: bool,
+    /// Remove what `empty-dirs` or `empty-files` finds.
+    pub delete: bool,
+}
+
+pub fn parse_cleanup_command(cmd: &str) -> Result<CleanupCommand> {
+    let argv = parse_and_validate_command(cmd, "cleanup")?;
+    let action = argv
+        .get(1)
+        .and_then(|name| CleanupAction::ALL.into_iter().find(|a| a.name() == name))
+        .ok_or_else(|| anyhow!("Use cleanup largest, usage, empty-dirs or empty-files"))?;
+    let mut command = CleanupCommand {
+        action,
+        path: PathBuf::from("."),
+        top: DEFAULT_TOP,
+        hidden: false,
+        delete: false,
+    };
+    let mut path = None;
+    let mut args = argv[2..].iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--hidden" => command.hidden = true,
+            "--delete" if action.removes() => command.delete = true,
+            "--delete" => {
+                return Err(anyhow!("--delete only goes with empty-dirs or empty-files"));
+            }
+            "--top" => command.top = parse_top(args.next().map(String::as_str))?,
+            flag if flag.starts_with("--top=") => command.top = parse_top(flag.get(6..))?,
+            flag if flag.starts_with('-') => return Err(anyhow!("Unknown option {}", flag)),
+            _ if path.is_some() => return Err(anyhow!("cleanup takes one folder")),
+            folder => path = Some(inside_path(folder)?),
+        }
+    }
+    if let Some(path) = path {
+        command.path = path;
+    }
+    Ok(command)
+}
+
+/// `folder` when it names a place under the current folder: relative, with
+/// no `..` and no `~`. `root_of` checks the same after resolving symlinks.
+fn inside_path(folder: &str) -> Result<PathBuf> {
+    let path = PathBuf::from(folder);
+    let escapes = folder.starts_with('~')
+        || path
+            .components()
+            .any(|part| !matches!(part, Component::Normal(_) | Component::CurDir));
+    if escapes {
+        return Err(anyhow!(
+            "cleanup only looks inside the current folder, not {}",
+            folder
+        ));
+    }
+    Ok(path)
+}
+
+fn parse_top(value: Option<&str>) -> Result<usize> {
+    value
+        .and_then(|value| value.parse().ok())
+        .filter(|top| *top > 0)
+        .ok_or_else(|| anyhow!("--top needs a number above 0"))
+}
+
+/// Whether `cmd` removes anything.
+pub fn removes_files(cmd: &str) -> bool {
+    parse_cleanup_command(cmd).is_ok_and(|command| command.delete)
+}
+
+/// What one walk of a folder found.
+#[derive(Debug, Default)]
+struct Scan {
+    /// Every regular file with its size.
+    files: Vec<(PathBuf, u64)>,
+    /// Folders holding nothing but empty folders, innermost first.
+    empty_dirs: Vec<PathBuf>,
+    /// Folders that could not be read.
+    unreadable: usize,
+}
+
+fn is_hidden(name: &std::ffi::OsStr) -> bool {
+    name.to_string_lossy().starts_with('.')
+}
+
+/// Walks `dir` without following symlinks; returns whether it is empty.
+/// A folder is empty when everything in it is an empty folder; a hidden
+/// or skipped entry still counts as content.
+fn walk(dir: &Path, hidden: bool, scan: &mut Scan) -> bool {
+    let Ok(entries) = fs::read_dir(dir) else {
+        scan.unreadable += 1;
+        return false;
+    };
+    let mut empty = true;
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let Ok(meta) = entry.metadata() else {
+            empty = false;
+            continue;
+        };
+        let skipped = (!hidden && is_hidden(&name))
+            || SKIPPED_DIRS
+                .iter()
+                .any(|skip| name == std::ffi::OsStr::new(skip));
+        let path = entry.path();
+        if meta.is_dir() && !skipped && walk(&path, hidden, scan) {
+            scan.empty_dirs.push(path);
+            continue;
+        }
+        empty = false;
+        if meta.is_file() && !skipped {
+            scan.files.push((path, meta.len()));
+        }
+    }
+    empty
+}
+
+/// `cmd`'s folder under `cwd`, which must exist and, with symlinks
+/// resolved, still be inside `cwd`.
+fn root_of(command: &CleanupCommand, cwd: &Path) -> Result<PathBuf> {
+    let root = cwd.join(&command.path);
+    if !root.is_dir() {
+        return Err(anyhow!("{} is not a folder", command.path.display()));
+    }
+    if !root.canonicalize()?.starts_with(cwd.canonicalize()?) {
+        return Err(anyhow!(
+            "{} leads outside the current folder",
+            command.path.display()
+        ));
+    }
+    Ok(root)
+}
+
+/// `path` as the user knows it: relative to `cwd` when under it.
+fn shown(path: &Path, cwd: &Path) -> String {
+    path.strip_prefix(cwd).unwrap_or(path).display().to_string()
+}
+
+/// `1.5 GB`, `340 KB`, `12 B`.
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// The entries `command` removes with `--delete`, innermost first so
+/// folders empty out before their parents go. Empty without `--delete`.
+pub fn planned_removals(command: &CleanupCommand, cwd: &Path) -> Result<Vec<PathBuf>> {
+    if !command.delete {
+        return Ok(Vec::new());
+    }
+    let root = root_of(command, cwd)?;
+    let mut scan = Scan::default();
+    walk(&root, command.hidden, &mut scan);
+    Ok(match command.action {
+        CleanupAction::EmptyDirs => scan.empty_dirs,
+        CleanupAction::EmptyFiles => empty_files(&scan),
+        CleanupAction::Largest | CleanupAction::Usage => Vec::new(),
+    })
+}
+
+/// What the last `--delete` preview of each command listed, by folder and
+/// command.
+type Previews = BTreeMap<(PathBuf, String), Vec<PathBuf>>;
+
+fn previewed() -> &'static Mutex<Previews> {
+    static PREVIEWED: OnceLock<Mutex<Previews>> = OnceLock::new();
+    PREVIEWED.get_or_init(Mutex::default)
+}
+
+fn remember_preview(cmd: &str, cwd: &Path, paths: &[PathBuf]) {
+    if let Ok(mut previewed) = previewed().lock() {
+        previewed.insert((cwd.to_path_buf(), cmd.to_string()), paths.to_vec());
+    }
+}
+
+/// The entries a run of `cmd` removes: `planned_removals` narrowed to what
+/// its preview listed, so nothing that turned up empty since goes. Without
+/// a preview in this process it is the plan as is; every entry is checked
+/// again when it is removed either way.
+pub fn removals_to_run(cmd: &str, cwd: &Path) -> Result<Vec<PathBuf>> {
+    let command = parse_cleanup_command(cmd)?;
+    let mut planned = planned_removals(&command, cwd)?;
+    let shown = previewed()
+        .lock()
+        .ok()
+        .and_then(|mut previewed| previewed.remove(&(cwd.to_path_buf(), cmd.to_string())));
+    if let Some(shown) = shown {
+        planned.retain(|path| shown.contains(path));
+    }
+    Ok(planned)
+}
+
+/// Whether `path` is still what `command` removes: a folder holding only
+/// empty folders, or a zero-byte file.
+pub fn still_empty(path: &Path, command: &CleanupCommand) -> bool {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return false;
+    };
+    match command.action {
+        CleanupAction::EmptyDirs => {
+            meta.is_dir() && walk(path, command.hidden, &mut Scan::default())
+        }
+        _ => meta.is_file() && meta.len() == 0,
+    }
+}
+
+fn empty_files(scan: &Scan) -> Vec<PathBuf> {
+    scan.files
+        .iter()
+        .filter(|(_, size)| *size == 0)
+        .map(|(path, _)| path.clone())
+        .collect()
+}
+
+/// `paths` without the ones inside another of them: moving a folder moves
+/// its contents along.
+pub fn outermost(paths: &[PathBuf]) -> Vec<PathBuf> {
+    paths
+        .iter()
+        .filter(|path| {
+            !paths
+                .iter()
+                .any(|other| other != *path && path.starts_with(other))
+        })
+        .cloned()
+        .collect()
+}
+
+/// The biggest files under the root, or each entry's total for `usage`.
+fn size_rows(command: &CleanupCommand, root: &Path, scan: &Scan) -> Vec<(String, u64)> {
+    let mut rows: Vec<(String, u64)> = match command.action {
+        CleanupAction::Usage => {
+            let mut totals: BTreeMap<String, u64> = BTreeMap::new();
+            for (path, size) in &scan.files {
+                let entry = match path.strip_prefix(root).ok().and_then(first_component) {
+                    Some(entry) => entry,
+                    None => continue,
+                };
+                *totals.entry(entry).or_default() += size;
+            }
+            totals.into_iter().collect()
+        }
+        _ => scan
+            .files
+            .iter()
+            .map(|(path, size)| {
+                let relative = path.strip_prefix(root).unwrap_or(path);
+                (relative.display().to_string(), *size)
+            })
+            .collect(),
+    };
+    rows.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    rows
+}
+
+fn first_component(path: &Path) -> Option<String> {
+    match path.components().next()? {
+        Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+        _ => None,
+    }
+}
+
+/// What `command` finds, as the preview shows it. A `--delete` preview is
+/// remembered under `cmd`, for the run to stay within.
+fn preview(cmd: &str, command: &CleanupCommand, cwd: &Path) -> Result<PreviewContent> {
+    let root = root_of(command, cwd)?;
+    let mut scan = Scan::default();
+    walk(&root, command.hidden, &mut scan);
+    let folder = command.path.display();
+    let unreadable = if scan.unreadable > 0 {
+        format!(" ({} folder(s) could not be read)", scan.unreadable)
+    } else {
+        String::new()
+    };
+
+    if !command.action.removes() {
+        let rows = size_rows(command, &root, &scan);
+        let total: u64 = scan.files.iter().map(|(_, size)| size).sum();
+        let summary = format!(
+            "{} file(s), {} in {}{}",
+            scan.files.len(),
+            human_size(total),
+            folder,
+            unreadable
+        );
+        let (title, first) = match command.action {
+            CleanupAction::Usage => ("Space per entry", "ENTRY"),
+            _ => ("Biggest files", "FILE"),
+        };
+        return Ok(PreviewContent::Sections(vec![
+            PreviewContent::Text(summary),
+            PreviewContent::Table {
+                title: format!("{} (top {})", title, command.top.min(rows.len())),
+                headers: vec![first.to_string(), "SIZE".to_string()],
+                rows: rows
+                    .into_iter()
+                    .take(command.top)
+                    .map(|(name, size)| vec![name, human_size(size)])
+                    .collect(),
+            },
+        ]));
+    }
+
+    let (found, what) = match command.action {
+        CleanupAction::EmptyDirs => (scan.empty_dirs.clone(), "empty folder(s)"),
+        _ => (empty_files(&scan), "empty file(s)"),
+    };
+    if command.delete {
+        remember_preview(cmd, cwd, &found);
+        // Exactly what will go, one row each.
+        return Ok(PreviewContent::DiffList(
+            found
+                .iter()
+                .map(|path| DiffItem {
+                    original: shown(path, cwd),
+                    new: String::new(),
+                    status: Some("remove".to_string()),
+                })
+                .collect(),
+        ));
+    }
+    let mut lines = vec![format!(
+        "{} {} in {}{}",
+        found.len(),
+        what,
+        folder,
+        unreadable
+    )];
+    lines.extend(found.iter().map(|path| format!("  {}", shown(path, cwd))));
+    if !found.is_empty() {
+        lines.push(String::new());
+        lines.push("Nothing is removed without --delete.".to_string());
+    }
+    Ok(PreviewContent::Text(lines.join("\n")))
+}
+
+/// Removes `paths` in order, each only while it is still empty. Returns the
+/// ones removed and a line for each one kept.
+fn remove_empty(paths: &[PathBuf], action: CleanupAction) -> (usize, Vec<String>) {
+    let mut removed = 0;
+    let mut kept = Vec::new();
+    for path in paths {
+        let result = match action {
+            // Fails on its own when the folder is no longer empty.
+            CleanupAction::EmptyDirs => fs::remove_dir(path),
+            _ => match fs::symlink_metadata(path) {
+                Ok(meta) if meta.is_file() && meta.len() == 0 => fs::remove_file(path),
+                Ok(_) => Err(std::io::Error::other("no longer an empty file")),
+                Err(e) => Err(e),
+            },
+        };
+        match result {
+            Ok(()) => removed += 1,
+            Err(e) => kept.push(format!("  kept {}: {}", path.display(), e)),
+        }
+    }
+    (removed, kept)
+}
+
+/// Disk usage reports and removal of empty folders and files, built in.
+#[derive(Default)]
+pub struct CleanupPlugin {
+    settings: PluginSettings,
+}
+
+#[async_trait]
+impl Plugin for CleanupPlugin {
+    fn name(&self) -> &str {
+        "cleanup"
+    }
+
+    fn description(&self) -> &str {
+        "Find the biggest files, see what takes up space, and find or remove empty folders and files, built into Dexter."
+    }
+
+    fn configure(&mut self, settings: PluginSettings) {
+        self.settings = settings;
+    }
+
+    fn settings(&self) -> &PluginSettings {
+        &self.settings
+    }
+
+    fn capabilities(&self) -> &[Capability] {
+        &[Capability::Cleanup]
+    }
+
+    fn built_in(&self) -> bool {
+        true
+    }
+
+    async fn is_installed(&self) -> bool {
+        true
+    }
+
+    fn get_doc_for_router(&self) -> &str {
+        "Best for disk usage questions (biggest files, what takes up space in a folder) and for finding or deleting empty folders and zero-byte files. Not for duplicates (jdupes) or for deleting files with content."
+    }
+
+    fn get_doc_for_executor(&self) -> &str {
+        r#"cleanup Usage (built into Dexter):
+- Biggest files here: cleanup largest
+- Biggest 50 files in a folder: cleanup largest Downloads --top 50
+- Space per entry of a folder: cleanup usage Projects
+- List empty folders: cleanup empty-dirs
+- Delete empty folders: cleanup empty-dirs --delete
+- Delete zero-byte files in a folder: cleanup empty-files build --delete
+- Include dot files and folders: cleanup largest --hidden
+
+Notes:
+1. One action (largest, usage, empty-dirs, empty-files), then at most one folder (default: the current one).
+2. --top N only changes how many rows largest and usage list (default 20).
+3. --delete only goes with empty-dirs and empty-files; without it they only list.
+4. Nothing else is ever deleted: files with content are only reported."#
+    }
+
+    fn get_executor_prompt_with_doc(&self, doc: &str, context: &str, user_input: &str) -> String {
+        format!(
+            r#"You are the Disk Cleanup Specialist Agent for Dexter.
+Your goal is to generate ONE `cleanup` command.
+
+### HARD CONSTRAINTS (MUST FOLLOW):
+1. OUTPUT ONLY: Output ONLY the command. No backticks, no markdown, no explanations.
+2. NO SHELL CHAINS: Do NOT use pipes, redirection, `&&`, `||`, `;`, backticks, or `$()`.
+3. SYNTAX: `cleanup <largest|usage|empty-dirs|empty-files> [FOLDER] [--top N] [--hidden] [--delete]`.
+4. DELETE RULE: Add --delete only when the user asks to delete or remove empty folders or files.
+5. PRECISION: Treat folder names as literal strings from context; quote names with spaces.
+
+### Documentation:
+{}
+
+### Context:
+{}
+
+### User Request:
+{}
+"#,
+            doc, context, user_input
+        )
+    }
+
+    fn validate_command(&self, cmd: &str) -> bool {
+        parse_cleanup_command(cmd).is_ok()
+    }
+
+    fn confirm_twice(&self, cmd: &str) -> bool {
+        removes_files(cmd)
+    }
+
+    async fn dry_run(&self, cmd: &str, _llm: Option<&dyn LlmBridge>) -> Result<PreviewContent> {
+        let command = parse_cleanup_command(cmd)?;
+        let cwd = working_dir()?;
+        let cmd = cmd.to_string();
+        tokio::task::spawn_blocking(move || preview(&cmd, &command, &cwd)).await?
+    }
+
+    async fn execute(&self, cmd: &str) -> Result<String> {
+        self.execute_with_progress(cmd, mpsc::channel(1).0).await
+    }
+
+    async fn execute_with_progress(
+        &self,
+        cmd: &str,
+        progress_tx: mpsc::Sender<Progress>,
+    ) -> Result<String> {
+        let command = parse_cleanup_command(cmd)?;
+        let cwd = working_dir()?;
+        let _ = progress_tx
+            .send(Progress {
+                percentage: None,
+                message: format!("Scanning {}...", command.path.display()),
+            })
+            .await;
+        let owned = cmd.to_string();
+        if !command.delete {
+            let preview =
+                tokio::task::spawn_blocking(move || preview(&owned, &command, &cwd)).await??;
+            return Ok(preview_text(&preview));
+        }
+
+        let (removed, kept) = tokio::task::spawn_blocking(move || {
+            let paths = removals_to_run(&owned, &cwd)?;
+            Ok::<_, anyhow::Error>(remove_empty(&paths, command.action))
+        })
+        .await??;
+        let what = match parse_cleanup_command(cmd)?.action {
+            CleanupAction::EmptyDirs => "empty folder(s)",
+            _ => "empty file(s)",
+        };
+        let mut lines = vec![format!("Removed {} {}.", removed, what)];
+        lines.extend(kept);
+        Ok(lines.join("\n"))
+    }
+}
+
+/// A report preview as plain text, for the run output.
+fn preview_text(preview: &PreviewContent) -> String {
+    match preview {
+        PreviewContent::Text(text) => text.clone(),
+        PreviewContent::Sections(parts) => parts
+            .iter()
+            .map(preview_text)
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        PreviewContent::Table { title, rows, .. } => {
+            let mut lines = vec![title.clone()];
+            lines.extend(
+                rows.iter()
+                    .map(|row| format!("  {:>10}  {}", row[1], row[0])),
+            );
+            lines.join("\n")
+        }
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_actions_and_rejects_stray_options() {
+        let command = parse_cleanup_command("cleanup largest \"My Files\" --top 5").unwrap();
+        assert_eq!(command.action, CleanupAction::Largest);
+        assert_eq!(command.path, PathBuf::from("My Files"));
+        assert_eq!(command.top, 5);
+        assert!(!command.delete);
+
+        assert!(removes_files("cleanup empty-dirs --delete"));
+        assert!(!removes_files("cleanup empty-dirs"));
+        assert!(parse_cleanup_command("cleanup largest --delete").is_err());
+        assert!(parse_cleanup_command("cleanup largest --top 0").is_err());
+        assert!(parse_cleanup_command("cleanup usage a b").is_err());
+        assert!(parse_cleanup_command("cleanup wipe").is_err());
+        assert!(parse_cleanup_command("cleanup empty-dirs . --force").is_err());
+        assert!(parse_cleanup_command("rm -r empty").is_err());
+        assert!(parse_cleanup_command("cleanup empty-dirs /etc --delete").is_err());
+        assert!(parse_cleanup_command("cleanup empty-files ../x --delete").is_err());
+        assert!(parse_cleanup_command("cleanup largest a/../../b").is_err());
+        assert!(parse_cleanup_command("cleanup usage ~/Downloads").is_err());
+        assert!(parse_cleanup_command("cleanup usage ./Downloads").is_ok());
+    }
+
+    #[test]
+    fn finds_nested_empty_folders_and_only_removes_empty_ones() {
+        let dir = std::env::temp_dir().join(format!("dexter-cleanup-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("a/b/c")).unwrap();
+        fs::create_dir_all(dir.join("kept/.git")).unwrap();
+        fs::create_dir_all(dir.join("full")).unwrap();
+        fs::write(dir.join("full/data.bin"), vec![0u8; 2048]).unwrap();
+        fs::write(dir.join("blank.txt"), "").unwrap();
+
+        let command = parse_cleanup_command("cleanup empty-dirs --delete").unwrap();
+        let removals = planned_removals(&command, &dir).unwrap();
+        let names: Vec<String> = removals.iter().map(|p| shown(p, &dir)).collect();
+        assert_eq!(names, ["a/b/c", "a/b", "a"]);
+        assert_eq!(outermost(&removals), [dir.join("a")]);
+
+        // A run stays within its preview, and checks each entry again.
+        let cmd = "cleanup empty-dirs --delete";
+        preview(cmd, &command, &dir).unwrap();
+        fs::create_dir_all(dir.join("later")).unwrap();
+        assert_eq!(removals_to_run(cmd, &dir).unwrap(), removals);
+        fs::remove_dir(dir.join("later")).unwrap();
+        assert!(still_empty(&dir.join("a"), &command));
+        assert!(!still_empty(&dir.join("full"), &command));
+
+        fs::write(dir.join("a/b/late.txt"), "x").unwrap();
+        let (removed, kept) = remove_empty(&removals, CleanupAction::EmptyDirs);
+        assert_eq!((removed, kept.len()), (1, 2));
+        assert!(dir.join("a/b/late.txt").exists());
+
+        let largest = parse_cleanup_command("cleanup largest").unwrap();
+        match preview("cleanup largest", &largest, &dir).unwrap() {
+            PreviewContent::Sections(parts) => assert_eq!(
+                parts[1],
+                PreviewContent::Table {
+                    title: "Biggest files (top 3)".to_string(),
+                    headers: vec!["FILE".to_string(), "SIZE".to_string()],
+                    rows: vec![
+                        vec!["full/data.bin".to_string(), "2.0 KB".to_string()],
+                        vec!["a/b/late.txt".to_string(), "1 B".to_string()],
+                        vec!["blank.txt".to_string(), "0 B".to_string()],
+                    ],
+                }
+            ),
+            other => panic!("unexpected preview {:?}", other),
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,11 +1,92 @@
 use anyhow::{anyhow, Result};
-use std::path::Path;
-use std::process::{Command, Output, Stdio};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Output, Stdio};
 use tokio::process::{Child, Command as TokioCommand};
 
+use crate::sandbox::wrap_active;
+
+tokio::task_local! {
+    static WORKING_DIR: PathBuf;
+    static PREVIEW_OFFLINE: bool;
+}
+
+/// Runs `future` with `dir` as the directory plugins run their commands
+/// in, so jobs in different directories can run at once without changing
+/// the process's cwd.
+pub async fn in_dir<F: Future>(dir: PathBuf, future: F) -> F::Output {
+    WORKING_DIR.scope(dir, future).await
+}
+
+/// `path` with a leading `~` or `~/` resolved against `home`; unchanged
+/// when there is no home.
+pub fn expand_home(path: &str, home: Option<&Path>) -> PathBuf {
+    match (path.strip_prefix("~/"), home) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ if path == "~" => home.map_or_else(|| PathBuf::from(path), Path::to_path_buf),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// The directory `in_dir` set for this task, or else the process's cwd.
+pub fn working_dir() -> std::io::Result<PathBuf> {
+    WORKING_DIR
+        .try_with(PathBuf::clone)
+        .or_else(|_| std::env::current_dir())
+}
+
+/// Runs the preview `future` with network fetches that only enrich it
+/// turned off when `offline`, as on a metered connection.
+pub async fn preview_offline<F: Future>(offline: bool, future: F) -> F::Output {
+    PREVIEW_OFFLINE.scope(offline, future).await
+}
+
+/// Whether a preview may fetch from the network; see `preview_offline`.
+pub fn preview_may_fetch() -> bool {
+    !PREVIEW_OFFLINE
+        .try_with(|offline| *offline)
+        .unwrap_or(false)
+}
+
+/// `argv` as this task would spawn it: wrapped in the active sandbox, if
+/// any. For commands handed to another task, which doesn't inherit it.
+pub fn sandboxed_argv(argv: &[String]) -> Vec<String> {
+    wrap_active(argv)
+}
+
 const FORBIDDEN_EXACT_TOKENS: &[&str] = &[";", "&&", "||", "|", ">", "<", ">>", "<<"];
 const FORBIDDEN_SUBSTRINGS: &[&str] = &["`", "$(", "${", ";", "&&", "||", "|", ">", "<"];
 
+/// A tool that ran and exited unsuccessfully. Displays as its message, so
+/// wrapping an error in it changes nothing users see.
+#[derive(Debug)]
+pub struct ExitFailure {
+    /// `None` when the process was killed by a signal.
+    pub code: Option<i32>,
+    message: String,
+}
+
+impl std::fmt::Display for ExitFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ExitFailure {}
+
+pub fn exit_failure(status: ExitStatus, message: impl Into<String>) -> anyhow::Error {
+    ExitFailure {
+        code: status.code(),
+        message: message.into(),
+    }
+    .into()
+}
+
+/// The exit code carried by an execution error, when the tool ran at all.
+pub fn exit_code_of(error: &anyhow::Error) -> Option<i32> {
+    error.downcast_ref::<ExitFailure>().and_then(|e| e.code)
+}
+
 pub fn parse_and_validate_command(raw: &str, expected_program: &str) -> Result<Vec<String>> {
     let trimmed = raw.trim();
     if trimmed.is_empty() {
@@ -42,6 +123,7 @@ pub fn spawn_checked(argv: &[String], cwd: impl AsRef<Path>) -> Result<Output> {
     if argv.is_empty() {
         return Err(anyhow!("Command is empty"));
     }
+    let argv = &wrap_active(argv);
 
     let mut cmd = Command::new(&argv[0]);
     cmd.args(argv.iter().skip(1));
@@ -53,6 +135,7 @@ pub async fn spawn_checked_async(argv: &[String], cwd: impl AsRef<Path>) -> Resu
     if argv.is_empty() {
         return Err(anyhow!("Command is empty"));
     }
+    let argv = &wrap_active(argv);
 
     let mut cmd = TokioCommand::new(&argv[0]);
     cmd.args(argv.iter().skip(1)).current_dir(cwd.as_ref());
@@ -64,6 +147,7 @@ pub fn spawn_checked_piped(argv: &[String], cwd: impl AsRef<Path>) -> Result<Chi
     if argv.is_empty() {
         return Err(anyhow!("Command is empty"));
     }
+    let argv = &wrap_active(argv);
 
     let mut cmd = TokioCommand::new(&argv[0]);
     cmd.args(argv.iter().skip(1))
@@ -127,4 +211,20 @@ mod tests {
     fn spawn_checked_piped_rejects_empty_argv() {
         assert!(spawn_checked_piped(&[], ".").is_err());
     }
+
+    #[tokio::test]
+    async fn previews_fetch_unless_marked_offline() {
+        assert!(preview_may_fetch());
+        assert!(!preview_offline(true, async { preview_may_fetch() }).await);
+        assert!(preview_offline(false, async { preview_may_fetch() }).await);
+    }
+
+    #[test]
+    fn expands_a_leading_tilde_against_the_given_home() {
+        let home = Path::new("/home/me");
+        assert_eq!(expand_home("~/a.css", Some(home)), home.join("a.css"));
+        assert_eq!(expand_home("~", Some(home)), home);
+        assert_eq!(expand_home("~other/a", Some(home)), Path::new("~other/a"));
+        assert_eq!(expand_home("~/a.css", None), Path::new("~/a.css"));
+    }
 }
@@ -1,11 +1,62 @@
-use crate::command_exec::{contains_arg, parse_and_validate_command, spawn_checked};
-use crate::{DiffItem, Plugin, PreviewContent};
-use anyhow::Result;
+use crate::command_exec::{
+    contains_arg, exit_failure, parse_and_validate_command, spawn_checked, spawn_checked_async,
+    working_dir,
+};
+use crate::{
+    Capability, DiffItem, DocBundle, ExecMode, ModeFlags, Package, Plugin, PluginSettings,
+    PreviewContent, Progress,
+};
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
-pub struct F2Plugin;
+#[derive(Default)]
+pub struct F2Plugin {
+    settings: PluginSettings,
+}
+
+/// f2 only prints the planned renames unless given `-x`.
+pub const F2_MODE_FLAGS: ModeFlags = ModeFlags {
+    preview: &[],
+    apply: &["-x", "-X", "--exec"],
+};
+
+/// f2 v2 switched variables to single braces (`{ext}`, `{%03d}`); the
+/// default docs describe v1's double-brace syntax.
+const F2_V2_DOC: &str = r#"f2 v2 Command Usage:
+- Simple find/replace: f2 -f "old" -r "new"
+- Regex find/replace: f2 -f "(\d+)" -r "IMG_$1"
+- Target specific file: f2 -f "old" -r "new" "file.txt"
+- Undo last operation: f2 -u -x
+- Rename from a CSV mapping (source path, new name per row): f2 --csv mapping.csv
+- Variable Syntax:
+    - Use {var} for file attributes (e.g., {ext}, {f} for the name without extension, {p} for the parent directory).
+    - Dates: {mtime.YYYY}, {mtime.MM}, {mtime.DD}; EXIF capture date: {x.cdt.YYYY}.
+    - Use $1, $2, etc., for Regex capture groups.
+    - Correction: Use SINGLE curly braces {ext}, NOT {{ext}} (that is f2 v1 syntax).
+    - Counter: Use {%03d} for zero-padded numbers, NOT %03d.
+
+Complex Examples:
+- Rename with Regex capture + 3-digit counter + Execute:
+  f2 -f 'Photo_(\d+)' -r 'Trip_$1_{%03d}' -x
+- Prefix files with their modification date:
+  f2 -f '^' -r '{mtime.YYYY}-{mtime.MM}-{mtime.DD}_'
+- Remove enclosing brackets but keep content (e.g., "【1】" -> "1"):
+  f2 -f '【(\d+)】' -r '$1'
+- Undo the last operation:
+  f2 -u -x
+
+Notes:
+1. CAPTURE GROUPS: To preserve part of the matched text (like a number inside brackets), you MUST wrap that part in parentheses `()` in the `-f` pattern and refer to it as `$1` in the `-r` pattern.
+2. Always include -x if you want to apply the changes, otherwise f2 only shows a preview.
+3. For maximum precision, include the specific filename as a trailing argument.
+4. f2 supports full regular expressions in the -f pattern by default; -s treats it as a literal string.
+"#;
+
+const PACKAGES: &[Package] = &[Package::brew("f2"), Package::scoop("f2")];
 
 #[async_trait]
 impl Plugin for F2Plugin {
@@ -17,6 +68,18 @@ impl Plugin for F2Plugin {
         "A fast, safe, and powerful batch renamer written in Go."
     }
 
+    fn configure(&mut self, settings: PluginSettings) {
+        self.settings = settings;
+    }
+
+    fn settings(&self) -> &PluginSettings {
+        &self.settings
+    }
+
+    fn capabilities(&self) -> &[Capability] {
+        &[Capability::Renaming]
+    }
+
     async fn is_installed(&self) -> bool {
         Command::new("f2")
             .arg("--version")
@@ -25,12 +88,8 @@ impl Plugin for F2Plugin {
             .unwrap_or(false)
     }
 
-    async fn install(&self) -> Result<()> {
-        // Assume user uses brew on macOS or has it installed
-        // In a real app, we might provide specific instructions
-        Err(anyhow::anyhow!(
-            "Please install F2 manually: 'brew install f2'"
-        ))
+    fn packages(&self) -> &[Package] {
+        PACKAGES
     }
 
     fn get_doc_for_router(&self) -> &str {
@@ -43,6 +102,7 @@ impl Plugin for F2Plugin {
 - Regex find/replace: f2 -f "(\d+)" -r "IMG_$1"
 - Target specific file: f2 -f "old" -r "new" "file.txt"
 - Undo last operation: f2 -u -x
+- Rename from a CSV mapping (source path, new name per row): f2 --csv mapping.csv
 - Variable Syntax:
     - Use {{var}} for file attributes (e.g., {{ext}}, {{isoDate}}).
     - Use $1, $2, etc., for Regex capture groups.
@@ -67,7 +127,14 @@ Notes:
 "#
     }
 
-    fn get_executor_prompt(&self, context: &str, user_input: &str) -> String {
+    fn doc_bundles(&self) -> &[DocBundle] {
+        &[DocBundle {
+            major: 2,
+            doc: F2_V2_DOC,
+        }]
+    }
+
+    fn get_executor_prompt_with_doc(&self, doc: &str, context: &str, user_input: &str) -> String {
         format!(
             r#"You are the Renaming Specialist Agent for Dexter. 
 Your goal is to generate a precise `f2` command (a powerful batch renamer).
@@ -84,6 +151,7 @@ Your goal is to generate a precise `f2` command (a powerful batch renamer).
 5. NEVER COLLAPSE FILES: Never generate replacements that rename many files to the same literal name.
    - Do NOT use `-r "{{.}}"` or similar placeholder-only replacements.
    - If `-f` uses a catch-all regex (like `.*`), `-r` must preserve uniqueness via captures (`$1`) or clear per-file variables.
+6. CSV MAPPINGS: If the user asks to rename according to a `.csv` file, output `f2 --csv <that file>` with no `-f` or `-r`.
 
 ### Documentation:
 {}
@@ -94,9 +162,7 @@ Your goal is to generate a precise `f2` command (a powerful batch renamer).
 ### User Request:
 {}
 "#,
-            self.get_doc_for_executor(),
-            context,
-            user_input
+            doc, context, user_input
         )
     }
 
@@ -104,119 +170,480 @@ Your goal is to generate a precise `f2` command (a powerful batch renamer).
         validate_f2_command(cmd)
     }
 
+    fn mode_flags(&self) -> ModeFlags {
+        F2_MODE_FLAGS
+    }
+
+    fn command_argv(&self, cmd: &str, mode: ExecMode) -> Result<Vec<String>> {
+        let mut argv = parse_and_validate_command(cmd, "f2")?;
+        F2_MODE_FLAGS.apply_to(&mut argv, mode);
+        if !contains_arg(&argv, "--no-color") {
+            argv.push("--no-color".to_string());
+        }
+        Ok(argv)
+    }
+
     async fn dry_run(
         &self,
         cmd: &str,
         _llm: Option<&dyn crate::LlmBridge>,
     ) -> Result<PreviewContent> {
-        let argv = build_f2_argv(cmd, false)?;
-        let cwd = std::env::current_dir()?;
-        let output = spawn_checked(&argv, cwd)?;
-
-        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-        let combined = format!("{}{}", stdout, stderr);
-
-        // Parse Output even if it failed, as f2 returns non-zero on conflicts
-        let mut diffs = Vec::new();
-        for line in combined.lines() {
-            let mut trimmed = line.trim().to_string();
-            if trimmed.is_empty() {
-                continue;
+        let argv = self.command_argv(cmd, ExecMode::Preview)?;
+        let cwd = working_dir()?;
+        if let Some(csv) = find_arg_value(&argv, "--csv", "--csv") {
+            let rows = load_csv_renames(&cwd.join(csv))?;
+            return Ok(PreviewContent::DiffList(
+                rows.into_iter()
+                    .map(|row| DiffItem {
+                        original: row.source,
+                        new: row.target,
+                        status: Some("ok".to_string()),
+                    })
+                    .collect(),
+            ));
+        }
+        let batches = f2_batches(&argv, &cwd);
+        if batches.len() == 1 {
+            let output = spawn_checked(&argv, cwd)?;
+            let combined = format!(
+                "{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            );
+            let diffs = parse_f2_preview(&combined);
+            return if !diffs.is_empty() {
+                Ok(PreviewContent::DiffList(diffs))
+            } else if !output.status.success() {
+                // If we couldn't parse any diffs AND it failed, then return the error
+                Err(anyhow::anyhow!("f2 error: {}", combined))
+            } else {
+                // If it succeeded but we couldn't parse logic diffs, just return text
+                Ok(PreviewContent::Text(combined))
+            };
+        }
+
+        // Batches preview a few at a time; their tables are joined in order.
+        let total = batches.len();
+        let mut outputs = vec![None; total];
+        let mut running = tokio::task::JoinSet::new();
+        let mut queued = batches.into_iter().enumerate();
+        loop {
+            while running.len() < PREVIEW_PARALLELISM {
+                let Some((idx, batch)) = queued.next() else {
+                    break;
+                };
+                let cwd = cwd.clone();
+                running.spawn(async move { (idx, spawn_checked_async(&batch, cwd).await) });
             }
+            let Some(joined) = running.join_next().await else {
+                break;
+            };
+            let (idx, output) = joined?;
+            let output = output?;
+            let combined = format!(
+                "{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            );
+            let diffs = parse_f2_preview(&combined);
+            if diffs.is_empty() && !output.status.success() {
+                return Err(anyhow!(
+                    "f2 error in batch {}/{}: {}",
+                    idx + 1,
+                    total,
+                    combined
+                ));
+            }
+            outputs[idx] = Some(diffs);
+        }
+        let batches: Vec<Vec<DiffItem>> = outputs.into_iter().flatten().collect();
+        Ok(PreviewContent::DiffList(join_batch_previews(batches)))
+    }
 
-            // More aggressive cleaning for f2's fancy table corners/borders in error cases
-            trimmed = trimmed
-                .replace("|*", "|")
-                .replace("*|", "|")
-                .replace("—", "")
-                .replace("*", "")
-                .trim()
-                .to_string();
+    async fn execute(&self, cmd: &str) -> Result<String> {
+        self.execute_with_progress(cmd, tokio::sync::mpsc::channel(1).0)
+            .await
+    }
 
-            if trimmed.is_empty() {
-                continue;
+    async fn execute_with_progress(
+        &self,
+        cmd: &str,
+        progress_tx: tokio::sync::mpsc::Sender<Progress>,
+    ) -> Result<String> {
+        let argv = self.command_argv(cmd, ExecMode::Apply)?;
+        let cwd = working_dir()?;
+        if let Some(csv) = find_arg_value(&argv, "--csv", "--csv") {
+            load_csv_renames(&cwd.join(csv))?;
+        }
+        let batches = f2_batches(&argv, &cwd);
+        let total = batches.len();
+        let mut combined = String::new();
+        for (idx, batch) in batches.iter().enumerate() {
+            if total > 1 {
+                let _ = progress_tx
+                    .send(Progress {
+                        percentage: Some(idx as f64 * 100.0 / total as f64),
+                        message: format!("Renaming batch {}/{}...", idx + 1, total),
+                    })
+                    .await;
             }
-
-            // Strategy 1: " -> "
-            if trimmed.contains(" -> ") {
-                let parts: Vec<&str> = trimmed.split(" -> ").collect();
-                if parts.len() == 2 {
-                    diffs.push(DiffItem {
-                        original: parts[0].trim().to_string(),
-                        new: parts[1].trim().to_string(),
-                        status: None, // No status in this format
-                    });
-                    continue;
-                }
+            let output = spawn_checked_async(batch, &cwd).await?;
+            let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            if !output.status.success() {
+                let applied = if idx > 0 {
+                    format!(
+                        "\nBatches 1-{} of {} were already applied; `f2 -u -x` only undoes the last one.",
+                        idx, total
+                    )
+                } else {
+                    String::new()
+                };
+                return Err(exit_failure(
+                    output.status,
+                    format!("f2 error: {}\n{}{}", stdout, stderr, applied),
+                ));
             }
+            combined.push_str(&stdout);
+            combined.push_str(&stderr);
+        }
+        Ok(combined)
+    }
+}
 
-            // Strategy 2: "|" table style
-            if trimmed.contains('|') {
-                let parts: Vec<&str> = trimmed
-                    .split('|')
-                    .map(|s| s.trim())
-                    .filter(|s| !s.is_empty())
-                    .collect();
-
-                if parts.len() >= 2 {
-                    let old_name = parts[0];
-                    let new_name = parts[1];
-                    let status = parts.get(2).map(|s| s.to_string());
-
-                    if is_f2_table_header(old_name, new_name, status.as_deref()) {
-                        continue;
+/// Most paths given to one f2 run, and their total length; longer lists
+/// are split so no run hits the OS argument limit (32 KiB on Windows).
+const BATCH_PATHS: usize = 1000;
+const BATCH_BYTES: usize = 24 * 1024;
+/// Batch previews running at once.
+const PREVIEW_PARALLELISM: usize = 4;
+
+/// Flags whose next argument is a value, not a path.
+const VALUE_FLAGS: &[&str] = &[
+    "-f",
+    "--find",
+    "-r",
+    "--replace",
+    "-E",
+    "--exclude",
+    "-l",
+    "--replace-limit",
+    "-m",
+    "--max-depth",
+    "--csv",
+    "--sort",
+    "--sortr",
+    "--sort-var",
+    "--start-num",
+    "--exiftool-opts",
+    "-t",
+    "--target-dir",
+    "--exclude-dir",
+    "--fix-conflicts-pattern",
+    "--pair-order",
+];
+
+/// Flags that make f2 walk into directories or rename them, so a
+/// directory target can't be swapped for the files directly inside it.
+const WALKING_FLAGS: &[&str] = &[
+    "-R",
+    "--recursive",
+    "-d",
+    "--include-dir",
+    "-D",
+    "--only-dir",
+];
+
+/// `argv` split into runs over at most `BATCH_PATHS` of its path
+/// arguments each, every run with all the flags; `argv` alone when it is
+/// short enough. Paths are the non-flag arguments that exist in `cwd`; a
+/// directory among them, or `cwd` when there are none, counts as the
+/// files directly inside it unless f2 is told to recurse or rename
+/// directories. Undo, CSV mappings and counters (`{%03d}`, which restarts
+/// in every run) always run as one.
+pub fn f2_batches(argv: &[String], cwd: &Path) -> Vec<Vec<String>> {
+    let single = vec![argv.to_vec()];
+    let one_run = argv.iter().any(|arg| {
+        matches!(arg.as_str(), "-u" | "--undo" | "--csv")
+            || arg.contains("{%")
+            || arg.contains("{{%")
+    });
+    if one_run {
+        return single;
+    }
+    let mut flags = Vec::new();
+    let mut paths = Vec::new();
+    let mut value_next = false;
+    for (idx, arg) in argv.iter().enumerate() {
+        let is_path = idx > 0 && !value_next && !arg.starts_with('-') && cwd.join(arg).exists();
+        value_next = VALUE_FLAGS.contains(&arg.as_str());
+        if is_path {
+            paths.push(arg.clone());
+        } else {
+            flags.push(arg.clone());
+        }
+    }
+    if !argv.iter().any(|arg| WALKING_FLAGS.contains(&arg.as_str())) {
+        let hidden = argv.iter().any(|arg| arg == "-H" || arg == "--hidden");
+        if paths.is_empty() {
+            paths = dir_files(cwd, None, hidden);
+        } else if paths.iter().any(|path| cwd.join(path).is_dir()) {
+            paths = paths
+                .into_iter()
+                .flat_map(|path| {
+                    if cwd.join(&path).is_dir() {
+                        dir_files(&cwd.join(&path), Some(&path), hidden)
+                    } else {
+                        vec![path]
                     }
+                })
+                .collect();
+        }
+    }
+    let bytes: usize = paths.iter().map(|path| path.len() + 1).sum();
+    if paths.len() <= BATCH_PATHS && bytes <= BATCH_BYTES {
+        return single;
+    }
+
+    let mut batches = Vec::new();
+    let mut current: Vec<String> = Vec::new();
+    let mut current_bytes = 0;
+    for path in paths {
+        if !current.is_empty()
+            && (current.len() >= BATCH_PATHS || current_bytes + path.len() + 1 > BATCH_BYTES)
+        {
+            batches.push(std::mem::take(&mut current));
+            current_bytes = 0;
+        }
+        current_bytes += path.len() + 1;
+        current.push(path);
+    }
+    batches.push(current);
+    batches
+        .into_iter()
+        .map(|batch| flags.iter().cloned().chain(batch).collect())
+        .collect()
+}
+
+/// The files directly inside `dir`, named as f2 arguments: under `prefix`
+/// when the directory was one, sorted. Dot files only with `hidden`, as
+/// f2 skips them otherwise.
+fn dir_files(dir: &Path, prefix: Option<&str>, hidden: bool) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_file()))
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| hidden || !name.starts_with('.'))
+        .collect();
+    names.sort();
+    match prefix {
+        Some(prefix) => names
+            .into_iter()
+            .map(|name| format!("{}/{}", prefix.trim_end_matches('/'), name))
+            .collect(),
+        None => names,
+    }
+}
 
-                    diffs.push(DiffItem {
-                        original: old_name.to_string(),
-                        new: new_name.to_string(),
-                        status,
-                    });
+/// The batches' rename tables as one. f2 only checks for clashes within a
+/// run, so a new name that another batch also produces is marked here,
+/// including one that differs only in case or in how the path is spelled
+/// (`./a.jpg`, `a.jpg`), which case-insensitive file systems treat alike.
+fn join_batch_previews(batches: Vec<Vec<DiffItem>>) -> Vec<DiffItem> {
+    let mut first_batch: HashMap<String, usize> = HashMap::new();
+    let mut clashing = HashSet::new();
+    for (idx, batch) in batches.iter().enumerate() {
+        for item in batch {
+            let key = name_key(&item.new);
+            match first_batch.get(&key) {
+                Some(&other) if other != idx => {
+                    clashing.insert(key);
+                }
+                Some(_) => {}
+                None => {
+                    first_batch.insert(key, idx);
                 }
             }
         }
+    }
+    batches
+        .into_iter()
+        .flatten()
+        .map(|mut item| {
+            if clashing.contains(&name_key(&item.new)) {
+                item.status = Some("conflict: same new name in another batch".to_string());
+            }
+            item
+        })
+        .collect()
+}
 
-        if !diffs.is_empty() {
-            Ok(PreviewContent::DiffList(diffs))
-        } else if !output.status.success() {
-            // If we couldn't parse any diffs AND it failed, then return the error
-            Err(anyhow::anyhow!("f2 error: {}", combined))
-        } else {
-            // If it succeeded but we couldn't parse logic diffs, just return text
-            Ok(PreviewContent::Text(combined))
+/// `name` lowercased, with `\` as `/` and `.` and empty path segments
+/// dropped, so spellings of one file compare equal.
+fn name_key(name: &str) -> String {
+    name.trim()
+        .replace('\\', "/")
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect::<Vec<_>>()
+        .join("/")
+        .to_lowercase()
+}
+
+/// The renames in f2's preview output, read even when it failed: f2 exits
+/// non-zero on conflicts but still prints its table.
+fn parse_f2_preview(combined: &str) -> Vec<DiffItem> {
+    let mut diffs = Vec::new();
+    for line in combined.lines() {
+        let mut trimmed = line.trim().to_string();
+        if trimmed.is_empty() {
+            continue;
         }
-    }
 
-    async fn execute(&self, cmd: &str) -> Result<String> {
-        let argv = build_f2_argv(cmd, true)?;
-        let cwd = std::env::current_dir()?;
-        let output = spawn_checked(&argv, cwd)?;
+        // More aggressive cleaning for f2's fancy table corners/borders in error cases
+        trimmed = trimmed
+            .replace("|*", "|")
+            .replace("*|", "|")
+            .replace("—", "")
+            .replace("*", "")
+            .trim()
+            .to_string();
+
+        if trimmed.is_empty() {
+            continue;
+        }
 
-        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        // Strategy 1: " -> "
+        if trimmed.contains(" -> ") {
+            let parts: Vec<&str> = trimmed.split(" -> ").collect();
+            if parts.len() == 2 {
+                diffs.push(DiffItem {
+                    original: parts[0].trim().to_string(),
+                    new: parts[1].trim().to_string(),
+                    status: None, // No status in this format
+                });
+                continue;
+            }
+        }
 
-        if output.status.success() {
-            let combined = format!("{}{}", stdout, stderr);
-            Ok(combined)
-        } else {
-            Err(anyhow::anyhow!("f2 error: {}\n{}", stdout, stderr))
+        // Strategy 2: "|" table style
+        if trimmed.contains('|') {
+            let parts: Vec<&str> = trimmed
+                .split('|')
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .collect();
+
+            if parts.len() >= 2 {
+                let old_name = parts[0];
+                let new_name = parts[1];
+                let status = parts.get(2).map(|s| s.to_string());
+
+                if is_f2_table_header(old_name, new_name, status.as_deref()) {
+                    continue;
+                }
+
+                diffs.push(DiffItem {
+                    original: old_name.to_string(),
+                    new: new_name.to_string(),
+                    status,
+                });
+            }
         }
     }
+    diffs
 }
 
-fn build_f2_argv(cmd: &str, execute_mode: bool) -> Result<Vec<String>> {
-    let mut argv = parse_and_validate_command(cmd, "f2")?;
-    argv.retain(|a| a != "-x" && a != "-X");
+/// One row of an `f2 --csv` mapping.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CsvRename {
+    source: String,
+    target: String,
+}
 
-    if execute_mode && !contains_arg(&argv, "-x") && !contains_arg(&argv, "-X") {
-        argv.push("-x".to_string());
+/// Reads the rename mapping `f2 --csv` would apply: the first column is the
+/// source path (relative to the CSV's directory), the second the new name.
+/// Fails when a source is missing, so nothing runs on a stale mapping.
+fn load_csv_renames(csv_path: &Path) -> Result<Vec<CsvRename>> {
+    let raw = std::fs::read_to_string(csv_path)
+        .map_err(|e| anyhow!("Could not read {}: {}", csv_path.display(), e))?;
+    let base = csv_path.parent().map(Path::to_path_buf).unwrap_or_default();
+    let rows = parse_csv_renames(&raw);
+    if rows.is_empty() {
+        return Err(anyhow!("{} has no rename rows", csv_path.display()));
+    }
+    let missing: Vec<&str> = rows
+        .iter()
+        .filter(|row| !resolve_csv_source(&base, &row.source).exists())
+        .map(|row| row.source.as_str())
+        .collect();
+    if !missing.is_empty() {
+        return Err(anyhow!(
+            "{} names {} missing file(s): {}",
+            csv_path.display(),
+            missing.len(),
+            missing.join(", ")
+        ));
     }
-    if !contains_arg(&argv, "--no-color") {
-        argv.push("--no-color".to_string());
+    Ok(rows)
+}
+
+fn resolve_csv_source(base: &Path, source: &str) -> PathBuf {
+    let path = Path::new(source);
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        base.join(path)
     }
+}
 
-    Ok(argv)
+/// Rows with at least two non-empty columns; a leading header row such as
+/// `original,new` is skipped.
+fn parse_csv_renames(raw: &str) -> Vec<CsvRename> {
+    let mut rows: Vec<CsvRename> = raw
+        .lines()
+        .map(split_csv_line)
+        .filter_map(|fields| {
+            let source = fields.first()?.trim();
+            let target = fields.get(1)?.trim();
+            (!source.is_empty() && !target.is_empty()).then(|| CsvRename {
+                source: source.to_string(),
+                target: target.to_string(),
+            })
+        })
+        .collect();
+    if rows.first().is_some_and(|row| {
+        let source = row.source.to_lowercase();
+        matches!(source.as_str(), "source" | "from" | "path")
+            || is_f2_table_header(&row.source, &row.target, None)
+    }) {
+        rows.remove(0);
+    }
+    rows
+}
+
+/// Splits one CSV line, honouring double-quoted fields and `""` escapes.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
 }
 
 fn validate_f2_command(cmd: &str) -> bool {
@@ -233,9 +660,27 @@ fn validate_f2_command(cmd: &str) -> bool {
         return false;
     }
 
+    if clusters_exec_flag(&argv) {
+        return false;
+    }
+
     true
 }
 
+/// Whether a short cluster such as `-ix` carries `-x` or `-X`, which the
+/// mode flags can't take out of it. Option values are skipped: `-f -xyz`
+/// finds a name.
+fn clusters_exec_flag(argv: &[String]) -> bool {
+    argv.iter().enumerate().skip(1).any(|(idx, arg)| {
+        !VALUE_FLAGS.contains(&argv[idx - 1].as_str())
+            && arg.len() > 2
+            && arg.starts_with('-')
+            && !arg.starts_with("--")
+            && arg[1..].chars().all(|c| c.is_ascii_alphabetic())
+            && arg[1..].contains(['x', 'X'])
+    })
+}
+
 fn has_invalid_placeholder_replacement(argv: &[String]) -> bool {
     let replacement = find_arg_value(argv, "-r", "--replace");
     let Some(replacement) = replacement else {
@@ -332,6 +777,156 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn execute_flag_follows_the_mode() {
+        let preview = F2Plugin::default()
+            .command_argv("f2 -f a -r b --exec", ExecMode::Preview)
+            .unwrap();
+        assert_eq!(preview, ["f2", "-f", "a", "-r", "b", "--no-color"]);
+        let apply = F2Plugin::default()
+            .command_argv("f2 -f a -r b -X", ExecMode::Apply)
+            .unwrap();
+        assert_eq!(apply, ["f2", "-f", "a", "-r", "b", "-x", "--no-color"]);
+        let preview = F2Plugin::default()
+            .command_argv("f2 -f a -r b --exec=true", ExecMode::Preview)
+            .unwrap();
+        assert_eq!(preview, ["f2", "-f", "a", "-r", "b", "--no-color"]);
+    }
+
+    #[test]
+    fn validate_rejects_exec_inside_short_clusters() {
+        assert!(!validate_f2_command("f2 -f a -r b -ix"));
+        assert!(!validate_f2_command("f2 -Xi -f a -r b"));
+        assert!(validate_f2_command("f2 -f -xmas -r b -i"));
+        assert!(validate_f2_command("f2 -f a -r b -x"));
+    }
+
+    #[test]
+    fn long_path_lists_run_in_batches() {
+        let dir = std::env::temp_dir().join(format!("dexter-f2-batch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut argv: Vec<String> = ["f2", "-f", "a", "-r", "b"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        for idx in 0..2500 {
+            let name = format!("a{:04}.jpg", idx);
+            std::fs::write(dir.join(&name), "").unwrap();
+            argv.push(name);
+        }
+        argv.push("-x".to_string());
+
+        let batches = f2_batches(&argv, &dir);
+        assert_eq!(batches.len(), 3);
+        assert_eq!(batches[0][..5], argv[..5]);
+        assert_eq!(batches[0][5], "-x");
+        assert_eq!(batches[0].len(), 6 + BATCH_PATHS);
+        assert_eq!(batches[2].last().map(String::as_str), Some("a2499.jpg"));
+        // Counters restart in every run, so such commands stay whole.
+        argv[4] = "b_{%03d}".to_string();
+        assert_eq!(f2_batches(&argv, &dir).len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let item = |original: &str, new: &str| DiffItem {
+            original: original.to_string(),
+            new: new.to_string(),
+            status: Some("ok".to_string()),
+        };
+        let joined = join_batch_previews(vec![
+            vec![item("a1", "b1"), item("a2", "b")],
+            vec![item("x", "b")],
+        ]);
+        let statuses: Vec<&str> = joined
+            .iter()
+            .map(|item| item.status.as_deref().unwrap_or_default())
+            .collect();
+        assert_eq!(
+            statuses,
+            [
+                "ok",
+                "conflict: same new name in another batch",
+                "conflict: same new name in another batch"
+            ]
+        );
+    }
+
+    #[test]
+    fn directory_targets_run_in_batches_of_their_files() {
+        let dir = std::env::temp_dir().join(format!("dexter-f2-dir-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("photos")).unwrap();
+        for idx in 0..1500 {
+            std::fs::write(dir.join("photos").join(format!("a{:04}.jpg", idx)), "").unwrap();
+        }
+        std::fs::write(dir.join("photos").join(".hidden"), "").unwrap();
+        let argv =
+            |args: &[&str]| -> Vec<String> { args.iter().map(|arg| arg.to_string()).collect() };
+
+        let batches = f2_batches(&argv(&["f2", "-f", "a", "-r", "b", "photos"]), &dir);
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0][5], "photos/a0000.jpg");
+        assert_eq!(
+            batches[1].last().map(String::as_str),
+            Some("photos/a1499.jpg")
+        );
+        // No path means the working directory.
+        let batches = f2_batches(&argv(&["f2", "-f", "a", "-r", "b"]), &dir.join("photos"));
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0][5], "a0000.jpg");
+        // Walking into or renaming directories needs the directory itself.
+        let recursive = argv(&["f2", "-f", "a", "-r", "b", "-R", "photos"]);
+        assert_eq!(f2_batches(&recursive, &dir), vec![recursive.clone()]);
+        // A value that names a directory isn't a target.
+        let target_dir = argv(&["f2", "-f", "a", "-r", "b", "-t", "photos", "x.jpg"]);
+        assert_eq!(f2_batches(&target_dir, &dir), vec![target_dir.clone()]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn batches_producing_names_that_differ_in_case_or_spelling_conflict() {
+        let item = |new: &str| DiffItem {
+            original: "x".to_string(),
+            new: new.to_string(),
+            status: Some("ok".to_string()),
+        };
+        let joined = join_batch_previews(vec![
+            vec![item("Photo.JPG"), item("dir/a.jpg"), item("b.jpg")],
+            vec![item("photo.jpg"), item("./dir//a.jpg"), item("c.jpg")],
+        ]);
+        let conflicts: Vec<&str> = joined
+            .iter()
+            .filter(|item| item.status.as_deref() != Some("ok"))
+            .map(|item| item.new.as_str())
+            .collect();
+        assert_eq!(
+            conflicts,
+            ["Photo.JPG", "dir/a.jpg", "photo.jpg", "./dir//a.jpg"]
+        );
+    }
+
+    #[test]
+    fn csv_mapping_previews_rows_and_rejects_missing_sources() {
+        let dir = std::env::temp_dir().join(format!("dexter-f2-csv-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a 1.jpg"), "").unwrap();
+        let csv = dir.join("mapping.csv");
+        std::fs::write(&csv, "original,new\n\"a 1.jpg\",\"Beach, day 1.jpg\"\n").unwrap();
+
+        let rows = load_csv_renames(&csv).unwrap();
+        assert_eq!(
+            rows,
+            [CsvRename {
+                source: "a 1.jpg".to_string(),
+                target: "Beach, day 1.jpg".to_string(),
+            }]
+        );
+
+        std::fs::write(&csv, "a 1.jpg,b.jpg\ngone.jpg,c.jpg\n").unwrap();
+        let err = load_csv_renames(&csv).unwrap_err().to_string();
+        assert!(err.contains("gone.jpg"));
+        assert!(validate_f2_command("f2 --csv mapping.csv"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn header_detection_supports_chinese_table_labels() {
         assert!(is_f2_table_header("原始文件名", "新文件名", Some("状态")));
@@ -1,11 +1,107 @@
-use crate::command_exec::{parse_and_validate_command, spawn_checked_piped};
-use crate::{Plugin, PreviewContent};
-use anyhow::Result;
+use crate::command_exec::{
+    exit_failure, parse_and_validate_command, spawn_checked_async, spawn_checked_piped, working_dir,
+};
+use crate::{Capability, KeyValueSection, Package, Plugin, PluginSettings, PreviewContent};
+use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
 use std::process::Command;
+use std::sync::{Mutex, OnceLock};
 use tokio::io::AsyncBufReadExt;
 
-pub struct FFmpegPlugin;
+#[derive(Default)]
+pub struct FFmpegPlugin {
+    settings: PluginSettings,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum StreamKind {
+    Video,
+    Audio,
+    Subtitle,
+    Other,
+}
+
+impl StreamKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            StreamKind::Video => "video",
+            StreamKind::Audio => "audio",
+            StreamKind::Subtitle => "subtitle",
+            StreamKind::Other => "other",
+        }
+    }
+}
+
+/// One stream of the input file as reported by `ffprobe -show_streams`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MediaStream {
+    pub index: u32,
+    pub kind: StreamKind,
+    /// Position among streams of the same kind (the `k` in `0:a:k`).
+    pub kind_index: u32,
+    pub codec: String,
+    pub language: Option<String>,
+    pub title: Option<String>,
+    pub channels: Option<u32>,
+    /// Width and height of video streams.
+    pub resolution: Option<(u32, u32)>,
+    pub default: bool,
+    /// Length in milliseconds, when the container records one per stream.
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+    /// Bits per second, when the container records one per stream.
+    #[serde(default)]
+    pub bit_rate: Option<u64>,
+}
+
+impl MediaStream {
+    pub fn describe(&self) -> String {
+        let mut out = format!("#{} {} {}", self.index, self.kind.label(), self.codec);
+        if let Some(lang) = &self.language {
+            out.push_str(&format!(" [{}]", lang));
+        }
+        if let Some(channels) = self.channels {
+            out.push_str(&format!(" {}ch", channels));
+        }
+        if let Some(title) = &self.title {
+            out.push_str(&format!(" \"{}\"", title));
+        }
+        if self.default {
+            out.push_str(" (default)");
+        }
+        out
+    }
+
+    /// Resolution of a video stream, channel count of an audio one.
+    pub fn detail(&self) -> String {
+        match (self.resolution, self.channels) {
+            (Some((width, height)), _) => format!("{}x{}", width, height),
+            (None, Some(channels)) => format!("{}ch", channels),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+/// Dry-run result for a single-input command whose input carries more than
+/// one audio or subtitle stream. `streams` holds only the selectable
+/// (audio/subtitle) streams and `selected` what the command keeps as written.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StreamSelection {
+    pub summary: String,
+    pub input: String,
+    pub streams: Vec<MediaStream>,
+    pub selected: Vec<bool>,
+}
+
+pub(crate) const PACKAGES: &[Package] = &[
+    Package::brew("ffmpeg"),
+    Package::apt("ffmpeg"),
+    Package::winget("Gyan.FFmpeg"),
+    Package::scoop("ffmpeg"),
+];
 
 #[async_trait]
 impl Plugin for FFmpegPlugin {
@@ -17,6 +113,18 @@ impl Plugin for FFmpegPlugin {
         "A complete, cross-platform solution to record, convert and stream audio and video."
     }
 
+    fn configure(&mut self, settings: PluginSettings) {
+        self.settings = settings;
+    }
+
+    fn settings(&self) -> &PluginSettings {
+        &self.settings
+    }
+
+    fn capabilities(&self) -> &[Capability] {
+        &[Capability::MediaVideo, Capability::MediaAudio]
+    }
+
     async fn is_installed(&self) -> bool {
         Command::new("ffmpeg")
             .arg("-version")
@@ -25,10 +133,8 @@ impl Plugin for FFmpegPlugin {
             .unwrap_or(false)
     }
 
-    async fn install(&self) -> Result<()> {
-        Err(anyhow::anyhow!(
-            "Please install ffmpeg manually: 'brew install ffmpeg'"
-        ))
+    fn packages(&self) -> &[Package] {
+        PACKAGES
     }
 
     fn get_doc_for_router(&self) -> &str {
@@ -41,6 +147,16 @@ impl Plugin for FFmpegPlugin {
 - Extract audio: ffmpeg -i input.mp4 -vn -c:a libmp3lame output.mp3
 - Change resolution: ffmpeg -i input.mp4 -vf scale=1280:720 output_720p.mp4
 - Fast seek and clip (Place -ss BEFORE -i): ffmpeg -ss 00:00:10 -i input.mp4 -t 00:00:30 -c copy output.mp4
+- Cut from 2:00 to 10:30: ffmpeg -ss 00:02:00 -to 00:10:30 -i input.mp4 -c copy output.mp4
+- Split every 10 minutes: ffmpeg -i input.mp4 -map 0 -c copy -f segment -segment_time 600 -reset_timestamps 1 output_%03d.mp4
+- Split at given times (e.g. chapter starts, in seconds): ffmpeg -i input.mkv -map 0 -c copy -f segment -segment_times 252,630.5 -reset_timestamps 1 output_%02d.mkv
+- Normalize loudness (EBU R128): ffmpeg -i input.mp4 -c:v copy -af loudnorm=I=-16:TP=-1.5:LRA=11 -ar 48000 -c:a aac -b:a 192k output.mp4
+- Apply ReplayGain tags already in the file: ffmpeg -i input.flac -af volume=replaygain=track output.flac
+
+Loudness:
+- Targets: -16 LUFS for podcasts and streaming (-14 for YouTube/Spotify), -23 LUFS for EBU R128 broadcast; true peak -1.5 or -1 dBTP.
+- loudnorm runs in two passes: write only the targets (I, TP, LRA) and Dexter measures the input first, then adds the measured values for the second pass.
+- "ReplayGain" or "album gain" means volume=replaygain=track or volume=replaygain=album; use loudnorm when the file has no ReplayGain tags.
 
 Modern Usage & Syntax Override:
 1. Stream Selection: ALWAYS use -c:v / -c:a instead of -vcodec / -acodec.
@@ -56,10 +172,14 @@ Complex Examples:
 CRITICAL RULES:
 - NEVER use -sameq (it does not exist). Use -crf (video) or -q:a (audio).
 - Distinguish -vf (single stream) vs -filter_complex (multi-stream/input).
+- Use either -t (duration) or -to (end time), never both. With -ss and -to both before -i, -to is an absolute end time.
+- Segment outputs MUST contain a number pattern such as %03d.
+- NEVER write measured_I, measured_TP, measured_LRA, measured_thresh or offset for loudnorm; Dexter fills them in.
+- loudnorm resamples to 192 kHz: ALWAYS add -ar 48000 (or the input's rate) and an audio codec.
 "#
     }
 
-    fn get_executor_prompt(&self, context: &str, user_input: &str) -> String {
+    fn get_executor_prompt_with_doc(&self, doc: &str, context: &str, user_input: &str) -> String {
         format!(
             r#"You are the Media Processing Specialist Agent for Dexter. 
 Your goal is to generate a valid `ffmpeg` command.
@@ -79,14 +199,14 @@ Your goal is to generate a valid `ffmpeg` command.
 ### User Request:
 {}
 "#,
-            self.get_doc_for_executor(),
-            context,
-            user_input
+            doc, context, user_input
         )
     }
 
     fn validate_command(&self, cmd: &str) -> bool {
-        parse_and_validate_command(cmd, "ffmpeg").is_ok()
+        parse_and_validate_command(cmd, "ffmpeg")
+            .and_then(|argv| timing_flags(&argv))
+            .is_ok()
     }
 
     async fn dry_run(
@@ -94,16 +214,47 @@ Your goal is to generate a valid `ffmpeg` command.
         cmd: &str,
         llm: Option<&dyn crate::LlmBridge>,
     ) -> Result<PreviewContent> {
-        if let Some(llm) = llm {
+        let mut summary = if let Some(llm) = llm {
             let system_prompt = "You are a playful but precise command explainer for Dexter. Describe what this FFmpeg command will do in simple terms. Mention input, output, and key transformations. Output plain text only.";
-            let text = llm.chat(system_prompt, cmd).await?;
-            Ok(PreviewContent::Text(text))
+            llm.chat(system_prompt, cmd).await?
         } else {
-            Ok(PreviewContent::Text(format!(
-                "Executing media command: {}",
-                cmd
-            )))
+            format!("Executing media command: {}", cmd)
+        };
+
+        let argv = parse_and_validate_command(cmd, "ffmpeg")?;
+        timing_flags(&argv)?;
+        if let Some(input) = single_local_input(&argv) {
+            if let Some(segments) = segment_preview(&argv, &input).await? {
+                summary = format!("{}\n\n{}", summary, segments);
+            }
         }
+        if let Some(plan) = loudnorm_plan(&argv) {
+            let cwd = working_dir()?;
+            let loudness = match measure_loudness(&plan, &argv, &cwd).await {
+                Ok(measured) => plan.describe(&measured),
+                Err(e) => format!("Loudness analysis (pass 1 of 2) failed: {}", e),
+            };
+            summary = format!("{}\n\n{}", summary, loudness);
+        }
+
+        // The media facts and stream selection are best effort: without
+        // ffprobe, or for several inputs, the plain summary is enough.
+        let Some(input) = single_local_input(&argv) else {
+            return Ok(PreviewContent::Text(summary));
+        };
+        let Ok(media) = probe_media(&input).await else {
+            return Ok(PreviewContent::Text(summary));
+        };
+        if stream_selection_input(&argv).is_some() {
+            if let Some(selection) =
+                build_stream_selection(&argv, &input, summary.clone(), media.streams.clone())
+            {
+                return Ok(PreviewContent::StreamSelection(selection));
+            }
+        }
+        let mut sections = vec![PreviewContent::Text(summary)];
+        sections.extend(media.preview(&input));
+        Ok(PreviewContent::Sections(sections))
     }
 
     async fn execute(&self, cmd: &str) -> Result<String> {
@@ -116,8 +267,24 @@ Your goal is to generate a valid `ffmpeg` command.
         cmd: &str,
         progress_tx: tokio::sync::mpsc::Sender<crate::Progress>,
     ) -> Result<String> {
-        let argv = parse_and_validate_command(cmd, "ffmpeg")?;
-        let cwd = std::env::current_dir()?;
+        let mut argv = parse_and_validate_command(cmd, "ffmpeg")?;
+        let cwd = working_dir()?;
+        if let Some(plan) = loudnorm_plan(&argv) {
+            let _ = progress_tx
+                .send(crate::Progress {
+                    percentage: None,
+                    message: "Pass 1/2: measuring loudness".to_string(),
+                })
+                .await;
+            let measured = measure_loudness(&plan, &argv, &cwd).await?;
+            let _ = progress_tx
+                .send(crate::Progress {
+                    percentage: None,
+                    message: format!("Pass 2/2: normalizing from {} LUFS", measured.input_i),
+                })
+                .await;
+            argv = plan.apply_argv(&argv, &measured);
+        }
         let mut child = spawn_checked_piped(&argv, &cwd)?;
 
         // FFmpeg writes progress to stderr
@@ -185,9 +352,983 @@ Your goal is to generate a valid `ffmpeg` command.
                 combined
             })
         } else {
-            Err(anyhow::anyhow!(err_output))
+            Err(exit_failure(status, err_output))
+        }
+    }
+}
+
+/// The single input file of `argv`, when the command is simple enough for
+/// its `-map` flags to be rewritten safely (one `-i`, no filter graph).
+fn stream_selection_input(argv: &[String]) -> Option<String> {
+    if argv
+        .iter()
+        .any(|a| a == "-filter_complex" || a == "-lavfi" || a.starts_with("-filter_complex_script"))
+    {
+        return None;
+    }
+    single_local_input(argv)
+}
+
+/// The input of `argv` when it reads exactly one local file.
+fn single_local_input(argv: &[String]) -> Option<String> {
+    let inputs: Vec<&String> = argv
+        .windows(2)
+        .filter(|w| w[0] == "-i")
+        .map(|w| &w[1])
+        .collect();
+    match inputs.as_slice() {
+        [input] if !input.contains("://") && input.as_str() != "-" => Some((*input).clone()),
+        _ => None,
+    }
+}
+
+pub async fn probe_streams(input: &str) -> Result<Vec<MediaStream>> {
+    parse_ffprobe_streams(&run_ffprobe(&["-show_streams"], input).await?)
+}
+
+/// Whether ffprobe can read `input`'s container; errors with its message.
+pub async fn probe_format(input: &str) -> Result<()> {
+    run_ffprobe(&["-show_format"], input).await.map(|_| ())
+}
+
+/// Container facts and streams of one input, for the preview.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MediaInfo {
+    pub container: Option<String>,
+    pub duration: Option<f64>,
+    pub size: Option<u64>,
+    pub bit_rate: Option<u64>,
+    pub streams: Vec<MediaStream>,
+}
+
+impl MediaInfo {
+    /// A key/value group for the container and a table of the streams.
+    pub fn preview(&self, input: &str) -> Vec<PreviewContent> {
+        let mut out = vec![self.summary(input)];
+        if !self.streams.is_empty() {
+            out.push(PreviewContent::Table {
+                title: "STREAMS".to_string(),
+                headers: ["#", "TYPE", "CODEC", "LANG", "DETAIL", "TITLE"]
+                    .map(String::from)
+                    .to_vec(),
+                rows: self.streams.iter().map(stream_row).collect(),
+            });
+        }
+        out
+    }
+
+    /// The container's format, duration, size and bitrate.
+    pub fn summary(&self, input: &str) -> PreviewContent {
+        let mut entries = Vec::new();
+        if let Some(container) = &self.container {
+            entries.push(("Container".to_string(), container.clone()));
+        }
+        if let Some(duration) = self.duration {
+            entries.push(("Duration".to_string(), format_timestamp(duration)));
+        }
+        if let Some(size) = self.size {
+            entries.push((
+                "Size".to_string(),
+                format!("{:.1} MB", size as f64 / 1_000_000.0),
+            ));
+        }
+        if let Some(bit_rate) = self.bit_rate {
+            entries.push(("Bitrate".to_string(), format_bit_rate(bit_rate)));
+        }
+        PreviewContent::KeyValue(vec![KeyValueSection {
+            title: format!("INPUT {}", input),
+            entries,
+        }])
+    }
+}
+
+pub fn format_bit_rate(bits_per_second: u64) -> String {
+    format!("{} kb/s", bits_per_second / 1000)
+}
+
+fn stream_row(stream: &MediaStream) -> Vec<String> {
+    let title = match (&stream.title, stream.default) {
+        (Some(title), true) => format!("{} (default)", title),
+        (Some(title), false) => title.clone(),
+        (None, true) => "(default)".to_string(),
+        (None, false) => String::new(),
+    };
+    vec![
+        stream.index.to_string(),
+        stream.kind.label().to_string(),
+        stream.codec.clone(),
+        stream.language.clone().unwrap_or_default(),
+        stream.detail(),
+        title,
+    ]
+}
+
+pub async fn probe_media(input: &str) -> Result<MediaInfo> {
+    parse_ffprobe_media(&run_ffprobe(&["-show_streams", "-show_format"], input).await?)
+}
+
+/// Runs `ffprobe` with `args` on `input`, inside the active sandbox like
+/// any other command, and returns its JSON output.
+async fn run_ffprobe(args: &[&str], input: &str) -> Result<String> {
+    let argv: Vec<String> = ["ffprobe", "-v", "error"]
+        .into_iter()
+        .chain(args.iter().copied())
+        .chain(["-of", "json", "--", input])
+        .map(String::from)
+        .collect();
+    let output = spawn_checked_async(&argv, working_dir()?)
+        .await
+        .context("Failed to run ffprobe")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "ffprobe failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[derive(Deserialize)]
+struct ProbeOutput {
+    #[serde(default)]
+    streams: Vec<ProbeStream>,
+    format: Option<ProbeFormat>,
+}
+
+#[derive(Deserialize)]
+struct ProbeStream {
+    index: u32,
+    #[serde(default)]
+    codec_type: String,
+    #[serde(default)]
+    codec_name: String,
+    channels: Option<u32>,
+    width: Option<u32>,
+    height: Option<u32>,
+    duration: Option<String>,
+    bit_rate: Option<String>,
+    #[serde(default)]
+    tags: std::collections::HashMap<String, String>,
+    #[serde(default)]
+    disposition: std::collections::HashMap<String, i64>,
+}
+
+fn parse_ffprobe_streams(json: &str) -> Result<Vec<MediaStream>> {
+    parse_ffprobe_media(json).map(|media| media.streams)
+}
+
+pub(crate) fn parse_ffprobe_media(json: &str) -> Result<MediaInfo> {
+    let probe: ProbeOutput = serde_json::from_str(json).context("Invalid ffprobe JSON")?;
+    let format = probe.format.unwrap_or_default();
+    let mut per_kind = std::collections::HashMap::new();
+    let streams = probe
+        .streams
+        .into_iter()
+        .map(|s| {
+            let kind = match s.codec_type.as_str() {
+                "video" => StreamKind::Video,
+                "audio" => StreamKind::Audio,
+                "subtitle" => StreamKind::Subtitle,
+                _ => StreamKind::Other,
+            };
+            let counter = per_kind.entry(kind).or_insert(0u32);
+            let kind_index = *counter;
+            *counter += 1;
+            let tag = |key: &str| {
+                s.tags
+                    .iter()
+                    .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                    .map(|(_, v)| v.clone())
+                    .filter(|v| !v.is_empty() && v != "und")
+            };
+            MediaStream {
+                index: s.index,
+                kind,
+                kind_index,
+                codec: s.codec_name,
+                language: tag("language"),
+                title: tag("title"),
+                channels: s.channels,
+                resolution: s.width.zip(s.height),
+                default: s.disposition.get("default").copied().unwrap_or(0) != 0,
+                duration_ms: s
+                    .duration
+                    .and_then(|v| v.parse::<f64>().ok())
+                    .map(|secs| (secs * 1000.0).round() as u64),
+                bit_rate: s.bit_rate.and_then(|v| v.parse().ok()),
+            }
+        })
+        .collect();
+    Ok(MediaInfo {
+        container: format.format_long_name.filter(|name| !name.is_empty()),
+        duration: format.duration.and_then(|v| v.parse().ok()),
+        size: format.size.and_then(|v| v.parse().ok()),
+        bit_rate: format.bit_rate.and_then(|v| v.parse().ok()),
+        streams,
+    })
+}
+
+fn build_stream_selection(
+    argv: &[String],
+    input: &str,
+    summary: String,
+    streams: Vec<MediaStream>,
+) -> Option<StreamSelection> {
+    let audio = streams
+        .iter()
+        .filter(|s| s.kind == StreamKind::Audio)
+        .count();
+    let subtitles = streams
+        .iter()
+        .filter(|s| s.kind == StreamKind::Subtitle)
+        .count();
+    if audio < 2 && subtitles < 2 {
+        return None;
+    }
+    let selectable: Vec<MediaStream> = streams
+        .into_iter()
+        .filter(|s| matches!(s.kind, StreamKind::Audio | StreamKind::Subtitle))
+        .collect();
+    let selected = initial_selection(argv, &selectable);
+    Some(StreamSelection {
+        summary,
+        input: input.to_string(),
+        streams: selectable,
+        selected,
+    })
+}
+
+/// Which selectable streams `argv` keeps as written. Without any `-map`,
+/// ffmpeg's default picks one audio stream (the default-flagged one here)
+/// and, for most containers, no subtitles.
+fn initial_selection(argv: &[String], streams: &[MediaStream]) -> Vec<bool> {
+    let maps = map_specs(argv);
+    if maps.is_empty() {
+        let pick = streams
+            .iter()
+            .position(|s| s.kind == StreamKind::Audio && s.default)
+            .or_else(|| streams.iter().position(|s| s.kind == StreamKind::Audio));
+        return (0..streams.len()).map(|i| Some(i) == pick).collect();
+    }
+    streams
+        .iter()
+        .map(|stream| {
+            let mut kept = false;
+            for spec in &maps {
+                let (negative, spec) = match spec.strip_prefix('-') {
+                    Some(rest) => (true, rest),
+                    None => (false, spec.as_str()),
+                };
+                if map_selects(spec, stream) {
+                    kept = !negative;
+                }
+            }
+            kept
+        })
+        .collect()
+}
+
+fn map_specs(argv: &[String]) -> Vec<String> {
+    argv.windows(2)
+        .filter(|w| w[0] == "-map")
+        .map(|w| w[1].clone())
+        .collect()
+}
+
+/// Whether a positive input-0 map specifier (`0`, `0:a`, `0:s:1`, `0:3`,
+/// optionally with a trailing `?`) selects `stream`.
+fn map_selects(spec: &str, stream: &MediaStream) -> bool {
+    let spec = spec.trim_end_matches('?');
+    let mut parts = spec.split(':');
+    if parts.next() != Some("0") {
+        return false;
+    }
+    match (parts.next(), parts.next()) {
+        (None, _) => true,
+        (Some(n), None) if n.chars().all(|c| c.is_ascii_digit()) => {
+            n.parse::<u32>().ok() == Some(stream.index)
+        }
+        (Some(kind), k) => {
+            let kind_matches = match kind {
+                "a" => stream.kind == StreamKind::Audio,
+                "s" => stream.kind == StreamKind::Subtitle,
+                _ => false,
+            };
+            kind_matches
+                && k.map(|k| k.parse::<u32>().ok() == Some(stream.kind_index))
+                    .unwrap_or(true)
+        }
+    }
+}
+
+/// Rewrites the `-map` flags of `cmd` so exactly the `keep` streams of
+/// `selection` are copied, leaving video (and other inputs' maps) alone.
+pub fn apply_stream_selection(
+    cmd: &str,
+    selection: &StreamSelection,
+    keep: &[bool],
+) -> Result<String> {
+    let argv = parse_and_validate_command(cmd, "ffmpeg")?;
+    if stream_selection_input(&argv).as_deref() != Some(selection.input.as_str()) {
+        return Err(anyhow!(
+            "Command no longer has a single input to select streams from"
+        ));
+    }
+    if argv.len() < 2 {
+        return Err(anyhow!("Command has no output file"));
+    }
+
+    let mut out: Vec<String> = Vec::with_capacity(argv.len() + keep.len() * 2);
+    let mut kept_other_map = false;
+    let mut idx = 0;
+    while idx < argv.len() {
+        if argv[idx] == "-map" && idx + 1 < argv.len() {
+            let spec = &argv[idx + 1];
+            let target = spec.strip_prefix('-').unwrap_or(spec);
+            let touches_selection = target.trim_end_matches('?') == "0"
+                || selection.streams.iter().any(|s| map_selects(target, s));
+            if !touches_selection {
+                out.push(argv[idx].clone());
+                out.push(spec.clone());
+                kept_other_map |= !spec.starts_with('-');
+            }
+            idx += 2;
+            continue;
+        }
+        out.push(argv[idx].clone());
+        idx += 1;
+    }
+
+    let output = out
+        .pop()
+        .ok_or_else(|| anyhow!("Command has no output file"))?;
+    if !kept_other_map {
+        // Explicit maps switch off ffmpeg's automatic selection, so keep the
+        // video explicitly (`V` skips attached cover art).
+        out.push("-map".to_string());
+        out.push("0:V?".to_string());
+    }
+    let mut keeps_subtitles = false;
+    for (stream, _) in selection
+        .streams
+        .iter()
+        .zip(keep.iter())
+        .filter(|(_, keep)| **keep)
+    {
+        out.push("-map".to_string());
+        out.push(format!("0:{}", stream.index));
+        keeps_subtitles |= stream.kind == StreamKind::Subtitle;
+    }
+    let lower = output.to_lowercase();
+    let mp4_family = [".mp4", ".m4v", ".mov"]
+        .iter()
+        .any(|ext| lower.ends_with(ext));
+    if keeps_subtitles && mp4_family && !out.iter().any(|a| a == "-c:s" || a == "-scodec") {
+        out.push("-c:s".to_string());
+        out.push("mov_text".to_string());
+    }
+    out.push(output);
+    Ok(shell_words::join(&out))
+}
+
+/// A chapter of the input as reported by `ffprobe -show_chapters`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chapter {
+    pub start: f64,
+    pub end: f64,
+    pub title: Option<String>,
+}
+
+/// Length and chapters of the input; either may be unknown.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MediaTimeline {
+    pub duration: Option<f64>,
+    pub chapters: Vec<Chapter>,
+}
+
+pub async fn probe_timeline(input: &str) -> Result<MediaTimeline> {
+    let json = run_ffprobe(
+        &["-show_chapters", "-show_entries", "format=duration"],
+        input,
+    )
+    .await?;
+    parse_ffprobe_timeline(&json)
+}
+
+#[derive(Deserialize)]
+struct ProbeTimeline {
+    #[serde(default)]
+    chapters: Vec<ProbeChapter>,
+    format: Option<ProbeFormat>,
+}
+
+#[derive(Deserialize)]
+struct ProbeChapter {
+    start_time: String,
+    end_time: String,
+    #[serde(default)]
+    tags: std::collections::HashMap<String, String>,
+}
+
+#[derive(Deserialize, Default)]
+struct ProbeFormat {
+    duration: Option<String>,
+    format_long_name: Option<String>,
+    size: Option<String>,
+    bit_rate: Option<String>,
+}
+
+fn parse_ffprobe_timeline(json: &str) -> Result<MediaTimeline> {
+    let probe: ProbeTimeline = serde_json::from_str(json).context("Invalid ffprobe JSON")?;
+    let chapters = probe
+        .chapters
+        .into_iter()
+        .filter_map(|c| {
+            Some(Chapter {
+                start: c.start_time.parse().ok()?,
+                end: c.end_time.parse().ok()?,
+                title: c
+                    .tags
+                    .iter()
+                    .find(|(k, _)| k.eq_ignore_ascii_case("title"))
+                    .map(|(_, v)| v.clone())
+                    .filter(|v| !v.is_empty()),
+            })
+        })
+        .collect();
+    Ok(MediaTimeline {
+        duration: probe
+            .format
+            .and_then(|f| f.duration)
+            .and_then(|d| d.parse().ok()),
+        chapters,
+    })
+}
+
+/// Seconds in an ffmpeg time duration: `[HH:]MM:SS[.m...]`, or a number of
+/// seconds with an optional `s`, `ms` or `us` unit. Negative times are
+/// rejected since none of the flags checked here take one.
+pub fn parse_timestamp(value: &str) -> Option<f64> {
+    fn digits(part: &str, fraction: bool) -> Option<f64> {
+        let valid = !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_digit() || (fraction && c == '.'));
+        if valid {
+            part.parse().ok()
+        } else {
+            None
+        }
+    }
+
+    let value = value.trim();
+    if value.contains(':') {
+        let parts: Vec<&str> = value.split(':').collect();
+        let (seconds, whole) = parts.split_last()?;
+        let seconds = digits(seconds, true)?;
+        if whole.len() > 2 || seconds >= 60.0 {
+            return None;
+        }
+        let mut total = 0.0;
+        for (i, part) in whole.iter().enumerate() {
+            let part = digits(part, false)?;
+            // Minutes only wrap when hours are given.
+            if whole.len() == 2 && i == 1 && part >= 60.0 {
+                return None;
+            }
+            total = total * 60.0 + part;
+        }
+        return Some(total * 60.0 + seconds);
+    }
+    let (number, scale) = if let Some(n) = value.strip_suffix("ms") {
+        (n, 1e-3)
+    } else if let Some(n) = value.strip_suffix("us") {
+        (n, 1e-6)
+    } else {
+        (value.strip_suffix('s').unwrap_or(value), 1.0)
+    };
+    digits(number, true).map(|n| n * scale)
+}
+
+/// `seconds` as `HH:MM:SS`, with milliseconds when there are any.
+pub fn format_timestamp(seconds: f64) -> String {
+    let millis = (seconds * 1000.0).round() as u64;
+    let (h, m, s, ms) = (
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000,
+    );
+    if ms == 0 {
+        format!("{:02}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{:02}:{:02}:{:02}.{:03}", h, m, s, ms)
+    }
+}
+
+/// The cut window and segment-muxer options of a command, in seconds of
+/// the input.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct TimingFlags {
+    start: Option<f64>,
+    end: Option<f64>,
+    /// `-f segment` (or `ssegment`).
+    segment: bool,
+    segment_time: Option<f64>,
+    /// Split points relative to the start of the output.
+    segment_times: Vec<f64>,
+}
+
+impl TimingFlags {
+    fn cuts(&self) -> bool {
+        self.start.is_some() || self.end.is_some()
+    }
+}
+
+/// Reads and checks the `-ss`/`-to`/`-t` and `-segment_time(s)` flags.
+/// Flags before the last `-i` apply to the input: there `-to` is a position
+/// in the source, while after it `-to` counts from the input seek point.
+fn timing_flags(argv: &[String]) -> Result<TimingFlags> {
+    let time = |flag: &str, value: &str| {
+        parse_timestamp(value).ok_or_else(|| anyhow!("{} {} is not a valid time", flag, value))
+    };
+    let last_input = argv.iter().rposition(|a| a == "-i").unwrap_or(0);
+    let mut input = (None, None, None);
+    let mut output = (None, None, None);
+    let mut flags = TimingFlags::default();
+    let mut segment_flag = None;
+
+    for (idx, pair) in argv.windows(2).enumerate().skip(1) {
+        let (flag, value) = (pair[0].as_str(), pair[1].as_str());
+        let side = if idx < last_input {
+            &mut input
+        } else {
+            &mut output
+        };
+        match flag {
+            "-ss" => side.0 = Some(time(flag, value)?),
+            "-to" => side.1 = Some(time(flag, value)?),
+            "-t" => side.2 = Some(time(flag, value)?),
+            "-f" => flags.segment = matches!(value, "segment" | "ssegment" | "stream_segment"),
+            "-segment_time" => {
+                let step = time(flag, value)?;
+                if step <= 0.0 {
+                    return Err(anyhow!("-segment_time must be longer than zero"));
+                }
+                flags.segment_time = Some(step);
+                segment_flag = Some(flag);
+            }
+            "-segment_times" => {
+                let times = value
+                    .split(',')
+                    .map(|t| time(flag, t))
+                    .collect::<Result<Vec<f64>>>()?;
+                if times.windows(2).any(|w| w[1] <= w[0]) {
+                    return Err(anyhow!(
+                        "-segment_times {} is not in ascending order",
+                        value
+                    ));
+                }
+                flags.segment_times = times;
+                segment_flag = Some(flag);
+            }
+            _ => {}
         }
     }
+
+    for (to, t) in [(input.1, input.2), (output.1, output.2)] {
+        if to.is_some() && t.is_some() {
+            return Err(anyhow!("Both -to and -t are set; use only one of them"));
+        }
+    }
+    let seek = input.0.unwrap_or(0.0);
+    let start = seek + output.0.unwrap_or(0.0);
+    let ends = [
+        input.1,
+        input.2.map(|t| seek + t),
+        output.1.map(|to| seek + to),
+        output.2.map(|t| start + t),
+    ];
+    flags.end = ends.into_iter().flatten().reduce(f64::min);
+    if input.0.is_some() || output.0.is_some() {
+        flags.start = Some(start);
+    }
+    if let Some(end) = flags.end {
+        if end <= start {
+            return Err(anyhow!(
+                "The cut ends at {} before it starts at {}",
+                format_timestamp(end),
+                format_timestamp(start)
+            ));
+        }
+    }
+
+    if let Some(flag) = segment_flag.filter(|_| !flags.segment) {
+        return Err(anyhow!("{} only works with -f segment", flag));
+    }
+    if flags.segment {
+        let output = argv.last().map(String::as_str).unwrap_or_default();
+        if !segment_pattern().is_match(output) {
+            return Err(anyhow!(
+                "Segment output {} needs a number pattern such as %03d",
+                output
+            ));
+        }
+    }
+    Ok(flags)
+}
+
+fn segment_pattern() -> regex::Regex {
+    regex::Regex::new(r"%(\d*)d").unwrap()
+}
+
+/// Segments listed in the preview at most; the rest are counted.
+const MAX_LISTED_SEGMENTS: usize = 20;
+/// Split points planned at most, so a tiny `-segment_time` stays cheap.
+const MAX_PLANNED_SEGMENTS: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq)]
+struct PlannedSegment {
+    start: f64,
+    /// `None` when the input's duration is unknown.
+    end: Option<f64>,
+    output: String,
+}
+
+/// The files `argv` writes and the part of the input each one covers.
+fn plan_segments(
+    argv: &[String],
+    flags: &TimingFlags,
+    duration: Option<f64>,
+) -> Vec<PlannedSegment> {
+    let output = argv.last().cloned().unwrap_or_default();
+    let start = flags.start.unwrap_or(0.0);
+    let end = match (flags.end, duration) {
+        (Some(end), Some(duration)) => Some(end.min(duration)),
+        (end, duration) => end.or(duration),
+    };
+    if !flags.segment {
+        return vec![PlannedSegment { start, end, output }];
+    }
+
+    let mut splits: Vec<f64> = if !flags.segment_times.is_empty() {
+        flags.segment_times.iter().map(|t| start + t).collect()
+    } else if let Some(end) = end {
+        // ffmpeg splits every 2 seconds unless told otherwise.
+        let step = flags.segment_time.unwrap_or(2.0);
+        (1..MAX_PLANNED_SEGMENTS)
+            .map(|i| start + step * i as f64)
+            .take_while(|t| *t < end)
+            .collect()
+    } else {
+        Vec::new()
+    };
+    splits.retain(|t| *t > start && end.is_none_or(|end| *t < end));
+
+    let starts = std::iter::once(start).chain(splits.iter().copied());
+    let ends = splits.iter().copied().map(Some).chain(std::iter::once(end));
+    starts
+        .zip(ends)
+        .enumerate()
+        .map(|(i, (start, end))| PlannedSegment {
+            start,
+            end,
+            output: segment_name(&output, i),
+        })
+        .collect()
+}
+
+/// `pattern` with its `%d`/`%03d` replaced by `index`.
+fn segment_name(pattern: &str, index: usize) -> String {
+    segment_pattern()
+        .replace(pattern, |caps: &regex::Captures| {
+            let width: usize = caps[1].parse().unwrap_or(0);
+            if caps[1].starts_with('0') {
+                format!("{:0width$}", index, width = width)
+            } else {
+                format!("{:width$}", index, width = width)
+            }
+        })
+        .into_owned()
+}
+
+/// Lists what a cut or split writes, naming the chapter each piece starts
+/// in. For splits that don't follow the chapters, the chapter starts are
+/// offered as `-segment_times`. Fails when the cut starts past the end.
+fn describe_segments(
+    argv: &[String],
+    flags: &TimingFlags,
+    timeline: &MediaTimeline,
+    input: &str,
+) -> Result<String> {
+    let start = flags.start.unwrap_or(0.0);
+    if let Some(duration) = timeline.duration.filter(|d| start >= *d) {
+        return Err(anyhow!(
+            "-ss {} is past the end of {} ({})",
+            format_timestamp(start),
+            input,
+            format_timestamp(duration)
+        ));
+    }
+    let chapter_at = |at: f64| {
+        timeline
+            .chapters
+            .iter()
+            .enumerate()
+            .find(|(_, c)| at >= c.start && at < c.end)
+            .map(|(i, c)| {
+                c.title
+                    .clone()
+                    .unwrap_or_else(|| format!("Chapter {}", i + 1))
+            })
+    };
+
+    let open_ended = flags.end.is_none() && timeline.duration.is_none();
+    if flags.segment && flags.segment_times.is_empty() && open_ended {
+        return Ok(format!(
+            "Segments: one every {} from {} (the input's length is unknown)",
+            format_timestamp(flags.segment_time.unwrap_or(2.0)),
+            format_timestamp(start)
+        ));
+    }
+    let segments = plan_segments(argv, flags, timeline.duration);
+    let mut out = format!("Segments ({}):", segments.len());
+    for (i, segment) in segments.iter().take(MAX_LISTED_SEGMENTS).enumerate() {
+        let end = segment
+            .end
+            .map(format_timestamp)
+            .unwrap_or_else(|| "end".to_string());
+        out.push_str(&format!(
+            "\n  {}. {} - {}  {}",
+            i + 1,
+            format_timestamp(segment.start),
+            end,
+            segment.output
+        ));
+        if let Some(chapter) = chapter_at(segment.start) {
+            out.push_str(&format!("  ({})", chapter));
+        }
+    }
+    if segments.len() > MAX_LISTED_SEGMENTS {
+        out.push_str(&format!(
+            "\n  ... and {} more",
+            segments.len() - MAX_LISTED_SEGMENTS
+        ));
+    }
+
+    let chapter_splits: Vec<f64> = timeline
+        .chapters
+        .iter()
+        .map(|c| c.start - start)
+        .filter(|t| *t > 0.0)
+        .collect();
+    let follows_chapters = chapter_splits.len() == flags.segment_times.len()
+        && chapter_splits
+            .iter()
+            .zip(&flags.segment_times)
+            .all(|(a, b)| (a - b).abs() < 0.5);
+    if flags.segment && !chapter_splits.is_empty() && !follows_chapters {
+        let times: Vec<String> = chapter_splits
+            .iter()
+            .map(|t| ((t * 1000.0).round() / 1000.0).to_string())
+            .collect();
+        out.push_str(&format!(
+            "\nThe input has {} chapters; split on them with -segment_times {}",
+            timeline.chapters.len(),
+            times.join(",")
+        ));
+        for (i, chapter) in timeline.chapters.iter().enumerate() {
+            out.push_str(&format!(
+                "\n  {}. {}  {}",
+                i + 1,
+                format_timestamp(chapter.start),
+                chapter.title.as_deref().unwrap_or("")
+            ));
+        }
+    }
+    Ok(out)
+}
+
+/// Segment list for commands that cut or split their input, `None` for the
+/// rest. Probing is best effort: without ffprobe the list has no end times
+/// or chapters.
+async fn segment_preview(argv: &[String], input: &str) -> Result<Option<String>> {
+    let flags = timing_flags(argv)?;
+    if !flags.cuts() && !flags.segment {
+        return Ok(None);
+    }
+    let timeline = probe_timeline(input).await.unwrap_or_default();
+    describe_segments(argv, &flags, &timeline, input).map(Some)
+}
+
+/// An `-af loudnorm` that has no measured values yet. Such a command runs
+/// as a plan of two passes: an analysis pass into the null muxer, then the
+/// command itself with the measured values added to the filter.
+#[derive(Debug, Clone, PartialEq)]
+struct LoudnormPlan {
+    /// Position of the audio filter chain in argv.
+    chain_at: usize,
+    /// Position of `loudnorm` in the chain.
+    filter_at: usize,
+    /// The filter's own options, such as `I=-16`.
+    options: Vec<String>,
+}
+
+/// What the analysis pass measured, as `loudnorm` prints it.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct LoudnessMeasurement {
+    input_i: String,
+    input_tp: String,
+    input_lra: String,
+    input_thresh: String,
+    target_offset: String,
+}
+
+/// The two-pass plan for `argv`, when it normalizes a single local input
+/// with a `loudnorm` that hasn't been measured.
+fn loudnorm_plan(argv: &[String]) -> Option<LoudnormPlan> {
+    single_local_input(argv)?;
+    let chain_at = argv
+        .iter()
+        .position(|a| a == "-af" || a.starts_with("-filter:a"))?
+        + 1;
+    let filters: Vec<&str> = argv.get(chain_at)?.split(',').map(str::trim).collect();
+    let filter_at = filters
+        .iter()
+        .position(|f| *f == "loudnorm" || f.starts_with("loudnorm="))?;
+    let options: Vec<String> = filters[filter_at]
+        .trim_start_matches("loudnorm")
+        .trim_start_matches('=')
+        .split(':')
+        .filter(|option| !option.is_empty())
+        .map(String::from)
+        .collect();
+    let measured = options.iter().any(|option| {
+        let key = option.to_ascii_lowercase();
+        key.starts_with("measured_") || key.starts_with("offset=")
+    });
+    (!measured).then_some(LoudnormPlan {
+        chain_at,
+        filter_at,
+        options,
+    })
+}
+
+impl LoudnormPlan {
+    /// The filter's option named `key`, else `loudnorm`'s default for it.
+    fn target(&self, key: &str, default: &str) -> String {
+        self.options
+            .iter()
+            .filter_map(|option| option.split_once('='))
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.to_string())
+            .unwrap_or_else(|| default.to_string())
+    }
+
+    /// `argv` with `loudnorm` given `options` instead.
+    fn with_options(&self, argv: &[String], options: &[String]) -> Vec<String> {
+        let mut argv = argv.to_vec();
+        let mut filters: Vec<String> = argv[self.chain_at]
+            .split(',')
+            .map(|f| f.trim().to_string())
+            .collect();
+        filters[self.filter_at] = format!("loudnorm={}", options.join(":"));
+        argv[self.chain_at] = filters.join(",");
+        argv
+    }
+
+    /// Pass 1: the same input and filters up to the output, which is
+    /// replaced by the null muxer, with `loudnorm` printing what it measured.
+    fn analysis_argv(&self, argv: &[String]) -> Vec<String> {
+        let mut options: Vec<String> = self
+            .options
+            .iter()
+            .filter(|option| !option.to_ascii_lowercase().starts_with("print_format="))
+            .cloned()
+            .collect();
+        options.push("print_format=json".to_string());
+        let mut pass = self.with_options(argv, &options);
+        pass.pop();
+        pass.splice(1..1, ["-hide_banner", "-nostats"].map(String::from));
+        pass.extend(["-vn", "-sn", "-dn", "-f", "null", "-"].map(String::from));
+        pass
+    }
+
+    /// Pass 2: `argv` with the measured values added, in linear mode so the
+    /// whole file gets one gain where the targets allow it.
+    fn apply_argv(&self, argv: &[String], measured: &LoudnessMeasurement) -> Vec<String> {
+        let mut options = self.options.clone();
+        options.extend([
+            format!("measured_I={}", measured.input_i),
+            format!("measured_TP={}", measured.input_tp),
+            format!("measured_LRA={}", measured.input_lra),
+            format!("measured_thresh={}", measured.input_thresh),
+            format!("offset={}", measured.target_offset),
+            "linear=true".to_string(),
+        ]);
+        self.with_options(argv, &options)
+    }
+
+    /// The measured values next to the targets, for the preview.
+    fn describe(&self, measured: &LoudnessMeasurement) -> String {
+        format!(
+            "Loudness, measured in pass 1 of 2:\n  \
+             Integrated {} LUFS (target {})\n  \
+             True peak {} dBTP (target {})\n  \
+             Range {} LU (target {})\n  \
+             Threshold {} LUFS\n\
+             Pass 2 applies these with linear loudnorm.",
+            measured.input_i,
+            self.target("I", "-24"),
+            measured.input_tp,
+            self.target("TP", "-2"),
+            measured.input_lra,
+            self.target("LRA", "7"),
+            measured.input_thresh,
+        )
+    }
+}
+
+/// Runs the analysis pass of `plan`, once per input and filter chain: the
+/// preview's measurement is reused when the command runs.
+async fn measure_loudness(
+    plan: &LoudnormPlan,
+    argv: &[String],
+    cwd: &Path,
+) -> Result<LoudnessMeasurement> {
+    static MEASURED: OnceLock<Mutex<HashMap<String, LoudnessMeasurement>>> = OnceLock::new();
+    let pass = plan.analysis_argv(argv);
+    let modified = single_local_input(argv)
+        .and_then(|input| std::fs::metadata(cwd.join(input)).ok())
+        .and_then(|meta| meta.modified().ok());
+    let key = format!("{}\0{}\0{:?}", cwd.display(), pass.join("\0"), modified);
+    let cache = MEASURED.get_or_init(Default::default);
+    if let Some(measured) = cache.lock().unwrap().get(&key) {
+        return Ok(measured.clone());
+    }
+
+    let output = spawn_checked_async(&pass, cwd).await?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        return Err(exit_failure(output.status, stderr.into_owned()));
+    }
+    let measured = parse_loudnorm_json(&stderr)?;
+    cache.lock().unwrap().insert(key, measured.clone());
+    Ok(measured)
+}
+
+/// The JSON block `loudnorm=print_format=json` writes at the end of stderr.
+fn parse_loudnorm_json(stderr: &str) -> Result<LoudnessMeasurement> {
+    let tail = stderr
+        .rfind("Parsed_loudnorm")
+        .map(|at| &stderr[at..])
+        .ok_or_else(|| anyhow!("ffmpeg printed no loudness measurement"))?;
+    let json = tail
+        .find('{')
+        .zip(tail.rfind('}'))
+        .map(|(start, end)| &tail[start..=end])
+        .ok_or_else(|| anyhow!("ffmpeg printed no loudness measurement"))?;
+    serde_json::from_str(json).context("Invalid loudnorm JSON")
 }
 
 #[cfg(test)]
@@ -196,13 +1337,212 @@ mod tests {
 
     #[test]
     fn validate_rejects_shell_injection() {
-        let plugin = FFmpegPlugin;
+        let plugin = FFmpegPlugin::default();
         assert!(!plugin.validate_command("ffmpeg -i a.mp4 b.mp4; rm -rf /"));
     }
 
     #[test]
     fn validate_allows_quoted_paths() {
-        let plugin = FFmpegPlugin;
+        let plugin = FFmpegPlugin::default();
         assert!(plugin.validate_command("ffmpeg -i \"in file.mp4\" \"out file.mp4\""));
     }
+
+    const PROBE_JSON: &str = r#"{"streams":[
+        {"index":0,"codec_type":"video","codec_name":"h264","disposition":{"default":1}},
+        {"index":1,"codec_type":"audio","codec_name":"aac","channels":2,
+         "tags":{"language":"eng"},"disposition":{"default":1}},
+        {"index":2,"codec_type":"audio","codec_name":"ac3","channels":6,
+         "tags":{"language":"jpn","title":"Original"},"disposition":{"default":0}},
+        {"index":3,"codec_type":"subtitle","codec_name":"subrip","tags":{"language":"und"}}
+    ]}"#;
+
+    fn argv(cmd: &str) -> Vec<String> {
+        shell_words::split(cmd).unwrap()
+    }
+
+    #[test]
+    fn parses_ffprobe_streams_and_initial_selection() {
+        let streams = parse_ffprobe_streams(PROBE_JSON).unwrap();
+        assert_eq!(streams.len(), 4);
+        assert_eq!(streams[2].kind_index, 1);
+        assert_eq!(streams[2].describe(), "#2 audio ac3 [jpn] 6ch \"Original\"");
+        assert_eq!(streams[3].language, None);
+
+        let cmd = argv("ffmpeg -i in.mkv -c copy out.mkv");
+        let selection = build_stream_selection(&cmd, "in.mkv", String::new(), streams.clone())
+            .expect("two audio streams");
+        assert_eq!(selection.streams.len(), 3);
+        assert_eq!(selection.selected, vec![true, false, false]);
+
+        let mapped = argv("ffmpeg -i in.mkv -map 0 -map -0:a:0 out.mkv");
+        assert_eq!(
+            initial_selection(&mapped, &selection.streams),
+            vec![false, true, true]
+        );
+        assert_eq!(
+            stream_selection_input(&argv("ffmpeg -i a.mp4 -i b.png -filter_complex x out.mp4")),
+            None
+        );
+    }
+
+    #[test]
+    fn media_preview_tabulates_streams() {
+        let json = r#"{"streams":[
+            {"index":0,"codec_type":"video","codec_name":"h264","width":1920,"height":1080,
+             "disposition":{"default":1}},
+            {"index":1,"codec_type":"audio","codec_name":"aac","channels":2,
+             "tags":{"language":"eng"}}
+        ],"format":{"format_long_name":"Matroska / WebM","duration":"61.5",
+                    "size":"12500000","bit_rate":"1626000"}}"#;
+        let media = parse_ffprobe_media(json).unwrap();
+        let preview = media.preview("in.mkv");
+        let PreviewContent::KeyValue(groups) = &preview[0] else {
+            panic!("expected key/value group");
+        };
+        assert_eq!(groups[0].title, "INPUT in.mkv");
+        assert_eq!(
+            groups[0].entries[1..],
+            [
+                ("Duration".to_string(), format_timestamp(61.5)),
+                ("Size".to_string(), "12.5 MB".to_string()),
+                ("Bitrate".to_string(), "1626 kb/s".to_string()),
+            ]
+        );
+        let PreviewContent::Table { rows, .. } = &preview[1] else {
+            panic!("expected stream table");
+        };
+        assert_eq!(
+            rows[0],
+            ["0", "video", "h264", "", "1920x1080", "(default)"]
+        );
+        assert_eq!(rows[1], ["1", "audio", "aac", "eng", "2ch", ""]);
+    }
+
+    #[test]
+    fn rewrites_map_flags_for_selected_streams() {
+        let streams = parse_ffprobe_streams(PROBE_JSON).unwrap();
+        let cmd = "ffmpeg -i in.mkv -map 0:v:0 -map 0:a -c:v copy out.mp4";
+        let selection =
+            build_stream_selection(&argv(cmd), "in.mkv", String::new(), streams).unwrap();
+        assert_eq!(selection.selected, vec![true, true, false]);
+
+        let rewritten = apply_stream_selection(cmd, &selection, &[false, true, true]).unwrap();
+        assert_eq!(
+            rewritten,
+            "ffmpeg -i in.mkv -map 0:v:0 -c:v copy -map 0:2 -map 0:3 -c:s mov_text out.mp4"
+        );
+
+        let plain = "ffmpeg -i in.mkv -c copy out.mkv";
+        assert_eq!(
+            apply_stream_selection(plain, &selection, &[true, false, false]).unwrap(),
+            "ffmpeg -i in.mkv -c copy -map '0:V?' -map 0:1 out.mkv"
+        );
+    }
+
+    #[test]
+    fn parses_ffmpeg_times() {
+        assert_eq!(parse_timestamp("2:00"), Some(120.0));
+        assert_eq!(parse_timestamp("01:10:30.5"), Some(4230.5));
+        assert_eq!(parse_timestamp("90"), Some(90.0));
+        assert_eq!(parse_timestamp("1500ms"), Some(1.5));
+        assert_eq!(parse_timestamp("1:75"), None);
+        assert_eq!(parse_timestamp("-5"), None);
+        assert_eq!(parse_timestamp("ten"), None);
+        assert_eq!(format_timestamp(4230.5), "01:10:30.500");
+    }
+
+    #[test]
+    fn checks_cut_and_segment_flags() {
+        let plugin = FFmpegPlugin::default();
+        assert!(plugin.validate_command("ffmpeg -ss 2:00 -to 10:30 -i in.mp4 -c copy out.mp4"));
+        assert!(!plugin.validate_command("ffmpeg -ss 10:30 -to 2:00 -i in.mp4 out.mp4"));
+        assert!(!plugin.validate_command("ffmpeg -ss 1:xx -i in.mp4 out.mp4"));
+        assert!(!plugin.validate_command("ffmpeg -i in.mp4 -t 5 -to 9 out.mp4"));
+        assert!(!plugin.validate_command("ffmpeg -i in.mp4 -f segment -segment_time 60 out.mp4"));
+        assert!(!plugin.validate_command("ffmpeg -i in.mp4 -segment_time 60 out_%03d.mp4"));
+        assert!(!plugin
+            .validate_command("ffmpeg -i in.mp4 -f segment -segment_times 60,30 out_%03d.mp4"));
+
+        // After an input seek, an output -to counts from the seek point.
+        let flags = timing_flags(&argv("ffmpeg -ss 60 -i in.mp4 -to 30 out.mp4")).unwrap();
+        assert_eq!((flags.start, flags.end), (Some(60.0), Some(90.0)));
+    }
+
+    #[test]
+    fn lists_segments_with_their_chapters() {
+        let timeline = parse_ffprobe_timeline(
+            r#"{"chapters":[
+                {"id":0,"start_time":"0.000000","end_time":"252.000000","tags":{"title":"Intro"}},
+                {"id":1,"start_time":"252.000000","end_time":"630.500000","tags":{"title":"Talk"}},
+                {"id":2,"start_time":"630.500000","end_time":"700.000000"}
+            ],"format":{"duration":"700.000000"}}"#,
+        )
+        .unwrap();
+        assert_eq!(timeline.duration, Some(700.0));
+        assert_eq!(timeline.chapters[2].title, None);
+
+        let cmd = argv(
+            "ffmpeg -i in.mkv -map 0 -c copy -f segment -segment_times 252,630.5 out_%02d.mkv",
+        );
+        let flags = timing_flags(&cmd).unwrap();
+        assert_eq!(
+            describe_segments(&cmd, &flags, &timeline, "in.mkv").unwrap(),
+            "Segments (3):\n  1. 00:00:00 - 00:04:12  out_00.mkv  (Intro)\n  \
+             2. 00:04:12 - 00:10:30.500  out_01.mkv  (Talk)\n  \
+             3. 00:10:30.500 - 00:11:40  out_02.mkv  (Chapter 3)"
+        );
+
+        let cmd = argv("ffmpeg -i in.mkv -c copy -f segment -segment_time 300 out_%d.mkv");
+        let flags = timing_flags(&cmd).unwrap();
+        let preview = describe_segments(&cmd, &flags, &timeline, "in.mkv").unwrap();
+        assert!(preview.starts_with("Segments (3):"));
+        assert!(preview.contains("  3. 00:10:00 - 00:11:40  out_2.mkv  (Talk)"));
+        assert!(preview.contains("split on them with -segment_times 252,630.5"));
+
+        let cmd = argv("ffmpeg -ss 12:00 -i in.mkv -c copy out.mkv");
+        let flags = timing_flags(&cmd).unwrap();
+        let err = describe_segments(&cmd, &flags, &timeline, "in.mkv").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "-ss 00:12:00 is past the end of in.mkv (00:11:40)"
+        );
+    }
+
+    #[test]
+    fn plans_two_pass_loudnorm() {
+        assert_eq!(
+            loudnorm_plan(&argv("ffmpeg -i in.wav -af volume=2 out.wav")),
+            None
+        );
+        let measured_cmd = "ffmpeg -i in.wav -af loudnorm=I=-16:measured_I=-27 out.wav";
+        assert_eq!(loudnorm_plan(&argv(measured_cmd)), None);
+
+        let cmd = argv("ffmpeg -i talk.mp4 -c:v copy -af highpass=f=80,loudnorm=I=-16:TP=-1.5 -ar 48000 out.mp4");
+        let plan = loudnorm_plan(&cmd).unwrap();
+        assert_eq!(
+            plan.analysis_argv(&cmd).join(" "),
+            "ffmpeg -hide_banner -nostats -i talk.mp4 -c:v copy \
+             -af highpass=f=80,loudnorm=I=-16:TP=-1.5:print_format=json -ar 48000 \
+             -vn -sn -dn -f null -"
+        );
+
+        let measured = parse_loudnorm_json(
+            "size=N/A time=00:01:00.00\n[Parsed_loudnorm_1 @ 0x5581] \n{\n\
+             \t\"input_i\" : \"-27.61\",\n\t\"input_tp\" : \"-4.47\",\n\
+             \t\"input_lra\" : \"18.06\",\n\t\"input_thresh\" : \"-39.20\",\n\
+             \t\"output_i\" : \"-16.58\",\n\t\"normalization_type\" : \"dynamic\",\n\
+             \t\"target_offset\" : \"0.58\"\n}\n",
+        )
+        .unwrap();
+        assert_eq!(measured.input_i, "-27.61");
+        assert_eq!(
+            plan.apply_argv(&cmd, &measured)[6],
+            "highpass=f=80,loudnorm=I=-16:TP=-1.5:measured_I=-27.61:measured_TP=-4.47:\
+             measured_LRA=18.06:measured_thresh=-39.20:offset=0.58:linear=true"
+        );
+        let preview = plan.describe(&measured);
+        assert!(preview.contains("Integrated -27.61 LUFS (target -16)"));
+        assert!(preview.contains("Range 18.06 LU (target 7)"));
+        assert!(parse_loudnorm_json("Error opening input").is_err());
+    }
 }
@@ -0,0 +1,173 @@
+use crate::Plugin;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// Longest version line kept; some tools print build flags on the same line.
+const MAX_VERSION_CHARS: usize = 60;
+
+/// Longest a `--version` probe may take; a tool that hangs (waiting on a
+/// license prompt or a network mount) reads as having no version.
+const VERSION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What a plugin's external tool looks like on this machine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolHealth {
+    pub installed: bool,
+    /// The first of the plugin's binaries found in `$PATH`, or the
+    /// preferred one when none is.
+    pub binary: String,
+    pub path: Option<PathBuf>,
+    pub version: Option<String>,
+}
+
+impl ToolHealth {
+    pub async fn check(plugin: &dyn Plugin) -> Self {
+        if plugin.built_in() && plugin.binaries().is_empty() {
+            return Self {
+                installed: true,
+                binary: "dexter".to_string(),
+                path: std::env::current_exe().ok(),
+                version: Some("built in".to_string()),
+            };
+        }
+        let names = binary_names(plugin);
+        let found = names
+            .iter()
+            .find_map(|name| find_in_path(name).map(|path| (*name, path)));
+        let (binary, path) = match found {
+            Some((name, path)) => (name.to_string(), Some(path)),
+            None => (names[0].to_string(), None),
+        };
+        let version = match &path {
+            Some(path) => detect_version(path).await,
+            None => None,
+        };
+        Self {
+            installed: plugin.is_installed().await,
+            binary,
+            path,
+            version,
+        }
+    }
+}
+
+fn binary_names(plugin: &dyn Plugin) -> Vec<&str> {
+    match plugin.binaries() {
+        [] => vec![plugin.name()],
+        names => names.to_vec(),
+    }
+}
+
+/// Path of the first of the plugin's binaries found on `PATH`.
+pub fn installed_binary(plugin: &dyn Plugin) -> Option<PathBuf> {
+    binary_names(plugin).into_iter().find_map(find_in_path)
+}
+
+/// The version line of the first of the plugin's binaries found.
+pub async fn installed_version(plugin: &dyn Plugin) -> Option<String> {
+    detect_version(&installed_binary(plugin)?).await
+}
+
+/// Major version in a `--version` line: the first number followed by a
+/// dot, with or without a leading `v` (`f2 version v2.0.3` -> 2).
+pub fn major_version(line: &str) -> Option<u32> {
+    line.split(|c: char| !c.is_ascii_alphanumeric() && c != '.')
+        .map(|word| word.strip_prefix('v').unwrap_or(word))
+        .find_map(|word| {
+            let (major, rest) = word.split_once('.')?;
+            if rest.is_empty() || !rest.starts_with(|c: char| c.is_ascii_digit()) {
+                return None;
+            }
+            major.parse().ok()
+        })
+}
+
+pub fn find_in_path(binary: &str) -> Option<PathBuf> {
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths).find_map(|dir| {
+        let candidate = dir.join(binary);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        let exe = dir.join(format!("{}.exe", binary));
+        (cfg!(windows) && exe.is_file()).then_some(exe)
+    })
+}
+
+/// First line of `--version` (or ffmpeg-style `-version`) output.
+async fn detect_version(path: &PathBuf) -> Option<String> {
+    detect_version_within(path, VERSION_TIMEOUT).await
+}
+
+async fn detect_version_within(path: &PathBuf, limit: Duration) -> Option<String> {
+    for arg in ["--version", "-version"] {
+        let output = Command::new(path)
+            .arg(arg)
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output();
+        let Ok(Ok(output)) = tokio::time::timeout(limit, output).await else {
+            return None;
+        };
+        if !output.status.success() {
+            continue;
+        }
+        let text = if output.stdout.is_empty() {
+            output.stderr
+        } else {
+            output.stdout
+        };
+        return version_line(&String::from_utf8_lossy(&text));
+    }
+    None
+}
+
+fn version_line(output: &str) -> Option<String> {
+    let line = output.lines().map(str::trim).find(|l| !l.is_empty())?;
+    if line.chars().count() <= MAX_VERSION_CHARS {
+        return Some(line.to_string());
+    }
+    let kept: String = line.chars().take(MAX_VERSION_CHARS - 3).collect();
+    Some(format!("{}...", kept.trim_end()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_line_takes_the_first_non_empty_line() {
+        assert_eq!(
+            version_line("\n  pandoc 3.1.11\nFeatures: +server\n").as_deref(),
+            Some("pandoc 3.1.11")
+        );
+        let long = format!("curl 8.5.0 {}", "x".repeat(80));
+        let line = version_line(&long).unwrap();
+        assert_eq!(line.chars().count(), MAX_VERSION_CHARS);
+        assert!(line.ends_with("..."));
+        assert_eq!(version_line("  \n"), None);
+
+        assert_eq!(major_version("f2 version v2.0.3"), Some(2));
+        assert_eq!(major_version("pandoc 3.1.11"), Some(3));
+        assert_eq!(major_version("ffmpeg version n7.0-static"), None);
+        assert_eq!(major_version("ffmpeg version 6.1.1-3ubuntu5"), Some(6));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn a_hanging_tool_has_no_version() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("dexter-health-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let tool = dir.join("hangs");
+        std::fs::write(&tool, "#!/bin/sh\nsleep 30\n").unwrap();
+        std::fs::set_permissions(&tool, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let version = detect_version_within(&tool, Duration::from_millis(100)).await;
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(version, None);
+    }
+}
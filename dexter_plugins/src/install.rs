@@ -0,0 +1,324 @@
+//! Installs or updates a plugin's external tool with a package manager
+//! found on this machine: Homebrew, apt, winget, Scoop or pipx. Each plugin
+//! lists its package under every manager that has it, preferred first, and
+//! the first manager present wins.
+
+use anyhow::{anyhow, Result};
+use std::collections::VecDeque;
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+
+use crate::health::find_in_path;
+
+/// Output lines kept for the error of a failed install.
+const ERROR_TAIL_LINES: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageManager {
+    Brew,
+    Apt,
+    Winget,
+    Scoop,
+    Pipx,
+}
+
+impl PackageManager {
+    pub fn binary(self) -> &'static str {
+        match self {
+            PackageManager::Brew => "brew",
+            PackageManager::Apt => "apt-get",
+            PackageManager::Winget => "winget",
+            PackageManager::Scoop => "scoop",
+            PackageManager::Pipx => "pipx",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PackageManager::Brew => "Homebrew",
+            PackageManager::Apt => "apt (Debian/Ubuntu)",
+            PackageManager::Winget => "winget",
+            PackageManager::Scoop => "Scoop",
+            PackageManager::Pipx => "pipx",
+        }
+    }
+
+    fn supported(self) -> bool {
+        match self {
+            PackageManager::Brew => cfg!(any(target_os = "macos", target_os = "linux")),
+            PackageManager::Apt => cfg!(target_os = "linux"),
+            PackageManager::Winget | PackageManager::Scoop => cfg!(windows),
+            PackageManager::Pipx => true,
+        }
+    }
+
+    /// Supported on this system and found in `$PATH`.
+    pub fn available(self) -> bool {
+        self.supported() && find_in_path(self.binary()).is_some()
+    }
+
+    /// The command installing `package`, or with `update` upgrading it,
+    /// without questions: nothing can be typed into it from the TUI, so apt
+    /// runs under `sudo -n` and fails rather than wait for a password.
+    pub fn argv(self, package: &str, update: bool) -> Vec<String> {
+        let args: Vec<&str> = match (self, update) {
+            (PackageManager::Brew, false) => vec!["brew", "install", package],
+            (PackageManager::Brew, true) => vec!["brew", "upgrade", package],
+            (PackageManager::Apt, false) => vec!["apt-get", "install", "-y", package],
+            (PackageManager::Apt, true) => {
+                vec!["apt-get", "install", "-y", "--only-upgrade", package]
+            }
+            (PackageManager::Winget, update) => vec![
+                "winget",
+                if update { "upgrade" } else { "install" },
+                "--exact",
+                "--id",
+                package,
+                "--accept-package-agreements",
+                "--accept-source-agreements",
+                "--disable-interactivity",
+            ],
+            (PackageManager::Scoop, false) => vec!["scoop", "install", package],
+            (PackageManager::Scoop, true) => vec!["scoop", "update", package],
+            (PackageManager::Pipx, false) => vec!["pipx", "install", package],
+            (PackageManager::Pipx, true) => vec!["pipx", "upgrade", package],
+        };
+        let mut argv: Vec<String> = args.into_iter().map(String::from).collect();
+        if self == PackageManager::Apt && find_in_path("sudo").is_some() {
+            argv.splice(0..0, ["sudo".to_string(), "-n".to_string()]);
+        }
+        argv
+    }
+
+    /// What to type by hand to install `package`.
+    fn manual_command(self, package: &str) -> String {
+        match self {
+            PackageManager::Brew => format!("brew install {}", package),
+            PackageManager::Apt => format!("sudo apt install {}", package),
+            PackageManager::Winget => format!("winget install --id {}", package),
+            PackageManager::Scoop => format!("scoop install {}", package),
+            PackageManager::Pipx => format!("pipx install {}", package),
+        }
+    }
+}
+
+/// A plugin's tool as one package manager names it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Package {
+    pub manager: PackageManager,
+    pub name: &'static str,
+}
+
+impl Package {
+    pub const fn brew(name: &'static str) -> Self {
+        Self::new(PackageManager::Brew, name)
+    }
+
+    pub const fn apt(name: &'static str) -> Self {
+        Self::new(PackageManager::Apt, name)
+    }
+
+    pub const fn winget(name: &'static str) -> Self {
+        Self::new(PackageManager::Winget, name)
+    }
+
+    pub const fn scoop(name: &'static str) -> Self {
+        Self::new(PackageManager::Scoop, name)
+    }
+
+    pub const fn pipx(name: &'static str) -> Self {
+        Self::new(PackageManager::Pipx, name)
+    }
+
+    const fn new(manager: PackageManager, name: &'static str) -> Self {
+        Self { manager, name }
+    }
+}
+
+/// One install or update to run: the plugin, the manager picked for it and
+/// the exact command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstallPlan {
+    pub plugin: String,
+    pub manager: PackageManager,
+    pub package: String,
+    pub update: bool,
+    pub argv: Vec<String>,
+}
+
+impl InstallPlan {
+    /// The first of `packages` whose manager is on this machine.
+    pub fn for_packages(plugin: &str, packages: &[Package], update: bool) -> Option<Self> {
+        Self::pick(plugin, packages, update, PackageManager::available)
+    }
+
+    fn pick(
+        plugin: &str,
+        packages: &[Package],
+        update: bool,
+        available: impl Fn(PackageManager) -> bool,
+    ) -> Option<Self> {
+        let package = packages.iter().find(|p| available(p.manager))?;
+        Some(Self {
+            plugin: plugin.to_string(),
+            manager: package.manager,
+            package: package.name.to_string(),
+            update,
+            argv: package.manager.argv(package.name, update),
+        })
+    }
+
+    /// The command as a shell would show it.
+    pub fn command_line(&self) -> String {
+        shell_words::join(&self.argv)
+    }
+
+    /// Runs the command, handing each line of its output (stdout and stderr
+    /// as they arrive) to `on_line`. Dropping the future kills the process.
+    pub async fn run_streamed(&self, mut on_line: impl FnMut(String) + Send) -> Result<()> {
+        let (program, args) = self
+            .argv
+            .split_first()
+            .ok_or_else(|| anyhow!("Empty install command"))?;
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| anyhow!("Couldn't start {}: {}", program, e))?;
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        if let Some(stdout) = child.stdout.take() {
+            tokio::spawn(forward_lines(stdout, tx.clone()));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(forward_lines(stderr, tx));
+        }
+        let mut tail = VecDeque::with_capacity(ERROR_TAIL_LINES);
+        while let Some(line) = rx.recv().await {
+            if tail.len() == ERROR_TAIL_LINES {
+                tail.pop_front();
+            }
+            tail.push_back(line.clone());
+            on_line(line);
+        }
+
+        let status = child.wait().await?;
+        if status.success() {
+            return Ok(());
+        }
+        let mut message = format!("`{}` failed ({})", self.command_line(), status);
+        for line in tail {
+            message.push('\n');
+            message.push_str(&line);
+        }
+        Err(anyhow!(message))
+    }
+
+    pub async fn run(&self) -> Result<()> {
+        self.run_streamed(|_| {}).await
+    }
+}
+
+async fn forward_lines(stream: impl AsyncRead + Unpin, tx: mpsc::UnboundedSender<String>) {
+    let mut lines = BufReader::new(stream).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if tx.send(line).is_err() {
+            break;
+        }
+    }
+}
+
+/// How to install `plugin`'s tool by hand, one line per package manager,
+/// then `note`.
+pub fn manual_instructions(plugin: &str, packages: &[Package], note: Option<&str>) -> String {
+    let mut text = format!(
+        "No supported package manager found. Please install {} manually:",
+        plugin
+    );
+    for package in packages {
+        text.push_str(&format!(
+            "\n- {}: {}",
+            package.manager.label(),
+            package.manager.manual_command(package.name)
+        ));
+    }
+    if let Some(note) = note {
+        text.push_str("\n\n");
+        text.push_str(note);
+    }
+    text
+}
+
+/// `Plugin::install`: the first available manager's install, or the manual
+/// instructions as the error when there is none.
+pub async fn install_packages(
+    plugin: &str,
+    packages: &[Package],
+    note: Option<&str>,
+) -> Result<()> {
+    match InstallPlan::for_packages(plugin, packages, false) {
+        Some(plan) => plan.run().await,
+        None => Err(anyhow!(manual_instructions(plugin, packages, note))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PACKAGES: &[Package] = &[
+        Package::brew("yt-dlp"),
+        Package::pipx("yt-dlp"),
+        Package::winget("yt-dlp.yt-dlp"),
+    ];
+
+    #[test]
+    fn picks_the_first_available_manager() {
+        let plan =
+            InstallPlan::pick("yt-dlp", PACKAGES, false, |m| m != PackageManager::Brew).unwrap();
+        assert_eq!(plan.manager, PackageManager::Pipx);
+        assert_eq!(plan.command_line(), "pipx install yt-dlp");
+
+        let plan =
+            InstallPlan::pick("yt-dlp", PACKAGES, true, |m| m == PackageManager::Winget).unwrap();
+        assert_eq!(
+            plan.argv[..5],
+            ["winget", "upgrade", "--exact", "--id", "yt-dlp.yt-dlp"]
+        );
+        assert!(InstallPlan::pick("yt-dlp", PACKAGES, false, |_| false).is_none());
+
+        let manual = manual_instructions("yt-dlp", PACKAGES, Some("Or use pip."));
+        assert!(manual.contains("\n- Homebrew: brew install yt-dlp\n- pipx: pipx install yt-dlp"));
+        assert!(manual.ends_with("\n\nOr use pip."));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn streams_output_and_reports_failures() {
+        let plan = |script: &str| InstallPlan {
+            plugin: "test".to_string(),
+            manager: PackageManager::Brew,
+            package: "test".to_string(),
+            update: false,
+            argv: vec!["sh".to_string(), "-c".to_string(), script.to_string()],
+        };
+        let mut lines = Vec::new();
+        plan("echo one; echo two >&2")
+            .run_streamed(|line| lines.push(line))
+            .await
+            .unwrap();
+        lines.sort();
+        assert_eq!(lines, ["one", "two"]);
+
+        let err = plan("echo missing package >&2; exit 100")
+            .run()
+            .await
+            .unwrap_err();
+        assert!(err.to_string().ends_with("\nmissing package"));
+    }
+}
@@ -1,10 +1,15 @@
-use crate::command_exec::{parse_and_validate_command, spawn_checked_async};
-use crate::{LlmBridge, Plugin, PreviewContent, Progress};
+use crate::command_exec::{
+    exit_failure, parse_and_validate_command, spawn_checked_async, working_dir,
+};
+use crate::{Capability, LlmBridge, Package, Plugin, PluginSettings, PreviewContent, Progress};
 use anyhow::Result;
 use async_trait::async_trait;
 use std::process::Command;
 
-pub struct JdupesPlugin;
+#[derive(Default)]
+pub struct JdupesPlugin {
+    settings: PluginSettings,
+}
 
 fn contains_flag(argv: &[String], flag: &str) -> bool {
     argv.iter().any(|a| a == flag)
@@ -118,6 +123,12 @@ fn validate_jdupes_command(cmd: &str) -> bool {
     true
 }
 
+const PACKAGES: &[Package] = &[
+    Package::brew("jdupes"),
+    Package::apt("jdupes"),
+    Package::scoop("jdupes"),
+];
+
 #[async_trait]
 impl Plugin for JdupesPlugin {
     fn name(&self) -> &str {
@@ -128,6 +139,18 @@ impl Plugin for JdupesPlugin {
         "Find duplicate files quickly with safe scan/summary workflows and optional controlled delete mode."
     }
 
+    fn configure(&mut self, settings: PluginSettings) {
+        self.settings = settings;
+    }
+
+    fn settings(&self) -> &PluginSettings {
+        &self.settings
+    }
+
+    fn capabilities(&self) -> &[Capability] {
+        &[Capability::Deduplication]
+    }
+
     async fn is_installed(&self) -> bool {
         Command::new("jdupes")
             .arg("--version")
@@ -136,10 +159,8 @@ impl Plugin for JdupesPlugin {
             .unwrap_or(false)
     }
 
-    async fn install(&self) -> Result<()> {
-        Err(anyhow::anyhow!(
-            "Please install jdupes manually:\n- macOS (brew): brew install jdupes\n- Debian/Ubuntu: sudo apt install jdupes"
-        ))
+    fn packages(&self) -> &[Package] {
+        PACKAGES
     }
 
     fn get_doc_for_router(&self) -> &str {
@@ -162,7 +183,7 @@ Safety Constraints:
 4. Always include at least one explicit target path or directory."#
     }
 
-    fn get_executor_prompt(&self, context: &str, user_input: &str) -> String {
+    fn get_executor_prompt_with_doc(&self, doc: &str, context: &str, user_input: &str) -> String {
         format!(
             r#"You are the Duplicate File Analysis Specialist Agent for Dexter.
 Your goal is to generate a valid `jdupes` command.
@@ -185,9 +206,7 @@ Your goal is to generate a valid `jdupes` command.
 ### User Request:
 {}
 "#,
-            self.get_doc_for_executor(),
-            context,
-            user_input
+            doc, context, user_input
         )
     }
 
@@ -237,7 +256,7 @@ Your goal is to generate a valid `jdupes` command.
             })
             .await;
 
-        let cwd = std::env::current_dir()?;
+        let cwd = working_dir()?;
         let output = spawn_checked_async(&argv, cwd).await?;
 
         let stdout = String::from_utf8_lossy(&output.stdout).to_string();
@@ -246,7 +265,10 @@ Your goal is to generate a valid `jdupes` command.
         if output.status.success() {
             Ok(format!("{}{}", stdout, stderr))
         } else {
-            Err(anyhow::anyhow!("jdupes error: {}\n{}", stdout, stderr))
+            Err(exit_failure(
+                output.status,
+                format!("jdupes error: {}\n{}", stdout, stderr),
+            ))
         }
     }
 }
@@ -1,26 +1,43 @@
+pub mod cleanup;
 pub mod command_exec;
+pub mod download;
 pub mod f2;
 pub mod ffmpeg;
+pub mod health;
+pub mod install;
 pub mod jdupes;
 pub mod libvips;
+pub mod mediainfo;
 pub mod ocrmypdf;
 pub mod pandoc;
 pub mod qpdf;
+pub mod sandbox;
+pub mod settings;
+pub mod structured;
 pub mod whispercpp;
 pub mod ytdlp;
 
+pub use cleanup::CleanupPlugin;
+pub use download::DownloadPlugin;
 pub use f2::F2Plugin;
-pub use ffmpeg::FFmpegPlugin;
+pub use ffmpeg::{FFmpegPlugin, MediaInfo, MediaStream, StreamKind, StreamSelection};
+pub use health::ToolHealth;
+pub use install::{InstallPlan, Package, PackageManager};
 pub use jdupes::JdupesPlugin;
 pub use libvips::LibvipsPlugin;
+pub use mediainfo::MediaInfoPlugin;
 pub use ocrmypdf::OcrmypdfPlugin;
-pub use pandoc::PandocPlugin;
+pub use pandoc::{default_pandoc_presets, program_running_arg, PandocPlugin, PandocPreset};
 pub use qpdf::QpdfPlugin;
+pub use sandbox::{Sandbox, SandboxBackend};
+pub use settings::PluginSettings;
+pub use structured::StructuredCommand;
 pub use whispercpp::WhisperCppPlugin;
-pub use ytdlp::YtDlpPlugin;
+pub use ytdlp::{YtDlpPlugin, YtDlpPolicy};
 
 use anyhow::Result;
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 
 #[async_trait]
 pub trait LlmBridge: Send + Sync {
@@ -33,23 +50,291 @@ pub struct Progress {
     pub message: String,
 }
 
+/// Coarse task categories a plugin handles. The router uses them to keep
+/// clearly unrelated plugins out of its prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    MediaVideo,
+    MediaAudio,
+    Images,
+    Documents,
+    Ocr,
+    Transcription,
+    Renaming,
+    Downloading,
+    Deduplication,
+    Cleanup,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 10] = [
+        Capability::MediaVideo,
+        Capability::MediaAudio,
+        Capability::Images,
+        Capability::Documents,
+        Capability::Ocr,
+        Capability::Transcription,
+        Capability::Renaming,
+        Capability::Downloading,
+        Capability::Deduplication,
+        Capability::Cleanup,
+    ];
+
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Capability::MediaVideo => "media-video",
+            Capability::MediaAudio => "media-audio",
+            Capability::Images => "images",
+            Capability::Documents => "documents",
+            Capability::Ocr => "ocr",
+            Capability::Transcription => "transcription",
+            Capability::Renaming => "renaming",
+            Capability::Downloading => "downloading",
+            Capability::Deduplication => "deduplication",
+            Capability::Cleanup => "cleanup",
+        }
+    }
+
+    /// Words that suggest a request needs this capability. Keywords of four
+    /// or more characters also match as word prefixes (`video` -> `videos`).
+    pub fn keywords(&self) -> &'static [&'static str] {
+        match self {
+            Capability::MediaVideo => &[
+                "video",
+                "movie",
+                "film",
+                "clip",
+                "mp4",
+                "mkv",
+                "mov",
+                "avi",
+                "webm",
+                "m4v",
+                "transcode",
+                "encode",
+                "h264",
+                "h265",
+                "hevc",
+                "x264",
+                "x265",
+                "fps",
+                "frame",
+                "gif",
+                "trim",
+                "subtitle",
+                "stream",
+                "bitrate",
+                "resolution",
+                "1080p",
+                "720p",
+                "4k",
+            ],
+            Capability::MediaAudio => &[
+                "audio", "sound", "music", "song", "podcast", "mp3", "wav", "flac", "aac", "m4a",
+                "ogg", "opus", "volume", "loudness", "bitrate", "track",
+            ],
+            Capability::Images => &[
+                "image",
+                "photo",
+                "picture",
+                "pic",
+                "jpg",
+                "jpeg",
+                "png",
+                "webp",
+                "heic",
+                "tif",
+                "tiff",
+                "avif",
+                "thumbnail",
+                "resize",
+                "crop",
+                "rotate",
+                "dpi",
+            ],
+            Capability::Documents => &[
+                "document",
+                "doc",
+                "docx",
+                "pdf",
+                "markdown",
+                "md",
+                "html",
+                "epub",
+                "latex",
+                "tex",
+                "odt",
+                "rtf",
+                "page",
+                "merge",
+                "split",
+                "decrypt",
+                "encrypt",
+                "password",
+                "compress",
+                "linearize",
+            ],
+            Capability::Ocr => &["ocr", "scan", "searchable", "recognize", "text layer"],
+            Capability::Transcription => &[
+                "transcribe",
+                "transcript",
+                "speech",
+                "subtitle",
+                "caption",
+                "srt",
+                "vtt",
+                "dictation",
+            ],
+            Capability::Renaming => &[
+                "rename",
+                "renam",
+                "filename",
+                "name",
+                "prefix",
+                "suffix",
+                "numbering",
+                "lowercase",
+                "uppercase",
+                "extension",
+                "mapping",
+            ],
+            Capability::Downloading => &[
+                "download", "youtube", "url", "http", "https", "playlist", "fetch", "vimeo",
+                "bilibili",
+            ],
+            Capability::Deduplication => &[
+                "duplicate",
+                "dupe",
+                "dedup",
+                "identical",
+                "same",
+                "hardlink",
+                "symlink",
+            ],
+            Capability::Cleanup => &[
+                "disk", "space", "usage", "biggest", "largest", "empty", "cleanup", "clean up",
+                "free up", "du",
+            ],
+        }
+    }
+}
+
+/// What `Plugin::settings` returns for plugins that keep none.
+static NO_SETTINGS: PluginSettings = PluginSettings {
+    default_flags: String::new(),
+    output_dir: None,
+    never_overwrite: false,
+    prompt_append: Vec::new(),
+    prompt_override: None,
+};
+
 #[async_trait]
 pub trait Plugin: Send + Sync {
     fn name(&self) -> &str;
     fn description(&self) -> &str;
 
+    /// Takes the user's settings for this plugin (`[plugins.<name>]`);
+    /// called once, before the plugin is shared. Plugins that keep no
+    /// settings ignore them.
+    fn configure(&mut self, _settings: PluginSettings) {}
+    /// The settings given to `configure`, the defaults until then.
+    fn settings(&self) -> &PluginSettings {
+        &NO_SETTINGS
+    }
+
+    /// Empty means "always offer to the router".
+    fn capabilities(&self) -> &[Capability] {
+        &[]
+    }
+
+    /// Executables the plugin drives, preferred first; empty means the one
+    /// named like the plugin. Used by the health check.
+    fn binaries(&self) -> &[&str] {
+        &[]
+    }
+
+    /// Implemented inside Dexter: no docs to sync and nothing that can run
+    /// on a remote host. Nothing to install or check either, unless it
+    /// names `binaries` it drives.
+    fn built_in(&self) -> bool {
+        false
+    }
+
     // Installation
     async fn is_installed(&self) -> bool;
-    async fn install(&self) -> Result<()>;
+
+    /// The tool's package under each package manager that has it, preferred
+    /// first; `install` uses the first whose manager is on this machine.
+    fn packages(&self) -> &[Package] {
+        &[]
+    }
+
+    /// Added to the manual install instructions, e.g. where to build from.
+    fn install_note(&self) -> Option<&str> {
+        None
+    }
+
+    async fn install(&self) -> Result<()> {
+        install::install_packages(self.name(), self.packages(), self.install_note()).await
+    }
 
     // AI Interaction
     fn get_doc_for_router(&self) -> &str; // Short description
+    /// A command for `user_input` that needs no model, for requests plain
+    /// enough to read directly ("show me info about video.mkv"). `files`
+    /// is the scanned context, relative to the working directory.
+    fn direct_command(&self, _user_input: &str, _files: &[String]) -> Option<String> {
+        None
+    }
     fn get_doc_for_executor(&self) -> &str; // Full docs
-    fn get_executor_prompt(&self, context: &str, user_input: &str) -> String; // Full system prompt
+    /// Docs for specific major versions of the tool, used in place of
+    /// `get_doc_for_executor` when the installed binary matches one.
+    fn doc_bundles(&self) -> &[DocBundle] {
+        &[]
+    }
+    fn get_executor_prompt(&self, context: &str, user_input: &str) -> String {
+        self.get_executor_prompt_with_doc(self.get_doc_for_executor(), context, user_input)
+    }
+    /// Full system prompt around `doc`, the executor docs to follow.
+    fn get_executor_prompt_with_doc(&self, doc: &str, context: &str, user_input: &str) -> String;
+
+    /// Reads a generated reply as a structured command. `None` keeps the
+    /// reply as a plain command line.
+    fn parse_structured(&self, reply: &str) -> Option<StructuredCommand> {
+        StructuredCommand::from_reply(reply)
+    }
 
     // Execution
     fn validate_command(&self, cmd: &str) -> bool;
+    /// Whether `cmd` removes files and so needs a second, explicit
+    /// confirmation after its preview.
+    fn confirm_twice(&self, _cmd: &str) -> bool {
+        false
+    }
     async fn execute(&self, cmd: &str) -> Result<String>;
+    /// Flags that switch the tool between previewing and applying. Commands
+    /// are generated without them; `command_argv` adds the one for the mode.
+    fn mode_flags(&self) -> ModeFlags {
+        ModeFlags::default()
+    }
+    /// The argv spawned for `cmd` in `mode`, with the flags the plugin adds
+    /// or drops on its own (`-x` for f2 when applying, `--newline` for
+    /// yt-dlp). Overrides should go through `mode_flags` too.
+    fn command_argv(&self, cmd: &str, mode: ExecMode) -> Result<Vec<String>> {
+        let mut argv = shell_words::split(cmd)
+            .map_err(|e| anyhow::anyhow!("Invalid command syntax: {}", e))?;
+        if argv.is_empty() {
+            return Err(anyhow::anyhow!("Command is empty"));
+        }
+        self.mode_flags().apply_to(&mut argv, mode);
+        Ok(argv)
+    }
+    /// The argv `execute` falls back to when `argv` fails in a way the
+    /// plugin recovers from (`--skip-text` for ocrmypdf on a PDF that
+    /// already has text), so the preview can show it too.
+    fn retry_argv(&self, _argv: &[String]) -> Option<Vec<String>> {
+        None
+    }
     async fn dry_run(&self, cmd: &str, llm: Option<&dyn LlmBridge>) -> Result<PreviewContent>;
 
     // New method with default implementation
@@ -63,13 +348,75 @@ pub trait Plugin: Send + Sync {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// Whether a command runs for real or only reports what it would do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecMode {
+    Preview,
+    Apply,
+}
+
+/// A tool's preview and apply switches. The first flag of each list is the
+/// one added; the others are spellings of it, removed with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ModeFlags {
+    pub preview: &'static [&'static str],
+    pub apply: &'static [&'static str],
+}
+
+impl ModeFlags {
+    /// Replaces whatever mode flags `argv` has with the one for `mode`,
+    /// long ones given a value (`--exec=true`) included.
+    pub fn apply_to(&self, argv: &mut Vec<String>, mode: ExecMode) {
+        argv.retain(|arg| {
+            let name = match arg.split_once('=') {
+                Some((name, _)) if name.starts_with("--") => name,
+                _ => arg.as_str(),
+            };
+            !self.preview.contains(&name) && !self.apply.contains(&name)
+        });
+        let flags = match mode {
+            ExecMode::Preview => self.preview,
+            ExecMode::Apply => self.apply,
+        };
+        if let Some(flag) = flags.first() {
+            argv.push(flag.to_string());
+        }
+    }
+}
+
+/// Executor docs written for one major version of a plugin's tool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DocBundle {
+    pub major: u32,
+    pub doc: &'static str,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum PreviewContent {
     Text(String),
     DiffList(Vec<DiffItem>),
+    /// Media preview whose input has several audio/subtitle streams to pick from.
+    StreamSelection(StreamSelection),
+    /// Rows under a header, such as an input's streams.
+    Table {
+        title: String,
+        headers: Vec<String>,
+        rows: Vec<Vec<String>>,
+    },
+    /// `key: value` pairs in titled groups, such as one per URL.
+    KeyValue(Vec<KeyValueSection>),
+    /// Several previews one under the other, usually a `Text` summary
+    /// followed by tables or key/value groups.
+    Sections(Vec<PreviewContent>),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyValueSection {
+    pub title: String,
+    pub entries: Vec<(String, String)>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DiffItem {
     pub original: String,
     pub new: String,
@@ -1,10 +1,15 @@
-use crate::command_exec::{parse_and_validate_command, spawn_checked_async};
-use crate::{LlmBridge, Plugin, PreviewContent, Progress};
+use crate::command_exec::{
+    exit_failure, parse_and_validate_command, spawn_checked_async, working_dir,
+};
+use crate::{Capability, LlmBridge, Package, Plugin, PluginSettings, PreviewContent, Progress};
 use anyhow::Result;
 use async_trait::async_trait;
 use std::process::Command;
 
-pub struct LibvipsPlugin;
+#[derive(Default)]
+pub struct LibvipsPlugin {
+    settings: PluginSettings,
+}
 
 fn parse_libvips_command(cmd: &str) -> Result<Vec<String>> {
     parse_and_validate_command(cmd, "vips")
@@ -121,6 +126,12 @@ fn progress_message(argv: &[String]) -> String {
     }
 }
 
+const PACKAGES: &[Package] = &[
+    Package::brew("vips"),
+    Package::apt("libvips-tools"),
+    Package::scoop("libvips"),
+];
+
 #[async_trait]
 impl Plugin for LibvipsPlugin {
     fn name(&self) -> &str {
@@ -131,6 +142,22 @@ impl Plugin for LibvipsPlugin {
         "High-performance image processing with vips/vipsthumbnail for resize, crop, rotate, and conversion workflows."
     }
 
+    fn configure(&mut self, settings: PluginSettings) {
+        self.settings = settings;
+    }
+
+    fn settings(&self) -> &PluginSettings {
+        &self.settings
+    }
+
+    fn capabilities(&self) -> &[Capability] {
+        &[Capability::Images]
+    }
+
+    fn binaries(&self) -> &[&str] {
+        &["vips", "vipsthumbnail"]
+    }
+
     async fn is_installed(&self) -> bool {
         let has_vips = Command::new("vips")
             .arg("--version")
@@ -147,10 +174,8 @@ impl Plugin for LibvipsPlugin {
         has_vips || has_thumbnail
     }
 
-    async fn install(&self) -> Result<()> {
-        Err(anyhow::anyhow!(
-            "Please install libvips tools manually:\n- macOS (brew): brew install vips\n- Debian/Ubuntu: sudo apt install libvips-tools"
-        ))
+    fn packages(&self) -> &[Package] {
+        PACKAGES
     }
 
     fn get_doc_for_router(&self) -> &str {
@@ -172,7 +197,7 @@ Safety Constraints:
 4. Do NOT use shell redirection; keep all IO in command arguments."#
     }
 
-    fn get_executor_prompt(&self, context: &str, user_input: &str) -> String {
+    fn get_executor_prompt_with_doc(&self, doc: &str, context: &str, user_input: &str) -> String {
         format!(
             r#"You are the Image Processing Specialist Agent for Dexter.
 Your goal is to generate a valid `vips` or `vipsthumbnail` command for libvips.
@@ -194,9 +219,7 @@ Your goal is to generate a valid `vips` or `vipsthumbnail` command for libvips.
 ### User Request:
 {}
 "#,
-            self.get_doc_for_executor(),
-            context,
-            user_input
+            doc, context, user_input
         )
     }
 
@@ -239,7 +262,7 @@ Your goal is to generate a valid `vips` or `vipsthumbnail` command for libvips.
             })
             .await;
 
-        let cwd = std::env::current_dir()?;
+        let cwd = working_dir()?;
         let output = spawn_checked_async(&argv, cwd).await?;
 
         let stdout = String::from_utf8_lossy(&output.stdout).to_string();
@@ -248,7 +271,10 @@ Your goal is to generate a valid `vips` or `vipsthumbnail` command for libvips.
         if output.status.success() {
             Ok(format!("{}{}", stdout, stderr))
         } else {
-            Err(anyhow::anyhow!("libvips error: {}\n{}", stdout, stderr))
+            Err(exit_failure(
+                output.status,
+                format!("libvips error: {}\n{}", stdout, stderr),
+            ))
         }
     }
 }
@@ -0,0 +1,419 @@
+//! Stream, codec, duration and bitrate facts about one media file, read
+//! with ffprobe and shown as a table. Plain questions such as "show me info
+//! about video.mkv" become a `mediainfo` command without asking the model.
+
+use crate::command_exec::{parse_and_validate_command, working_dir};
+use crate::ffmpeg::{format_bit_rate, format_timestamp, probe_media, MediaInfo, PACKAGES};
+use crate::health::find_in_path;
+use crate::{Capability, LlmBridge, Package, Plugin, PluginSettings, PreviewContent};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use regex::Regex;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Extensions ffprobe is asked about without the model.
+const MEDIA_EXTENSIONS: [&str; 22] = [
+    "mkv", "mp4", "m4v", "mov", "avi", "webm", "wmv", "flv", "mpg", "mpeg", "ts", "m2ts", "mp3",
+    "m4a", "aac", "flac", "wav", "ogg", "opus", "wma", "aiff", "mka",
+];
+
+/// Words that ask about a file.
+const INFO_WORDS: [&str; 11] = [
+    "info",
+    "details",
+    "metadata",
+    "mediainfo",
+    "probe",
+    "streams",
+    "codec",
+    "bitrate",
+    "duration",
+    "how long is",
+    "what's in",
+];
+
+/// Words an info request opens with: a question or a request to show.
+const LEAD_WORDS: [&str; 19] = [
+    "what",
+    "what's",
+    "whats",
+    "which",
+    "how",
+    "show",
+    "tell",
+    "give",
+    "get",
+    "list",
+    "display",
+    "describe",
+    "print",
+    "check",
+    "info",
+    "details",
+    "metadata",
+    "mediainfo",
+    "probe",
+];
+
+/// The other words an info request is made of. A request with any word
+/// outside these, such as "reduce the bitrate of a.mkv", is routed.
+const QUESTION_WORDS: [&str; 43] = [
+    "me",
+    "the",
+    "a",
+    "an",
+    "of",
+    "in",
+    "for",
+    "about",
+    "on",
+    "is",
+    "are",
+    "does",
+    "it",
+    "its",
+    "this",
+    "that",
+    "file",
+    "video",
+    "audio",
+    "clip",
+    "song",
+    "movie",
+    "track",
+    "please",
+    "and",
+    "all",
+    "long",
+    "last",
+    "inside",
+    "stream",
+    "streams",
+    "codec",
+    "codecs",
+    "bitrate",
+    "duration",
+    "resolution",
+    "format",
+    "container",
+    "size",
+    "length",
+    "information",
+    "details",
+    "metadata",
+];
+
+/// The file a request only asks about, as written: exactly one media file
+/// name in a request shaped like "what/show/info ... FILE", made only of
+/// question words and naming at least one info word.
+pub fn info_request(user_input: &str) -> Option<String> {
+    static TOKEN: OnceLock<Regex> = OnceLock::new();
+    let token = TOKEN.get_or_init(|| {
+        Regex::new(r#""([^"]+)"|'([^']+\.\w+)'|(\S+)"#).expect("valid token pattern")
+    });
+    let mut files = Vec::new();
+    let mut rest = String::new();
+    for caps in token.captures_iter(user_input) {
+        let Some(raw) = caps.get(1).or(caps.get(2)).or(caps.get(3)) else {
+            continue;
+        };
+        let name = raw
+            .as_str()
+            .trim_end_matches(['?', '!', ',', ';', ':', '.', ')']);
+        let is_media = Path::new(name)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| MEDIA_EXTENSIONS.contains(&ext.to_lowercase().as_str()));
+        if is_media {
+            files.push(name.to_string());
+        } else {
+            rest.push_str(&caps[0]);
+            rest.push(' ');
+        }
+    }
+    let [file] = <[String; 1]>::try_from(files).ok()?;
+
+    let lower = rest.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|word| !word.is_empty())
+        .collect();
+    let has = |phrase: &str| {
+        if phrase.contains(' ') {
+            lower.contains(phrase)
+        } else {
+            words.contains(&phrase)
+        }
+    };
+    let shaped = words.first().is_some_and(|lead| LEAD_WORDS.contains(lead))
+        && words
+            .iter()
+            .all(|word| LEAD_WORDS.contains(word) || QUESTION_WORDS.contains(word));
+    (shaped && INFO_WORDS.into_iter().any(has)).then_some(file)
+}
+
+/// The file of a `mediainfo FILE` command.
+pub fn parse_mediainfo_command(cmd: &str) -> Result<String> {
+    let argv = parse_and_validate_command(cmd, "mediainfo")?;
+    match &argv[1..] {
+        [file] if !file.starts_with('-') => Ok(file.clone()),
+        _ => Err(anyhow!("Use mediainfo FILE with one media file")),
+    }
+}
+
+/// The container summary and a table of every stream.
+fn report(media: &MediaInfo, file: &str) -> PreviewContent {
+    let mut parts = vec![media.summary(file)];
+    if media.streams.is_empty() {
+        parts.push(PreviewContent::Text("No streams found.".to_string()));
+    } else {
+        parts.push(PreviewContent::Table {
+            title: format!("STREAMS ({})", media.streams.len()),
+            headers: [
+                "#", "TYPE", "CODEC", "DETAIL", "DURATION", "BITRATE", "LANG",
+            ]
+            .map(String::from)
+            .to_vec(),
+            rows: media
+                .streams
+                .iter()
+                .map(|stream| {
+                    vec![
+                        stream.index.to_string(),
+                        stream.kind.label().to_string(),
+                        stream.codec.clone(),
+                        stream.detail(),
+                        stream
+                            .duration_ms
+                            .map(|ms| format_timestamp(ms as f64 / 1000.0))
+                            .unwrap_or_default(),
+                        stream.bit_rate.map(format_bit_rate).unwrap_or_default(),
+                        stream.language.clone().unwrap_or_default(),
+                    ]
+                })
+                .collect(),
+        });
+    }
+    PreviewContent::Sections(parts)
+}
+
+/// The report as plain text, for the run output.
+fn report_text(media: &MediaInfo, file: &str) -> String {
+    let mut facts = Vec::new();
+    facts.extend(media.container.clone());
+    facts.extend(media.duration.map(format_timestamp));
+    facts.extend(
+        media
+            .size
+            .map(|size| format!("{:.1} MB", size as f64 / 1_000_000.0)),
+    );
+    facts.extend(media.bit_rate.map(format_bit_rate));
+    let mut lines = vec![format!("{}: {}", file, facts.join(", "))];
+    lines.extend(media.streams.iter().map(|stream| {
+        let mut line = format!("  {}", stream.describe());
+        if let Some((width, height)) = stream.resolution {
+            line.push_str(&format!(" {}x{}", width, height));
+        }
+        if let Some(ms) = stream.duration_ms {
+            line.push_str(&format!(" {}", format_timestamp(ms as f64 / 1000.0)));
+        }
+        if let Some(bit_rate) = stream.bit_rate {
+            line.push_str(&format!(" {}", format_bit_rate(bit_rate)));
+        }
+        line
+    }));
+    lines.join("\n")
+}
+
+async fn probe(cmd: &str) -> Result<(MediaInfo, String)> {
+    let file = parse_mediainfo_command(cmd)?;
+    let path = working_dir()?.join(&file);
+    let media = probe_media(&path.to_string_lossy()).await?;
+    Ok((media, file))
+}
+
+/// Stream and container facts of a media file, built in; uses ffprobe.
+#[derive(Default)]
+pub struct MediaInfoPlugin {
+    settings: PluginSettings,
+}
+
+#[async_trait]
+impl Plugin for MediaInfoPlugin {
+    fn name(&self) -> &str {
+        "mediainfo"
+    }
+
+    fn description(&self) -> &str {
+        "Show the streams, codecs, duration and bitrate of a video or audio file, built into Dexter."
+    }
+
+    fn configure(&mut self, settings: PluginSettings) {
+        self.settings = settings;
+    }
+
+    fn settings(&self) -> &PluginSettings {
+        &self.settings
+    }
+
+    fn capabilities(&self) -> &[Capability] {
+        &[Capability::MediaVideo, Capability::MediaAudio]
+    }
+
+    fn binaries(&self) -> &[&str] {
+        &["ffprobe"]
+    }
+
+    fn built_in(&self) -> bool {
+        true
+    }
+
+    async fn is_installed(&self) -> bool {
+        find_in_path("ffprobe").is_some()
+    }
+
+    /// ffprobe ships with ffmpeg.
+    fn packages(&self) -> &[Package] {
+        PACKAGES
+    }
+
+    fn get_doc_for_router(&self) -> &str {
+        "Best for questions about one video or audio file: its streams, codecs, resolution, duration and bitrate. Changes nothing; not for converting or editing media (ffmpeg)."
+    }
+
+    fn get_doc_for_executor(&self) -> &str {
+        r#"mediainfo Usage (built into Dexter):
+- Streams, codecs, duration and bitrate of a file: mediainfo video.mkv
+- A name with spaces: mediainfo "My Clip.mp4"
+
+Notes:
+1. Exactly one file, no options.
+2. It only reads the file; nothing is written."#
+    }
+
+    fn get_executor_prompt_with_doc(&self, doc: &str, context: &str, user_input: &str) -> String {
+        format!(
+            r#"You are the Media Info Specialist Agent for Dexter.
+Your goal is to generate ONE `mediainfo` command.
+
+### HARD CONSTRAINTS (MUST FOLLOW):
+1. OUTPUT ONLY: Output ONLY the command. No backticks, no markdown, no explanations.
+2. NO SHELL CHAINS: Do NOT use pipes, redirection, `&&`, `||`, `;`, backticks, or `$()`.
+3. SYNTAX: `mediainfo FILE`.
+4. PRECISION: Treat file names as literal strings from context; quote names with spaces.
+
+### Documentation:
+{}
+
+### Context:
+{}
+
+### User Request:
+{}
+"#,
+            doc, context, user_input
+        )
+    }
+
+    fn direct_command(&self, user_input: &str, files: &[String]) -> Option<String> {
+        let file = info_request(user_input)?;
+        files
+            .contains(&file)
+            .then(|| format!("mediainfo {}", shell_words::quote(&file)))
+    }
+
+    fn validate_command(&self, cmd: &str) -> bool {
+        parse_mediainfo_command(cmd).is_ok()
+    }
+
+    async fn dry_run(&self, cmd: &str, _llm: Option<&dyn LlmBridge>) -> Result<PreviewContent> {
+        let (media, file) = probe(cmd).await?;
+        Ok(report(&media, &file))
+    }
+
+    async fn execute(&self, cmd: &str) -> Result<String> {
+        let (media, file) = probe(cmd).await?;
+        Ok(report_text(&media, &file))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffmpeg::parse_ffprobe_media;
+
+    #[test]
+    fn reads_info_requests_without_the_model() {
+        assert_eq!(
+            info_request("show me info about video.mkv").as_deref(),
+            Some("video.mkv")
+        );
+        assert_eq!(
+            info_request("what's in \"Holiday 2024.MP4\"?").as_deref(),
+            Some("Holiday 2024.MP4")
+        );
+        assert_eq!(
+            info_request("how long is song.flac?").as_deref(),
+            Some("song.flac")
+        );
+        assert_eq!(info_request("convert video.mkv to mp4"), None);
+        assert_eq!(info_request("show the codec of a.mkv and b.mkv"), None);
+        assert_eq!(info_request("info about notes.txt"), None);
+        assert_eq!(info_request("play video.mkv"), None);
+        assert_eq!(info_request("reduce the bitrate of movie.mkv"), None);
+        assert_eq!(info_request("lower the bitrate of movie.mkv"), None);
+        assert_eq!(
+            info_request("what's the best way to shrink the bitrate of movie.mkv?"),
+            None
+        );
+        assert_eq!(
+            info_request("what is the bitrate of movie.mkv").as_deref(),
+            Some("movie.mkv")
+        );
+
+        assert_eq!(
+            parse_mediainfo_command("mediainfo 'My Clip.mp4'").unwrap(),
+            "My Clip.mp4"
+        );
+        assert!(parse_mediainfo_command("mediainfo a.mkv b.mkv").is_err());
+        assert!(parse_mediainfo_command("mediainfo -v a.mkv").is_err());
+        assert!(parse_mediainfo_command("ffprobe a.mkv").is_err());
+    }
+
+    #[test]
+    fn tabulates_stream_duration_and_bitrate() {
+        let json = r#"{"streams":[
+            {"index":0,"codec_type":"video","codec_name":"hevc","width":3840,"height":2160,
+             "duration":"90.000","bit_rate":"12000000"},
+            {"index":1,"codec_type":"audio","codec_name":"opus","channels":6,
+             "tags":{"language":"eng"}}
+        ],"format":{"format_long_name":"Matroska / WebM","duration":"90.04",
+                    "size":"135000000","bit_rate":"12100000"}}"#;
+        let media = parse_ffprobe_media(json).unwrap();
+        let PreviewContent::Sections(parts) = report(&media, "clip.mkv") else {
+            panic!("expected sections");
+        };
+        let PreviewContent::Table { title, rows, .. } = &parts[1] else {
+            panic!("expected stream table");
+        };
+        assert_eq!(title, "STREAMS (2)");
+        assert_eq!(
+            rows[0],
+            [
+                "0",
+                "video",
+                "hevc",
+                "3840x2160",
+                "00:01:30",
+                "12000 kb/s",
+                ""
+            ]
+        );
+        assert_eq!(rows[1], ["1", "audio", "opus", "6ch", "", "", "eng"]);
+        assert_eq!(
+            report_text(&media, "clip.mkv").lines().next(),
+            Some("clip.mkv: Matroska / WebM, 00:01:30.040, 135.0 MB, 12100 kb/s")
+        );
+    }
+}
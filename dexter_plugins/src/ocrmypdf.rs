@@ -1,5 +1,9 @@
-use crate::command_exec::{parse_and_validate_command, spawn_checked_async, spawn_checked_piped};
-use crate::{LlmBridge, Plugin, PreviewContent, Progress};
+use crate::command_exec::{
+    exit_failure, parse_and_validate_command, spawn_checked_async, spawn_checked_piped, working_dir,
+};
+use crate::{
+    Capability, ExecMode, LlmBridge, Package, Plugin, PluginSettings, PreviewContent, Progress,
+};
 use anyhow::Result;
 use async_trait::async_trait;
 use regex::Regex;
@@ -7,7 +11,10 @@ use std::process::Command;
 use std::sync::Arc;
 use tokio::io::AsyncBufReadExt;
 
-pub struct OcrmypdfPlugin;
+#[derive(Default)]
+pub struct OcrmypdfPlugin {
+    settings: PluginSettings,
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum OcrMode {
@@ -105,6 +112,12 @@ fn validate_ocrmypdf_command(cmd: &str) -> bool {
     true
 }
 
+const PACKAGES: &[Package] = &[
+    Package::brew("ocrmypdf"),
+    Package::apt("ocrmypdf"),
+    Package::pipx("ocrmypdf"),
+];
+
 #[async_trait]
 impl Plugin for OcrmypdfPlugin {
     fn name(&self) -> &str {
@@ -115,6 +128,18 @@ impl Plugin for OcrmypdfPlugin {
         "OCR scanned PDFs into searchable PDF/PDF-A with language and cleanup controls."
     }
 
+    fn configure(&mut self, settings: PluginSettings) {
+        self.settings = settings;
+    }
+
+    fn settings(&self) -> &PluginSettings {
+        &self.settings
+    }
+
+    fn capabilities(&self) -> &[Capability] {
+        &[Capability::Documents, Capability::Ocr]
+    }
+
     async fn is_installed(&self) -> bool {
         Command::new("ocrmypdf")
             .arg("--version")
@@ -123,10 +148,12 @@ impl Plugin for OcrmypdfPlugin {
             .unwrap_or(false)
     }
 
-    async fn install(&self) -> Result<()> {
-        Err(anyhow::anyhow!(
-            "Please install OCRmyPDF manually:\n- macOS (brew): brew install ocrmypdf\n- Debian/Ubuntu: sudo apt install ocrmypdf\n- pipx: pipx install ocrmypdf"
-        ))
+    fn packages(&self) -> &[Package] {
+        PACKAGES
+    }
+
+    fn install_note(&self) -> Option<&str> {
+        Some("The pipx package also needs Tesseract and Ghostscript.")
     }
 
     fn get_doc_for_router(&self) -> &str {
@@ -152,7 +179,7 @@ Safety Constraints:
 6. Use input/output file arguments directly (no shell redirection)."#
     }
 
-    fn get_executor_prompt(&self, context: &str, user_input: &str) -> String {
+    fn get_executor_prompt_with_doc(&self, doc: &str, context: &str, user_input: &str) -> String {
         format!(
             r#"You are the OCR Specialist Agent for Dexter.
 Your goal is to generate a valid `ocrmypdf` command.
@@ -175,9 +202,7 @@ Your goal is to generate a valid `ocrmypdf` command.
 ### User Request:
 {}
 "#,
-            self.get_doc_for_executor(),
-            context,
-            user_input
+            doc, context, user_input
         )
     }
 
@@ -198,6 +223,18 @@ Your goal is to generate a valid `ocrmypdf` command.
         }
     }
 
+    fn command_argv(&self, cmd: &str, _mode: ExecMode) -> Result<Vec<String>> {
+        let argv = parse_and_validate_command(cmd, "ocrmypdf")?;
+        if !validate_ocrmypdf_command(cmd) {
+            return Err(anyhow::anyhow!("Command failed ocrmypdf validation logic"));
+        }
+        Ok(argv)
+    }
+
+    fn retry_argv(&self, argv: &[String]) -> Option<Vec<String>> {
+        may_retry_with_skip_text(argv).then(|| inject_skip_text_arg(argv))
+    }
+
     async fn execute(&self, cmd: &str) -> Result<String> {
         self.execute_with_progress(cmd, tokio::sync::mpsc::channel(1).0)
             .await
@@ -208,10 +245,7 @@ Your goal is to generate a valid `ocrmypdf` command.
         cmd: &str,
         progress_tx: tokio::sync::mpsc::Sender<Progress>,
     ) -> Result<String> {
-        let argv = parse_and_validate_command(cmd, "ocrmypdf")?;
-        if !validate_ocrmypdf_command(cmd) {
-            return Err(anyhow::anyhow!("Command failed ocrmypdf validation logic"));
-        }
+        let argv = self.command_argv(cmd, ExecMode::Apply)?;
 
         let _ = progress_tx
             .send(Progress {
@@ -220,7 +254,7 @@ Your goal is to generate a valid `ocrmypdf` command.
             })
             .await;
 
-        let cwd = std::env::current_dir()?;
+        let cwd = working_dir()?;
         let mut child = spawn_checked_piped(&argv, &cwd)?;
 
         let stderr = child
@@ -313,29 +347,37 @@ Your goal is to generate a valid `ocrmypdf` command.
                 );
                 Ok(combined)
             } else {
-                Err(anyhow::anyhow!(
-                    "ocrmypdf error (initial + retry with --skip-text):\ninitial:\n{}\nretry:\n{}\n{}",
-                    err_output,
-                    retry_stdout,
-                    retry_stderr
+                Err(exit_failure(
+                    retry_output.status,
+                    format!(
+                        "ocrmypdf error (initial + retry with --skip-text):\ninitial:\n{}\nretry:\n{}\n{}",
+                        err_output, retry_stdout, retry_stderr
+                    ),
                 ))
             }
         } else {
-            Err(anyhow::anyhow!("ocrmypdf error:\n{}", err_output))
+            Err(exit_failure(
+                status,
+                format!("ocrmypdf error:\n{}", err_output),
+            ))
         }
     }
 }
 
 fn should_retry_with_skip_text(argv: &[String], err_output: &str) -> bool {
-    if contains_flag(argv, "--skip-text")
+    may_retry_with_skip_text(argv) && has_text_layer_error(err_output)
+}
+
+/// Whether `argv` leaves the OCR mode open, so a failure on existing text
+/// can be retried with `--skip-text`.
+fn may_retry_with_skip_text(argv: &[String]) -> bool {
+    let explicit = contains_flag(argv, "--skip-text")
         || contains_flag(argv, "--force-ocr")
-        || contains_flag(argv, "--redo-ocr")
-    {
-        return false;
-    }
-    if parse_mode(argv) == Some(OcrMode::Force) || parse_mode(argv) == Some(OcrMode::Redo) {
-        return false;
-    }
+        || contains_flag(argv, "--redo-ocr");
+    !explicit && !matches!(parse_mode(argv), Some(OcrMode::Force | OcrMode::Redo))
+}
+
+fn has_text_layer_error(err_output: &str) -> bool {
     let err = err_output.to_lowercase();
     err.contains("already has text")
         || err.contains("priorocrfounderror")
@@ -1,12 +1,168 @@
-use crate::command_exec::{parse_and_validate_command, spawn_checked_async};
-use crate::{LlmBridge, Plugin, PreviewContent, Progress};
+use crate::command_exec::{
+    exit_failure, expand_home, parse_and_validate_command, spawn_checked_async, working_dir,
+};
+use crate::{
+    Capability, ExecMode, LlmBridge, Package, Plugin, PluginSettings, PreviewContent, Progress,
+};
 use anyhow::Result;
 use async_trait::async_trait;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::OnceLock;
 
-pub struct PandocPlugin;
+/// A named bundle of pandoc arguments the executor model can pick with
+/// `--preset=<name>`. Dexter expands the marker before validation and
+/// execution, and refuses to run while a required asset is missing.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct PandocPreset {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub args: Vec<String>,
+    /// Templates, reference docs, CSL files and the like (`~/` is expanded).
+    #[serde(default)]
+    pub required_files: Vec<String>,
+    /// Font families that must be known to fontconfig.
+    #[serde(default)]
+    pub required_fonts: Vec<String>,
+}
+
+pub fn default_pandoc_presets() -> Vec<PandocPreset> {
+    let preset = |name: &str, description: &str, args: &[&str]| PandocPreset {
+        name: name.to_string(),
+        description: description.to_string(),
+        args: args.iter().map(|a| a.to_string()).collect(),
+        required_files: Vec::new(),
+        required_fonts: Vec::new(),
+    };
+    vec![
+        preset(
+            "academic-pdf",
+            "Paper-style PDF with numbered sections and a table of contents",
+            &["--number-sections", "--toc", "-V", "geometry:margin=1in"],
+        ),
+        preset(
+            "ebook-epub",
+            "EPUB ebook with a two-level table of contents",
+            &["--toc", "--toc-depth=2"],
+        ),
+        preset(
+            "slides",
+            "Standalone reveal.js HTML slide deck",
+            &["-t", "revealjs", "-s"],
+        ),
+    ]
+}
+
+#[derive(Default)]
+pub struct PandocPlugin {
+    presets: Vec<PandocPreset>,
+    /// What `~` in a preset's `required_files` stands for.
+    home: Option<PathBuf>,
+    settings: PluginSettings,
+}
+
+impl PandocPlugin {
+    pub fn new(presets: Vec<PandocPreset>, home: Option<PathBuf>) -> Self {
+        Self {
+            presets,
+            home,
+            settings: PluginSettings::default(),
+        }
+    }
+
+    fn preset(&self, name: &str) -> Option<&PandocPreset> {
+        self.presets.iter().find(|p| p.name == name)
+    }
+
+    /// Replaces every `--preset=<name>` / `--preset <name>` with the preset's
+    /// arguments, returning the expanded command and the presets used.
+    fn expand_presets(&self, cmd: &str) -> Result<(String, Vec<&PandocPreset>)> {
+        let argv = shell_words::split(cmd)
+            .map_err(|e| anyhow::anyhow!("Invalid command syntax: {}", e))?;
+        let mut out = Vec::with_capacity(argv.len());
+        let mut used = Vec::new();
+        let mut iter = argv.into_iter();
+        while let Some(arg) = iter.next() {
+            let name = match arg.strip_prefix("--preset") {
+                Some(rest) if rest.starts_with('=') => rest[1..].to_string(),
+                Some("") => iter
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--preset needs a preset name"))?,
+                _ => {
+                    out.push(arg);
+                    continue;
+                }
+            };
+            let preset = self
+                .preset(&name)
+                .ok_or_else(|| anyhow::anyhow!("Unknown pandoc preset: {}", name))?;
+            out.extend(preset.args.iter().cloned());
+            used.push(preset);
+        }
+        Ok((shell_words::join(&out), used))
+    }
+
+    fn presets_prompt(&self) -> String {
+        if self.presets.is_empty() {
+            return "(none configured)".to_string();
+        }
+        self.presets
+            .iter()
+            .map(|p| {
+                format!(
+                    "- --preset={}: {} (expands to: {})",
+                    p.name,
+                    p.description,
+                    shell_words::join(&p.args)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Lists preset assets that cannot be found. Fonts are only checked when
+/// fontconfig's `fc-list` is available.
+fn missing_preset_assets(presets: &[&PandocPreset], home: Option<&Path>) -> Vec<String> {
+    let mut missing = Vec::new();
+    for preset in presets {
+        for file in &preset.required_files {
+            if !expand_home(file, home).exists() {
+                missing.push(format!("{} (file for preset {})", file, preset.name));
+            }
+        }
+    }
+
+    let fonts: Vec<(&str, &str)> = presets
+        .iter()
+        .flat_map(|p| {
+            p.required_fonts
+                .iter()
+                .map(|f| (f.as_str(), p.name.as_str()))
+        })
+        .collect();
+    if fonts.is_empty() {
+        return missing;
+    }
+    let Ok(output) = Command::new("fc-list").args([":", "family"]).output() else {
+        return missing;
+    };
+    let installed = String::from_utf8_lossy(&output.stdout).to_lowercase();
+    let installed: Vec<&str> = installed
+        .lines()
+        .flat_map(|line| line.split(','))
+        .map(str::trim)
+        .collect();
+    for (font, preset) in fonts {
+        if !installed.contains(&font.to_lowercase().as_str()) {
+            missing.push(format!("{} (font for preset {})", font, preset));
+        }
+    }
+    missing
+}
 
 fn strip_surrounding_quotes(value: &str) -> &str {
     let bytes = value.as_bytes();
@@ -39,10 +195,36 @@ fn extract_output_path(cmd: &str) -> Option<String> {
     Some(strip_surrounding_quotes(raw).to_string())
 }
 
+/// Long options that make pandoc run another program.
+const PROGRAM_OPTIONS: [&str; 4] = [
+    "--filter",
+    "--lua-filter",
+    "--pdf-engine",
+    "--pdf-engine-opt",
+];
+
+/// The first of `args` that makes pandoc run another program: a filter
+/// (`-F`, `-L`, `--filter`, `--lua-filter`) or a PDF engine, however it is
+/// spelled, abbreviated long options and short clusters included. Presets
+/// from a shared bundle with one aren't imported.
+pub fn program_running_arg(args: &[String]) -> Option<&str> {
+    args.iter().map(String::as_str).find(|arg| {
+        if let Some(long) = arg.strip_prefix("--") {
+            let name = format!("--{}", long.split('=').next().unwrap_or_default());
+            name.len() > 2
+                && PROGRAM_OPTIONS
+                    .iter()
+                    .any(|full| full.starts_with(&name) || name.starts_with(full))
+        } else {
+            arg.len() > 1 && arg.starts_with('-') && arg[1..].contains(['F', 'L'])
+        }
+    })
+}
+
 fn validate_pandoc_command(cmd: &str) -> bool {
-    if parse_and_validate_command(cmd, "pandoc").is_err() {
+    let Ok(argv) = parse_and_validate_command(cmd, "pandoc") else {
         return false;
-    }
+    };
 
     let trimmed = cmd.trim();
 
@@ -54,7 +236,15 @@ fn validate_pandoc_command(cmd: &str) -> bool {
     static BANNED_ARGS_RE: OnceLock<Regex> = OnceLock::new();
     let banned_args_re =
         BANNED_ARGS_RE.get_or_init(|| Regex::new(r"(?i)(^|\s)--(lua-)?filter(\s|=|$)").unwrap());
-    if banned_args_re.is_match(trimmed) {
+    // Check tokens too: a quoted `'--lua-filter=x'` hides from the raw scan.
+    if banned_args_re.is_match(trimmed) || argv.iter().any(|a| banned_args_re.is_match(a)) {
+        return false;
+    }
+    // `-F` and `-L` are the short spellings.
+    if argv[1..]
+        .iter()
+        .any(|a| a.starts_with("-F") || a.starts_with("-L"))
+    {
         return false;
     }
 
@@ -77,6 +267,13 @@ fn validate_pandoc_command(cmd: &str) -> bool {
     true
 }
 
+const PACKAGES: &[Package] = &[
+    Package::brew("pandoc"),
+    Package::apt("pandoc"),
+    Package::winget("JohnMacFarlane.Pandoc"),
+    Package::scoop("pandoc"),
+];
+
 #[async_trait]
 impl Plugin for PandocPlugin {
     fn name(&self) -> &str {
@@ -87,6 +284,18 @@ impl Plugin for PandocPlugin {
         "A universal document converter (Markdown/DOCX/HTML/PDF and more)."
     }
 
+    fn configure(&mut self, settings: PluginSettings) {
+        self.settings = settings;
+    }
+
+    fn settings(&self) -> &PluginSettings {
+        &self.settings
+    }
+
+    fn capabilities(&self) -> &[Capability] {
+        &[Capability::Documents]
+    }
+
     async fn is_installed(&self) -> bool {
         Command::new("pandoc")
             .arg("--version")
@@ -95,10 +304,12 @@ impl Plugin for PandocPlugin {
             .unwrap_or(false)
     }
 
-    async fn install(&self) -> Result<()> {
-        Err(anyhow::anyhow!(
-            "Please install pandoc manually:\n- macOS (brew): brew install pandoc\n- Windows (choco): choco install pandoc\n- Linux: use your package manager (e.g. apt/yum/pacman)\n\nNote: PDF output often requires a TeX engine (e.g. MacTeX/TeX Live)."
-        ))
+    fn packages(&self) -> &[Package] {
+        PACKAGES
+    }
+
+    fn install_note(&self) -> Option<&str> {
+        Some("PDF output often requires a TeX engine (e.g. MacTeX/TeX Live).")
     }
 
     fn get_doc_for_router(&self) -> &str {
@@ -118,7 +329,7 @@ Notes:
 3. PDF output may require a TeX engine (e.g. MacTeX/TeX Live) to be installed."#
     }
 
-    fn get_executor_prompt(&self, context: &str, user_input: &str) -> String {
+    fn get_executor_prompt_with_doc(&self, doc: &str, context: &str, user_input: &str) -> String {
         format!(
             r#"You are the Document Conversion Specialist Agent for Dexter.
 Your goal is to generate a valid `pandoc` command.
@@ -135,33 +346,63 @@ Your goal is to generate a valid `pandoc` command.
 ### Documentation:
 {}
 
+### Presets:
+Prefer a preset when the request matches one; write it as `--preset=<name>` in
+place of its arguments and Dexter expands it. You may add other options alongside.
+{}
+
 ### Context:
 {}
 
 ### User Request:
 {}
 "#,
-            self.get_doc_for_executor(),
+            doc,
+            self.presets_prompt(),
             context,
             user_input
         )
     }
 
     fn validate_command(&self, cmd: &str) -> bool {
-        validate_pandoc_command(cmd)
+        match self.expand_presets(cmd) {
+            Ok((expanded, _)) => validate_pandoc_command(&expanded),
+            Err(_) => false,
+        }
     }
 
     async fn dry_run(&self, cmd: &str, llm: Option<&dyn LlmBridge>) -> Result<PreviewContent> {
-        if let Some(llm) = llm {
+        let (expanded, presets) = self.expand_presets(cmd)?;
+        let mut text = if let Some(llm) = llm {
             let system_prompt = "You are a clear and concise command explainer for Dexter. Describe what this pandoc command will do in simple terms. Mention input file(s), output file, and the output format. If output is PDF, mention that a TeX engine may be required. Output plain text only.";
-            let text = llm.chat(system_prompt, cmd).await?;
-            Ok(PreviewContent::Text(text))
+            llm.chat(system_prompt, &expanded).await?
         } else {
-            Ok(PreviewContent::Text(format!(
-                "Executing document conversion: {}",
-                cmd
-            )))
+            format!("Executing document conversion: {}", expanded)
+        };
+
+        if !presets.is_empty() {
+            text.push_str(&format!("\n\nExpanded command: {}", expanded));
+            let missing = missing_preset_assets(&presets, self.home.as_deref());
+            if !missing.is_empty() {
+                text.push_str(&format!(
+                    "\n\nWARNING: execution will be refused until these preset assets exist:\n- {}",
+                    missing.join("\n- ")
+                ));
+            }
         }
+        Ok(PreviewContent::Text(text))
+    }
+
+    fn command_argv(&self, cmd: &str, _mode: ExecMode) -> Result<Vec<String>> {
+        let (expanded, presets) = self.expand_presets(cmd)?;
+        let missing = missing_preset_assets(&presets, self.home.as_deref());
+        if !missing.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Missing preset assets:\n- {}",
+                missing.join("\n- ")
+            ));
+        }
+        parse_and_validate_command(&expanded, "pandoc")
     }
 
     async fn execute(&self, cmd: &str) -> Result<String> {
@@ -181,8 +422,8 @@ Your goal is to generate a valid `pandoc` command.
             })
             .await;
 
-        let argv = parse_and_validate_command(cmd, "pandoc")?;
-        let cwd = std::env::current_dir()?;
+        let argv = self.command_argv(cmd, ExecMode::Apply)?;
+        let cwd = working_dir()?;
         let output = spawn_checked_async(&argv, cwd).await?;
 
         let stdout = String::from_utf8_lossy(&output.stdout).to_string();
@@ -191,7 +432,10 @@ Your goal is to generate a valid `pandoc` command.
         if output.status.success() {
             Ok(format!("{}{}", stdout, stderr))
         } else {
-            Err(anyhow::anyhow!("pandoc error: {}\n{}", stdout, stderr))
+            Err(exit_failure(
+                output.status,
+                format!("pandoc error: {}\n{}", stdout, stderr),
+            ))
         }
     }
 }
@@ -238,6 +482,91 @@ mod tests {
         assert!(!validate_pandoc_command(
             "pandoc input.md -o out.html --lua-filter=my.lua"
         ));
+        assert!(!validate_pandoc_command("pandoc input.md -o out.html -F x"));
+        assert!(!validate_pandoc_command(
+            "pandoc input.md -o out.html -Lx.lua"
+        ));
+    }
+
+    #[test]
+    fn finds_args_that_run_programs() {
+        let args = |args: &[&str]| -> Vec<String> { args.iter().map(|a| a.to_string()).collect() };
+        for bad in [
+            &["-F", "x"][..],
+            &["-L", "x.lua"],
+            &["-Lx.lua"],
+            &["-sF", "x"],
+            &["--filter=x"],
+            &["--lua-filter", "x.lua"],
+            &["--pdf-engine=./payload"],
+            &["--pdf-engine-opt=-shell-escape"],
+            &["--pdf-eng=./payload"],
+            &["--lua-f=x.lua"],
+        ] {
+            assert!(program_running_arg(&args(bad)).is_some(), "{:?}", bad);
+        }
+        for good in [
+            &["--toc", "--number-sections"][..],
+            &["-V", "geometry:margin=1in"],
+            &["-t", "revealjs", "-s"],
+            &["--template=thesis.tex"],
+        ] {
+            assert_eq!(program_running_arg(&args(good)), None, "{:?}", good);
+        }
+    }
+
+    fn plugin_with(preset: PandocPreset) -> PandocPlugin {
+        PandocPlugin::new(vec![preset], None)
+    }
+
+    fn preset(args: &[&str]) -> PandocPreset {
+        PandocPreset {
+            name: "paper".to_string(),
+            description: String::new(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            required_files: Vec::new(),
+            required_fonts: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn expands_presets_in_both_spellings() {
+        let plugin = plugin_with(preset(&["--toc", "-V", "mainfont=Noto Serif"]));
+        let (expanded, used) = plugin
+            .expand_presets("pandoc in.md --preset=paper -o out.pdf")
+            .unwrap();
+        assert_eq!(
+            expanded,
+            "pandoc in.md --toc -V 'mainfont=Noto Serif' -o out.pdf"
+        );
+        assert_eq!(used.len(), 1);
+
+        let (expanded, _) = plugin
+            .expand_presets("pandoc --preset paper in.md -o out.pdf")
+            .unwrap();
+        assert_eq!(
+            expanded,
+            "pandoc --toc -V 'mainfont=Noto Serif' in.md -o out.pdf"
+        );
+    }
+
+    #[test]
+    fn unknown_or_unsafe_presets_fail_validation() {
+        let plugin = plugin_with(preset(&["--lua-filter=wordcount.lua"]));
+        assert!(!plugin.validate_command("pandoc in.md --preset=missing -o out.pdf"));
+        assert!(!plugin.validate_command("pandoc in.md --preset=paper -o out.pdf"));
+        assert!(PandocPlugin::default().validate_command("pandoc in.md -o out.pdf"));
+    }
+
+    #[test]
+    fn reports_missing_required_files() {
+        let mut with_assets = preset(&["--template=/nonexistent/paper.tex"]);
+        with_assets.required_files = vec!["/nonexistent/paper.tex".to_string()];
+        let missing = missing_preset_assets(&[&with_assets], None);
+        assert_eq!(
+            missing,
+            vec!["/nonexistent/paper.tex (file for preset paper)".to_string()]
+        );
     }
 
     #[test]
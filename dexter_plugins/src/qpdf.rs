@@ -1,10 +1,15 @@
-use crate::command_exec::{parse_and_validate_command, spawn_checked_async};
-use crate::{LlmBridge, Plugin, PreviewContent, Progress};
+use crate::command_exec::{
+    exit_failure, parse_and_validate_command, spawn_checked_async, working_dir,
+};
+use crate::{Capability, LlmBridge, Package, Plugin, PluginSettings, PreviewContent, Progress};
 use anyhow::Result;
 use async_trait::async_trait;
 use std::process::Command;
 
-pub struct QpdfPlugin;
+#[derive(Default)]
+pub struct QpdfPlugin {
+    settings: PluginSettings,
+}
 
 fn contains_flag(argv: &[String], flag: &str) -> bool {
     argv.iter().any(|a| a == flag)
@@ -80,6 +85,13 @@ fn validate_qpdf_command(cmd: &str) -> bool {
     true
 }
 
+const PACKAGES: &[Package] = &[
+    Package::brew("qpdf"),
+    Package::apt("qpdf"),
+    Package::winget("QPDF.QPDF"),
+    Package::scoop("qpdf"),
+];
+
 #[async_trait]
 impl Plugin for QpdfPlugin {
     fn name(&self) -> &str {
@@ -90,6 +102,18 @@ impl Plugin for QpdfPlugin {
         "PDF structural transformations: check, linearize, encrypt/decrypt, and page selection."
     }
 
+    fn configure(&mut self, settings: PluginSettings) {
+        self.settings = settings;
+    }
+
+    fn settings(&self) -> &PluginSettings {
+        &self.settings
+    }
+
+    fn capabilities(&self) -> &[Capability] {
+        &[Capability::Documents]
+    }
+
     async fn is_installed(&self) -> bool {
         Command::new("qpdf")
             .arg("--version")
@@ -98,10 +122,8 @@ impl Plugin for QpdfPlugin {
             .unwrap_or(false)
     }
 
-    async fn install(&self) -> Result<()> {
-        Err(anyhow::anyhow!(
-            "Please install qpdf manually:\n- macOS (brew): brew install qpdf\n- Debian/Ubuntu: sudo apt install qpdf\n- Windows (choco): choco install qpdf"
-        ))
+    fn packages(&self) -> &[Package] {
+        PACKAGES
     }
 
     fn get_doc_for_router(&self) -> &str {
@@ -124,7 +146,7 @@ Safety Constraints:
 5. Encryption must use --bits=256."#
     }
 
-    fn get_executor_prompt(&self, context: &str, user_input: &str) -> String {
+    fn get_executor_prompt_with_doc(&self, doc: &str, context: &str, user_input: &str) -> String {
         format!(
             r#"You are the PDF Structure Specialist Agent for Dexter.
 Your goal is to generate a valid `qpdf` command.
@@ -151,9 +173,7 @@ Your goal is to generate a valid `qpdf` command.
 ### User Request:
 {}
 "#,
-            self.get_doc_for_executor(),
-            context,
-            user_input
+            doc, context, user_input
         )
     }
 
@@ -210,7 +230,7 @@ Your goal is to generate a valid `qpdf` command.
             })
             .await;
 
-        let cwd = std::env::current_dir()?;
+        let cwd = working_dir()?;
         let output = spawn_checked_async(&argv, cwd).await?;
 
         let stdout = String::from_utf8_lossy(&output.stdout).to_string();
@@ -219,7 +239,10 @@ Your goal is to generate a valid `qpdf` command.
         if output.status.success() {
             Ok(format!("{}{}", stdout, stderr))
         } else {
-            Err(anyhow::anyhow!("qpdf error: {}\n{}", stdout, stderr))
+            Err(exit_failure(
+                output.status,
+                format!("qpdf error: {}\n{}", stdout, stderr),
+            ))
         }
     }
 }
@@ -0,0 +1,268 @@
+//! Runs plugin commands under an OS sandbox: bubblewrap or firejail on
+//! Linux, `sandbox-exec` on macOS. Inside it the filesystem is read-only
+//! except for the target directory and a private temp dir, secret
+//! directories such as `~/.ssh` are hidden, and the network is cut unless
+//! the sandbox allows it.
+//!
+//! Plugins don't see any of this: `scope` makes a sandbox current for one
+//! execution, and the `command_exec` spawn helpers wrap every argv they
+//! start while it is.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+use crate::health::find_in_path;
+
+/// Which sandbox tool to use; `auto` takes the first one installed.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SandboxBackend {
+    #[default]
+    Auto,
+    Bubblewrap,
+    Firejail,
+    SandboxExec,
+}
+
+impl SandboxBackend {
+    /// Tried in order by `auto`.
+    const PREFERRED: [SandboxBackend; 3] = [
+        SandboxBackend::Bubblewrap,
+        SandboxBackend::Firejail,
+        SandboxBackend::SandboxExec,
+    ];
+
+    pub fn binary(self) -> &'static str {
+        match self {
+            SandboxBackend::Auto => "",
+            SandboxBackend::Bubblewrap => "bwrap",
+            SandboxBackend::Firejail => "firejail",
+            SandboxBackend::SandboxExec => "sandbox-exec",
+        }
+    }
+
+    fn supported(self) -> bool {
+        match self {
+            SandboxBackend::Auto => true,
+            SandboxBackend::Bubblewrap | SandboxBackend::Firejail => cfg!(target_os = "linux"),
+            SandboxBackend::SandboxExec => cfg!(target_os = "macos"),
+        }
+    }
+
+    /// The tool to run: this one, or for `auto` the first supported one
+    /// found in `$PATH`. Errors rather than fall back to running unsandboxed.
+    pub fn resolve(self) -> Result<SandboxBackend> {
+        let candidates: Vec<SandboxBackend> = match self {
+            SandboxBackend::Auto => Self::PREFERRED.to_vec(),
+            backend => vec![backend],
+        };
+        candidates
+            .into_iter()
+            .find(|backend| backend.supported() && find_in_path(backend.binary()).is_some())
+            .ok_or_else(|| match self {
+                SandboxBackend::Auto => anyhow!(
+                    "Sandbox unavailable: install bubblewrap or firejail (Linux); \
+                     macOS ships sandbox-exec"
+                ),
+                backend if !backend.supported() => {
+                    anyhow!(
+                        "Sandbox unavailable: {} is not supported on this system",
+                        backend.binary()
+                    )
+                }
+                backend => anyhow!(
+                    "Sandbox unavailable: {} not found in PATH",
+                    backend.binary()
+                ),
+            })
+    }
+}
+
+/// One resolved sandbox: the tool, the only directory commands may write
+/// to, whether they may reach the network, and directories they may not
+/// even read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sandbox {
+    pub backend: SandboxBackend,
+    pub root: PathBuf,
+    pub network: bool,
+    pub hidden: Vec<PathBuf>,
+}
+
+impl Sandbox {
+    /// `argv` prefixed with the sandbox tool and its flags.
+    pub fn wrap(&self, argv: &[String]) -> Vec<String> {
+        let root = self.root.to_string_lossy().into_owned();
+        let mut wrapped: Vec<String> = match self.backend {
+            SandboxBackend::Bubblewrap => {
+                let mut args = vec![
+                    "bwrap",
+                    "--ro-bind",
+                    "/",
+                    "/",
+                    "--dev",
+                    "/dev",
+                    "--proc",
+                    "/proc",
+                    "--tmpfs",
+                    "/tmp",
+                ]
+                .into_iter()
+                .map(String::from)
+                .collect::<Vec<_>>();
+                args.extend(["--bind".to_string(), root.clone(), root.clone()]);
+                for dir in &self.hidden {
+                    args.extend(["--tmpfs".to_string(), dir.to_string_lossy().into_owned()]);
+                }
+                if !self.network {
+                    args.push("--unshare-net".to_string());
+                }
+                args.extend(["--die-with-parent".to_string(), "--chdir".to_string(), root]);
+                args.push("--".to_string());
+                args
+            }
+            SandboxBackend::Firejail => {
+                let mut args = vec![
+                    "firejail".to_string(),
+                    "--quiet".to_string(),
+                    "--noprofile".to_string(),
+                    "--private-tmp".to_string(),
+                    "--read-only=/".to_string(),
+                    format!("--read-write={}", root),
+                ];
+                args.extend(
+                    self.hidden
+                        .iter()
+                        .map(|dir| format!("--blacklist={}", dir.display())),
+                );
+                if !self.network {
+                    args.push("--net=none".to_string());
+                }
+                args.push("--".to_string());
+                args
+            }
+            SandboxBackend::SandboxExec | SandboxBackend::Auto => {
+                vec![
+                    "sandbox-exec".to_string(),
+                    "-p".to_string(),
+                    self.seatbelt_profile(),
+                ]
+            }
+        };
+        wrapped.extend(argv.iter().cloned());
+        wrapped
+    }
+
+    /// macOS Seatbelt profile: reads allowed outside the hidden dirs,
+    /// writes only under the root, the temp dirs and `/dev`.
+    fn seatbelt_profile(&self) -> String {
+        let quote = |path: &Path| {
+            path.to_string_lossy()
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+        };
+        let mut profile = format!(
+            "(version 1)\n(allow default)\n(deny file-write*)\n\
+             (allow file-write* (subpath \"{}\") (subpath \"/private/tmp\") \
+             (subpath \"/private/var/folders\") (subpath \"/dev\"))\n",
+            quote(&self.root)
+        );
+        for dir in &self.hidden {
+            profile.push_str(&format!("(deny file-read* (subpath \"{}\"))\n", quote(dir)));
+        }
+        if !self.network {
+            profile.push_str("(deny network*)\n");
+        }
+        profile
+    }
+}
+
+tokio::task_local! {
+    static ACTIVE: Sandbox;
+}
+
+/// Runs `future` with `sandbox` current, so every command the plugin spawns
+/// inside it is wrapped. `None` runs it as is.
+pub async fn scope<F: Future>(sandbox: Option<Sandbox>, future: F) -> F::Output {
+    match sandbox {
+        Some(sandbox) => ACTIVE.scope(sandbox, future).await,
+        None => future.await,
+    }
+}
+
+/// `argv` wrapped in the current sandbox, if any.
+pub(crate) fn wrap_active(argv: &[String]) -> Vec<String> {
+    ACTIVE
+        .try_with(|sandbox| sandbox.wrap(argv))
+        .unwrap_or_else(|_| argv.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn wraps_commands_for_each_backend() {
+        let command = argv(&["ffmpeg", "-i", "in.mp4", "out.webm"]);
+        let mut sandbox = Sandbox {
+            backend: SandboxBackend::Bubblewrap,
+            root: PathBuf::from("/home/me/clips"),
+            network: false,
+            hidden: vec![PathBuf::from("/home/me/.ssh")],
+        };
+        let wrapped = sandbox.wrap(&command);
+        assert_eq!(wrapped[..3], argv(&["bwrap", "--ro-bind", "/"]));
+        assert!(wrapped
+            .windows(3)
+            .any(|w| w == argv(&["--bind", "/home/me/clips", "/home/me/clips"])));
+        assert!(wrapped
+            .windows(2)
+            .any(|w| w == argv(&["--tmpfs", "/home/me/.ssh"])));
+        assert!(wrapped.contains(&"--unshare-net".to_string()));
+        assert_eq!(
+            wrapped[wrapped.len() - 5..],
+            argv(&["--", "ffmpeg", "-i", "in.mp4", "out.webm"])
+        );
+
+        sandbox.backend = SandboxBackend::Firejail;
+        sandbox.network = true;
+        let wrapped = sandbox.wrap(&command);
+        assert!(wrapped.contains(&"--read-write=/home/me/clips".to_string()));
+        assert!(wrapped.contains(&"--blacklist=/home/me/.ssh".to_string()));
+        assert!(!wrapped.iter().any(|arg| arg.starts_with("--net")));
+
+        sandbox.backend = SandboxBackend::SandboxExec;
+        sandbox.root = PathBuf::from("/Users/me/a \"b\"");
+        sandbox.network = false;
+        let wrapped = sandbox.wrap(&command);
+        assert_eq!(wrapped[..2], argv(&["sandbox-exec", "-p"]));
+        assert!(wrapped[2].contains("(subpath \"/Users/me/a \\\"b\\\"\")"));
+        assert!(wrapped[2].contains("(deny network*)"));
+        assert!(wrapped[2].contains("(deny file-read* (subpath \"/home/me/.ssh\"))"));
+        assert_eq!(wrapped[3..], command[..]);
+    }
+
+    #[test]
+    fn only_scoped_spawns_are_wrapped() {
+        let command = argv(&["f2", "-f", "a", "-r", "b"]);
+        assert_eq!(wrap_active(&command), command);
+        let sandbox = Sandbox {
+            backend: SandboxBackend::Firejail,
+            root: PathBuf::from("/srv"),
+            network: false,
+            hidden: Vec::new(),
+        };
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let wrapped = runtime.block_on(scope(Some(sandbox), async { wrap_active(&command) }));
+        assert_eq!(wrapped[0], "firejail");
+        assert_eq!(wrap_active(&command), command);
+    }
+}
@@ -0,0 +1,134 @@
+//! The user's settings for one plugin, the `[plugins.<name>]` tables of
+//! config.toml. They reach each plugin through `Plugin::configure`.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+pub struct PluginSettings {
+    /// Flags every generated command carries unless the request says
+    /// otherwise, written as on a command line: `-hide_banner -loglevel error`.
+    #[serde(default)]
+    pub default_flags: String,
+    /// Where new output files go unless the request names a place. A
+    /// project's `output_dir` wins over it.
+    #[serde(default)]
+    pub output_dir: Option<String>,
+    /// Existing outputs are renamed around instead of overwritten, whatever
+    /// `output_conflicts` says.
+    #[serde(default)]
+    pub never_overwrite: bool,
+    /// Instructions added to every executor prompt as written: `always use
+    /// two-pass encoding`, `never touch files starting with _`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prompt_append: Vec<String>,
+    /// Replaces the plugin's own executor prompt. `{doc}`, `{context}` and
+    /// `{request}` are filled in; the context and request are added at the
+    /// end when the template leaves them out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_override: Option<String>,
+}
+
+impl PluginSettings {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// The executor prompt's rules for these settings; empty without any.
+    pub fn prompt_rules(&self) -> String {
+        let mut rules = Vec::new();
+        if let Some(dir) = self
+            .output_dir
+            .as_deref()
+            .filter(|dir| !dir.trim().is_empty())
+        {
+            rules.push(format!(
+                "- Write new output files into `{}/` unless the user names another location.",
+                dir.trim().trim_end_matches('/')
+            ));
+        }
+        let flags = self.default_flags.trim();
+        if !flags.is_empty() {
+            rules.push(format!(
+                "- Include these flags unless the request contradicts them: `{}`",
+                flags
+            ));
+        }
+        if self.never_overwrite {
+            rules.push("- Never overwrite existing files; pick new output names.".to_string());
+        }
+        for fragment in self.prompt_fragments() {
+            rules.push(format!("- {}", fragment));
+        }
+        if rules.is_empty() {
+            return String::new();
+        }
+        format!("\n\nUser settings for this tool:\n{}", rules.join("\n"))
+    }
+
+    /// The non-empty `prompt_append` entries, trimmed.
+    pub fn prompt_fragments(&self) -> impl Iterator<Item = &str> {
+        self.prompt_append
+            .iter()
+            .map(|fragment| fragment.trim())
+            .filter(|fragment| !fragment.is_empty())
+    }
+
+    /// `prompt_override` with its placeholders filled in; `None` without
+    /// one, when the plugin's own prompt is used.
+    pub fn override_prompt(&self, doc: &str, context: &str, user_input: &str) -> Option<String> {
+        let template = self
+            .prompt_override
+            .as_deref()
+            .filter(|template| !template.trim().is_empty())?;
+        let mut prompt = template
+            .replace("{doc}", doc)
+            .replace("{context}", context)
+            .replace("{request}", user_input);
+        if !template.contains("{context}") {
+            prompt.push_str(&format!("\n\n### Context:\n{}", context));
+        }
+        if !template.contains("{request}") {
+            prompt.push_str(&format!("\n\n### User Request:\n{}", user_input));
+        }
+        Some(prompt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prompt_rules_cover_every_setting() {
+        assert_eq!(PluginSettings::default().prompt_rules(), "");
+        let settings = PluginSettings {
+            default_flags: " -hide_banner ".to_string(),
+            output_dir: Some("out".to_string()),
+            never_overwrite: true,
+            prompt_append: vec!["always use two-pass encoding".to_string(), " ".to_string()],
+            prompt_override: None,
+        };
+        assert!(!settings.is_default());
+        let rules = settings.prompt_rules();
+        assert!(rules
+            .starts_with("\n\nUser settings for this tool:\n- Write new output files into `out/`"));
+        assert!(rules.contains("contradicts them: `-hide_banner`\n- Never overwrite"));
+        assert!(rules.ends_with("new output names.\n- always use two-pass encoding"));
+    }
+
+    #[test]
+    fn prompt_override_fills_placeholders_and_keeps_the_request() {
+        assert_eq!(
+            PluginSettings::default().override_prompt("doc", "ctx", "req"),
+            None
+        );
+        let settings = PluginSettings {
+            prompt_override: Some("Write one ffmpeg command.\n{doc}\nFiles: {context}".to_string()),
+            ..PluginSettings::default()
+        };
+        assert_eq!(
+            settings.override_prompt("DOC", "a.mkv", "to mp4").unwrap(),
+            "Write one ffmpeg command.\nDOC\nFiles: a.mkv\n\n### User Request:\nto mp4"
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+
+/// An argument that is only this starts a new line of the command.
+const LINE_BREAK: &str = "\n";
+
+/// What goes between two lines of a command: a shell line continuation.
+const CONTINUATION: &str = " \\\n  ";
+
+/// A command as a program and its argument list, with the files it reads
+/// and writes named separately. Models may reply with one as JSON instead
+/// of a command line; it is quoted once, by `to_command_line`, so spaces
+/// and quotes in file names can't break the arguments.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StructuredCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    /// Files the command reads, as they appear in `args`.
+    pub inputs: Vec<String>,
+    /// Files the command writes, as they appear in `args`.
+    pub outputs: Vec<String>,
+    /// Indices into `args` that start a new line, taken from the `"\n"`
+    /// arguments of the reply so a long command keeps its layout.
+    #[serde(skip)]
+    pub line_starts: Vec<usize>,
+}
+
+impl StructuredCommand {
+    /// The argument list without declared inputs or outputs.
+    pub fn from_argv(argv: &[String]) -> Option<Self> {
+        let (program, args) = argv.split_first()?;
+        Some(Self {
+            program: program.clone(),
+            args: args.to_vec(),
+            ..Self::default()
+        })
+    }
+
+    /// A model reply holding a JSON command object, optionally fenced.
+    /// `None` for anything else, which is then taken as a command line.
+    pub fn from_reply(reply: &str) -> Option<Self> {
+        let trimmed = reply.trim();
+        let body = trimmed
+            .strip_prefix("```json")
+            .or_else(|| trimmed.strip_prefix("```"))
+            .and_then(|rest| rest.strip_suffix("```"))
+            .unwrap_or(trimmed)
+            .trim();
+        if !body.starts_with('{') {
+            return None;
+        }
+        let mut command: Self = serde_json::from_str(body).ok()?;
+        if command.program.trim().is_empty() {
+            return None;
+        }
+        let mut args = Vec::with_capacity(command.args.len());
+        for arg in std::mem::take(&mut command.args) {
+            if arg.trim_matches(' ') != LINE_BREAK {
+                args.push(arg);
+            } else if !args.is_empty() && command.line_starts.last() != Some(&args.len()) {
+                command.line_starts.push(args.len());
+            }
+        }
+        command.line_starts.retain(|&start| start < args.len());
+        command.args = args;
+        Some(command)
+    }
+
+    /// Declared inputs and outputs that don't appear among the arguments,
+    /// which means the declaration can't be trusted for path checks.
+    pub fn undeclared_paths(&self) -> Vec<&str> {
+        self.inputs
+            .iter()
+            .chain(&self.outputs)
+            .map(String::as_str)
+            .filter(|path| !self.args.iter().any(|arg| arg == path))
+            .collect()
+    }
+
+    pub fn argv(&self) -> Vec<String> {
+        std::iter::once(self.program.clone())
+            .chain(self.args.iter().cloned())
+            .collect()
+    }
+
+    /// The command line the rest of Dexter shows, stores and runs, split
+    /// with line continuations where the reply broke it.
+    pub fn to_command_line(&self) -> String {
+        let mut lines = Vec::with_capacity(self.line_starts.len() + 1);
+        let mut line = vec![self.program.as_str()];
+        for (idx, arg) in self.args.iter().enumerate() {
+            if self.line_starts.contains(&idx) {
+                lines.push(shell_words::join(std::mem::take(&mut line)));
+            }
+            line.push(arg);
+        }
+        lines.push(shell_words::join(line));
+        lines.join(CONTINUATION)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_replies_quote_their_arguments_once() {
+        let reply = r#"```json
+{"program": "ffmpeg", "args": ["-i", "my clip's.mov", "out put.mp4"], "inputs": ["my clip's.mov"], "outputs": ["out put.mp4"]}
+```"#;
+        let command = StructuredCommand::from_reply(reply).unwrap();
+        assert_eq!(command.outputs, ["out put.mp4"]);
+        let line = command.to_command_line();
+        assert_eq!(shell_words::split(&line).unwrap(), command.argv());
+
+        assert_eq!(StructuredCommand::from_reply("ffmpeg -i a.mov b.mp4"), None);
+        assert_eq!(StructuredCommand::from_reply(r#"{"args": ["-i"]}"#), None);
+
+        let plain = StructuredCommand::from_argv(&["f2".to_string(), "-f".to_string()]).unwrap();
+        assert_eq!(plain.to_command_line(), "f2 -f");
+        assert!(plain.inputs.is_empty());
+    }
+
+    #[test]
+    fn declarations_must_name_arguments() {
+        let command = StructuredCommand {
+            program: "qpdf".to_string(),
+            args: vec!["in.pdf".to_string(), "out.pdf".to_string()],
+            inputs: vec!["in.pdf".to_string()],
+            outputs: vec!["other.pdf".to_string()],
+            ..StructuredCommand::default()
+        };
+        assert_eq!(command.undeclared_paths(), ["other.pdf"]);
+    }
+
+    #[test]
+    fn line_break_arguments_keep_the_command_on_several_lines() {
+        let reply = r#"{"program": "ffmpeg", "args": ["-i", "a b.mov", "\n", "-c:v", "libx264", "\n", "\n", "out.mp4", "\n"]}"#;
+        let command = StructuredCommand::from_reply(reply).unwrap();
+        assert_eq!(
+            command.args,
+            ["-i", "a b.mov", "-c:v", "libx264", "out.mp4"]
+        );
+        let line = command.to_command_line();
+        assert_eq!(line, "ffmpeg -i 'a b.mov' \\\n  -c:v libx264 \\\n  out.mp4");
+        assert_eq!(shell_words::split(&line).unwrap(), command.argv());
+    }
+}
@@ -1,5 +1,7 @@
-use crate::command_exec::{parse_and_validate_command, spawn_checked_piped};
-use crate::{LlmBridge, Plugin, PreviewContent, Progress};
+use crate::command_exec::{
+    exit_failure, parse_and_validate_command, spawn_checked_piped, working_dir,
+};
+use crate::{Capability, LlmBridge, Package, Plugin, PluginSettings, PreviewContent, Progress};
 use anyhow::Result;
 use async_trait::async_trait;
 use regex::Regex;
@@ -7,7 +9,10 @@ use std::process::Command;
 use std::sync::Arc;
 use tokio::io::AsyncBufReadExt;
 
-pub struct WhisperCppPlugin;
+#[derive(Default)]
+pub struct WhisperCppPlugin {
+    settings: PluginSettings,
+}
 
 fn parse_whisper_command(cmd: &str) -> Result<Vec<String>> {
     parse_and_validate_command(cmd, "whisper-cli")
@@ -78,6 +83,8 @@ fn validate_whisper_command(cmd: &str) -> bool {
     true
 }
 
+const PACKAGES: &[Package] = &[Package::brew("whisper-cpp")];
+
 #[async_trait]
 impl Plugin for WhisperCppPlugin {
     fn name(&self) -> &str {
@@ -88,6 +95,22 @@ impl Plugin for WhisperCppPlugin {
         "Local speech-to-text via whisper.cpp (transcription, translation, and subtitle outputs)."
     }
 
+    fn configure(&mut self, settings: PluginSettings) {
+        self.settings = settings;
+    }
+
+    fn settings(&self) -> &PluginSettings {
+        &self.settings
+    }
+
+    fn capabilities(&self) -> &[Capability] {
+        &[Capability::MediaAudio, Capability::Transcription]
+    }
+
+    fn binaries(&self) -> &[&str] {
+        &["whisper-cli", "whisper-cpp"]
+    }
+
     async fn is_installed(&self) -> bool {
         let has_whisper_cli = Command::new("whisper-cli")
             .arg("-h")
@@ -104,10 +127,12 @@ impl Plugin for WhisperCppPlugin {
         has_whisper_cli || has_whisper_cpp
     }
 
-    async fn install(&self) -> Result<()> {
-        Err(anyhow::anyhow!(
-            "Please install whisper.cpp manually:\n- macOS (brew): brew install whisper-cpp\n- Or build from source: https://github.com/ggml-org/whisper.cpp"
-        ))
+    fn packages(&self) -> &[Package] {
+        PACKAGES
+    }
+
+    fn install_note(&self) -> Option<&str> {
+        Some("Or build from source: https://github.com/ggml-org/whisper.cpp")
     }
 
     fn get_doc_for_router(&self) -> &str {
@@ -128,7 +153,7 @@ Safety Constraints:
 4. Use output flags (-otxt/-osrt/-ovtt/-oj/-ojf) and optional -of prefix for deterministic files."#
     }
 
-    fn get_executor_prompt(&self, context: &str, user_input: &str) -> String {
+    fn get_executor_prompt_with_doc(&self, doc: &str, context: &str, user_input: &str) -> String {
         format!(
             r#"You are the Speech Transcription Specialist Agent for Dexter.
 Your goal is to generate a valid `whisper-cli` command for whisper.cpp.
@@ -150,9 +175,7 @@ Your goal is to generate a valid `whisper-cli` command for whisper.cpp.
 ### User Request:
 {}
 "#,
-            self.get_doc_for_executor(),
-            context,
-            user_input
+            doc, context, user_input
         )
     }
 
@@ -197,7 +220,7 @@ Your goal is to generate a valid `whisper-cli` command for whisper.cpp.
             })
             .await;
 
-        let cwd = std::env::current_dir()?;
+        let cwd = working_dir()?;
         let mut child = spawn_checked_piped(&argv, &cwd)?;
 
         let stderr = child
@@ -270,7 +293,10 @@ Your goal is to generate a valid `whisper-cli` command for whisper.cpp.
                 combined
             })
         } else {
-            Err(anyhow::anyhow!("whisper.cpp error:\n{}", err_output))
+            Err(exit_failure(
+                status,
+                format!("whisper.cpp error:\n{}", err_output),
+            ))
         }
     }
 }
@@ -1,13 +1,250 @@
-use crate::command_exec::{contains_arg, parse_and_validate_command, spawn_checked_piped};
-use crate::{Plugin, PreviewContent};
-use anyhow::Result;
+use crate::command_exec::{
+    contains_arg, exit_failure, expand_home, parse_and_validate_command, preview_may_fetch,
+    sandboxed_argv, spawn_checked_piped, working_dir,
+};
+use crate::ffmpeg::format_timestamp;
+use crate::{
+    Capability, ExecMode, KeyValueSection, Package, Plugin, PluginSettings, PreviewContent,
+};
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
 use std::process::Command;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::io::AsyncBufReadExt;
 
-pub struct YtDlpPlugin;
+/// Most URLs whose metadata a preview fetches.
+const METADATA_URLS: usize = 5;
+const METADATA_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Limits on where yt-dlp may write and what it may run, checked in
+/// `validate_command` before any preview or execution.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct YtDlpPolicy {
+    /// Directories downloads may land in (`~/` is expanded, relative paths
+    /// are taken from the working directory). Empty allows any location.
+    #[serde(default)]
+    pub allowed_output_dirs: Vec<String>,
+    /// When set, every command needs an `-o` whose file name part is exactly
+    /// this template, e.g. `%(title)s [%(id)s].%(ext)s`.
+    #[serde(default)]
+    pub output_template: Option<String>,
+    /// Flags refused outright; `--exec` is refused even when missing here.
+    #[serde(default = "default_forbidden_flags")]
+    pub forbidden_flags: Vec<String>,
+}
+
+impl Default for YtDlpPolicy {
+    fn default() -> Self {
+        Self {
+            allowed_output_dirs: Vec::new(),
+            output_template: None,
+            forbidden_flags: default_forbidden_flags(),
+        }
+    }
+}
+
+/// Post-processing hooks, external downloaders, a swapped-in ffmpeg and
+/// `--netrc-cmd` all run arbitrary programs, and post-processor arguments
+/// reach ffmpeg's command line; config and plugin locations could smuggle
+/// any of them back in.
+fn default_forbidden_flags() -> Vec<String> {
+    [
+        "--exec",
+        "--exec-before-download",
+        "--external-downloader",
+        "--downloader",
+        "--external-downloader-args",
+        "--downloader-args",
+        "--use-postprocessor",
+        "--postprocessor-args",
+        "--ppa",
+        "--ffmpeg-location",
+        "--netrc-cmd",
+        "--config-locations",
+        "--config-location",
+        "--plugin-dirs",
+    ]
+    .iter()
+    .map(|flag| flag.to_string())
+    .collect()
+}
+
+impl YtDlpPolicy {
+    /// Why `argv` breaks the policy, if it does. `~` in paths is `home`.
+    pub fn violation(&self, argv: &[String], cwd: &Path, home: Option<&Path>) -> Option<String> {
+        for (flag, _) in flags_with_values(argv) {
+            if flag == "--exec"
+                || self
+                    .forbidden_flags
+                    .iter()
+                    .any(|forbidden| forbidden == flag)
+            {
+                return Some(format!("{} is not allowed by the yt-dlp policy", flag));
+            }
+        }
+
+        let outputs: Vec<&str> = flags_with_values(argv)
+            .filter(|(flag, _)| *flag == "-o" || *flag == "--output")
+            .filter_map(|(_, value)| value)
+            .map(strip_output_type)
+            .collect();
+        if let Some(template) = self.output_template.as_deref() {
+            if outputs.is_empty() {
+                return Some(format!("the yt-dlp policy requires -o \"{}\"", template));
+            }
+            if let Some(other) = outputs
+                .iter()
+                .find(|output| file_name_part(output) != template)
+            {
+                return Some(format!(
+                    "output `{}` does not use the required template `{}`",
+                    other, template
+                ));
+            }
+        }
+
+        if self.allowed_output_dirs.is_empty() {
+            return None;
+        }
+        let allowed: Vec<PathBuf> = self
+            .allowed_output_dirs
+            .iter()
+            .map(|dir| normalize(&cwd.join(expand_home(dir, home))))
+            .collect();
+        // `-P/--paths` moves the base for relative `-o` templates.
+        let mut bases = vec![cwd.to_path_buf()];
+        for (flag, value) in flags_with_values(argv) {
+            if flag == "-P" || flag == "--paths" {
+                let Some(value) = value else { continue };
+                let path = normalize(&cwd.join(expand_home(strip_output_type(value), home)));
+                if !allowed.iter().any(|dir| path.starts_with(dir)) {
+                    return Some(format!("download path {} is not allowed", path.display()));
+                }
+                bases = vec![path];
+            }
+        }
+        let outputs = if outputs.is_empty() {
+            vec!["%(title)s [%(id)s].%(ext)s"]
+        } else {
+            outputs
+        };
+        for output in outputs {
+            for base in &bases {
+                let dir = normalize(&base.join(expand_home(static_dir(output), home)));
+                if !allowed.iter().any(|allowed| dir.starts_with(allowed)) {
+                    return Some(format!("output directory {} is not allowed", dir.display()));
+                }
+            }
+        }
+        None
+    }
+
+    fn prompt_rules(&self) -> String {
+        let mut rules = Vec::new();
+        if !self.allowed_output_dirs.is_empty() {
+            rules.push(format!(
+                "- Save only inside: {}",
+                self.allowed_output_dirs.join(", ")
+            ));
+        }
+        if let Some(template) = &self.output_template {
+            rules.push(format!(
+                "- Always pass -o with the file name template \"{}\" (a directory prefix is allowed)",
+                template
+            ));
+        }
+        rules.push(format!("- Never use: {}", self.forbidden_flags.join(", ")));
+        rules.join("\n")
+    }
+}
+
+/// `(flag, value)` for every option in `argv`, splitting `--flag=value`. The
+/// value is the next argument for the options that take one.
+fn flags_with_values(argv: &[String]) -> impl Iterator<Item = (&str, Option<&str>)> {
+    const TAKES_VALUE: [&str; 4] = ["-o", "--output", "-P", "--paths"];
+    argv.iter()
+        .enumerate()
+        .skip(1)
+        .filter_map(move |(idx, arg)| {
+            if !arg.starts_with('-') {
+                return None;
+            }
+            if let Some((flag, value)) = arg.split_once('=') {
+                if flag.starts_with("--") {
+                    return Some((flag, Some(value)));
+                }
+            }
+            let value = TAKES_VALUE
+                .contains(&arg.as_str())
+                .then(|| argv.get(idx + 1).map(String::as_str))
+                .flatten();
+            Some((arg.as_str(), value))
+        })
+}
+
+/// Drops a `thumbnail:` / `home:` style prefix from an `-o` or `-P` value.
+fn strip_output_type(value: &str) -> &str {
+    match value.split_once(':') {
+        Some((kind, rest))
+            if kind.len() > 1 && kind.chars().all(|c| c.is_ascii_lowercase() || c == '_') =>
+        {
+            rest
+        }
+        _ => value,
+    }
+}
+
+fn file_name_part(output: &str) -> &str {
+    output.rsplit('/').next().unwrap_or(output)
+}
+
+/// The directory part of a template that doesn't depend on the video:
+/// yt-dlp sanitizes field values, so only the literal prefix can escape.
+fn static_dir(output: &str) -> &str {
+    let literal = output.split("%(").next().unwrap_or_default();
+    match literal.rfind('/') {
+        Some(idx) => &output[..idx.max(1)],
+        None => ".",
+    }
+}
+
+/// Resolves `.` and `..` without touching the file system, so paths that
+/// don't exist yet compare correctly.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+#[derive(Default)]
+pub struct YtDlpPlugin {
+    policy: YtDlpPolicy,
+    /// What `~` in the policy's paths stands for.
+    home: Option<PathBuf>,
+    settings: PluginSettings,
+}
+
+impl YtDlpPlugin {
+    pub fn new(policy: YtDlpPolicy, home: Option<PathBuf>) -> Self {
+        Self {
+            policy,
+            home,
+            settings: PluginSettings::default(),
+        }
+    }
+}
 
 fn extract_percentage(re: &Regex, line: &str) -> Option<f64> {
     let caps = re.captures(line)?;
@@ -15,6 +252,14 @@ fn extract_percentage(re: &Regex, line: &str) -> Option<f64> {
     Some(value.clamp(0.0, 100.0))
 }
 
+const PACKAGES: &[Package] = &[
+    Package::brew("yt-dlp"),
+    Package::pipx("yt-dlp"),
+    Package::winget("yt-dlp.yt-dlp"),
+    Package::scoop("yt-dlp"),
+    Package::apt("yt-dlp"),
+];
+
 #[async_trait]
 impl Plugin for YtDlpPlugin {
     fn name(&self) -> &str {
@@ -25,6 +270,22 @@ impl Plugin for YtDlpPlugin {
         "A feature-rich video/audio downloader with format selection and audio extraction."
     }
 
+    fn configure(&mut self, settings: PluginSettings) {
+        self.settings = settings;
+    }
+
+    fn settings(&self) -> &PluginSettings {
+        &self.settings
+    }
+
+    fn capabilities(&self) -> &[Capability] {
+        &[
+            Capability::Downloading,
+            Capability::MediaVideo,
+            Capability::MediaAudio,
+        ]
+    }
+
     async fn is_installed(&self) -> bool {
         Command::new("yt-dlp")
             .arg("--version")
@@ -33,10 +294,12 @@ impl Plugin for YtDlpPlugin {
             .unwrap_or(false)
     }
 
-    async fn install(&self) -> Result<()> {
-        Err(anyhow::anyhow!(
-            "Please install yt-dlp manually:\n- macOS (brew): brew install yt-dlp\n- pipx: pipx install yt-dlp\n- pip: pip install -U yt-dlp\n- Debian/Ubuntu: sudo apt install yt-dlp"
-        ))
+    fn packages(&self) -> &[Package] {
+        PACKAGES
+    }
+
+    fn install_note(&self) -> Option<&str> {
+        Some("pip works too: pip install -U yt-dlp")
     }
 
     fn get_doc_for_router(&self) -> &str {
@@ -60,7 +323,7 @@ Notes:
 "#
     }
 
-    fn get_executor_prompt(&self, context: &str, user_input: &str) -> String {
+    fn get_executor_prompt_with_doc(&self, doc: &str, context: &str, user_input: &str) -> String {
         format!(
             r#"You are the Download Specialist Agent for Dexter.
 Your goal is to generate a valid `yt-dlp` command.
@@ -73,6 +336,9 @@ Your goal is to generate a valid `yt-dlp` command.
 5. PRECISION: Treat URLs and filenames as literal strings; use exact characters from context.
 6. NO --newline: Dexter will add `--newline` during execution if needed.
 
+### Download Policy:
+{}
+
 ### Documentation:
 {}
 
@@ -82,14 +348,23 @@ Your goal is to generate a valid `yt-dlp` command.
 ### User Request:
 {}
 "#,
-            self.get_doc_for_executor(),
+            self.policy.prompt_rules(),
+            doc,
             context,
             user_input
         )
     }
 
     fn validate_command(&self, cmd: &str) -> bool {
-        if parse_and_validate_command(cmd, "yt-dlp").is_err() {
+        let Ok(argv) = parse_and_validate_command(cmd, "yt-dlp") else {
+            return false;
+        };
+        let cwd = working_dir().unwrap_or_default();
+        if self
+            .policy
+            .violation(&argv, &cwd, self.home.as_deref())
+            .is_some()
+        {
             return false;
         }
 
@@ -112,16 +387,53 @@ Your goal is to generate a valid `yt-dlp` command.
         cmd: &str,
         llm: Option<&dyn crate::LlmBridge>,
     ) -> Result<PreviewContent> {
-        if let Some(llm) = llm {
+        let summary = if let Some(llm) = llm {
             let system_prompt = "You are a clear and concise command explainer for Dexter. Describe what this yt-dlp command will do in simple terms. Mention source URL(s), output naming, and key options. Output plain text only.";
-            let text = llm.chat(system_prompt, cmd).await?;
-            Ok(PreviewContent::Text(text))
+            llm.chat(system_prompt, cmd).await?
         } else {
-            Ok(PreviewContent::Text(format!(
-                "Executing download command: {}",
-                cmd
-            )))
+            format!("Executing download command: {}", cmd)
+        };
+
+        // Metadata is best effort: offline, on a metered connection, or for
+        // sites yt-dlp can't read up front, the summary stands alone.
+        let argv = parse_and_validate_command(cmd, "yt-dlp")?;
+        let urls = match preview_may_fetch() {
+            true => command_urls(&argv),
+            false => Vec::new(),
+        };
+        let cwd = working_dir()?;
+        let fetches: Vec<_> = urls
+            .into_iter()
+            .take(METADATA_URLS)
+            .map(|url| {
+                // Wrapped here: the spawned task doesn't inherit the sandbox.
+                let argv = sandboxed_argv(&metadata_argv(url));
+                let (url, cwd) = (url.to_string(), cwd.clone());
+                tokio::spawn(async move { fetch_metadata(&argv, &cwd, &url).await })
+            })
+            .collect();
+        let mut sections = Vec::new();
+        for fetch in fetches {
+            if let Ok(Ok(section)) = fetch.await {
+                sections.push(section);
+            }
+        }
+        if sections.is_empty() {
+            return Ok(PreviewContent::Text(summary));
         }
+        Ok(PreviewContent::Sections(vec![
+            PreviewContent::Text(summary),
+            PreviewContent::KeyValue(sections),
+        ]))
+    }
+
+    fn command_argv(&self, cmd: &str, _mode: ExecMode) -> Result<Vec<String>> {
+        // One progress line per update, so percentages can be read.
+        let mut argv = parse_and_validate_command(cmd, "yt-dlp")?;
+        if !contains_arg(&argv, "--newline") {
+            argv.push("--newline".to_string());
+        }
+        Ok(argv)
     }
 
     async fn execute(&self, cmd: &str) -> Result<String> {
@@ -134,12 +446,8 @@ Your goal is to generate a valid `yt-dlp` command.
         cmd: &str,
         progress_tx: tokio::sync::mpsc::Sender<crate::Progress>,
     ) -> Result<String> {
-        let mut argv = parse_and_validate_command(cmd, "yt-dlp")?;
-        if !contains_arg(&argv, "--newline") {
-            argv.push("--newline".to_string());
-        }
-
-        let cwd = std::env::current_dir()?;
+        let argv = self.command_argv(cmd, ExecMode::Apply)?;
+        let cwd = working_dir()?;
         let mut child = spawn_checked_piped(&argv, &cwd)?;
 
         let stderr = child
@@ -205,24 +513,184 @@ Your goal is to generate a valid `yt-dlp` command.
                 combined
             })
         } else {
-            Err(anyhow::anyhow!(format!("yt-dlp error:\n{}", err_output)))
+            Err(exit_failure(
+                status,
+                format!("yt-dlp error:\n{}", err_output),
+            ))
         }
     }
 }
 
+/// The URLs `argv` downloads.
+fn command_urls(argv: &[String]) -> Vec<&str> {
+    argv.iter()
+        .skip(1)
+        .map(String::as_str)
+        .filter(|arg| arg.starts_with("http://") || arg.starts_with("https://"))
+        .collect()
+}
+
+/// The fields of `yt-dlp -J` the preview shows.
+#[derive(Debug, Default, Deserialize)]
+struct UrlMetadata {
+    #[serde(rename = "_type")]
+    kind: Option<String>,
+    title: Option<String>,
+    channel: Option<String>,
+    uploader: Option<String>,
+    duration: Option<f64>,
+    extractor_key: Option<String>,
+    playlist_count: Option<u64>,
+    #[serde(default)]
+    entries: Vec<serde_json::Value>,
+}
+
+impl UrlMetadata {
+    fn section(self, url: &str) -> KeyValueSection {
+        let mut entries = Vec::new();
+        let mut push = |key: &str, value: Option<String>| {
+            if let Some(value) = value.filter(|v| !v.is_empty()) {
+                entries.push((key.to_string(), value));
+            }
+        };
+        push("Title", self.title);
+        push("Channel", self.channel.or(self.uploader));
+        push("Site", self.extractor_key);
+        push("Duration", self.duration.map(format_timestamp));
+        if self.kind.as_deref() == Some("playlist") {
+            let count = self.playlist_count.unwrap_or(self.entries.len() as u64);
+            push("Playlist", Some(format!("{} item(s)", count)));
+        }
+        KeyValueSection {
+            title: url.to_string(),
+            entries,
+        }
+    }
+}
+
+fn metadata_argv(url: &str) -> Vec<String> {
+    [
+        "yt-dlp",
+        "-J",
+        "--flat-playlist",
+        "--no-warnings",
+        "--",
+        url,
+    ]
+    .map(String::from)
+    .to_vec()
+}
+
+/// Title, channel, length and playlist size of `url`, without downloading,
+/// by running `argv` (`metadata_argv`, already sandboxed) in `cwd`.
+async fn fetch_metadata(argv: &[String], cwd: &Path, url: &str) -> Result<KeyValueSection> {
+    let output = tokio::time::timeout(
+        METADATA_TIMEOUT,
+        tokio::process::Command::new(&argv[0])
+            .args(&argv[1..])
+            .current_dir(cwd)
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .map_err(|_| anyhow!("yt-dlp metadata timed out"))??;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "yt-dlp metadata failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let metadata: UrlMetadata = serde_json::from_slice(&output.stdout)?;
+    Ok(metadata.section(url))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn validate_rejects_shell_injection() {
-        let plugin = YtDlpPlugin;
+        let plugin = YtDlpPlugin::default();
         assert!(!plugin.validate_command("yt-dlp \"url\" && echo x"));
     }
 
+    #[test]
+    fn policy_limits_outputs_and_flags() {
+        let argv = |cmd: &str| shell_words::split(cmd).unwrap();
+        let cwd = Path::new("/home/me/videos");
+        let policy = YtDlpPolicy {
+            allowed_output_dirs: vec!["/home/me/videos".to_string(), "/mnt/media".to_string()],
+            output_template: Some("%(title)s.%(ext)s".to_string()),
+            ..YtDlpPolicy::default()
+        };
+        let check = |cmd: &str| policy.violation(&argv(cmd), cwd, None);
+
+        assert_eq!(check("yt-dlp -o \"%(title)s.%(ext)s\" url"), None);
+        assert_eq!(
+            check("yt-dlp -o \"clips/%(uploader)s/%(title)s.%(ext)s\" url"),
+            None
+        );
+        assert_eq!(
+            check("yt-dlp -P /mnt/media/new --output=%(title)s.%(ext)s url"),
+            None
+        );
+        assert!(check("yt-dlp url").unwrap().contains("requires -o"));
+        assert!(check("yt-dlp -o \"%(id)s.%(ext)s\" url").is_some());
+        assert!(check("yt-dlp -o \"../%(title)s.%(ext)s\" url")
+            .unwrap()
+            .contains("/home/me"));
+        assert!(check("yt-dlp -o \"/etc/%(title)s.%(ext)s\" url").is_some());
+        assert!(check("yt-dlp -P /tmp -o \"%(title)s.%(ext)s\" url").is_some());
+        assert!(check("yt-dlp --downloader aria2c -o \"%(title)s.%(ext)s\" url").is_some());
+        assert!(check("yt-dlp --exec-before-download=x -o \"%(title)s.%(ext)s\" url").is_some());
+        for flag in [
+            "--ffmpeg-location ./evil",
+            "--netrc-cmd 'sh -c x'",
+            "--postprocessor-args 'ffmpeg:-f x'",
+            "--ppa=ffmpeg:-y",
+        ] {
+            let cmd = format!("yt-dlp {} -o \"%(title)s.%(ext)s\" url", flag);
+            assert!(check(&cmd).is_some(), "{}", flag);
+        }
+        assert_eq!(
+            YtDlpPolicy::default().violation(&argv("yt-dlp -o /tmp/x.mp4 url"), cwd, None),
+            None
+        );
+    }
+
     #[test]
     fn validate_allows_quoted_url() {
-        let plugin = YtDlpPlugin;
+        let plugin = YtDlpPlugin::default();
         assert!(plugin.validate_command("yt-dlp \"https://example.com/watch?v=a&b=1\""));
     }
+
+    #[test]
+    fn metadata_sections_list_urls_and_playlists() {
+        let args =
+            shell_words::split("yt-dlp -f best https://a.com/v?id=1 -o x.mp4 http://b.org/list")
+                .unwrap();
+        assert_eq!(
+            command_urls(&args),
+            ["https://a.com/v?id=1", "http://b.org/list"]
+        );
+
+        let video: UrlMetadata = serde_json::from_str(
+            r#"{"title":"Talk","uploader":"Conf","duration":3725.0,"extractor_key":"Youtube"}"#,
+        )
+        .unwrap();
+        let section = video.section("https://a.com/v?id=1");
+        assert_eq!(section.title, "https://a.com/v?id=1");
+        let keys: Vec<&str> = section.entries.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, ["Title", "Channel", "Site", "Duration"]);
+        assert_eq!(section.entries[1].1, "Conf");
+
+        let playlist: UrlMetadata =
+            serde_json::from_str(r#"{"_type":"playlist","title":"Mix","entries":[{},{},{}]}"#)
+                .unwrap();
+        let section = playlist.section("http://b.org/list");
+        assert_eq!(
+            section.entries.last(),
+            Some(&("Playlist".to_string(), "3 item(s)".to_string()))
+        );
+    }
 }
@@ -1,10 +1,49 @@
 use anyhow::Result;
-use dexter_core::CachePolicy;
+use dexter_core::router::clarify_plan;
+use dexter_core::{CachePolicy, StartupMode};
 
 use crate::app::editor::char_count;
+use crate::app::search::Search;
 use crate::app::state::{App, AppState, FocusArea, FooterAction};
+use crate::app::telemetry;
+use crate::app::templates::{find_template, has_placeholders, placeholder_ranges};
+
+/// Opens the screen `mode` asks for. A recipe loads the template into the
+/// input and, when nothing is left to fill in, submits it so the proposal
+/// is waiting on the confirmation screen.
+pub async fn apply_startup(app: &mut App, mode: &StartupMode) -> Result<()> {
+    match mode {
+        StartupMode::Input => {}
+        StartupMode::History => app.open_history_view().await?,
+        StartupMode::Recipe(name) => {
+            let templates = app.intent_templates();
+            let Some(template) = find_template(&templates, name) else {
+                let names: Vec<&str> = templates.iter().map(|t| t.name.as_str()).collect();
+                app.push_log(format!(
+                    "No template named \"{}\". Available: {}",
+                    name,
+                    names.join(", ")
+                ));
+                app.dirty = true;
+                return Ok(());
+            };
+            let ready = placeholder_ranges(&template.text).is_empty();
+            app.template_selected = templates.iter().position(|t| t == template).unwrap_or(0);
+            app.apply_selected_template();
+            if ready {
+                perform_footer_action(app, FooterAction::Submit).await?;
+            }
+        }
+    }
+    Ok(())
+}
 
 pub async fn perform_footer_action(app: &mut App, action: FooterAction) -> Result<bool> {
+    if app.read_only && action.runs_commands() {
+        app.push_warning("safety", "Read-only mode: nothing is run.");
+        app.dirty = true;
+        return Ok(false);
+    }
     match action {
         FooterAction::Quit => return Ok(true),
         FooterAction::Settings => {
@@ -19,6 +58,113 @@ pub async fn perform_footer_action(app: &mut App, action: FooterAction) -> Resul
             }
             app.dirty = true;
         }
+        FooterAction::OpenSearch => {
+            app.search = Some(Search::load(app).await);
+            app.dirty = true;
+        }
+        FooterAction::OpenTemplates => {
+            app.open_template_picker();
+        }
+        FooterAction::ApplyTemplate => {
+            app.apply_selected_template();
+        }
+        FooterAction::CloseTemplates => {
+            app.close_template_picker();
+        }
+        FooterAction::ResolveConflict(policy) => {
+            app.focus = FocusArea::FooterButtons;
+            app.footer_focus = 0;
+            app.resolve_output_conflicts(policy).await?;
+        }
+        FooterAction::CycleLogLevel => {
+            match telemetry::cycle_verbosity() {
+                Some(level) => app.push_log(format!("Log verbosity: {}", level)),
+                None => app.push_log("File logging is unavailable.".to_string()),
+            }
+            app.dirty = true;
+        }
+        FooterAction::ToggleQueue => {
+            app.show_queue = !app.show_queue;
+            app.output_scroll = 0;
+            app.dirty = true;
+        }
+        FooterAction::StartNextJob => {
+            app.start_next_job();
+            app.dirty = true;
+        }
+        FooterAction::CancelQueued => {
+            let dropped = app.jobs.cancel_pending();
+            app.push_log(format!("Dropped {} queued job(s).", dropped));
+            app.dirty = true;
+        }
+        FooterAction::RunLater => {
+            app.open_run_later();
+        }
+        FooterAction::ConfirmRunLater => {
+            app.focus = FocusArea::FooterButtons;
+            app.footer_focus = 0;
+            app.confirm_run_later().await?;
+        }
+        FooterAction::CancelRunLater => {
+            app.cancel_run_later();
+        }
+        FooterAction::ToggleSafetyRule => {
+            app.toggle_safety_rule();
+        }
+        FooterAction::RegenerateWithinRule => {
+            app.regenerate_within_rule();
+        }
+        FooterAction::OverrideSafety => {
+            app.start_override();
+        }
+        FooterAction::ConfirmOverride => {
+            app.confirm_override();
+        }
+        FooterAction::CancelOverride => {
+            app.cancel_override();
+        }
+        FooterAction::ToggleSchedule => {
+            app.show_schedule = !app.show_schedule;
+            if app.show_schedule {
+                app.reload_scheduled().await;
+            }
+            app.output_scroll = 0;
+            app.dirty = true;
+        }
+        FooterAction::CycleScheduled => {
+            app.cycle_scheduled_selection();
+        }
+        FooterAction::CancelScheduled => {
+            app.cancel_selected_scheduled().await?;
+        }
+        FooterAction::ToggleLogFilter => {
+            app.log_filter = app.log_filter.toggle();
+            app.dirty = true;
+        }
+        FooterAction::CycleLayout => {
+            app.output_layout = app.output_layout.next();
+            app.push_log(format!("Output layout: {}", app.output_layout.label()));
+            app.dirty = true;
+        }
+        FooterAction::ToggleOffline => {
+            let mut config = app.config.clone();
+            config.offline = !config.offline;
+            app.apply_config(config);
+            app.push_log(if app.config.offline {
+                "Offline mode ON: keyword routing, local Ollama models only."
+            } else {
+                "Offline mode OFF."
+            });
+        }
+        FooterAction::SelfUpdate => app.start_self_update(),
+        FooterAction::ForgetSession => {
+            app.session.clear();
+            app.push_log("Session memory cleared.");
+            app.dirty = true;
+        }
+        FooterAction::CycleTarget => {
+            app.cycle_execution_target().await;
+        }
         FooterAction::ToggleDebug => {
             app.show_debug = !app.show_debug;
             app.push_log(format!(
@@ -35,25 +181,45 @@ pub async fn perform_footer_action(app: &mut App, action: FooterAction) -> Resul
                 app.dirty = true;
             } else {
                 app.reset_for_new_request();
+                app.subtasks = None;
+                app.jobs.begin(&app.input);
                 app.focus = FocusArea::FooterButtons;
                 app.footer_focus = 0;
                 app.state = AppState::PendingRouting;
                 app.dirty = true;
             }
         }
+        FooterAction::InstallMissing => app.start_install(),
+        FooterAction::RefreshPreview => app.refresh_preview().await?,
+        FooterAction::ToggleInvocation => {
+            app.show_invocation = !app.show_invocation;
+            app.dirty = true;
+        }
         FooterAction::ClearInput => {
             app.input.clear();
             app.input_cursor = 0;
+            app.recipe = None;
             app.notice = None;
             app.clarify = None;
             app.focus = FocusArea::Proposal;
             app.dirty = true;
         }
         FooterAction::Submit => {
-            if !app.input.trim().is_empty() {
+            let unfilled = app
+                .recipe_template()
+                .is_some_and(|template| has_placeholders(&template.text, &app.input));
+            if unfilled {
+                app.notice = Some(
+                    "Fill in the remaining {placeholders} first (Tab jumps to the next one)."
+                        .to_string(),
+                );
+                app.focus = FocusArea::Proposal;
+                app.dirty = true;
+            } else if !app.input.trim().is_empty() {
                 app.push_log(format!("Input submitted ({} chars)", app.input.len()));
                 app.log_block("INPUT_SUBMIT", &app.input);
                 app.reset_for_new_request();
+                app.jobs.begin(&app.input);
                 app.focus = FocusArea::FooterButtons;
                 app.footer_focus = 0;
                 app.state = AppState::PendingRouting;
@@ -66,9 +232,20 @@ pub async fn perform_footer_action(app: &mut App, action: FooterAction) -> Resul
             app.execute_command().await?;
             app.dirty = true;
         }
+        FooterAction::ConfirmDelete => {
+            if app.delete_hold {
+                app.delete_confirmed = true;
+                app.execute_command().await?;
+            }
+            app.dirty = true;
+        }
         FooterAction::BackToInput => {
             app.reset_to_input_preserve_text();
         }
+        FooterAction::SendOverBudget => app.send_over_budget(),
+        FooterAction::EditCommand if app.safety_block.is_some() => {
+            app.edit_blocked_command();
+        }
         FooterAction::EditCommand => {
             if let Some(cmd) = &app.generated_command {
                 app.command_draft = cmd.clone();
@@ -80,10 +257,16 @@ pub async fn perform_footer_action(app: &mut App, action: FooterAction) -> Resul
             app.dirty = true;
         }
         FooterAction::EditInput => {
+            // Refining the request diffs the next command against this one.
+            let previous = app
+                .selected_plugin
+                .clone()
+                .zip(app.generated_command.clone());
             app.reset_to_input_preserve_text();
+            app.previous_command = previous;
         }
         FooterAction::Regenerate => {
-            app.generated_command = None;
+            app.remember_previous_command();
             app.command_draft.clear();
             app.command_cursor = 0;
             app.dry_run_output = None;
@@ -99,7 +282,12 @@ pub async fn perform_footer_action(app: &mut App, action: FooterAction) -> Resul
         FooterAction::PreviewEditedCommand => {
             let new_cmd = app.command_draft.trim().to_string();
             if !new_cmd.is_empty() {
+                if app.generated_command.as_ref() != Some(&new_cmd) {
+                    app.remember_previous_command();
+                }
                 app.generated_command = Some(new_cmd.clone());
+                // An edited command is no longer the recipe's to auto-run.
+                app.recipe = None;
                 app.push_log(format!("Command edited: {}", new_cmd));
                 app.log_block("COMMAND_EDIT", &new_cmd);
                 app.command_cursor = char_count(&app.command_draft);
@@ -111,6 +299,9 @@ pub async fn perform_footer_action(app: &mut App, action: FooterAction) -> Resul
                 app.dirty = true;
             }
         }
+        FooterAction::CancelEditCommand if app.safety_block.is_some() => {
+            app.return_to_block();
+        }
         FooterAction::CancelEditCommand => {
             if let Some(cmd) = &app.generated_command {
                 app.command_draft = cmd.clone();
@@ -132,11 +323,37 @@ pub async fn perform_footer_action(app: &mut App, action: FooterAction) -> Resul
             app.toggle_history_pin_for_selected().await?;
             app.dirty = true;
         }
+        FooterAction::ResumeInterrupted => {
+            app.resume_interrupted().await?;
+        }
+        FooterAction::RollBackInterrupted => {
+            app.roll_back_interrupted()?;
+        }
+        FooterAction::DismissInterrupted => {
+            app.dismiss_interrupted()?;
+        }
+        FooterAction::ToggleCommandPin => {
+            app.toggle_command_pin().await?;
+        }
+        FooterAction::OpenFolder => app.open_result_folder(),
+        FooterAction::PlayOutput => app.play_result(),
+        FooterAction::CopyPaths => app.copy_result_paths().await,
+        FooterAction::ViewFullOutput => app.open_output_viewer(),
         FooterAction::ExecuteHistoryCommand => {
             app.execute_history_selected_command().await?;
             app.dirty = true;
         }
         FooterAction::ClarifySelect(idx) => {
+            if let Some(payload) = app.clarify.as_mut() {
+                // Combinable options toggle; RUN SELECTED starts them.
+                if payload.allows_multiple()
+                    && payload.options.get(idx).is_some_and(|opt| opt.combinable)
+                {
+                    payload.selected[idx] = !payload.selected[idx];
+                    app.dirty = true;
+                    return Ok(false);
+                }
+            }
             if let Some(payload) = &app.clarify {
                 if let Some(opt) = payload.options.get(idx) {
                     let label = opt.label.clone();
@@ -156,6 +373,44 @@ pub async fn perform_footer_action(app: &mut App, action: FooterAction) -> Resul
                 }
             }
         }
+        FooterAction::ClarifyRunSelected => {
+            let Some(payload) = &app.clarify else {
+                return Ok(false);
+            };
+            let plan = match clarify_plan(&payload.options, &payload.selected_indices()) {
+                Ok(plan) => plan,
+                Err(e) => {
+                    app.push_log(format!("Clarify: {}", e));
+                    app.dirty = true;
+                    return Ok(false);
+                }
+            };
+            app.log_block(
+                "CLARIFY_PLAN",
+                &plan
+                    .iter()
+                    .enumerate()
+                    .map(|(i, step)| format!("step.{}={}", i + 1, step))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            );
+            let mut steps = plan.into_iter();
+            let first = steps.next().unwrap_or_default();
+            // Later steps wait in the queue, which starts each one when the
+            // step before it finishes.
+            for (i, step) in steps.enumerate() {
+                let id = app.jobs.enqueue(&step);
+                app.push_log(format!("Queued step {} as job #{}: {}", i + 2, id, step));
+            }
+            app.push_log(format!("Clarify step 1: {}", first));
+            app.input = first;
+            app.input_cursor = char_count(&app.input);
+            app.reset_for_new_request();
+            app.focus = FocusArea::FooterButtons;
+            app.footer_focus = 0;
+            app.state = AppState::PendingRouting;
+            app.dirty = true;
+        }
     }
 
     Ok(false)
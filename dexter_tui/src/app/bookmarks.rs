@@ -0,0 +1,157 @@
+//! The Ctrl+B switcher on the input screen: moves Dexter to one of the
+//! directories bookmarked in config.toml and scans the context there, so
+//! another folder doesn't need a restart.
+
+use dexter_core::{replay, Bookmark, Config};
+use std::path::Path;
+
+use crate::app::state::App;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BookmarkPicker {
+    /// Index into `config.bookmarks`.
+    pub selected: usize,
+}
+
+impl BookmarkPicker {
+    /// Opens on the bookmark of `cwd`, if there is one.
+    pub fn open(bookmarks: &[Bookmark], cwd: &Path) -> Self {
+        let selected = bookmarks
+            .iter()
+            .position(|bookmark| bookmark.dir() == cwd)
+            .unwrap_or(0);
+        Self { selected }
+    }
+
+    pub fn move_selection(&mut self, delta: isize, len: usize) {
+        if len == 0 {
+            self.selected = 0;
+            return;
+        }
+        self.selected = (self.selected as isize + delta).rem_euclid(len as isize) as usize;
+    }
+}
+
+/// A bookmark for `dir`, named after its last component and written with
+/// `~/` when under `home`; `None` when one already points there.
+pub fn new_bookmark(bookmarks: &[Bookmark], dir: &Path, home: Option<&Path>) -> Option<Bookmark> {
+    if bookmarks.iter().any(|bookmark| bookmark.dir() == dir) {
+        return None;
+    }
+    let path = match home.and_then(|home| dir.strip_prefix(home).ok()) {
+        Some(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Some(rest) => format!("~/{}", rest.display()),
+        None => dir.display().to_string(),
+    };
+    let name = dir
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.clone());
+    Some(Bookmark { name, path })
+}
+
+impl App {
+    /// Makes the bookmark's directory the working directory and rescans
+    /// the context there.
+    pub async fn switch_to_bookmark(&mut self, bookmark: &Bookmark) {
+        self.dirty = true;
+        let dir = bookmark.dir();
+        if let Err(e) = std::env::set_current_dir(&dir) {
+            self.push_error(
+                "bookmarks",
+                format!("Cannot switch to {}: {}", dir.display(), e),
+            );
+            return;
+        }
+        self.push_log(format!("Working directory: {}", dir.display()));
+        if let Err(e) = self.update_context().await {
+            self.push_error("context", format!("Scan failed: {}", e));
+        }
+    }
+
+    /// Bookmarks the working directory unless it already is.
+    pub async fn bookmark_cwd(&mut self) {
+        self.dirty = true;
+        let Ok(cwd) = std::env::current_dir() else {
+            return;
+        };
+        let home = dexter_core::paths::home_dir();
+        let Some(bookmark) = new_bookmark(&self.config.bookmarks, &cwd, home.as_deref()) else {
+            self.push_log(format!("{} is already bookmarked.", cwd.display()));
+            return;
+        };
+        let mut bookmarks = self.config.bookmarks.clone();
+        let message = format!("Bookmarked {} as {}.", bookmark.path, bookmark.name);
+        bookmarks.push(bookmark);
+        self.save_bookmarks(bookmarks, message).await;
+    }
+
+    pub async fn remove_bookmark(&mut self, idx: usize) {
+        self.dirty = true;
+        let mut bookmarks = self.config.bookmarks.clone();
+        if idx >= bookmarks.len() {
+            return;
+        }
+        let removed = bookmarks.remove(idx);
+        let message = format!("Removed the bookmark {}.", removed.name);
+        self.save_bookmarks(bookmarks, message).await;
+    }
+
+    /// Writes `bookmarks` into config.toml as it is on disk, so settings
+    /// only toggled for this session (offline mode) stay unsaved.
+    async fn save_bookmarks(&mut self, bookmarks: Vec<Bookmark>, message: String) {
+        if !replay::is_replaying() {
+            let saved = match Config::load().await {
+                Ok(mut config) => {
+                    config.bookmarks = bookmarks.clone();
+                    config.save().await
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = saved {
+                self.push_error(
+                    "bookmarks",
+                    format!("Could not save the bookmarks: {:#}", e),
+                );
+                return;
+            }
+        }
+        self.config.bookmarks = bookmarks;
+        self.push_log(message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn bookmarks_each_directory_once() {
+        let home = PathBuf::from("/home/ada");
+        let bookmark = new_bookmark(&[], &home.join("Videos/raw"), Some(&home)).unwrap();
+        assert_eq!(
+            (bookmark.name.as_str(), bookmark.path.as_str()),
+            ("raw", "~/Videos/raw")
+        );
+        let outside = new_bookmark(&[], Path::new("/srv/media"), Some(&home)).unwrap();
+        assert_eq!(outside.path, "/srv/media");
+
+        let bookmarks = vec![
+            outside,
+            Bookmark {
+                name: "tmp".to_string(),
+                path: "/tmp/".to_string(),
+            },
+        ];
+        assert_eq!(
+            new_bookmark(&bookmarks, Path::new("/tmp"), Some(&home)),
+            None
+        );
+        assert_eq!(
+            BookmarkPicker::open(&bookmarks, Path::new("/tmp")).selected,
+            1
+        );
+        assert_eq!(BookmarkPicker::open(&bookmarks, &home).selected, 0);
+    }
+}
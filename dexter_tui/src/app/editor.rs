@@ -1,21 +1,28 @@
+//! Cursor editing for the prompt, command and queue editors. Cursors are
+//! char offsets into the text, but they only ever stop between grapheme
+//! clusters, so an emoji, a flag or a letter with combining accents moves
+//! and deletes as one; columns are counted in terminal cells, so CJK and
+//! other double-width characters line up when moving between lines or
+//! clicking.
+
 use ratatui::layout::Rect;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
-pub fn split_line_at_char(line: &str, idx: usize) -> (String, Option<char>, String) {
-    let mut before = String::new();
-    let mut current = None;
-    let mut after = String::new();
-
-    for (i, ch) in line.chars().enumerate() {
-        if i < idx {
-            before.push(ch);
-        } else if i == idx {
-            current = Some(ch);
-        } else {
-            after.push(ch);
-        }
+/// The line split at the cluster starting at char offset `idx`: the text
+/// before it, the cluster itself (none at the end of the line) and the
+/// text after it.
+pub fn split_line_at_grapheme(line: &str, idx: usize) -> (String, Option<String>, String) {
+    let start = byte_index(line, idx);
+    let rest = &line[start..];
+    match rest.graphemes(true).next() {
+        Some(cluster) => (
+            line[..start].to_string(),
+            Some(cluster.to_string()),
+            rest[cluster.len()..].to_string(),
+        ),
+        None => (line.to_string(), None, String::new()),
     }
-
-    (before, current, after)
 }
 
 pub fn char_count(text: &str) -> usize {
@@ -32,97 +39,151 @@ fn byte_index(text: &str, char_idx: usize) -> usize {
     text.len()
 }
 
+/// Char offsets of the cluster boundaries of `text`, from 0 to its end.
+fn boundaries(text: &str) -> Vec<usize> {
+    let mut offsets = vec![0];
+    let mut offset = 0;
+    for cluster in text.graphemes(true) {
+        offset += char_count(cluster);
+        offsets.push(offset);
+    }
+    offsets
+}
+
+/// The end of the cluster holding char offset `idx`: `idx` itself when a
+/// cluster starts there.
+pub fn grapheme_end(text: &str, idx: usize) -> usize {
+    boundaries(text)
+        .into_iter()
+        .find(|offset| *offset >= idx)
+        .unwrap_or_else(|| char_count(text))
+}
+
+/// The end of the cluster starting at `idx`, or `idx` at the end of `text`.
+pub fn next_grapheme(text: &str, idx: usize) -> usize {
+    boundaries(text)
+        .into_iter()
+        .find(|offset| *offset > idx)
+        .unwrap_or(idx)
+}
+
+/// The start of the cluster ending at `idx`, or 0.
+fn previous_grapheme(text: &str, idx: usize) -> usize {
+    boundaries(text)
+        .into_iter()
+        .rev()
+        .find(|offset| *offset < idx)
+        .unwrap_or(0)
+}
+
+/// Inserts `ch` and moves past it. A combining mark or joiner extends the
+/// cluster before it, so the cursor moves to the end of that cluster.
 pub fn insert_char_at_cursor(text: &mut String, cursor: &mut usize, ch: char) {
     let idx = byte_index(text, *cursor);
     text.insert(idx, ch);
-    *cursor += 1;
+    *cursor = grapheme_end(text, *cursor + 1);
 }
 
-pub fn delete_char_before_cursor(text: &mut String, cursor: &mut usize) {
+pub fn delete_grapheme_before_cursor(text: &mut String, cursor: &mut usize) {
     if *cursor == 0 {
         return;
     }
-    let start = byte_index(text, *cursor - 1);
+    let previous = previous_grapheme(text, *cursor);
+    let start = byte_index(text, previous);
     let end = byte_index(text, *cursor);
-    if start < end {
-        text.replace_range(start..end, "");
-        *cursor -= 1;
-    }
+    text.replace_range(start..end, "");
+    *cursor = previous;
 }
 
-pub fn delete_char_at_cursor(text: &mut String, cursor: &mut usize) {
-    let len = char_count(text);
-    if *cursor >= len {
+pub fn delete_grapheme_at_cursor(text: &mut String, cursor: &mut usize) {
+    let next = next_grapheme(text, *cursor);
+    if next == *cursor {
         return;
     }
     let start = byte_index(text, *cursor);
-    let end = byte_index(text, *cursor + 1);
-    if start < end {
-        text.replace_range(start..end, "");
-    }
+    let end = byte_index(text, next);
+    text.replace_range(start..end, "");
 }
 
-fn line_lengths(text: &str) -> Vec<usize> {
-    let mut lines: Vec<usize> = text.split('\n').map(|line| line.chars().count()).collect();
-    if lines.is_empty() {
-        lines.push(0);
-    }
-    lines
+pub fn move_cursor_left(text: &str, cursor: &mut usize) {
+    *cursor = previous_grapheme(text, *cursor);
 }
 
-fn cursor_line_col(line_lens: &[usize], cursor: usize) -> (usize, usize) {
-    let mut remaining = cursor;
-    for (i, len) in line_lens.iter().enumerate() {
-        if remaining <= *len {
-            return (i, remaining);
+pub fn move_cursor_right(text: &str, cursor: &mut usize) {
+    *cursor = next_grapheme(text, *cursor);
+}
+
+/// The lines of `text` with the char offset each one starts at.
+fn lines_with_offsets(text: &str) -> Vec<(usize, &str)> {
+    let mut offset = 0;
+    text.split('\n')
+        .map(|line| {
+            let start = offset;
+            offset += char_count(line) + 1;
+            (start, line)
+        })
+        .collect()
+}
+
+/// The line the cursor is on and the cells before it on that line.
+fn cursor_line_column(lines: &[(usize, &str)], cursor: usize) -> (usize, usize) {
+    let line = lines
+        .iter()
+        .rposition(|(start, _)| *start <= cursor)
+        .unwrap_or(0);
+    let (start, text) = lines[line];
+    let before = &text[..byte_index(text, cursor - start)];
+    (line, before.width())
+}
+
+/// Char offset in `line` of the cluster covering cell `column`, or the end
+/// of the line when it is shorter.
+fn offset_at_column(line: &str, column: usize) -> usize {
+    let mut width = 0;
+    let mut offset = 0;
+    for cluster in line.graphemes(true) {
+        width += cluster.width();
+        if width > column {
+            return offset;
         }
-        remaining = remaining.saturating_sub(len + 1);
+        offset += char_count(cluster);
     }
-    let last = line_lens.len().saturating_sub(1);
-    let last_len = *line_lens.get(last).unwrap_or(&0);
-    (last, last_len)
+    offset
 }
 
-fn cursor_from_line_col(line_lens: &[usize], line_idx: usize, col: usize) -> usize {
-    let mut idx = 0usize;
-    for i in 0..line_idx {
-        idx = idx.saturating_add(line_lens.get(i).copied().unwrap_or(0) + 1);
-    }
-    let line_len = line_lens.get(line_idx).copied().unwrap_or(0);
-    idx.saturating_add(col.min(line_len))
+/// Moves to line `line_idx`, at the cell column the cursor had.
+fn move_to_line(text: &str, cursor: &mut usize, line_idx: usize, column: usize) {
+    let lines = lines_with_offsets(text);
+    let (start, line) = lines[line_idx.min(lines.len() - 1)];
+    *cursor = start + offset_at_column(line, column);
 }
 
 pub fn move_cursor_up(text: &str, cursor: &mut usize) {
-    let line_lens = line_lengths(text);
-    let (line, col) = cursor_line_col(&line_lens, *cursor);
-    if line == 0 {
-        *cursor = cursor_from_line_col(&line_lens, 0, col);
-        return;
+    let (line, column) = cursor_line_column(&lines_with_offsets(text), *cursor);
+    if line > 0 {
+        move_to_line(text, cursor, line - 1, column);
     }
-    *cursor = cursor_from_line_col(&line_lens, line - 1, col);
 }
 
 pub fn move_cursor_down(text: &str, cursor: &mut usize) {
-    let line_lens = line_lengths(text);
-    let (line, col) = cursor_line_col(&line_lens, *cursor);
-    if line + 1 >= line_lens.len() {
-        *cursor = cursor_from_line_col(&line_lens, line, col);
-        return;
+    let lines = lines_with_offsets(text);
+    let (line, column) = cursor_line_column(&lines, *cursor);
+    if line + 1 < lines.len() {
+        move_to_line(text, cursor, line + 1, column);
     }
-    *cursor = cursor_from_line_col(&line_lens, line + 1, col);
 }
 
 pub fn move_cursor_line_start(text: &str, cursor: &mut usize) {
-    let line_lens = line_lengths(text);
-    let (line, _) = cursor_line_col(&line_lens, *cursor);
-    *cursor = cursor_from_line_col(&line_lens, line, 0);
+    let lines = lines_with_offsets(text);
+    let (line, _) = cursor_line_column(&lines, *cursor);
+    *cursor = lines[line].0;
 }
 
 pub fn move_cursor_line_end(text: &str, cursor: &mut usize) {
-    let line_lens = line_lengths(text);
-    let (line, _) = cursor_line_col(&line_lens, *cursor);
-    let line_len = line_lens.get(line).copied().unwrap_or(0);
-    *cursor = cursor_from_line_col(&line_lens, line, line_len);
+    let lines = lines_with_offsets(text);
+    let (line, _) = cursor_line_column(&lines, *cursor);
+    let (start, line) = lines[line];
+    *cursor = start + char_count(line);
 }
 
 pub fn point_in_rect(rect: Rect, col: u16, row: u16) -> bool {
@@ -140,14 +201,63 @@ pub fn set_cursor_from_click(text: &str, cursor: &mut usize, area: Rect, col: u1
     let row_in_area = row.saturating_sub(area.y) as usize;
     let col_in_area = col.saturating_sub(area.x) as usize;
     let text_row = row_in_area.saturating_sub(1);
-    let lines: Vec<&str> = text.split('\n').collect();
-    let line_idx = text_row.min(lines.len().saturating_sub(1));
-    let line = lines.get(line_idx).copied().unwrap_or("");
 
     let prefix_len = 3usize;
     let col_in_text = col_in_area.saturating_sub(prefix_len);
-    let target_col = col_in_text.min(line.chars().count());
+    move_to_line(text, cursor, text_row, col_in_text);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let line_lens = line_lengths(text);
-    *cursor = cursor_from_line_col(&line_lens, line_idx, target_col);
+    #[test]
+    fn clusters_move_and_delete_as_one() {
+        // "e" + combining acute, a family emoji joined by ZWJs, a flag.
+        let mut text = "ae\u{301}👨‍👩‍👧🇫🇷".to_string();
+        let mut cursor = char_count(&text);
+        move_cursor_left(&text, &mut cursor);
+        assert_eq!(cursor, char_count("ae\u{301}👨‍👩‍👧"));
+        delete_grapheme_before_cursor(&mut text, &mut cursor);
+        assert_eq!(text, "ae\u{301}🇫🇷");
+        move_cursor_left(&text, &mut cursor);
+        assert_eq!(cursor, 1);
+        delete_grapheme_at_cursor(&mut text, &mut cursor);
+        assert_eq!(text, "a🇫🇷");
+        move_cursor_right(&text, &mut cursor);
+        assert_eq!(cursor, char_count(&text));
+
+        let mut text = "e".to_string();
+        let mut cursor = 1;
+        insert_char_at_cursor(&mut text, &mut cursor, '\u{301}');
+        assert_eq!((text.as_str(), cursor), ("e\u{301}", 2));
+        let (before, current, after) = split_line_at_grapheme("ae\u{301}b", 1);
+        assert_eq!(
+            (before.as_str(), current.as_deref(), after.as_str()),
+            ("a", Some("e\u{301}"), "b")
+        );
+    }
+
+    #[test]
+    fn columns_count_terminal_cells() {
+        // The cursor after "日本" sits at cell 4, over the "c" below.
+        let text = "日本語\nabcdef";
+        let mut cursor = 2;
+        move_cursor_down(text, &mut cursor);
+        assert_eq!(cursor, 4 + 4);
+        move_cursor_up(text, &mut cursor);
+        assert_eq!(cursor, 2);
+        // Cell 3 is the right half of "本".
+        move_cursor_down(text, &mut cursor);
+        cursor -= 1;
+        move_cursor_up(text, &mut cursor);
+        assert_eq!(cursor, 1);
+
+        let area = Rect::new(0, 0, 40, 5);
+        let mut cursor = 0;
+        set_cursor_from_click(text, &mut cursor, area, 3 + 5, 1);
+        assert_eq!(cursor, 2);
+        set_cursor_from_click(text, &mut cursor, area, 3 + 30, 2);
+        assert_eq!(cursor, char_count(text));
+    }
 }
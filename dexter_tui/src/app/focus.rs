@@ -0,0 +1,245 @@
+//! Keyboard focus over everything the mouse can click: the input, the
+//! footer's buttons, the HISTORY and SETTINGS buttons and the output
+//! scrollbar, so Dexter works over SSH without mouse reporting.
+
+use crate::app::state::{App, AppState, FocusArea, FooterAction};
+use crate::ui::main_view::footer_buttons_for_state;
+
+/// One place `Tab` can stop.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FocusStop {
+    Proposal,
+    Footer(usize),
+    History,
+    Settings,
+    Scrollbar,
+}
+
+impl App {
+    /// `Tab`: the next stop, wrapping around.
+    pub fn focus_next(&mut self) {
+        self.move_focus(1);
+    }
+
+    /// `Shift+Tab`: the previous stop, wrapping around.
+    pub fn focus_prev(&mut self) {
+        self.move_focus(-1);
+    }
+
+    /// `Left`/`Right` along the buttons: the footer's, then HISTORY and
+    /// SETTINGS.
+    pub fn focus_sideways(&mut self, step: isize) {
+        let buttons: Vec<FocusStop> = self
+            .all_stops()
+            .into_iter()
+            .filter(|stop| !matches!(stop, FocusStop::Proposal | FocusStop::Scrollbar))
+            .collect();
+        self.step_through(&buttons, step);
+    }
+
+    /// What `Enter` or `Space` presses: the focused button's action.
+    pub fn focused_action(&self) -> Option<FooterAction> {
+        match self.focus {
+            FocusArea::FooterButtons => {
+                self.footer_buttons.get(self.footer_focus).map(|b| b.action)
+            }
+            FocusArea::HistoryButton => Some(FooterAction::ToggleHistory),
+            FocusArea::SettingsButton => Some(FooterAction::Settings),
+            FocusArea::Proposal | FocusArea::Scrollbar => None,
+        }
+    }
+
+    /// Moves the focus onto the footer button for `action`, if the footer
+    /// shows one in this state.
+    pub fn focus_footer_action(&mut self, action: FooterAction) -> bool {
+        let Some(idx) = footer_buttons_for_state(self)
+            .iter()
+            .position(|(a, _)| *a == action)
+        else {
+            return false;
+        };
+        self.set_stop(FocusStop::Footer(idx));
+        self.dirty = true;
+        true
+    }
+
+    pub fn button_focused(&self) -> bool {
+        matches!(
+            self.focus,
+            FocusArea::FooterButtons | FocusArea::HistoryButton | FocusArea::SettingsButton
+        )
+    }
+
+    /// Whether the arrow keys move the output scrollbar.
+    pub fn scrollbar_focused(&self) -> bool {
+        self.focus == FocusArea::Scrollbar && self.output_scrollbar_rect.is_some()
+    }
+
+    fn move_focus(&mut self, step: isize) {
+        let stops = self.tab_stops();
+        self.step_through(&stops, step);
+    }
+
+    fn step_through(&mut self, stops: &[FocusStop], step: isize) {
+        if stops.is_empty() {
+            return;
+        }
+        let next = match stops.iter().position(|stop| *stop == self.current_stop()) {
+            Some(idx) => (idx as isize + step).rem_euclid(stops.len() as isize) as usize,
+            None if step < 0 => stops.len() - 1,
+            None => 0,
+        };
+        self.set_stop(stops[next]);
+        self.dirty = true;
+    }
+
+    /// Where `Tab` goes in this state. Where the input takes typing, the
+    /// footer is one stop that `Left`/`Right` move inside; elsewhere each of
+    /// its buttons is a stop of its own.
+    fn tab_stops(&self) -> Vec<FocusStop> {
+        if !self.takes_typing() {
+            return self.all_stops();
+        }
+        let mut stops = vec![FocusStop::Proposal];
+        if !self.footer_buttons.is_empty() {
+            stops.push(FocusStop::Footer(
+                self.footer_focus.min(self.footer_buttons.len() - 1),
+            ));
+        }
+        stops.extend(
+            self.all_stops()
+                .into_iter()
+                .filter(|stop| !matches!(stop, FocusStop::Proposal | FocusStop::Footer(_))),
+        );
+        stops
+    }
+
+    /// Every stop on screen, in reading order.
+    fn all_stops(&self) -> Vec<FocusStop> {
+        let mut stops = Vec::new();
+        if self.takes_typing() {
+            stops.push(FocusStop::Proposal);
+        }
+        stops.extend((0..self.footer_buttons.len()).map(FocusStop::Footer));
+        if self.history_button_rect.is_some() {
+            stops.push(FocusStop::History);
+        }
+        if self.settings_button_rect.is_some() {
+            stops.push(FocusStop::Settings);
+        }
+        if self.output_scrollbar_rect.is_some() {
+            stops.push(FocusStop::Scrollbar);
+        }
+        stops
+    }
+
+    fn takes_typing(&self) -> bool {
+        matches!(
+            self.state,
+            AppState::Input | AppState::EditingCommand | AppState::Executing
+        )
+    }
+
+    fn current_stop(&self) -> FocusStop {
+        match self.focus {
+            FocusArea::Proposal => FocusStop::Proposal,
+            FocusArea::FooterButtons => FocusStop::Footer(self.footer_focus),
+            FocusArea::HistoryButton => FocusStop::History,
+            FocusArea::SettingsButton => FocusStop::Settings,
+            FocusArea::Scrollbar => FocusStop::Scrollbar,
+        }
+    }
+
+    fn set_stop(&mut self, stop: FocusStop) {
+        self.focus = match stop {
+            FocusStop::Proposal => FocusArea::Proposal,
+            FocusStop::Footer(idx) => {
+                self.footer_focus = idx;
+                FocusArea::FooterButtons
+            }
+            FocusStop::History => FocusArea::HistoryButton,
+            FocusStop::Settings => FocusArea::SettingsButton,
+            FocusStop::Scrollbar => FocusArea::Scrollbar,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::state::FooterButton;
+    use dexter_core::Config;
+    use ratatui::layout::Rect;
+
+    fn app_with_buttons(state: AppState) -> App {
+        let mut app = App::new(Config {
+            theme: "dark".to_string(),
+            ..Config::default()
+        });
+        app.state = state;
+        app.footer_buttons = [FooterAction::Execute, FooterAction::BackToInput]
+            .into_iter()
+            .map(|action| FooterButton {
+                rect: Rect::default(),
+                action,
+            })
+            .collect();
+        app.history_button_rect = Some(Rect::default());
+        app.settings_button_rect = Some(Rect::default());
+        app
+    }
+
+    #[test]
+    fn tab_reaches_every_button_and_the_scrollbar() {
+        let mut app = app_with_buttons(AppState::AwaitingConfirmation);
+        app.output_scrollbar_rect = Some(Rect::default());
+        app.focus = FocusArea::FooterButtons;
+        app.footer_focus = 0;
+        let mut seen = Vec::new();
+        for _ in 0..5 {
+            app.focus_next();
+            seen.push((app.focus, app.focused_action()));
+        }
+        assert_eq!(
+            seen,
+            [
+                (FocusArea::FooterButtons, Some(FooterAction::BackToInput)),
+                (FocusArea::HistoryButton, Some(FooterAction::ToggleHistory)),
+                (FocusArea::SettingsButton, Some(FooterAction::Settings)),
+                (FocusArea::Scrollbar, None),
+                (FocusArea::FooterButtons, Some(FooterAction::Execute)),
+            ]
+        );
+        app.focus_prev();
+        assert!(app.scrollbar_focused());
+    }
+
+    #[test]
+    fn the_input_screen_keeps_the_footer_as_one_stop() {
+        let mut app = app_with_buttons(AppState::Input);
+        app.focus = FocusArea::Proposal;
+        app.focus_next();
+        assert_eq!(app.focus, FocusArea::FooterButtons);
+        app.focus_sideways(1);
+        assert_eq!(app.footer_focus, 1);
+        app.focus_sideways(1);
+        assert_eq!(app.focus, FocusArea::HistoryButton);
+        app.focus_next();
+        assert_eq!(app.focus, FocusArea::SettingsButton);
+        app.focus_next();
+        assert_eq!(app.focus, FocusArea::Proposal);
+        assert!(!app.button_focused());
+    }
+
+    #[test]
+    fn a_finished_job_focuses_next_job_instead_of_starting_it() {
+        let mut app = app_with_buttons(AppState::Finished("done".to_string()));
+        app.jobs.enqueue("compress the clips");
+        assert!(app.focus_footer_action(FooterAction::StartNextJob));
+        let buttons = footer_buttons_for_state(&app);
+        assert_eq!(buttons[app.footer_focus].0, FooterAction::StartNextJob);
+        assert_eq!(app.focus, FocusArea::FooterButtons);
+        assert_eq!(app.jobs.pending_count(), 1);
+        assert!(!app.focus_footer_action(FooterAction::CancelQueued));
+    }
+}
@@ -0,0 +1,274 @@
+//! The `?` help overlay: the keys that work in the current state. Its
+//! actions are `footer_buttons_for_state`, the list the footer and the
+//! palette are drawn from, so the help can't offer what the footer hides.
+
+use dexter_core::ConflictPolicy;
+
+use crate::app::state::{App, AppState, FooterAction};
+use crate::ui::diff_table;
+use crate::ui::main_view::footer_buttons_for_state;
+
+const DIGITS: [&str; 9] = ["1", "2", "3", "4", "5", "6", "7", "8", "9"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HelpEntry {
+    /// Empty for actions only reached through the footer or the palette.
+    pub key: &'static str,
+    pub label: String,
+}
+
+impl HelpEntry {
+    fn new(key: &'static str, label: impl Into<String>) -> Self {
+        Self {
+            key,
+            label: label.into(),
+        }
+    }
+}
+
+/// The key that runs `action` in the current state, as the runtime's key
+/// handlers read it.
+pub fn action_key(app: &App, action: FooterAction) -> Option<&'static str> {
+    let key = match (&app.state, action) {
+        (_, FooterAction::ToggleQueue) => "Ctrl+Q",
+        (_, FooterAction::CycleLayout) => "Ctrl+L",
+        (_, FooterAction::ToggleLogFilter) => "Ctrl+E",
+        (_, FooterAction::OpenSearch) => "Ctrl+F",
+        (_, FooterAction::ToggleHistory) => "H",
+        (AppState::Input, FooterAction::Submit) => "Ctrl+D",
+        (AppState::Input, FooterAction::ClearInput) => "Ctrl+U",
+        (AppState::Input, FooterAction::OpenTemplates) => "Ctrl+P",
+        (AppState::Input, FooterAction::ToggleDebug) => "Ctrl+T",
+        (AppState::Input, FooterAction::CycleLogLevel) => "Ctrl+V",
+        (AppState::Input, FooterAction::CycleTarget) => "Ctrl+R",
+        (AppState::Input, FooterAction::ToggleOffline) => "Ctrl+O",
+        (AppState::Input, FooterAction::ForgetSession) => "Ctrl+N",
+        (AppState::Input, FooterAction::Quit) => "Esc",
+        (AppState::AwaitingConfirmation, FooterAction::ConfirmRunLater) => "Enter",
+        (AppState::AwaitingConfirmation, FooterAction::CancelRunLater) => "Esc",
+        (AppState::AwaitingConfirmation, _) if app.schedule_input.is_some() => return None,
+        (AppState::AwaitingConfirmation, FooterAction::Execute) => "Y",
+        (AppState::AwaitingConfirmation, FooterAction::ConfirmDelete) => "D",
+        (AppState::AwaitingConfirmation, FooterAction::RunLater) => "L",
+        (AppState::AwaitingConfirmation, FooterAction::BackToInput) => "N",
+        (AppState::AwaitingConfirmation, FooterAction::EditCommand) => "M",
+        (AppState::AwaitingConfirmation, FooterAction::EditInput) => "E",
+        (AppState::AwaitingConfirmation, FooterAction::Regenerate) => "R",
+        (AppState::AwaitingConfirmation, FooterAction::ToggleCommandPin) => "P",
+        (AppState::AwaitingConfirmation, FooterAction::ToggleInvocation) => "I",
+        (AppState::AwaitingConfirmation, FooterAction::RefreshPreview)
+            if app.context_change.is_some() =>
+        {
+            "F"
+        }
+        (AppState::AwaitingConfirmation, FooterAction::ResolveConflict(policy))
+            if !app.output_conflicts.is_empty() =>
+        {
+            match policy {
+                ConflictPolicy::Overwrite => "O",
+                ConflictPolicy::AutoRename => "A",
+                ConflictPolicy::Skip => "S",
+                _ => return None,
+            }
+        }
+        (AppState::EditingCommand, FooterAction::PreviewEditedCommand) => "Ctrl+D",
+        (AppState::EditingCommand, FooterAction::CancelEditCommand) => "Esc",
+        (AppState::History, FooterAction::ExecuteHistoryCommand) => "X",
+        (AppState::History, FooterAction::ToggleHistoryPin) => "P",
+        (AppState::History, FooterAction::CloseHistory) => "Esc",
+        (AppState::TemplatePicker, FooterAction::ApplyTemplate) => "Enter",
+        (AppState::TemplatePicker, FooterAction::CloseTemplates) => "Esc",
+        (AppState::Error(_), FooterAction::ConfirmOverride) => "Enter",
+        (AppState::Error(_), FooterAction::CancelOverride) => "Esc",
+        (AppState::Error(_), _) if app.override_input.is_some() => return None,
+        (AppState::Error(_), FooterAction::ToggleSafetyRule) => "V",
+        (AppState::Error(_), FooterAction::EditCommand) if app.safety_block.is_some() => "M",
+        (AppState::Error(_), FooterAction::RegenerateWithinRule) => "S",
+        (AppState::Error(_), FooterAction::OverrideSafety) if app.can_override() => "O",
+        (AppState::Finished(_) | AppState::Error(_), FooterAction::Retry) => "R",
+        (AppState::Finished(_) | AppState::Error(_), FooterAction::ResetToInput) => "Enter",
+        (AppState::Finished(_) | AppState::Error(_), FooterAction::ToggleCommandPin)
+            if app.generated_command.is_some() =>
+        {
+            "P"
+        }
+        (AppState::Error(_), FooterAction::InstallMissing) if app.failure_install.is_some() => "I",
+        (AppState::Finished(_), FooterAction::OpenFolder) => "O",
+        (AppState::Finished(_), FooterAction::PlayOutput) => "V",
+        (AppState::Finished(_), FooterAction::CopyPaths) => "C",
+        (AppState::Finished(_), FooterAction::ViewFullOutput) => "F",
+        (AppState::ConfirmingBudget, FooterAction::SendOverBudget) => "Y",
+        (AppState::ConfirmingBudget, FooterAction::BackToInput) => "Esc",
+        (AppState::Clarifying, FooterAction::ClarifySelect(idx)) => DIGITS.get(idx)?,
+        (AppState::Clarifying, FooterAction::BackToInput) => "Esc",
+        _ => return None,
+    };
+    Some(key)
+}
+
+/// The overlay's sections: the footer's actions, the other keys of this
+/// state, then the keys that work everywhere.
+pub fn help_sections(app: &App) -> Vec<(&'static str, Vec<HelpEntry>)> {
+    let actions = footer_buttons_for_state(app)
+        .into_iter()
+        .map(|(action, label)| HelpEntry {
+            key: action_key(app, action).unwrap_or_default(),
+            label,
+        })
+        .collect();
+
+    let mut sections = vec![("ACTIONS", actions)];
+    let keys = state_keys(app);
+    if !keys.is_empty() {
+        sections.push(("IN THIS VIEW", keys));
+    }
+    sections.push((
+        "EVERYWHERE",
+        vec![
+            HelpEntry::new("Ctrl+K", "All actions, searchable"),
+            HelpEntry::new("Ctrl+F", "Search history, templates and the log"),
+            HelpEntry::new("Ctrl+Q", "Show or hide the job queue"),
+            HelpEntry::new("Ctrl+L", "Change the output layout"),
+            HelpEntry::new("Ctrl+E", "Show errors only in the log"),
+            HelpEntry::new(
+                "Tab  Shift+Tab",
+                "Next or previous button, the input or the scrollbar",
+            ),
+            HelpEntry::new(
+                "Left/Right",
+                "Move along the buttons; Enter or Space presses",
+            ),
+            HelpEntry::new("?  F1", "This help; any key closes it"),
+        ],
+    ));
+    sections
+}
+
+/// Keys of the current state that aren't footer actions.
+fn state_keys(app: &App) -> Vec<HelpEntry> {
+    let mut keys = Vec::new();
+    match &app.state {
+        AppState::Input => {
+            keys.push(HelpEntry::new("Ctrl+Enter", "Submit (same as Ctrl+D)"));
+            keys.push(HelpEntry::new("Enter", "New line"));
+            keys.push(HelpEntry::new(
+                "Ctrl+G",
+                "Pick the model for the next request",
+            ));
+            keys.push(HelpEntry::new("Ctrl+B", "Switch to a bookmarked directory"));
+            keys.push(HelpEntry::new("Tab", "Next {placeholder} of a template"));
+            keys.push(HelpEntry::new("@name", "Insert a file name (Tab accepts)"));
+        }
+        AppState::AwaitingConfirmation if app.schedule_input.is_some() => {
+            keys.push(HelpEntry::new("Type", "When to run: 2am, 14:30, in 2h"));
+        }
+        AppState::AwaitingConfirmation => {
+            if !app.read_only {
+                keys.push(HelpEntry::new("Enter", "Execute (same as Y)"));
+            }
+            keys.push(HelpEntry::new("Esc", "Back (same as N)"));
+            keys.push(HelpEntry::new("I", "Show or hide the exact invocation"));
+            if !app.stream_keep.is_empty() {
+                keys.push(HelpEntry::new("1-9", "Keep or drop a stream"));
+            }
+            if diff_table::shown_diffs(app).is_some() {
+                keys.push(HelpEntry::new("T", "Sort the renames by status"));
+                keys.push(HelpEntry::new("[  ]", "Scroll the names sideways"));
+                keys.push(HelpEntry::new("<  >", "Previous or next page"));
+                keys.push(HelpEntry::new("g  G", "First or last page"));
+            }
+        }
+        AppState::EditingCommand => {
+            keys.push(HelpEntry::new("Ctrl+U", "Clear the command"));
+        }
+        AppState::History => {
+            keys.push(HelpEntry::new("Up/Down", "Select a command"));
+            keys.push(HelpEntry::new("F", "Change the filter"));
+        }
+        AppState::TemplatePicker => {
+            keys.push(HelpEntry::new("Up/Down", "Select a template"));
+        }
+        AppState::Executing => {
+            keys.push(HelpEntry::new("Enter", "Queue the request typed below"));
+        }
+        AppState::Error(_) if app.override_input.is_some() => {
+            keys.push(HelpEntry::new("Type", "Why this command is safe to run"));
+        }
+        AppState::Finished(_) | AppState::Error(_) => {
+            keys.push(HelpEntry::new("Esc", "Back (same as Enter)"));
+        }
+        _ => {}
+    }
+    if app.subtasks.is_some()
+        && matches!(
+            app.state,
+            AppState::AwaitingConfirmation | AppState::Finished(_) | AppState::Error(_)
+        )
+    {
+        keys.push(HelpEntry::new("<  >", "Previous or next task"));
+    }
+    if !matches!(
+        app.state,
+        AppState::Input | AppState::EditingCommand | AppState::History | AppState::TemplatePicker
+    ) {
+        keys.push(HelpEntry::new(
+            "Up/Down",
+            "Scroll the output; PgUp/PgDn by page",
+        ));
+        keys.push(HelpEntry::new("Home/End", "Oldest output or the live view"));
+    }
+    if !matches!(app.state, AppState::Input | AppState::EditingCommand) {
+        keys.push(HelpEntry::new("H", "Open or close the history"));
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dexter_core::Config;
+
+    #[test]
+    fn lists_the_footer_actions_with_their_keys() {
+        let mut app = App::new(Config {
+            theme: "dark".to_string(),
+            ..Config::default()
+        });
+        app.state = AppState::AwaitingConfirmation;
+        app.generated_command = Some("f2 -f a -r b".to_string());
+        let sections = help_sections(&app);
+        let (title, actions) = &sections[0];
+        assert_eq!(*title, "ACTIONS");
+        let footer = footer_buttons_for_state(&app);
+        assert_eq!(actions.len(), footer.len());
+        let key_of = |label: &str| {
+            actions
+                .iter()
+                .find(|entry| entry.label == label)
+                .map(|entry| entry.key)
+        };
+        assert_eq!(key_of("EXECUTE"), Some("Y"));
+        assert_eq!(key_of("EDIT CMD"), Some("M"));
+        assert_eq!(key_of("EDIT INPUT"), Some("E"));
+        assert_eq!(key_of("REGEN"), Some("R"));
+        assert_eq!(key_of("QUIT"), Some(""));
+
+        // Read-only drops EXECUTE from the footer, and so from the help.
+        app.read_only = true;
+        assert!(help_sections(&app)[0]
+            .1
+            .iter()
+            .all(|entry| entry.label != "EXECUTE"));
+
+        app.state = AppState::Input;
+        assert_eq!(action_key(&app, FooterAction::Submit), Some("Ctrl+D"));
+        assert_eq!(action_key(&app, FooterAction::Execute), None);
+        assert_eq!(
+            action_key(&app, FooterAction::CycleLogLevel),
+            Some("Ctrl+V")
+        );
+
+        app.state = AppState::TemplatePicker;
+        assert_eq!(action_key(&app, FooterAction::ApplyTemplate), Some("Enter"));
+    }
+}
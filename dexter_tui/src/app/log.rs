@@ -0,0 +1,106 @@
+//! The live log shown in the output pane: each line carries how much it
+//! matters, which part of Dexter wrote it and when, so failures can be
+//! styled apart and filtered out of the routine lines.
+
+use chrono::{DateTime, Local};
+
+/// Log lines kept at most; the oldest go first.
+pub const LOG_LINES: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn label(self) -> &'static str {
+        match self {
+            Severity::Info => "INFO",
+            Severity::Warning => "WARN",
+            Severity::Error => "ERROR",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub severity: Severity,
+    /// The part that wrote it: `router`, `executor`, `safety`, ...
+    pub source: &'static str,
+    pub at: DateTime<Local>,
+    pub message: String,
+}
+
+impl LogEntry {
+    pub fn new(severity: Severity, source: &'static str, message: String) -> Self {
+        Self {
+            severity,
+            source,
+            at: Local::now(),
+            message,
+        }
+    }
+
+    /// `14:02:11 WARN  safety: ...`
+    pub fn line(&self) -> String {
+        format!(
+            "{} {:<5} {}: {}",
+            self.at.format("%H:%M:%S"),
+            self.severity.label(),
+            self.source,
+            self.message
+        )
+    }
+}
+
+/// Which log lines the output views show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFilter {
+    #[default]
+    All,
+    ErrorsOnly,
+}
+
+impl LogFilter {
+    pub fn toggle(self) -> Self {
+        match self {
+            LogFilter::All => LogFilter::ErrorsOnly,
+            LogFilter::ErrorsOnly => LogFilter::All,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LogFilter::All => "all",
+            LogFilter::ErrorsOnly => "errors",
+        }
+    }
+
+    pub fn shows(self, entry: &LogEntry) -> bool {
+        match self {
+            LogFilter::All => true,
+            LogFilter::ErrorsOnly => entry.severity == Severity::Error,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_only_hides_info_and_warnings() {
+        let entries = [
+            LogEntry::new(Severity::Info, "router", "Routed to f2.".to_string()),
+            LogEntry::new(Severity::Warning, "critic", "1 issue".to_string()),
+            LogEntry::new(Severity::Error, "executor", "exit 1".to_string()),
+        ];
+        let shown = |filter: LogFilter| entries.iter().filter(|e| filter.shows(e)).count();
+        assert_eq!(shown(LogFilter::All), 3);
+        assert_eq!(shown(LogFilter::ErrorsOnly), 1);
+        assert!(entries[2].line().ends_with(" ERROR executor: exit 1"));
+        assert!(entries[1].line().contains(" WARN  critic: "));
+    }
+}
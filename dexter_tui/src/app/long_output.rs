@@ -0,0 +1,186 @@
+//! Run output longer than `config.output_line_limit`: the result view
+//! shows its start and end, the whole of it goes to a timestamped file in
+//! the data dir, and VIEW FULL OUTPUT opens that file full screen.
+
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::app::state::App;
+
+/// Saved output files kept; older ones are removed as new ones are written.
+const KEPT_OUTPUT_FILES: usize = 50;
+
+/// The full-screen viewer over a saved output file.
+#[derive(Debug, Default)]
+pub struct OutputViewer {
+    pub path: PathBuf,
+    pub lines: Vec<String>,
+    /// First line on screen.
+    pub scroll: usize,
+    /// Lines the last frame had room for.
+    pub rows: usize,
+}
+
+impl OutputViewer {
+    pub fn max_scroll(&self) -> usize {
+        self.lines.len().saturating_sub(self.rows.max(1))
+    }
+
+    pub fn scroll_by(&mut self, step: isize) {
+        self.scroll = self
+            .scroll
+            .saturating_add_signed(step)
+            .min(self.max_scroll());
+    }
+
+    /// A screen less one line, so a line of context stays in view.
+    pub fn page(&self) -> isize {
+        self.rows.saturating_sub(1).max(1) as isize
+    }
+}
+
+/// `<data dir>/dexter/output/`.
+pub fn output_dir() -> Option<PathBuf> {
+    dexter_core::paths::data_dir().map(|dir| dir.join("dexter").join("output"))
+}
+
+/// Writes `output` to `dir` as `<timestamp>-<plugin>.txt`, then drops the
+/// oldest files past `KEPT_OUTPUT_FILES`. A second save within the same
+/// second gets a `-2`, `-3`, ... suffix rather than replacing the first.
+pub fn save_output(dir: &Path, plugin: &str, output: &str) -> io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+    let mut attempt = 1;
+    let (path, mut file) = loop {
+        let name = match attempt {
+            1 => format!("{}-{}.txt", stamp, plugin),
+            n => format!("{}-{}-{}.txt", stamp, plugin, n),
+        };
+        let path = dir.join(name);
+        match std::fs::File::options()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(file) => break (path, file),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => attempt += 1,
+            Err(e) => return Err(e),
+        }
+    };
+    file.write_all(output.as_bytes())?;
+
+    let mut saved: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+    saved.sort();
+    let excess = saved.len().saturating_sub(KEPT_OUTPUT_FILES);
+    for old in &saved[..excess] {
+        let _ = std::fs::remove_file(old);
+    }
+    Ok(path)
+}
+
+/// `limit` lines of `output`, half from its start and half from its end,
+/// around a note of how many were cut and where the rest is.
+pub fn shorten(output: &str, limit: usize, saved: Option<&Path>) -> String {
+    let lines: Vec<&str> = output.lines().collect();
+    let head = limit.div_ceil(2);
+    let tail = limit / 2;
+    let cut = lines.len() - head - tail;
+    let note = match saved {
+        Some(path) => format!(
+            "... {} lines cut; full output in {} (F: VIEW FULL OUTPUT) ...",
+            cut,
+            path.display()
+        ),
+        None => format!(
+            "... {} lines cut; the full output could not be saved ...",
+            cut
+        ),
+    };
+    lines[..head]
+        .iter()
+        .copied()
+        .chain([note.as_str()])
+        .chain(lines[lines.len() - tail..].iter().copied())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl App {
+    /// The output the result view shows: `output` itself, or when it runs
+    /// past `config.output_line_limit`, its start and end after the whole
+    /// is saved for VIEW FULL OUTPUT.
+    pub fn limit_output(&mut self, output: String) -> String {
+        self.full_output = None;
+        let limit = self.config.output_line_limit;
+        if limit == 0 || output.lines().count() <= limit {
+            return output;
+        }
+        let plugin = self.selected_plugin.as_deref().unwrap_or("output");
+        let saved = match output_dir() {
+            Some(dir) => save_output(&dir, plugin, &output),
+            None => Err(io::Error::new(io::ErrorKind::NotFound, "no data directory")),
+        };
+        match saved {
+            Ok(path) => {
+                self.push_log(format!("Full output saved to {}", path.display()));
+                self.full_output = Some(path);
+            }
+            Err(e) => self.push_error("output", format!("Could not save the full output: {}", e)),
+        }
+        shorten(&output, limit, self.full_output.as_deref())
+    }
+
+    pub fn open_output_viewer(&mut self) {
+        let Some(path) = self.full_output.clone() else {
+            return;
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(text) => {
+                self.output_viewer = Some(OutputViewer {
+                    lines: text.lines().map(str::to_string).collect(),
+                    path,
+                    ..OutputViewer::default()
+                });
+            }
+            Err(e) => self.push_error("output", format!("Cannot open {}: {}", path.display(), e)),
+        }
+        self.dirty = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_start_and_end_and_saves_the_rest() {
+        let output: String = (1..=10).map(|n| format!("line {}\n", n)).collect();
+        let dir = tempfile::tempdir().unwrap();
+        let path = save_output(dir.path(), "ffmpeg", &output).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), output);
+        let again = save_output(dir.path(), "ffmpeg", "second run").unwrap();
+        assert_ne!(again, path);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), output);
+
+        let short = shorten(&output, 5, Some(&path));
+        let lines: Vec<&str> = short.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[..3], ["line 1", "line 2", "line 3"]);
+        assert!(lines[3].contains("5 lines cut"));
+        assert_eq!(lines[4..], ["line 9", "line 10"]);
+
+        let mut viewer = OutputViewer {
+            lines: output.lines().map(str::to_string).collect(),
+            rows: 4,
+            ..OutputViewer::default()
+        };
+        viewer.scroll_by(100);
+        assert_eq!(viewer.scroll, 6);
+        viewer.scroll_by(-viewer.page());
+        assert_eq!(viewer.scroll, 3);
+    }
+}
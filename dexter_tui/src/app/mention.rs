@@ -0,0 +1,195 @@
+//! `@` file references in the intent: typing `@` and part of a name lists
+//! the scanned files that match, and accepting one writes its exact name,
+//! so the model never has to guess which file "the long one" is.
+
+use crate::app::state::{App, AppState, FocusArea};
+
+/// Names listed at most.
+const MAX_MENTIONS: usize = 8;
+
+impl App {
+    /// The `@` being typed at the cursor: its char position and the text
+    /// after it. An `@` counts at the start of the input or after a space,
+    /// so e-mail addresses and `user@host` stay as they are.
+    fn mention_query(&self) -> Option<(usize, String)> {
+        if self.state != AppState::Input || self.focus != FocusArea::Proposal {
+            return None;
+        }
+        let before: Vec<char> = self.input.chars().take(self.input_cursor).collect();
+        let at = before
+            .iter()
+            .rposition(|c| *c == '@' || c.is_whitespace())?;
+        if before[at] != '@' || at > 0 && !before[at - 1].is_whitespace() {
+            return None;
+        }
+        if self
+            .mention_dismissed
+            .as_ref()
+            .is_some_and(|(dismissed, input)| *dismissed == at && *input == self.input)
+        {
+            return None;
+        }
+        Some((at, before[at + 1..].iter().collect()))
+    }
+
+    /// Scanned files matching the `@` being typed: names starting with it
+    /// first, then names and paths containing it.
+    pub fn mention_matches(&self) -> Vec<String> {
+        let (Some((_, query)), Some(context)) = (self.mention_query(), &self.current_context)
+        else {
+            return Vec::new();
+        };
+        mention_matches(&context.files, &query)
+    }
+
+    /// The highlighted match, kept inside the list as it shrinks.
+    pub fn mention_selection(&self, matches: &[String]) -> usize {
+        self.mention_selected.min(matches.len().saturating_sub(1))
+    }
+
+    pub fn mention_move(&mut self, step: isize) {
+        let len = self.mention_matches().len();
+        if len > 0 {
+            let current = self.mention_selected.min(len - 1) as isize;
+            self.mention_selected = (current + step).rem_euclid(len as isize) as usize;
+        }
+        self.dirty = true;
+    }
+
+    /// Replaces `@query` with the highlighted name, quoted when it has
+    /// spaces, and leaves the cursor after it and a space.
+    pub fn accept_mention(&mut self) {
+        let matches = self.mention_matches();
+        let (Some((at, query)), Some(name)) = (
+            self.mention_query(),
+            matches.get(self.mention_selection(&matches)),
+        ) else {
+            return;
+        };
+        let mut name = if name.chars().any(char::is_whitespace) {
+            format!("\"{}\"", name)
+        } else {
+            name.clone()
+        };
+        let chars: Vec<char> = self.input.chars().collect();
+        let end = at + 1 + query.chars().count();
+        let cursor = at + name.chars().count() + 1;
+        if !chars.get(end).is_some_and(|c| c.is_whitespace()) {
+            name.push(' ');
+        }
+        self.input = chars[..at]
+            .iter()
+            .chain(&name.chars().collect::<Vec<_>>())
+            .chain(&chars[end..])
+            .collect();
+        self.input_cursor = cursor;
+        self.mention_selected = 0;
+        self.dirty = true;
+    }
+
+    /// `Esc`: closes the list until the input is edited.
+    pub fn dismiss_mention(&mut self) {
+        self.mention_dismissed = self.mention_query().map(|(at, _)| (at, self.input.clone()));
+        self.mention_selected = 0;
+        self.dirty = true;
+    }
+}
+
+fn mention_matches(files: &[String], query: &str) -> Vec<String> {
+    let query = query.to_lowercase();
+    let name_of = |path: &str| {
+        path.rsplit(['/', '\\'])
+            .next()
+            .unwrap_or(path)
+            .to_lowercase()
+    };
+    let starts: Vec<&String> = files
+        .iter()
+        .filter(|file| name_of(file).starts_with(&query))
+        .collect();
+    let contains = files
+        .iter()
+        .filter(|file| !starts.contains(file) && file.to_lowercase().contains(&query));
+    starts
+        .iter()
+        .copied()
+        .chain(contains)
+        .take(MAX_MENTIONS)
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dexter_core::{Config, FileContext};
+
+    fn app_with_files(files: &[&str]) -> App {
+        let mut app = App::new(Config {
+            theme: "dark".to_string(),
+            ..Config::default()
+        });
+        app.focus = FocusArea::Proposal;
+        app.current_context = Some(FileContext {
+            files: files.iter().map(|f| f.to_string()).collect(),
+            ..FileContext::default()
+        });
+        app
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        app.input.push_str(text);
+        app.input_cursor = app.input.chars().count();
+    }
+
+    #[test]
+    fn lists_names_starting_with_the_query_first() {
+        let files = [
+            "clips/intro.mp4",
+            "talk.mkv",
+            "Intro notes.txt",
+            "outro.mp4",
+        ];
+        let mut app = app_with_files(&files);
+        type_text(&mut app, "convert @intro");
+        assert_eq!(
+            app.mention_matches(),
+            ["clips/intro.mp4", "Intro notes.txt"]
+        );
+        type_text(&mut app, ".m");
+        assert_eq!(app.mention_matches(), ["clips/intro.mp4"]);
+        assert_eq!(mention_matches(&files.map(String::from), "mp4").len(), 2);
+
+        let mut mail = app_with_files(&files);
+        type_text(&mut mail, "send to me@intro");
+        assert!(mail.mention_matches().is_empty());
+    }
+
+    #[test]
+    fn accepting_writes_the_exact_name() {
+        let mut app = app_with_files(&["Intro notes.txt", "intro.mp4"]);
+        type_text(&mut app, "convert @in to pdf");
+        app.input_cursor = "convert @in".len();
+        app.mention_move(1);
+        app.accept_mention();
+        assert_eq!(app.input, "convert intro.mp4 to pdf");
+        assert_eq!(app.input_cursor, "convert intro.mp4 ".len());
+
+        app.input = String::new();
+        type_text(&mut app, "@notes");
+        app.accept_mention();
+        assert_eq!(app.input, "\"Intro notes.txt\" ");
+        assert_eq!(app.input_cursor, app.input.chars().count());
+
+        type_text(&mut app, "@intro");
+        app.dismiss_mention();
+        assert!(app.mention_matches().is_empty());
+        app.input.pop();
+        app.input_cursor -= 1;
+        assert_eq!(app.mention_matches(), ["Intro notes.txt", "intro.mp4"]);
+
+        app.input = String::new();
+        type_text(&mut app, "@intro");
+        assert_eq!(app.mention_matches(), ["Intro notes.txt", "intro.mp4"]);
+    }
+}
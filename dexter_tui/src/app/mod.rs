@@ -1,5 +1,21 @@
 pub mod actions;
+pub mod bookmarks;
 pub mod editor;
+pub mod focus;
+pub mod help;
+pub mod log;
+pub mod long_output;
+pub mod mention;
+pub mod model_picker;
+pub mod palette;
+pub mod queue;
+pub mod replay;
+pub mod results;
 pub mod runtime;
-pub mod session_log;
+pub mod safety_block;
+pub mod scrollback;
+pub mod search;
 pub mod state;
+pub mod subtasks;
+pub mod telemetry;
+pub mod templates;
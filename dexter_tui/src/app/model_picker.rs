@@ -0,0 +1,119 @@
+//! The Ctrl+G picker on the input screen: sends the next generation to one
+//! configured route without touching config.toml.
+
+use dexter_core::{Config, ModelRoute};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModelPicker {
+    /// Index into `picker_entries`; 0 is the configured routing.
+    pub selected: usize,
+}
+
+/// Routes that can be picked: the executor routes, then the router
+/// routes, then each provider's own models, for providers usable right now
+/// (only Ollama when offline).
+pub fn route_choices(config: &Config) -> Vec<ModelRoute> {
+    let providers = config.llm_providers();
+    let provider_models = providers.iter().flat_map(|provider| {
+        provider.models.iter().map(|model| ModelRoute {
+            provider: provider.kind,
+            model: model.clone(),
+        })
+    });
+    let mut choices: Vec<ModelRoute> = Vec::new();
+    for route in config
+        .models
+        .executor_routes
+        .iter()
+        .chain(&config.models.router_routes)
+        .cloned()
+        .chain(provider_models)
+    {
+        let usable =
+            !route.model.trim().is_empty() && providers.iter().any(|p| p.kind == route.provider);
+        if usable && !choices.contains(&route) {
+            choices.push(route);
+        }
+    }
+    choices
+}
+
+pub fn route_label(route: &ModelRoute) -> String {
+    format!("{} / {}", route.provider.display_name(), route.model.trim())
+}
+
+/// `None` first, for going back to the configured routing, then every
+/// choice.
+pub fn picker_entries(config: &Config) -> Vec<Option<ModelRoute>> {
+    std::iter::once(None)
+        .chain(route_choices(config).into_iter().map(Some))
+        .collect()
+}
+
+impl ModelPicker {
+    /// Opens on the route already picked, if any.
+    pub fn open(config: &Config, current: Option<&ModelRoute>) -> Self {
+        let selected = picker_entries(config)
+            .iter()
+            .position(|entry| entry.as_ref() == current)
+            .unwrap_or(0);
+        Self { selected }
+    }
+
+    pub fn move_selection(&mut self, delta: isize, len: usize) {
+        if len == 0 {
+            self.selected = 0;
+            return;
+        }
+        self.selected = (self.selected as isize + delta).rem_euclid(len as isize) as usize;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dexter_core::{ProviderConfig, ProviderKind};
+
+    #[test]
+    fn offers_each_usable_route_once() {
+        let mut config = Config {
+            providers: vec![
+                ProviderConfig {
+                    models: vec!["gpt-4.1".to_string(), "gpt-4.1-mini".to_string()],
+                    ..ProviderConfig::builtin(ProviderKind::OpenAI, Some("sk-test".to_string()))
+                },
+                ProviderConfig {
+                    models: vec!["qwen3:8b".to_string()],
+                    ..ProviderConfig::builtin(ProviderKind::Ollama, None)
+                },
+            ],
+            ..Config::default()
+        };
+        let route = |provider, model: &str| ModelRoute {
+            provider,
+            model: model.to_string(),
+        };
+        config.models.executor_routes = vec![
+            route(ProviderKind::OpenAI, "gpt-4.1"),
+            route(ProviderKind::Gemini, "gemini-2.5-flash"),
+        ];
+        config.models.router_routes = vec![route(ProviderKind::Ollama, "qwen3:8b")];
+
+        let labels: Vec<String> = route_choices(&config).iter().map(route_label).collect();
+        assert_eq!(
+            labels,
+            [
+                "OPENAI / gpt-4.1",
+                "OLLAMA / qwen3:8b",
+                "OPENAI / gpt-4.1-mini"
+            ]
+        );
+
+        let picked = route(ProviderKind::Ollama, "qwen3:8b");
+        assert_eq!(ModelPicker::open(&config, Some(&picked)).selected, 2);
+        assert_eq!(ModelPicker::open(&config, None).selected, 0);
+
+        config.offline = true;
+        assert_eq!(route_choices(&config), [picked]);
+    }
+}
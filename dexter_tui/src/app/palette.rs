@@ -0,0 +1,176 @@
+//! The Ctrl+K palette: every action the footer offers in the current state,
+//! including the ones cut off on narrow terminals, plus the global ones,
+//! narrowed down by a fuzzy query.
+
+use crate::app::help::action_key;
+use crate::app::state::{App, AppState, FooterAction};
+use crate::ui::main_view::footer_buttons_for_state;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Palette {
+    pub query: String,
+    /// Index into the filtered entries.
+    pub selected: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaletteEntry {
+    pub action: FooterAction,
+    pub label: String,
+    /// Key that runs the action without the palette in the current state.
+    pub key: Option<&'static str>,
+}
+
+/// The current state's actions first, then the global ones not already
+/// listed.
+pub fn palette_entries(app: &App) -> Vec<PaletteEntry> {
+    let debug = if app.show_debug {
+        "DEBUG:ON"
+    } else {
+        "DEBUG:OFF"
+    };
+    let offline = if app.config.offline {
+        "OFFLINE:ON"
+    } else {
+        "OFFLINE:OFF"
+    };
+    let history = if app.state == AppState::History {
+        "CLOSE HISTORY"
+    } else {
+        "HISTORY"
+    };
+    let globals = [
+        (FooterAction::ToggleHistory, history.to_string()),
+        (FooterAction::OpenSearch, "SEARCH".to_string()),
+        (FooterAction::Settings, "SETTINGS".to_string()),
+        (FooterAction::ToggleDebug, debug.to_string()),
+        (
+            FooterAction::ToggleLogFilter,
+            format!("LOGS:{}", app.log_filter.label().to_uppercase()),
+        ),
+        (
+            FooterAction::CycleLayout,
+            format!("LAYOUT:{}", app.output_layout.label().to_uppercase()),
+        ),
+        (
+            FooterAction::ToggleQueue,
+            format!("QUEUE:{}", app.jobs.pending_count()),
+        ),
+        (FooterAction::ToggleOffline, offline.to_string()),
+        (FooterAction::ForgetSession, "FORGET SESSION".to_string()),
+        (FooterAction::Quit, "QUIT".to_string()),
+    ];
+    let update = app.update_release.as_ref().map(|release| {
+        (
+            FooterAction::SelfUpdate,
+            format!("UPDATE TO {}", release.version()),
+        )
+    });
+
+    let mut entries: Vec<PaletteEntry> = Vec::new();
+    for (action, label) in footer_buttons_for_state(app)
+        .into_iter()
+        .chain(globals)
+        .chain(update)
+    {
+        if entries.iter().any(|entry| entry.action == action)
+            || (app.read_only && action.runs_commands())
+        {
+            continue;
+        }
+        entries.push(PaletteEntry {
+            action,
+            label,
+            key: action_key(app, action),
+        });
+    }
+    entries
+}
+
+/// How well `query` matches `label`: its characters in order, ignoring
+/// case, scoring runs of adjacent characters and word starts higher.
+/// `None` when it doesn't match at all.
+pub fn fuzzy_score(query: &str, label: &str) -> Option<u32> {
+    let label: Vec<char> = label.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut from = 0;
+    let mut previous: Option<usize> = None;
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let idx = from + label[from..].iter().position(|l| *l == c)?;
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == idx) {
+            score += 4;
+        }
+        if idx == 0 || !label[idx - 1].is_alphanumeric() {
+            score += 2;
+        }
+        previous = Some(idx);
+        from = idx + 1;
+    }
+    Some(score)
+}
+
+impl Palette {
+    /// Entries matching the query, best first; ties keep their order.
+    pub fn matches(&self, app: &App) -> Vec<PaletteEntry> {
+        let mut scored: Vec<(u32, PaletteEntry)> = palette_entries(app)
+            .into_iter()
+            .filter_map(|entry| Some((fuzzy_score(&self.query, &entry.label)?, entry)))
+            .collect();
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        scored.into_iter().map(|(_, entry)| entry).collect()
+    }
+
+    pub fn move_selection(&mut self, delta: isize, len: usize) {
+        if len == 0 {
+            self.selected = 0;
+            return;
+        }
+        self.selected = (self.selected as isize + delta).rem_euclid(len as isize) as usize;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dexter_core::Config;
+
+    #[test]
+    fn fuzzy_matches_prefer_word_starts_and_runs() {
+        assert!(fuzzy_score("", "EXECUTE").is_some());
+        assert!(fuzzy_score("exe", "EXECUTE").is_some());
+        assert!(fuzzy_score("ec", "EDIT CMD").is_some());
+        assert_eq!(fuzzy_score("xq", "EXECUTE"), None);
+        assert!(fuzzy_score("hist", "HISTORY") > fuzzy_score("hist", "SCHEDULED LIST"));
+        assert!(fuzzy_score("ec", "EDIT CMD") > fuzzy_score("ec", "EXECUTE"));
+    }
+
+    #[test]
+    fn lists_state_actions_before_global_ones() {
+        let mut app = App::new(Config {
+            theme: "dark".to_string(),
+            ..Config::default()
+        });
+        app.state = AppState::AwaitingConfirmation;
+        app.generated_command = Some("f2 -f a -r b".to_string());
+        let entries = palette_entries(&app);
+        assert_eq!(entries[0].action, FooterAction::Execute);
+        // Quit is in the footer already and listed once.
+        assert_eq!(
+            entries
+                .iter()
+                .filter(|entry| entry.action == FooterAction::Quit)
+                .count(),
+            1
+        );
+        assert!(entries
+            .iter()
+            .any(|entry| entry.action == FooterAction::Settings));
+
+        let palette = Palette {
+            query: "regen".to_string(),
+            selected: 0,
+        };
+        assert_eq!(palette.matches(&app)[0].action, FooterAction::Regenerate);
+    }
+}
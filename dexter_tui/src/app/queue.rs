@@ -0,0 +1,205 @@
+use crate::app::state::AppState;
+
+/// Pipeline stage of the job currently driving the main state machine.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobStage {
+    Routing,
+    Generating,
+    Previewing,
+    Confirming,
+    Executing,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum JobStatus {
+    Pending,
+    Running(JobStage),
+    Completed,
+    Failed(String),
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Completed | JobStatus::Failed(_) | JobStatus::Cancelled
+        )
+    }
+
+    pub fn can_transition_to(&self, next: &JobStatus) -> bool {
+        match (self, next) {
+            (JobStatus::Pending, JobStatus::Running(JobStage::Routing)) => true,
+            (JobStatus::Pending, JobStatus::Cancelled) => true,
+            (JobStatus::Running(_), next) => next != &JobStatus::Pending,
+            _ => false,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            JobStatus::Pending => "PENDING",
+            JobStatus::Running(JobStage::Routing) => "ROUTING",
+            JobStatus::Running(JobStage::Generating) => "GENERATING",
+            JobStatus::Running(JobStage::Previewing) => "PREVIEWING",
+            JobStatus::Running(JobStage::Confirming) => "AWAITING CONFIRMATION",
+            JobStatus::Running(JobStage::Executing) => "EXECUTING",
+            JobStatus::Completed => "COMPLETED",
+            JobStatus::Failed(_) => "FAILED",
+            JobStatus::Cancelled => "CANCELLED",
+        }
+    }
+
+    /// Job status implied by the main state machine for the active job, or
+    /// `None` for views (history, templates) that leave the job untouched.
+    pub fn from_app_state(state: &AppState) -> Option<JobStatus> {
+        Some(match state {
+            AppState::PendingRouting | AppState::Routing | AppState::Clarifying => {
+                JobStatus::Running(JobStage::Routing)
+            }
+            AppState::PendingGeneration | AppState::Generating => {
+                JobStatus::Running(JobStage::Generating)
+            }
+            AppState::PendingDryRun | AppState::DryRunning | AppState::EditingCommand => {
+                JobStatus::Running(JobStage::Previewing)
+            }
+            AppState::AwaitingConfirmation | AppState::ConfirmingBudget => {
+                JobStatus::Running(JobStage::Confirming)
+            }
+            AppState::Executing => JobStatus::Running(JobStage::Executing),
+            AppState::Finished(_) => JobStatus::Completed,
+            AppState::Error(e) => JobStatus::Failed(e.clone()),
+            AppState::Input => JobStatus::Cancelled,
+            AppState::History | AppState::TemplatePicker => return None,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Job {
+    pub id: u64,
+    pub intent: String,
+    pub status: JobStatus,
+    pub command: Option<String>,
+}
+
+/// Every submitted intent becomes a job. At most one job is running (it owns
+/// `App::state`); the rest wait as `Pending` and start one at a time.
+#[derive(Debug, Default)]
+pub struct JobQueue {
+    jobs: Vec<Job>,
+    next_id: u64,
+}
+
+impl JobQueue {
+    pub fn jobs(&self) -> &[Job] {
+        &self.jobs
+    }
+
+    pub fn enqueue(&mut self, intent: &str) -> u64 {
+        self.next_id += 1;
+        self.jobs.push(Job {
+            id: self.next_id,
+            intent: intent.trim().to_string(),
+            status: JobStatus::Pending,
+            command: None,
+        });
+        self.next_id
+    }
+
+    /// Adds a job that starts running immediately, cancelling whatever job
+    /// was still active (e.g. one abandoned at the confirmation step).
+    pub fn begin(&mut self, intent: &str) -> u64 {
+        if let Some(active) = self.active_mut() {
+            active.status = JobStatus::Cancelled;
+        }
+        let id = self.enqueue(intent);
+        self.transition(id, JobStatus::Running(JobStage::Routing));
+        id
+    }
+
+    pub fn active_mut(&mut self) -> Option<&mut Job> {
+        self.jobs
+            .iter_mut()
+            .find(|j| matches!(j.status, JobStatus::Running(_)))
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.jobs
+            .iter()
+            .filter(|j| j.status == JobStatus::Pending)
+            .count()
+    }
+
+    /// Marks the oldest pending job as running and returns its intent.
+    pub fn start_next(&mut self) -> Option<(u64, String)> {
+        if self.active_mut().is_some() {
+            return None;
+        }
+        let job = self
+            .jobs
+            .iter_mut()
+            .find(|j| j.status == JobStatus::Pending)?;
+        job.status = JobStatus::Running(JobStage::Routing);
+        Some((job.id, job.intent.clone()))
+    }
+
+    pub fn transition(&mut self, id: u64, next: JobStatus) -> bool {
+        let Some(job) = self.jobs.iter_mut().find(|j| j.id == id) else {
+            return false;
+        };
+        if job.status == next || !job.status.can_transition_to(&next) {
+            return false;
+        }
+        job.status = next;
+        true
+    }
+
+    pub fn cancel_pending(&mut self) -> usize {
+        let mut cancelled = 0;
+        for job in self
+            .jobs
+            .iter_mut()
+            .filter(|j| j.status == JobStatus::Pending)
+        {
+            job.status = JobStatus::Cancelled;
+            cancelled += 1;
+        }
+        cancelled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jobs_start_in_order_one_at_a_time() {
+        let mut queue = JobQueue::default();
+        let running = queue.begin("convert a.mov");
+        let first = queue.enqueue("rename photos");
+        let second = queue.enqueue("ocr scan.pdf");
+
+        assert_eq!(queue.start_next(), None);
+        assert!(queue.transition(running, JobStatus::Completed));
+        assert_eq!(
+            queue.start_next(),
+            Some((first, "rename photos".to_string()))
+        );
+        assert_eq!(queue.pending_count(), 1);
+
+        assert_eq!(queue.cancel_pending(), 1);
+        assert_eq!(queue.jobs()[2].id, second);
+        assert_eq!(queue.jobs()[2].status, JobStatus::Cancelled);
+    }
+
+    #[test]
+    fn terminal_jobs_do_not_restart() {
+        let mut queue = JobQueue::default();
+        let id = queue.begin("convert a.mov");
+        assert!(queue.transition(id, JobStatus::Running(JobStage::Executing)));
+        assert!(queue.transition(id, JobStatus::Failed("boom".to_string())));
+        assert!(!queue.transition(id, JobStatus::Running(JobStage::Routing)));
+        assert!(queue.jobs()[0].status.is_terminal());
+    }
+}
@@ -0,0 +1,349 @@
+//! The TUI's side of session logs (see `dexter_core::replay`): input
+//! events are recorded with the state they arrived in, and `dexter replay`
+//! sends them again once the app reaches that state, from a throwaway home
+//! and working directory so the maintainer's config, history and files are
+//! never touched.
+
+use anyhow::{anyhow, Context, Result};
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use dexter_core::replay::{self, InputEvent};
+use dexter_core::{paths, SessionLog};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::app::state::{App, AppState};
+
+/// Pause between replayed events, so the session can be followed on screen.
+pub const REPLAY_STEP: Duration = Duration::from_millis(150);
+/// Longest an event waits for the state it was recorded in before it is
+/// sent anyway.
+const REPLAY_STATE_WAIT: Duration = Duration::from_secs(10);
+
+/// The recorded events still to send.
+#[derive(Debug, Default)]
+pub struct ReplayInput {
+    events: VecDeque<(String, Event)>,
+    pub total: usize,
+    /// The throwaway home the replay runs in, removed on exit.
+    pub root: PathBuf,
+    last_step: Option<Instant>,
+    waiting_since: Option<Instant>,
+}
+
+impl ReplayInput {
+    pub fn is_playing(&self) -> bool {
+        !self.events.is_empty()
+    }
+
+    pub fn sent(&self) -> usize {
+        self.total - self.events.len()
+    }
+}
+
+/// Reads the log at `path` and sets the process up to replay it: a fresh
+/// private home holding the recorded config, stripped of everything that
+/// starts programs, an empty working directory, and the tape serving model
+/// replies, scans and runs.
+pub fn prepare_replay(path: &Path) -> Result<ReplayInput> {
+    let mut log = SessionLog::read(path)?;
+    let root = private_dir()?;
+    paths::set_home(&root)?;
+    let work = root.join("work");
+    std::fs::create_dir(&work).with_context(|| format!("Cannot create {}", work.display()))?;
+    let config_dir = paths::config_dir()
+        .ok_or_else(|| anyhow!("No config directory under {}", root.display()))?
+        .join("dexter");
+    std::fs::create_dir_all(&config_dir)?;
+    std::fs::write(
+        config_dir.join("config.toml"),
+        replay::replayed_config(&log.config)?,
+    )?;
+    std::env::set_current_dir(&work)?;
+
+    let events: VecDeque<(String, Event)> = std::mem::take(&mut log.inputs)
+        .into_iter()
+        .filter_map(|(state, input)| Some((state, to_event(&input)?)))
+        .collect();
+    replay::start_replay(log)?;
+    Ok(ReplayInput {
+        total: events.len(),
+        events,
+        root,
+        ..ReplayInput::default()
+    })
+}
+
+/// A new directory only this user can enter, under a name nobody could
+/// have prepared; an existing one is an error rather than reused.
+fn private_dir() -> Result<PathBuf> {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let root =
+        std::env::temp_dir().join(format!("dexter-replay-{}-{:x}", std::process::id(), nanos));
+    let mut builder = std::fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder
+        .create(&root)
+        .with_context(|| format!("Cannot create {}", root.display()))?;
+    Ok(root)
+}
+
+/// `Input`, `AwaitingConfirmation`, `Finished`: the state without its data.
+pub fn state_name(state: &AppState) -> String {
+    let name = format!("{:?}", state);
+    name.split('(').next().unwrap_or_default().to_string()
+}
+
+impl App {
+    /// Adds a terminal event to the session being recorded.
+    pub fn record_input(&self, event: &Event) {
+        if !replay::is_recording() {
+            return;
+        }
+        if let Some(input) = to_input(event) {
+            let state = state_name(&self.state);
+            replay::record(|| replay::SessionEntry::Input {
+                state,
+                event: input,
+            });
+        }
+    }
+
+    /// The next recorded event, once its step is due and the app is in the
+    /// state it was recorded in.
+    pub fn next_replayed_event(&mut self) -> Option<Event> {
+        let state = state_name(&self.state);
+        let replay = self.replay.as_mut()?;
+        let (expected, _) = replay.events.front()?;
+        if replay
+            .last_step
+            .is_some_and(|last| last.elapsed() < REPLAY_STEP)
+        {
+            return None;
+        }
+        let waited = replay
+            .waiting_since
+            .get_or_insert_with(Instant::now)
+            .elapsed();
+        if *expected != state && waited < REPLAY_STATE_WAIT {
+            return None;
+        }
+        let mismatch = (*expected != state).then(|| expected.clone());
+        let (_, event) = replay.events.pop_front()?;
+        replay.waiting_since = None;
+        replay.last_step = Some(Instant::now());
+        let (sent, total, done) = (replay.sent(), replay.total, !replay.is_playing());
+        if let Some(expected) = mismatch {
+            self.push_warning(
+                "replay",
+                format!(
+                    "Event {} was recorded in {} but the app is in {}; sent anyway.",
+                    sent, expected, state
+                ),
+            );
+        }
+        if done {
+            self.push_log(format!(
+                "Replay finished: {} of {} events sent. The keyboard is yours again.",
+                sent, total
+            ));
+        }
+        Some(event)
+    }
+
+    /// Drops the events not sent yet and gives the keyboard back.
+    pub fn stop_replay(&mut self) {
+        let Some(replay) = self.replay.as_mut() else {
+            return;
+        };
+        let (sent, total) = (replay.sent(), replay.total);
+        replay.events.clear();
+        self.push_log(format!(
+            "Replay stopped after {} of {} events. The keyboard is yours again.",
+            sent, total
+        ));
+        self.dirty = true;
+    }
+}
+
+fn to_input(event: &Event) -> Option<InputEvent> {
+    match event {
+        Event::Key(key) if key.kind == KeyEventKind::Press => Some(InputEvent::Key {
+            key: key_name(key)?,
+        }),
+        Event::Paste(text) => Some(InputEvent::Paste { text: text.clone() }),
+        Event::Mouse(mouse) => Some(InputEvent::Mouse {
+            action: mouse_action(mouse.kind)?,
+            column: mouse.column,
+            row: mouse.row,
+        }),
+        _ => None,
+    }
+}
+
+fn to_event(input: &InputEvent) -> Option<Event> {
+    Some(match input {
+        InputEvent::Key { key } => Event::Key(parse_key(key)?),
+        InputEvent::Paste { text } => Event::Paste(text.clone()),
+        InputEvent::Mouse {
+            action,
+            column,
+            row,
+        } => Event::Mouse(MouseEvent {
+            kind: parse_mouse_action(action)?,
+            column: *column,
+            row: *row,
+            modifiers: KeyModifiers::NONE,
+        }),
+    })
+}
+
+const MODIFIER_NAMES: [(KeyModifiers, &str); 3] = [
+    (KeyModifiers::CONTROL, "ctrl+"),
+    (KeyModifiers::ALT, "alt+"),
+    (KeyModifiers::SHIFT, "shift+"),
+];
+
+const KEY_NAMES: [(KeyCode, &str); 15] = [
+    (KeyCode::Enter, "Enter"),
+    (KeyCode::Esc, "Esc"),
+    (KeyCode::Backspace, "Backspace"),
+    (KeyCode::Tab, "Tab"),
+    (KeyCode::BackTab, "BackTab"),
+    (KeyCode::Up, "Up"),
+    (KeyCode::Down, "Down"),
+    (KeyCode::Left, "Left"),
+    (KeyCode::Right, "Right"),
+    (KeyCode::Home, "Home"),
+    (KeyCode::End, "End"),
+    (KeyCode::PageUp, "PageUp"),
+    (KeyCode::PageDown, "PageDown"),
+    (KeyCode::Delete, "Delete"),
+    (KeyCode::Insert, "Insert"),
+];
+
+/// `ctrl+k`, `shift+BackTab`, `F5`; `None` for keys the app never reads.
+fn key_name(key: &KeyEvent) -> Option<String> {
+    let code = match key.code {
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        code => KEY_NAMES
+            .iter()
+            .find(|(known, _)| *known == code)?
+            .1
+            .to_string(),
+    };
+    let modifiers: String = MODIFIER_NAMES
+        .iter()
+        .filter(|(modifier, _)| key.modifiers.contains(*modifier))
+        .map(|(_, name)| *name)
+        .collect();
+    Some(modifiers + code.as_str())
+}
+
+fn parse_key(name: &str) -> Option<KeyEvent> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = name;
+    while let Some((modifier, prefix)) = MODIFIER_NAMES
+        .iter()
+        .find(|(_, prefix)| rest.len() > prefix.len() && rest.starts_with(prefix))
+    {
+        modifiers |= *modifier;
+        rest = &rest[prefix.len()..];
+    }
+    let mut chars = rest.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => match KEY_NAMES.iter().find(|(_, known)| *known == rest) {
+            Some((code, _)) => *code,
+            None => KeyCode::F(rest.strip_prefix('F')?.parse().ok()?),
+        },
+    };
+    Some(KeyEvent::new(code, modifiers))
+}
+
+const MOUSE_BUTTONS: [(MouseButton, &str); 3] = [
+    (MouseButton::Left, "left"),
+    (MouseButton::Right, "right"),
+    (MouseButton::Middle, "middle"),
+];
+
+/// `down_left`, `drag_right`, `scroll_up`; `None` for moves, which only
+/// matter to the next click.
+fn mouse_action(kind: MouseEventKind) -> Option<String> {
+    let button = |button: MouseButton| {
+        MOUSE_BUTTONS
+            .iter()
+            .find(|(b, _)| *b == button)
+            .map(|(_, name)| *name)
+    };
+    Some(match kind {
+        MouseEventKind::Down(b) => format!("down_{}", button(b)?),
+        MouseEventKind::Up(b) => format!("up_{}", button(b)?),
+        MouseEventKind::Drag(b) => format!("drag_{}", button(b)?),
+        MouseEventKind::ScrollUp => "scroll_up".to_string(),
+        MouseEventKind::ScrollDown => "scroll_down".to_string(),
+        _ => return None,
+    })
+}
+
+fn parse_mouse_action(action: &str) -> Option<MouseEventKind> {
+    match action {
+        "scroll_up" => return Some(MouseEventKind::ScrollUp),
+        "scroll_down" => return Some(MouseEventKind::ScrollDown),
+        _ => {}
+    }
+    let (kind, name) = action.split_once('_')?;
+    let button = MOUSE_BUTTONS.iter().find(|(_, n)| *n == name)?.0;
+    match kind {
+        "down" => Some(MouseEventKind::Down(button)),
+        "up" => Some(MouseEventKind::Up(button)),
+        "drag" => Some(MouseEventKind::Drag(button)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_survive_the_log() {
+        let events = [
+            Event::Key(KeyEvent::new(KeyCode::Char('k'), KeyModifiers::CONTROL)),
+            Event::Key(KeyEvent::new(KeyCode::Char('+'), KeyModifiers::NONE)),
+            Event::Key(KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT)),
+            Event::Key(KeyEvent::new(KeyCode::F(5), KeyModifiers::NONE)),
+            Event::Paste("shrink a.mp4".to_string()),
+            Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column: 3,
+                row: 7,
+                modifiers: KeyModifiers::NONE,
+            }),
+        ];
+        for event in events {
+            let input = to_input(&event).unwrap();
+            assert_eq!(to_event(&input), Some(event));
+        }
+        assert_eq!(
+            to_input(&Event::Key(KeyEvent::new(
+                KeyCode::Char('k'),
+                KeyModifiers::CONTROL
+            ))),
+            Some(InputEvent::Key {
+                key: "ctrl+k".to_string()
+            })
+        );
+        assert_eq!(
+            state_name(&AppState::Finished("ok".to_string())),
+            "Finished"
+        );
+    }
+}
@@ -0,0 +1,104 @@
+//! OPEN FOLDER, PLAY and COPY PATHS on the Finished view of a local run,
+//! and VIEW FULL OUTPUT after long output.
+
+use base64::Engine;
+use dexter_core::opener::{self, copy_to_clipboard, output_folder, playable};
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::app::state::{App, AppState, FooterAction};
+
+impl App {
+    /// Remembers the outputs a successful run left, for the result
+    /// actions. Runs on a remote host leave nothing to open here.
+    pub fn collect_result_outputs(&mut self) {
+        self.result_outputs = match (&self.selected_plugin, &self.generated_command) {
+            (Some(plugin), Some(command)) if self.active_remote().is_none() => {
+                std::env::current_dir()
+                    .ok()
+                    .map(|cwd| opener::result_outputs(plugin, command, &cwd))
+            }
+            _ => None,
+        };
+    }
+
+    /// The result actions this run offers, in footer order.
+    pub fn result_actions(&self) -> Vec<(FooterAction, String)> {
+        if !matches!(self.state, AppState::Finished(_)) {
+            return Vec::new();
+        }
+        let mut actions = Vec::new();
+        if self.full_output.is_some() {
+            actions.push((FooterAction::ViewFullOutput, "VIEW FULL OUTPUT".to_string()));
+        }
+        let policy = &self.config.open;
+        let Some(outputs) = self.result_outputs.as_ref().filter(|_| policy.enabled) else {
+            return actions;
+        };
+        if policy.can_open() {
+            actions.push((FooterAction::OpenFolder, "OPEN FOLDER".to_string()));
+            if playable(outputs).is_some() {
+                actions.push((FooterAction::PlayOutput, "PLAY".to_string()));
+            }
+        }
+        if !outputs.is_empty() {
+            actions.push((FooterAction::CopyPaths, "COPY PATHS".to_string()));
+        }
+        actions
+    }
+
+    pub fn open_result_folder(&mut self) {
+        let Some(outputs) = &self.result_outputs else {
+            return;
+        };
+        let folder = output_folder(outputs, &std::env::current_dir().unwrap_or_default());
+        self.open_with_desktop(folder);
+    }
+
+    pub fn play_result(&mut self) {
+        let Some(file) = self.result_outputs.as_deref().and_then(playable).cloned() else {
+            self.push_log("No audio or video output to play.".to_string());
+            return;
+        };
+        self.open_with_desktop(file);
+    }
+
+    fn open_with_desktop(&mut self, target: PathBuf) {
+        match self.config.open.open(&target) {
+            Ok(()) => self.push_log(format!("Opened {}", target.display())),
+            Err(e) => self.push_error("open", e.to_string()),
+        }
+        self.dirty = true;
+    }
+
+    /// Copies the output paths, one per line, with a clipboard tool or,
+    /// without one (over SSH), through the terminal's OSC 52 clipboard.
+    pub async fn copy_result_paths(&mut self) {
+        let Some(outputs) = self.result_outputs.as_ref().filter(|o| !o.is_empty()) else {
+            return;
+        };
+        let text = outputs
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let count = outputs.len();
+        let via = match copy_to_clipboard(&text).await {
+            Ok(tool) => tool.to_string(),
+            Err(e) => {
+                tracing::debug!(error = %e, "falling back to the terminal clipboard");
+                let data = base64::engine::general_purpose::STANDARD.encode(&text);
+                let mut out = std::io::stdout();
+                match write!(out, "\x1b]52;c;{}\x07", data).and_then(|_| out.flush()) {
+                    Ok(()) => "the terminal".to_string(),
+                    Err(e) => {
+                        self.push_error("open", format!("Copy failed: {}", e));
+                        return;
+                    }
+                }
+            }
+        };
+        self.push_log(format!("Copied {} output path(s) via {}.", count, via));
+        self.dirty = true;
+    }
+}
@@ -1,27 +1,55 @@
 use anyhow::{anyhow, Result};
+use crossterm::cursor::MoveTo;
 use crossterm::event::{
-    self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent,
+    Event, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent,
     MouseEventKind,
 };
-use dexter_core::{CachePolicy, Executor, RouteOutcome, Router, SafetyGuard};
+use crossterm::execute;
+use crossterm::style::Print;
+use dexter_core::conflict::format_conflicts;
+use dexter_core::executor::{record_dir_use, record_plugin_success};
+use dexter_core::remote::remote_command_argv;
+use dexter_core::replay;
+use dexter_core::{
+    CachePolicy, ConflictOutcome, ConflictPolicy, LlmClient, RemoteHost, RouteExplanation,
+    RouteOutcome, SafetyViolation, ScopeSummary, SessionEntry,
+};
+use futures_util::StreamExt;
 use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
 use std::io::Stdout;
 use std::time::{Duration, Instant};
 use tokio::sync::oneshot;
+use tracing::Instrument;
 
 use crate::app::actions::perform_footer_action;
+use crate::app::bookmarks::BookmarkPicker;
 use crate::app::editor::{
-    char_count, delete_char_at_cursor, delete_char_before_cursor, insert_char_at_cursor,
-    move_cursor_down, move_cursor_line_end, move_cursor_line_start, move_cursor_up, point_in_rect,
-    set_cursor_from_click,
+    char_count, delete_grapheme_at_cursor, delete_grapheme_before_cursor, insert_char_at_cursor,
+    move_cursor_down, move_cursor_left, move_cursor_line_end, move_cursor_line_start,
+    move_cursor_right, move_cursor_up, point_in_rect, set_cursor_from_click,
 };
-use crate::app::state::{App, AppState, ClarifyPayload, FocusArea, FooterAction};
+use crate::app::model_picker::{picker_entries, route_choices, route_label, ModelPicker};
+use crate::app::palette::Palette;
+use crate::app::replay::{ReplayInput, REPLAY_STEP};
+use crate::app::search::{log_excerpt, Search, SearchHit, SearchTarget};
+use crate::app::state::{App, AppState, ClarifyPayload, FocusArea, FooterAction, PromptStage};
+use crate::app::telemetry;
 use crate::setup::runtime::run_settings_panel;
+use crate::ui::diff_table;
 use crate::ui::main_view::ui;
+use crate::ui::thumbnail::{kitty_clear_sequence, GraphicsProtocol, ThumbnailPayload};
 use dexter_plugins::PreviewContent;
 
 const PROGRESS_LOG_MIN_INTERVAL: Duration = Duration::from_millis(800);
+/// How often the run journal looks at the planned files while executing.
+const JOURNAL_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+const DIR_WATCH_INTERVAL: Duration = Duration::from_secs(1);
+/// How often the open History view looks for runs other instances added.
+const HISTORY_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+/// Spinner and conveyor belt frame; the loop only ticks this often while a
+/// processing state is on screen.
+const ANIMATION_FRAME: Duration = Duration::from_millis(50);
 
 pub async fn run_app(
     terminal: &mut Terminal<CrosstermBackend<Stdout>>,
@@ -29,51 +57,279 @@ pub async fn run_app(
 ) -> Result<()> {
     // Initial context fetch
     let _ = app.update_context().await;
+    app.reload_scheduled().await;
 
+    let started = Instant::now();
+    // Its reader thread holds the terminal, so it is dropped while the
+    // settings panel reads keys itself.
+    let mut events: Option<EventStream> = None;
     loop {
+        app.tick_count = (started.elapsed().as_millis() / ANIMATION_FRAME.as_millis()) as u64;
+        if app.pending_open_settings {
+            events = None;
+        }
         progress_state_and_settings(terminal, app).await?;
 
         if app.dirty || app.is_processing_state() {
             terminal.draw(|f| ui(f, app))?;
             app.dirty = false;
+            sync_thumbnail_graphics(terminal, app)?;
         }
 
-        let poll_ms = if app.is_processing_state() { 50 } else { 200 };
-        if event::poll(Duration::from_millis(poll_ms))? {
+        if let Some(event) = app.next_replayed_event() {
             app.dirty = true;
-            if handle_runtime_event(app, event::read()?).await? {
+            if handle_runtime_event(app, event).await? {
                 return Ok(());
             }
+            continue;
+        }
+
+        // Sleeps until input, a background result or the next tick of its
+        // own; with nothing running that is the next key press.
+        let tick = next_tick(app);
+        let wake = app.wake.clone();
+        let events = events.get_or_insert_with(EventStream::new);
+        tokio::select! {
+            event = events.next() => {
+                let Some(event) = event else {
+                    return Ok(());
+                };
+                let event = event?;
+                app.dirty = true;
+                // While a replay plays, the keyboard only stops it.
+                if app.replay.as_ref().is_some_and(ReplayInput::is_playing) {
+                    if matches!(&event, Event::Key(key) if key.code == KeyCode::Esc) {
+                        app.stop_replay();
+                    }
+                    continue;
+                }
+                app.record_input(&event);
+                if handle_runtime_event(app, event).await? {
+                    return Ok(());
+                }
+            }
+            _ = wake.notified() => {}
+            _ = tokio::time::sleep(tick.unwrap_or(ANIMATION_FRAME)), if tick.is_some() => {}
+        }
+    }
+}
+
+/// How long the loop may wait for input before it has work of its own:
+/// animation frames while processing, directory checks and the
+/// confirmation timeout while a preview waits for confirmation, history
+/// checks while History is open, the next event of a replay. `None` waits
+/// for input or a wakeup only.
+fn next_tick(app: &App) -> Option<Duration> {
+    if app.replay.as_ref().is_some_and(ReplayInput::is_playing) {
+        return Some(REPLAY_STEP);
+    }
+    if app.is_processing_state() {
+        return Some(ANIMATION_FRAME);
+    }
+    if app.state == AppState::History {
+        return Some(HISTORY_CHECK_INTERVAL);
+    }
+    let watching = app.dir_watch.is_some() || app.config.confirm_timeout.minutes > 0;
+    (app.state == AppState::AwaitingConfirmation && watching).then_some(DIR_WATCH_INTERVAL)
+}
+
+/// Keeps terminal graphics in step with the box the UI reserved for them.
+/// Images live outside ratatui's buffer, so moving or hiding one means
+/// clearing the screen and letting the next frame repaint everything.
+fn sync_thumbnail_graphics(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    app: &mut App,
+) -> Result<()> {
+    let wanted = match &app.thumbnail {
+        Some(thumbnail) if thumbnail.is_graphics() => app.thumbnail_rect,
+        _ => None,
+    };
+    if wanted == app.thumbnail_painted {
+        return Ok(());
+    }
+
+    if app.thumbnail_painted.is_some() {
+        return clear_thumbnail_graphics(terminal, app);
+    }
+
+    if let (Some(rect), Some(thumbnail)) = (wanted, &app.thumbnail) {
+        if let ThumbnailPayload::Graphics(sequence) = &thumbnail.payload {
+            execute!(
+                terminal.backend_mut(),
+                MoveTo(rect.x, rect.y),
+                Print(sequence)
+            )?;
+            app.thumbnail_painted = Some(rect);
         }
     }
+    Ok(())
+}
+
+fn clear_thumbnail_graphics(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    app: &mut App,
+) -> Result<()> {
+    if app.thumbnail_painted.take().is_none() {
+        return Ok(());
+    }
+    if GraphicsProtocol::detect(app.config.thumbnails) == Some(GraphicsProtocol::Kitty) {
+        execute!(terminal.backend_mut(), Print(kitty_clear_sequence()))?;
+    }
+    terminal.clear()?;
+    app.dirty = true;
+    Ok(())
 }
 
 async fn progress_state_and_settings(
     terminal: &mut Terminal<CrosstermBackend<Stdout>>,
     app: &mut App,
 ) -> Result<()> {
-    app.tick_count += 1;
+    if let Some(rx) = &mut app.critic_rx {
+        if let Ok(result) = rx.try_recv() {
+            app.critic_rx = None;
+            match result {
+                Ok(issues) if issues.is_empty() => app.push_log("Critic: no issues found."),
+                Ok(issues) => {
+                    app.push_warning("critic", format!("Flagged {} issue(s).", issues.len()));
+                    app.log_block("CRITIC_ISSUES", &issues.join("\n"));
+                    app.critic_issues = issues;
+                }
+                Err(e) => app.push_warning("critic", format!("Review unavailable: {}", e)),
+            }
+            app.dirty = true;
+        }
+    }
+
+    for error in app.executor.hooks().take_errors() {
+        app.push_warning("hooks", error);
+        app.dirty = true;
+    }
+
+    let watch_due = app
+        .dir_watch_checked_at
+        .map(|last| last.elapsed() >= DIR_WATCH_INTERVAL)
+        .unwrap_or(true);
+    if app.state == AppState::AwaitingConfirmation && app.dir_watch.is_some() && watch_due {
+        app.check_dir_watch();
+    }
+
+    let history_due = app
+        .history_checked_at
+        .map(|last| last.elapsed() >= HISTORY_CHECK_INTERVAL)
+        .unwrap_or(true);
+    if app.state == AppState::History && history_due {
+        if let Err(e) = app.refresh_history_if_changed().await {
+            app.push_log(format!("History reload failed: {}", e));
+        }
+    }
+
+    if app.state != AppState::AwaitingConfirmation {
+        app.awaiting_since = None;
+    } else if app.awaiting_since.is_none() {
+        app.awaiting_since = Some(Instant::now());
+    } else if let Some(deadline) = app.confirm_deadline() {
+        if Instant::now() >= deadline {
+            app.expire_confirmation().await?;
+        }
+        // Keeps the countdown current.
+        app.dirty = true;
+    }
+
+    if let Some(rx) = &mut app.install_rx {
+        if let Ok(result) = rx.try_recv() {
+            app.install_rx = None;
+            match result {
+                Ok(output) => {
+                    app.log_block("INSTALL_OUTPUT", &output);
+                    app.push_log("Install finished; press RETRY to run the request again.");
+                }
+                Err(e) => app.push_error("install", format!("Install failed: {}", e)),
+            }
+            app.dirty = true;
+        }
+    }
+
+    if let Some(rx) = &mut app.update_rx {
+        if let Ok(result) = rx.try_recv() {
+            app.update_rx = None;
+            match result {
+                Ok(Some(release)) => {
+                    app.push_log(format!(
+                        "Dexter {} is available; Ctrl+K > UPDATE installs it.",
+                        release.version()
+                    ));
+                    app.update_release = Some(release);
+                }
+                Ok(None) => {}
+                Err(e) => app.push_warning("update", format!("Update check failed: {}", e)),
+            }
+            app.dirty = true;
+        }
+    }
+
+    if let Some(rx) = &mut app.self_update_rx {
+        if let Ok(result) = rx.try_recv() {
+            app.self_update_rx = None;
+            match result {
+                Ok(path) => {
+                    if let Some(release) = app.update_release.take() {
+                        app.push_log(format!(
+                            "Installed Dexter {} to {}; restart to use it.",
+                            release.version(),
+                            path.display()
+                        ));
+                    }
+                }
+                Err(e) => app.push_error("update", e.to_string()),
+            }
+            app.dirty = true;
+        }
+    }
+
+    if let Some(rx) = &mut app.remote_scan_rx {
+        if let Ok(result) = rx.try_recv() {
+            app.remote_scan_rx = None;
+            app.finish_remote_scan(result);
+        }
+    }
+
+    if let Some(rx) = &mut app.thumbnail_rx {
+        if let Ok(result) = rx.try_recv() {
+            app.thumbnail_rx = None;
+            match result {
+                Ok(thumbnail) => app.thumbnail = Some(thumbnail),
+                Err(e) => app.push_warning("preview", format!("Thumbnails unavailable: {}", e)),
+            }
+            app.dirty = true;
+        }
+    }
+
+    app.poll_subtasks();
 
     // Non-blocking automatic state transitions.
     match app.state {
         AppState::PendingRouting => {
             app.state = AppState::Routing;
             let _ = app.update_context().await;
-            let input = app.input.clone();
-            let context =
-                app.current_context
-                    .clone()
-                    .unwrap_or(dexter_core::context::FileContext {
-                        files: Vec::new(),
-                        summary: None,
-                    });
+            let input = app.llm_input();
+            if app.take_direct_command(&input) {
+                return Ok(());
+            }
+            let context = app.current_context.clone().unwrap_or_default();
             let plugins = app.plugins.clone();
-            let llm = app.router.llm_client().clone();
+            if app.subtasks.is_none() && app.start_subtasks(&context) {
+                return Ok(());
+            }
+            let router = app.router.clone().with_session(app.session.transcript());
+            if let Some(prompt) = router.prompt_for(&input, &context, &plugins) {
+                if app.hold_for_budget(PromptStage::Routing, &prompt) {
+                    return Ok(());
+                }
+            }
 
             let (tx, rx) = oneshot::channel();
             tokio::spawn(async move {
-                let router = Router::new(llm);
-                let res = router.route(&input, &context, &plugins).await;
+                let res = router.route_explained(&input, &context, &plugins).await;
                 let _ = tx.send(res);
             });
             app.routing_result_rx = Some(rx);
@@ -96,24 +352,58 @@ async fn progress_state_and_settings(
                     return Ok(());
                 }
             };
-            let input = app.input.clone();
-            let context =
-                app.current_context
-                    .clone()
-                    .unwrap_or(dexter_core::context::FileContext {
-                        files: Vec::new(),
-                        summary: None,
-                    });
-            let llm = app.executor.llm_client().clone();
+            let input = app.llm_input();
+            let context = app.current_context.clone().unwrap_or_default();
+            let mut executor = app.executor.clone().with_session(app.session.transcript());
+            let prompt = executor.prompt_for(&input, &context, plugin.as_ref()).await;
+            if app.hold_for_budget(PromptStage::Generation, &prompt) {
+                return Ok(());
+            }
+            if let Some(route) = app.model_override.take() {
+                app.push_log(format!(
+                    "Generating with {} for this request only.",
+                    route_label(&route)
+                ));
+                executor = executor.with_llm_client(
+                    LlmClient::for_route(app.config.llm_providers(), route)
+                        .with_spend_tracking(app.config.pricing.clone()),
+                );
+            }
             let cache_policy = app.generation_cache_policy;
             app.generation_cache_policy = CachePolicy::Normal;
+            let repair = app.pending_repair.take();
+            let constraint = app.pending_constraint.take();
 
             let (tx, rx) = oneshot::channel();
             tokio::spawn(async move {
-                let executor = Executor::new(llm);
-                let res = executor
-                    .generate_command_with_policy(&input, &context, plugin.as_ref(), cache_policy)
-                    .await;
+                let res = match (repair, constraint) {
+                    (Some((failed, error)), _) => {
+                        executor
+                            .repair_command(&input, &context, plugin.as_ref(), &failed, &error)
+                            .await
+                    }
+                    (None, Some((blocked, reason))) => {
+                        executor
+                            .constrained_command(
+                                &input,
+                                &context,
+                                plugin.as_ref(),
+                                &blocked,
+                                &reason,
+                            )
+                            .await
+                    }
+                    (None, None) => {
+                        executor
+                            .generate_command_with_policy(
+                                &input,
+                                &context,
+                                plugin.as_ref(),
+                                cache_policy,
+                            )
+                            .await
+                    }
+                };
                 let _ = tx.send(res);
             });
             app.generation_result_rx = Some(rx);
@@ -145,63 +435,165 @@ async fn progress_state_and_settings(
                 }
             };
             let llm = app.executor.llm_client().clone();
+            let remote = app.active_remote().cloned();
+            let overridden = app.overrides(&cmd);
+            let checked = if overridden {
+                Ok(())
+            } else {
+                app.safety_guard().and_then(|guard| guard.check(&cmd))
+            };
+            if let Err(e) = checked {
+                app.push_error("preview", format!("Preview failed: {}", e));
+                app.log_block("DRY_RUN_ERROR", &e.to_string());
+                app.refuse_command(&cmd, &e.to_string(), true);
+                return Ok(());
+            }
+            app.safety_block = None;
+            let mut cmd = cmd;
+            app.output_conflicts.clear();
+            // Remote outputs live on the other host; only local runs are checked.
+            if remote.is_none() {
+                let cwd = std::env::current_dir().unwrap_or_default();
+                let policy = app.config.conflict_policy_for(plugin.name());
+                match app
+                    .executor
+                    .resolve_output_conflicts(&plugin_name, &cmd, &cwd, policy)
+                {
+                    Ok(ConflictOutcome::Clear) => {}
+                    Ok(ConflictOutcome::Rewritten { command, conflicts }) => {
+                        app.push_log(format!(
+                            "{} existing output(s); policy {:?} rewrote the command.",
+                            conflicts.len(),
+                            policy
+                        ));
+                        app.log_block("OUTPUT_CONFLICTS", &format_conflicts(&conflicts));
+                        // Renamed outputs don't change what was overridden.
+                        if overridden {
+                            if let Some(safety_override) = &mut app.safety_override {
+                                safety_override.command = command.clone();
+                            }
+                        }
+                        cmd = command;
+                        app.command_draft = cmd.clone();
+                        app.command_cursor = char_count(&cmd);
+                        app.generated_command = Some(cmd.clone());
+                    }
+                    Ok(ConflictOutcome::Skip { conflicts }) => {
+                        let report = format_conflicts(&conflicts);
+                        app.push_log("Skipped: output files already exist.".to_string());
+                        app.log_block("OUTPUT_CONFLICTS", &report);
+                        app.state = AppState::Finished(format!("Skipped.\n{}", report));
+                        app.dirty = true;
+                        return Ok(());
+                    }
+                    Ok(ConflictOutcome::Ask { conflicts }) => {
+                        app.log_block("OUTPUT_CONFLICTS", &format_conflicts(&conflicts));
+                        app.output_conflicts = conflicts;
+                    }
+                    Err(e) => app.push_warning(
+                        "safety",
+                        format!("Output conflict check unavailable: {}", e),
+                    ),
+                }
+            }
+            app.start_critic(&cmd);
+            let dry_run_span = tracing::info_span!(
+                "dry_run",
+                plugin = %plugin_name,
+                target = %app.execution_target_label()
+            );
 
+            let sandbox = app.config.sandbox.clone();
+            let metered = app.config.metered;
             let (tx, rx) = oneshot::channel();
-            tokio::spawn(async move {
-                if let Err(e) = SafetyGuard::default().check(&cmd) {
-                    let _ = tx.send(Err(anyhow!("Safety check failed: {}", e)));
-                    return;
-                }
-                if !plugin.validate_command(&cmd) {
-                    let _ = tx.send(Err(anyhow!("Command failed plugin validation logic")));
-                    return;
+            tokio::spawn(
+                async move {
+                    if !plugin.validate_command(&cmd) {
+                        let _ = tx.send(Err(anyhow!("Command failed plugin validation logic")));
+                        return;
+                    }
+                    // Plugin dry runs inspect the local filesystem, which would be
+                    // misleading for a remote target; show the ssh invocation instead.
+                    let res = match (replay::replayed_dry_run(), remote) {
+                        (Some(replayed), _) => replayed,
+                        (None, Some(remote)) => {
+                            remote_preview(&remote, &cmd).map(|preview| (preview, None))
+                        }
+                        (None, None) => {
+                            let cwd = std::env::current_dir().unwrap_or_default();
+                            let preview = sandbox.dry_run(plugin.as_ref(), &cmd, &cwd, Some(&llm));
+                            metered
+                                .preview(plugin.as_ref(), preview)
+                                .await
+                                .map(|preview| {
+                                    let scope =
+                                        ScopeSummary::measure(plugin.name(), &cmd, &cwd, &preview);
+                                    (preview, scope)
+                                })
+                        }
+                    };
+                    if let Err(e) = &res {
+                        tracing::warn!(error = %e, "dry run failed");
+                    }
+                    replay::record(|| SessionEntry::DryRun {
+                        preview: replay::error_text(&res),
+                    });
+                    let _ = tx.send(res);
                 }
-                let res = plugin.dry_run(&cmd, Some(&llm)).await;
-                let _ = tx.send(res);
-            });
+                .instrument(dry_run_span),
+            );
             app.dry_run_result_rx = Some(rx);
         }
         AppState::Routing => {
             if let Some(rx) = &mut app.routing_result_rx {
                 if let Ok(result) = rx.try_recv() {
                     app.routing_result_rx = None;
+                    app.check_spending();
                     match result {
-                        Ok(outcome) => match outcome {
-                            RouteOutcome::Selected { plugin, .. } => {
-                                app.selected_plugin = Some(plugin.clone());
-                                app.push_log(format!("Routed to plugin: {}", plugin));
-                                app.generation_cache_policy = CachePolicy::Normal;
-                                app.state = AppState::PendingGeneration;
-                                app.dirty = true;
-                            }
-                            RouteOutcome::Unsupported { reason } => {
-                                app.notice = Some(format!(
-                                    "This request isn’t supported.\n{}\nTry: convert formats or rename files (rename only, no conversion).",
+                        Ok((outcome, explanation)) => {
+                            app.log_block(
+                                "ROUTING_EXPLANATION",
+                                &format_route_explanation(&explanation),
+                            );
+                            app.route_explanation = Some(explanation);
+                            match outcome {
+                                RouteOutcome::Selected { plugin, .. } => {
+                                    app.selected_plugin = Some(plugin.clone());
+                                    app.push_log(format!("Routed to plugin: {}", plugin));
+                                    app.generation_cache_policy = CachePolicy::Normal;
+                                    app.state = AppState::PendingGeneration;
+                                    app.dirty = true;
+                                }
+                                RouteOutcome::Unsupported { reason } => {
+                                    app.notice = Some(format!(
+                                    "This request isn't supported.\n{}\nTry: convert formats or rename files (rename only, no conversion).",
                                     reason
                                 ));
-                                app.push_log("Routing result: unsupported request".to_string());
-                                app.log_block("ROUTING_UNSUPPORTED", &reason);
-                                app.state = AppState::Input;
-                                app.focus = FocusArea::Proposal;
-                                app.footer_focus = 0;
-                                app.dirty = true;
-                            }
-                            RouteOutcome::Clarify {
-                                question, options, ..
-                            } => {
-                                let clarify_text = format_clarify_block(&question, &options);
-                                app.clarify = Some(ClarifyPayload { question, options });
-                                app.notice = None;
-                                app.push_log("Routing requires clarification".to_string());
-                                app.log_block("ROUTING_CLARIFY", &clarify_text);
-                                app.state = AppState::Clarifying;
-                                app.focus = FocusArea::FooterButtons;
-                                app.footer_focus = 0;
-                                app.dirty = true;
+                                    app.push_log("Routing result: unsupported request".to_string());
+                                    app.log_block("ROUTING_UNSUPPORTED", &reason);
+                                    app.state = AppState::Input;
+                                    app.focus = FocusArea::Proposal;
+                                    app.footer_focus = 0;
+                                    app.dirty = true;
+                                }
+                                RouteOutcome::Clarify {
+                                    question, options, ..
+                                } => {
+                                    let clarify_text = format_clarify_block(&question, &options);
+                                    app.clarify = Some(ClarifyPayload::new(question, options));
+                                    app.notice = None;
+                                    app.push_log("Routing requires clarification".to_string());
+                                    app.log_block("ROUTING_CLARIFY", &clarify_text);
+                                    app.state = AppState::Clarifying;
+                                    app.focus = FocusArea::FooterButtons;
+                                    app.footer_focus = 0;
+                                    app.dirty = true;
+                                }
                             }
-                        },
+                        }
                         Err(e) => {
                             app.log_block("ROUTING_ERROR", &e.to_string());
+                            app.push_error("router", e.to_string());
                             app.state = AppState::Error(format!("Routing error: {}", e));
                             app.dirty = true;
                         }
@@ -213,6 +605,7 @@ async fn progress_state_and_settings(
             if let Some(rx) = &mut app.generation_result_rx {
                 if let Ok(result) = rx.try_recv() {
                     app.generation_result_rx = None;
+                    app.check_spending();
                     match result {
                         Ok(cmd) => {
                             app.generated_command = Some(cmd.clone());
@@ -227,7 +620,15 @@ async fn progress_state_and_settings(
                         }
                         Err(e) => {
                             app.log_block("GENERATION_ERROR", &e.to_string());
-                            app.state = AppState::Error(format!("Generation error: {}", e));
+                            app.push_error("executor", format!("Generation failed: {}", e));
+                            match e.downcast_ref::<SafetyViolation>() {
+                                Some(violation) => {
+                                    app.refuse_command(&violation.command, &violation.reason, true)
+                                }
+                                None => {
+                                    app.state = AppState::Error(format!("Generation error: {}", e))
+                                }
+                            }
                             app.dirty = true;
                         }
                     }
@@ -239,19 +640,36 @@ async fn progress_state_and_settings(
                 if let Ok(result) = rx.try_recv() {
                     app.dry_run_result_rx = None;
                     match result {
-                        Ok(output) => {
+                        Ok((output, scope)) => {
+                            if let (Some(subtasks), Some(command)) =
+                                (&mut app.subtasks, &app.generated_command)
+                            {
+                                subtasks.update_command(command);
+                            }
                             let preview_text = preview_to_log(&output);
                             app.push_log("Preview data captured successfully.".to_string());
                             app.log_block("DRY_RUN_PREVIEW", &preview_text);
+                            if let PreviewContent::StreamSelection(selection) = &output {
+                                app.stream_keep = selection.selected.clone();
+                                app.stream_base_command = app.generated_command.clone();
+                            }
                             app.dry_run_output = Some(output);
+                            app.preview_scope = scope;
                             app.output_scroll = 0;
+                            app.diff_page = 0;
+                            app.diff_sort_by_status = false;
+                            app.diff_hscroll = 0;
+                            app.delete_hold = false;
+                            app.delete_confirmed = false;
                             app.state = AppState::AwaitingConfirmation;
+                            app.start_thumbnail();
+                            app.start_dir_watch();
                             app.dirty = true;
                         }
                         Err(e) => {
-                            app.push_log(format!("Preview failed: {}", e));
+                            app.push_error("preview", format!("Preview failed: {}", e));
                             app.log_block("DRY_RUN_ERROR", &e.to_string());
-                            app.state = AppState::Error(format!("Dry run failed: {}", e));
+                            app.fail_with(format!("Dry run failed: {}", e));
                             app.dirty = true;
                         }
                     }
@@ -275,7 +693,7 @@ async fn progress_state_and_settings(
                         .map(|last| now.duration_since(last) >= PROGRESS_LOG_MIN_INTERVAL)
                         .unwrap_or(true);
                     if line_changed || interval_elapsed {
-                        app.session_logger.event("PROGRESS", &progress_line);
+                        telemetry::event("PROGRESS", &progress_line);
                         app.last_progress_log_line = Some(progress_line);
                         app.last_progress_log_at = Some(now);
                     }
@@ -284,22 +702,63 @@ async fn progress_state_and_settings(
                 }
             }
 
+            let journal_due = app
+                .journal_checked_at
+                .map(|last| last.elapsed() >= JOURNAL_CHECK_INTERVAL)
+                .unwrap_or(true);
+            if app.journal.is_some() && journal_due {
+                app.check_journal();
+            }
+
             // Check for completion.
             let mut finished = false;
             if let Some(rx) = &mut app.execution_result_rx {
-                if let Ok(result) = rx.try_recv() {
+                if let Ok((result, report)) = rx.try_recv() {
                     finished = true;
+                    app.record_run_outcome(&result).await;
                     match result {
                         Ok(output) => {
+                            if let Some(subtasks) = &mut app.subtasks {
+                                subtasks.record_run(true);
+                            }
                             app.log_block("EXECUTION_OUTPUT", &output);
+                            if let Some(report) = report {
+                                app.record_execution_report(report);
+                            }
+                            let output = app.limit_output(output);
                             app.state = AppState::Finished(output);
+                            app.collect_result_outputs();
                             app.push_log("Execution completed successfully.".to_string());
+                            if let (Some(plugin), Some(command)) =
+                                (&app.selected_plugin, &app.generated_command)
+                            {
+                                app.session.record(&app.input, plugin, command);
+                            }
+                            if let Some(plugin) = app.selected_plugin.clone() {
+                                if let Err(e) = record_plugin_success(&plugin).await {
+                                    tracing::warn!(error = %e, "failed to record plugin success");
+                                }
+                                let cwd = std::env::current_dir()
+                                    .ok()
+                                    .filter(|_| app.active_remote().is_none());
+                                if let Some(cwd) = cwd {
+                                    if let Err(e) = record_dir_use(&cwd, &plugin).await {
+                                        tracing::warn!(error = %e, "failed to record directory use");
+                                    }
+                                }
+                            }
                             let _ = app.update_context().await;
                             app.dirty = true;
                         }
                         Err(e) => {
+                            if let Some(subtasks) = &mut app.subtasks {
+                                subtasks.record_run(false);
+                            }
                             app.log_block("EXECUTION_ERROR", &e.to_string());
-                            app.state = AppState::Error(format!("Execution failed: {}", e));
+                            app.push_error("executor", format!("Execution failed: {}", e));
+                            if !app.start_repair(&e.to_string()) {
+                                app.fail_with(format!("Execution failed: {}", e));
+                            }
                             app.dirty = true;
                         }
                     }
@@ -307,6 +766,19 @@ async fn progress_state_and_settings(
             }
 
             if finished {
+                // The queue waits for the user: NEXT JOB takes the focus so
+                // one Enter starts it, and the output stays up until then.
+                let pending = app.jobs.pending_count();
+                if matches!(app.state, AppState::Finished(_))
+                    && pending > 0
+                    && app.focus_footer_action(FooterAction::StartNextJob)
+                {
+                    app.push_log(format!(
+                        "{} queued job(s) waiting. Press Enter to start the next.",
+                        pending
+                    ));
+                }
+                app.finish_journal();
                 app.progress_rx = None;
                 app.execution_result_rx = None;
                 app.progress = None;
@@ -318,6 +790,8 @@ async fn progress_state_and_settings(
         _ => {}
     }
 
+    app.sync_active_job();
+
     if app.pending_open_settings {
         app.pending_open_settings = false;
         let busy = matches!(
@@ -333,7 +807,8 @@ async fn progress_state_and_settings(
         if busy {
             app.push_log("Cannot open settings while a task is running.".to_string());
         } else {
-            match run_settings_panel(terminal, app.config.clone()).await {
+            clear_thumbnail_graphics(terminal, app)?;
+            match run_settings_panel(terminal, app.config.clone(), app.read_only).await {
                 Ok(new_config) => {
                     app.apply_config(new_config);
                     app.push_log("Settings updated.".to_string());
@@ -342,7 +817,7 @@ async fn progress_state_and_settings(
                 Err(e) => {
                     let msg = e.to_string();
                     if !msg.to_lowercase().contains("aborted") {
-                        app.push_log(format!("Settings update failed: {}", msg));
+                        app.push_error("settings", format!("Update failed: {}", msg));
                         app.log_block("SETTINGS_ERROR", &msg);
                         app.dirty = true;
                     }
@@ -354,6 +829,41 @@ async fn progress_state_and_settings(
     Ok(())
 }
 
+fn remote_preview(remote: &RemoteHost, cmd: &str) -> Result<PreviewContent> {
+    let argv = remote_command_argv(remote, cmd)?;
+    Ok(PreviewContent::Text(format!(
+        "Remote target: {} ({})\nWorking directory: {}\nLocal dry-run preview is skipped for remote targets.\n\nInvocation:\n{}",
+        remote.name,
+        remote.destination(),
+        remote.workdir(),
+        argv.join(" ")
+    )))
+}
+
+fn format_route_explanation(explanation: &RouteExplanation) -> String {
+    let mut out = vec![
+        format!("rule_based={}", explanation.rule_based),
+        format!("cached={}", explanation.cached),
+        format!("decision={}", explanation.decision),
+    ];
+    if let Some(plugin) = &explanation.chosen_plugin {
+        out.push(format!("chosen_plugin={}", plugin));
+    }
+    if let Some(confidence) = explanation.confidence {
+        out.push(format!("confidence={:.2}", confidence));
+    }
+    if !explanation.reasoning.is_empty() {
+        out.push(format!("reasoning={}", explanation.reasoning));
+    }
+    for score in &explanation.scores {
+        out.push(format!(
+            "score.{}={:.2} {}",
+            score.plugin, score.score, score.reason
+        ));
+    }
+    out.join("\n")
+}
+
 fn preview_to_log(preview: &PreviewContent) -> String {
     match preview {
         PreviewContent::Text(text) => text.clone(),
@@ -373,6 +883,45 @@ fn preview_to_log(preview: &PreviewContent) -> String {
             }
             out.join("\n")
         }
+        PreviewContent::StreamSelection(selection) => {
+            let mut out = vec![
+                selection.summary.clone(),
+                format!("input={}", selection.input),
+            ];
+            for (stream, kept) in selection.streams.iter().zip(&selection.selected) {
+                out.push(format!(
+                    "stream {} kept={}",
+                    stream.describe(),
+                    if *kept { "yes" } else { "no" }
+                ));
+            }
+            out.join("\n")
+        }
+        PreviewContent::Table {
+            title,
+            headers,
+            rows,
+        } => {
+            let mut out = vec![title.clone(), headers.join(" | ")];
+            out.extend(rows.iter().map(|row| row.join(" | ")));
+            out.join("\n")
+        }
+        PreviewContent::KeyValue(sections) => {
+            let mut out = Vec::new();
+            for section in sections {
+                out.push(section.title.clone());
+                for (key, value) in &section.entries {
+                    out.push(format!("{}={}", key.to_lowercase(), value));
+                }
+                out.push(String::new());
+            }
+            out.join("\n")
+        }
+        PreviewContent::Sections(parts) => parts
+            .iter()
+            .map(preview_to_log)
+            .collect::<Vec<_>>()
+            .join("\n\n"),
     }
 }
 
@@ -383,12 +932,75 @@ fn format_clarify_block(question: &str, options: &[dexter_core::ClarifyOption])
         out.push(format!("option.label={}", opt.label));
         out.push(format!("option.detail={}", opt.detail));
         out.push(format!("option.resolved_intent={}", opt.resolved_intent));
+        out.push(format!("option.combinable={}", opt.combinable));
         out.push(String::new());
     }
     out.join("\n")
 }
 
 async fn handle_runtime_event(app: &mut App, event: Event) -> Result<bool> {
+    if app.awaiting_since.is_some() && matches!(event, Event::Key(_)) {
+        app.awaiting_since = Some(Instant::now());
+    }
+    if app.show_help {
+        if matches!(event, Event::Key(key) if key.kind == KeyEventKind::Press) {
+            app.show_help = false;
+            app.dirty = true;
+        }
+        return Ok(false);
+    }
+    if app.output_viewer.is_some() {
+        if let Event::Key(key) = event {
+            if key.kind == KeyEventKind::Press {
+                handle_output_viewer_key(app, key);
+            }
+        }
+        return Ok(false);
+    }
+    if app.model_picker.is_some() {
+        if let Event::Key(key) = event {
+            if key.kind == KeyEventKind::Press {
+                handle_model_picker_key(app, key);
+            }
+        }
+        return Ok(false);
+    }
+    if app.bookmark_picker.is_some() {
+        if let Event::Key(key) = event {
+            if key.kind == KeyEventKind::Press {
+                handle_bookmark_picker_key(app, key).await;
+            }
+        }
+        return Ok(false);
+    }
+    if let Some(search) = &mut app.search {
+        match event {
+            Event::Key(key) if key.kind == KeyEventKind::Press => {
+                return handle_search_key(app, key).await
+            }
+            Event::Paste(text) if search.excerpt.is_none() => {
+                search.query.push_str(text.trim());
+                search.selected = 0;
+                app.dirty = true;
+            }
+            _ => {}
+        }
+        return Ok(false);
+    }
+    if let Some(palette) = &mut app.palette {
+        match event {
+            Event::Key(key) if key.kind == KeyEventKind::Press => {
+                return handle_palette_key(app, key).await
+            }
+            Event::Paste(text) => {
+                palette.query.push_str(text.trim());
+                palette.selected = 0;
+                app.dirty = true;
+            }
+            _ => {}
+        }
+        return Ok(false);
+    }
     match event {
         Event::Key(key) if key.kind == KeyEventKind::Press => handle_key_press(app, key).await,
         Event::Paste(text) => {
@@ -401,33 +1013,92 @@ async fn handle_runtime_event(app: &mut App, event: Event) -> Result<bool> {
 }
 
 async fn handle_key_press(app: &mut App, key: KeyEvent) -> Result<bool> {
+    if key.code == KeyCode::Char('k') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        app.palette = Some(Palette::default());
+        app.dirty = true;
+        return Ok(false);
+    }
+    if key.code == KeyCode::Char('f') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        return perform_footer_action(app, FooterAction::OpenSearch).await;
+    }
+    if key.code == KeyCode::F(1) {
+        app.show_help = true;
+        app.dirty = true;
+        return Ok(false);
+    }
+    if app.state == AppState::AwaitingConfirmation && app.schedule_input.is_some() {
+        return handle_run_later_key(app, key).await;
+    }
+    if matches!(app.state, AppState::Error(_)) && app.override_input.is_some() {
+        return handle_override_key(app, key).await;
+    }
     let editing = app.focus == FocusArea::Proposal
-        && matches!(app.state, AppState::Input | AppState::EditingCommand);
+        && matches!(
+            app.state,
+            AppState::Input | AppState::EditingCommand | AppState::Executing
+        );
+    // `<` and `>` switch tabs only outside text fields, where they'd be
+    // typed.
+    let plain = !key
+        .modifiers
+        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+    if let (Some(subtasks), KeyCode::Char(c @ ('<' | '>'))) = (&app.subtasks, key.code) {
+        if !editing
+            && plain
+            && matches!(
+                app.state,
+                AppState::AwaitingConfirmation | AppState::Finished(_) | AppState::Error(_)
+            )
+        {
+            let idx = subtasks.neighbour(if c == '<' { -1 } else { 1 });
+            app.show_subtask(idx);
+            return Ok(false);
+        }
+    }
+
+    if key.code == KeyCode::Char('q') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        return perform_footer_action(app, FooterAction::ToggleQueue).await;
+    }
+    if key.code == KeyCode::Char('l') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        return perform_footer_action(app, FooterAction::CycleLayout).await;
+    }
+    if key.code == KeyCode::Char('e') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        return perform_footer_action(app, FooterAction::ToggleLogFilter).await;
+    }
 
-    // Global output scrolling keys (work in most states).
-    if !editing && app.state != AppState::History {
+    // Global output scrolling keys (work in most states, and in all of them
+    // with the scrollbar focused). The queue input is a single line, so
+    // while a job runs the arrows and page keys still scroll its output;
+    // only Home/End stay with the input's cursor.
+    let queue_typing = editing && app.state == AppState::Executing;
+    if app.scrollbar_focused()
+        || queue_typing
+        || !editing && !matches!(app.state, AppState::History | AppState::TemplatePicker)
+    {
         match key.code {
             KeyCode::Up => {
-                app.output_scroll = app.output_scroll.saturating_sub(1);
+                app.scroll_output_up(1);
                 return Ok(false);
             }
             KeyCode::Down => {
-                app.output_scroll = app.output_scroll.saturating_add(1);
+                app.scroll_output_down(1);
                 return Ok(false);
             }
             KeyCode::PageUp => {
-                app.output_scroll = app.output_scroll.saturating_sub(10);
+                app.scroll_output_up(10);
                 return Ok(false);
             }
             KeyCode::PageDown => {
-                app.output_scroll = app.output_scroll.saturating_add(10);
+                app.scroll_output_down(10);
                 return Ok(false);
             }
-            KeyCode::Home => {
+            KeyCode::Home if !queue_typing => {
                 app.output_scroll = 0;
+                app.scrollback.back = app.scrollback.len();
                 return Ok(false);
             }
-            KeyCode::End => {
+            KeyCode::End if !queue_typing => {
+                app.scrollback.back = 0;
                 app.output_scroll = app.output_max_scroll;
                 return Ok(false);
             }
@@ -437,6 +1108,11 @@ async fn handle_key_press(app: &mut App, key: KeyEvent) -> Result<bool> {
 
     if !editing {
         match key.code {
+            KeyCode::Char('?') => {
+                app.show_help = true;
+                app.dirty = true;
+                return Ok(false);
+            }
             KeyCode::Char('h') | KeyCode::Char('H') => {
                 if perform_footer_action(app, FooterAction::ToggleHistory).await? {
                     return Ok(true);
@@ -447,60 +1123,62 @@ async fn handle_key_press(app: &mut App, key: KeyEvent) -> Result<bool> {
         }
     }
 
-    // Focus switching / button navigation.
-    match key.code {
-        KeyCode::Tab => {
-            match app.state {
-                AppState::Input | AppState::EditingCommand => {
-                    app.focus = match app.focus {
-                        FocusArea::Proposal => FocusArea::FooterButtons,
-                        FocusArea::FooterButtons => FocusArea::Proposal,
-                    };
-                }
-                _ => {
-                    app.focus = FocusArea::FooterButtons;
-                    if !app.footer_buttons.is_empty() {
-                        app.footer_focus = (app.footer_focus + 1) % app.footer_buttons.len();
-                    }
-                }
+    // The `@` file list, while open, takes the keys that move through,
+    // accept and close it.
+    if editing && app.state == AppState::Input && !app.mention_matches().is_empty() {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Up => {
+                app.mention_move(-1);
+                return Ok(false);
             }
-            return Ok(false);
-        }
-        KeyCode::Left => {
-            if app.focus == FocusArea::FooterButtons && !app.footer_buttons.is_empty() {
-                if app.footer_focus == 0 {
-                    app.footer_focus = app.footer_buttons.len() - 1;
-                } else {
-                    app.footer_focus -= 1;
-                }
+            KeyCode::Down => {
+                app.mention_move(1);
                 return Ok(false);
             }
-        }
-        KeyCode::Right => {
-            if app.focus == FocusArea::FooterButtons && !app.footer_buttons.is_empty() {
-                app.footer_focus = (app.footer_focus + 1) % app.footer_buttons.len();
+            KeyCode::Tab | KeyCode::Enter if !ctrl => {
+                app.accept_mention();
                 return Ok(false);
             }
-        }
-        KeyCode::Enter => {
-            if app.focus == FocusArea::FooterButtons {
-                if let Some(action) = app.footer_buttons.get(app.footer_focus).map(|b| b.action) {
-                    if perform_footer_action(app, action).await? {
-                        return Ok(true);
-                    }
-                }
+            KeyCode::Esc => {
+                app.dismiss_mention();
                 return Ok(false);
             }
+            _ => {}
         }
-        KeyCode::Char(' ') => {
-            if app.focus == FocusArea::FooterButtons {
-                if let Some(action) = app.footer_buttons.get(app.footer_focus).map(|b| b.action) {
-                    if perform_footer_action(app, action).await? {
-                        return Ok(true);
-                    }
+    }
+
+    // Template tab-stops take precedence over focus switching while any
+    // `{placeholder}` is left in the input.
+    if key.code == KeyCode::Tab
+        && editing
+        && app.state == AppState::Input
+        && app.jump_to_next_placeholder()
+    {
+        return Ok(false);
+    }
+
+    // Focus switching / button navigation.
+    match key.code {
+        KeyCode::Tab => {
+            app.focus_next();
+            return Ok(false);
+        }
+        KeyCode::BackTab => {
+            app.focus_prev();
+            return Ok(false);
+        }
+        KeyCode::Left | KeyCode::Right if app.button_focused() => {
+            app.focus_sideways(if key.code == KeyCode::Left { -1 } else { 1 });
+            return Ok(false);
+        }
+        KeyCode::Enter | KeyCode::Char(' ') if app.button_focused() => {
+            if let Some(action) = app.focused_action() {
+                if perform_footer_action(app, action).await? {
+                    return Ok(true);
                 }
-                return Ok(false);
             }
+            return Ok(false);
         }
         _ => {}
     }
@@ -508,178 +1186,216 @@ async fn handle_key_press(app: &mut App, key: KeyEvent) -> Result<bool> {
     match app.state {
         AppState::Input => match key.code {
             KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                if perform_footer_action(app, FooterAction::ToggleDebug).await? {
-                    return Ok(true);
-                }
+                return perform_footer_action(app, FooterAction::ToggleDebug).await
             }
             KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                if perform_footer_action(app, FooterAction::ClearInput).await? {
-                    return Ok(true);
+                return perform_footer_action(app, FooterAction::ClearInput).await
+            }
+            KeyCode::Char('v')
+                if key.modifiers.contains(KeyModifiers::CONTROL) && app.show_debug =>
+            {
+                return perform_footer_action(app, FooterAction::CycleLogLevel).await
+            }
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return perform_footer_action(app, FooterAction::CycleTarget).await
+            }
+            KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return perform_footer_action(app, FooterAction::OpenTemplates).await
+            }
+            KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return perform_footer_action(app, FooterAction::ToggleOffline).await
+            }
+            KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                if route_choices(&app.config).is_empty() {
+                    app.push_log("No configured models to pick from.".to_string());
+                } else {
+                    app.model_picker =
+                        Some(ModelPicker::open(&app.config, app.model_override.as_ref()));
                 }
+                app.dirty = true;
+                return Ok(false);
+            }
+            KeyCode::Char('b') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                let cwd = std::env::current_dir().unwrap_or_default();
+                app.bookmark_picker = Some(BookmarkPicker::open(&app.config.bookmarks, &cwd));
+                app.dirty = true;
+                return Ok(false);
+            }
+            KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return perform_footer_action(app, FooterAction::ForgetSession).await
             }
             KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                if perform_footer_action(app, FooterAction::Submit).await? {
-                    return Ok(true);
-                }
+                return perform_footer_action(app, FooterAction::Submit).await
             }
             KeyCode::Enter if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                if perform_footer_action(app, FooterAction::Submit).await? {
-                    return Ok(true);
-                }
+                return perform_footer_action(app, FooterAction::Submit).await
             }
-            KeyCode::Enter => {
-                if app.focus == FocusArea::Proposal {
-                    insert_char_at_cursor(&mut app.input, &mut app.input_cursor, '\n');
-                }
+            KeyCode::Enter if app.focus == FocusArea::Proposal => {
+                insert_char_at_cursor(&mut app.input, &mut app.input_cursor, '\n');
             }
-            KeyCode::Left => {
-                if app.focus == FocusArea::Proposal && app.input_cursor > 0 {
-                    app.input_cursor -= 1;
-                }
+            KeyCode::Left if app.focus == FocusArea::Proposal => {
+                move_cursor_left(&app.input, &mut app.input_cursor);
             }
-            KeyCode::Right => {
-                if app.focus == FocusArea::Proposal && app.input_cursor < char_count(&app.input) {
-                    app.input_cursor += 1;
-                }
+            KeyCode::Right if app.focus == FocusArea::Proposal => {
+                move_cursor_right(&app.input, &mut app.input_cursor);
             }
-            KeyCode::Up => {
-                if app.focus == FocusArea::Proposal {
-                    move_cursor_up(&app.input, &mut app.input_cursor);
-                }
+            KeyCode::Up if app.focus == FocusArea::Proposal => {
+                move_cursor_up(&app.input, &mut app.input_cursor);
             }
-            KeyCode::Down => {
-                if app.focus == FocusArea::Proposal {
-                    move_cursor_down(&app.input, &mut app.input_cursor);
-                }
+            KeyCode::Down if app.focus == FocusArea::Proposal => {
+                move_cursor_down(&app.input, &mut app.input_cursor);
             }
-            KeyCode::Home => {
-                if app.focus == FocusArea::Proposal {
-                    move_cursor_line_start(&app.input, &mut app.input_cursor);
-                }
+            KeyCode::Home if app.focus == FocusArea::Proposal => {
+                move_cursor_line_start(&app.input, &mut app.input_cursor);
             }
-            KeyCode::End => {
-                if app.focus == FocusArea::Proposal {
-                    move_cursor_line_end(&app.input, &mut app.input_cursor);
-                }
+            KeyCode::End if app.focus == FocusArea::Proposal => {
+                move_cursor_line_end(&app.input, &mut app.input_cursor);
             }
-            KeyCode::Char(c) => {
-                if app.focus == FocusArea::Proposal {
-                    insert_char_at_cursor(&mut app.input, &mut app.input_cursor, c);
-                    app.notice = None;
-                    app.clarify = None;
-                }
+            KeyCode::Char(c) if app.focus == FocusArea::Proposal => {
+                insert_char_at_cursor(&mut app.input, &mut app.input_cursor, c);
+                app.notice = None;
+                app.clarify = None;
             }
-            KeyCode::Backspace => {
-                if app.focus == FocusArea::Proposal {
-                    delete_char_before_cursor(&mut app.input, &mut app.input_cursor);
-                    app.notice = None;
-                    app.clarify = None;
-                }
+            KeyCode::Backspace if app.focus == FocusArea::Proposal => {
+                delete_grapheme_before_cursor(&mut app.input, &mut app.input_cursor);
+                app.notice = None;
+                app.clarify = None;
             }
-            KeyCode::Delete => {
-                if app.focus == FocusArea::Proposal {
-                    delete_char_at_cursor(&mut app.input, &mut app.input_cursor);
-                }
+            KeyCode::Delete if app.focus == FocusArea::Proposal => {
+                delete_grapheme_at_cursor(&mut app.input, &mut app.input_cursor);
             }
             KeyCode::Esc => return Ok(true),
             _ => {}
         },
         AppState::AwaitingConfirmation => match key.code {
             KeyCode::Char('y') | KeyCode::Enter => {
-                if perform_footer_action(app, FooterAction::Execute).await? {
-                    return Ok(true);
-                }
+                return perform_footer_action(app, FooterAction::Execute).await
             }
             KeyCode::Char('m') => {
-                if perform_footer_action(app, FooterAction::EditCommand).await? {
-                    return Ok(true);
-                }
-            }
-            KeyCode::Char('e') => {
-                if perform_footer_action(app, FooterAction::EditInput).await? {
-                    return Ok(true);
-                }
+                return perform_footer_action(app, FooterAction::EditCommand).await
             }
+            KeyCode::Char('e') => return perform_footer_action(app, FooterAction::EditInput).await,
             KeyCode::Char('r') => {
-                if perform_footer_action(app, FooterAction::Regenerate).await? {
-                    return Ok(true);
-                }
+                return perform_footer_action(app, FooterAction::Regenerate).await
             }
             KeyCode::Char('n') | KeyCode::Esc => {
-                if perform_footer_action(app, FooterAction::BackToInput).await? {
-                    return Ok(true);
+                return perform_footer_action(app, FooterAction::BackToInput).await
+            }
+            KeyCode::Char(c @ '1'..='9') => {
+                app.toggle_stream(c as usize - '1' as usize);
+            }
+            KeyCode::Char('l') => return perform_footer_action(app, FooterAction::RunLater).await,
+            KeyCode::Char('d') if app.delete_hold => {
+                return perform_footer_action(app, FooterAction::ConfirmDelete).await
+            }
+            KeyCode::Char('i') => {
+                return perform_footer_action(app, FooterAction::ToggleInvocation).await
+            }
+            KeyCode::Char('f') if app.context_change.is_some() => {
+                return perform_footer_action(app, FooterAction::RefreshPreview).await
+            }
+            KeyCode::Char('t') if diff_table::shown_diffs(app).is_some() => {
+                app.diff_sort_by_status = !app.diff_sort_by_status;
+                app.diff_page = 0;
+                app.output_scroll = 0;
+                app.dirty = true;
+            }
+            KeyCode::Char('[') if diff_table::shown_diffs(app).is_some() => {
+                app.diff_hscroll = app.diff_hscroll.saturating_sub(diff_table::HSCROLL_STEP);
+                app.dirty = true;
+            }
+            KeyCode::Char(']') if diff_table::shown_diffs(app).is_some() => {
+                let max = diff_table::shown_diffs(app).map_or(0, diff_table::max_hscroll);
+                app.diff_hscroll = (app.diff_hscroll + diff_table::HSCROLL_STEP).min(max);
+                app.dirty = true;
+            }
+            // `,` and `.`: the keys of `<` and `>`, which switch split tabs.
+            KeyCode::Char(c @ (',' | '.' | 'g' | 'G'))
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                if let Some(diffs) = diff_table::shown_diffs(app) {
+                    let last = diff_table::page_count(diffs) - 1;
+                    app.diff_page = match c {
+                        ',' => app.diff_page.min(last).saturating_sub(1),
+                        '.' => (app.diff_page + 1).min(last),
+                        'g' => 0,
+                        _ => last,
+                    };
+                    app.output_scroll = 0;
+                    app.dirty = true;
                 }
             }
+            KeyCode::Char('p') => {
+                return perform_footer_action(app, FooterAction::ToggleCommandPin).await
+            }
+            KeyCode::Char('o') if !app.output_conflicts.is_empty() => {
+                return perform_footer_action(
+                    app,
+                    FooterAction::ResolveConflict(ConflictPolicy::Overwrite),
+                )
+                .await
+            }
+            KeyCode::Char('a') if !app.output_conflicts.is_empty() => {
+                return perform_footer_action(
+                    app,
+                    FooterAction::ResolveConflict(ConflictPolicy::AutoRename),
+                )
+                .await
+            }
+            KeyCode::Char('s') if !app.output_conflicts.is_empty() => {
+                return perform_footer_action(
+                    app,
+                    FooterAction::ResolveConflict(ConflictPolicy::Skip),
+                )
+                .await
+            }
             _ => {}
         },
         AppState::EditingCommand => match key.code {
             KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                if perform_footer_action(app, FooterAction::PreviewEditedCommand).await? {
-                    return Ok(true);
-                }
+                return perform_footer_action(app, FooterAction::PreviewEditedCommand).await
             }
             KeyCode::Enter if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                if perform_footer_action(app, FooterAction::PreviewEditedCommand).await? {
-                    return Ok(true);
-                }
+                return perform_footer_action(app, FooterAction::PreviewEditedCommand).await
             }
-            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                if app.focus == FocusArea::Proposal {
-                    app.command_draft.clear();
-                    app.command_cursor = 0;
-                }
+            KeyCode::Char('u')
+                if key.modifiers.contains(KeyModifiers::CONTROL)
+                    && app.focus == FocusArea::Proposal =>
+            {
+                app.command_draft.clear();
+                app.command_cursor = 0;
             }
-            KeyCode::Left => {
-                if app.focus == FocusArea::Proposal && app.command_cursor > 0 {
-                    app.command_cursor -= 1;
-                }
+            KeyCode::Left if app.focus == FocusArea::Proposal => {
+                move_cursor_left(&app.command_draft, &mut app.command_cursor);
             }
-            KeyCode::Right => {
-                if app.focus == FocusArea::Proposal
-                    && app.command_cursor < char_count(&app.command_draft)
-                {
-                    app.command_cursor += 1;
-                }
+            KeyCode::Right if app.focus == FocusArea::Proposal => {
+                move_cursor_right(&app.command_draft, &mut app.command_cursor);
             }
-            KeyCode::Up => {
-                if app.focus == FocusArea::Proposal {
-                    move_cursor_up(&app.command_draft, &mut app.command_cursor);
-                }
+            KeyCode::Up if app.focus == FocusArea::Proposal => {
+                move_cursor_up(&app.command_draft, &mut app.command_cursor);
             }
-            KeyCode::Down => {
-                if app.focus == FocusArea::Proposal {
-                    move_cursor_down(&app.command_draft, &mut app.command_cursor);
-                }
+            KeyCode::Down if app.focus == FocusArea::Proposal => {
+                move_cursor_down(&app.command_draft, &mut app.command_cursor);
             }
-            KeyCode::Home => {
-                if app.focus == FocusArea::Proposal {
-                    move_cursor_line_start(&app.command_draft, &mut app.command_cursor);
-                }
+            KeyCode::Home if app.focus == FocusArea::Proposal => {
+                move_cursor_line_start(&app.command_draft, &mut app.command_cursor);
             }
-            KeyCode::End => {
-                if app.focus == FocusArea::Proposal {
-                    move_cursor_line_end(&app.command_draft, &mut app.command_cursor);
-                }
+            KeyCode::End if app.focus == FocusArea::Proposal => {
+                move_cursor_line_end(&app.command_draft, &mut app.command_cursor);
             }
-            KeyCode::Char(c) => {
-                if app.focus == FocusArea::Proposal {
-                    insert_char_at_cursor(&mut app.command_draft, &mut app.command_cursor, c);
-                }
+            KeyCode::Char(c) if app.focus == FocusArea::Proposal => {
+                insert_char_at_cursor(&mut app.command_draft, &mut app.command_cursor, c);
             }
-            KeyCode::Backspace => {
-                if app.focus == FocusArea::Proposal {
-                    delete_char_before_cursor(&mut app.command_draft, &mut app.command_cursor);
-                }
+            KeyCode::Backspace if app.focus == FocusArea::Proposal => {
+                delete_grapheme_before_cursor(&mut app.command_draft, &mut app.command_cursor);
             }
-            KeyCode::Delete => {
-                if app.focus == FocusArea::Proposal {
-                    delete_char_at_cursor(&mut app.command_draft, &mut app.command_cursor);
-                }
+            KeyCode::Delete if app.focus == FocusArea::Proposal => {
+                delete_grapheme_at_cursor(&mut app.command_draft, &mut app.command_cursor);
             }
             KeyCode::Esc => {
-                if perform_footer_action(app, FooterAction::CancelEditCommand).await? {
-                    return Ok(true);
-                }
+                return perform_footer_action(app, FooterAction::CancelEditCommand).await
             }
             _ => {}
         },
@@ -691,53 +1407,439 @@ async fn handle_key_press(app: &mut App, key: KeyEvent) -> Result<bool> {
             KeyCode::Home => app.history_home(),
             KeyCode::End => app.history_end(),
             KeyCode::Char('x') | KeyCode::Char('X') | KeyCode::Char('e') | KeyCode::Char('E') => {
-                if perform_footer_action(app, FooterAction::ExecuteHistoryCommand).await? {
-                    return Ok(true);
-                }
+                return perform_footer_action(app, FooterAction::ExecuteHistoryCommand).await
             }
             KeyCode::Char('p') | KeyCode::Char('P') => {
-                if perform_footer_action(app, FooterAction::ToggleHistoryPin).await? {
-                    return Ok(true);
-                }
+                return perform_footer_action(app, FooterAction::ToggleHistoryPin).await
             }
-            KeyCode::Esc => {
-                if perform_footer_action(app, FooterAction::CloseHistory).await? {
-                    return Ok(true);
+            KeyCode::Char('f') | KeyCode::Char('F') => {
+                if let Err(e) = app.cycle_history_filter().await {
+                    app.push_error("history", format!("Reload failed: {}", e));
                 }
             }
+            KeyCode::Esc => return perform_footer_action(app, FooterAction::CloseHistory).await,
+            _ => {}
+        },
+        AppState::TemplatePicker => match key.code {
+            KeyCode::Up => app.template_move_up(),
+            KeyCode::Down => app.template_move_down(),
+            KeyCode::Enter => return perform_footer_action(app, FooterAction::ApplyTemplate).await,
+            KeyCode::Esc => return perform_footer_action(app, FooterAction::CloseTemplates).await,
+            _ => {}
+        },
+        AppState::Executing => match key.code {
+            KeyCode::Enter if app.focus == FocusArea::Proposal => app.queue_intent(),
+            KeyCode::Left if app.focus == FocusArea::Proposal => {
+                move_cursor_left(&app.queue_input, &mut app.queue_cursor);
+            }
+            KeyCode::Right if app.focus == FocusArea::Proposal => {
+                move_cursor_right(&app.queue_input, &mut app.queue_cursor);
+            }
+            KeyCode::Home if app.focus == FocusArea::Proposal => app.queue_cursor = 0,
+            KeyCode::End if app.focus == FocusArea::Proposal => {
+                app.queue_cursor = char_count(&app.queue_input);
+            }
+            KeyCode::Char(c) if app.focus == FocusArea::Proposal => {
+                insert_char_at_cursor(&mut app.queue_input, &mut app.queue_cursor, c);
+            }
+            KeyCode::Backspace if app.focus == FocusArea::Proposal => {
+                delete_grapheme_before_cursor(&mut app.queue_input, &mut app.queue_cursor);
+            }
+            KeyCode::Delete if app.focus == FocusArea::Proposal => {
+                delete_grapheme_at_cursor(&mut app.queue_input, &mut app.queue_cursor);
+            }
             _ => {}
         },
         AppState::Finished(_) | AppState::Error(_) => match key.code {
-            KeyCode::Char('r') => {
-                if perform_footer_action(app, FooterAction::Retry).await? {
-                    return Ok(true);
+            KeyCode::Char('r') => return perform_footer_action(app, FooterAction::Retry).await,
+            KeyCode::Char('v') if app.safety_block.is_some() => {
+                return perform_footer_action(app, FooterAction::ToggleSafetyRule).await
+            }
+            KeyCode::Char('m') if app.safety_block.is_some() => {
+                return perform_footer_action(app, FooterAction::EditCommand).await
+            }
+            KeyCode::Char('s') if app.safety_block.is_some() => {
+                return perform_footer_action(app, FooterAction::RegenerateWithinRule).await
+            }
+            KeyCode::Char('o') if app.can_override() => {
+                return perform_footer_action(app, FooterAction::OverrideSafety).await
+            }
+            KeyCode::Char('i') if app.failure_install.is_some() => {
+                return perform_footer_action(app, FooterAction::InstallMissing).await
+            }
+            KeyCode::Char('p') if app.generated_command.is_some() => {
+                return perform_footer_action(app, FooterAction::ToggleCommandPin).await
+            }
+            KeyCode::Char(c @ ('o' | 'v' | 'c' | 'f')) => {
+                let action = match c {
+                    'o' => FooterAction::OpenFolder,
+                    'v' => FooterAction::PlayOutput,
+                    'f' => FooterAction::ViewFullOutput,
+                    _ => FooterAction::CopyPaths,
+                };
+                if app
+                    .result_actions()
+                    .iter()
+                    .any(|(offered, _)| *offered == action)
+                {
+                    return perform_footer_action(app, action).await;
                 }
             }
             KeyCode::Enter | KeyCode::Esc | KeyCode::Char(' ') => {
-                if perform_footer_action(app, FooterAction::ResetToInput).await? {
-                    return Ok(true);
-                }
+                return perform_footer_action(app, FooterAction::ResetToInput).await
             }
             _ => {}
         },
         AppState::Routing
         | AppState::Generating
-        | AppState::Executing
         | AppState::DryRunning
         | AppState::PendingRouting
         | AppState::PendingGeneration
-        | AppState::PendingDryRun
-        | AppState::Clarifying => {
+        | AppState::PendingDryRun => {
             // Non-interactive states.
         }
+        AppState::ConfirmingBudget => match key.code {
+            KeyCode::Char('y') => {
+                return perform_footer_action(app, FooterAction::SendOverBudget).await
+            }
+            KeyCode::Esc | KeyCode::Char('n') => {
+                return perform_footer_action(app, FooterAction::BackToInput).await
+            }
+            _ => {}
+        },
+        AppState::Clarifying => match key.code {
+            KeyCode::Char(c @ '1'..='9') => {
+                let idx = c as usize - '1' as usize;
+                return perform_footer_action(app, FooterAction::ClarifySelect(idx)).await;
+            }
+            KeyCode::Esc => return perform_footer_action(app, FooterAction::BackToInput).await,
+            _ => {}
+        },
     }
 
     Ok(false)
 }
 
+/// Keys while the RUN LATER prompt is open: Tab still reaches the footer,
+/// everything else edits the start time.
+async fn handle_run_later_key(app: &mut App, key: KeyEvent) -> Result<bool> {
+    if app.focus == FocusArea::FooterButtons && key.code != KeyCode::Esc {
+        match key.code {
+            KeyCode::Tab => app.focus = FocusArea::Proposal,
+            KeyCode::Left if !app.footer_buttons.is_empty() => {
+                app.footer_focus =
+                    (app.footer_focus + app.footer_buttons.len() - 1) % app.footer_buttons.len();
+            }
+            KeyCode::Right if !app.footer_buttons.is_empty() => {
+                app.footer_focus = (app.footer_focus + 1) % app.footer_buttons.len();
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                if let Some(action) = app.footer_buttons.get(app.footer_focus).map(|b| b.action) {
+                    return perform_footer_action(app, action).await;
+                }
+            }
+            _ => {}
+        }
+        return Ok(false);
+    }
+    let Some(input) = app.schedule_input.as_mut() else {
+        return Ok(false);
+    };
+    match key.code {
+        KeyCode::Enter => return perform_footer_action(app, FooterAction::ConfirmRunLater).await,
+        KeyCode::Esc => return perform_footer_action(app, FooterAction::CancelRunLater).await,
+        KeyCode::Tab => {
+            app.focus = FocusArea::FooterButtons;
+            app.footer_focus = 0;
+        }
+        KeyCode::Left => move_cursor_left(input, &mut app.schedule_cursor),
+        KeyCode::Right => move_cursor_right(input, &mut app.schedule_cursor),
+        KeyCode::Home => app.schedule_cursor = 0,
+        KeyCode::End => app.schedule_cursor = char_count(input),
+        KeyCode::Char(c) => insert_char_at_cursor(input, &mut app.schedule_cursor, c),
+        KeyCode::Backspace => delete_grapheme_before_cursor(input, &mut app.schedule_cursor),
+        KeyCode::Delete => delete_grapheme_at_cursor(input, &mut app.schedule_cursor),
+        _ => {}
+    }
+    Ok(false)
+}
+
+/// Keys while the OVERRIDE justification is being typed.
+async fn handle_override_key(app: &mut App, key: KeyEvent) -> Result<bool> {
+    if app.focus == FocusArea::FooterButtons && key.code != KeyCode::Esc {
+        match key.code {
+            KeyCode::Tab => app.focus = FocusArea::Proposal,
+            KeyCode::Left if !app.footer_buttons.is_empty() => {
+                app.footer_focus =
+                    (app.footer_focus + app.footer_buttons.len() - 1) % app.footer_buttons.len();
+            }
+            KeyCode::Right if !app.footer_buttons.is_empty() => {
+                app.footer_focus = (app.footer_focus + 1) % app.footer_buttons.len();
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                if let Some(action) = app.footer_buttons.get(app.footer_focus).map(|b| b.action) {
+                    return perform_footer_action(app, action).await;
+                }
+            }
+            _ => {}
+        }
+        return Ok(false);
+    }
+    let Some(input) = app.override_input.as_mut() else {
+        return Ok(false);
+    };
+    match key.code {
+        KeyCode::Enter => return perform_footer_action(app, FooterAction::ConfirmOverride).await,
+        KeyCode::Esc => return perform_footer_action(app, FooterAction::CancelOverride).await,
+        KeyCode::Tab => {
+            app.focus = FocusArea::FooterButtons;
+            app.footer_focus = 0;
+        }
+        KeyCode::Left => move_cursor_left(input, &mut app.override_cursor),
+        KeyCode::Right => move_cursor_right(input, &mut app.override_cursor),
+        KeyCode::Home => app.override_cursor = 0,
+        KeyCode::End => app.override_cursor = char_count(input),
+        KeyCode::Char(c) => insert_char_at_cursor(input, &mut app.override_cursor, c),
+        KeyCode::Backspace => delete_grapheme_before_cursor(input, &mut app.override_cursor),
+        KeyCode::Delete => delete_grapheme_at_cursor(input, &mut app.override_cursor),
+        _ => {}
+    }
+    Ok(false)
+}
+
+/// Keys while the palette is open: typing filters it, Enter runs the
+/// selected action, Esc or Ctrl+K closes it.
+async fn handle_palette_key(app: &mut App, key: KeyEvent) -> Result<bool> {
+    let Some(mut palette) = app.palette.take() else {
+        return Ok(false);
+    };
+    app.dirty = true;
+    let matches = palette.matches(app);
+    match key.code {
+        KeyCode::Esc => return Ok(false),
+        KeyCode::Char('k') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(false),
+        KeyCode::Enter => {
+            return match matches.get(palette.selected) {
+                Some(entry) => perform_footer_action(app, entry.action).await,
+                None => Ok(false),
+            };
+        }
+        KeyCode::Up => palette.move_selection(-1, matches.len()),
+        KeyCode::Down | KeyCode::Tab => palette.move_selection(1, matches.len()),
+        KeyCode::Backspace => {
+            palette.query.pop();
+            palette.selected = 0;
+        }
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+            palette.query.push(c);
+            palette.selected = 0;
+        }
+        _ => {}
+    }
+    app.palette = Some(palette);
+    Ok(false)
+}
+
+/// Keys in the full output viewer: the arrows, Page Up/Down, Home and End
+/// scroll; Esc, Q or F closes it.
+fn handle_output_viewer_key(app: &mut App, key: KeyEvent) {
+    let Some(viewer) = &mut app.output_viewer else {
+        return;
+    };
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => viewer.scroll_by(-1),
+        KeyCode::Down | KeyCode::Char('j') => viewer.scroll_by(1),
+        KeyCode::PageUp => viewer.scroll_by(-viewer.page()),
+        KeyCode::PageDown | KeyCode::Char(' ') => viewer.scroll_by(viewer.page()),
+        KeyCode::Home => viewer.scroll = 0,
+        KeyCode::End => viewer.scroll = viewer.max_scroll(),
+        KeyCode::Esc | KeyCode::Char('q' | 'f') | KeyCode::Enter => app.output_viewer = None,
+        _ => return,
+    }
+    app.dirty = true;
+}
+
+/// Keys while the search is open: typing filters it, Enter jumps to the
+/// selected hit, Ctrl+P pins or unpins a history hit. Esc closes an open
+/// log excerpt, then the search.
+async fn handle_search_key(app: &mut App, key: KeyEvent) -> Result<bool> {
+    let Some(mut search) = app.search.take() else {
+        return Ok(false);
+    };
+    app.dirty = true;
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    if key.code == KeyCode::Char('f') && ctrl {
+        return Ok(false);
+    }
+    if search.excerpt.is_some() {
+        if matches!(key.code, KeyCode::Esc | KeyCode::Enter | KeyCode::Backspace) {
+            search.excerpt = None;
+        }
+        app.search = Some(search);
+        return Ok(false);
+    }
+    let len = search.matches().len();
+    match key.code {
+        KeyCode::Esc => return Ok(false),
+        KeyCode::Enter => {
+            let Some(hit) = search.selected_hit().cloned() else {
+                app.search = Some(search);
+                return Ok(false);
+            };
+            if app.is_processing_state() && !matches!(hit.target, SearchTarget::Log { .. }) {
+                app.push_log("Wait for the running task before jumping to a search hit.");
+                app.search = Some(search);
+                return Ok(false);
+            }
+            match hit.target {
+                SearchTarget::History(item) => {
+                    app.execute_history_item(item).await?;
+                    return Ok(false);
+                }
+                SearchTarget::Recipe(template) => {
+                    app.reset_for_new_request();
+                    app.template_selected = app
+                        .intent_templates()
+                        .iter()
+                        .position(|t| *t == template)
+                        .unwrap_or(0);
+                    app.apply_selected_template();
+                    return Ok(false);
+                }
+                SearchTarget::Log { file, line } => match log_excerpt(&file, line) {
+                    Ok(excerpt) => search.excerpt = Some(excerpt),
+                    Err(e) => app.push_log(format!("Cannot open {}: {}", file.display(), e)),
+                },
+            }
+        }
+        KeyCode::Char('p') if ctrl => toggle_search_pin(app, &mut search).await?,
+        KeyCode::Up => search.move_selection(-1, len),
+        KeyCode::Down | KeyCode::Tab => search.move_selection(1, len),
+        KeyCode::Backspace => {
+            search.query.pop();
+            search.selected = 0;
+        }
+        KeyCode::Char(c) if !ctrl => {
+            search.query.push(c);
+            search.selected = 0;
+        }
+        _ => {}
+    }
+    app.search = Some(search);
+    Ok(false)
+}
+
+/// Pins or unpins the selected history hit, keeping the History view in
+/// step when it is open underneath.
+async fn toggle_search_pin(app: &mut App, search: &mut Search) -> Result<()> {
+    let Some(SearchHit {
+        target: SearchTarget::History(item),
+        ..
+    }) = search.selected_hit_mut()
+    else {
+        return Ok(());
+    };
+    if item.pinned_at.is_some() {
+        app.executor.unset_pin(&item.entry).await?;
+        item.pinned_at = None;
+        app.push_log(format!(
+            "Unpinned [{}] {}",
+            item.entry.plugin, item.entry.command
+        ));
+    } else {
+        app.executor
+            .set_pin(&item.entry, item.intent.as_deref())
+            .await?;
+        let pinned_at = app
+            .executor
+            .load_pinned_entries()
+            .await?
+            .into_iter()
+            .find(|pin| {
+                pin.timestamp == item.entry.timestamp
+                    && pin.plugin == item.entry.plugin
+                    && pin.command == item.entry.command
+            })
+            .map(|pin| pin.pinned_at);
+        item.pinned_at = Some(pinned_at.unwrap_or_default());
+        app.push_log(format!(
+            "Pinned [{}] {}",
+            item.entry.plugin, item.entry.command
+        ));
+    }
+    if app.state == AppState::History {
+        app.open_history_view().await?;
+    }
+    Ok(())
+}
+
+/// Keys while the model picker is open: Enter picks the selected route
+/// for the next generation (or goes back to the configured routing), Esc
+/// closes it unchanged.
+fn handle_model_picker_key(app: &mut App, key: KeyEvent) {
+    let Some(mut picker) = app.model_picker.take() else {
+        return;
+    };
+    app.dirty = true;
+    let entries = picker_entries(&app.config);
+    match key.code {
+        KeyCode::Esc => return,
+        KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => return,
+        KeyCode::Enter => {
+            app.model_override = entries.get(picker.selected).cloned().flatten();
+            match &app.model_override {
+                Some(route) => {
+                    app.push_log(format!("Next generation uses {}.", route_label(route)))
+                }
+                None => app.push_log("Next generation uses the configured routing.".to_string()),
+            }
+            return;
+        }
+        KeyCode::Up => picker.move_selection(-1, entries.len()),
+        KeyCode::Down | KeyCode::Tab => picker.move_selection(1, entries.len()),
+        _ => {}
+    }
+    app.model_picker = Some(picker);
+}
+
+/// Keys while the directory switcher is open: Enter moves to the selected
+/// bookmark, A bookmarks the working directory, D removes the selected
+/// bookmark, Esc closes it.
+async fn handle_bookmark_picker_key(app: &mut App, key: KeyEvent) {
+    let Some(mut picker) = app.bookmark_picker.take() else {
+        return;
+    };
+    app.dirty = true;
+    let len = app.config.bookmarks.len();
+    match key.code {
+        KeyCode::Esc => return,
+        KeyCode::Char('b') if key.modifiers.contains(KeyModifiers::CONTROL) => return,
+        KeyCode::Enter => {
+            if let Some(bookmark) = app.config.bookmarks.get(picker.selected).cloned() {
+                app.switch_to_bookmark(&bookmark).await;
+                return;
+            }
+        }
+        KeyCode::Char('a') | KeyCode::Char('A') => app.bookmark_cwd().await,
+        KeyCode::Char('d') | KeyCode::Char('D') | KeyCode::Delete => {
+            app.remove_bookmark(picker.selected).await;
+            picker.selected = picker
+                .selected
+                .min(app.config.bookmarks.len().saturating_sub(1));
+        }
+        KeyCode::Up => picker.move_selection(-1, len),
+        KeyCode::Down | KeyCode::Tab => picker.move_selection(1, len),
+        _ => {}
+    }
+    app.bookmark_picker = Some(picker);
+}
+
 fn handle_paste(app: &mut App, text: &str) {
     let editing_proposal = app.focus == FocusArea::Proposal
-        && matches!(app.state, AppState::Input | AppState::EditingCommand);
+        && matches!(
+            app.state,
+            AppState::Input | AppState::EditingCommand | AppState::Executing
+        );
     if !editing_proposal {
         return;
     }
@@ -755,18 +1857,19 @@ fn handle_paste(app: &mut App, text: &str) {
                 insert_char_at_cursor(&mut app.command_draft, &mut app.command_cursor, ch);
             }
         }
+        AppState::Executing => {
+            for ch in text.chars().filter(|c| *c != '\n' && *c != '\r') {
+                insert_char_at_cursor(&mut app.queue_input, &mut app.queue_cursor, ch);
+            }
+        }
         _ => {}
     }
 }
 
 async fn handle_mouse_event(app: &mut App, mouse: MouseEvent) -> Result<bool> {
     match mouse.kind {
-        MouseEventKind::ScrollUp => {
-            app.output_scroll = app.output_scroll.saturating_sub(3);
-        }
-        MouseEventKind::ScrollDown => {
-            app.output_scroll = app.output_scroll.saturating_add(3);
-        }
+        MouseEventKind::ScrollUp => app.scroll_output_up(3),
+        MouseEventKind::ScrollDown => app.scroll_output_down(3),
         MouseEventKind::Drag(MouseButton::Left) | MouseEventKind::Down(MouseButton::Left) => {
             // If the scrollbar is visible, allow clicking/dragging it to jump.
             if let Some(sb) = app.output_scrollbar_rect {
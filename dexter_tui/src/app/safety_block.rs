@@ -0,0 +1,243 @@
+//! What the error view offers when a safety rule refuses a command: the
+//! rule and where it is set, editing the command, asking the model for one
+//! that keeps to the rule, and with `safety.allow_override`, running it
+//! anyway once a justification is typed.
+
+use anyhow::Result;
+use dexter_core::workspace::WORKSPACE_FILE;
+use dexter_core::AuditEvent;
+
+use crate::app::editor::char_count;
+use crate::app::state::{App, AppState, FocusArea};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SafetyBlock {
+    pub command: String,
+    /// The guard's message.
+    pub reason: String,
+    /// Whether `safety.allow_override` can lift it: the patterns, shell
+    /// and path rules can be, read-only mode, plugin blocks, delete
+    /// handling and plugin validation can't.
+    pub overridable: bool,
+    pub show_rule: bool,
+}
+
+/// A confirmed override: lets exactly `command` past the rule it broke.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SafetyOverride {
+    pub command: String,
+    pub reason: String,
+    pub justification: String,
+}
+
+impl App {
+    /// Stops at the error view with the ways out of the block. `command`
+    /// becomes the one on screen, so EDIT CMD starts from it.
+    pub fn refuse_command(&mut self, command: &str, reason: &str, overridable: bool) {
+        self.generated_command = Some(command.to_string());
+        self.command_draft = command.to_string();
+        self.command_cursor = char_count(command);
+        self.safety_block = Some(SafetyBlock {
+            command: command.to_string(),
+            reason: reason.to_string(),
+            overridable: overridable && !self.read_only,
+            show_rule: false,
+        });
+        self.state = AppState::Error(format!("Safety check failed: {}", reason));
+        self.dirty = true;
+    }
+
+    /// Whether OVERRIDE is offered for the block on screen.
+    pub fn can_override(&self) -> bool {
+        self.config.safety.allow_override
+            && self
+                .safety_block
+                .as_ref()
+                .is_some_and(|block| block.overridable)
+    }
+
+    /// Whether a confirmed override covers `command`; any edit ends it.
+    pub fn overrides(&self, command: &str) -> bool {
+        !self.read_only
+            && self
+                .safety_override
+                .as_ref()
+                .is_some_and(|o| o.command.trim() == command.trim())
+    }
+
+    /// The RULE lines: the guard's message, then for a pattern, the
+    /// pattern and the file that sets it.
+    pub fn safety_rule_lines(&self) -> Vec<String> {
+        let Some(block) = &self.safety_block else {
+            return Vec::new();
+        };
+        let mut lines = vec![block.reason.clone()];
+        if block.reason.contains("shell composition") {
+            lines.push(
+                "Commands run without a shell, so ; | && || > < ` and $( ) are refused."
+                    .to_string(),
+            );
+        }
+        let pattern = self
+            .safety_guard()
+            .ok()
+            .and_then(|guard| guard.matched_pattern(&block.command).map(str::to_string));
+        if let Some(pattern) = pattern {
+            let source = if self.config.safety.blocked_patterns.contains(&pattern) {
+                "safety.blocked_patterns in config.toml".to_string()
+            } else if self.workspace.settings.blocked_patterns.contains(&pattern) {
+                match &self.workspace.root {
+                    Some(root) => format!(
+                        "blocked_patterns in {}",
+                        root.join(WORKSPACE_FILE).display()
+                    ),
+                    None => format!("blocked_patterns in {}", WORKSPACE_FILE),
+                }
+            } else {
+                "Dexter's built-in rules".to_string()
+            };
+            lines.push(format!("Pattern: {}", pattern));
+            lines.push(format!("Set in: {}", source));
+        }
+        lines
+    }
+
+    pub fn toggle_safety_rule(&mut self) {
+        if let Some(block) = &mut self.safety_block {
+            block.show_rule = !block.show_rule;
+        }
+        self.dirty = true;
+    }
+
+    /// Opens the command editor on the blocked command; its preview is
+    /// checked again.
+    pub fn edit_blocked_command(&mut self) {
+        let Some(block) = &self.safety_block else {
+            return;
+        };
+        self.command_draft = block.command.clone();
+        self.command_cursor = char_count(&self.command_draft);
+        self.state = AppState::EditingCommand;
+        self.focus = FocusArea::Proposal;
+        self.footer_focus = 0;
+        self.dirty = true;
+    }
+
+    /// Back from the editor to the block it was opened from.
+    pub fn return_to_block(&mut self) {
+        let Some(block) = &self.safety_block else {
+            return;
+        };
+        self.command_draft = block.command.clone();
+        self.command_cursor = char_count(&self.command_draft);
+        self.state = AppState::Error(format!("Safety check failed: {}", block.reason));
+        self.focus = FocusArea::FooterButtons;
+        self.footer_focus = 0;
+        self.dirty = true;
+    }
+
+    /// Sends the blocked command and the reason back to the executor for a
+    /// command that keeps to the rule.
+    pub fn regenerate_within_rule(&mut self) {
+        let Some(block) = self.safety_block.take() else {
+            return;
+        };
+        self.push_log("Asking for a command within the safety rules.");
+        self.log_block(
+            "SAFETY_RETRY",
+            &format!("command={}\nreason={}", block.command, block.reason),
+        );
+        self.remember_previous_command();
+        self.pending_constraint = Some((block.command, block.reason));
+        self.command_draft.clear();
+        self.command_cursor = 0;
+        self.dry_run_output = None;
+        self.output_scroll = 0;
+        self.focus = FocusArea::FooterButtons;
+        self.footer_focus = 0;
+        self.state = AppState::PendingGeneration;
+        self.dirty = true;
+    }
+
+    /// Opens the justification prompt.
+    pub fn start_override(&mut self) {
+        if !self.can_override() {
+            return;
+        }
+        self.override_input = Some(String::new());
+        self.override_cursor = 0;
+        self.focus = FocusArea::Proposal;
+        self.dirty = true;
+    }
+
+    pub fn cancel_override(&mut self) {
+        self.override_input = None;
+        self.override_cursor = 0;
+        self.focus = FocusArea::FooterButtons;
+        self.footer_focus = 0;
+        self.dirty = true;
+    }
+
+    /// Lets the blocked command through with the typed justification. It
+    /// is previewed and confirmed like any other command before it runs.
+    pub fn confirm_override(&mut self) {
+        let justification = self
+            .override_input
+            .as_deref()
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+        if justification.is_empty() {
+            self.push_log("Type why this command is safe to run first.");
+            self.dirty = true;
+            return;
+        }
+        let Some(block) = self.safety_block.take() else {
+            self.cancel_override();
+            return;
+        };
+        self.override_input = None;
+        self.override_cursor = 0;
+        self.push_warning(
+            "safety",
+            format!("Safety rule overridden: {}", justification),
+        );
+        self.log_block(
+            "SAFETY_OVERRIDE",
+            &format!(
+                "command={}\nreason={}\njustification={}",
+                block.command, block.reason, justification
+            ),
+        );
+        self.safety_override = Some(SafetyOverride {
+            command: block.command,
+            reason: block.reason,
+            justification,
+        });
+        self.dry_run_output = None;
+        self.output_scroll = 0;
+        self.focus = FocusArea::FooterButtons;
+        self.footer_focus = 0;
+        self.state = AppState::PendingDryRun;
+        self.dirty = true;
+    }
+
+    /// Writes the override of the command about to run to the audit log.
+    pub fn audit_override(&self, plugin: &str) -> Result<()> {
+        let Some(safety_override) = &self.safety_override else {
+            return Ok(());
+        };
+        let cwd = self.audit_cwd();
+        let event = AuditEvent {
+            plugin,
+            command: &safety_override.command,
+            cwd: &cwd,
+            host: self.active_remote().map(|remote| remote.name.as_str()),
+        };
+        self.config.audit.record_override(
+            &event,
+            &safety_override.reason,
+            &safety_override.justification,
+        )
+    }
+}
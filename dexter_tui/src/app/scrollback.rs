@@ -0,0 +1,129 @@
+//! Output pane content from earlier views, so the preview or the logs can
+//! still be scrolled back to after the pane has moved on to the result.
+
+use ratatui::text::{Line, Span};
+use std::collections::VecDeque;
+
+use crate::theme::Theme;
+
+/// Archived lines kept at most; the oldest go first.
+pub const SCROLLBACK_LINES: usize = 10_000;
+
+#[derive(Debug, Default)]
+pub struct Scrollback {
+    lines: VecDeque<Line<'static>>,
+    /// How many archived lines are scrolled into view above the live pane.
+    pub back: usize,
+    /// Title and last frame of the view on screen, archived once another
+    /// view replaces it.
+    live_title: Option<&'static str>,
+    live: Vec<Line<'static>>,
+}
+
+impl Scrollback {
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Records what the pane shows this frame. When the view changed since
+    /// the last frame, the old view's last frame is archived under its
+    /// title and the pane returns to the live view.
+    pub fn track(&mut self, title: &'static str, lines: &[Line<'_>], theme: &Theme) {
+        if self.live_title.is_some_and(|live| live != title) {
+            let previous = std::mem::take(&mut self.live);
+            self.archive(self.live_title.unwrap_or_default(), previous, theme);
+            self.back = 0;
+        }
+        self.live_title = Some(title);
+        self.live = lines.iter().map(owned_line).collect();
+    }
+
+    fn archive(&mut self, title: &str, mut lines: Vec<Line<'static>>, theme: &Theme) {
+        while lines.last().is_some_and(|line| line.width() == 0) {
+            lines.pop();
+        }
+        if lines.is_empty() {
+            return;
+        }
+        let rule = if theme.plain { "--" } else { "──" };
+        self.lines.push_back(Line::from(Span::styled(
+            format!("{} {} {}", rule, title.trim(), rule),
+            theme.header_subtitle_style,
+        )));
+        self.lines.extend(lines);
+        let overflow = self.lines.len().saturating_sub(SCROLLBACK_LINES);
+        self.lines.drain(..overflow);
+    }
+
+    /// The archived lines scrolled into view, oldest first.
+    pub fn visible(&self) -> impl Iterator<Item = &Line<'static>> {
+        self.lines
+            .range(self.lines.len() - self.back.min(self.lines.len())..)
+    }
+
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.back = (self.back + lines).min(self.lines.len());
+    }
+
+    /// Scrolls toward the live pane; returns the lines left over once it
+    /// is reached.
+    pub fn scroll_down(&mut self, lines: usize) -> usize {
+        let moved = lines.min(self.back);
+        self.back -= moved;
+        lines - moved
+    }
+}
+
+fn owned_line(line: &Line<'_>) -> Line<'static> {
+    Line {
+        style: line.style,
+        alignment: line.alignment,
+        spans: line
+            .spans
+            .iter()
+            .map(|span| Span::styled(span.content.to_string(), span.style))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(lines: &[Line]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn archives_a_view_when_the_pane_moves_on() {
+        let mut scrollback = Scrollback::default();
+        let theme = &Theme::dark();
+        scrollback.track(" PREVIEW ", &[Line::from("draft")], theme);
+        scrollback.track(
+            " PREVIEW ",
+            &[Line::from("a.jpg -> b.jpg"), Line::from("")],
+            theme,
+        );
+        assert!(scrollback.is_empty());
+
+        scrollback.track(" RESULTS ", &[Line::from("done")], theme);
+        assert_eq!(scrollback.len(), 2);
+        scrollback.scroll_up(5);
+        assert_eq!(scrollback.back, 2);
+        let visible: Vec<Line> = scrollback.visible().cloned().collect();
+        assert_eq!(text(&visible), ["── PREVIEW ──", "a.jpg -> b.jpg"]);
+
+        assert_eq!(scrollback.scroll_down(3), 1);
+        assert_eq!(scrollback.visible().count(), 0);
+
+        for i in 0..SCROLLBACK_LINES {
+            scrollback.track(" A ", &[Line::from(i.to_string())], theme);
+            scrollback.track(" B ", &[Line::from(i.to_string())], theme);
+        }
+        assert_eq!(scrollback.len(), SCROLLBACK_LINES);
+    }
+}
@@ -0,0 +1,344 @@
+//! The Ctrl+F search: the history with its pins, the intent templates and
+//! the session log in one ranked list. Enter jumps to a hit: it re-runs a
+//! history command, loads a template into the input, or opens the log
+//! around a line.
+
+use anyhow::Result;
+use dexter_core::IntentTemplate;
+use std::path::{Path, PathBuf};
+
+use crate::app::state::{merge_history_items, App, HistoryItem};
+use crate::app::telemetry::{self, SESSION_TARGET};
+
+/// Newest session log lines searched; older ones are left out.
+const MAX_LOG_HITS: usize = 5000;
+/// Lines shown on each side of an opened log hit.
+const EXCERPT_CONTEXT: usize = 8;
+
+#[derive(Debug, Clone)]
+pub enum SearchTarget {
+    History(HistoryItem),
+    Recipe(IntentTemplate),
+    /// Line `line` (0-based) of `file`.
+    Log {
+        file: PathBuf,
+        line: usize,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub target: SearchTarget,
+    /// The command, template text or log message.
+    pub text: String,
+    /// Where it comes from: plugin and time, template name, log time.
+    pub detail: String,
+}
+
+impl SearchHit {
+    pub fn kind(&self) -> &'static str {
+        match &self.target {
+            SearchTarget::History(item) if item.pinned_at.is_some() => "PINNED",
+            SearchTarget::History(_) => "HISTORY",
+            SearchTarget::Recipe(_) => "RECIPE",
+            SearchTarget::Log { .. } => "LOG",
+        }
+    }
+
+    /// What Enter does with this hit.
+    pub fn action_label(&self) -> &'static str {
+        match &self.target {
+            SearchTarget::History(_) => "ENTER=RE-RUN  CTRL+P=PIN/UNPIN",
+            SearchTarget::Recipe(_) => "ENTER=LOAD TEMPLATE",
+            SearchTarget::Log { .. } => "ENTER=OPEN LOG",
+        }
+    }
+}
+
+/// Log lines around an opened hit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogExcerpt {
+    pub title: String,
+    pub lines: Vec<String>,
+    /// Index of the hit within `lines`.
+    pub hit: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Search {
+    pub query: String,
+    /// Index into the ranked matches.
+    pub selected: usize,
+    /// Open log hit; Esc goes back to the results.
+    pub excerpt: Option<LogExcerpt>,
+    hits: Vec<SearchHit>,
+}
+
+impl Search {
+    /// Reads every store once; typing then only re-ranks what was read.
+    /// Stores that can't be read are left out.
+    pub async fn load(app: &App) -> Self {
+        let entries = app
+            .executor
+            .load_history_entries()
+            .await
+            .unwrap_or_default();
+        let pins = app.executor.load_pinned_entries().await.unwrap_or_default();
+        let mut hits: Vec<SearchHit> = merge_history_items(entries, pins)
+            .into_iter()
+            .map(history_hit)
+            .collect();
+        hits.extend(
+            app.intent_templates()
+                .into_iter()
+                .map(|template| SearchHit {
+                    text: template.text.clone(),
+                    detail: template.name.clone(),
+                    target: SearchTarget::Recipe(template),
+                }),
+        );
+        let files = telemetry::log_files();
+        if let Ok(logged) = tokio::task::spawn_blocking(move || log_hits(&files)).await {
+            hits.extend(logged);
+        }
+        Self::with_hits(hits)
+    }
+
+    pub fn with_hits(hits: Vec<SearchHit>) -> Self {
+        Self {
+            hits,
+            ..Self::default()
+        }
+    }
+
+    /// Indices into `hits` matching the query, best first; ties keep the
+    /// load order (pins, newest history, templates, newest log lines).
+    fn ranked(&self) -> Vec<usize> {
+        let mut scored: Vec<(u32, usize)> = self
+            .hits
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, hit)| {
+                let haystack = format!("{} {}", hit.text, hit.detail);
+                Some((match_score(&self.query, &haystack)?, idx))
+            })
+            .collect();
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        scored.into_iter().map(|(_, idx)| idx).collect()
+    }
+
+    pub fn matches(&self) -> Vec<&SearchHit> {
+        self.ranked()
+            .into_iter()
+            .map(|idx| &self.hits[idx])
+            .collect()
+    }
+
+    pub fn selected_hit(&self) -> Option<&SearchHit> {
+        let idx = *self.ranked().get(self.selected)?;
+        self.hits.get(idx)
+    }
+
+    pub fn selected_hit_mut(&mut self) -> Option<&mut SearchHit> {
+        let idx = *self.ranked().get(self.selected)?;
+        self.hits.get_mut(idx)
+    }
+
+    pub fn move_selection(&mut self, delta: isize, len: usize) {
+        if len == 0 {
+            self.selected = 0;
+            return;
+        }
+        self.selected = (self.selected as isize + delta).rem_euclid(len as isize) as usize;
+    }
+}
+
+/// How well `query` matches `text`: every word of it has to occur,
+/// ignoring case; words found at a word start, and the whole query found
+/// as one phrase, score higher. `None` when a word is missing.
+pub fn match_score(query: &str, text: &str) -> Option<u32> {
+    let text = text.to_lowercase();
+    let query = query.to_lowercase();
+    let words: Vec<&str> = query.split_whitespace().collect();
+    let mut score = 0;
+    for word in &words {
+        let mut found = text.match_indices(word).map(|(idx, _)| idx).peekable();
+        found.peek()?;
+        score += 1;
+        if found.any(|idx| !text[..idx].ends_with(char::is_alphanumeric)) {
+            score += 2;
+        }
+    }
+    if words.len() > 1 && text.contains(&words.join(" ")) {
+        score += 4;
+    }
+    Some(score)
+}
+
+fn history_hit(item: HistoryItem) -> SearchHit {
+    let mut detail = format!(
+        "{}  {}",
+        item.entry.plugin,
+        short_time(&item.entry.timestamp)
+    );
+    if let Some(intent) = &item.intent {
+        detail.push_str(&format!("  \"{}\"", intent));
+    }
+    SearchHit {
+        text: item.entry.command.clone(),
+        detail,
+        target: SearchTarget::History(item),
+    }
+}
+
+/// `2026-03-04T10:20:30Z` as `2026-03-04 10:20`.
+fn short_time(timestamp: &str) -> String {
+    timestamp
+        .get(..16)
+        .unwrap_or(timestamp)
+        .replacen('T', " ", 1)
+}
+
+/// Session events in `files` (newest file first), newest first.
+fn log_hits(files: &[PathBuf]) -> Vec<SearchHit> {
+    let mut hits = Vec::new();
+    for file in files {
+        let Ok(bytes) = std::fs::read(file) else {
+            continue;
+        };
+        let text = String::from_utf8_lossy(&bytes);
+        let mut events: Vec<SearchHit> = text
+            .lines()
+            .enumerate()
+            .filter_map(|(line, raw)| {
+                let (time, message) = parse_log_line(raw)?;
+                Some(SearchHit {
+                    text: message,
+                    detail: time,
+                    target: SearchTarget::Log {
+                        file: file.clone(),
+                        line,
+                    },
+                })
+            })
+            .collect();
+        events.reverse();
+        hits.extend(events);
+        if hits.len() >= MAX_LOG_HITS {
+            break;
+        }
+    }
+    hits.truncate(MAX_LOG_HITS);
+    hits
+}
+
+/// Time and message of a one-line session event; `None` for other lines,
+/// and for detail blocks, whose body starts on the next line.
+fn parse_log_line(line: &str) -> Option<(String, String)> {
+    let marker = format!(" {}: ", SESSION_TARGET);
+    let (head, rest) = line.split_once(&marker)?;
+    let message = match rest.rfind(" label=") {
+        Some(idx) => &rest[..idx],
+        None => rest,
+    };
+    let message = message.trim();
+    if message.is_empty() {
+        return None;
+    }
+    let time = head.split_whitespace().next().unwrap_or_default();
+    Some((short_time(time), message.to_string()))
+}
+
+/// The lines of `file` around `line`.
+pub fn log_excerpt(file: &Path, line: usize) -> Result<LogExcerpt> {
+    let bytes = std::fs::read(file)?;
+    let text = String::from_utf8_lossy(&bytes);
+    let lines: Vec<&str> = text.lines().collect();
+    let start = line.saturating_sub(EXCERPT_CONTEXT);
+    let end = (line + EXCERPT_CONTEXT + 1).min(lines.len());
+    let name = file
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    Ok(LogExcerpt {
+        title: format!("{}:{}", name, line + 1),
+        lines: lines
+            .get(start..end)
+            .unwrap_or_default()
+            .iter()
+            .map(|line| line.to_string())
+            .collect(),
+        hit: line - start,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dexter_core::HistoryEntry;
+
+    #[test]
+    fn ranks_word_starts_and_phrases_higher() {
+        assert_eq!(match_score("", "anything"), Some(0));
+        assert_eq!(match_score("webm mkv", "ffmpeg -i a.mkv a.mp4"), None);
+        assert!(match_score("mkv", "ffmpeg -i a.mkv") > match_score("mkv", "xmkv"));
+        assert!(
+            match_score("extract audio", "extract audio from x")
+                > match_score("extract audio", "audio: extract x")
+        );
+    }
+
+    #[test]
+    fn searches_history_recipes_and_log_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("dexter.log");
+        std::fs::write(
+            &log,
+            "2026-03-04T10:20:30.5Z  INFO dexter::session: session started pid=1\n\
+             2026-03-04T10:21:00.1Z DEBUG dexter::session: \n\
+             plugin=ffmpeg label=\"HISTORY_EXECUTE_SELECTED\"\n\
+             2026-03-04T10:22:00.1Z  INFO dexter::session: Executed webm convert label=\"LOG\"\n",
+        )
+        .unwrap();
+        let mut hits = vec![history_hit(HistoryItem {
+            entry: HistoryEntry {
+                timestamp: "2026-02-01T09:00:00Z".to_string(),
+                plugin: "ffmpeg".to_string(),
+                command: "ffmpeg -i talk.mkv talk.webm".to_string(),
+                outcome: None,
+                intent: None,
+                environment: None,
+            },
+            pinned_at: None,
+            intent: None,
+        })];
+        hits.push(SearchHit {
+            text: "convert {video} to webm".to_string(),
+            detail: "Web video".to_string(),
+            target: SearchTarget::Recipe(IntentTemplate {
+                name: "Web video".to_string(),
+                text: "convert {video} to webm".to_string(),
+            }),
+        });
+        let logged = log_hits(std::slice::from_ref(&log));
+        assert_eq!(logged.len(), 2);
+        assert_eq!(logged[0].text, "Executed webm convert");
+        assert_eq!(logged[0].detail, "2026-03-04 10:22");
+        hits.extend(logged);
+
+        let mut search = Search::with_hits(hits);
+        search.query = "webm".to_string();
+        let kinds: Vec<&str> = search.matches().iter().map(|hit| hit.kind()).collect();
+        assert_eq!(kinds, ["HISTORY", "RECIPE", "LOG"]);
+
+        search.query = "webm convert".to_string();
+        assert_eq!(search.matches()[0].kind(), "LOG");
+        search.query = "ffmpeg 2026-02".to_string();
+        assert_eq!(search.matches().len(), 1);
+
+        let excerpt = log_excerpt(&log, 3).unwrap();
+        assert_eq!(excerpt.title, "dexter.log:4");
+        assert_eq!(excerpt.lines.len(), 4);
+        assert_eq!(excerpt.hit, 3);
+    }
+}
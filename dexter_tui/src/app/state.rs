@@ -1,22 +1,64 @@
 use anyhow::{anyhow, Result};
+use dexter_core::collision::format_collisions;
+use dexter_core::conflict::{apply_conflict_policy, format_conflicts};
+use dexter_core::context::intent_paths;
+use dexter_core::executor::load_dir_uses;
+use dexter_core::in_use::{self, FileInUse};
+use dexter_core::journal::plan_file_ops;
+use dexter_core::ocr;
+use dexter_core::paths;
+use dexter_core::recycle;
+use dexter_core::replay;
+use dexter_core::schedule::resolve_run_at;
+use dexter_core::splitter::split_intent;
+use dexter_core::trash;
+use dexter_core::update;
+use dexter_core::verify::verify_outputs;
+use dexter_core::workspace::WORKSPACE_FILE;
 use dexter_core::{
-    CachePolicy, ClarifyOption, Config, ContextScanner, Executor, HistoryEntry, LlmClient,
-    PinnedHistoryEntry, RouteOutcome, Router, SafetyGuard,
+    redact_sensitive_text, AuditEvent, BudgetAlert, CachePolicy, ClarifyOption, CollisionChecker,
+    Config, ConflictOutcome, ConflictPolicy, ContextChange, ContextScanner, Critic, DirWatch,
+    ExecutionReport, Executor, FailureHint, FileContext, HistoryEntry, HistoryStamp, Hooks,
+    IntentTemplate, InterruptedRun, Invocation, Journal, LlmClient, MeteredVerdict, ModelRoute,
+    OutputConflict, OutputLayout, PinnedHistoryEntry, PluginPolicy, PromptEstimate, ProviderConfig,
+    ProviderKind, Release, RemoteHost, RemoteRunner, RouteExplanation, RouteOutcome, Router,
+    RunEnvironment, RunOutcome, SafetyGuard, ScheduledJob, Scheduler, ScopeSummary, SessionEntry,
+    SessionMemory, UsageTracker, Workspace,
 };
+use dexter_plugins::ffmpeg::apply_stream_selection;
 use dexter_plugins::{
-    F2Plugin, FFmpegPlugin, JdupesPlugin, LibvipsPlugin, OcrmypdfPlugin, PandocPlugin, Plugin,
-    PreviewContent, QpdfPlugin, WhisperCppPlugin, YtDlpPlugin,
+    CleanupPlugin, DownloadPlugin, F2Plugin, FFmpegPlugin, JdupesPlugin, LibvipsPlugin,
+    MediaInfoPlugin, OcrmypdfPlugin, PandocPlugin, Plugin, PreviewContent, QpdfPlugin,
+    WhisperCppPlugin, YtDlpPlugin,
 };
 use ratatui::layout::Rect;
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Instant;
-use tokio::sync::{mpsc, oneshot};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot, Notify};
+use tracing::Instrument;
 
+use crate::app::bookmarks::BookmarkPicker;
 use crate::app::editor::char_count;
-use crate::app::session_log::SessionLogger;
+use crate::app::log::{LogEntry, LogFilter, Severity, LOG_LINES};
+use crate::app::long_output::OutputViewer;
+use crate::app::model_picker::{route_label, ModelPicker};
+use crate::app::palette::Palette;
+use crate::app::queue::{JobQueue, JobStatus};
+use crate::app::replay::ReplayInput;
+use crate::app::safety_block::{SafetyBlock, SafetyOverride};
+use crate::app::scrollback::Scrollback;
+use crate::app::search::Search;
+use crate::app::subtasks::{SubTaskStatus, SubTasks};
+use crate::app::telemetry;
+use crate::app::templates::{
+    builtin_templates, find_template, follows_template, placeholder_ranges, take_next_placeholder,
+};
 use crate::theme::Theme;
+use crate::ui::command_diff::CommandDiff;
+use crate::ui::thumbnail::{load_thumbnail, thumbnail_source, GraphicsProtocol, Thumbnail};
 
 #[derive(Clone, PartialEq, Debug)]
 pub enum AppState {
@@ -34,12 +76,42 @@ pub enum AppState {
     PendingGeneration,
     PendingDryRun,
     DryRunning,
+    TemplatePicker,
+    /// A prompt is over `config.token_budget`; waiting for SEND ANYWAY.
+    ConfirmingBudget,
+}
+
+/// Which model call a prompt estimate belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PromptStage {
+    Routing,
+    Generation,
+}
+
+impl PromptStage {
+    pub fn label(self) -> &'static str {
+        match self {
+            PromptStage::Routing => "router",
+            PromptStage::Generation => "executor",
+        }
+    }
+
+    fn resume_state(self) -> AppState {
+        match self {
+            PromptStage::Routing => AppState::PendingRouting,
+            PromptStage::Generation => AppState::PendingGeneration,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum FocusArea {
     Proposal,
     FooterButtons,
+    HistoryButton,
+    SettingsButton,
+    /// The output pane's scrollbar; the arrow keys scroll it in every view.
+    Scrollbar,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -52,6 +124,7 @@ pub enum FooterAction {
     Quit,
     Retry,
     Execute,
+    ConfirmDelete,
     BackToInput,
     EditCommand,
     EditInput,
@@ -62,7 +135,67 @@ pub enum FooterAction {
     CloseHistory,
     ExecuteHistoryCommand,
     ToggleHistoryPin,
+    ToggleCommandPin,
     ClarifySelect(usize),
+    ClarifyRunSelected,
+    CycleTarget,
+    OpenTemplates,
+    ApplyTemplate,
+    CloseTemplates,
+    ToggleQueue,
+    StartNextJob,
+    CancelQueued,
+    CycleLogLevel,
+    ToggleLogFilter,
+    ResolveConflict(ConflictPolicy),
+    RunLater,
+    ConfirmRunLater,
+    CancelRunLater,
+    ToggleSchedule,
+    CycleScheduled,
+    CancelScheduled,
+    CycleLayout,
+    ToggleOffline,
+    ForgetSession,
+    ResumeInterrupted,
+    RollBackInterrupted,
+    DismissInterrupted,
+    SendOverBudget,
+    InstallMissing,
+    RefreshPreview,
+    OpenSearch,
+    ToggleInvocation,
+    SelfUpdate,
+    OpenFolder,
+    PlayOutput,
+    CopyPaths,
+    ViewFullOutput,
+    ToggleSafetyRule,
+    RegenerateWithinRule,
+    OverrideSafety,
+    ConfirmOverride,
+    CancelOverride,
+}
+
+impl FooterAction {
+    /// Runs a command, an installer or an update, or moves files back;
+    /// none is offered with `--read-only`.
+    pub fn runs_commands(self) -> bool {
+        matches!(
+            self,
+            FooterAction::Execute
+                | FooterAction::RunLater
+                | FooterAction::ConfirmRunLater
+                | FooterAction::ConfirmDelete
+                | FooterAction::ExecuteHistoryCommand
+                | FooterAction::ResumeInterrupted
+                | FooterAction::RollBackInterrupted
+                | FooterAction::InstallMissing
+                | FooterAction::SelfUpdate
+                | FooterAction::OverrideSafety
+                | FooterAction::ConfirmOverride
+        )
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -75,18 +208,87 @@ pub struct FooterButton {
 pub struct ClarifyPayload {
     pub question: String,
     pub options: Vec<ClarifyOption>,
+    /// Combinable options toggled on, by index into `options`.
+    pub selected: Vec<bool>,
+}
+
+impl ClarifyPayload {
+    pub fn new(question: String, options: Vec<ClarifyOption>) -> Self {
+        let selected = vec![false; options.len()];
+        Self {
+            question,
+            options,
+            selected,
+        }
+    }
+
+    pub fn allows_multiple(&self) -> bool {
+        self.options.iter().filter(|opt| opt.combinable).count() >= 2
+    }
+
+    pub fn selected_indices(&self) -> Vec<usize> {
+        (0..self.options.len())
+            .filter(|&idx| self.selected[idx])
+            .collect()
+    }
 }
 
+/// Runs the History view loads at most, newest kept; pins are listed
+/// whatever their age.
+pub const HISTORY_ROWS: usize = 2_000;
+
 #[derive(Clone, Debug)]
 pub struct HistoryItem {
     pub entry: HistoryEntry,
     pub pinned_at: Option<String>,
+    pub intent: Option<String>,
+}
+
+impl HistoryItem {
+    pub fn failed(&self) -> bool {
+        self.entry.outcome.is_some_and(|outcome| !outcome.success)
+    }
+}
+
+/// Which entries the History view lists; F cycles through them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum HistoryFilter {
+    #[default]
+    All,
+    FailuresFirst,
+    FailuresOnly,
+}
+
+impl HistoryFilter {
+    pub fn next(self) -> Self {
+        match self {
+            HistoryFilter::All => HistoryFilter::FailuresFirst,
+            HistoryFilter::FailuresFirst => HistoryFilter::FailuresOnly,
+            HistoryFilter::FailuresOnly => HistoryFilter::All,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            HistoryFilter::All => "all runs",
+            HistoryFilter::FailuresFirst => "failures first",
+            HistoryFilter::FailuresOnly => "failures only",
+        }
+    }
 }
 
+/// A dry run's preview with the scope measured alongside it.
+pub type DryRunResult = (PreviewContent, Option<ScopeSummary>);
+
 pub struct App {
     pub state: AppState,
     pub input: String,
     pub input_cursor: usize,
+    /// Highlighted row of the `@` file list.
+    pub mention_selected: usize,
+    /// The `@` whose list `Esc` closed, with the input at the time; any
+    /// edit to the input brings the list back.
+    pub mention_dismissed: Option<(usize, String)>,
     pub router: Router,
     pub executor: Executor,
     pub plugins: Vec<Arc<dyn Plugin>>,
@@ -94,11 +296,19 @@ pub struct App {
     pub generated_command: Option<String>,
     pub command_draft: String,
     pub command_cursor: usize,
-    pub logs: Vec<String>,
+    pub logs: VecDeque<LogEntry>,
+    /// What the log views show; `Ctrl+E` switches to errors only.
+    pub log_filter: LogFilter,
     pub tick_count: u64,
+    /// Notified by background work that can finish outside the processing
+    /// states (critic, thumbnails, installs, sub-tasks), so the idle loop
+    /// wakes up for its result.
+    pub wake: Arc<Notify>,
     pub current_context: Option<dexter_core::context::FileContext>,
     pub dry_run_output: Option<PreviewContent>,
     pub show_debug: bool,
+    /// Starts from `config.output_layout`; Ctrl+L cycles it for the session.
+    pub output_layout: OutputLayout,
     pub config: Config,
     pub theme: Theme,
     pub notice: Option<String>,
@@ -108,72 +318,313 @@ pub struct App {
     pub footer_focus: usize,
     pub output_scroll: u16,
     pub output_max_scroll: u16,
+    /// Earlier output pane views, reached by scrolling up past the top.
+    pub scrollback: Scrollback,
     pub output_text_width: u16,
+    /// Rows of the output pane in the last frame.
+    pub output_viewport_height: u16,
     pub output_scrollbar_rect: Option<Rect>,
     pub history_items: Vec<HistoryItem>,
     pub history_selected: usize,
+    pub history_filter: HistoryFilter,
+    /// The history files as last loaded; another instance writing them
+    /// changes it and the open History view reloads.
+    pub history_stamp: HistoryStamp,
+    pub history_checked_at: Option<Instant>,
     pub proposal_rect: Option<Rect>,
     pub settings_button_rect: Option<Rect>,
     pub history_button_rect: Option<Rect>,
     pub history_return_state: Option<AppState>,
-    pub routing_result_rx: Option<oneshot::Receiver<Result<RouteOutcome>>>,
+    /// The entry written to history for the command that last ran.
+    pub last_history_entry: Option<HistoryEntry>,
+    /// Pin created from the confirmation or results view for the current
+    /// proposal; moved onto the history entry once the command runs.
+    pub command_pin: Option<HistoryEntry>,
+    pub routing_result_rx: Option<oneshot::Receiver<Result<(RouteOutcome, RouteExplanation)>>>,
+    pub route_explanation: Option<RouteExplanation>,
     pub generation_result_rx: Option<oneshot::Receiver<Result<String>>>,
-    pub dry_run_result_rx: Option<oneshot::Receiver<Result<PreviewContent>>>,
+    pub dry_run_result_rx: Option<oneshot::Receiver<Result<DryRunResult>>>,
+    /// Inputs of the previewed command, measured with the dry run.
+    pub preview_scope: Option<ScopeSummary>,
+    /// Rename table ordering (`T`), horizontal scroll of its name
+    /// columns (`[` / `]`) and page (`<` / `>`).
+    pub diff_sort_by_status: bool,
+    pub diff_hscroll: u16,
+    pub diff_page: usize,
+    /// The preview lists the exact argv, directory and environment (`I`).
+    pub show_invocation: bool,
     pub progress_rx: Option<mpsc::Receiver<dexter_plugins::Progress>>,
-    pub execution_result_rx: Option<oneshot::Receiver<Result<String>>>,
+    /// The run's result, with its output checks when `verify.outputs` is on.
+    pub execution_result_rx: Option<oneshot::Receiver<(Result<String>, Option<ExecutionReport>)>>,
+    /// Output checks of the last finished run, shown in the Finished view.
+    pub execution_report: Option<ExecutionReport>,
+    /// Outputs of the last successful local run that exist; `None` after
+    /// remote runs and before any.
+    pub result_outputs: Option<Vec<PathBuf>>,
+    /// Where the last run's output was saved whole, when the result view
+    /// only shows part of it.
+    pub full_output: Option<PathBuf>,
+    /// The full-screen viewer over `full_output`.
+    pub output_viewer: Option<OutputViewer>,
+    /// The recorded session `dexter replay` is sending.
+    pub replay: Option<ReplayInput>,
     pub progress: Option<dexter_plugins::Progress>,
     pub last_progress_log_line: Option<String>,
     pub last_progress_log_at: Option<Instant>,
+    /// When the command in `execution_result_rx` started, for its
+    /// history outcome.
+    pub execution_started_at: Option<Instant>,
     pub generation_cache_policy: CachePolicy,
+    /// Size of the prompt being sent, shown in the processing view.
+    pub prompt_estimate: Option<(PromptStage, PromptEstimate)>,
+    /// Stage held back by the token budget while `ConfirmingBudget`.
+    pub budget_hold: Option<PromptStage>,
+    /// Lets the next over-budget prompt through once.
+    pub budget_approved: bool,
+    /// Estimated spend so far, priced from `config.pricing`.
+    pub usage: UsageTracker,
+    /// The spending limit passed, shown as a banner in the header.
+    pub budget_alert: Option<BudgetAlert>,
+    /// A download held back by `metered.action = "block"`; the next
+    /// EXECUTE runs it anyway.
+    pub metered_hold: bool,
+    /// Files the command touches that another process has open; the next
+    /// EXECUTE runs it anyway.
+    pub files_in_use: Vec<FileInUse>,
+    /// Why the collision simulation of an f2 rename failed; the next
+    /// EXECUTE runs it unchecked.
+    pub collision_hold: Option<String>,
+    /// Plugin and command that REGEN, EDIT CMD or going back to the input
+    /// replaced; the confirmation view shows what the new one changed.
+    pub previous_command: Option<(String, String)>,
+    /// The diff `replaced_command` makes with the command on screen, kept
+    /// by `refresh_command_diff` while neither changes.
+    pub command_diff: Option<CommandDiff>,
+    /// A command that removes files, shown once more before it runs; only
+    /// CONFIRM DELETE, on its own key and slot, runs it.
+    pub delete_hold: bool,
+    /// Set by CONFIRM DELETE for the run it starts.
+    pub delete_confirmed: bool,
+    /// The known failure behind the error on screen.
+    pub failure_hint: Option<&'static FailureHint>,
+    /// The hint's install command, when it can run here.
+    pub failure_install: Option<&'static [&'static str]>,
+    pub install_rx: Option<oneshot::Receiver<Result<String>>>,
+    /// Set by `--read-only`: commands are previewed but never run.
+    pub read_only: bool,
+    /// A newer release found by the startup check (`updates.check`).
+    pub update_release: Option<Release>,
+    pub update_rx: Option<oneshot::Receiver<Result<Option<Release>>>>,
+    pub self_update_rx: Option<oneshot::Receiver<Result<PathBuf>>>,
+    /// Failed command and its error output for the next generation round
+    /// to repair instead of generating from scratch.
+    pub pending_repair: Option<(String, String)>,
+    pub repair_attempts: u32,
+    /// Blocked command and the rule it broke, for the next generation
+    /// round to avoid (SAFE REGEN).
+    pub pending_constraint: Option<(String, String)>,
+    /// Shown above the preview of a repaired command.
+    pub repair_note: Option<String>,
+    /// Reviews each command before its preview when `models.critic_model`
+    /// is set.
+    pub critic: Option<Arc<Critic>>,
+    pub critic_rx: Option<oneshot::Receiver<Result<Vec<String>>>>,
+    /// What the critic flagged for the command on screen.
+    pub critic_issues: Vec<String>,
     pub pending_open_settings: bool,
     pub dirty: bool,
-    pub session_logger: SessionLogger,
+    /// Index into `config.remotes`; `None` executes locally.
+    pub remote_target: Option<usize>,
+    /// A remote context scan started by switching targets.
+    pub remote_scan_rx: Option<oneshot::Receiver<Result<FileContext>>>,
+    pub template_selected: usize,
+    pub thumbnail: Option<Thumbnail>,
+    pub thumbnail_rx: Option<oneshot::Receiver<Result<Thumbnail>>>,
+    /// Where the UI reserved room for a graphics thumbnail in the last frame.
+    pub thumbnail_rect: Option<Rect>,
+    /// Where a graphics thumbnail is currently on screen, if anywhere.
+    pub thumbnail_painted: Option<Rect>,
+    pub jobs: JobQueue,
+    /// Intent being typed for the queue while a job executes.
+    pub queue_input: String,
+    pub queue_cursor: usize,
+    pub show_queue: bool,
+    /// Streams kept for a `StreamSelection` preview, and the command as
+    /// generated before any `-map` rewrite.
+    pub stream_keep: Vec<bool>,
+    pub stream_base_command: Option<String>,
+    /// Existing files the proposed command would overwrite, awaiting the
+    /// user's choice (`ask` policy). Execution is blocked while non-empty.
+    pub output_conflicts: Vec<OutputConflict>,
+    /// Start time being typed for RUN LATER; `Some` while the prompt is open.
+    pub schedule_input: Option<String>,
+    pub schedule_cursor: usize,
+    /// The command a safety rule refused, while the error view offers ways
+    /// around it.
+    pub safety_block: Option<SafetyBlock>,
+    /// Justification being typed for OVERRIDE; `Some` while the prompt is
+    /// open.
+    pub override_input: Option<String>,
+    pub override_cursor: usize,
+    pub safety_override: Option<SafetyOverride>,
+    pub scheduler: Option<Scheduler>,
+    pub scheduled_jobs: Vec<ScheduledJob>,
+    pub schedule_selected: usize,
+    pub show_schedule: bool,
+    /// Journal of the local command currently executing.
+    pub journal: Option<Journal>,
+    pub journal_checked_at: Option<Instant>,
+    /// Watches the cwd while a preview waits for confirmation.
+    pub dir_watch: Option<DirWatch>,
+    pub dir_watch_checked_at: Option<Instant>,
+    /// Last keypress while a preview waits for confirmation; the
+    /// `confirm_timeout` counts from here.
+    pub awaiting_since: Option<Instant>,
+    /// Name of the template the input was loaded from.
+    pub recipe: Option<String>,
+    /// Files added or removed since the preview was built; EXECUTE is
+    /// refused until the preview is refreshed.
+    pub context_change: Option<ContextChange>,
+    /// The Ctrl+K action palette, drawn over whatever state is showing.
+    pub palette: Option<Palette>,
+    /// The `?` overlay listing the current state's keys.
+    pub show_help: bool,
+    /// The Ctrl+F search over history, templates and the session log.
+    pub search: Option<Search>,
+    /// The Ctrl+G model picker, open over the input screen.
+    pub model_picker: Option<ModelPicker>,
+    /// Route the next generation goes to instead of the configured ones;
+    /// cleared once it is used.
+    pub model_override: Option<ModelRoute>,
+    /// The Ctrl+B directory switcher, open over the input screen.
+    pub bookmark_picker: Option<BookmarkPicker>,
+    /// Tabs for an input split into unrelated tasks; `None` for a single
+    /// request.
+    pub subtasks: Option<SubTasks>,
+    /// Runs a previous session left unfinished, oldest first; the first one
+    /// is offered for RESUME / ROLL BACK on the input screen.
+    pub interrupted_runs: Vec<InterruptedRun>,
+    /// `.dexter.toml` settings for the cwd, re-read when the cwd changes.
+    pub workspace: Workspace,
+    pub workspace_cwd: Option<PathBuf>,
+    /// Why the `.dexter.toml` for the cwd couldn't be read; every command
+    /// is blocked until it is fixed.
+    pub workspace_error: Option<String>,
+    /// Runs completed this session, offered to the models for follow-ups.
+    pub session: SessionMemory,
+}
+
+pub fn builtin_plugins(config: &Config) -> Vec<Arc<dyn Plugin>> {
+    let plugins: Vec<Box<dyn Plugin>> = vec![
+        Box::new(F2Plugin::default()),
+        Box::new(FFmpegPlugin::default()),
+        Box::new(PandocPlugin::new(
+            config.pandoc_presets.clone(),
+            paths::home_dir(),
+        )),
+        Box::new(QpdfPlugin::default()),
+        Box::new(OcrmypdfPlugin::default()),
+        Box::new(YtDlpPlugin::new(config.ytdlp.clone(), paths::home_dir())),
+        Box::new(DownloadPlugin::default()),
+        Box::new(WhisperCppPlugin::default()),
+        Box::new(JdupesPlugin::default()),
+        Box::new(LibvipsPlugin::default()),
+        Box::new(CleanupPlugin::default()),
+        Box::new(MediaInfoPlugin::default()),
+    ];
+    plugins
+        .into_iter()
+        .map(|mut plugin| {
+            plugin.configure(config.plugin_settings(plugin.name()));
+            Arc::from(plugin)
+        })
+        .collect()
+}
+
+fn critic_from_config(config: &Config) -> Option<Arc<Critic>> {
+    let model = config.models.critic_model.trim();
+    if model.is_empty() {
+        return None;
+    }
+    let client = LlmClient::with_fallbacks(config.llm_providers(), model.to_string(), Vec::new())
+        .with_spend_tracking(config.pricing.clone());
+    Some(Arc::new(
+        Critic::new(client)
+            .with_language(&config.language)
+            .with_context_file_limit(config.context_file_limit),
+    ))
+}
+
+/// Router and executor clients over `providers`, routed as `config` says.
+fn model_clients(config: &Config, providers: Vec<ProviderConfig>) -> (LlmClient, LlmClient) {
+    let router_client = LlmClient::with_routes(
+        providers.clone(),
+        config.models.router_routes.clone(),
+        config.models.router_model.clone(),
+        config.models.router_fallback_models.clone(),
+    )
+    .with_spend_tracking(config.pricing.clone());
+    let executor_client = LlmClient::with_routes(
+        providers,
+        config.models.executor_routes.clone(),
+        config.models.executor_model.clone(),
+        config.models.executor_fallback_models.clone(),
+    )
+    .with_spend_tracking(config.pricing.clone());
+    (router_client, executor_client)
+}
+
+/// Router and executor clients over the local `providers` only: the
+/// configured routes to them, then their own models. The global model
+/// names are left out, as they usually name cloud models.
+fn local_model_clients(config: &Config, providers: Vec<ProviderConfig>) -> (LlmClient, LlmClient) {
+    let client = |routes: &[ModelRoute]| {
+        let local_routes = routes
+            .iter()
+            .filter(|route| providers.iter().any(|p| p.kind == route.provider))
+            .cloned()
+            .collect();
+        LlmClient::with_routes(providers.clone(), local_routes, String::new(), Vec::new())
+            .with_spend_tracking(config.pricing.clone())
+    };
+    (
+        client(&config.models.router_routes),
+        client(&config.models.executor_routes),
+    )
 }
 
 impl App {
     pub fn new(config: Config) -> Self {
-        let providers = config.configured_providers();
-        let router_client = LlmClient::with_routes(
-            providers.clone(),
-            config.models.router_routes.clone(),
-            config.models.router_model.clone(),
-            config.models.router_fallback_models.clone(),
-        );
-        let executor_client = LlmClient::with_routes(
-            providers,
-            config.models.executor_routes.clone(),
-            config.models.executor_model.clone(),
-            config.models.executor_fallback_models.clone(),
-        );
+        let (router_client, executor_client) = model_clients(&config, config.llm_providers());
 
-        let theme = Theme::from_config(&config.theme);
-        let session_logger = SessionLogger::new();
+        let theme = Theme::from_config(&config.theme).with_plain(config.plain_ui);
+        let critic = critic_from_config(&config);
         let mut app = Self {
             state: AppState::Input,
             input: String::new(),
             input_cursor: 0,
-            router: Router::new(router_client),
-            executor: Executor::new(executor_client),
-            plugins: vec![
-                Arc::new(F2Plugin) as Arc<dyn Plugin>,
-                Arc::new(FFmpegPlugin) as Arc<dyn Plugin>,
-                Arc::new(PandocPlugin) as Arc<dyn Plugin>,
-                Arc::new(QpdfPlugin) as Arc<dyn Plugin>,
-                Arc::new(OcrmypdfPlugin) as Arc<dyn Plugin>,
-                Arc::new(YtDlpPlugin) as Arc<dyn Plugin>,
-                Arc::new(WhisperCppPlugin) as Arc<dyn Plugin>,
-                Arc::new(JdupesPlugin) as Arc<dyn Plugin>,
-                Arc::new(LibvipsPlugin) as Arc<dyn Plugin>,
-            ],
+            mention_selected: 0,
+            mention_dismissed: None,
+            router: Router::new(router_client)
+                .with_offline(config.offline)
+                .with_strategy(config.models.router_strategy)
+                .with_routing(config.models.routing)
+                .with_context_file_limit(config.context_file_limit)
+                .with_clarify_options(config.clarify_options),
+            executor: Executor::new(executor_client).with_hooks(Hooks::from_config_dir()),
+            plugins: builtin_plugins(&config),
             selected_plugin: None,
             generated_command: None,
             command_draft: String::new(),
             command_cursor: 0,
-            logs: Vec::new(),
+            logs: VecDeque::with_capacity(LOG_LINES),
+            log_filter: LogFilter::default(),
             tick_count: 0,
+            wake: Arc::new(Notify::new()),
             current_context: None,
             dry_run_output: None,
             show_debug: false,
+            output_layout: config.output_layout,
             config,
             theme,
             notice: None,
@@ -183,53 +634,299 @@ impl App {
             footer_focus: 0,
             output_scroll: 0,
             output_max_scroll: 0,
+            scrollback: Scrollback::default(),
             output_text_width: 0,
+            output_viewport_height: 0,
             output_scrollbar_rect: None,
             history_items: Vec::new(),
             history_selected: 0,
+            history_filter: HistoryFilter::default(),
+            history_stamp: HistoryStamp::default(),
+            history_checked_at: None,
             proposal_rect: None,
             settings_button_rect: None,
             history_button_rect: None,
             history_return_state: None,
+            last_history_entry: None,
+            command_pin: None,
             routing_result_rx: None,
+            route_explanation: None,
             generation_result_rx: None,
             dry_run_result_rx: None,
+            preview_scope: None,
+            diff_sort_by_status: false,
+            diff_hscroll: 0,
+            diff_page: 0,
+            show_invocation: false,
             progress_rx: None,
             execution_result_rx: None,
+            execution_report: None,
+            result_outputs: None,
+            full_output: None,
+            output_viewer: None,
+            replay: None,
             progress: None,
             last_progress_log_line: None,
             last_progress_log_at: None,
+            execution_started_at: None,
             generation_cache_policy: CachePolicy::Normal,
+            prompt_estimate: None,
+            budget_hold: None,
+            budget_approved: false,
+            usage: UsageTracker::load(),
+            budget_alert: None,
+            metered_hold: false,
+            files_in_use: Vec::new(),
+            collision_hold: None,
+            delete_hold: false,
+            delete_confirmed: false,
+            previous_command: None,
+            command_diff: None,
+            failure_hint: None,
+            failure_install: None,
+            install_rx: None,
+            read_only: false,
+            update_release: None,
+            update_rx: None,
+            self_update_rx: None,
+            pending_repair: None,
+            pending_constraint: None,
+            repair_attempts: 0,
+            repair_note: None,
+            critic,
+            critic_rx: None,
+            critic_issues: Vec::new(),
             pending_open_settings: false,
             dirty: true,
-            session_logger,
+            remote_target: None,
+            remote_scan_rx: None,
+            template_selected: 0,
+            thumbnail: None,
+            thumbnail_rx: None,
+            thumbnail_rect: None,
+            thumbnail_painted: None,
+            jobs: JobQueue::default(),
+            queue_input: String::new(),
+            queue_cursor: 0,
+            show_queue: false,
+            stream_keep: Vec::new(),
+            stream_base_command: None,
+            output_conflicts: Vec::new(),
+            schedule_input: None,
+            schedule_cursor: 0,
+            safety_block: None,
+            override_input: None,
+            override_cursor: 0,
+            safety_override: None,
+            scheduler: Scheduler::new().ok(),
+            journal: None,
+            journal_checked_at: None,
+            dir_watch: None,
+            dir_watch_checked_at: None,
+            awaiting_since: None,
+            recipe: None,
+            context_change: None,
+            palette: None,
+            show_help: false,
+            search: None,
+            model_picker: None,
+            model_override: None,
+            bookmark_picker: None,
+            subtasks: None,
+            interrupted_runs: Vec::new(),
+            scheduled_jobs: Vec::new(),
+            schedule_selected: 0,
+            show_schedule: false,
+            workspace: Workspace::default(),
+            workspace_cwd: None,
+            workspace_error: None,
+            session: SessionMemory::default(),
         };
         app.push_log("Dexter initialized. Ready for your command.");
-        if let Some(path) = app.session_logger.display_path() {
-            app.push_log(format!("Session log file: {}", path));
+        app.refresh_workspace();
+        app.check_spending();
+        if let Some(path) = telemetry::log_path() {
+            app.push_log(format!("Log file: {}", path));
         }
         app
     }
 
     pub fn apply_config(&mut self, config: Config) {
-        let providers = config.configured_providers();
-        let router_client = LlmClient::with_routes(
-            providers.clone(),
-            config.models.router_routes.clone(),
-            config.models.router_model.clone(),
-            config.models.router_fallback_models.clone(),
-        );
-        let executor_client = LlmClient::with_routes(
-            providers,
-            config.models.executor_routes.clone(),
-            config.models.executor_model.clone(),
-            config.models.executor_fallback_models.clone(),
-        );
-
-        self.router = Router::new(router_client);
-        self.executor = Executor::new(executor_client);
-        self.theme = Theme::from_config(&config.theme);
+        self.install_model_clients(&config, model_clients(&config, config.llm_providers()));
+        self.critic = critic_from_config(&config);
+        self.plugins = builtin_plugins(&config);
+        self.theme = Theme::from_config(&config.theme).with_plain(config.plain_ui);
+        if self
+            .remote_target
+            .is_some_and(|idx| idx >= config.remotes.len())
+        {
+            self.remote_target = None;
+        }
         self.config = config;
+        self.budget_alert = None;
+        self.check_spending();
+        self.dirty = true;
+    }
+
+    fn install_model_clients(
+        &mut self,
+        config: &Config,
+        (router_client, executor_client): (LlmClient, LlmClient),
+    ) {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        self.router = Router::new(router_client)
+            .with_offline(config.offline)
+            .with_strategy(config.models.router_strategy)
+            .with_routing(config.models.routing)
+            .with_language(&config.language)
+            .with_context_file_limit(config.context_file_limit)
+            .with_clarify_options(config.clarify_options)
+            .with_preferred_plugins(self.workspace.settings.preferred_plugins.clone());
+        self.executor = Executor::new(executor_client)
+            .with_language(&config.language)
+            .with_history_examples(config.history_examples)
+            .with_context_file_limit(config.context_file_limit)
+            .with_output_dir(self.workspace.output_dir(&cwd))
+            .with_hooks(self.executor.hooks().clone());
+        if let Ok(guard) = self.safety_guard() {
+            self.executor = self.executor.clone().with_safety_guard(guard);
+        }
+    }
+
+    /// Raises the budget banner once the spend the model clients tracked
+    /// passes a limit in `config.token_budget`, and moves requests to local
+    /// models when `switch_to_local` is set.
+    pub fn check_spending(&mut self) {
+        if self.budget_alert.is_some() {
+            return;
+        }
+        self.usage.reload();
+        let Some(alert) = self.usage.alert(&self.config.token_budget) else {
+            return;
+        };
+        self.push_warning("budget", format!("Spent {}.", alert.label()));
+        self.budget_alert = Some(alert);
+        self.dirty = true;
+        if !self.config.token_budget.switch_to_local {
+            return;
+        }
+        let local: Vec<ProviderConfig> = self
+            .config
+            .llm_providers()
+            .into_iter()
+            .filter(|p| p.kind == ProviderKind::Ollama)
+            .collect();
+        if local.is_empty() {
+            self.push_log("No local Ollama provider configured; keeping the current models.");
+            return;
+        }
+        let config = self.config.clone();
+        self.install_model_clients(&config, local_model_clients(&config, local));
+        self.push_log("Switched to local Ollama models for the rest of the session.");
+    }
+
+    /// The project's rules plus the config's `[safety]` rules; an error
+    /// while the project's `.dexter.toml` is invalid.
+    pub fn safety_guard(&self) -> Result<SafetyGuard> {
+        if let Some(e) = &self.workspace_error {
+            return Err(anyhow!("Command blocked: {}", e));
+        }
+        let cwd = std::env::current_dir()?;
+        self.workspace
+            .safety_guard()
+            .clone()
+            .with_policy(&self.config.safety, &cwd)
+    }
+
+    /// `safety_guard`, turned read-only by `--read-only`.
+    fn execution_guard(&self, plugin: &str) -> Result<SafetyGuard> {
+        let guard = self.safety_guard()?.with_read_only(self.read_only);
+        guard.check_plugin(plugin)?;
+        Ok(guard)
+    }
+
+    pub fn active_remote(&self) -> Option<&RemoteHost> {
+        self.remote_target
+            .and_then(|idx| self.config.remotes.get(idx))
+    }
+
+    /// How the previewed command will be started on the current target.
+    pub fn invocation(&self) -> Option<Result<Invocation>> {
+        let cmd = self.generated_command.as_deref()?;
+        let plugin_name = self.selected_plugin.as_deref()?;
+        let plugin = self.plugins.iter().find(|p| p.name() == plugin_name)?;
+        let plan = std::env::current_dir().map_err(Into::into).and_then(|cwd| {
+            Invocation::plan(
+                plugin.as_ref(),
+                cmd,
+                &cwd,
+                &self.config.sandbox,
+                self.active_remote(),
+            )
+        });
+        Some(plan)
+    }
+
+    pub fn execution_target_label(&self) -> String {
+        match self.active_remote() {
+            Some(remote) => remote.name.clone(),
+            None => "local".to_string(),
+        }
+    }
+
+    /// Steps through local -> each configured remote -> local, rescanning the
+    /// context for the new target. Remote hosts are scanned in the background
+    /// so an unreachable one doesn't hold up the UI.
+    pub async fn cycle_execution_target(&mut self) {
+        if self.config.remotes.is_empty() {
+            self.push_log("No remote hosts configured.".to_string());
+            self.dirty = true;
+            return;
+        }
+        self.remote_target = match self.remote_target {
+            None => Some(0),
+            Some(idx) if idx + 1 < self.config.remotes.len() => Some(idx + 1),
+            Some(_) => None,
+        };
+        self.push_log(format!(
+            "Execution target: {}",
+            self.execution_target_label()
+        ));
+        match self.active_remote().cloned() {
+            Some(remote) => self.start_remote_scan(remote),
+            None => {
+                self.remote_scan_rx = None;
+                if let Err(e) = self.update_context().await {
+                    self.push_error("context", format!("Scan failed: {}", e));
+                }
+            }
+        }
+        self.dirty = true;
+    }
+
+    fn start_remote_scan(&mut self, remote: RemoteHost) {
+        // The previous target's context no longer applies.
+        self.current_context = None;
+        self.router = self.router.clone().with_dir_history(Vec::new());
+        self.push_log(format!("Scanning {}...", remote.name));
+        let (tx, rx) = oneshot::channel();
+        let wake = self.wake.clone();
+        tokio::spawn(async move {
+            let _ = tx.send(RemoteRunner::scan(&remote).await);
+            wake.notify_one();
+        });
+        self.remote_scan_rx = Some(rx);
+    }
+
+    /// Takes the result of a background remote scan.
+    pub fn finish_remote_scan(&mut self, scanned: Result<FileContext>) {
+        replay::record(|| SessionEntry::Context {
+            context: replay::error_text(&scanned),
+        });
+        match scanned {
+            Ok(context) => self.set_context(context),
+            Err(e) => self.push_error("context", format!("Scan failed: {}", e)),
+        }
         self.dirty = true;
     }
 
@@ -288,7 +985,9 @@ impl App {
                 selected.entry.plugin, selected.entry.command
             ));
         } else {
-            self.executor.set_pin(&selected.entry).await?;
+            self.executor
+                .set_pin(&selected.entry, selected.intent.as_deref())
+                .await?;
             self.push_log(format!(
                 "History pinned [{}] {}",
                 selected.entry.plugin, selected.entry.command
@@ -300,13 +999,82 @@ impl App {
         Ok(())
     }
 
+    /// Whether the proposal on screen is the one `command_pin` was made for.
+    pub fn current_command_is_pinned(&self) -> bool {
+        match (
+            &self.command_pin,
+            &self.selected_plugin,
+            &self.generated_command,
+        ) {
+            (Some(pin), Some(plugin), Some(cmd)) => {
+                pin.plugin == *plugin && pin.command == redact_sensitive_text(cmd)
+            }
+            _ => false,
+        }
+    }
+
+    /// Pins or unpins the proposed command together with the intent that
+    /// produced it. After a run the pin goes on the recorded history entry;
+    /// before one it is a favorite of its own until the command executes.
+    pub async fn toggle_command_pin(&mut self) -> Result<()> {
+        self.dirty = true;
+        let (Some(plugin), Some(cmd)) =
+            (self.selected_plugin.clone(), self.generated_command.clone())
+        else {
+            self.push_log("No command to pin.".to_string());
+            return Ok(());
+        };
+        if self.current_command_is_pinned() {
+            if let Some(pin) = self.command_pin.take() {
+                self.executor.unset_pin(&pin).await?;
+            }
+            self.push_log(format!("Unpinned [{}] {}", plugin, cmd));
+            return Ok(());
+        }
+
+        let entry = match &self.last_history_entry {
+            Some(ran)
+                if matches!(self.state, AppState::Finished(_) | AppState::Error(_))
+                    && ran.plugin == plugin
+                    && ran.command == redact_sensitive_text(&cmd) =>
+            {
+                ran.clone()
+            }
+            _ => HistoryEntry::new(&plugin, &cmd),
+        };
+        let intent = Some(self.input.trim()).filter(|intent| !intent.is_empty());
+        self.executor.set_pin(&entry, intent).await?;
+        self.push_log(format!("Pinned [{}] {}", plugin, cmd));
+        self.command_pin = Some(entry);
+        Ok(())
+    }
+
+    /// Keeps a pin made before execution on the history entry of the run,
+    /// so the favorite is listed once rather than next to its own run.
+    async fn move_command_pin_to(&mut self, recorded: &HistoryEntry) -> Result<()> {
+        if !self.current_command_is_pinned() {
+            return Ok(());
+        }
+        let Some(pin) = self.command_pin.take() else {
+            return Ok(());
+        };
+        let intent = Some(self.input.trim()).filter(|intent| !intent.is_empty());
+        self.executor.unset_pin(&pin).await?;
+        self.executor.set_pin(recorded, intent).await?;
+        self.command_pin = Some(recorded.clone());
+        Ok(())
+    }
+
     pub async fn execute_history_selected_command(&mut self) -> Result<()> {
         let Some(selected) = self.history_items.get(self.history_selected).cloned() else {
             self.push_log("No history command selected.".to_string());
             self.dirty = true;
             return Ok(());
         };
+        self.execute_history_item(selected).await
+    }
 
+    pub async fn execute_history_item(&mut self, selected: HistoryItem) -> Result<()> {
         if !self
             .plugins
             .iter()
@@ -407,7 +1175,11 @@ impl App {
     }
 
     async fn reload_history_items(&mut self) -> Result<()> {
-        let entries = self.executor.load_history_entries().await?;
+        self.history_stamp = self.executor.history_stamp();
+        let entries = self
+            .executor
+            .load_recent_history_entries(HISTORY_ROWS)
+            .await?;
         let pinned = match self.executor.load_pinned_entries().await {
             Ok(items) => items,
             Err(err) => {
@@ -418,12 +1190,48 @@ impl App {
                 Vec::new()
             }
         };
-        self.history_items = merge_history_items(entries, pinned);
+        self.history_items =
+            filter_history_items(merge_history_items(entries, pinned), self.history_filter);
         self.history_selected = clamp_history_selection(self.history_selected, &self.history_items);
         self.sync_history_scroll_to_selection();
         Ok(())
     }
 
+    /// Reloads the open History view when another Dexter has written the
+    /// history or the pins since it was loaded, keeping the same row
+    /// selected.
+    pub async fn refresh_history_if_changed(&mut self) -> Result<()> {
+        self.history_checked_at = Some(Instant::now());
+        if self.executor.history_stamp() == self.history_stamp {
+            return Ok(());
+        }
+        let selected = self
+            .history_items
+            .get(self.history_selected)
+            .map(|item| item.entry.clone());
+        self.reload_history_items().await?;
+        if let Some(idx) = selected.and_then(|selected| {
+            self.history_items.iter().position(|item| {
+                item.entry.timestamp == selected.timestamp
+                    && item.entry.plugin == selected.plugin
+                    && item.entry.command == selected.command
+            })
+        }) {
+            self.history_selected = idx;
+            self.sync_history_scroll_to_selection();
+        }
+        self.dirty = true;
+        Ok(())
+    }
+
+    pub async fn cycle_history_filter(&mut self) -> Result<()> {
+        self.history_filter = self.history_filter.next();
+        self.history_selected = 0;
+        self.reload_history_items().await?;
+        self.dirty = true;
+        Ok(())
+    }
+
     fn sync_history_scroll_to_selection(&mut self) {
         if self.history_items.is_empty() {
             self.output_scroll = 0;
@@ -435,17 +1243,294 @@ impl App {
         self.output_scroll = selected_line.saturating_sub(2);
     }
 
+    /// Loads the `.dexter.toml` governing the cwd after startup or a change
+    /// of directory, then rebuilds the clients it customizes.
+    pub fn refresh_workspace(&mut self) {
+        let Ok(cwd) = std::env::current_dir() else {
+            return;
+        };
+        if self.workspace_cwd.as_ref() == Some(&cwd) {
+            return;
+        }
+        let workspace = match Workspace::discover(&cwd) {
+            Ok(workspace) => {
+                self.workspace_error = None;
+                workspace
+            }
+            Err(e) => {
+                self.push_error(
+                    "config",
+                    format!("{:#}. Commands are blocked until it is fixed.", e),
+                );
+                self.workspace_error = Some(format!("{:#}", e));
+                Workspace::default()
+            }
+        };
+        if let Some(root) = &workspace.root {
+            self.push_log(format!(
+                "Project config: {}",
+                root.join(WORKSPACE_FILE).display()
+            ));
+        }
+        self.workspace = workspace;
+        self.workspace_cwd = Some(cwd);
+        self.apply_config(self.config.clone());
+    }
+
+    /// Scans the paths named in the intent (`@path` or leading paths), or
+    /// the cwd when there are none. Remote targets always scan their
+    /// configured directory.
     pub async fn update_context(&mut self) -> Result<()> {
-        let context = ContextScanner::scan_cwd().await?;
+        // This scan supersedes one still running in the background.
+        self.remote_scan_rx = None;
+        let context = match replay::replayed_context() {
+            Some(replayed) => replayed?,
+            None => {
+                let scanned = self.scan_context().await;
+                replay::record(|| SessionEntry::Context {
+                    context: replay::error_text(&scanned),
+                });
+                scanned?
+            }
+        };
+        self.set_context(context);
+        Ok(())
+    }
+
+    fn set_context(&mut self, context: FileContext) {
         let summary = format_context_lines(&context);
-        self.session_logger.block("CONTEXT_SCAN", &summary);
+        self.log_block("CONTEXT_SCAN", &summary);
         self.push_log(format!("Context scanned ({} files).", context.files.len()));
         self.current_context = Some(context);
         self.dirty = true;
-        Ok(())
+    }
+
+    async fn scan_context(&mut self) -> Result<FileContext> {
+        let context = match self.active_remote() {
+            Some(remote) => RemoteRunner::scan(remote).await?,
+            None => {
+                let cwd = std::env::current_dir()?;
+                self.refresh_workspace();
+                let (paths, _) = intent_paths(&self.input, &cwd);
+                let mut context = if paths.is_empty() {
+                    ContextScanner::scan_cwd().await?
+                } else {
+                    let names: Vec<&str> = paths.iter().map(|p| p.display.as_str()).collect();
+                    self.push_log(format!("Context paths: {}", names.join(", ")));
+                    ContextScanner::scan_paths(&paths).await?
+                };
+                self.workspace.pin_context(&cwd, &mut context);
+                ocr::enrich(&mut context, &cwd, &self.input, &self.config.ocr).await;
+                if !context.ocr.is_empty() {
+                    self.push_log(format!("Read text in {} image(s).", context.ocr.len()));
+                }
+                context
+            }
+        };
+        // Runs recorded here, for the router's hint; none apply to a remote.
+        let dir_history = match self.active_remote() {
+            Some(_) => Vec::new(),
+            None => load_dir_uses(&std::env::current_dir()?)
+                .await
+                .unwrap_or_default(),
+        };
+        self.router = self.router.clone().with_dir_history(dir_history);
+        Ok(context)
+    }
+
+    /// The intent as sent to the models: `@path` markers become plain paths.
+    pub fn llm_input(&self) -> String {
+        self.llm_text(&self.input)
+    }
+
+    /// Skips routing and generation when a plugin reads `input` without
+    /// the model ("show me info about video.mkv") and previews its command.
+    pub fn take_direct_command(&mut self, input: &str) -> bool {
+        if self.active_remote().is_some() {
+            return false;
+        }
+        let files = self
+            .current_context
+            .as_ref()
+            .map(|context| context.files.as_slice())
+            .unwrap_or_default();
+        let Some((plugin, cmd)) = self.plugins.iter().find_map(|plugin| {
+            plugin
+                .direct_command(input, files)
+                .map(|cmd| (plugin.name().to_string(), cmd))
+        }) else {
+            return false;
+        };
+        self.push_log(format!("Matched {} without the model: {}", plugin, cmd));
+        self.log_block(
+            "DIRECT_COMMAND",
+            &format!("plugin={}\ncommand={}", plugin, cmd),
+        );
+        self.selected_plugin = Some(plugin);
+        self.generated_command = Some(cmd.clone());
+        self.command_draft = cmd;
+        self.command_cursor = char_count(&self.command_draft);
+        self.dry_run_output = None;
+        self.output_scroll = 0;
+        self.state = AppState::PendingDryRun;
+        self.dirty = true;
+        true
+    }
+
+    /// `text` as the models get it: local paths in it resolved.
+    fn llm_text(&self, text: &str) -> String {
+        if self.active_remote().is_some() {
+            return text.to_string();
+        }
+        match std::env::current_dir() {
+            Ok(cwd) => intent_paths(text, &cwd).1,
+            Err(_) => text.to_string(),
+        }
+    }
+
+    /// Splits the input when it clearly asks for several unrelated things
+    /// and starts routing and generating every part at once, after their
+    /// routing prompts passed the token budget. `false` when it stays one
+    /// request; `true` also while the prompts wait for the budget.
+    pub fn start_subtasks(&mut self, context: &FileContext) -> bool {
+        let parts = split_intent(&self.input);
+        if parts.is_empty() {
+            return false;
+        }
+        let intents: Vec<(String, String)> = parts
+            .into_iter()
+            .map(|part| {
+                let resolved = self.llm_text(&part);
+                (part, resolved)
+            })
+            .collect();
+        let transcript = self.session.transcript();
+        let router = self.router.clone().with_session(transcript.clone());
+        let prompts: Vec<String> = intents
+            .iter()
+            .filter_map(|(_, resolved)| router.prompt_for(resolved, context, &self.plugins))
+            .collect();
+        if !prompts.is_empty() && self.hold_for_budget(PromptStage::Routing, &prompts.join("\n\n"))
+        {
+            return true;
+        }
+        self.push_log(format!("Split into {} independent tasks.", intents.len()));
+        self.log_block(
+            "INTENT_SPLIT",
+            &intents
+                .iter()
+                .enumerate()
+                .map(|(i, (part, _))| format!("task.{}={}", i + 1, part))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+        let mut executor = self.executor.clone().with_session(transcript);
+        if let Some(route) = self.model_override.take() {
+            self.push_log(format!(
+                "Generating with {} for this request only.",
+                route_label(&route)
+            ));
+            executor = executor.with_llm_client(
+                LlmClient::for_route(self.config.llm_providers(), route)
+                    .with_spend_tracking(self.config.pricing.clone()),
+            );
+        }
+        self.subtasks = Some(SubTasks::start(
+            intents,
+            router,
+            executor,
+            self.plugins.clone(),
+            context.clone(),
+            self.wake.clone(),
+        ));
+        true
+    }
+
+    /// Takes in prepared sub-intents. The first one ready goes on screen;
+    /// when none could be prepared, the request fails with their errors.
+    pub fn poll_subtasks(&mut self) {
+        let Some(subtasks) = self.subtasks.as_mut() else {
+            return;
+        };
+        if !subtasks.poll() {
+            return;
+        }
+        self.check_spending();
+        let Some(subtasks) = self.subtasks.as_mut() else {
+            return;
+        };
+        self.dirty = true;
+        if subtasks.active.is_some() || self.state != AppState::Routing {
+            return;
+        }
+        if let Some(idx) = subtasks.tabs.iter().position(|t| t.proposal().is_some()) {
+            self.show_subtask(idx);
+        } else if !subtasks.is_preparing() {
+            let errors: Vec<String> = subtasks
+                .tabs
+                .iter()
+                .enumerate()
+                .filter_map(|(i, tab)| match &tab.status {
+                    SubTaskStatus::Failed(e) => Some(format!("Task {}: {}", i + 1, e)),
+                    _ => None,
+                })
+                .collect();
+            self.state = AppState::Error(errors.join("\n"));
+        }
+    }
+
+    /// Puts sub-task `idx` on screen: a generated one is previewed again,
+    /// a failed one shows why.
+    pub fn show_subtask(&mut self, idx: usize) {
+        let Some(subtasks) = self.subtasks.as_mut() else {
+            return;
+        };
+        let Some(tab) = subtasks.tabs.get(idx).cloned() else {
+            return;
+        };
+        if tab.status == SubTaskStatus::Preparing {
+            self.push_log(format!("Task {} is still being generated.", idx + 1));
+            self.dirty = true;
+            return;
+        }
+        subtasks.active = Some(idx);
+        self.reset_for_new_request();
+        self.input = tab.intent.clone();
+        self.input_cursor = char_count(&self.input);
+        match (&tab.status, tab.proposal()) {
+            (_, Some((plugin, command))) => {
+                self.push_log(format!("Task {}: {}", idx + 1, tab.intent));
+                self.selected_plugin = Some(plugin.to_string());
+                self.generated_command = Some(command.to_string());
+                self.command_draft = command.to_string();
+                self.command_cursor = char_count(command);
+                self.state = AppState::PendingDryRun;
+            }
+            (SubTaskStatus::Failed(e), None) => {
+                self.state = AppState::Error(format!("Task {}: {}", idx + 1, e));
+            }
+            _ => {}
+        }
+        self.focus = FocusArea::FooterButtons;
+        self.footer_focus = 0;
+        self.dirty = true;
     }
 
     pub async fn execute_command(&mut self) -> Result<()> {
+        if !self.output_conflicts.is_empty() {
+            self.push_log(
+                "Output files already exist: choose OVERWRITE, RENAME or SKIP first.".to_string(),
+            );
+            return Ok(());
+        }
+        self.recheck_dir_watch();
+        if let Some(change) = &self.context_change {
+            self.push_log(format!(
+                "Files changed since the preview ({}); refresh it before running.",
+                change.label()
+            ));
+            return Ok(());
+        }
         if let Some(cmd) = self.generated_command.clone() {
             let plugin_name = self.selected_plugin.clone().unwrap_or_default();
 
@@ -456,15 +1541,42 @@ impl App {
                 .ok_or_else(|| anyhow!("Plugin not found"))?
                 .clone();
 
-            if let Err(e) = SafetyGuard::default().check(&cmd) {
-                self.push_log(format!("Safety check failed before execution: {}", e));
+            // A plugin blocked outright can't be overridden; a command an
+            // override covers skips the command rules.
+            let checked = self
+                .execution_guard(&plugin_name)
+                .map_err(|e| (e, false))
+                .and_then(|guard| match self.overrides(&cmd) {
+                    true => Ok(()),
+                    false => guard.check_execution(&cmd).map_err(|e| (e, true)),
+                });
+            if let Err((e, overridable)) = checked {
+                self.push_error("safety", format!("Check failed before execution: {}", e));
                 self.log_block("EXECUTE_BLOCKED", &format!("command={}\nreason={}", cmd, e));
-                self.state = AppState::Error(format!("Safety check failed: {}", e));
-                self.dirty = true;
+                self.refuse_command(&cmd, &e.to_string(), overridable);
                 return Ok(());
             }
+            let trash = match self.safety_guard().and_then(|guard| {
+                guard.check_deletes(&plugin_name, &cmd, self.config.trash.deletes)
+            }) {
+                // The trash tools only reach this machine's files.
+                Ok(Some(_)) if self.active_remote().is_some() => Err(anyhow!(
+                    "Command blocked: remote deletes can't go to the trash; \
+                     set trash.deletes to \"permanent\" to allow them"
+                )),
+                checked => checked,
+            };
+            let trash = match trash {
+                Ok(trash) => trash,
+                Err(e) => {
+                    self.push_error("safety", format!("Check failed before execution: {}", e));
+                    self.log_block("EXECUTE_BLOCKED", &format!("command={}\nreason={}", cmd, e));
+                    self.refuse_command(&cmd, &e.to_string(), false);
+                    return Ok(());
+                }
+            };
             if !plugin.validate_command(&cmd) {
-                self.push_log("Plugin validation failed before execution.".to_string());
+                self.push_error("safety", "Plugin validation failed before execution.");
                 self.log_block(
                     "EXECUTE_BLOCKED",
                     &format!("command={}\nreason=plugin_validation", cmd),
@@ -473,29 +1585,237 @@ impl App {
                 self.dirty = true;
                 return Ok(());
             }
+            if plugin.built_in() && self.active_remote().is_some() {
+                let reason = format!("{} is built into Dexter and only runs here", plugin_name);
+                self.push_error(
+                    "safety",
+                    format!("Check failed before execution: {}", reason),
+                );
+                self.log_block(
+                    "EXECUTE_BLOCKED",
+                    &format!("command={}\nreason={}", cmd, reason),
+                );
+                self.refuse_command(&cmd, &reason, false);
+                return Ok(());
+            }
 
-            self.state = AppState::Executing;
-            self.output_scroll = 0;
-            self.push_log(format!("Executing [{}]: {}", plugin_name, cmd));
-            if let Err(e) = self.executor.record_history(&plugin_name, &cmd).await {
-                self.push_log(format!("History log failed: {}", e));
+            match self.config.metered.check(plugin.as_ref()) {
+                MeteredVerdict::Block if !std::mem::take(&mut self.metered_hold) => {
+                    self.push_log(
+                        "Metered connection: press RUN ON METERED to download anyway.".to_string(),
+                    );
+                    self.metered_hold = true;
+                    self.dirty = true;
+                    return Ok(());
+                }
+                MeteredVerdict::Warn => {
+                    self.push_warning("network", "Downloading over a metered connection.");
+                }
+                _ => {}
             }
-            self.session_logger.block(
-                "EXECUTE_COMMAND",
-                &format!("plugin={}\ncommand={}", plugin_name, cmd),
-            );
 
-            let final_cmd = cmd;
+            if plugin_name == "f2" && self.active_remote().is_none() {
+                let cwd = std::env::current_dir()?;
+                match CollisionChecker::check_f2(&cmd, &cwd).await {
+                    Ok(collisions) if !collisions.is_empty() => {
+                        let report = format_collisions(&collisions);
+                        self.push_log(format!(
+                            "Rename blocked: {} case-insensitive collision(s).",
+                            collisions.len()
+                        ));
+                        self.log_block("EXECUTE_BLOCKED", &format!("command={}\n{}", cmd, report));
+                        self.state = AppState::Error(report);
+                        self.dirty = true;
+                        return Ok(());
+                    }
+                    Ok(_) => {}
+                    Err(e) => match self.collision_hold.take() {
+                        None => {
+                            self.push_warning(
+                                "safety",
+                                format!(
+                                    "Collision simulation unavailable: {}. Press RUN UNCHECKED to rename anyway.",
+                                    e
+                                ),
+                            );
+                            self.collision_hold = Some(e.to_string());
+                            self.dirty = true;
+                            return Ok(());
+                        }
+                        Some(_) => {
+                            self.push_warning("safety", "Renaming without the collision check.");
+                        }
+                    },
+                }
+            }
+
+            // A file held open elsewhere would leave the batch half done.
+            if self.active_remote().is_none() && std::mem::take(&mut self.files_in_use).is_empty() {
+                let cwd = std::env::current_dir()?;
+                let files = in_use::files_in_use(&plugin_name, &cmd, &cwd).await;
+                if !files.is_empty() {
+                    self.push_warning(
+                        "safety",
+                        format!(
+                            "{} file(s) are open in another process: close them, or RUN ANYWAY.",
+                            files.len()
+                        ),
+                    );
+                    self.log_block(
+                        "EXECUTE_HELD",
+                        &format!("command={}\n{}", cmd, in_use::format_in_use(&files)),
+                    );
+                    self.files_in_use = files;
+                    self.dirty = true;
+                    return Ok(());
+                }
+            }
+
+            if plugin.confirm_twice(&cmd) && !std::mem::take(&mut self.delete_confirmed) {
+                if !self.delete_hold {
+                    self.push_warning(
+                        "safety",
+                        "This removes the files listed in the preview: press D (CONFIRM DELETE) to run it.",
+                    );
+                }
+                self.delete_hold = true;
+                self.dirty = true;
+                return Ok(());
+            }
+            self.delete_hold = false;
+
+            // An override without its audit entry doesn't run.
+            if self.overrides(&cmd) {
+                if let Err(e) = self.audit_override(&plugin_name) {
+                    let message = format!("Could not record the override: {}", e);
+                    self.push_error("audit", message.clone());
+                    self.state = AppState::Error(message);
+                    self.dirty = true;
+                    return Ok(());
+                }
+            }
+
+            // The run changes the directory itself.
+            self.dir_watch = None;
+            self.state = AppState::Executing;
+            // Keystrokes go to the queue editor while the job runs.
+            self.focus = FocusArea::Proposal;
+            self.output_scroll = 0;
+            self.execution_report = None;
+            self.result_outputs = None;
+            self.full_output = None;
+            self.push_log(format!("Executing [{}]: {}", plugin_name, cmd));
+            // The tab's own part of a split request, else the whole input.
+            let intent = self
+                .subtasks
+                .as_ref()
+                .and_then(|subtasks| subtasks.tabs.get(subtasks.active?))
+                .map_or(self.input.clone(), |tab| tab.intent.clone());
+            // A remote host's tools aren't this machine's.
+            let environment = match self.active_remote() {
+                None => Some(RunEnvironment::capture(plugin.as_ref()).await),
+                Some(_) => None,
+            };
+            match self
+                .executor
+                .record_history(&plugin_name, &cmd, Some(&intent), environment)
+                .await
+            {
+                Ok(entry) => {
+                    if let Err(e) = self.move_command_pin_to(&entry).await {
+                        self.push_error("history", format!("Pin update failed: {}", e));
+                    }
+                    self.last_history_entry = Some(entry);
+                }
+                Err(e) => self.push_error("history", format!("Log failed: {}", e)),
+            }
+            let remote = self.active_remote().cloned();
+            if remote.is_none() {
+                if let Err(e) = self.begin_journal(&plugin_name, &cmd).await {
+                    self.push_error("journal", e.to_string());
+                    self.state = AppState::Error(e.to_string());
+                    self.dirty = true;
+                    return Ok(());
+                }
+            }
+            self.audit_run_start(&plugin_name, &cmd);
+            self.log_block(
+                "EXECUTE_COMMAND",
+                &format!(
+                    "plugin={}\ntarget={}\ncommand={}\nargv={}",
+                    plugin_name,
+                    self.execution_target_label(),
+                    cmd,
+                    match self.invocation() {
+                        Some(Ok(invocation)) => invocation.command_line(),
+                        _ => "-".to_string(),
+                    }
+                ),
+            );
+
+            let final_cmd = cmd;
+            let sandbox = self.config.sandbox.clone();
+            let cwd = std::env::current_dir()?;
+            // Remote outputs aren't reachable from here, nor a replayed
+            // run's.
+            let verify = self.config.verify.outputs && remote.is_none() && !replay::is_replaying();
+            let verify_plugin = plugin_name.clone();
 
             let (prog_tx, prog_rx) = mpsc::channel(10);
             let (res_tx, res_rx) = oneshot::channel();
-            tokio::spawn(async move {
-                let result = plugin.execute_with_progress(&final_cmd, prog_tx).await;
-                let _ = res_tx.send(result);
-            });
+            let span = tracing::info_span!(
+                "execution",
+                plugin = %plugin_name,
+                target = %self.execution_target_label()
+            );
+            tokio::spawn(
+                async move {
+                    let replayed =
+                        replay::replayed_run().map(|run| run.map(|output| (output, None)));
+                    let result = match (replayed, remote) {
+                        (Some(replayed), _) => replayed,
+                        (None, Some(remote)) => RemoteRunner::execute(&remote, &final_cmd, prog_tx)
+                            .await
+                            .map(|output| (output, None)),
+                        (None, None) => {
+                            recycle::execute(
+                                trash,
+                                &sandbox,
+                                plugin.as_ref(),
+                                &final_cmd,
+                                &cwd,
+                                prog_tx,
+                            )
+                            .await
+                        }
+                    };
+                    let (result, trashed) = match result {
+                        Ok((output, trashed)) => (Ok(output), trashed),
+                        Err(e) => (Err(e), None),
+                    };
+                    replay::record(|| SessionEntry::Run {
+                        output: replay::error_text(&result),
+                    });
+                    if let Err(e) = &result {
+                        tracing::warn!(error = %e, "execution failed");
+                    }
+                    let mut report = match &result {
+                        Ok(_) if verify => {
+                            Some(verify_outputs(&verify_plugin, &final_cmd, &cwd).await)
+                        }
+                        _ => None,
+                    };
+                    if trashed.is_some() {
+                        report.get_or_insert_with(ExecutionReport::default).trashed = trashed;
+                    }
+                    let _ = res_tx.send((result, report));
+                }
+                .instrument(span),
+            );
 
             self.progress_rx = Some(prog_rx);
             self.execution_result_rx = Some(res_rx);
+            self.execution_started_at = Some(Instant::now());
             self.progress = None;
             self.last_progress_log_line = None;
             self.last_progress_log_at = None;
@@ -504,37 +1824,431 @@ impl App {
         Ok(())
     }
 
+    /// Where the audited command runs: the remote host's workdir, or ours.
+    pub fn audit_cwd(&self) -> PathBuf {
+        match self.active_remote() {
+            Some(remote) => PathBuf::from(remote.workdir()),
+            None => std::env::current_dir().unwrap_or_default(),
+        }
+    }
+
+    /// Mirrors the command to the system log before it runs, when
+    /// `audit.system_log` is set.
+    fn audit_run_start(&self, plugin: &str, command: &str) {
+        let cwd = self.audit_cwd();
+        let event = AuditEvent {
+            plugin,
+            command,
+            cwd: &cwd,
+            host: self.active_remote().map(|remote| remote.name.as_str()),
+        };
+        self.config.audit.record_start(&event);
+    }
+
+    /// Completes the history entry written before the command ran and
+    /// mirrors how it ended to the system log when `audit.system_log` is
+    /// set.
+    pub async fn record_run_outcome(&mut self, result: &Result<String>) {
+        let Some(started) = self.execution_started_at.take() else {
+            return;
+        };
+        if let Some(command) = &self.generated_command {
+            let remote = self.active_remote();
+            let cwd = self.audit_cwd();
+            let event = AuditEvent {
+                plugin: self.selected_plugin.as_deref().unwrap_or_default(),
+                command,
+                cwd: &cwd,
+                host: remote.map(|remote| remote.name.as_str()),
+            };
+            self.config.audit.record(&event, result);
+        }
+        let outcome = RunOutcome::from_result(started.elapsed(), result);
+        let Some(entry) = self.last_history_entry.as_mut() else {
+            return;
+        };
+        if let Err(e) = self.executor.record_outcome(entry, outcome).await {
+            self.push_error("history", format!("Update failed: {}", e));
+            return;
+        }
+        entry.outcome = Some(outcome);
+    }
+
+    /// Keeps the output checks and trashed files of a successful run for
+    /// the Finished view and warns about outputs that look empty or cut short.
+    pub fn record_execution_report(&mut self, report: ExecutionReport) {
+        if report.outputs.is_empty() && report.trashed.is_none() {
+            return;
+        }
+        self.log_block("OUTPUT_CHECKS", &report.summary());
+        let suspicious: Vec<&str> = report
+            .suspicious()
+            .map(|check| check.path.as_str())
+            .collect();
+        if !suspicious.is_empty() {
+            self.push_log(format!(
+                "Warning: {} output(s) look wrong: {}",
+                suspicious.len(),
+                suspicious.join(", ")
+            ));
+        }
+        self.execution_report = Some(report);
+    }
+
+    /// Scrolls the output pane up, continuing into the scrollback once
+    /// the live view is at its top.
+    pub fn scroll_output_up(&mut self, lines: u16) {
+        let within = lines.min(self.output_scroll);
+        self.output_scroll -= within;
+        self.scrollback.scroll_up((lines - within) as usize);
+    }
+
+    pub fn scroll_output_down(&mut self, lines: u16) {
+        let rest = self.scrollback.scroll_down(lines as usize) as u16;
+        self.output_scroll = self.output_scroll.saturating_add(rest);
+    }
+
     pub fn push_log<S: Into<String>>(&mut self, message: S) {
+        self.log(Severity::Info, "dexter", message);
+    }
+
+    pub fn push_warning<S: Into<String>>(&mut self, source: &'static str, message: S) {
+        self.log(Severity::Warning, source, message);
+    }
+
+    pub fn push_error<S: Into<String>>(&mut self, source: &'static str, message: S) {
+        self.log(Severity::Error, source, message);
+    }
+
+    pub fn log<S: Into<String>>(&mut self, severity: Severity, source: &'static str, message: S) {
         let message = message.into();
-        self.session_logger.event("LOG", &message);
-        self.logs.push(message);
-        const MAX_LOG_LINES: usize = 500;
-        if self.logs.len() > MAX_LOG_LINES {
-            let overflow = self.logs.len() - MAX_LOG_LINES;
-            self.logs.drain(0..overflow);
+        telemetry::event(severity.label(), &format!("{}: {}", source, message));
+        if self.logs.len() == LOG_LINES {
+            self.logs.pop_front();
+        }
+        self.logs
+            .push_back(LogEntry::new(severity, source, message));
+    }
+
+    /// The log lines `log_filter` lets through, oldest first.
+    pub fn visible_logs(&self) -> impl DoubleEndedIterator<Item = &LogEntry> {
+        self.logs
+            .iter()
+            .filter(|entry| self.log_filter.shows(entry))
+    }
+
+    /// After a failed run, queues a generation round that feeds the command
+    /// and its error back to the executor model. Returns false once
+    /// `auto_repair_attempts` is used up; the fix is still dry-run and must
+    /// be confirmed before it runs.
+    pub fn start_repair(&mut self, error: &str) -> bool {
+        let max = self.config.auto_repair_attempts;
+        let Some(command) = self.generated_command.clone() else {
+            return false;
+        };
+        if self.repair_attempts >= max {
+            if max > 0 {
+                self.push_log(format!("Auto-repair gave up after {} attempt(s).", max));
+            }
+            return false;
+        }
+        self.repair_attempts += 1;
+        self.push_log(format!(
+            "Execution failed; asking for a fix (attempt {}/{}).",
+            self.repair_attempts, max
+        ));
+        self.log_block(
+            "REPAIR_REQUEST",
+            &format!(
+                "attempt={}/{}\ncommand={}\nerror={}",
+                self.repair_attempts, max, command, error
+            ),
+        );
+        let reason = error
+            .lines()
+            .find(|l| !l.trim().is_empty())
+            .unwrap_or(error);
+        self.repair_note = Some(format!(
+            "AUTO-REPAIR {}/{}: `{}` failed: {}",
+            self.repair_attempts,
+            max,
+            command,
+            reason.trim()
+        ));
+        self.pending_repair = Some((command, error.to_string()));
+        self.dry_run_output = None;
+        self.output_conflicts.clear();
+        self.output_scroll = 0;
+        self.state = AppState::PendingGeneration;
+        true
+    }
+
+    /// Fails only when soft-delete staging was asked for and could not be
+    /// done, in which case the command must not run.
+    async fn begin_journal(&mut self, plugin_name: &str, cmd: &str) -> Result<()> {
+        let cwd = match std::env::current_dir() {
+            Ok(cwd) => cwd,
+            Err(e) => {
+                self.push_warning("journal", format!("Unavailable: {}", e));
+                return Ok(());
+            }
+        };
+        let mut ops = plan_file_ops(plugin_name, cmd, &cwd).await;
+        match trash::stage_overwrites(&self.config.trash, plugin_name, cmd, &cwd, &ops) {
+            Ok(staged) if !staged.is_empty() => {
+                self.push_log(format!(
+                    "Staged {} file(s) in the Dexter trash before running.",
+                    staged.len()
+                ));
+                ops.splice(0..0, staged);
+            }
+            Ok(_) => {}
+            Err(e) => return Err(anyhow!("Soft-delete staging failed: {}", e)),
+        }
+        match Journal::begin(self.input.trim(), plugin_name, cmd, &cwd, ops) {
+            Ok(journal) => {
+                self.log_block(
+                    "JOURNAL_BEGIN",
+                    &format!("planned_file_ops={}", journal.op_count()),
+                );
+                self.journal = Some(journal);
+                self.journal_checked_at = None;
+            }
+            Err(e) => self.push_warning("journal", format!("Unavailable: {}", e)),
+        }
+        Ok(())
+    }
+
+    pub fn check_journal(&mut self) {
+        self.journal_checked_at = Some(Instant::now());
+        if let Some(journal) = self.journal.as_mut() {
+            if let Err(e) = journal.check_progress() {
+                self.push_error("journal", format!("Write failed: {}", e));
+            }
+        }
+    }
+
+    /// The command ended while we watched it, so its journal has nothing
+    /// left to recover.
+    pub fn finish_journal(&mut self) {
+        if let Some(journal) = self.journal.take() {
+            if let Err(e) = journal.finish() {
+                self.push_warning("journal", format!("Cleanup failed: {}", e));
+            }
+        }
+        self.journal_checked_at = None;
+    }
+
+    pub fn load_interrupted_runs(&mut self) {
+        match dexter_core::journal::interrupted_runs() {
+            Ok(runs) => self.interrupted_runs = runs,
+            Err(e) => self.push_error("journal", format!("Journals could not be read: {}", e)),
+        }
+        self.show_interrupted_run();
+    }
+
+    fn show_interrupted_run(&mut self) {
+        self.dirty = true;
+        let Some(run) = self.interrupted_runs.first().cloned() else {
+            self.notice = None;
+            return;
+        };
+        self.push_log(format!(
+            "Interrupted run found: [{}] {}",
+            run.header.plugin, run.header.command
+        ));
+        self.notice = Some(format!(
+            "{}\nRESUME previews the command again, ROLL BACK undoes the completed operations, DISMISS keeps the files as they are.",
+            run.summary()
+        ));
+    }
+
+    /// Puts the interrupted command back in front of the user for a fresh
+    /// dry run and confirmation, in the directory it ran in.
+    pub async fn resume_interrupted(&mut self) -> Result<()> {
+        if self.interrupted_runs.is_empty() {
+            return Ok(());
+        }
+        let run = self.interrupted_runs.remove(0);
+        if !self.plugins.iter().any(|p| p.name() == run.header.plugin) {
+            self.push_log(format!("Plugin not available: {}", run.header.plugin));
+            self.interrupted_runs.insert(0, run);
+            self.dirty = true;
+            return Ok(());
+        }
+        let cwd = run.header.cwd.clone();
+        if std::env::current_dir().ok().as_deref() != Some(cwd.as_path()) {
+            if let Err(e) = std::env::set_current_dir(&cwd) {
+                self.push_log(format!("Cannot resume in {}: {}", cwd.display(), e));
+                self.interrupted_runs.insert(0, run);
+                self.dirty = true;
+                return Ok(());
+            }
+            self.push_log(format!("Working directory: {}", cwd.display()));
+            let _ = self.update_context().await;
         }
+        run.discard()?;
+        self.reset_for_new_request();
+        self.input = run.header.intent.clone();
+        self.input_cursor = char_count(&self.input);
+        self.jobs.begin(&self.input);
+        self.selected_plugin = Some(run.header.plugin.clone());
+        self.generated_command = Some(run.header.command.clone());
+        self.command_draft = run.header.command.clone();
+        self.command_cursor = char_count(&self.command_draft);
+        self.push_log(format!(
+            "Resuming interrupted run ({} of {} file operation(s) were done)",
+            run.done_count(),
+            run.header.ops.len()
+        ));
+        self.focus = FocusArea::FooterButtons;
+        self.state = AppState::PendingDryRun;
+        Ok(())
+    }
+
+    pub fn roll_back_interrupted(&mut self) -> Result<()> {
+        if self.interrupted_runs.is_empty() {
+            return Ok(());
+        }
+        let run = self.interrupted_runs.remove(0);
+        let report = run.roll_back()?;
+        self.push_log(format!(
+            "Rolled back {} file operation(s) of [{}] {}",
+            report.undone, run.header.plugin, run.header.command
+        ));
+        for kept in &report.kept {
+            self.push_log(format!("Left in place: {}", kept));
+        }
+        self.log_block(
+            "JOURNAL_ROLLBACK",
+            &format!("undone={}\nkept={}", report.undone, report.kept.join("\n")),
+        );
+        self.show_interrupted_run();
+        Ok(())
+    }
+
+    pub fn dismiss_interrupted(&mut self) -> Result<()> {
+        if self.interrupted_runs.is_empty() {
+            return Ok(());
+        }
+        let run = self.interrupted_runs.remove(0);
+        run.discard()?;
+        self.push_log(format!(
+            "Dismissed interrupted run [{}] {}",
+            run.header.plugin, run.header.command
+        ));
+        self.show_interrupted_run();
+        Ok(())
     }
 
     pub fn log_block(&self, label: &str, body: &str) {
-        self.session_logger.block(label, body);
+        telemetry::block(label, body);
+    }
+
+    /// Estimates `prompt` for the processing view and logs it. Returns
+    /// `true` when it is over the token budget: the request then waits in
+    /// `ConfirmingBudget` until the user sends it anyway or goes back.
+    pub fn hold_for_budget(&mut self, stage: PromptStage, prompt: &str) -> bool {
+        let estimate = self.estimate_prompt(stage, prompt);
+        self.push_log(format!("{} prompt: {}", stage.label(), estimate.label()));
+        let over = self.config.token_budget.exceeded_by(&estimate)
+            && !std::mem::take(&mut self.budget_approved);
+        self.prompt_estimate = Some((stage, estimate));
+        if over {
+            self.push_log(format!(
+                "Holding the {} prompt: over the {}-token budget.",
+                stage.label(),
+                self.config.token_budget.warn_tokens
+            ));
+            self.budget_hold = Some(stage);
+            self.state = AppState::ConfirmingBudget;
+            // Focus BACK so a stray Enter doesn't send it.
+            self.focus = FocusArea::FooterButtons;
+            self.footer_focus = 1;
+            self.dirty = true;
+        }
+        over
+    }
+
+    /// `prompt` priced for the model `stage` sends it to first.
+    fn estimate_prompt(&self, stage: PromptStage, prompt: &str) -> PromptEstimate {
+        let client = match stage {
+            PromptStage::Routing => self.router.llm_client(),
+            PromptStage::Generation => self.executor.llm_client(),
+        };
+        match client.primary_target() {
+            Some((provider, model)) => {
+                PromptEstimate::with_prices(prompt, Some(provider), model, &self.config.pricing)
+            }
+            None => PromptEstimate::new(prompt, None, "no model"),
+        }
+    }
+
+    /// Sends the prompt held by `hold_for_budget`.
+    pub fn send_over_budget(&mut self) {
+        let Some(stage) = self.budget_hold.take() else {
+            return;
+        };
+        self.budget_approved = true;
+        self.state = stage.resume_state();
+        self.focus = FocusArea::Proposal;
+        self.dirty = true;
     }
 
     pub fn reset_for_new_request(&mut self) {
         self.state = AppState::Input;
+        self.recipe = None;
+        self.output_conflicts.clear();
+        self.schedule_input = None;
         self.generated_command = None;
+        self.previous_command = None;
         self.command_draft.clear();
         self.command_cursor = 0;
         self.dry_run_output = None;
+        self.thumbnail = None;
+        self.thumbnail_rx = None;
         self.output_scroll = 0;
         self.selected_plugin = None;
         self.notice = None;
         self.clarify = None;
         self.generation_cache_policy = CachePolicy::Normal;
+        self.prompt_estimate = None;
+        self.budget_hold = None;
+        self.budget_approved = false;
+        self.metered_hold = false;
+        self.files_in_use.clear();
+        self.collision_hold = None;
+        self.delete_hold = false;
+        self.delete_confirmed = false;
+        self.dir_watch = None;
+        self.context_change = None;
+        self.failure_hint = None;
+        self.failure_install = None;
+        self.pending_repair = None;
+        self.pending_constraint = None;
+        self.safety_block = None;
+        self.override_input = None;
+        self.safety_override = None;
+        self.repair_attempts = 0;
+        self.repair_note = None;
+        self.critic_rx = None;
+        self.critic_issues.clear();
+        self.preview_scope = None;
+        self.diff_sort_by_status = false;
+        self.diff_hscroll = 0;
+        self.diff_page = 0;
+        self.command_pin = None;
+        self.last_history_entry = None;
         self.routing_result_rx = None;
+        self.route_explanation = None;
         self.generation_result_rx = None;
         self.dry_run_result_rx = None;
         self.progress_rx = None;
         self.execution_result_rx = None;
+        self.execution_report = None;
+        self.result_outputs = None;
+        self.full_output = None;
+        self.output_viewer = None;
         self.progress = None;
         self.last_progress_log_line = None;
         self.last_progress_log_at = None;
@@ -544,17 +2258,89 @@ impl App {
         self.dirty = true;
     }
 
+    /// Keeps the command on screen as the one to diff the next against,
+    /// and clears it.
+    pub fn remember_previous_command(&mut self) {
+        if let Some(previous) = self
+            .selected_plugin
+            .clone()
+            .zip(self.generated_command.take())
+        {
+            self.previous_command = Some(previous);
+        }
+    }
+
+    /// The command the one on screen replaced, while it is for the same
+    /// plugin and differs.
+    pub fn replaced_command(&self) -> Option<&str> {
+        let (plugin, command) = self.previous_command.as_ref()?;
+        (self.selected_plugin.as_ref() == Some(plugin)
+            && self.generated_command.as_ref() != Some(command))
+        .then_some(command.as_str())
+    }
+
+    /// Recomputes `command_diff` when the replaced command or the one on
+    /// screen changed since the last frame.
+    pub fn refresh_command_diff(&mut self) {
+        let (Some(old), Some(new)) = (self.replaced_command(), self.generated_command.as_deref())
+        else {
+            self.command_diff = None;
+            return;
+        };
+        if self
+            .command_diff
+            .as_ref()
+            .is_some_and(|diff| diff.is_for(old, new))
+        {
+            return;
+        }
+        let diff = CommandDiff::new(old, new);
+        self.command_diff = Some(diff);
+    }
+
     pub fn reset_to_input_preserve_text(&mut self) {
         self.state = AppState::Input;
+        self.output_conflicts.clear();
+        self.schedule_input = None;
         self.generated_command = None;
+        self.previous_command = None;
         self.command_draft.clear();
         self.command_cursor = 0;
         self.dry_run_output = None;
+        self.thumbnail = None;
+        self.thumbnail_rx = None;
         self.output_scroll = 0;
         self.selected_plugin = None;
         self.notice = None;
         self.clarify = None;
         self.generation_cache_policy = CachePolicy::Normal;
+        self.prompt_estimate = None;
+        self.budget_hold = None;
+        self.budget_approved = false;
+        self.metered_hold = false;
+        self.files_in_use.clear();
+        self.collision_hold = None;
+        self.delete_hold = false;
+        self.delete_confirmed = false;
+        self.dir_watch = None;
+        self.context_change = None;
+        self.failure_hint = None;
+        self.failure_install = None;
+        self.pending_repair = None;
+        self.pending_constraint = None;
+        self.safety_block = None;
+        self.override_input = None;
+        self.safety_override = None;
+        self.repair_attempts = 0;
+        self.repair_note = None;
+        self.critic_rx = None;
+        self.critic_issues.clear();
+        self.preview_scope = None;
+        self.diff_sort_by_status = false;
+        self.diff_hscroll = 0;
+        self.diff_page = 0;
+        self.command_pin = None;
+        self.last_history_entry = None;
         self.last_progress_log_line = None;
         self.last_progress_log_at = None;
         self.history_return_state = None;
@@ -564,9 +2350,633 @@ impl App {
         self.dirty = true;
     }
 
+    /// Starts rendering a thumbnail of the command's first media input when
+    /// the plugin, execution target and config allow one.
+    pub fn start_thumbnail(&mut self) {
+        self.thumbnail = None;
+        self.thumbnail_rx = None;
+        let Some(protocol) = GraphicsProtocol::detect(self.config.thumbnails) else {
+            return;
+        };
+        if self.active_remote().is_some() {
+            return;
+        }
+        let (Some(plugin), Some(cmd)) = (&self.selected_plugin, &self.generated_command) else {
+            return;
+        };
+        let Ok(cwd) = std::env::current_dir() else {
+            return;
+        };
+        let Some(source) = thumbnail_source(plugin, cmd, &cwd) else {
+            return;
+        };
+
+        let cell_px = crossterm::terminal::window_size()
+            .ok()
+            .filter(|size| size.columns > 0 && size.rows > 0)
+            .map(|size| (size.width / size.columns, size.height / size.rows))
+            .unwrap_or((0, 0));
+        let (tx, rx) = oneshot::channel();
+        let wake = self.wake.clone();
+        tokio::spawn(async move {
+            let _ = tx.send(load_thumbnail(source, protocol, cell_px).await);
+            wake.notify_one();
+        });
+        self.thumbnail_rx = Some(rx);
+    }
+
+    /// Shows `message` in the error view, with a fix when the tool output
+    /// in it matches a known failure.
+    pub fn fail_with(&mut self, message: String) {
+        self.failure_hint = dexter_core::triage::triage(self.selected_plugin.as_deref(), &message);
+        self.failure_install = self.failure_hint.and_then(FailureHint::available_install);
+        if let Some(hint) = self.failure_hint {
+            self.push_warning("triage", format!("Known failure: {}", hint.problem));
+        }
+        self.state = AppState::Error(message);
+    }
+
+    /// Looks for a newer release in the background when `updates.check`
+    /// is on.
+    pub fn start_update_check(&mut self) {
+        if !self.config.updates.check || self.config.offline {
+            return;
+        }
+        let policy = self.config.updates.clone();
+        let (tx, rx) = oneshot::channel();
+        let wake = self.wake.clone();
+        tokio::spawn(async move {
+            let _ = tx.send(update::check(&policy, false, env!("CARGO_PKG_VERSION")).await);
+            wake.notify_one();
+        });
+        self.update_rx = Some(rx);
+    }
+
+    /// Downloads the release the check found over the running binary.
+    pub fn start_self_update(&mut self) {
+        let Some(release) = self.update_release.clone() else {
+            return;
+        };
+        if self.self_update_rx.is_some() {
+            return;
+        }
+        self.push_log(format!("Downloading Dexter {}...", release.version()));
+        let (tx, rx) = oneshot::channel();
+        let wake = self.wake.clone();
+        tokio::spawn(async move {
+            let _ = tx.send(update::self_update(&release, env!("CARGO_PKG_VERSION")).await);
+            wake.notify_one();
+        });
+        self.self_update_rx = Some(rx);
+        self.dirty = true;
+    }
+
+    /// Runs the failure hint's install command in the background.
+    pub fn start_install(&mut self) {
+        let Some(argv) = self.failure_install.take() else {
+            return;
+        };
+        if let Err(e) = replay::refuse_spawn(argv[0]) {
+            self.push_error("install", e.to_string());
+            return;
+        }
+        self.push_log(format!("Installing: {}", argv.join(" ")));
+        let (tx, rx) = oneshot::channel();
+        let wake = self.wake.clone();
+        tokio::spawn(async move {
+            let result = tokio::process::Command::new(argv[0])
+                .args(&argv[1..])
+                .output()
+                .await
+                .map_err(|e| anyhow!("Could not run {}: {}", argv[0], e))
+                .and_then(|output| {
+                    let text = format!(
+                        "{}{}",
+                        String::from_utf8_lossy(&output.stdout),
+                        String::from_utf8_lossy(&output.stderr)
+                    );
+                    if output.status.success() {
+                        Ok(text)
+                    } else {
+                        Err(anyhow!("{} failed: {}", argv.join(" "), text.trim()))
+                    }
+                });
+            let _ = tx.send(result);
+            wake.notify_one();
+        });
+        self.install_rx = Some(rx);
+        self.dirty = true;
+    }
+
+    /// When the preview on screen stops waiting for confirmation.
+    pub fn confirm_deadline(&self) -> Option<Instant> {
+        let minutes = self.config.confirm_timeout.minutes;
+        if minutes == 0 {
+            return None;
+        }
+        Some(self.awaiting_since? + Duration::from_secs(minutes * 60))
+    }
+
+    /// The template the input was loaded from.
+    pub fn recipe_template(&self) -> Option<IntentTemplate> {
+        let templates = self.intent_templates();
+        find_template(&templates, self.recipe.as_deref()?).cloned()
+    }
+
+    /// The `confirm_timeout.auto_run` template the request on screen was
+    /// made from, while its input and command are still the template's.
+    pub fn auto_run_recipe(&self) -> Option<String> {
+        let template = self.recipe_template()?;
+        let listed = self
+            .config
+            .confirm_timeout
+            .auto_run
+            .iter()
+            .any(|name| find_template(std::slice::from_ref(&template), name).is_some());
+        let deletes = match (&self.selected_plugin, &self.generated_command) {
+            (Some(plugin), Some(command)) => recycle::deletes_files(plugin, command),
+            _ => true,
+        };
+        // `safety.plugins` can ask for a confirmation every time.
+        let confirm = self
+            .selected_plugin
+            .as_deref()
+            .is_some_and(|plugin| self.config.safety.plugin_policy(plugin) != PluginPolicy::Allow);
+        (listed && !deletes && !confirm && follows_template(&template.text, &self.input))
+            .then_some(template.name)
+    }
+
+    /// Ends a wait for confirmation that ran past `confirm_timeout`: runs
+    /// an `auto_run` recipe, and cancels anything else back to the input,
+    /// so the next Enter previews again instead of running a stale plan.
+    pub async fn expire_confirmation(&mut self) -> Result<()> {
+        self.awaiting_since = None;
+        let minutes = self.config.confirm_timeout.minutes;
+        if let Some(recipe) = self.auto_run_recipe() {
+            self.push_log(format!(
+                "No confirmation after {} min; running recipe \"{}\" (confirm_timeout.auto_run).",
+                minutes, recipe
+            ));
+            return self.execute_command().await;
+        }
+        let command = self.generated_command.clone().unwrap_or_default();
+        self.log_block(
+            "CONFIRM_TIMEOUT",
+            &format!("command={}\nminutes={}", command, minutes),
+        );
+        self.push_log(format!(
+            "No confirmation after {} min; the command was cancelled.",
+            minutes
+        ));
+        self.reset_to_input_preserve_text();
+        self.notice = Some(format!(
+            "Cancelled after {} min without confirmation: {}",
+            minutes, command
+        ));
+        Ok(())
+    }
+
+    /// Starts watching the cwd for the preview now on screen. Remote runs
+    /// are not watched.
+    pub fn start_dir_watch(&mut self) {
+        self.context_change = None;
+        self.dir_watch = None;
+        if self.active_remote().is_some() {
+            return;
+        }
+        let watch = std::env::current_dir()
+            .map_err(anyhow::Error::from)
+            .and_then(|cwd| DirWatch::new(&cwd));
+        match watch {
+            Ok(watch) => self.dir_watch = Some(watch),
+            Err(e) => tracing::debug!(error = %e, "directory watch unavailable"),
+        }
+    }
+
+    pub fn check_dir_watch(&mut self) {
+        self.dir_watch_checked_at = Some(Instant::now());
+        let Some(watch) = self.dir_watch.as_mut() else {
+            return;
+        };
+        let change = watch.poll().cloned();
+        self.set_context_change(change);
+    }
+
+    /// Re-lists the watched directory now rather than trusting the last
+    /// poll, which can be up to a second old.
+    fn recheck_dir_watch(&mut self) {
+        self.dir_watch_checked_at = Some(Instant::now());
+        let Some(watch) = self.dir_watch.as_mut() else {
+            return;
+        };
+        let change = watch.rescan().cloned();
+        self.set_context_change(change);
+    }
+
+    fn set_context_change(&mut self, change: Option<ContextChange>) {
+        if change != self.context_change {
+            if let Some(change) = &change {
+                self.push_log(format!("Context changed: {}.", change.label()));
+            }
+            self.context_change = change;
+            self.dirty = true;
+        }
+    }
+
+    /// Rescans the context and reruns the dry run for the same command, so
+    /// the plan matches the directory again.
+    pub async fn refresh_preview(&mut self) -> Result<()> {
+        self.dir_watch = None;
+        self.context_change = None;
+        self.update_context().await?;
+        self.dry_run_output = None;
+        self.output_scroll = 0;
+        self.state = AppState::PendingDryRun;
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Asks the critic about `cmd` in the background; its issues replace
+    /// the previous command's once they arrive.
+    pub fn start_critic(&mut self, cmd: &str) {
+        self.critic_issues.clear();
+        self.critic_rx = None;
+        let (Some(critic), Some(plugin)) = (self.critic.clone(), self.selected_plugin.clone())
+        else {
+            return;
+        };
+        let input = self.llm_input();
+        let context = self.current_context.clone().unwrap_or_default();
+        let cmd = cmd.to_string();
+        let (tx, rx) = oneshot::channel();
+        let wake = self.wake.clone();
+        tokio::spawn(async move {
+            let _ = tx.send(critic.review(&input, &context, &plugin, &cmd).await);
+            wake.notify_one();
+        });
+        self.critic_rx = Some(rx);
+    }
+
+    /// Answers a pending overwrite question with `policy`. Overwrite runs the
+    /// command straight away; rename stays on the confirmation screen so the
+    /// new output names can be reviewed.
+    pub async fn resolve_output_conflicts(&mut self, policy: ConflictPolicy) -> Result<()> {
+        let (Some(cmd), Some(plugin)) =
+            (self.generated_command.clone(), self.selected_plugin.clone())
+        else {
+            return Ok(());
+        };
+        let conflicts = std::mem::take(&mut self.output_conflicts);
+        if conflicts.is_empty() {
+            return Ok(());
+        }
+        match apply_conflict_policy(&plugin, &cmd, conflicts.clone(), policy) {
+            Ok(ConflictOutcome::Rewritten { command, .. }) => {
+                self.push_log(format!(
+                    "Output conflicts resolved ({:?}): {}",
+                    policy, command
+                ));
+                self.command_draft = command.clone();
+                self.command_cursor = char_count(&command);
+                self.generated_command = Some(command.clone());
+                if self.stream_base_command.is_some() {
+                    self.stream_base_command = Some(command);
+                }
+                if policy == ConflictPolicy::Overwrite {
+                    self.execute_command().await?;
+                }
+            }
+            Ok(ConflictOutcome::Skip { conflicts }) => {
+                self.push_log("Skipped: output files already exist.".to_string());
+                self.state =
+                    AppState::Finished(format!("Skipped.\n{}", format_conflicts(&conflicts)));
+            }
+            Ok(ConflictOutcome::Ask { .. }) | Ok(ConflictOutcome::Clear) => {
+                self.output_conflicts = conflicts;
+            }
+            Err(e) => {
+                self.push_error(
+                    "safety",
+                    format!("Could not resolve output conflicts: {}", e),
+                );
+                self.output_conflicts = conflicts;
+            }
+        }
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Toggles stream `idx` of the current stream-selection preview and
+    /// rewrites the proposed command's `-map` flags to match.
+    pub fn toggle_stream(&mut self, idx: usize) {
+        let Some(PreviewContent::StreamSelection(selection)) = &self.dry_run_output else {
+            return;
+        };
+        let (Some(base), Some(keep)) = (&self.stream_base_command, self.stream_keep.get(idx))
+        else {
+            return;
+        };
+        let mut next = self.stream_keep.clone();
+        next[idx] = !keep;
+        match apply_stream_selection(base, selection, &next) {
+            Ok(cmd) => {
+                let stream = selection.streams[idx].describe();
+                self.push_log(format!(
+                    "{} stream {}",
+                    if next[idx] { "Keeping" } else { "Dropping" },
+                    stream
+                ));
+                self.stream_keep = next;
+                self.command_draft = cmd.clone();
+                self.command_cursor = char_count(&cmd);
+                self.generated_command = Some(cmd);
+            }
+            Err(e) => self.push_error("executor", format!("Stream selection failed: {}", e)),
+        }
+        self.dirty = true;
+    }
+
+    /// Opens the RUN LATER prompt for the command awaiting confirmation.
+    pub fn open_run_later(&mut self) {
+        if !self.output_conflicts.is_empty() {
+            self.push_log(
+                "Output files already exist: choose OVERWRITE, RENAME or SKIP first.".to_string(),
+            );
+            return;
+        }
+        if self.active_remote().is_some() {
+            self.push_log("RUN LATER only schedules local commands.".to_string());
+            return;
+        }
+        let removes = match (&self.selected_plugin, &self.generated_command) {
+            (Some(name), Some(command)) => self
+                .plugins
+                .iter()
+                .any(|p| p.name() == name && p.confirm_twice(command)),
+            _ => false,
+        };
+        if removes {
+            self.push_log(
+                "Commands that remove files are confirmed twice, so RUN LATER can't schedule them."
+                    .to_string(),
+            );
+            return;
+        }
+        if self.scheduler.is_none() {
+            self.push_log("Scheduling is unavailable: no data directory.".to_string());
+            return;
+        }
+        self.schedule_input = Some(String::new());
+        self.schedule_cursor = 0;
+        self.focus = FocusArea::Proposal;
+        self.dirty = true;
+    }
+
+    pub fn cancel_run_later(&mut self) {
+        self.schedule_input = None;
+        self.schedule_cursor = 0;
+        self.dirty = true;
+    }
+
+    /// Persists the confirmed command for the time typed into the prompt.
+    /// A time that doesn't parse keeps the prompt open.
+    pub async fn confirm_run_later(&mut self) -> Result<()> {
+        let Some(when) = self.schedule_input.clone() else {
+            return Ok(());
+        };
+        let (Some(cmd), Some(scheduler)) = (self.generated_command.clone(), self.scheduler.clone())
+        else {
+            self.cancel_run_later();
+            return Ok(());
+        };
+        let run_at = match resolve_run_at(&when) {
+            Ok(run_at) => run_at,
+            Err(e) => {
+                self.push_log(format!("RUN LATER: {}", e));
+                self.dirty = true;
+                return Ok(());
+            }
+        };
+        let plugin_name = self.selected_plugin.clone().unwrap_or_default();
+        let plugin = self
+            .plugins
+            .iter()
+            .find(|p| p.name() == plugin_name)
+            .ok_or_else(|| anyhow!("Plugin not found"))?
+            .clone();
+        if let Err(e) = self
+            .execution_guard(&plugin_name)
+            .and_then(|guard| guard.check_execution(&cmd))
+        {
+            self.schedule_input = None;
+            // Scheduled jobs run unattended, out of an override's reach.
+            self.refuse_command(&cmd, &e.to_string(), false);
+            return Ok(());
+        }
+        if !plugin.validate_command(&cmd) {
+            self.schedule_input = None;
+            self.state = AppState::Error("Command failed plugin validation logic".to_string());
+            self.dirty = true;
+            return Ok(());
+        }
+
+        let cwd = std::env::current_dir()?;
+        let job = scheduler.add(&plugin_name, &cmd, &cwd, run_at).await?;
+        self.log_block(
+            "SCHEDULED",
+            &format!("id={}\nrun_at={}\ncommand={}", job.id, job.run_at, cmd),
+        );
+        self.push_log(format!(
+            "Scheduled [{}] for {}.",
+            plugin_name,
+            job.run_at_label()
+        ));
+        self.schedule_input = None;
+        self.reload_scheduled().await;
+        self.state = AppState::Finished(format!(
+            "Scheduled for {}:\n{}\n\nA running `dexter daemon` starts it on time; otherwise it runs the next time Dexter launches.",
+            job.run_at_label(),
+            cmd
+        ));
+        self.dirty = true;
+        Ok(())
+    }
+
+    pub async fn reload_scheduled(&mut self) {
+        let Some(scheduler) = &self.scheduler else {
+            return;
+        };
+        match scheduler.load().await {
+            Ok(jobs) => self.scheduled_jobs = jobs,
+            Err(e) => self.push_warning("scheduler", format!("Scheduled jobs unavailable: {}", e)),
+        }
+        self.schedule_selected = self
+            .schedule_selected
+            .min(self.scheduled_jobs.len().saturating_sub(1));
+        self.dirty = true;
+    }
+
+    pub fn cycle_scheduled_selection(&mut self) {
+        if !self.scheduled_jobs.is_empty() {
+            self.schedule_selected = (self.schedule_selected + 1) % self.scheduled_jobs.len();
+        }
+        self.dirty = true;
+    }
+
+    pub async fn cancel_selected_scheduled(&mut self) -> Result<()> {
+        let (Some(scheduler), Some(job)) = (
+            self.scheduler.clone(),
+            self.scheduled_jobs.get(self.schedule_selected).cloned(),
+        ) else {
+            return Ok(());
+        };
+        if scheduler.cancel(&job.id).await? {
+            self.push_log(format!(
+                "Cancelled scheduled job for {}: {}",
+                job.run_at_label(),
+                job.command
+            ));
+        } else {
+            self.push_log("That job already ran or was cancelled.".to_string());
+        }
+        self.reload_scheduled().await;
+        Ok(())
+    }
+
+    /// Mirrors the main state machine onto the active job.
+    pub fn sync_active_job(&mut self) {
+        let Some(status) = JobStatus::from_app_state(&self.state) else {
+            return;
+        };
+        let command = self.generated_command.clone();
+        let Some(job) = self.jobs.active_mut() else {
+            return;
+        };
+        if command.is_some() {
+            job.command = command;
+        }
+        let id = job.id;
+        if self.jobs.transition(id, status.clone()) && status.is_terminal() {
+            self.push_log(format!("Job #{} {}.", id, status.label().to_lowercase()));
+        }
+    }
+
+    pub fn queue_intent(&mut self) {
+        let intent = self.queue_input.trim().to_string();
+        if intent.is_empty() {
+            return;
+        }
+        let id = self.jobs.enqueue(&intent);
+        self.push_log(format!("Queued job #{}: {}", id, intent));
+        self.queue_input.clear();
+        self.queue_cursor = 0;
+        self.dirty = true;
+    }
+
+    /// Starts the oldest pending job if nothing else is in flight.
+    pub fn start_next_job(&mut self) -> bool {
+        if !matches!(
+            self.state,
+            AppState::Input | AppState::Finished(_) | AppState::Error(_)
+        ) {
+            return false;
+        }
+        let Some((id, intent)) = self.jobs.start_next() else {
+            return false;
+        };
+        self.push_log(format!("Starting queued job #{}", id));
+        self.input = intent;
+        self.input_cursor = char_count(&self.input);
+        self.log_block("INPUT_SUBMIT", &self.input.clone());
+        self.reset_for_new_request();
+        self.subtasks = None;
+        self.focus = FocusArea::FooterButtons;
+        self.footer_focus = 0;
+        self.state = AppState::PendingRouting;
+        true
+    }
+
     pub fn is_processing_state(&self) -> bool {
         is_processing_state(&self.state)
     }
+
+    /// User templates from config first, then the built-in set.
+    pub fn intent_templates(&self) -> Vec<IntentTemplate> {
+        let mut templates = self.config.templates.clone();
+        templates.extend(builtin_templates());
+        templates
+    }
+
+    pub fn open_template_picker(&mut self) {
+        if self.state != AppState::Input {
+            return;
+        }
+        self.template_selected = 0;
+        self.state = AppState::TemplatePicker;
+        self.focus = FocusArea::FooterButtons;
+        self.footer_focus = 0;
+        self.output_scroll = 0;
+        self.dirty = true;
+    }
+
+    pub fn close_template_picker(&mut self) {
+        self.state = AppState::Input;
+        self.focus = FocusArea::Proposal;
+        self.footer_focus = 0;
+        self.output_scroll = 0;
+        self.dirty = true;
+    }
+
+    pub fn template_move_up(&mut self) {
+        self.template_selected = self.template_selected.saturating_sub(1);
+        self.dirty = true;
+    }
+
+    pub fn template_move_down(&mut self) {
+        let max_idx = self.intent_templates().len().saturating_sub(1);
+        self.template_selected = (self.template_selected + 1).min(max_idx);
+        self.dirty = true;
+    }
+
+    /// Replaces the input with the selected template and jumps to its first
+    /// placeholder.
+    pub fn apply_selected_template(&mut self) {
+        let Some(template) = self
+            .intent_templates()
+            .into_iter()
+            .nth(self.template_selected)
+        else {
+            self.close_template_picker();
+            return;
+        };
+        self.input = template.text;
+        self.recipe = Some(template.name);
+        self.input_cursor = 0;
+        self.clarify = None;
+        self.close_template_picker();
+        self.jump_to_next_placeholder();
+    }
+
+    /// Tab-stop navigation: consumes the next `{placeholder}` in the input
+    /// and parks the cursor there. Returns false when none are left.
+    pub fn jump_to_next_placeholder(&mut self) -> bool {
+        match take_next_placeholder(&mut self.input, &mut self.input_cursor) {
+            Some(name) => {
+                let remaining = placeholder_ranges(&self.input).len();
+                self.notice = Some(if remaining == 0 {
+                    format!("Fill in: {}", name)
+                } else {
+                    format!(
+                        "Fill in: {}  (Tab: next placeholder, {} left)",
+                        name, remaining
+                    )
+                });
+                self.dirty = true;
+                true
+            }
+            None => false,
+        }
+    }
 }
 
 fn format_context_lines(ctx: &dexter_core::context::FileContext) -> String {
@@ -576,6 +2986,9 @@ fn format_context_lines(ctx: &dexter_core::context::FileContext) -> String {
 
     let mut out = Vec::new();
     out.push(format!("File count: {}", ctx.files.len()));
+    if ctx.truncated {
+        out.push("Scan truncated: directory too large to list in time.".to_string());
+    }
     for (idx, file) in ctx.files.iter().enumerate() {
         out.push(format!("{:02}. {}", idx + 1, file));
     }
@@ -606,11 +3019,22 @@ fn clamp_history_selection(current: usize, items: &[HistoryItem]) -> usize {
     }
 }
 
-fn merge_history_items(
+/// Pins stay on top within each group; sorting is stable, so time order
+/// is kept otherwise.
+fn filter_history_items(mut items: Vec<HistoryItem>, filter: HistoryFilter) -> Vec<HistoryItem> {
+    match filter {
+        HistoryFilter::All => {}
+        HistoryFilter::FailuresFirst => items.sort_by_key(|item| !item.failed()),
+        HistoryFilter::FailuresOnly => items.retain(HistoryItem::failed),
+    }
+    items
+}
+
+pub fn merge_history_items(
     history_entries: Vec<HistoryEntry>,
     pinned_entries: Vec<PinnedHistoryEntry>,
 ) -> Vec<HistoryItem> {
-    let mut pin_map: HashMap<(String, String, String), String> = HashMap::new();
+    let mut pin_map: HashMap<(String, String, String), PinnedHistoryEntry> = HashMap::new();
     for pin in pinned_entries {
         let key = (
             pin.timestamp.clone(),
@@ -619,12 +3043,12 @@ fn merge_history_items(
         );
         match pin_map.get_mut(&key) {
             Some(existing) => {
-                if pin.pinned_at > *existing {
-                    *existing = pin.pinned_at;
+                if pin.pinned_at > existing.pinned_at {
+                    *existing = pin;
                 }
             }
             None => {
-                pin_map.insert(key, pin.pinned_at);
+                pin_map.insert(key, pin);
             }
         }
     }
@@ -637,12 +3061,27 @@ fn merge_history_items(
                 entry.plugin.clone(),
                 entry.command.clone(),
             );
+            let pin = pin_map.remove(&key);
             HistoryItem {
-                pinned_at: pin_map.get(&key).cloned(),
+                pinned_at: pin.as_ref().map(|pin| pin.pinned_at.clone()),
+                intent: pin.and_then(|pin| pin.intent),
                 entry,
             }
         })
         .collect();
+    // Pins without a history line were made from a preview that never ran.
+    items.extend(pin_map.into_values().map(|pin| HistoryItem {
+        entry: HistoryEntry {
+            timestamp: pin.timestamp,
+            plugin: pin.plugin,
+            command: pin.command,
+            outcome: None,
+            intent: None,
+            environment: None,
+        },
+        pinned_at: Some(pin.pinned_at),
+        intent: pin.intent,
+    }));
 
     items.sort_by(|a, b| {
         let base_order = match (&a.pinned_at, &b.pinned_at) {
@@ -669,6 +3108,48 @@ fn compare_desc_timestamp(a: &str, b: &str) -> Ordering {
 mod tests {
     use super::*;
 
+    #[test]
+    fn local_clients_try_ollama_models_before_the_global_names() {
+        let mut config = Config::default();
+        config.models.router_model = "gpt-4.1-mini".to_string();
+        config.models.executor_model = "gpt-4.1".to_string();
+        let ollama = ProviderConfig {
+            models: vec!["qwen2.5".to_string()],
+            ..ProviderConfig::builtin(ProviderKind::Ollama, None)
+        };
+        let (router, executor) = local_model_clients(&config, vec![ollama]);
+        for client in [router, executor] {
+            let routes = client.routes();
+            assert_eq!(routes[0].model, "qwen2.5");
+            assert!(routes.iter().all(|route| !route.model.starts_with("gpt-")));
+        }
+    }
+
+    #[test]
+    fn the_command_diff_follows_the_commands_and_resets_with_the_input() {
+        let mut app = App::new(Config::default());
+        app.selected_plugin = Some("ffmpeg".to_string());
+        app.previous_command = Some(("ffmpeg".to_string(), "ffmpeg -crf 28".to_string()));
+        app.generated_command = Some("ffmpeg -crf 23".to_string());
+        app.refresh_command_diff();
+        let diff = app.command_diff.clone().unwrap();
+        assert!(diff.is_for("ffmpeg -crf 28", "ffmpeg -crf 23"));
+        app.refresh_command_diff();
+        assert_eq!(app.command_diff, Some(diff));
+
+        app.generated_command = Some("ffmpeg -crf 20".to_string());
+        app.refresh_command_diff();
+        assert!(app
+            .command_diff
+            .as_ref()
+            .is_some_and(|diff| diff.is_for("ffmpeg -crf 28", "ffmpeg -crf 20")));
+
+        app.reset_to_input_preserve_text();
+        assert_eq!(app.previous_command, None);
+        app.refresh_command_diff();
+        assert_eq!(app.command_diff, None);
+    }
+
     #[test]
     fn history_sort_pins_first_then_pin_time_desc_then_execution_time_desc() {
         let history_entries = vec![
@@ -676,21 +3157,33 @@ mod tests {
                 timestamp: "2026-02-08T10:00:00Z".to_string(),
                 plugin: "f2".to_string(),
                 command: "cmd-a".to_string(),
+                outcome: None,
+                intent: None,
+                environment: None,
             },
             HistoryEntry {
                 timestamp: "2026-02-08T11:00:00Z".to_string(),
                 plugin: "ffmpeg".to_string(),
                 command: "cmd-b".to_string(),
+                outcome: None,
+                intent: None,
+                environment: None,
             },
             HistoryEntry {
                 timestamp: "2026-02-08T12:00:00Z".to_string(),
                 plugin: "pandoc".to_string(),
                 command: "cmd-c".to_string(),
+                outcome: None,
+                intent: None,
+                environment: None,
             },
             HistoryEntry {
                 timestamp: "2026-02-08T09:00:00Z".to_string(),
                 plugin: "qpdf".to_string(),
                 command: "cmd-d".to_string(),
+                outcome: None,
+                intent: None,
+                environment: None,
             },
         ];
         let pinned_entries = vec![
@@ -699,12 +3192,14 @@ mod tests {
                 plugin: "ffmpeg".to_string(),
                 command: "cmd-b".to_string(),
                 pinned_at: "2026-02-08T20:00:00Z".to_string(),
+                intent: None,
             },
             PinnedHistoryEntry {
                 timestamp: "2026-02-08T10:00:00Z".to_string(),
                 plugin: "f2".to_string(),
                 command: "cmd-a".to_string(),
                 pinned_at: "2026-02-08T21:00:00Z".to_string(),
+                intent: None,
             },
         ];
 
@@ -719,6 +3214,48 @@ mod tests {
         assert!(merged[2].pinned_at.is_none());
     }
 
+    #[test]
+    fn history_filter_lists_failures_first_or_only() {
+        let item = |command: &str, success: Option<bool>| HistoryItem {
+            entry: HistoryEntry {
+                timestamp: "2026-02-08T10:00:00Z".to_string(),
+                plugin: "ffmpeg".to_string(),
+                command: command.to_string(),
+                outcome: success.map(|success| RunOutcome {
+                    success,
+                    exit_code: Some(if success { 0 } else { 1 }),
+                    duration_ms: 10,
+                    output_bytes: 0,
+                }),
+                intent: None,
+                environment: None,
+            },
+            pinned_at: None,
+            intent: None,
+        };
+        let items = vec![
+            item("ok", Some(true)),
+            item("bad-1", Some(false)),
+            item("unknown", None),
+            item("bad-2", Some(false)),
+        ];
+        let commands = |items: Vec<HistoryItem>| -> Vec<String> {
+            items.into_iter().map(|i| i.entry.command).collect()
+        };
+        assert_eq!(
+            commands(filter_history_items(
+                items.clone(),
+                HistoryFilter::FailuresFirst
+            )),
+            ["bad-1", "bad-2", "ok", "unknown"]
+        );
+        assert_eq!(
+            commands(filter_history_items(items, HistoryFilter::FailuresOnly)),
+            ["bad-1", "bad-2"]
+        );
+        assert_eq!(HistoryFilter::FailuresOnly.next(), HistoryFilter::All);
+    }
+
     #[test]
     fn clamp_history_selection_keeps_index_in_bounds() {
         let empty: Vec<HistoryItem> = Vec::new();
@@ -729,12 +3266,41 @@ mod tests {
                 timestamp: "2026-02-08T10:00:00Z".to_string(),
                 plugin: "f2".to_string(),
                 command: "cmd".to_string(),
+                outcome: None,
+                intent: None,
+                environment: None,
             },
             pinned_at: None,
+            intent: None,
         }];
         assert_eq!(clamp_history_selection(9, &items), 0);
     }
 
+    #[test]
+    fn pins_from_a_preview_are_listed_without_a_history_line() {
+        let history_entries = vec![HistoryEntry {
+            timestamp: "2026-02-08T10:00:00Z".to_string(),
+            plugin: "f2".to_string(),
+            command: "cmd-ran".to_string(),
+            outcome: None,
+            intent: None,
+            environment: None,
+        }];
+        let pinned_entries = vec![PinnedHistoryEntry {
+            timestamp: "2026-02-08T11:00:00Z".to_string(),
+            plugin: "ffmpeg".to_string(),
+            command: "cmd-never-ran".to_string(),
+            pinned_at: "2026-02-08T11:00:01Z".to_string(),
+            intent: Some("make a gif".to_string()),
+        }];
+
+        let merged = merge_history_items(history_entries, pinned_entries);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].entry.command, "cmd-never-ran");
+        assert_eq!(merged[0].intent.as_deref(), Some("make a gif"));
+        assert!(merged[1].pinned_at.is_none());
+    }
+
     #[test]
     fn processing_states_are_blocked_for_history_open() {
         assert!(is_processing_state(&AppState::PendingRouting));
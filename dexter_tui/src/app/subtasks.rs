@@ -0,0 +1,173 @@
+//! Tabs for an intent split into unrelated sub-intents (see
+//! `dexter_core::splitter`). Every sub-intent is routed and generated in
+//! the background at once; the tab on screen then goes through the usual
+//! preview and confirmation, and switching tabs previews another one.
+
+use anyhow::{anyhow, Result};
+use dexter_core::{Executor, FileContext, RouteOutcome, Router};
+use dexter_plugins::Plugin;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Notify};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubTaskStatus {
+    Preparing,
+    Ready {
+        plugin: String,
+        command: String,
+    },
+    Failed(String),
+    /// Executed at least once; `success` is how the last run ended.
+    Ran {
+        plugin: String,
+        command: String,
+        success: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct SubTask {
+    pub intent: String,
+    pub status: SubTaskStatus,
+}
+
+impl SubTask {
+    /// Plugin and command, once generated.
+    pub fn proposal(&self) -> Option<(&str, &str)> {
+        match &self.status {
+            SubTaskStatus::Ready { plugin, command }
+            | SubTaskStatus::Ran {
+                plugin, command, ..
+            } => Some((plugin, command)),
+            _ => None,
+        }
+    }
+}
+
+type Prepared = (usize, Result<(String, String)>);
+
+pub struct SubTasks {
+    pub tabs: Vec<SubTask>,
+    /// The tab on screen; `None` until one is ready.
+    pub active: Option<usize>,
+    rx: mpsc::UnboundedReceiver<Prepared>,
+}
+
+impl SubTasks {
+    /// Starts routing and generating every intent in parallel. Each comes
+    /// as typed and as the models get it; `wake` is notified as each one
+    /// is ready.
+    pub fn start(
+        intents: Vec<(String, String)>,
+        router: Router,
+        executor: Executor,
+        plugins: Vec<Arc<dyn Plugin>>,
+        context: FileContext,
+        wake: Arc<Notify>,
+    ) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        for (idx, (_, intent)) in intents.iter().enumerate() {
+            let (tx, intent) = (tx.clone(), intent.clone());
+            let (router, executor) = (router.clone(), executor.clone());
+            let (plugins, context) = (plugins.clone(), context.clone());
+            let wake = wake.clone();
+            tokio::spawn(async move {
+                let result = prepare(&intent, &router, &executor, &plugins, &context).await;
+                let _ = tx.send((idx, result));
+                wake.notify_one();
+            });
+        }
+        Self {
+            tabs: intents
+                .into_iter()
+                .map(|(intent, _)| SubTask {
+                    intent,
+                    status: SubTaskStatus::Preparing,
+                })
+                .collect(),
+            active: None,
+            rx,
+        }
+    }
+
+    /// Takes in the sub-intents prepared since the last call; `true` when
+    /// any were.
+    pub fn poll(&mut self) -> bool {
+        let mut changed = false;
+        while let Ok((idx, result)) = self.rx.try_recv() {
+            if let Some(tab) = self.tabs.get_mut(idx) {
+                tab.status = match result {
+                    Ok((plugin, command)) => SubTaskStatus::Ready { plugin, command },
+                    Err(e) => SubTaskStatus::Failed(e.to_string()),
+                };
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    pub fn is_preparing(&self) -> bool {
+        self.tabs
+            .iter()
+            .any(|tab| tab.status == SubTaskStatus::Preparing)
+    }
+
+    /// The tab `delta` steps from the active one, wrapping around.
+    pub fn neighbour(&self, delta: isize) -> usize {
+        let len = self.tabs.len() as isize;
+        let from = self.active.unwrap_or(0) as isize;
+        (from + delta).rem_euclid(len.max(1)) as usize
+    }
+
+    /// Records the active tab's run.
+    pub fn record_run(&mut self, success: bool) {
+        let Some(tab) = self.active.and_then(|idx| self.tabs.get_mut(idx)) else {
+            return;
+        };
+        if let Some((plugin, command)) = tab.proposal() {
+            tab.status = SubTaskStatus::Ran {
+                plugin: plugin.to_string(),
+                command: command.to_string(),
+                success,
+            };
+        }
+    }
+
+    /// Keeps a regenerated or edited command on the active tab.
+    pub fn update_command(&mut self, new_command: &str) {
+        let Some(tab) = self.active.and_then(|idx| self.tabs.get_mut(idx)) else {
+            return;
+        };
+        if let SubTaskStatus::Ready { command, .. } | SubTaskStatus::Ran { command, .. } =
+            &mut tab.status
+        {
+            *command = new_command.to_string();
+        }
+    }
+}
+
+async fn prepare(
+    intent: &str,
+    router: &Router,
+    executor: &Executor,
+    plugins: &[Arc<dyn Plugin>],
+    context: &FileContext,
+) -> Result<(String, String)> {
+    match router.route(intent, context, plugins).await? {
+        RouteOutcome::Selected { plugin, .. } => {
+            let found = plugins
+                .iter()
+                .find(|p| p.name() == plugin)
+                .ok_or_else(|| anyhow!("Plugin not found: {}", plugin))?;
+            let command = executor
+                .generate_command(intent, context, found.as_ref())
+                .await?;
+            Ok((plugin, command))
+        }
+        RouteOutcome::Unsupported { reason } => Err(anyhow!(reason)),
+        RouteOutcome::Clarify { question, .. } => Err(anyhow!(
+            "Needs clarification ({}); submit this part on its own.",
+            question
+        )),
+    }
+}
@@ -0,0 +1,287 @@
+use dexter_core::redact_sensitive_text;
+use std::fs::{create_dir_all, rename, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{reload, Registry};
+
+/// Target for events that mirror the on-screen log and its detail blocks.
+pub const SESSION_TARGET: &str = "dexter::session";
+
+const LOG_FILE_NAME: &str = "dexter.log";
+const MAX_LOG_FILE_BYTES: u64 = 5 * 1024 * 1024;
+const KEPT_LOG_FILES: usize = 5;
+
+/// Verbosity steps offered by the TUI toggle, quietest first.
+const VERBOSITY_LEVELS: [LevelFilter; 4] = [
+    LevelFilter::WARN,
+    LevelFilter::INFO,
+    LevelFilter::DEBUG,
+    LevelFilter::TRACE,
+];
+
+struct Telemetry {
+    path: PathBuf,
+    level: Mutex<LevelFilter>,
+    handle: reload::Handle<Targets, Registry>,
+}
+
+static TELEMETRY: OnceLock<Telemetry> = OnceLock::new();
+
+/// Installs the global subscriber writing Dexter's spans and events to a
+/// size-rotated file in the data dir. `DEXTER_LOG` (e.g. `trace`) sets the
+/// starting verbosity; the default keeps the detail blocks (`debug`).
+/// Failure to open the file leaves tracing disabled rather than aborting.
+pub fn init() {
+    if TELEMETRY.get().is_some() {
+        return;
+    }
+    let Some(dir) = log_dir() else {
+        return;
+    };
+    let Ok(writer) = RotatingFile::open(&dir, LOG_FILE_NAME, MAX_LOG_FILE_BYTES, KEPT_LOG_FILES)
+    else {
+        return;
+    };
+    let level = std::env::var("DEXTER_LOG")
+        .ok()
+        .and_then(|v| v.parse::<LevelFilter>().ok())
+        .unwrap_or(LevelFilter::DEBUG);
+
+    let (filter, handle) = reload::Layer::new(filter_for(level));
+    let fmt = tracing_subscriber::fmt::layer()
+        .with_writer(Arc::new(writer))
+        .with_ansi(false)
+        .with_target(true)
+        .with_span_events(FmtSpan::CLOSE);
+    if tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt)
+        .try_init()
+        .is_err()
+    {
+        return;
+    }
+    let _ = TELEMETRY.set(Telemetry {
+        path: dir.join(LOG_FILE_NAME),
+        level: Mutex::new(level),
+        handle,
+    });
+    tracing::info!(
+        target: SESSION_TARGET,
+        pid = std::process::id(),
+        version = env!("CARGO_PKG_VERSION"),
+        "session started"
+    );
+}
+
+pub fn log_path() -> Option<String> {
+    TELEMETRY.get().map(|t| t.path.display().to_string())
+}
+
+/// The current log file and its rotated copies that exist, newest first.
+pub fn log_files() -> Vec<PathBuf> {
+    let Some(telemetry) = TELEMETRY.get() else {
+        return Vec::new();
+    };
+    let rotated = (1..=KEPT_LOG_FILES).map(|idx| {
+        let mut name = telemetry.path.clone().into_os_string();
+        name.push(format!(".{}", idx));
+        PathBuf::from(name)
+    });
+    std::iter::once(telemetry.path.clone())
+        .chain(rotated)
+        .filter(|path| path.exists())
+        .collect()
+}
+
+pub fn verbosity() -> Option<LevelFilter> {
+    let telemetry = TELEMETRY.get()?;
+    telemetry.level.lock().ok().map(|level| *level)
+}
+
+/// Moves to the next verbosity step (wrapping) and returns it.
+pub fn cycle_verbosity() -> Option<LevelFilter> {
+    let telemetry = TELEMETRY.get()?;
+    let mut level = telemetry.level.lock().ok()?;
+    let idx = VERBOSITY_LEVELS
+        .iter()
+        .position(|l| l == &*level)
+        .map(|i| (i + 1) % VERBOSITY_LEVELS.len())
+        .unwrap_or(0);
+    let next = VERBOSITY_LEVELS[idx];
+    telemetry.handle.reload(filter_for(next)).ok()?;
+    *level = next;
+    tracing::info!(target: SESSION_TARGET, level = %next, "log verbosity changed");
+    Some(next)
+}
+
+/// Applies `level` to Dexter's own crates only; HTTP and runtime internals
+/// stay at `warn` so provider debugging does not drown in connection noise.
+fn filter_for(level: LevelFilter) -> Targets {
+    Targets::new()
+        .with_target("dexter", level)
+        .with_default(LevelFilter::WARN)
+}
+
+pub fn event(label: &str, message: &str) {
+    let bounded = truncate_with_notice(message, MAX_EVENT_BYTES);
+    tracing::info!(target: SESSION_TARGET, label, "{}", bounded);
+}
+
+pub fn block(label: &str, body: &str) {
+    let bounded = truncate_with_notice(body, MAX_BLOCK_BYTES);
+    tracing::debug!(target: SESSION_TARGET, label, "\n{}", bounded);
+}
+
+fn log_dir() -> Option<PathBuf> {
+    let base = dexter_core::paths::data_dir().or_else(|| std::env::current_dir().ok())?;
+    Some(base.join("dexter").join("logs"))
+}
+
+/// Append-only log file that rolls over to `name.1` .. `name.N` once it
+/// would exceed `max_bytes`. Every write is redacted before it hits disk.
+struct RotatingFile {
+    dir: PathBuf,
+    name: String,
+    max_bytes: u64,
+    keep: usize,
+    state: Mutex<(File, u64)>,
+}
+
+impl RotatingFile {
+    fn open(dir: &Path, name: &str, max_bytes: u64, keep: usize) -> io::Result<Self> {
+        create_dir_all(dir)?;
+        let file = open_append(&dir.join(name))?;
+        let len = file.metadata()?.len();
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            name: name.to_string(),
+            max_bytes,
+            keep,
+            state: Mutex::new((file, len)),
+        })
+    }
+
+    fn rotated(&self, idx: usize) -> PathBuf {
+        self.dir.join(format!("{}.{}", self.name, idx))
+    }
+
+    fn rotate(&self, state: &mut (File, u64)) -> io::Result<()> {
+        state.0.flush()?;
+        for idx in (1..self.keep).rev() {
+            let from = self.rotated(idx);
+            if from.exists() {
+                rename(&from, self.rotated(idx + 1))?;
+            }
+        }
+        let current = self.dir.join(&self.name);
+        rename(&current, self.rotated(1))?;
+        *state = (open_append(&current)?, 0);
+        Ok(())
+    }
+}
+
+impl Write for &RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let redacted = sanitize_for_log(&String::from_utf8_lossy(buf));
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| io::Error::other("log writer poisoned"))?;
+        if state.1 > 0 && state.1 + redacted.len() as u64 > self.max_bytes {
+            self.rotate(&mut state)?;
+        }
+        state.0.write_all(redacted.as_bytes())?;
+        state.1 += redacted.len() as u64;
+        // Report the caller's byte count; redaction may change the length.
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.state.lock() {
+            Ok(mut state) => state.0.flush(),
+            Err(_) => Ok(()),
+        }
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+const MAX_EVENT_BYTES: usize = 4096;
+const MAX_BLOCK_BYTES: usize = 65536;
+
+fn sanitize_for_log(input: &str) -> String {
+    redact_sensitive_text(input)
+}
+
+fn truncate_with_notice(input: &str, limit: usize) -> String {
+    if input.len() <= limit {
+        return input.to_string();
+    }
+
+    let mut out = String::new();
+    for ch in input.chars() {
+        if out.len() + ch.len_utf8() > limit.saturating_sub(64) {
+            break;
+        }
+        out.push(ch);
+    }
+    let omitted = input.len().saturating_sub(out.len());
+    out.push_str(&format!("\n...[truncated {} bytes]", omitted));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn sanitize_masks_common_secrets() {
+        let raw = r#"Authorization: Bearer abc123token
+x-api-key: supersecret
+https://a.com/path?token=abc&x=1
+yt-dlp --cookies cookies.txt "https://a.com""#;
+
+        let masked = sanitize_for_log(raw);
+        assert!(!masked.contains("abc123token"));
+        assert!(!masked.contains("supersecret"));
+        assert!(!masked.contains("cookies.txt"));
+        assert!(masked.contains("Authorization: Bearer [REDACTED]"));
+        assert!(masked.contains("x-api-key: [REDACTED]"));
+        assert!(masked.contains("token=[REDACTED]"));
+    }
+
+    #[test]
+    fn truncate_adds_notice_when_over_limit() {
+        let s = "x".repeat(1000);
+        let out = truncate_with_notice(&s, 120);
+        assert!(out.contains("[truncated"));
+        assert!(out.len() < 260);
+    }
+
+    #[test]
+    fn rotating_file_rolls_over_and_redacts() -> io::Result<()> {
+        let dir = tempdir()?;
+        let file = RotatingFile::open(dir.path(), "t.log", 32, 2)?;
+        let mut writer = &file;
+        for i in 0..4 {
+            writeln!(writer, "line {} x-api-key: secret{}", i, i)?;
+        }
+
+        let current = std::fs::read_to_string(dir.path().join("t.log"))?;
+        assert!(current.contains("line 3"));
+        assert!(!current.contains("secret"));
+        assert!(dir.path().join("t.log.1").exists());
+        assert!(dir.path().join("t.log.2").exists());
+        assert!(!dir.path().join("t.log.3").exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,227 @@
+use dexter_core::IntentTemplate;
+
+pub fn builtin_templates() -> Vec<IntentTemplate> {
+    [
+        (
+            "Convert media",
+            "convert all {ext} files to {target} at {quality} quality",
+        ),
+        (
+            "Extract audio",
+            "extract the audio from {video} as {format}",
+        ),
+        (
+            "Batch rename",
+            "rename all {ext} files to {pattern} with a numbered suffix",
+        ),
+        (
+            "Resize images",
+            "resize all {ext} images to {width} pixels wide",
+        ),
+        ("OCR PDF", "make {pdf} searchable with {language} OCR"),
+        ("Download audio", "download {url} as {format} into {folder}"),
+        (
+            "Convert document",
+            "convert {document} to {target} keeping the table of contents",
+        ),
+    ]
+    .into_iter()
+    .map(|(name, text)| IntentTemplate {
+        name: name.to_string(),
+        text: text.to_string(),
+    })
+    .collect()
+}
+
+/// The template called `name`, ignoring case and treating spaces, dashes
+/// and underscores alike, so `convert-media` finds "Convert media".
+pub fn find_template<'a>(
+    templates: &'a [IntentTemplate],
+    name: &str,
+) -> Option<&'a IntentTemplate> {
+    let key = |name: &str| -> String {
+        name.split(|c: char| c.is_whitespace() || c == '-' || c == '_')
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>()
+            .join("-")
+    };
+    let wanted = key(name);
+    templates
+        .iter()
+        .find(|template| key(&template.name) == wanted)
+}
+
+/// Char ranges (`start..end`, braces included) of every `{name}` placeholder.
+pub fn placeholder_ranges(text: &str) -> Vec<(usize, usize)> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == '{' {
+            let name_len = chars[i + 1..]
+                .iter()
+                .take_while(|c| !c.is_whitespace() && **c != '{' && **c != '}')
+                .count();
+            let close = i + 1 + name_len;
+            if name_len > 0 && chars.get(close) == Some(&'}') {
+                out.push((i, close + 1));
+                i = close + 1;
+                continue;
+            }
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Whether `input` still holds one of `template`'s `{name}` placeholders.
+/// Braces typed into the input itself, such as an f2 `{date}` pattern,
+/// don't count.
+pub fn has_placeholders(template: &str, input: &str) -> bool {
+    let names = placeholder_names(template);
+    placeholder_names(input)
+        .iter()
+        .any(|name| names.contains(name))
+}
+
+fn placeholder_names(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    placeholder_ranges(text)
+        .into_iter()
+        .map(|(start, end)| chars[start..end].iter().collect())
+        .collect()
+}
+
+/// Removes the next placeholder at or after `cursor` (wrapping to the first)
+/// and parks the cursor in its place, returning the placeholder name so the
+/// caller can tell the user what to type.
+pub fn take_next_placeholder(text: &mut String, cursor: &mut usize) -> Option<String> {
+    let ranges = placeholder_ranges(text);
+    let (start, end) = ranges
+        .iter()
+        .find(|(start, _)| *start >= *cursor)
+        .or_else(|| ranges.first())
+        .copied()?;
+
+    let chars: Vec<char> = text.chars().collect();
+    let name: String = chars[start + 1..end - 1].iter().collect();
+    *text = chars[..start].iter().chain(&chars[end..]).collect();
+    *cursor = start;
+    Some(name)
+}
+
+/// Whether `input` is still `template` with its placeholders filled in:
+/// it starts and ends like the template, has every fixed part in order and
+/// something typed into every placeholder.
+pub fn follows_template(template: &str, input: &str) -> bool {
+    let chars: Vec<char> = template.chars().collect();
+    let mut bounds = vec![0];
+    for (start, end) in placeholder_ranges(template) {
+        bounds.extend([start, end]);
+    }
+    bounds.push(chars.len());
+    let fixed: Vec<String> = bounds
+        .chunks(2)
+        .map(|part| {
+            chars[part[0]..part[1]]
+                .iter()
+                .collect::<String>()
+                .trim()
+                .to_string()
+        })
+        .collect();
+    let Some((first, slots)) = fixed.split_first() else {
+        return false;
+    };
+    let Some(mut rest) = input.trim().strip_prefix(first.as_str()) else {
+        return false;
+    };
+    for (i, fixed) in slots.iter().enumerate() {
+        let end = if i + 1 == slots.len() {
+            rest.strip_suffix(fixed.as_str()).map(str::len)
+        } else {
+            rest.match_indices(fixed.as_str())
+                .map(|(idx, _)| idx)
+                .find(|idx| !rest[..*idx].trim().is_empty())
+        };
+        let Some(end) = end else {
+            return false;
+        };
+        if rest[..end].trim().is_empty() {
+            return false;
+        }
+        rest = &rest[end + fixed.len()..];
+    }
+    rest.trim().is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::editor::char_count;
+
+    #[test]
+    fn finds_templates_by_loose_name() {
+        let templates = builtin_templates();
+        let found = find_template(&templates, "convert-media").map(|t| t.name.as_str());
+        assert_eq!(found, Some("Convert media"));
+        let found = find_template(&templates, " OCR_pdf ").map(|t| t.name.as_str());
+        assert_eq!(found, Some("OCR PDF"));
+        assert!(find_template(&templates, "convert").is_none());
+    }
+
+    #[test]
+    fn filled_in_templates_still_follow_them() {
+        let template = "convert {video} to webm at {quality}";
+        assert!(follows_template(
+            template,
+            "convert talk.mkv to webm at 720p"
+        ));
+        assert!(!follows_template(template, "convert  to webm at "));
+        assert!(!follows_template(template, "convert talk.mkv to mp4"));
+        assert!(!follows_template(template, "delete every webm"));
+        assert!(!follows_template(
+            template,
+            "first delete all, then convert a.mkv to webm at 720p"
+        ));
+        assert!(!follows_template(
+            "convert {video} to webm",
+            "convert a.mkv to webm and delete the originals"
+        ));
+        assert!(follows_template("list files", " list files "));
+    }
+
+    #[test]
+    fn finds_placeholders_and_ignores_malformed_braces() {
+        let text = "convert {ext} to {} at { bad} {quality}";
+        assert_eq!(placeholder_ranges(text), vec![(8, 13), (30, 39)]);
+        assert!(has_placeholders(
+            text,
+            "convert mkv to {} at { bad} {quality}"
+        ));
+        assert!(!has_placeholders(text, "convert mkv to webm at high"));
+        assert!(!has_placeholders(text, "rename to {date}_{num}"));
+    }
+
+    #[test]
+    fn take_next_placeholder_advances_and_wraps() {
+        let mut text = "convert {ext} to {target}".to_string();
+        let mut cursor = 0;
+
+        assert_eq!(
+            take_next_placeholder(&mut text, &mut cursor).as_deref(),
+            Some("ext")
+        );
+        assert_eq!(text, "convert  to {target}");
+        assert_eq!(cursor, 8);
+
+        cursor = char_count(&text);
+        assert_eq!(
+            take_next_placeholder(&mut text, &mut cursor).as_deref(),
+            Some("target")
+        );
+        assert_eq!(text, "convert  to ");
+        assert_eq!(take_next_placeholder(&mut text, &mut cursor), None);
+    }
+}
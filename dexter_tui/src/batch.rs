@@ -0,0 +1,404 @@
+use anyhow::{anyhow, Result};
+use dexter_core::collision::format_collisions;
+use dexter_core::conflict::format_conflicts;
+use dexter_core::encoders;
+use dexter_core::executor::{record_dir_use, record_plugin_success};
+use dexter_core::in_use::{files_in_use, format_in_use};
+use dexter_core::journal::plan_file_ops;
+use dexter_core::ocr;
+use dexter_core::recycle;
+use dexter_core::trash;
+use dexter_core::{
+    AuditEvent, BatchProgress, CollisionChecker, Config, ConflictOutcome, ContextScanner, Executor,
+    FileContext, Hooks, HwEncoder, JobLane, Journal, LlmClient, MeteredVerdict, RouteOutcome,
+    Router, RunEnvironment, RunOutcome, Workspace,
+};
+use dexter_plugins::Progress;
+use futures_util::future::join_all;
+use std::collections::HashMap;
+use std::io::{stdin, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tokio::sync::{mpsc, Semaphore};
+
+use crate::app::state::builtin_plugins;
+use crate::quick::prompt_line;
+
+/// `dexter batch "intent" [DIR...]`: route the intent once, then generate
+/// one command per directory (the current directory's subdirectories when
+/// none are given) through a single provider batch, confirm the whole set,
+/// and run each command inside its directory: CPU-only commands up to
+/// `config.parallel_jobs` at a time, those on a hardware encoder one at a
+/// time.
+pub async fn run_batch(args: &[String], read_only: bool) -> Result<()> {
+    let Some((intent, dir_args)) = args.split_first() else {
+        return Err(anyhow!("Usage: dexter batch \"intent\" [DIR...]"));
+    };
+    let intent = intent.trim().to_string();
+    if intent.is_empty() {
+        return Err(anyhow!("No intent given."));
+    }
+    let dirs = if dir_args.is_empty() {
+        subdirectories(&std::env::current_dir()?)?
+    } else {
+        dir_args.iter().map(PathBuf::from).collect()
+    };
+    if dirs.is_empty() {
+        return Err(anyhow!("No directories to process."));
+    }
+
+    let config = Config::load().await?;
+    if !config.has_keys() {
+        return Err(anyhow!(
+            "No provider is configured. Run `dexter --setup` first."
+        ));
+    }
+    let providers = config.llm_providers();
+    let router = Router::new(
+        LlmClient::with_routes(
+            providers.clone(),
+            config.models.router_routes.clone(),
+            config.models.router_model.clone(),
+            config.models.router_fallback_models.clone(),
+        )
+        .with_spend_tracking(config.pricing.clone()),
+    )
+    .with_offline(config.offline)
+    .with_strategy(config.models.router_strategy)
+    .with_routing(config.models.routing)
+    .with_language(&config.language)
+    .with_context_file_limit(config.context_file_limit)
+    .with_clarify_options(config.clarify_options);
+    let executor = Executor::new(
+        LlmClient::with_routes(
+            providers,
+            config.models.executor_routes.clone(),
+            config.models.executor_model.clone(),
+            config.models.executor_fallback_models.clone(),
+        )
+        .with_spend_tracking(config.pricing.clone()),
+    )
+    .with_language(&config.language)
+    .with_history_examples(config.history_examples)
+    .with_context_file_limit(config.context_file_limit)
+    .with_hooks(Hooks::from_config_dir())
+    .with_safety_guard(Workspace::guard_for(
+        &std::env::current_dir()?,
+        &config.safety,
+    )?);
+    let plugins = builtin_plugins(&config);
+
+    let mut contexts: Vec<(PathBuf, FileContext)> = Vec::new();
+    for dir in dirs {
+        match ContextScanner::scan_dir(&dir).await {
+            Ok(mut context) => {
+                ocr::enrich(&mut context, &dir, &intent, &config.ocr).await;
+                contexts.push((dir, context));
+            }
+            Err(e) => eprintln!("Skipping {}: {}", dir.display(), e),
+        }
+    }
+    let Some((_, first_context)) = contexts.first() else {
+        return Err(anyhow!("None of the directories could be scanned."));
+    };
+
+    // Similar directories get the same tool; routing each one separately
+    // would only multiply router calls.
+    let plugin_name = match router.route(&intent, first_context, &plugins).await? {
+        RouteOutcome::Selected { plugin, .. } => plugin,
+        RouteOutcome::Unsupported { reason } => {
+            return Err(anyhow!("This request isn't supported.\n{}", reason));
+        }
+        RouteOutcome::Clarify { question, .. } => {
+            return Err(anyhow!(
+                "The intent is ambiguous ({}). Rephrase it or use `dexter quick` in one directory.",
+                question
+            ));
+        }
+    };
+    let plugin = plugins
+        .iter()
+        .find(|p| p.name() == plugin_name)
+        .ok_or_else(|| anyhow!("Plugin not found: {}", plugin_name))?;
+
+    let jobs: Vec<(String, FileContext)> = contexts
+        .iter()
+        .map(|(_, context)| (intent.clone(), context.clone()))
+        .collect();
+    let (progress_tx, mut progress_rx) = mpsc::channel::<BatchProgress>(16);
+    let printer = tokio::spawn(async move {
+        while let Some(progress) = progress_rx.recv().await {
+            eprintln!("{}", format_batch_progress(&progress));
+        }
+    });
+    eprintln!("Generating {} {} command(s)...", jobs.len(), plugin_name);
+    let generated = executor
+        .generate_commands_batch(&jobs, plugin.as_ref(), progress_tx)
+        .await;
+    let _ = printer.await;
+    let generated = generated?;
+    for error in executor.hooks().take_errors() {
+        eprintln!("Hook failed: {}", error);
+    }
+
+    let mut planned: Vec<(PathBuf, String)> = Vec::new();
+    for ((dir, _), result) in contexts.into_iter().zip(generated) {
+        match result {
+            Ok(command) => {
+                println!("[{}] {}", dir.display(), command);
+                planned.push((dir, command));
+            }
+            Err(e) => println!("[{}] FAILED: {}", dir.display(), e),
+        }
+    }
+    if planned.is_empty() {
+        return Err(anyhow!("No command could be generated."));
+    }
+    let lanes: Vec<JobLane> = planned
+        .iter()
+        .map(|(_, command)| JobLane::of(command))
+        .collect();
+    let nvenc_busy = if lanes.contains(&JobLane::Hardware(HwEncoder::Nvenc)) {
+        encoders::nvenc_sessions_in_use().await
+    } else {
+        None
+    };
+    let parallel = config.parallel_jobs.max(1);
+    for line in encoders::describe_plan(&lanes, parallel, nvenc_busy) {
+        println!("Schedule: {}", line);
+    }
+    if read_only {
+        println!("Not executing: Dexter is in read-only mode.");
+        return Ok(());
+    }
+    if !stdin().is_terminal() {
+        println!("Not executing: confirmation needs an interactive terminal.");
+        return Ok(());
+    }
+    let answer = prompt_line(&format!("Run {} command(s)? [y/N] ", planned.len()))?;
+    if !matches!(answer.to_lowercase().as_str(), "y" | "yes") {
+        println!("Cancelled.");
+        return Ok(());
+    }
+    match config.metered.check(plugin.as_ref()) {
+        MeteredVerdict::Block => {
+            let answer = prompt_line("The connection is metered. Download anyway? [y/N] ")?;
+            if !matches!(answer.to_lowercase().as_str(), "y" | "yes") {
+                println!("Cancelled.");
+                return Ok(());
+            }
+        }
+        MeteredVerdict::Warn => eprintln!("Warning: downloading over a metered connection."),
+        MeteredVerdict::Clear => {}
+    }
+    if planned
+        .iter()
+        .any(|(_, command)| plugin.confirm_twice(command))
+    {
+        let answer = prompt_line("These commands remove files. Run them? [y/N] ")?;
+        if !matches!(answer.to_lowercase().as_str(), "y" | "yes") {
+            println!("Cancelled.");
+            return Ok(());
+        }
+    }
+
+    // One slot per hardware encoder, `parallel` for the CPU; each job waits
+    // for a slot in its lane, in the order the jobs were planned, and NVENC
+    // jobs then for a session other programs left free.
+    let slots: HashMap<JobLane, Semaphore> = lanes
+        .iter()
+        .map(|lane| match lane {
+            JobLane::Hardware(_) => (*lane, Semaphore::new(1)),
+            JobLane::Cpu => (*lane, Semaphore::new(parallel)),
+        })
+        .collect();
+    let runs = planned.iter().zip(&lanes).map(|((dir, command), lane)| {
+        let (executor, config, plugin) = (&executor, &config, plugin.as_ref());
+        let (plugin_name, intent, slots) = (&plugin_name, &intent, &slots);
+        async move {
+            let _slot = slots[lane].acquire().await;
+            // The lane is held while other programs fill every session.
+            if *lane == JobLane::Hardware(HwEncoder::Nvenc) {
+                encoders::wait_for_nvenc_session(|busy| {
+                    println!(
+                        "Waiting for an NVENC session for {}: {} in use",
+                        dir.display(),
+                        busy
+                    )
+                })
+                .await;
+            }
+            let result =
+                run_in_dir(executor, config, plugin, plugin_name, intent, command, dir).await;
+            println!("== {} ({})", dir.display(), lane.name());
+            match &result {
+                Ok(report) => println!("{}", report),
+                Err(e) => println!("Failed: {}", e),
+            }
+            result.is_err()
+        }
+    });
+    let failures = join_all(runs)
+        .await
+        .into_iter()
+        .filter(|failed| *failed)
+        .count();
+    if failures > 0 {
+        return Err(anyhow!("{} command(s) failed.", failures));
+    }
+    Ok(())
+}
+
+/// Runs `command` in `dir`: what to print for it, its output or why it was
+/// skipped.
+async fn run_in_dir(
+    executor: &Executor,
+    config: &Config,
+    plugin: &dyn dexter_plugins::Plugin,
+    plugin_name: &str,
+    intent: &str,
+    command: &str,
+    dir: &Path,
+) -> Result<String> {
+    let cwd = &std::env::current_dir()?.join(dir);
+    // Each directory may sit in a different project with its own rules.
+    let guard = Workspace::guard_for(cwd, &config.safety)?;
+    guard
+        .check_plugin(plugin_name)
+        .and_then(|()| guard.check(command))
+        .map_err(|e| anyhow!("Safety check failed: {}", e))?;
+    let trash_tool = guard
+        .check_deletes(plugin_name, command, config.trash.deletes)
+        .map_err(|e| anyhow!("Safety check failed: {}", e))?;
+    // Nobody is watching each step, so `ask` conflicts are skipped rather
+    // than overwritten.
+    let command = match executor.resolve_output_conflicts(
+        plugin_name,
+        command,
+        cwd,
+        config.conflict_policy_for(plugin.name()),
+    )? {
+        ConflictOutcome::Clear => command.to_string(),
+        ConflictOutcome::Rewritten { command, .. } => command,
+        ConflictOutcome::Skip { conflicts } | ConflictOutcome::Ask { conflicts } => {
+            return Ok(format!("Skipped. {}", format_conflicts(&conflicts)));
+        }
+    };
+    if plugin_name == "f2" {
+        match CollisionChecker::check_f2(&command, cwd).await {
+            Ok(collisions) if !collisions.is_empty() => {
+                return Err(anyhow!(format_collisions(&collisions)));
+            }
+            Ok(_) => {}
+            // Nobody can confirm a rename that couldn't be checked.
+            Err(e) => {
+                return Ok(format!("Skipped. Collision simulation unavailable: {}", e));
+            }
+        }
+    }
+    let in_use = files_in_use(plugin_name, &command, cwd).await;
+    if !in_use.is_empty() {
+        return Ok(format!("Skipped. {}", format_in_use(&in_use)));
+    }
+
+    let (progress_tx, mut progress_rx) = mpsc::channel::<Progress>(64);
+    let dir_label = cwd.display().to_string();
+    let printer = tokio::spawn(async move {
+        while let Some(progress) = progress_rx.recv().await {
+            eprintln!("[{}] {}", dir_label, progress.message);
+        }
+    });
+    // A crash mid-batch leaves this journal for the next `dexter` launch.
+    let mut ops = plan_file_ops(plugin_name, &command, cwd).await;
+    let staged = trash::stage_overwrites(&config.trash, plugin_name, &command, cwd, &ops)
+        .map_err(|e| anyhow!("Soft-delete staging failed: {}", e))?;
+    if !staged.is_empty() {
+        eprintln!("Staged {} file(s) in the Dexter trash.", staged.len());
+    }
+    ops.splice(0..0, staged);
+    let journal = Journal::begin(intent, plugin_name, &command, cwd, ops)
+        .map_err(|e| eprintln!("Run journal unavailable: {}", e))
+        .ok();
+    let event = AuditEvent {
+        plugin: plugin_name,
+        command: &command,
+        cwd,
+        host: None,
+    };
+    config.audit.record_start(&event);
+    let started = Instant::now();
+    let result = recycle::execute(
+        trash_tool,
+        &config.sandbox,
+        plugin,
+        &command,
+        cwd,
+        progress_tx,
+    )
+    .await
+    .map(|(output, _)| output);
+    let _ = printer.await;
+    if let Some(journal) = journal {
+        if let Err(e) = journal.finish() {
+            eprintln!("Run journal cleanup failed: {}", e);
+        }
+    }
+    config.audit.record(&event, &result);
+    let outcome = RunOutcome::from_result(started.elapsed(), &result);
+    if let Err(e) = executor
+        .record_run(
+            plugin_name,
+            &command,
+            Some(intent),
+            outcome,
+            Some(RunEnvironment::capture(plugin).await),
+        )
+        .await
+    {
+        eprintln!("Failed to record history: {}", e);
+    }
+    let output = result?;
+    let _ = record_plugin_success(plugin_name).await;
+    let _ = record_dir_use(cwd, plugin_name).await;
+    Ok(output.trim_end().to_string())
+}
+
+fn subdirectories(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut dirs: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .map(|entry| entry.path())
+        .collect();
+    dirs.sort();
+    Ok(dirs)
+}
+
+fn format_batch_progress(progress: &BatchProgress) -> String {
+    let done = progress.completed + progress.failed;
+    let pct = (done * 100).checked_div(progress.total).unwrap_or(0);
+    let mut line = format!(
+        "[batch {}] {}/{} done ({}%)",
+        progress.status, done, progress.total, pct
+    );
+    if progress.failed > 0 {
+        line.push_str(&format!(", {} failed", progress.failed));
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_line_reports_counts() {
+        let line = format_batch_progress(&BatchProgress {
+            status: "in_progress".to_string(),
+            completed: 3,
+            failed: 1,
+            total: 8,
+        });
+        assert_eq!(line, "[batch in_progress] 4/8 done (50%), 1 failed");
+    }
+}
@@ -0,0 +1,210 @@
+use anyhow::{anyhow, Result};
+use dexter_core::bench::{
+    bench_route, bench_routes, format_table, suggested_order, table_row, BENCH_PROMPTS,
+    TABLE_HEADER,
+};
+use dexter_core::{BenchResult, Config, HistoryEntry, OutputLayout};
+use ratatui::backend::{CrosstermBackend, TestBackend};
+use ratatui::layout::Constraint;
+use ratatui::widgets::{Borders, Row, Table};
+use ratatui::{Terminal, TerminalOptions, Viewport};
+use std::io::{stdout, IsTerminal};
+use std::time::{Duration, Instant};
+
+use crate::app::state::{App, AppState, HistoryItem, HISTORY_ROWS};
+use crate::theme::Theme;
+use crate::ui::main_view::ui;
+
+/// Simulated session lengths, in hours, at which frames are timed.
+const FRAME_CHECKPOINTS: [u32; 4] = [1, 2, 4, 8];
+/// A log line a second and a run every ten seconds.
+const LOG_LINES_PER_HOUR: usize = 3_600;
+const RUNS_PER_HOUR: usize = 360;
+const FRAMES: usize = 200;
+
+/// `dexter bench`: times the standard prompt set on every route and prints
+/// the results as a table, drawn in the terminal or, with `plain` or when
+/// piped, as aligned text.
+pub async fn run_bench(rounds: usize, plain: bool) -> Result<()> {
+    let config = Config::load().await?;
+    if !config.has_keys() {
+        return Err(anyhow!(
+            "No provider is configured. Run `dexter --setup` first."
+        ));
+    }
+    if rounds == 0 {
+        return Err(anyhow!("--rounds must be at least 1."));
+    }
+    let routes = bench_routes(&config);
+    eprintln!(
+        "Benchmarking {} route(s), {} request(s) each...",
+        routes.len(),
+        rounds * BENCH_PROMPTS.len()
+    );
+    let mut results = Vec::new();
+    for route in routes {
+        eprintln!("  {} / {}", route.provider.display_name(), route.model);
+        results.push(bench_route(&config, route, rounds).await);
+    }
+
+    if plain || !stdout().is_terminal() {
+        println!("{}", format_table(&results));
+    } else {
+        let theme = Theme::from_config(&config.theme).with_plain(config.plain_ui);
+        // Terminals that don't report the cursor position get the text.
+        if draw_table(&results, &theme).is_err() {
+            println!("{}", format_table(&results));
+        }
+    }
+    for result in &results {
+        if let Some(error) = &result.last_error {
+            eprintln!(
+                "{} / {} failed {} of {}: {}",
+                result.route.provider.display_name(),
+                result.route.model,
+                result.failures,
+                result.requests(),
+                // The fallback chain's summary line is the same for all;
+                // the last line is this route's own error.
+                error
+                    .lines()
+                    .last()
+                    .unwrap_or_default()
+                    .trim_start_matches("- ")
+            );
+        }
+    }
+    let order: Vec<String> = suggested_order(&results)
+        .iter()
+        .map(|route| format!("{} / {}", route.provider.display_name(), route.model))
+        .collect();
+    if !order.is_empty() {
+        println!("Fastest reliable order: {}", order.join(", "));
+    }
+    Ok(())
+}
+
+/// Draws the table below the cursor, leaving the rest of the terminal be.
+fn draw_table(results: &[BenchResult], theme: &Theme) -> Result<()> {
+    let mut terminal = Terminal::with_options(
+        CrosstermBackend::new(stdout()),
+        TerminalOptions {
+            viewport: Viewport::Inline(results.len() as u16 + 3),
+        },
+    )?;
+    terminal.draw(|frame| {
+        let rows = results.iter().map(|result| {
+            let style = if result.latencies.is_empty() {
+                theme.error_style
+            } else if result.failures > 0 {
+                theme.warning_style
+            } else {
+                theme.success_style
+            };
+            Row::new(table_row(result)).style(style)
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Min(24),
+                Constraint::Length(4),
+                Constraint::Length(7),
+                Constraint::Length(7),
+                Constraint::Length(7),
+                Constraint::Length(5),
+                Constraint::Length(7),
+            ],
+        )
+        .header(Row::new(TABLE_HEADER).style(theme.diff_header_style))
+        .column_spacing(2)
+        .block(
+            theme
+                .block()
+                .borders(Borders::ALL)
+                .border_style(theme.border_style)
+                .title(" BENCH "),
+        );
+        frame.render_widget(table, frame.area());
+    })?;
+    println!();
+    Ok(())
+}
+
+/// `dexter bench --frames`: draws the main view into an in-memory 120x40
+/// terminal as a simulated session grows to eight hours, and prints the
+/// frame times of the log and History views at each checkpoint. They
+/// should stay flat: the log and the History rows are capped, and only
+/// the rows on screen are built.
+pub async fn run_frame_bench() -> Result<()> {
+    let config = Config {
+        theme: "dark".to_string(),
+        output_layout: OutputLayout::Stacked,
+        ..Config::default()
+    };
+    let mut app = App::new(config);
+    app.logs.clear();
+    app.history_items.clear();
+    let mut terminal = Terminal::new(TestBackend::new(120, 40))?;
+
+    println!(
+        "{:<8} {:>9} {:>12} {:>9} {:>9} {:>12} {:>12}",
+        "SESSION", "LOG KEPT", "HISTORY ROWS", "LOG P50", "LOG P99", "HISTORY P50", "HISTORY P99"
+    );
+    let mut hours = 0;
+    for checkpoint in FRAME_CHECKPOINTS {
+        for _ in hours..checkpoint {
+            for line in 0..LOG_LINES_PER_HOUR {
+                app.push_log(format!("Progress line {} of the running job", line));
+            }
+            for run in 0..RUNS_PER_HOUR {
+                let command = format!("ffmpeg -i clip_{run:04}.mov -c:v libx264 clip_{run:04}.mp4");
+                app.history_items.push(HistoryItem {
+                    entry: HistoryEntry::new("ffmpeg", &command),
+                    pinned_at: None,
+                    intent: Some("convert the clips to mp4".to_string()),
+                });
+            }
+            // As reloading the History view keeps only the newest rows.
+            let overflow = app.history_items.len().saturating_sub(HISTORY_ROWS);
+            app.history_items.drain(..overflow);
+        }
+        hours = checkpoint;
+
+        app.state = AppState::Finished("Done.".to_string());
+        let log_view = time_frames(&mut terminal, &mut app)?;
+        app.state = AppState::History;
+        app.history_end();
+        let history_view = time_frames(&mut terminal, &mut app)?;
+        println!(
+            "{:<8} {:>9} {:>12} {:>9} {:>9} {:>12} {:>12}",
+            format!("{}h", checkpoint),
+            app.logs.len(),
+            app.history_items.len(),
+            format_frame_time(percentile(&log_view, 50)),
+            format_frame_time(percentile(&log_view, 99)),
+            format_frame_time(percentile(&history_view, 50)),
+            format_frame_time(percentile(&history_view, 99)),
+        );
+    }
+    Ok(())
+}
+
+/// How long each of `FRAMES` draws of the current view took, sorted.
+fn time_frames(terminal: &mut Terminal<TestBackend>, app: &mut App) -> Result<Vec<Duration>> {
+    let mut times = Vec::with_capacity(FRAMES);
+    for _ in 0..FRAMES {
+        let started = Instant::now();
+        terminal.draw(|f| ui(f, app))?;
+        times.push(started.elapsed());
+    }
+    times.sort();
+    Ok(times)
+}
+
+fn percentile(sorted: &[Duration], pct: usize) -> Duration {
+    sorted[(sorted.len() - 1) * pct / 100]
+}
+
+fn format_frame_time(time: Duration) -> String {
+    format!("{:.2}ms", time.as_secs_f64() * 1000.0)
+}
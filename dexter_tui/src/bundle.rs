@@ -0,0 +1,123 @@
+use anyhow::Result;
+use dexter_core::bundle::{self, BundleSelection, Conflict, ImportConflict, NewSetting};
+
+use crate::cli::{BundleAction, ConflictChoice};
+use crate::quick::prompt_line;
+
+pub async fn run_bundle(action: BundleAction) -> Result<()> {
+    match action {
+        BundleAction::Export {
+            path,
+            recipes,
+            plugins,
+            no_pins,
+        } => {
+            let selection = BundleSelection {
+                recipes,
+                plugins,
+                pins: !no_pins,
+            };
+            let bundle = bundle::export_bundle(&path, &selection).await?;
+            println!(
+                "Wrote {} recipe(s), {} pin(s) and {} preset(s) to {}.",
+                bundle.recipes.len(),
+                bundle.pins.len(),
+                bundle.plugins.len() + bundle.pandoc_presets.len(),
+                path.display()
+            );
+        }
+        BundleAction::Import {
+            path,
+            on_conflict,
+            accept_settings,
+            dry_run,
+        } => {
+            // A dry run lists the conflicts and new settings an import
+            // would ask about.
+            let mut open = Vec::new();
+            let mut unapproved = Vec::new();
+            let mut report = bundle::import_bundle(
+                &path,
+                |conflict| match on_conflict {
+                    ConflictChoice::Ask if dry_run => {
+                        open.push(conflict.key.clone());
+                        ImportConflict::Skip
+                    }
+                    ConflictChoice::Ask => ask(conflict),
+                    ConflictChoice::Skip => ImportConflict::Skip,
+                    ConflictChoice::Replace => ImportConflict::Replace,
+                    ConflictChoice::Rename => ImportConflict::Rename,
+                },
+                |setting| {
+                    if accept_settings {
+                        true
+                    } else if dry_run {
+                        unapproved.push(setting.key.clone());
+                        false
+                    } else {
+                        approve(setting)
+                    }
+                },
+                dry_run,
+            )
+            .await?;
+            report
+                .skipped
+                .retain(|key| !open.contains(key) && !unapproved.contains(key));
+            println!("{}", report.summary());
+            for key in &open {
+                println!("Conflict: {} (asked on import)", key);
+            }
+            for key in &unapproved {
+                println!("New: {} (asked on import)", key);
+            }
+            if dry_run {
+                println!("Dry run: nothing was written.");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Shows plugin settings or a preset the bundle adds and asks whether to
+/// add them. Without a terminal to ask on, they are left out.
+fn approve(setting: &NewSetting) -> bool {
+    println!("New: {}", setting.key);
+    for line in setting.incoming.lines() {
+        println!("    {}", line);
+    }
+    matches!(
+        prompt_line("[a]dd or [s]kip? [s] ")
+            .as_deref()
+            .map(str::to_lowercase)
+            .as_deref(),
+        Ok("a" | "add")
+    )
+}
+
+/// Shows both versions of a conflicting entry and asks which to keep.
+/// Without a terminal to ask on, the local one stays.
+fn ask(conflict: &Conflict) -> ImportConflict {
+    println!("Conflict: {}", conflict.key);
+    for (label, text) in [("here", &conflict.local), ("bundle", &conflict.incoming)] {
+        println!("  {}:", label);
+        for line in text.lines() {
+            println!("    {}", line);
+        }
+    }
+    let renames = !conflict.key.starts_with("plugin ");
+    let prompt = if renames {
+        "[s]kip, [r]eplace or keep [b]oth? [s] "
+    } else {
+        "[s]kip or [r]eplace? [s] "
+    };
+    match prompt_line(prompt)
+        .as_deref()
+        .map(str::to_lowercase)
+        .as_deref()
+    {
+        Ok("r" | "replace") => ImportConflict::Replace,
+        Ok("b" | "both") if renames => ImportConflict::Rename,
+        _ => ImportConflict::Skip,
+    }
+}
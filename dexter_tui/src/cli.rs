@@ -0,0 +1,361 @@
+use anyhow::Result;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use dexter_core::bench::DEFAULT_ROUNDS;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Natural-language front end for ffmpeg, pandoc, f2 and friends.
+///
+/// Without a subcommand Dexter opens the terminal UI.
+#[derive(Debug, Parser)]
+#[command(name = "dexter", version, about, long_about = None)]
+pub struct Cli {
+    /// Open the setup wizard before the UI, even when a provider is configured
+    #[arg(long)]
+    pub setup: bool,
+
+    /// Open straight into the History view
+    #[arg(long, conflicts_with = "recipe")]
+    pub history: bool,
+
+    /// Load the intent template with this name (`convert-media` matches
+    /// "Convert media") and submit it when it has no placeholders
+    #[arg(long, value_name = "NAME")]
+    pub recipe: Option<String>,
+
+    /// Route, generate and preview commands but never run them, for demos
+    /// and machines you don't trust Dexter with
+    #[arg(long, global = true)]
+    pub read_only: bool,
+
+    /// Write this session to FILE for a bug report: input, model replies,
+    /// scans and run output, with API keys left out
+    #[arg(long, value_name = "FILE")]
+    pub record_session: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CliCommand {
+    /// Keep config, model clients and context scans warm for `dexter quick`
+    Daemon,
+    /// Propose, confirm and run one command without opening the UI; file
+    /// names piped to stdin replace the scan of the current directory
+    #[command(visible_alias = "run")]
+    Quick {
+        /// What to do; prompted for when omitted. A `-` reads the file
+        /// names from stdin, which is otherwise only read without an intent
+        #[arg(trailing_var_arg = true)]
+        intent: Vec<String>,
+    },
+    /// Route, generate and dry-run a command for the current directory
+    /// without executing it
+    Preview {
+        /// What to do
+        #[arg(required = true)]
+        intent: Vec<String>,
+        /// Print one JSON document (plugin, command, diff, risk) instead of text
+        #[arg(long)]
+        json: bool,
+    },
+    /// Apply one intent to several directories
+    Batch {
+        /// What to do in every directory
+        intent: String,
+        /// Directories to process (default: non-hidden subdirectories of the current one)
+        dirs: Vec<String>,
+    },
+    /// List, restore or purge originals staged for the current directory
+    Trash {
+        #[command(subcommand)]
+        action: Option<TrashAction>,
+    },
+    /// Refresh executor docs from the installed tools' help
+    Docs {
+        #[command(subcommand)]
+        action: DocsAction,
+    },
+    /// Merge settings and pins with the copy in `sync.remote` (git or
+    /// WebDAV) and update both
+    Sync {
+        /// Side that wins keys changed on both (default: `sync.prefer`)
+        #[arg(long, value_enum)]
+        prefer: Option<SyncSide>,
+        /// Show what would change without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Share recipes, pinned commands and plugin presets as one file
+    Bundle {
+        #[command(subcommand)]
+        action: BundleAction,
+    },
+    /// Replace this binary with the latest GitHub release, or print the
+    /// command that installs it where no binary is published
+    Update {
+        /// Only say whether a newer release exists
+        #[arg(long)]
+        check: bool,
+    },
+    /// Time a standard prompt set on every configured route and report
+    /// latency percentiles, failure rate and tokens per second
+    Bench {
+        /// Times each prompt is sent to every route
+        #[arg(long, default_value_t = DEFAULT_ROUNDS)]
+        rounds: usize,
+        /// Print an aligned text table instead of drawing one
+        #[arg(long)]
+        plain: bool,
+        /// Time drawing the UI over a simulated eight-hour session instead;
+        /// needs no provider
+        #[arg(long)]
+        frames: bool,
+    },
+    /// Drive the UI through a session recorded with --record-session,
+    /// reading model replies, scans and run output from the log
+    Replay {
+        /// The session log
+        log: PathBuf,
+    },
+    /// Print a shell completion script
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Print the man page in roff format
+    Man,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SyncSide {
+    Local,
+    Remote,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BundleAction {
+    /// Write recipes, pins and plugin presets to a TOML file (JSON when
+    /// the name ends in .json)
+    Export {
+        path: PathBuf,
+        /// Recipe to include, repeatable (default: every recipe)
+        #[arg(long = "recipe", value_name = "NAME")]
+        recipes: Vec<String>,
+        /// Only this plugin's pins and presets, repeatable (default: all)
+        #[arg(long = "plugin", value_name = "NAME")]
+        plugins: Vec<String>,
+        /// Leave the pinned commands out
+        #[arg(long)]
+        no_pins: bool,
+    },
+    /// Add a bundle's recipes, pins and presets to this machine
+    Import {
+        path: PathBuf,
+        /// What to do with an entry that exists here with other contents
+        #[arg(long, value_enum, default_value_t = ConflictChoice::Ask)]
+        on_conflict: ConflictChoice,
+        /// Add new plugin settings and pandoc presets without asking
+        #[arg(long)]
+        accept_settings: bool,
+        /// Show what would change without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ConflictChoice {
+    /// Ask for each conflict (skip when there is no terminal)
+    Ask,
+    /// Keep the local entry
+    Skip,
+    /// Take the bundle's entry
+    Replace,
+    /// Keep both, the bundle's under a new name
+    Rename,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TrashAction {
+    /// Show the staging batches in the current directory (the default)
+    List,
+    /// Move a batch's files back to their old names
+    Restore {
+        /// Batch to restore (default: the newest)
+        id: Option<String>,
+    },
+    /// Delete batches older than `trash.keep_days`
+    Purge {
+        /// Delete every batch regardless of age
+        #[arg(long)]
+        all: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DocsAction {
+    /// Summarize each tool's --help with the executor model and cache the
+    /// result for the installed version
+    Sync {
+        /// Plugins to refresh (default: every installed one)
+        plugins: Vec<String>,
+    },
+}
+
+impl CliCommand {
+    /// What this subcommand would do that `--read-only` rules out: run a
+    /// program or change files outside the preview.
+    pub fn refused_when_read_only(&self) -> Option<&'static str> {
+        match self {
+            CliCommand::Update { check: false } => Some("updating Dexter"),
+            CliCommand::Trash {
+                action: Some(TrashAction::Restore { .. } | TrashAction::Purge { .. }),
+            } => Some("restoring or purging the trash"),
+            CliCommand::Sync { dry_run: false, .. } => Some("syncing"),
+            _ => None,
+        }
+    }
+}
+
+pub fn write_completions(shell: Shell, out: &mut dyn Write) {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, out);
+}
+
+pub fn write_man_page(out: &mut dyn Write) -> Result<()> {
+    clap_mangen::Man::new(Cli::command()).render(out)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_subcommands_and_generates_docs() {
+        Cli::command().debug_assert();
+
+        let cli = Cli::parse_from(["dexter", "quick", "shrink", "this", "video"]);
+        let Some(CliCommand::Quick { intent }) = cli.command else {
+            panic!("expected quick");
+        };
+        assert_eq!(intent.join(" "), "shrink this video");
+        let cli = Cli::parse_from(["dexter", "preview", "number the photos", "--json"]);
+        let Some(CliCommand::Preview { intent, json }) = cli.command else {
+            panic!("expected preview");
+        };
+        assert_eq!(
+            (intent.join(" ").as_str(), json),
+            ("number the photos", true)
+        );
+        let cli = Cli::parse_from(["dexter", "run", "convert these to mp4"]);
+        assert!(matches!(cli.command, Some(CliCommand::Quick { .. })));
+        assert!(Cli::parse_from(["dexter", "--setup"]).setup);
+        assert!(Cli::parse_from(["dexter", "--history"]).history);
+        let cli = Cli::parse_from(["dexter", "--record-session", "bug.jsonl"]);
+        assert_eq!(cli.record_session, Some(PathBuf::from("bug.jsonl")));
+        assert!(matches!(
+            Cli::parse_from(["dexter", "replay", "bug.jsonl"]).command,
+            Some(CliCommand::Replay { .. })
+        ));
+        assert!(Cli::parse_from(["dexter", "quick", "--read-only", "shrink"]).read_only);
+        let refused = |args: &[&str]| {
+            Cli::parse_from(args)
+                .command
+                .and_then(|command| command.refused_when_read_only())
+        };
+        assert_eq!(refused(&["dexter", "update"]), Some("updating Dexter"));
+        assert_eq!(refused(&["dexter", "update", "--check"]), None);
+        assert_eq!(refused(&["dexter", "sync"]), Some("syncing"));
+        assert_eq!(refused(&["dexter", "sync", "--dry-run"]), None);
+        assert!(refused(&["dexter", "trash", "restore"]).is_some());
+        assert_eq!(refused(&["dexter", "trash"]), None);
+        let cli = Cli::parse_from(["dexter", "--recipe", "convert-mp3"]);
+        assert_eq!(cli.recipe.as_deref(), Some("convert-mp3"));
+        assert!(Cli::try_parse_from(["dexter", "--history", "--recipe", "x"]).is_err());
+        let cli = Cli::parse_from(["dexter", "trash", "purge", "--all"]);
+        assert!(matches!(
+            cli.command,
+            Some(CliCommand::Trash {
+                action: Some(TrashAction::Purge { all: true })
+            })
+        ));
+
+        let cli = Cli::parse_from(["dexter", "docs", "sync", "ffmpeg"]);
+        let Some(CliCommand::Docs {
+            action: DocsAction::Sync { plugins },
+        }) = cli.command
+        else {
+            panic!("expected docs sync");
+        };
+        assert_eq!(plugins, ["ffmpeg"]);
+        let cli = Cli::parse_from(["dexter", "sync", "--prefer", "remote", "--dry-run"]);
+        assert!(matches!(
+            cli.command,
+            Some(CliCommand::Sync {
+                prefer: Some(SyncSide::Remote),
+                dry_run: true
+            })
+        ));
+
+        let cli = Cli::parse_from([
+            "dexter",
+            "bundle",
+            "export",
+            "team.toml",
+            "--recipe",
+            "Shrink",
+            "--no-pins",
+        ]);
+        let Some(CliCommand::Bundle {
+            action: BundleAction::Export {
+                recipes, no_pins, ..
+            },
+        }) = cli.command
+        else {
+            panic!("expected bundle export");
+        };
+        assert_eq!(
+            (recipes.as_slice(), no_pins),
+            (["Shrink".to_string()].as_slice(), true)
+        );
+        let cli = Cli::parse_from(["dexter", "bundle", "import", "team.toml"]);
+        assert!(matches!(
+            cli.command,
+            Some(CliCommand::Bundle {
+                action: BundleAction::Import {
+                    on_conflict: ConflictChoice::Ask,
+                    accept_settings: false,
+                    dry_run: false,
+                    ..
+                }
+            })
+        ));
+
+        let cli = Cli::parse_from(["dexter", "update", "--check"]);
+        assert!(matches!(
+            cli.command,
+            Some(CliCommand::Update { check: true })
+        ));
+        let cli = Cli::parse_from(["dexter", "bench", "--rounds", "5", "--plain"]);
+        assert!(matches!(
+            cli.command,
+            Some(CliCommand::Bench {
+                rounds: 5,
+                plain: true,
+                frames: false
+            })
+        ));
+
+        let mut script = Vec::new();
+        write_completions(Shell::Bash, &mut script);
+        assert!(String::from_utf8_lossy(&script).contains("completions"));
+        let mut man = Vec::new();
+        write_man_page(&mut man).unwrap();
+        assert!(String::from_utf8_lossy(&man).starts_with(".ie"));
+    }
+}
@@ -0,0 +1,55 @@
+use anyhow::{anyhow, Result};
+use dexter_core::docs::sync_doc;
+use dexter_core::{Config, LlmClient};
+
+use crate::app::state::builtin_plugins;
+use crate::cli::DocsAction;
+
+pub async fn run_docs(action: DocsAction) -> Result<()> {
+    match action {
+        DocsAction::Sync { plugins: names } => {
+            let config = Config::load().await?;
+            if !config.has_keys() {
+                return Err(anyhow!(
+                    "No provider is configured. Run `dexter --setup` first."
+                ));
+            }
+            let llm = LlmClient::with_routes(
+                config.llm_providers(),
+                config.models.executor_routes.clone(),
+                config.models.executor_model.clone(),
+                config.models.executor_fallback_models.clone(),
+            )
+            .with_spend_tracking(config.pricing.clone());
+            let plugins = builtin_plugins(&config);
+            if let Some(unknown) = names
+                .iter()
+                .find(|name| !plugins.iter().any(|p| p.name() == name.as_str()))
+            {
+                return Err(anyhow!("Unknown plugin: {}", unknown));
+            }
+
+            let mut failed = 0;
+            for plugin in &plugins {
+                if names.is_empty() {
+                    if plugin.built_in() || !plugin.is_installed().await {
+                        continue;
+                    }
+                } else if !names.iter().any(|name| name == plugin.name()) {
+                    continue;
+                }
+                match sync_doc(plugin.as_ref(), &llm).await {
+                    Ok(path) => println!("{}: {}", plugin.name(), path.display()),
+                    Err(e) => {
+                        failed += 1;
+                        eprintln!("{}: {}", plugin.name(), e);
+                    }
+                }
+            }
+            if failed > 0 {
+                return Err(anyhow!("{} plugin(s) could not be synced", failed));
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,9 +1,21 @@
 mod app;
+mod batch;
+mod bench;
+mod bundle;
+mod cli;
+mod docs;
+mod preview;
+mod quick;
+mod scheduled;
 mod setup;
+mod sync;
 mod theme;
+mod trash;
 mod ui;
+mod update;
 
 use anyhow::{anyhow, Result};
+use clap::Parser;
 use crossterm::{
     event::{
         DisableMouseCapture, EnableMouseCapture, KeyboardEnhancementFlags,
@@ -12,23 +24,77 @@ use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use dexter_core::Config;
+use dexter_core::{Config, StartupMode};
 use ratatui::Terminal;
 use std::io::{stdin, stdout, IsTerminal};
 
+use crate::app::actions::apply_startup;
+use crate::app::replay::prepare_replay;
 use crate::app::runtime::run_app;
 use crate::app::state::App;
-use crate::setup::runtime::run_setup_wizard;
+use crate::cli::{Cli, CliCommand};
+use crate::setup::runtime::{run_settings_panel_for_issues, run_setup_wizard};
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    // A replay runs in a home of its own, its log file included.
+    let replay = match &cli.command {
+        Some(CliCommand::Replay { log }) => Some(prepare_replay(log)?),
+        _ => None,
+    };
+    app::telemetry::init();
+    if cli.read_only {
+        if let Some(refused) = cli
+            .command
+            .as_ref()
+            .and_then(CliCommand::refused_when_read_only)
+        {
+            return Err(anyhow!("--read-only: {} is off", refused));
+        }
+    }
+    match cli.command {
+        Some(CliCommand::Daemon) => return quick::run_daemon(cli.read_only).await,
+        Some(CliCommand::Quick { intent }) => {
+            return quick::run_quick(&intent, cli.read_only).await
+        }
+        Some(CliCommand::Preview { intent, json }) => {
+            return preview::run_preview(&intent, json).await
+        }
+        Some(CliCommand::Batch { intent, dirs }) => {
+            let args: Vec<String> = std::iter::once(intent).chain(dirs).collect();
+            return batch::run_batch(&args, cli.read_only).await;
+        }
+        Some(CliCommand::Trash { action }) => return trash::run_trash(action).await,
+        Some(CliCommand::Docs { action }) => return docs::run_docs(action).await,
+        Some(CliCommand::Sync { prefer, dry_run }) => return sync::run_sync(prefer, dry_run).await,
+        Some(CliCommand::Bundle { action }) => return bundle::run_bundle(action).await,
+        Some(CliCommand::Update { check }) => return update::run_update(check).await,
+        Some(CliCommand::Bench { frames: true, .. }) => return bench::run_frame_bench().await,
+        Some(CliCommand::Bench { rounds, plain, .. }) => {
+            return bench::run_bench(rounds, plain).await
+        }
+        Some(CliCommand::Completions { shell }) => {
+            cli::write_completions(shell, &mut stdout());
+            return Ok(());
+        }
+        Some(CliCommand::Man) => return cli::write_man_page(&mut stdout()),
+        Some(CliCommand::Replay { .. }) | None => {}
+    }
+
     if !stdin().is_terminal() || !stdout().is_terminal() {
         return Err(anyhow!(
             "Dexter requires an interactive terminal (TTY). Run `dexter` directly in a terminal."
         ));
     }
 
-    let mut config = Config::load().await?;
+    let (mut config, config_issues) = Config::load_validated().await?;
+    // Due jobs wait for a session that may run them.
+    if !cli.read_only {
+        if let Err(e) = scheduled::run_due_jobs(&config).await {
+            eprintln!("Scheduled jobs could not be checked: {}", e);
+        }
+    }
 
     enable_raw_mode()?;
     let mut stdout = stdout();
@@ -46,11 +112,29 @@ async fn main() -> Result<()> {
     let backend = ratatui::backend::CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let args: Vec<String> = std::env::args().collect();
-    let force_setup = args.contains(&"--setup".to_string());
+    let force_setup = cli.setup;
 
-    if !config.has_keys() || force_setup {
-        match run_setup_wizard(&mut terminal, config.clone()).await {
+    let setup = if !config.has_keys() || force_setup {
+        Some(run_setup_wizard(&mut terminal, config.clone(), cli.read_only).await)
+    } else if !config_issues.is_empty() {
+        // A broken config opens the settings on the first problem instead of
+        // refusing to start; leaving them keeps whatever loaded cleanly.
+        if let Ok(fixed) = run_settings_panel_for_issues(
+            &mut terminal,
+            config.clone(),
+            config_issues.clone(),
+            cli.read_only,
+        )
+        .await
+        {
+            config = fixed;
+        }
+        None
+    } else {
+        None
+    };
+    if let Some(setup) = setup {
+        match setup {
             Ok(new_config) => {
                 config = new_config;
             }
@@ -70,6 +154,36 @@ async fn main() -> Result<()> {
     }
 
     let mut app = App::new(config);
+    // A replay shows a session; it never runs anything here.
+    app.read_only = cli.read_only || replay.is_some();
+    app.load_interrupted_runs();
+    if let Some(path) = &cli.record_session {
+        match dexter_core::replay::start_recording(path, &app.config) {
+            Ok(()) => app.push_log(format!("Recording this session to {}", path.display())),
+            Err(e) => app.push_error("replay", format!("Not recording: {:#}", e)),
+        }
+    }
+    if let Some(replay) = replay {
+        app.push_log(format!(
+            "Replaying {} recorded event(s); Esc stops.",
+            replay.total
+        ));
+        app.replay = Some(replay);
+    } else {
+        app.start_update_check();
+    }
+    for issue in &config_issues {
+        app.push_log(format!("config.toml: {}", issue));
+    }
+    let startup = if cli.history {
+        StartupMode::History
+    } else {
+        cli.recipe
+            .map_or_else(|| app.config.startup.clone(), StartupMode::Recipe)
+    };
+    if let Err(e) = apply_startup(&mut app, &startup).await {
+        app.push_error("dexter", format!("Startup failed: {}", e));
+    }
     let res = run_app(&mut terminal, &mut app).await;
 
     disable_raw_mode()?;
@@ -85,6 +199,10 @@ async fn main() -> Result<()> {
     if let Err(err) = res {
         println!("{:?}", err);
     }
+    if let Some(replay) = &app.replay {
+        let _ = std::fs::remove_dir_all(&replay.root);
+        println!("Replayed {} of {} event(s).", replay.sent(), replay.total);
+    }
 
     Ok(())
 }
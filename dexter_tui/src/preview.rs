@@ -0,0 +1,431 @@
+use anyhow::{anyhow, Result};
+use dexter_core::daemon::QuickOption;
+use dexter_core::journal::plan_file_ops;
+use dexter_core::{
+    CollisionChecker, Config, DaemonRequest, DaemonResponse, Invocation, LlmClient, RiskAssessment,
+    Workspace,
+};
+use dexter_plugins::{DiffItem, PreviewContent};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::app::state::builtin_plugins;
+use crate::quick::QuickBackend;
+
+/// What `dexter preview` found out, without running anything.
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum PreviewReport {
+    Ready {
+        intent: String,
+        cwd: String,
+        plugin: String,
+        command: String,
+        /// The process the command would start, flags the plugin adds and
+        /// sandbox wrapper included.
+        argv: Vec<String>,
+        /// Every rename-style row of the preview, sections included.
+        diff: Vec<DiffJson>,
+        /// The whole dry run; `null` when it failed.
+        preview: Option<Value>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        preview_error: Option<String>,
+        risk: RiskAssessment,
+    },
+    /// The command was generated but would not be allowed to run.
+    Blocked {
+        intent: String,
+        cwd: String,
+        plugin: String,
+        command: String,
+        reason: String,
+    },
+    Clarify {
+        intent: String,
+        question: String,
+        options: Vec<QuickOption>,
+    },
+    Unsupported {
+        intent: String,
+        reason: String,
+    },
+    /// No report could be made: no config, no provider, a failed request.
+    Error {
+        intent: String,
+        message: String,
+    },
+}
+
+#[derive(Debug, Serialize)]
+struct DiffJson {
+    original: String,
+    new: String,
+    status: Option<String>,
+}
+
+impl From<&DiffItem> for DiffJson {
+    fn from(item: &DiffItem) -> Self {
+        Self {
+            original: item.original.clone(),
+            new: item.new.clone(),
+            status: item.status.clone(),
+        }
+    }
+}
+
+/// `dexter preview "intent"`: route, generate and dry-run the command for
+/// the current directory, then print the result without executing it;
+/// `--json` prints one JSON document for other tools to read.
+pub async fn run_preview(args: &[String], as_json: bool) -> Result<()> {
+    let intent = args.join(" ").trim().to_string();
+    let report = match load_and_build(&intent).await {
+        Ok(report) => report,
+        // A script reading stdout still gets a document, and the exit
+        // status still says it failed.
+        Err(e) if as_json => {
+            let report = PreviewReport::Error {
+                intent,
+                message: format!("{:#}", e),
+            };
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            return Err(e);
+        }
+        Err(e) => return Err(e),
+    };
+    if as_json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print!("{}", report_text(&report));
+    }
+    Ok(())
+}
+
+async fn load_and_build(intent: &str) -> Result<PreviewReport> {
+    if intent.is_empty() {
+        return Err(anyhow!("No intent given."));
+    }
+    let config = Config::load().await?;
+    build_report(&config, intent).await
+}
+
+async fn build_report(config: &Config, intent: &str) -> Result<PreviewReport> {
+    let backend = QuickBackend::connect(config).await?;
+    let cwd = std::env::current_dir()?;
+    let request = DaemonRequest::Propose {
+        intent: intent.to_string(),
+        cwd: cwd.clone(),
+        files: None,
+    };
+    let (plugin_name, command) = match backend.send(request).await? {
+        DaemonResponse::Command { plugin, command } => (plugin, command),
+        DaemonResponse::Clarify { question, options } => {
+            return Ok(PreviewReport::Clarify {
+                intent: intent.to_string(),
+                question,
+                options,
+            });
+        }
+        DaemonResponse::Unsupported { reason } => {
+            return Ok(PreviewReport::Unsupported {
+                intent: intent.to_string(),
+                reason,
+            });
+        }
+        DaemonResponse::Error { message } => return Err(anyhow!(message)),
+        DaemonResponse::Recorded => return Err(anyhow!("Unexpected daemon response")),
+    };
+
+    let plugins = builtin_plugins(config);
+    let plugin = plugins
+        .iter()
+        .find(|p| p.name() == plugin_name)
+        .ok_or_else(|| anyhow!("Plugin not found: {}", plugin_name))?;
+    let blocked = |reason: String| PreviewReport::Blocked {
+        intent: intent.to_string(),
+        cwd: cwd.display().to_string(),
+        plugin: plugin_name.clone(),
+        command: command.clone(),
+        reason,
+    };
+    // The same checks the TUI runs before its dry run.
+    let guard = Workspace::guard_for(&cwd, &config.safety)?;
+    if let Err(e) = guard
+        .check_plugin(&plugin_name)
+        .and_then(|()| guard.check(&command))
+    {
+        return Ok(blocked(format!("Safety check failed: {}", e)));
+    }
+    if let Err(e) = guard.check_deletes(&plugin_name, &command, config.trash.deletes) {
+        return Ok(blocked(format!("Safety check failed: {}", e)));
+    }
+    if !plugin.validate_command(&command) {
+        return Ok(blocked(
+            "Command failed plugin validation logic".to_string(),
+        ));
+    }
+
+    let llm = LlmClient::with_routes(
+        config.llm_providers(),
+        config.models.executor_routes.clone(),
+        config.models.executor_model.clone(),
+        config.models.executor_fallback_models.clone(),
+    )
+    .with_spend_tracking(config.pricing.clone());
+    let dry_run = config
+        .sandbox
+        .dry_run(plugin.as_ref(), &command, &cwd, Some(&llm));
+    let (preview, preview_error) = match config.metered.preview(plugin.as_ref(), dry_run).await {
+        Ok(preview) => (Some(preview), None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+    let ops = plan_file_ops(&plugin_name, &command, &cwd).await;
+    let collisions = if plugin_name == "f2" {
+        CollisionChecker::check_f2(&command, &cwd)
+            .await
+            .map(|found| found.len())
+            .unwrap_or(0)
+    } else {
+        0
+    };
+    let risk = RiskAssessment::assess(
+        plugin.as_ref(),
+        &command,
+        &ops,
+        preview.as_ref(),
+        collisions,
+        config.trash.deletes,
+    );
+    let argv = Invocation::plan(plugin.as_ref(), &command, &cwd, &config.sandbox, None)
+        .map(|plan| plan.argv)
+        .unwrap_or_else(|_| shell_words::split(&command).unwrap_or_default());
+    let mut diff = Vec::new();
+    if let Some(preview) = &preview {
+        collect_diff(preview, &mut diff);
+    }
+    Ok(PreviewReport::Ready {
+        intent: intent.to_string(),
+        cwd: cwd.display().to_string(),
+        plugin: plugin_name,
+        command,
+        argv,
+        diff,
+        preview: preview.as_ref().map(preview_json),
+        preview_error,
+        risk,
+    })
+}
+
+fn collect_diff(preview: &PreviewContent, out: &mut Vec<DiffJson>) {
+    match preview {
+        PreviewContent::DiffList(items) => out.extend(items.iter().map(DiffJson::from)),
+        PreviewContent::Sections(parts) => {
+            for part in parts {
+                collect_diff(part, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// `preview` as JSON, tagged with its `kind`.
+fn preview_json(preview: &PreviewContent) -> Value {
+    match preview {
+        PreviewContent::Text(text) => json!({ "kind": "text", "text": text }),
+        PreviewContent::DiffList(items) => json!({
+            "kind": "diff",
+            "items": items.iter().map(DiffJson::from).collect::<Vec<_>>(),
+        }),
+        PreviewContent::StreamSelection(selection) => json!({
+            "kind": "streams",
+            "summary": selection.summary,
+            "input": selection.input,
+            "streams": selection
+                .streams
+                .iter()
+                .zip(&selection.selected)
+                .map(|(stream, selected)| json!({
+                    "index": stream.index,
+                    "description": stream.describe(),
+                    "selected": selected,
+                }))
+                .collect::<Vec<_>>(),
+        }),
+        PreviewContent::Table {
+            title,
+            headers,
+            rows,
+        } => json!({ "kind": "table", "title": title, "headers": headers, "rows": rows }),
+        PreviewContent::KeyValue(sections) => json!({
+            "kind": "key_value",
+            "sections": sections
+                .iter()
+                .map(|section| json!({ "title": section.title, "entries": section.entries }))
+                .collect::<Vec<_>>(),
+        }),
+        PreviewContent::Sections(parts) => json!({
+            "kind": "sections",
+            "parts": parts.iter().map(preview_json).collect::<Vec<_>>(),
+        }),
+    }
+}
+
+fn report_text(report: &PreviewReport) -> String {
+    match report {
+        PreviewReport::Ready {
+            plugin,
+            command,
+            preview,
+            preview_error,
+            risk,
+            ..
+        } => {
+            let mut text = format!("[{}] {}\n", plugin, command);
+            text.push_str(&format!("Risk: {:?} ({}/100)\n", risk.level, risk.score));
+            for reason in &risk.reasons {
+                text.push_str(&format!("  - {}\n", reason));
+            }
+            if let Some(e) = preview_error {
+                text.push_str(&format!("Dry run failed: {}\n", e));
+            }
+            if let Some(preview) = preview {
+                push_preview_text(preview, &mut text);
+            }
+            text
+        }
+        PreviewReport::Blocked {
+            plugin,
+            command,
+            reason,
+            ..
+        } => format!("[{}] {}\nBlocked: {}\n", plugin, command, reason),
+        PreviewReport::Clarify {
+            question, options, ..
+        } => {
+            let mut text = format!("{}\n", question);
+            for (i, option) in options.iter().enumerate() {
+                text.push_str(&format!("  {}. {}\n", i + 1, option.label));
+            }
+            text
+        }
+        PreviewReport::Unsupported { reason, .. } => {
+            format!("This request isn't supported.\n{}\n", reason)
+        }
+        PreviewReport::Error { message, .. } => format!("Error: {}\n", message),
+    }
+}
+
+/// The JSON of `preview_json` as plain lines.
+fn push_preview_text(preview: &Value, text: &mut String) {
+    let str_of = |value: &Value| value.as_str().unwrap_or_default().to_string();
+    let list = |value: &Value| value.as_array().cloned().unwrap_or_default();
+    match preview["kind"].as_str() {
+        Some("text") => text.push_str(&format!("{}\n", str_of(&preview["text"]).trim_end())),
+        Some("diff") => {
+            for item in list(&preview["items"]) {
+                text.push_str(&format!(
+                    "{} -> {}\n",
+                    str_of(&item["original"]),
+                    str_of(&item["new"])
+                ));
+            }
+        }
+        Some("streams") => {
+            text.push_str(&format!("{}\n", str_of(&preview["summary"]).trim_end()));
+            for stream in list(&preview["streams"]) {
+                let mark = if stream["selected"] == true { "x" } else { " " };
+                text.push_str(&format!(
+                    "  [{}] {}\n",
+                    mark,
+                    str_of(&stream["description"])
+                ));
+            }
+        }
+        Some("table") => {
+            text.push_str(&format!("{}\n", str_of(&preview["title"])));
+            for row in std::iter::once(&preview["headers"])
+                .chain(preview["rows"].as_array().into_iter().flatten())
+            {
+                let cells: Vec<String> = list(row).iter().map(str_of).collect();
+                text.push_str(&format!("  {}\n", cells.join("  ")));
+            }
+        }
+        Some("key_value") => {
+            for section in list(&preview["sections"]) {
+                text.push_str(&format!("{}\n", str_of(&section["title"])));
+                for entry in list(&section["entries"]) {
+                    text.push_str(&format!("  {}: {}\n", str_of(&entry[0]), str_of(&entry[1])));
+                }
+            }
+        }
+        Some("sections") => {
+            for part in list(&preview["parts"]) {
+                push_preview_text(&part, text);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dexter_core::RiskLevel;
+
+    #[test]
+    fn reports_are_tagged_json() {
+        let report = PreviewReport::Ready {
+            intent: "number the photos".to_string(),
+            cwd: "/photos".to_string(),
+            plugin: "f2".to_string(),
+            command: "f2 -f a -r b".to_string(),
+            argv: vec!["f2".to_string()],
+            diff: vec![DiffJson {
+                original: "a.jpg".to_string(),
+                new: "b.jpg".to_string(),
+                status: None,
+            }],
+            preview: Some(preview_json(&PreviewContent::DiffList(vec![DiffItem {
+                original: "a.jpg".to_string(),
+                new: "b.jpg".to_string(),
+                status: None,
+            }]))),
+            preview_error: None,
+            risk: RiskAssessment {
+                score: 10,
+                level: RiskLevel::Low,
+                reasons: vec!["renames 1 file(s)".to_string()],
+            },
+        };
+        let value = serde_json::to_value(&report).unwrap();
+        assert_eq!(value["status"], "ready");
+        assert_eq!(value["diff"][0]["new"], "b.jpg");
+        assert_eq!(value["preview"]["kind"], "diff");
+        assert_eq!(value["risk"]["level"], "low");
+        assert!(value.get("preview_error").is_none());
+        assert!(report_text(&report).contains("a.jpg -> b.jpg"));
+
+        let report = PreviewReport::Unsupported {
+            intent: "x".to_string(),
+            reason: "no tool".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_string(&report).unwrap(),
+            r#"{"status":"unsupported","intent":"x","reason":"no tool"}"#
+        );
+
+        let report = PreviewReport::Error {
+            intent: "x".to_string(),
+            message: "no providers".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_string(&report).unwrap(),
+            r#"{"status":"error","intent":"x","message":"no providers"}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn an_empty_intent_is_an_error() {
+        let e = load_and_build("").await.unwrap_err();
+        assert_eq!(e.to_string(), "No intent given.");
+    }
+}
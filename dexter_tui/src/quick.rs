@@ -0,0 +1,394 @@
+use anyhow::{anyhow, Result};
+use dexter_core::collision::format_collisions;
+use dexter_core::conflict::{apply_conflict_policy, find_output_conflicts, format_conflicts};
+use dexter_core::daemon::{daemon_available, send_request, socket_path};
+use dexter_core::executor::{record_dir_use, record_plugin_success};
+use dexter_core::in_use::{files_in_use, format_in_use};
+use dexter_core::journal::plan_file_ops;
+use dexter_core::recycle;
+use dexter_core::trash;
+use dexter_core::triage::triage;
+use dexter_core::{
+    AuditEvent, CollisionChecker, Config, ConflictOutcome, Daemon, DaemonRequest, DaemonResponse,
+    MeteredVerdict, RunOutcome, Workspace,
+};
+use dexter_plugins::Progress;
+use std::io::{stdin, stdout, BufRead, BufReader, IsTerminal, Read, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::app::state::builtin_plugins;
+use crate::scheduled::{run_due_jobs, DAEMON_CHECK_INTERVAL};
+
+/// `dexter daemon`: keep config, LLM clients and context scans warm for
+/// `dexter quick` until interrupted.
+pub async fn run_daemon(read_only: bool) -> Result<()> {
+    let config = Config::load().await?;
+    if !config.has_keys() {
+        return Err(anyhow!(
+            "No provider is configured. Run `dexter --setup` first."
+        ));
+    }
+    let socket = socket_path()?;
+    let daemon = Arc::new(Daemon::new(&config, builtin_plugins(&config)));
+    eprintln!("Dexter daemon listening on {}", socket.display());
+
+    let schedule_config = config.clone();
+    let schedule_loop = async move {
+        let mut ticker = tokio::time::interval(DAEMON_CHECK_INTERVAL);
+        loop {
+            ticker.tick().await;
+            if read_only {
+                continue;
+            }
+            if let Err(e) = run_due_jobs(&schedule_config).await {
+                eprintln!("Scheduled jobs could not be checked: {}", e);
+            }
+        }
+    };
+
+    let result = tokio::select! {
+        res = daemon.serve(&socket) => res,
+        _ = schedule_loop => Ok(()),
+        _ = tokio::signal::ctrl_c() => Ok(()),
+    };
+    let _ = std::fs::remove_file(&socket);
+    result
+}
+
+/// How long `dexter run` without an intent waits for piped file names
+/// before scanning the current directory instead.
+const STDIN_WAIT: Duration = Duration::from_secs(1);
+
+/// `dexter quick "intent"`: propose a command via the daemon (or in-process
+/// when none is running), confirm on the terminal, then run it here. A file
+/// list piped to stdin (`fd -e mkv | dexter run "..." -`) is the context in
+/// place of the cwd; prompts then read from the controlling terminal.
+pub async fn run_quick(args: &[String], read_only: bool) -> Result<()> {
+    let from_stdin = args.iter().any(|arg| arg == "-");
+    let args: Vec<&str> = args
+        .iter()
+        .map(String::as_str)
+        .filter(|arg| *arg != "-")
+        .collect();
+    let files = if stdin().is_terminal() {
+        None
+    } else if from_stdin {
+        read_piped_files(None).await?
+    } else if args.is_empty() {
+        read_piped_files(Some(STDIN_WAIT)).await?
+    } else {
+        None
+    };
+    let mut intent = args.join(" ").trim().to_string();
+    if intent.is_empty() {
+        intent = prompt_line("Intent: ")?;
+    }
+    if intent.is_empty() {
+        return Err(anyhow!("No intent given."));
+    }
+
+    let config = Config::load().await?;
+    let backend = QuickBackend::connect(&config).await?;
+    let cwd = std::env::current_dir()?;
+    loop {
+        let request = DaemonRequest::Propose {
+            intent: intent.clone(),
+            cwd: cwd.clone(),
+            files: files.clone(),
+        };
+        match backend.send(request).await? {
+            DaemonResponse::Command { plugin, command } if read_only => {
+                println!("[{}] {}", plugin, command);
+                println!("Not executing: Dexter is in read-only mode.");
+                return Ok(());
+            }
+            DaemonResponse::Command { plugin, command } => {
+                return confirm_and_run(&backend, &config, &intent, &plugin, &command).await;
+            }
+            DaemonResponse::Clarify { question, options } => {
+                println!("{}", question);
+                for (i, option) in options.iter().enumerate() {
+                    println!("  {}. {}", i + 1, option.label);
+                }
+                let choice = prompt_line("Choose an option (empty to cancel): ")?;
+                let Some(option) = choice
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|n| n.checked_sub(1))
+                    .and_then(|idx| options.get(idx))
+                else {
+                    println!("Cancelled.");
+                    return Ok(());
+                };
+                intent = option.resolved_intent.clone();
+            }
+            DaemonResponse::Unsupported { reason } => {
+                return Err(anyhow!("This request isn't supported.\n{}", reason));
+            }
+            DaemonResponse::Error { message } => return Err(anyhow!(message)),
+            DaemonResponse::Recorded => return Err(anyhow!("Unexpected daemon response")),
+        }
+    }
+}
+
+/// The file names piped to stdin; `None` when there are none. With `wait`,
+/// gives up when nothing has arrived by then, so a pipe that was inherited
+/// rather than filled doesn't hang the command.
+async fn read_piped_files(wait: Option<Duration>) -> Result<Option<String>> {
+    let (started_tx, started_rx) = oneshot::channel();
+    let (done_tx, done_rx) = oneshot::channel();
+    // A plain thread: one stuck on an idle pipe mustn't hold up the
+    // runtime's shutdown.
+    std::thread::spawn(move || {
+        let mut input = stdin().lock();
+        let mut piped = Vec::new();
+        let mut chunk = [0u8; 8192];
+        let mut started_tx = Some(started_tx);
+        let read = loop {
+            match input.read(&mut chunk) {
+                Ok(n) => {
+                    if let Some(tx) = started_tx.take() {
+                        let _ = tx.send(());
+                    }
+                    if n == 0 {
+                        break Ok(String::from_utf8_lossy(&piped).into_owned());
+                    }
+                    piped.extend_from_slice(&chunk[..n]);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => break Err(e),
+            }
+        };
+        let _ = done_tx.send(read);
+    });
+    if let Some(wait) = wait {
+        if tokio::time::timeout(wait, started_rx).await.is_err() {
+            return Ok(None);
+        }
+    }
+    let piped = done_rx
+        .await
+        .map_err(|_| anyhow!("Reading stdin stopped"))??;
+    Ok((!piped.trim().is_empty()).then_some(piped))
+}
+
+pub(crate) enum QuickBackend {
+    Daemon(PathBuf),
+    InProcess(Box<Daemon>),
+}
+
+impl QuickBackend {
+    pub(crate) async fn connect(config: &Config) -> Result<Self> {
+        let socket = socket_path()?;
+        if daemon_available(&socket).await {
+            return Ok(Self::Daemon(socket));
+        }
+        eprintln!("No Dexter daemon running; starting cold (run `dexter daemon` to stay warm).");
+        if !config.has_keys() {
+            return Err(anyhow!(
+                "No provider is configured. Run `dexter --setup` first."
+            ));
+        }
+        Ok(Self::InProcess(Box::new(Daemon::new(
+            config,
+            builtin_plugins(config),
+        ))))
+    }
+
+    pub(crate) async fn send(&self, request: DaemonRequest) -> Result<DaemonResponse> {
+        match self {
+            Self::Daemon(socket) => send_request(socket, &request).await,
+            Self::InProcess(daemon) => Ok(daemon.handle(request).await),
+        }
+    }
+}
+
+async fn confirm_and_run(
+    backend: &QuickBackend,
+    config: &Config,
+    intent: &str,
+    plugin_name: &str,
+    command: &str,
+) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let plugins = builtin_plugins(config);
+    let plugin = plugins
+        .iter()
+        .find(|p| p.name() == plugin_name)
+        .ok_or_else(|| anyhow!("Plugin not found: {}", plugin_name))?;
+    // Outputs that exist already are handled as in the TUI, before the
+    // command is shown; `never_overwrite` renames them instead of asking.
+    let conflicts = find_output_conflicts(plugin_name, command, &cwd)?;
+    let policy = config.conflict_policy_for(plugin_name);
+    let command = match apply_conflict_policy(plugin_name, command, conflicts, policy)? {
+        ConflictOutcome::Clear => command.to_string(),
+        ConflictOutcome::Rewritten { command, conflicts } => {
+            eprintln!("{}", format_conflicts(&conflicts));
+            command
+        }
+        ConflictOutcome::Skip { conflicts } => {
+            println!("Skipped. {}", format_conflicts(&conflicts));
+            return Ok(());
+        }
+        ConflictOutcome::Ask { conflicts } => {
+            eprintln!(
+                "{}\nRunning the command overwrites them.",
+                format_conflicts(&conflicts)
+            );
+            command.to_string()
+        }
+    };
+    let command = command.as_str();
+
+    println!("[{}] {}", plugin_name, command);
+    if terminal_input().is_none() {
+        println!("Not executing: confirmation needs an interactive terminal.");
+        return Ok(());
+    }
+    let answer = prompt_line("Run this command? [y/N] ")?;
+    if !matches!(answer.to_lowercase().as_str(), "y" | "yes") {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    // The daemon already validated the command, but it may be an older
+    // build; the safety rules and the plugin's own validation run again
+    // here, with this directory's workspace settings.
+    let guard = Workspace::guard_for(&cwd, &config.safety)?;
+    guard
+        .check_plugin(plugin_name)
+        .and_then(|()| guard.check(command))
+        .map_err(|e| anyhow!("Safety check failed: {}", e))?;
+    let trash_tool = guard
+        .check_deletes(plugin_name, command, config.trash.deletes)
+        .map_err(|e| anyhow!("Safety check failed: {}", e))?;
+    if !plugin.validate_command(command) {
+        return Err(anyhow!("Command failed plugin validation logic"));
+    }
+    match config.metered.check(plugin.as_ref()) {
+        MeteredVerdict::Block => {
+            let answer = prompt_line("The connection is metered. Download anyway? [y/N] ")?;
+            if !matches!(answer.to_lowercase().as_str(), "y" | "yes") {
+                println!("Cancelled.");
+                return Ok(());
+            }
+        }
+        MeteredVerdict::Warn => eprintln!("Warning: downloading over a metered connection."),
+        MeteredVerdict::Clear => {}
+    }
+    if plugin_name == "f2" {
+        match CollisionChecker::check_f2(command, &cwd).await {
+            Ok(collisions) if !collisions.is_empty() => {
+                return Err(anyhow!(format_collisions(&collisions)));
+            }
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("Collision simulation unavailable: {}", e);
+                let answer = prompt_line("Rename without the collision check? [y/N] ")?;
+                if !matches!(answer.to_lowercase().as_str(), "y" | "yes") {
+                    println!("Cancelled.");
+                    return Ok(());
+                }
+            }
+        }
+        if config.trash.stage_f2_overwrites {
+            let ops = plan_file_ops(plugin_name, command, &cwd).await;
+            let staged = trash::stage_overwrites(&config.trash, plugin_name, command, &cwd, &ops)
+                .map_err(|e| anyhow!("Soft-delete staging failed: {}", e))?;
+            if !staged.is_empty() {
+                eprintln!("Staged {} file(s) in the Dexter trash.", staged.len());
+            }
+        }
+    }
+    let in_use = files_in_use(plugin_name, command, &cwd).await;
+    if !in_use.is_empty() {
+        eprintln!("{}", format_in_use(&in_use));
+        let answer = prompt_line("Run anyway? [y/N] ")?;
+        if !matches!(answer.to_lowercase().as_str(), "y" | "yes") {
+            println!("Cancelled.");
+            return Ok(());
+        }
+    }
+    if plugin.confirm_twice(command) {
+        let answer = prompt_line("This command removes files. Run it? [y/N] ")?;
+        if !matches!(answer.to_lowercase().as_str(), "y" | "yes") {
+            println!("Cancelled.");
+            return Ok(());
+        }
+    }
+
+    let (progress_tx, mut progress_rx) = mpsc::channel::<Progress>(64);
+    let printer = tokio::spawn(async move {
+        while let Some(progress) = progress_rx.recv().await {
+            eprintln!("{}", progress.message);
+        }
+    });
+    let event = AuditEvent {
+        plugin: plugin_name,
+        command,
+        cwd: &cwd,
+        host: None,
+    };
+    config.audit.record_start(&event);
+    let started = Instant::now();
+    let result = recycle::execute(
+        trash_tool,
+        &config.sandbox,
+        plugin.as_ref(),
+        command,
+        &cwd,
+        progress_tx,
+    )
+    .await
+    .map(|(output, _)| output);
+    let _ = printer.await;
+    config.audit.record(&event, &result);
+
+    // Failed runs are recorded too, so the history can tell them apart.
+    let record = DaemonRequest::Record {
+        plugin: plugin_name.to_string(),
+        command: command.to_string(),
+        outcome: Some(RunOutcome::from_result(started.elapsed(), &result)),
+        intent: Some(intent.to_string()),
+    };
+    if let Ok(DaemonResponse::Error { message }) = backend.send(record).await {
+        eprintln!("Failed to record history: {}", message);
+    }
+    if let Err(e) = &result {
+        if let Some(hint) = triage(Some(plugin_name), &e.to_string()) {
+            eprintln!("Likely cause: {}\nFix: {}", hint.problem, hint.fix);
+            if let Some(argv) = hint.install {
+                eprintln!("Install: {}", argv.join(" "));
+            }
+        }
+    }
+    let output = result?;
+    println!("{}", output.trim_end());
+    let _ = record_plugin_success(plugin_name).await;
+    let _ = record_dir_use(&cwd, plugin_name).await;
+    Ok(())
+}
+
+pub(crate) fn prompt_line(prompt: &str) -> Result<String> {
+    print!("{}", prompt);
+    stdout().flush()?;
+    let mut input =
+        terminal_input().ok_or_else(|| anyhow!("No interactive terminal to read from."))?;
+    let mut line = String::new();
+    input.read_line(&mut line)?;
+    Ok(line.trim().to_string())
+}
+
+/// Where answers come from: stdin, or the controlling terminal when stdin
+/// carries piped input.
+fn terminal_input() -> Option<Box<dyn BufRead>> {
+    if stdin().is_terminal() {
+        return Some(Box::new(stdin().lock()));
+    }
+    let tty = if cfg!(windows) { "CONIN$" } else { "/dev/tty" };
+    let file = std::fs::File::open(tty).ok()?;
+    Some(Box::new(BufReader::new(file)))
+}
@@ -0,0 +1,46 @@
+use anyhow::Result;
+use dexter_core::{AuditEvent, Config, Executor, LlmClient, Scheduler};
+use std::time::Duration;
+
+use crate::app::state::builtin_plugins;
+
+/// How often `dexter daemon` looks for scheduled jobs that came due.
+pub const DAEMON_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Runs every scheduled job whose time has passed, printing each result.
+/// Called before the TUI starts and periodically by the daemon.
+pub async fn run_due_jobs(config: &Config) -> Result<()> {
+    let scheduler = Scheduler::new()?;
+    let plugins = builtin_plugins(config);
+    // History recording is all the executor is needed for here.
+    let executor = Executor::new(
+        LlmClient::with_routes(
+            config.llm_providers(),
+            config.models.executor_routes.clone(),
+            config.models.executor_model.clone(),
+            config.models.executor_fallback_models.clone(),
+        )
+        .with_spend_tracking(config.pricing.clone()),
+    );
+    for (job, result) in scheduler.run_due(&plugins, &executor, config).await? {
+        let event = AuditEvent {
+            plugin: &job.plugin,
+            command: &job.command,
+            cwd: &job.cwd,
+            host: None,
+        };
+        config.audit.record(&event, &result);
+        eprintln!(
+            "Scheduled job for {} ({}): {}",
+            job.run_at_label(),
+            job.cwd.display(),
+            job.command
+        );
+        match result {
+            Ok(output) if !output.trim().is_empty() => eprintln!("{}", output.trim_end()),
+            Ok(_) => eprintln!("Done."),
+            Err(e) => eprintln!("Failed: {}", e),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,342 @@
+//! API keys already on this machine: the providers' environment variables
+//! and the key stores of llm, aider and Continue. The import step offers
+//! them so a first run doesn't start with copying keys by hand.
+
+use dexter_core::ProviderKind;
+use std::path::{Path, PathBuf};
+
+/// Environment variables each provider's own SDK reads.
+const ENV_VARS: &[(&str, ProviderKind)] = &[
+    ("OPENAI_API_KEY", ProviderKind::OpenAI),
+    ("ANTHROPIC_API_KEY", ProviderKind::Anthropic),
+    ("GEMINI_API_KEY", ProviderKind::Gemini),
+    ("GOOGLE_API_KEY", ProviderKind::Gemini),
+    ("OPENROUTER_API_KEY", ProviderKind::OpenRouter),
+    ("DEEPSEEK_API_KEY", ProviderKind::Deepseek),
+    ("GROQ_API_KEY", ProviderKind::Groq),
+    ("MOONSHOT_API_KEY", ProviderKind::Moonshot),
+    ("BASETEN_API_KEY", ProviderKind::Baseten),
+];
+
+/// aider's own spellings of `--openai-api-key` and `--anthropic-api-key`.
+const AIDER_ENV_VARS: &[(&str, ProviderKind)] = &[
+    ("AIDER_OPENAI_API_KEY", ProviderKind::OpenAI),
+    ("AIDER_ANTHROPIC_API_KEY", ProviderKind::Anthropic),
+];
+
+/// aider's `--api-key` as a variable: `gemini=KEY,groq=KEY`.
+const AIDER_API_KEY: &str = "AIDER_API_KEY";
+
+/// Reads one `name=KEY` item of an aider option.
+type KeyPair = fn(&str) -> Option<(ProviderKind, String)>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoundKey {
+    pub kind: ProviderKind,
+    pub api_key: String,
+    /// Where it was found: the variable or file, and the tool.
+    pub source: String,
+}
+
+/// Where to look: the environment, the home directory and the config
+/// directory; split out so tests don't read the real ones.
+pub struct KeySources<'a> {
+    pub env: &'a dyn Fn(&str) -> Option<String>,
+    pub home: Option<PathBuf>,
+    pub config_dir: Option<PathBuf>,
+}
+
+impl KeySources<'_> {
+    pub fn system() -> KeySources<'static> {
+        KeySources {
+            env: &|name| std::env::var(name).ok(),
+            home: dexter_core::paths::home_dir(),
+            config_dir: dexter_core::paths::config_dir(),
+        }
+    }
+}
+
+/// Every key found, environment first; a key found twice for the same
+/// provider is listed once.
+pub fn find_keys(sources: &KeySources) -> Vec<FoundKey> {
+    let mut found = Vec::new();
+    for (var, kind) in ENV_VARS.iter().chain(AIDER_ENV_VARS) {
+        if let Some(key) = (sources.env)(var) {
+            push_key(&mut found, *kind, &key, format!("${}", var));
+        }
+    }
+    if let Some(value) = (sources.env)(AIDER_API_KEY) {
+        for (kind, key) in value.split(',').filter_map(provider_pair) {
+            push_key(&mut found, kind, &key, format!("${}", AIDER_API_KEY));
+        }
+    }
+
+    let llm_dir = (sources.env)("LLM_USER_PATH")
+        .map(PathBuf::from)
+        .or_else(|| Some(sources.config_dir.as_ref()?.join("io.datasette.llm")));
+    if let Some(path) = llm_dir.map(|dir| dir.join("keys.json")) {
+        for (name, key) in llm_keys(&read(&path)) {
+            if let Some(kind) = provider_named(&name) {
+                push_key(&mut found, kind, &key, source(&path, "llm"));
+            }
+        }
+    }
+
+    if let Some(home) = &sources.home {
+        let path = home.join(".aider.conf.yml");
+        for (kind, key) in aider_keys(&read(&path)) {
+            push_key(&mut found, kind, &key, source(&path, "aider"));
+        }
+        let path = home.join(".env");
+        for (kind, key) in dotenv_keys(&read(&path)) {
+            push_key(&mut found, kind, &key, source(&path, "aider"));
+        }
+        let path = home.join(".continue").join("config.json");
+        for (name, key) in continue_json_keys(&read(&path)) {
+            if let Some(kind) = provider_named(&name) {
+                push_key(&mut found, kind, &key, source(&path, "Continue"));
+            }
+        }
+        let path = home.join(".continue").join("config.yaml");
+        for (name, key) in continue_yaml_keys(&read(&path)) {
+            if let Some(kind) = provider_named(&name) {
+                push_key(&mut found, kind, &key, source(&path, "Continue"));
+            }
+        }
+    }
+    found
+}
+
+fn push_key(found: &mut Vec<FoundKey>, kind: ProviderKind, key: &str, source: String) {
+    let key = unquote(key);
+    // Templates such as `${{ secrets.OPENAI_KEY }}` point elsewhere.
+    if key.is_empty() || key.contains("${") {
+        return;
+    }
+    if found.iter().any(|f| f.kind == kind && f.api_key == key) {
+        return;
+    }
+    found.push(FoundKey {
+        kind,
+        api_key: key.to_string(),
+        source,
+    });
+}
+
+fn read(path: &Path) -> String {
+    std::fs::read_to_string(path).unwrap_or_default()
+}
+
+fn source(path: &Path, tool: &str) -> String {
+    format!("{} ({})", path.display(), tool)
+}
+
+/// The provider a tool's name for it stands for.
+fn provider_named(name: &str) -> Option<ProviderKind> {
+    Some(match name.trim().to_lowercase().as_str() {
+        "openai" => ProviderKind::OpenAI,
+        "anthropic" | "claude" => ProviderKind::Anthropic,
+        "gemini" | "google" => ProviderKind::Gemini,
+        "openrouter" => ProviderKind::OpenRouter,
+        "deepseek" => ProviderKind::Deepseek,
+        "groq" => ProviderKind::Groq,
+        "moonshot" => ProviderKind::Moonshot,
+        "baseten" => ProviderKind::Baseten,
+        _ => return None,
+    })
+}
+
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+        .unwrap_or(value)
+}
+
+/// llm's `keys.json`: key names to keys.
+fn llm_keys(text: &str) -> Vec<(String, String)> {
+    let Ok(serde_json::Value::Object(map)) = serde_json::from_str(text) else {
+        return Vec::new();
+    };
+    map.into_iter()
+        .filter_map(|(name, key)| Some((name, key.as_str()?.to_string())))
+        .collect()
+}
+
+/// `openai-api-key:`, `anthropic-api-key:`, the `api-key:` entries
+/// (`gemini=...`) and the provider variables under `set-env:`
+/// (`GOOGLE_API_KEY=...`) of `.aider.conf.yml`.
+fn aider_keys(text: &str) -> Vec<(ProviderKind, String)> {
+    let mut keys = Vec::new();
+    let mut list: Option<KeyPair> = None;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if let Some((item, parse)) = trimmed.strip_prefix("- ").zip(list) {
+            keys.extend(parse(item));
+            continue;
+        }
+        list = None;
+        let Some((name, value)) = trimmed.split_once(':') else {
+            continue;
+        };
+        let parse: KeyPair = match name.trim() {
+            "openai-api-key" => {
+                keys.push((ProviderKind::OpenAI, value.to_string()));
+                continue;
+            }
+            "anthropic-api-key" => {
+                keys.push((ProviderKind::Anthropic, value.to_string()));
+                continue;
+            }
+            "api-key" => provider_pair,
+            "set-env" => env_pair,
+            _ => continue,
+        };
+        let value = value.trim();
+        if value.is_empty() {
+            list = Some(parse);
+        } else {
+            let value = value.trim_start_matches('[').trim_end_matches(']');
+            keys.extend(value.split(',').filter_map(parse));
+        }
+    }
+    keys
+}
+
+/// `gemini=KEY` as aider's `--api-key` takes it.
+fn provider_pair(item: &str) -> Option<(ProviderKind, String)> {
+    let (name, key) = unquote(item).split_once('=')?;
+    Some((provider_named(name)?, key.to_string()))
+}
+
+/// `GOOGLE_API_KEY=KEY` as aider's `--set-env` takes it.
+fn env_pair(item: &str) -> Option<(ProviderKind, String)> {
+    let (var, key) = unquote(item).split_once('=')?;
+    let (_, kind) = ENV_VARS
+        .iter()
+        .chain(AIDER_ENV_VARS)
+        .find(|(name, _)| *name == var.trim())?;
+    Some((*kind, key.to_string()))
+}
+
+/// The provider variables set in a `.env` file, which aider reads.
+fn dotenv_keys(text: &str) -> Vec<(ProviderKind, String)> {
+    let mut keys = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        match line.split_once('=') {
+            Some((var, value)) if var.trim() == AIDER_API_KEY => {
+                keys.extend(unquote(value).split(',').filter_map(provider_pair));
+            }
+            _ => keys.extend(env_pair(line)),
+        }
+    }
+    keys
+}
+
+/// `provider` and `apiKey` of each model in Continue's `config.json`.
+fn continue_json_keys(text: &str) -> Vec<(String, String)> {
+    let Ok(config) = serde_json::from_str::<serde_json::Value>(text) else {
+        return Vec::new();
+    };
+    let models = config["models"].as_array().cloned().unwrap_or_default();
+    models
+        .iter()
+        .chain(std::iter::once(&config["tabAutocompleteModel"]))
+        .filter_map(|model| {
+            Some((
+                model["provider"].as_str()?.to_string(),
+                model["apiKey"].as_str()?.to_string(),
+            ))
+        })
+        .collect()
+}
+
+/// `provider` and `apiKey` of each list item in Continue's `config.yaml`.
+fn continue_yaml_keys(text: &str) -> Vec<(String, String)> {
+    let mut keys = Vec::new();
+    let mut provider = None;
+    let mut api_key = None;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        let field = match trimmed.strip_prefix("- ") {
+            Some(rest) => {
+                if let (Some(p), Some(k)) = (provider.take(), api_key.take()) {
+                    keys.push((p, k));
+                }
+                rest
+            }
+            None => trimmed,
+        };
+        match field.split_once(':') {
+            Some(("provider", value)) => provider = Some(unquote(value).to_string()),
+            Some(("apiKey", value)) => api_key = Some(unquote(value).to_string()),
+            _ => {}
+        }
+    }
+    if let (Some(p), Some(k)) = (provider, api_key) {
+        keys.push((p, k));
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_keys_in_env_and_tool_configs() {
+        let home = tempfile::tempdir().unwrap();
+        let config = home.path().join(".config");
+        std::fs::create_dir_all(config.join("io.datasette.llm")).unwrap();
+        std::fs::write(
+            config.join("io.datasette.llm/keys.json"),
+            r#"{"// Note": "keys", "openai": "sk-env", "claude": "sk-ant-llm"}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            home.path().join(".aider.conf.yml"),
+            "model: sonnet\nopenai-api-key: sk-aider\napi-key:\n  - gemini=AIza-aider\n  - groq=gsk\nset-env:\n  - GOOGLE_API_KEY=AIza-google\n  - OTHER=x\n",
+        )
+        .unwrap();
+        std::fs::write(
+            home.path().join(".env"),
+            "AIDER_ANTHROPIC_API_KEY=sk-ant-env\nexport AIDER_API_KEY=deepseek=sk-ds\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(home.path().join(".continue")).unwrap();
+        std::fs::write(
+            home.path().join(".continue/config.yaml"),
+            "models:\n  - name: Claude\n    provider: anthropic\n    apiKey: \"sk-ant-cont\"\n  - name: GPT\n    provider: openai\n    apiKey: ${{ secrets.OPENAI }}\n",
+        )
+        .unwrap();
+
+        let env = |name: &str| (name == "OPENAI_API_KEY").then(|| "sk-env".to_string());
+        let found = find_keys(&KeySources {
+            env: &env,
+            home: Some(home.path().to_path_buf()),
+            config_dir: Some(config),
+        });
+        let keys: Vec<(ProviderKind, &str)> =
+            found.iter().map(|f| (f.kind, f.api_key.as_str())).collect();
+        assert_eq!(
+            keys,
+            [
+                (ProviderKind::OpenAI, "sk-env"),
+                (ProviderKind::Anthropic, "sk-ant-llm"),
+                (ProviderKind::OpenAI, "sk-aider"),
+                (ProviderKind::Gemini, "AIza-aider"),
+                (ProviderKind::Groq, "gsk"),
+                (ProviderKind::Gemini, "AIza-google"),
+                (ProviderKind::Anthropic, "sk-ant-env"),
+                (ProviderKind::Deepseek, "sk-ds"),
+                (ProviderKind::Anthropic, "sk-ant-cont"),
+            ]
+        );
+        assert_eq!(found[0].source, "$OPENAI_API_KEY");
+        assert!(found[1].source.ends_with("keys.json (llm)"));
+    }
+}
@@ -0,0 +1,231 @@
+//! Tool installs queued from the plugin health page. They run one after
+//! another in the background, and their output is streamed into the
+//! progress view as it arrives.
+
+use dexter_plugins::InstallPlan;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Output lines kept for the progress view.
+const OUTPUT_LINES: usize = 200;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstallStatus {
+    Queued,
+    Running,
+    Done,
+    Failed(String),
+    Cancelled,
+}
+
+#[derive(Debug, Clone)]
+pub struct InstallJob {
+    pub plan: InstallPlan,
+    pub status: InstallStatus,
+}
+
+enum InstallEvent {
+    Line(String),
+    Finished(Result<(), String>),
+}
+
+/// What `InstallQueue::poll` saw.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueUpdate {
+    Unchanged,
+    Progress,
+    /// The last job ended; tool health should be checked again.
+    Finished,
+}
+
+#[derive(Default)]
+pub struct InstallQueue {
+    pub jobs: Vec<InstallJob>,
+    /// The running and earlier jobs' output, newest last.
+    pub output: Vec<String>,
+    events: Option<mpsc::UnboundedReceiver<InstallEvent>>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl InstallQueue {
+    pub fn contains(&self, plugin: &str) -> bool {
+        self.pending().any(|job| job.plan.plugin == plugin)
+    }
+
+    /// Queues `plan`, or takes it off the queue when its plugin is on it.
+    pub fn toggle(&mut self, plan: InstallPlan) {
+        if self.is_running() {
+            return;
+        }
+        self.clear_finished();
+        match self
+            .jobs
+            .iter()
+            .position(|job| job.plan.plugin == plan.plugin)
+        {
+            Some(idx) => {
+                self.jobs.remove(idx);
+            }
+            None => self.jobs.push(InstallJob {
+                plan,
+                status: InstallStatus::Queued,
+            }),
+        }
+    }
+
+    pub fn pending(&self) -> impl Iterator<Item = &InstallJob> {
+        self.jobs
+            .iter()
+            .filter(|job| job.status == InstallStatus::Queued)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.task.is_some()
+    }
+
+    /// Drops the jobs of an earlier run, keeping the queued ones.
+    pub fn clear_finished(&mut self) {
+        if self
+            .jobs
+            .iter()
+            .all(|job| job.status != InstallStatus::Queued)
+        {
+            self.output.clear();
+        }
+        self.jobs.retain(|job| job.status == InstallStatus::Queued);
+    }
+
+    /// Starts the next queued job; false when none is left.
+    pub fn start_next(&mut self) -> bool {
+        let Some(job) = self
+            .jobs
+            .iter_mut()
+            .find(|job| job.status == InstallStatus::Queued)
+        else {
+            return false;
+        };
+        job.status = InstallStatus::Running;
+        let plan = job.plan.clone();
+        self.push_output(format!("$ {}", plan.command_line()));
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.events = Some(rx);
+        self.task = Some(tokio::spawn(async move {
+            let lines = tx.clone();
+            let result = plan
+                .run_streamed(move |line| {
+                    let _ = lines.send(InstallEvent::Line(line));
+                })
+                .await;
+            let _ = tx.send(InstallEvent::Finished(result.map_err(|e| e.to_string())));
+        }));
+        true
+    }
+
+    /// Takes in the running job's output; when it ends, starts the next.
+    pub fn poll(&mut self) -> QueueUpdate {
+        let Some(events) = self.events.as_mut() else {
+            return QueueUpdate::Unchanged;
+        };
+        let mut received = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            received.push(event);
+        }
+        if received.is_empty() {
+            return QueueUpdate::Unchanged;
+        }
+        for event in received {
+            match event {
+                InstallEvent::Line(line) => self.push_output(line),
+                InstallEvent::Finished(result) => {
+                    self.events = None;
+                    self.task = None;
+                    if let Some(job) = self
+                        .jobs
+                        .iter_mut()
+                        .find(|job| job.status == InstallStatus::Running)
+                    {
+                        job.status = match result {
+                            Ok(()) => InstallStatus::Done,
+                            Err(e) => InstallStatus::Failed(e),
+                        };
+                    }
+                    if !self.start_next() {
+                        return QueueUpdate::Finished;
+                    }
+                    // The next job has its own channel.
+                    return QueueUpdate::Progress;
+                }
+            }
+        }
+        QueueUpdate::Progress
+    }
+
+    /// Kills the running job and drops the rest of the queue.
+    pub fn cancel(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+        self.events = None;
+        for job in &mut self.jobs {
+            if matches!(job.status, InstallStatus::Queued | InstallStatus::Running) {
+                job.status = InstallStatus::Cancelled;
+            }
+        }
+    }
+
+    fn push_output(&mut self, line: String) {
+        self.output.push(line);
+        if self.output.len() > OUTPUT_LINES {
+            self.output.remove(0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dexter_plugins::PackageManager;
+
+    fn plan(plugin: &str, script: &str) -> InstallPlan {
+        InstallPlan {
+            plugin: plugin.to_string(),
+            manager: PackageManager::Brew,
+            package: plugin.to_string(),
+            update: false,
+            argv: vec!["sh".to_string(), "-c".to_string(), script.to_string()],
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn runs_queued_installs_in_order() {
+        let mut queue = InstallQueue::default();
+        queue.toggle(plan("f2", "echo installing f2"));
+        queue.toggle(plan("qpdf", "echo no such package >&2; exit 1"));
+        queue.toggle(plan("jdupes", "true"));
+        queue.toggle(plan("jdupes", "true"));
+        assert!(queue.contains("qpdf") && !queue.contains("jdupes"));
+
+        assert!(queue.start_next());
+        let mut update = QueueUpdate::Unchanged;
+        for _ in 0..200 {
+            update = queue.poll();
+            if update == QueueUpdate::Finished {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(update, QueueUpdate::Finished);
+        assert_eq!(queue.jobs[0].status, InstallStatus::Done);
+        assert!(matches!(
+            &queue.jobs[1].status,
+            InstallStatus::Failed(e) if e.ends_with("no such package")
+        ));
+        assert!(queue.output.contains(&"installing f2".to_string()));
+        assert!(!queue.is_running());
+
+        queue.toggle(plan("jdupes", "true"));
+        assert_eq!(queue.jobs.len(), 1);
+        assert!(queue.output.is_empty());
+    }
+}
@@ -1,3 +1,6 @@
+pub mod import;
+pub mod install;
 pub mod runtime;
+pub mod safety;
 pub mod state;
 pub mod view;
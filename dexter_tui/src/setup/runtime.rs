@@ -5,31 +5,50 @@ use ratatui::Terminal;
 use std::io::Stdout;
 use std::time::Duration;
 
-use dexter_core::Config;
+use dexter_core::{Config, ConfigIssue};
 
-use crate::setup::state::{SetupApp, SetupState};
+use crate::setup::safety::SafetyRow;
+use crate::setup::state::{PluginSettingsField, ProviderConfigField, SetupApp, SetupState};
 use crate::setup::view::setup_ui;
 
 pub async fn run_setup_wizard(
     terminal: &mut Terminal<CrosstermBackend<Stdout>>,
     config: Config,
+    read_only: bool,
 ) -> Result<Config> {
-    run_setup_flow(terminal, config, true).await
+    run_setup_flow(terminal, config, true, Vec::new(), read_only).await
 }
 
 pub async fn run_settings_panel(
     terminal: &mut Terminal<CrosstermBackend<Stdout>>,
     config: Config,
+    read_only: bool,
 ) -> Result<Config> {
-    run_setup_flow(terminal, config, false).await
+    run_setup_flow(terminal, config, false, Vec::new(), read_only).await
+}
+
+/// The settings panel, opened on the first problem found at startup.
+pub async fn run_settings_panel_for_issues(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    config: Config,
+    issues: Vec<ConfigIssue>,
+    read_only: bool,
+) -> Result<Config> {
+    run_setup_flow(terminal, config, false, issues, read_only).await
 }
 
 pub async fn run_setup_flow(
     terminal: &mut Terminal<CrosstermBackend<Stdout>>,
     config: Config,
     show_welcome: bool,
+    issues: Vec<ConfigIssue>,
+    read_only: bool,
 ) -> Result<Config> {
     let mut app = SetupApp::new(config, show_welcome);
+    app.read_only = read_only;
+    if !issues.is_empty() {
+        app.focus_issues(issues);
+    }
     app.refresh_runtime_statuses().await;
 
     loop {
@@ -38,16 +57,18 @@ pub async fn run_setup_flow(
             app.dirty = false;
         }
 
-        if app.state == SetupState::FetchingProviderModels {
-            if let Err(e) = app.fetch_models_for_current_provider().await {
+        if app.state == SetupState::FetchingProviderModels && app.model_fetch.is_none() {
+            if let Err(e) = app.start_model_fetch() {
                 app.state = SetupState::Error(format!("Model discovery failed: {}", e));
-            } else {
-                app.state = SetupState::ProviderModelSelection;
             }
             app.dirty = true;
             continue;
         }
 
+        app.poll_model_fetch().await;
+        app.poll_installs().await;
+        app.poll_sign_in().await;
+
         let poll_ms = 220;
         if event::poll(Duration::from_millis(poll_ms))? {
             if let Event::Key(key) = event::read()? {
@@ -55,31 +76,66 @@ pub async fn run_setup_flow(
                     app.dirty = true;
                     match app.state {
                         SetupState::Welcome => match key.code {
-                            KeyCode::Enter => app.state = SetupState::ProviderSelection,
+                            KeyCode::Enter => {
+                                // Nothing to import: straight to step 1.
+                                let found = app.open_key_import();
+                                if !found {
+                                    app.state = SetupState::ProviderSelection;
+                                }
+                            }
                             KeyCode::Esc => return Err(anyhow!("Setup aborted by user")),
                             _ => {}
                         },
+                        SetupState::KeyImport => match key.code {
+                            KeyCode::Up | KeyCode::Left if app.found_key_cursor > 0 => {
+                                app.found_key_cursor -= 1;
+                            }
+                            KeyCode::Down | KeyCode::Right
+                                if app.found_key_cursor + 1 < app.found_keys.len() =>
+                            {
+                                app.found_key_cursor += 1;
+                            }
+                            KeyCode::Char(' ') => app.toggle_found_key(),
+                            KeyCode::Enter => {
+                                app.import_found_keys();
+                                app.state = SetupState::ProviderSelection;
+                            }
+                            KeyCode::Esc => {
+                                app.found_keys.clear();
+                                app.state = SetupState::ProviderSelection;
+                            }
+                            _ => {}
+                        },
                         SetupState::ProviderSelection => match key.code {
-                            KeyCode::Up | KeyCode::Left => {
-                                if app.selected_provider_idx > 0 {
-                                    app.selected_provider_idx -= 1;
-                                }
+                            KeyCode::Up | KeyCode::Left if app.selected_provider_idx > 0 => {
+                                app.selected_provider_idx -= 1;
                             }
-                            KeyCode::Down | KeyCode::Right => {
+                            KeyCode::Down | KeyCode::Right
                                 if app.selected_provider_idx
-                                    < app.provider_selection_len().saturating_sub(1)
-                                {
-                                    app.selected_provider_idx += 1;
-                                }
+                                    < app.provider_selection_len().saturating_sub(1) =>
+                            {
+                                app.selected_provider_idx += 1;
                             }
                             KeyCode::Char(' ') => {
                                 app.toggle_provider_enabled(app.selected_provider_idx);
                             }
+                            KeyCode::Char('p') | KeyCode::Char('P') => {
+                                app.open_plugin_health().await;
+                            }
+                            KeyCode::Char('i') | KeyCode::Char('I') => {
+                                app.open_key_import();
+                            }
+                            KeyCode::Char('s') | KeyCode::Char('S') => app.open_safety(),
                             KeyCode::Enter => {
                                 if let Err(e) = app.start_guided_flow() {
                                     app.state = SetupState::Error(e.to_string());
                                 }
                             }
+                            // Saved plugin and safety settings reach the app
+                            // even when the provider steps are skipped.
+                            KeyCode::Esc if app.settings_saved && app.config.has_keys() => {
+                                return Ok(app.config);
+                            }
                             KeyCode::Esc => return Err(anyhow!("Setup aborted")),
                             _ => {}
                         },
@@ -90,7 +146,14 @@ pub async fn run_setup_flow(
                                 continue;
                             };
                             let requires_key = app.providers[provider_idx].requires_api_key();
+                            let uses_oauth = app.providers[provider_idx].uses_oauth();
                             match key.code {
+                                KeyCode::Enter
+                                    if uses_oauth && !app.providers[provider_idx].signed_in =>
+                                {
+                                    app.start_sign_in().await;
+                                }
+                                KeyCode::F(2) if uses_oauth => app.start_sign_in().await,
                                 KeyCode::Enter => {
                                     if requires_key
                                         && app.providers[provider_idx].api_key.trim().is_empty()
@@ -103,16 +166,30 @@ pub async fn run_setup_flow(
                                         app.advance_provider_config();
                                     }
                                 }
-                                KeyCode::Char(c) => {
-                                    if requires_key {
-                                        app.providers[provider_idx].api_key.push(c);
+                                KeyCode::Tab | KeyCode::Down => {
+                                    app.config_field = app.config_field.next();
+                                    if !requires_key
+                                        && app.config_field == ProviderConfigField::ApiKey
+                                    {
+                                        app.config_field = app.config_field.next();
                                     }
                                 }
-                                KeyCode::Backspace => {
-                                    if requires_key {
-                                        app.providers[provider_idx].api_key.pop();
+                                KeyCode::BackTab | KeyCode::Up => {
+                                    app.config_field = app.config_field.prev();
+                                    if !requires_key
+                                        && app.config_field == ProviderConfigField::ApiKey
+                                    {
+                                        app.config_field = app.config_field.prev();
                                     }
                                 }
+                                KeyCode::Char(c) => {
+                                    let field = app.config_field;
+                                    app.providers[provider_idx].field_mut(field).push(c);
+                                }
+                                KeyCode::Backspace => {
+                                    let field = app.config_field;
+                                    app.providers[provider_idx].field_mut(field).pop();
+                                }
                                 KeyCode::Esc => {
                                     app.reset_guided_flow();
                                     app.state = SetupState::ProviderSelection;
@@ -120,7 +197,16 @@ pub async fn run_setup_flow(
                                 _ => {}
                             }
                         }
-                        SetupState::FetchingProviderModels => {}
+                        SetupState::SigningIn => {
+                            if key.code == KeyCode::Esc {
+                                app.cancel_sign_in();
+                            }
+                        }
+                        SetupState::FetchingProviderModels => match key.code {
+                            KeyCode::Char('s') | KeyCode::Char('S') => app.skip_provider(),
+                            KeyCode::Esc => app.cancel_model_fetch(),
+                            _ => {}
+                        },
                         SetupState::ProviderModelSelection => {
                             let Some(provider_idx) = app.config_provider_idx else {
                                 app.state = SetupState::ProviderSelection;
@@ -130,17 +216,19 @@ pub async fn run_setup_flow(
                             let model_count = app.providers[provider_idx].available_models.len();
                             let max_idx = model_count;
                             match key.code {
-                                KeyCode::Up | KeyCode::Left => {
-                                    if app.provider_model_cursor > 0 {
-                                        app.provider_model_cursor -= 1;
-                                    }
+                                KeyCode::Up | KeyCode::Left if app.provider_model_cursor > 0 => {
+                                    app.provider_model_cursor -= 1;
                                 }
-                                KeyCode::Down | KeyCode::Right => {
-                                    if app.provider_model_cursor < max_idx {
-                                        app.provider_model_cursor += 1;
-                                    }
+                                KeyCode::Down | KeyCode::Right
+                                    if app.provider_model_cursor < max_idx =>
+                                {
+                                    app.provider_model_cursor += 1;
                                 }
                                 KeyCode::Char(' ') => app.toggle_model_selection(),
+                                KeyCode::Char('r') | KeyCode::Char('R') => {
+                                    app.refresh_models = true;
+                                    app.state = SetupState::FetchingProviderModels;
+                                }
                                 KeyCode::Enter => {
                                     if app.providers[provider_idx].enabled
                                         && app.providers[provider_idx].active_models.is_empty()
@@ -157,21 +245,36 @@ pub async fn run_setup_flow(
                                 _ => {}
                             }
                         }
+                        SetupState::ModelFetchFailed => match key.code {
+                            KeyCode::Char('r') | KeyCode::Char('R') => {
+                                app.refresh_models = true;
+                                app.state = SetupState::FetchingProviderModels;
+                            }
+                            KeyCode::Enter => {
+                                app.use_fallback_models();
+                                app.state = SetupState::ProviderModelSelection;
+                            }
+                            KeyCode::Char('s') | KeyCode::Char('S') => app.skip_provider(),
+                            KeyCode::Esc => {
+                                app.model_fetch_failure = None;
+                                app.state = SetupState::ProviderConfig;
+                            }
+                            _ => {}
+                        },
                         SetupState::ModelOrderSelection => match key.code {
-                            KeyCode::Up | KeyCode::Left => {
-                                if app.model_order_cursor > 0 {
-                                    app.model_order_cursor -= 1;
-                                }
+                            KeyCode::Up | KeyCode::Left if app.model_order_cursor > 0 => {
+                                app.model_order_cursor -= 1;
                             }
-                            KeyCode::Down | KeyCode::Right => {
-                                if app.model_order_cursor < app.model_order.len().saturating_sub(1)
-                                {
-                                    app.model_order_cursor += 1;
-                                }
+                            KeyCode::Down | KeyCode::Right
+                                if app.model_order_cursor
+                                    < app.model_order.len().saturating_sub(1) =>
+                            {
+                                app.model_order_cursor += 1;
                             }
                             KeyCode::Char('u') | KeyCode::Char('k') => app.move_model_order_up(),
                             KeyCode::Char('d') | KeyCode::Char('j') => app.move_model_order_down(),
                             KeyCode::Enter => {
+                                app.sync_router_order();
                                 app.state =
                                     SetupState::on_model_order_enter(!app.model_order.is_empty());
                             }
@@ -181,25 +284,42 @@ pub async fn run_setup_flow(
                             }
                             _ => {}
                         },
+                        SetupState::RouterOrderSelection => match key.code {
+                            KeyCode::Up | KeyCode::Left if app.router_order_cursor > 0 => {
+                                app.router_order_cursor -= 1;
+                            }
+                            KeyCode::Down | KeyCode::Right
+                                if app.router_order_cursor
+                                    < app.router_order.len().saturating_sub(1) =>
+                            {
+                                app.router_order_cursor += 1;
+                            }
+                            KeyCode::Char('u') | KeyCode::Char('k') => app.move_router_order_up(),
+                            KeyCode::Char('d') | KeyCode::Char('j') => app.move_router_order_down(),
+                            KeyCode::Char('m') => app.mirror_model_order(),
+                            KeyCode::Char('r') => app.reset_router_order(),
+                            KeyCode::Enter => app.state = SetupState::on_router_order_enter(),
+                            KeyCode::Esc => app.state = SetupState::ModelOrderSelection,
+                            _ => {}
+                        },
                         SetupState::ThemeSelection => match key.code {
-                            KeyCode::Up | KeyCode::Left => {
-                                if app.selected_theme_idx > 0 {
-                                    app.selected_theme_idx -= 1;
-                                    let theme_id = app.available_themes[app.selected_theme_idx].0;
-                                    app.theme = crate::theme::Theme::from_config(theme_id);
-                                }
+                            KeyCode::Up | KeyCode::Left if app.selected_theme_idx > 0 => {
+                                app.selected_theme_idx -= 1;
+                                let theme_id = app.available_themes[app.selected_theme_idx].0;
+                                app.theme = crate::theme::Theme::from_config(theme_id)
+                                    .with_plain(app.config.plain_ui);
                             }
-                            KeyCode::Down | KeyCode::Right => {
+                            KeyCode::Down | KeyCode::Right
                                 if app.selected_theme_idx
-                                    < app.available_themes.len().saturating_sub(1)
-                                {
-                                    app.selected_theme_idx += 1;
-                                    let theme_id = app.available_themes[app.selected_theme_idx].0;
-                                    app.theme = crate::theme::Theme::from_config(theme_id);
-                                }
+                                    < app.available_themes.len().saturating_sub(1) =>
+                            {
+                                app.selected_theme_idx += 1;
+                                let theme_id = app.available_themes[app.selected_theme_idx].0;
+                                app.theme = crate::theme::Theme::from_config(theme_id)
+                                    .with_plain(app.config.plain_ui);
                             }
                             KeyCode::Enter => app.state = SetupState::on_theme_enter(),
-                            KeyCode::Esc => app.state = SetupState::ModelOrderSelection,
+                            KeyCode::Esc => app.state = SetupState::RouterOrderSelection,
                             _ => {}
                         },
                         SetupState::Confirm => match key.code {
@@ -220,6 +340,105 @@ pub async fn run_setup_flow(
                             }
                         }
                         SetupState::Saving => {}
+                        SetupState::PluginHealth => match key.code {
+                            KeyCode::Up | KeyCode::Left if app.plugin_cursor > 0 => {
+                                app.plugin_cursor -= 1;
+                            }
+                            KeyCode::Down | KeyCode::Right
+                                if app.plugin_cursor < app.plugin_rows.len().saturating_sub(1) =>
+                            {
+                                app.plugin_cursor += 1;
+                            }
+                            KeyCode::Char(' ') => app.toggle_selected_install(),
+                            KeyCode::Enter | KeyCode::Char('i') => app.confirm_installs(),
+                            KeyCode::Char('r') => app.refresh_plugin_health().await,
+                            KeyCode::Char('s') => app.open_plugin_settings(),
+                            KeyCode::Esc => app.state = SetupState::ProviderSelection,
+                            _ => {}
+                        },
+                        SetupState::PluginSettings => {
+                            let field = app.plugin_settings_field;
+                            match key.code {
+                                KeyCode::Tab | KeyCode::Down => {
+                                    app.plugin_settings_field = field.next();
+                                }
+                                KeyCode::BackTab | KeyCode::Up => {
+                                    app.plugin_settings_field = field.prev();
+                                }
+                                KeyCode::Char(' ')
+                                    if field == PluginSettingsField::NeverOverwrite =>
+                                {
+                                    if let Some(draft) = app.plugin_settings.as_mut() {
+                                        draft.never_overwrite = !draft.never_overwrite;
+                                    }
+                                }
+                                KeyCode::Char(c) => {
+                                    if let Some(text) =
+                                        app.plugin_settings.as_mut().and_then(|d| d.text_mut(field))
+                                    {
+                                        text.push(c);
+                                    }
+                                }
+                                KeyCode::Backspace => {
+                                    if let Some(text) =
+                                        app.plugin_settings.as_mut().and_then(|d| d.text_mut(field))
+                                    {
+                                        text.pop();
+                                    }
+                                }
+                                KeyCode::Enter => app.save_plugin_settings().await,
+                                KeyCode::Esc => {
+                                    app.plugin_settings = None;
+                                    app.state = SetupState::PluginHealth;
+                                }
+                                _ => {}
+                            }
+                        }
+                        SetupState::Safety => {
+                            let Some(draft) = app.safety.as_mut() else {
+                                app.state = SetupState::ProviderSelection;
+                                continue;
+                            };
+                            let toggle = matches!(
+                                draft.current(),
+                                SafetyRow::ContainPaths | SafetyRow::Plugin(_)
+                            );
+                            match key.code {
+                                KeyCode::Down | KeyCode::Tab => draft.move_by(1),
+                                KeyCode::Up | KeyCode::BackTab => draft.move_by(-1),
+                                KeyCode::PageDown => draft.move_by(10),
+                                KeyCode::PageUp => draft.move_by(-10),
+                                KeyCode::Char(' ') if toggle => draft.change(1),
+                                KeyCode::Right => draft.change(1),
+                                KeyCode::Left => draft.change(-1),
+                                KeyCode::Char(c) => {
+                                    if let Some(text) = draft.text_mut() {
+                                        text.push(c);
+                                    }
+                                }
+                                KeyCode::Backspace => draft.backspace(),
+                                KeyCode::Enter => app.save_safety().await,
+                                KeyCode::Esc => {
+                                    app.safety = None;
+                                    app.state = SetupState::ProviderSelection;
+                                }
+                                _ => {}
+                            }
+                        }
+                        SetupState::InstallConfirm => match key.code {
+                            KeyCode::Enter | KeyCode::Char('y') => app.start_installs(),
+                            KeyCode::Esc | KeyCode::Char('n') => {
+                                app.state = SetupState::PluginHealth
+                            }
+                            _ => {}
+                        },
+                        SetupState::Installing => match key.code {
+                            KeyCode::Esc if app.install_queue.is_running() => {
+                                app.install_queue.cancel();
+                            }
+                            KeyCode::Enter | KeyCode::Esc => app.leave_installs().await,
+                            _ => {}
+                        },
                     }
                 }
             }
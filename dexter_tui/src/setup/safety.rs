@@ -0,0 +1,250 @@
+//! The safety page: the config's `[safety]` rules and the confirmation
+//! timeout, edited next to the built-in and project rules they add to, with
+//! a box that checks a sample command against the rules on screen.
+
+use dexter_core::{Config, PluginPolicy, SafetyGuard, SafetyPolicy, Workspace};
+use dexter_plugins::Plugin;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// A line of the safety page the cursor can be on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SafetyRow {
+    /// One of the user's patterns; the one past the last adds a pattern.
+    Pattern(usize),
+    ContainPaths,
+    Plugin(usize),
+    ConfirmTimeout,
+    Test,
+}
+
+/// The rules a project's `.dexter.toml` adds, shown but not edited here.
+#[derive(Debug, Clone, Default)]
+pub struct ProjectRules {
+    pub root: Option<PathBuf>,
+    pub patterns: Vec<String>,
+    /// Why the project's file couldn't be read, when it couldn't.
+    pub error: Option<String>,
+    guard: SafetyGuard,
+}
+
+impl ProjectRules {
+    pub fn discover(cwd: &Path) -> Self {
+        match Workspace::discover(cwd) {
+            Ok(workspace) => Self {
+                root: workspace.root.clone(),
+                patterns: workspace.settings.blocked_patterns.clone(),
+                error: None,
+                guard: workspace.safety_guard().clone(),
+            },
+            Err(e) => Self {
+                error: Some(format!("{:#}", e)),
+                ..Self::default()
+            },
+        }
+    }
+}
+
+/// The page's copy of the rules; Enter puts them in the config.
+#[derive(Debug, Clone)]
+pub struct SafetyDraft {
+    pub policy: SafetyPolicy,
+    /// `confirm_timeout.minutes`.
+    pub confirm_minutes: u64,
+    /// Every plugin, in the order the page lists them.
+    pub plugins: Vec<String>,
+    pub project: ProjectRules,
+    pub cwd: PathBuf,
+    /// The command the test box checks.
+    pub sample: String,
+    pub row: usize,
+    /// Outcome of the last save.
+    pub message: Option<String>,
+}
+
+impl SafetyDraft {
+    pub fn new(config: &Config, plugins: &[Arc<dyn Plugin>], cwd: PathBuf) -> Self {
+        Self {
+            policy: config.safety.clone(),
+            confirm_minutes: config.confirm_timeout.minutes,
+            plugins: plugins.iter().map(|p| p.name().to_string()).collect(),
+            project: ProjectRules::discover(&cwd),
+            cwd,
+            sample: String::new(),
+            row: 0,
+            message: None,
+        }
+    }
+
+    pub fn rows(&self) -> Vec<SafetyRow> {
+        let patterns = self.policy.blocked_patterns.len();
+        (0..=patterns)
+            .map(SafetyRow::Pattern)
+            .chain([SafetyRow::ContainPaths])
+            .chain((0..self.plugins.len()).map(SafetyRow::Plugin))
+            .chain([SafetyRow::ConfirmTimeout, SafetyRow::Test])
+            .collect()
+    }
+
+    pub fn current(&self) -> SafetyRow {
+        let rows = self.rows();
+        rows[self.row.min(rows.len() - 1)]
+    }
+
+    pub fn move_by(&mut self, step: isize) {
+        let last = self.rows().len() as isize - 1;
+        self.row = (self.row as isize + step).clamp(0, last) as usize;
+    }
+
+    /// The text typing edits on the current row. Typing on the add row
+    /// starts a new pattern.
+    pub fn text_mut(&mut self) -> Option<&mut String> {
+        match self.current() {
+            SafetyRow::Pattern(idx) => {
+                let patterns = &mut self.policy.blocked_patterns;
+                if idx == patterns.len() {
+                    patterns.push(String::new());
+                }
+                patterns.get_mut(idx)
+            }
+            SafetyRow::Test => Some(&mut self.sample),
+            _ => None,
+        }
+    }
+
+    /// Backspace: deletes a character, or the pattern once it is empty.
+    pub fn backspace(&mut self) {
+        if let SafetyRow::Pattern(idx) = self.current() {
+            if self
+                .policy
+                .blocked_patterns
+                .get(idx)
+                .is_some_and(String::is_empty)
+            {
+                self.policy.blocked_patterns.remove(idx);
+                return;
+            }
+        }
+        if let Some(text) = self.text_mut() {
+            text.pop();
+        }
+    }
+
+    /// Space or Left/Right on a toggle: containment on or off, the next
+    /// plugin policy, a minute more or less.
+    pub fn change(&mut self, step: i64) {
+        match self.current() {
+            SafetyRow::ContainPaths => self.policy.contain_paths = !self.policy.contain_paths,
+            SafetyRow::Plugin(idx) => {
+                let name = self.plugins[idx].clone();
+                let mut policy = self.policy.plugin_policy(&name);
+                for _ in 0..step.rem_euclid(3) {
+                    policy = policy.next();
+                }
+                if policy == PluginPolicy::Allow {
+                    self.policy.plugins.remove(&name);
+                } else {
+                    self.policy.plugins.insert(name, policy);
+                }
+            }
+            SafetyRow::ConfirmTimeout => {
+                self.confirm_minutes = self.confirm_minutes.saturating_add_signed(step);
+            }
+            _ => {}
+        }
+    }
+
+    /// Why a pattern of the user's is not a valid regex.
+    pub fn pattern_error(&self, idx: usize) -> Option<String> {
+        let pattern = self.policy.blocked_patterns.get(idx)?;
+        let message = SafetyGuard::default()
+            .with_blocked_patterns(std::slice::from_ref(pattern))
+            .err()?
+            .to_string();
+        message.lines().last().map(|line| line.trim().to_string())
+    }
+
+    /// The rules as they will be saved: empty patterns dropped.
+    pub fn to_policy(&self) -> SafetyPolicy {
+        let mut policy = self.policy.clone();
+        policy
+            .blocked_patterns
+            .retain(|pattern| !pattern.trim().is_empty());
+        policy
+    }
+
+    /// The test box's verdict on the sample, by the rules on screen:
+    /// `Ok` with what runs it, or `Err` with why it would be blocked.
+    pub fn verdict(&self, plugins: &[Arc<dyn Plugin>]) -> Option<Result<String, String>> {
+        let sample = self.sample.trim();
+        if sample.is_empty() {
+            return None;
+        }
+        let guard = match self
+            .project
+            .guard
+            .clone()
+            .with_policy(&self.to_policy(), &self.cwd)
+        {
+            Ok(guard) => guard,
+            Err(e) => return Some(Err(e.to_string())),
+        };
+        let Some(plugin) = plugins.iter().find(|p| p.validate_command(sample)) else {
+            return Some(Err("No plugin runs this command".to_string()));
+        };
+        if let Err(e) = guard
+            .check_plugin(plugin.name())
+            .and_then(|()| guard.check(sample))
+        {
+            return Some(Err(e.to_string()));
+        }
+        Some(Ok(match self.policy.plugin_policy(plugin.name()) {
+            PluginPolicy::Confirm => format!(
+                "Allowed: runs with {} after a confirmation, never on its own",
+                plugin.name()
+            ),
+            _ => format!("Allowed: runs with {}", plugin.name()),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::state::builtin_plugins;
+
+    #[test]
+    fn edits_the_rules_and_checks_the_sample_against_them() {
+        let config = Config::default();
+        let plugins = builtin_plugins(&config);
+        let mut draft = SafetyDraft::new(&config, &plugins, PathBuf::from("/work"));
+        assert_eq!(draft.current(), SafetyRow::Pattern(0));
+
+        draft.text_mut().unwrap().push_str(r"\s-y\b");
+        assert_eq!(draft.policy.blocked_patterns, [r"\s-y\b"]);
+        draft.sample = "ffmpeg -y -i a.mkv a.mp4".to_string();
+        assert!(draft.verdict(&plugins).unwrap().is_err());
+        draft.sample = "ffmpeg -i /tmp/a.mkv a.mp4".to_string();
+        assert!(draft.verdict(&plugins).unwrap().is_ok());
+
+        draft.move_by(2);
+        assert_eq!(draft.current(), SafetyRow::ContainPaths);
+        draft.change(1);
+        assert!(draft.verdict(&plugins).unwrap().is_err());
+
+        let ffmpeg = draft.plugins.iter().position(|p| p == "ffmpeg").unwrap();
+        draft.move_by(1 + ffmpeg as isize);
+        draft.change(2);
+        assert_eq!(draft.policy.plugin_policy("ffmpeg"), PluginPolicy::Block);
+        draft.change(1);
+        assert!(draft.policy.plugins.is_empty());
+
+        draft.row = 0;
+        draft.text_mut().unwrap().push('(');
+        assert!(draft.pattern_error(0).is_some());
+        draft.text_mut().unwrap().clear();
+        draft.backspace();
+        assert!(draft.to_policy().blocked_patterns.is_empty());
+        assert_eq!(draft.rows()[0], SafetyRow::Pattern(0));
+    }
+}
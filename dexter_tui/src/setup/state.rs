@@ -1,32 +1,68 @@
 use anyhow::{anyhow, Result};
-use dexter_core::{Config, LlmClient, ModelRoute, ProviderAuth, ProviderConfig, ProviderKind};
+use dexter_core::executor::load_plugin_successes;
+use dexter_core::oauth::is_signed_in;
+use dexter_core::{
+    CachedModels, Config, ConfigField, ConfigIssue, DeviceCode, LlmClient, ModelCache,
+    ModelListError, ModelRoute, OAuthSettings, ProviderAuth, ProviderConfig, ProviderKind,
+};
+use dexter_plugins::install::manual_instructions;
+use dexter_plugins::{InstallPlan, Plugin, PluginSettings, ToolHealth};
+use std::sync::Arc;
 use std::time::Duration;
 
+use crate::app::state::builtin_plugins;
+use crate::setup::import::{find_keys, FoundKey, KeySources};
+use crate::setup::install::{InstallQueue, QueueUpdate};
+use crate::setup::safety::SafetyDraft;
 use crate::theme::Theme;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum SetupState {
     Welcome,
+    /// API keys found in the environment and other tools, offered for
+    /// import; opened after the welcome screen or with `I` from step 1.
+    KeyImport,
     ProviderSelection,
     ProviderConfig,
+    /// Waiting for the user to enter the device code of an OAuth provider.
+    SigningIn,
     FetchingProviderModels,
+    /// The model list fetch failed; shows why, with retry and fallback.
+    ModelFetchFailed,
     ProviderModelSelection,
     ModelOrderSelection,
+    /// Which of the chosen models route, and in what order.
+    RouterOrderSelection,
     ThemeSelection,
     Confirm,
     Saving,
     Error(String),
+    /// Side page listing each plugin's tool; opened with `P` from step 1.
+    PluginHealth,
+    /// The queued installs and their commands, waiting for a yes.
+    InstallConfirm,
+    /// The queued installs running, with their output.
+    Installing,
+    /// One plugin's settings, opened with `S` from the plugin health page.
+    PluginSettings,
+    /// Danger patterns, path containment, plugin policies and the
+    /// confirmation timeout; opened with `S` from step 1.
+    Safety,
 }
 
 impl SetupState {
     pub fn on_model_order_enter(has_models: bool) -> Self {
         if has_models {
-            SetupState::ThemeSelection
+            SetupState::RouterOrderSelection
         } else {
             SetupState::Error("No active models in routing order.".to_string())
         }
     }
 
+    pub fn on_router_order_enter() -> Self {
+        SetupState::ThemeSelection
+    }
+
     pub fn on_theme_enter() -> Self {
         SetupState::Confirm
     }
@@ -36,6 +72,108 @@ impl SetupState {
     }
 }
 
+/// Which value typing edits on the provider config step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderConfigField {
+    ApiKey,
+    Proxy,
+    CaCert,
+}
+
+impl ProviderConfigField {
+    pub fn next(self) -> Self {
+        match self {
+            ProviderConfigField::ApiKey => ProviderConfigField::Proxy,
+            ProviderConfigField::Proxy => ProviderConfigField::CaCert,
+            ProviderConfigField::CaCert => ProviderConfigField::ApiKey,
+        }
+    }
+
+    pub fn prev(self) -> Self {
+        self.next().next()
+    }
+}
+
+/// Which value a plugin's settings page edits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluginSettingsField {
+    DefaultFlags,
+    OutputDir,
+    NeverOverwrite,
+}
+
+impl PluginSettingsField {
+    pub fn next(self) -> Self {
+        match self {
+            PluginSettingsField::DefaultFlags => PluginSettingsField::OutputDir,
+            PluginSettingsField::OutputDir => PluginSettingsField::NeverOverwrite,
+            PluginSettingsField::NeverOverwrite => PluginSettingsField::DefaultFlags,
+        }
+    }
+
+    pub fn prev(self) -> Self {
+        self.next().next()
+    }
+}
+
+/// The settings page's copy of one plugin's settings; Enter puts it in
+/// the config.
+#[derive(Debug, Clone)]
+pub struct PluginSettingsDraft {
+    pub plugin: String,
+    pub default_flags: String,
+    pub output_dir: String,
+    pub never_overwrite: bool,
+    /// Not edited here; kept so saving the page doesn't drop them.
+    pub prompt_append: Vec<String>,
+    pub prompt_override: Option<String>,
+}
+
+impl PluginSettingsDraft {
+    fn new(plugin: &str, settings: PluginSettings) -> Self {
+        Self {
+            plugin: plugin.to_string(),
+            default_flags: settings.default_flags,
+            output_dir: settings.output_dir.unwrap_or_default(),
+            never_overwrite: settings.never_overwrite,
+            prompt_append: settings.prompt_append,
+            prompt_override: settings.prompt_override,
+        }
+    }
+
+    /// The text `field` holds; `None` for the toggle.
+    pub fn text_mut(&mut self, field: PluginSettingsField) -> Option<&mut String> {
+        match field {
+            PluginSettingsField::DefaultFlags => Some(&mut self.default_flags),
+            PluginSettingsField::OutputDir => Some(&mut self.output_dir),
+            PluginSettingsField::NeverOverwrite => None,
+        }
+    }
+
+    pub fn to_settings(&self) -> PluginSettings {
+        let output_dir = self.output_dir.trim();
+        PluginSettings {
+            default_flags: self.default_flags.trim().to_string(),
+            output_dir: (!output_dir.is_empty()).then(|| output_dir.to_string()),
+            never_overwrite: self.never_overwrite,
+            prompt_append: self.prompt_append.clone(),
+            prompt_override: self.prompt_override.clone(),
+        }
+    }
+}
+
+/// Why the last model list fetch failed, kept until it is retried or
+/// passed over.
+#[derive(Debug, Clone)]
+pub struct ModelFetchFailure {
+    /// Status and answer, when the request went out.
+    pub details: Option<ModelListError>,
+    /// The whole error, for failures before any request.
+    pub message: String,
+    /// The cached list Enter falls back to; the built-in one without it.
+    pub cached: Option<CachedModels>,
+}
+
 #[derive(Debug, Clone)]
 pub struct SetupProviderEntry {
     pub kind: ProviderKind,
@@ -46,6 +184,14 @@ pub struct SetupProviderEntry {
     pub available_models: Vec<String>,
     pub active_models: Vec<String>,
     pub runtime_ready: Option<bool>,
+    pub proxy: String,
+    pub ca_cert: String,
+    pub oauth: Option<OAuthSettings>,
+    /// Tokens for `auth = "oauth"` are in the token store.
+    pub signed_in: bool,
+    /// How old `available_models` is, as `3h ago`; `None` while it is the
+    /// built-in list because nothing could be fetched.
+    pub models_age: Option<String>,
 }
 
 impl SetupProviderEntry {
@@ -54,13 +200,25 @@ impl SetupProviderEntry {
     }
 
     pub fn requires_api_key(&self) -> bool {
-        !matches!(self.auth, ProviderAuth::None)
+        !matches!(self.auth, ProviderAuth::None | ProviderAuth::OAuth)
+    }
+
+    pub fn uses_oauth(&self) -> bool {
+        self.auth == ProviderAuth::OAuth
     }
 
     pub fn has_key(&self) -> bool {
         !self.api_key.trim().is_empty()
     }
 
+    pub fn field_mut(&mut self, field: ProviderConfigField) -> &mut String {
+        match field {
+            ProviderConfigField::ApiKey => &mut self.api_key,
+            ProviderConfigField::Proxy => &mut self.proxy,
+            ProviderConfigField::CaCert => &mut self.ca_cert,
+        }
+    }
+
     pub fn to_provider_config(&self) -> ProviderConfig {
         let api_key = if self.api_key.trim().is_empty() {
             None
@@ -75,6 +233,9 @@ impl SetupProviderEntry {
             auth: self.auth,
             enabled: self.enabled,
             models: dedup_models(self.active_models.clone()),
+            proxy: Some(self.proxy.clone()),
+            ca_cert: Some(self.ca_cert.clone()),
+            oauth: self.oauth.clone(),
         }
         .normalized()
     }
@@ -98,20 +259,83 @@ impl ModelRouteDraft {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct PluginHealthRow {
+    pub name: String,
+    pub health: ToolHealth,
+    /// `YYYY-MM-DD HH:MM` (UTC) of the last successful run.
+    pub last_success: Option<String>,
+}
+
+/// A device code sign-in waiting for the user.
+pub struct SignIn {
+    pub provider_idx: usize,
+    pub code: DeviceCode,
+    task: tokio::task::JoinHandle<Result<()>>,
+}
+
+/// A model list fetch running in the background, so a provider that
+/// doesn't answer can be skipped while it waits.
+pub struct ModelFetch {
+    provider_idx: usize,
+    provider_cfg: ProviderConfig,
+    /// The list to fall back to when the fetch fails.
+    cached: Option<CachedModels>,
+    task: tokio::task::JoinHandle<Result<Vec<String>>>,
+}
+
 pub struct SetupApp {
     pub state: SetupState,
     pub providers: Vec<SetupProviderEntry>,
     pub selected_provider_idx: usize,
     pub config_provider_idx: Option<usize>,
+    pub config_field: ProviderConfigField,
     pub guided_provider_order: Vec<usize>,
     pub guided_provider_pos: usize,
     pub provider_model_cursor: usize,
+    /// Executor routes: the primary model that writes commands, then its
+    /// fallbacks.
     pub model_order: Vec<ModelRouteDraft>,
     pub model_order_cursor: usize,
+    /// Router routes, drawn from the same models as `model_order`.
+    pub router_order: Vec<ModelRouteDraft>,
+    pub router_order_cursor: usize,
+    /// Set once the router order was reordered by hand or loaded from a
+    /// config that kept it apart; until then it follows the suggestion.
+    pub router_order_custom: bool,
     pub available_themes: Vec<(&'static str, &'static str)>,
     pub selected_theme_idx: usize,
     pub config: Config,
     pub theme: Theme,
+    /// Problems found while loading config.toml, shown above every step.
+    pub issues: Vec<ConfigIssue>,
+    pub plugins: Vec<Arc<dyn Plugin>>,
+    pub plugin_rows: Vec<PluginHealthRow>,
+    pub plugin_cursor: usize,
+    /// Outcome of the last INSTALL/UPDATE, instructions included.
+    pub plugin_message: Option<String>,
+    pub install_queue: InstallQueue,
+    /// `--read-only`: installers are not started.
+    pub read_only: bool,
+    /// The plugin settings page being edited.
+    pub plugin_settings: Option<PluginSettingsDraft>,
+    pub plugin_settings_field: PluginSettingsField,
+    /// The safety page being edited.
+    pub safety: Option<SafetyDraft>,
+    /// Plugin or safety settings were written to config.toml, so leaving
+    /// the panel keeps them.
+    pub settings_saved: bool,
+    /// Set by REFRESH on the models step: the next fetch skips the cache.
+    pub refresh_models: bool,
+    pub model_fetch_failure: Option<ModelFetchFailure>,
+    pub model_fetch: Option<ModelFetch>,
+    /// Providers of the guided flow passed over on the models step; their
+    /// settings are saved as they were.
+    pub skipped_providers: Vec<usize>,
+    /// Keys offered on the import step, each with whether it is ticked.
+    pub found_keys: Vec<(FoundKey, bool)>,
+    pub found_key_cursor: usize,
+    pub sign_in: Option<SignIn>,
     pub dirty: bool,
 }
 
@@ -139,11 +363,15 @@ impl SetupApp {
             providers,
             selected_provider_idx: 0,
             config_provider_idx: None,
+            config_field: ProviderConfigField::ApiKey,
             guided_provider_order: Vec::new(),
             guided_provider_pos: 0,
             provider_model_cursor: 0,
             model_order: Vec::new(),
             model_order_cursor: 0,
+            router_order: Vec::new(),
+            router_order_cursor: 0,
+            router_order_custom: false,
             available_themes: vec![
                 ("auto", "Auto (Follow system appearance)"),
                 ("dark", "Dark (Blue on charcoal)"),
@@ -151,8 +379,26 @@ impl SetupApp {
                 ("light", "Light (Clean blue/white for light terminals)"),
             ],
             selected_theme_idx: 0,
-            theme: Theme::from_config(&config.theme),
+            theme: Theme::from_config(&config.theme).with_plain(config.plain_ui),
+            plugins: builtin_plugins(&config),
             config,
+            issues: Vec::new(),
+            plugin_rows: Vec::new(),
+            plugin_cursor: 0,
+            plugin_message: None,
+            read_only: false,
+            install_queue: InstallQueue::default(),
+            plugin_settings: None,
+            plugin_settings_field: PluginSettingsField::DefaultFlags,
+            safety: None,
+            settings_saved: false,
+            refresh_models: false,
+            model_fetch_failure: None,
+            model_fetch: None,
+            skipped_providers: Vec::new(),
+            found_keys: Vec::new(),
+            found_key_cursor: 0,
+            sign_in: None,
             dirty: true,
         };
 
@@ -164,34 +410,55 @@ impl SetupApp {
             app.selected_theme_idx = idx;
         }
 
-        let mut seeded = false;
-        if !app.config.models.executor_routes.is_empty() {
-            for route in &app.config.models.executor_routes {
-                if let Some((provider_idx, _)) = app
-                    .providers
-                    .iter()
-                    .enumerate()
-                    .find(|(_, p)| p.kind == route.provider)
-                {
-                    if route.model.trim().is_empty() {
-                        continue;
-                    }
-                    app.model_order.push(ModelRouteDraft {
-                        provider_idx,
-                        model: route.model.clone(),
-                    });
-                    seeded = true;
-                }
-            }
-        }
-
-        if !seeded {
+        app.model_order = route_drafts(&app.config.models.executor_routes, &app.providers);
+        if app.model_order.is_empty() {
             app.update_model_order_from_active();
         }
+        if app.config.models.router_routes != app.config.models.executor_routes {
+            app.router_order = route_drafts(&app.config.models.router_routes, &app.providers);
+            app.router_order_custom = !app.router_order.is_empty();
+        }
+        app.sync_router_order();
 
         app
     }
 
+    /// Opens on the step that fixes the first issue. A malformed base URL is
+    /// reset to the provider default so saving repairs it.
+    pub fn focus_issues(&mut self, issues: Vec<ConfigIssue>) {
+        for issue in &issues {
+            if let ConfigField::BaseUrl(kind) = issue.field {
+                if let Some(entry) = self.providers.iter_mut().find(|p| p.kind == kind) {
+                    entry.base_url = kind.default_base_url().to_string();
+                }
+            }
+        }
+        if let Some(first) = issues.first() {
+            self.state = match &first.field {
+                ConfigField::Routes => SetupState::ModelOrderSelection,
+                ConfigField::Theme => SetupState::ThemeSelection,
+                ConfigField::Section(key) if key == "safety" => {
+                    self.open_safety();
+                    SetupState::Safety
+                }
+                _ => SetupState::ProviderSelection,
+            };
+            let kind = match first.field {
+                ConfigField::Provider(Some(kind))
+                | ConfigField::BaseUrl(kind)
+                | ConfigField::Network(kind) => Some(kind),
+                _ => None,
+            };
+            if let Some(idx) =
+                kind.and_then(|kind| self.providers.iter().position(|p| p.kind == kind))
+            {
+                self.selected_provider_idx = idx;
+            }
+        }
+        self.issues = issues;
+        self.dirty = true;
+    }
+
     pub async fn refresh_runtime_statuses(&mut self) {
         let client = match reqwest::Client::builder()
             .no_proxy()
@@ -219,6 +486,241 @@ impl SetupApp {
         }
     }
 
+    pub async fn open_plugin_health(&mut self) {
+        self.state = SetupState::PluginHealth;
+        self.plugin_message = None;
+        self.refresh_plugin_health().await;
+    }
+
+    pub async fn refresh_plugin_health(&mut self) {
+        let successes = load_plugin_successes().await.unwrap_or_default();
+        let mut rows = Vec::with_capacity(self.plugins.len());
+        for plugin in &self.plugins {
+            rows.push(PluginHealthRow {
+                name: plugin.name().to_string(),
+                health: ToolHealth::check(plugin.as_ref()).await,
+                last_success: successes
+                    .get(plugin.name())
+                    .map(|at| at.chars().take(16).collect::<String>().replace('T', " ")),
+            });
+        }
+        self.plugin_rows = rows;
+        self.plugin_cursor = self
+            .plugin_cursor
+            .min(self.plugin_rows.len().saturating_sub(1));
+        self.dirty = true;
+    }
+
+    /// The install, or update when the tool is there, of the selected
+    /// plugin. Without a package manager for it, the manual instructions
+    /// are shown instead.
+    fn selected_install_plan(&mut self) -> Option<InstallPlan> {
+        let plugin = self.plugins.get(self.plugin_cursor)?.clone();
+        if plugin.built_in() && plugin.packages().is_empty() {
+            self.plugin_message = Some(format!(
+                "{} is built into Dexter: there is nothing to install.",
+                plugin.name()
+            ));
+            return None;
+        }
+        let installed = self
+            .plugin_rows
+            .get(self.plugin_cursor)
+            .is_some_and(|row| row.health.installed);
+        let plan = InstallPlan::for_packages(plugin.name(), plugin.packages(), installed);
+        if plan.is_none() {
+            self.plugin_message = Some(manual_instructions(
+                plugin.name(),
+                plugin.packages(),
+                plugin.install_note(),
+            ));
+        }
+        plan
+    }
+
+    /// Adds the selected plugin to the install queue, or takes it off.
+    pub fn toggle_selected_install(&mut self) {
+        if let Some(plan) = self.selected_install_plan() {
+            self.plugin_message = None;
+            self.install_queue.toggle(plan);
+        }
+    }
+
+    /// Asks to run the queue, queueing the selected plugin first when
+    /// nothing is queued yet.
+    pub fn confirm_installs(&mut self) {
+        if self.read_only {
+            self.plugin_message = Some("Installs are off with --read-only.".to_string());
+            return;
+        }
+        if self.install_queue.pending().next().is_none() {
+            self.toggle_selected_install();
+        }
+        if self.install_queue.pending().next().is_some() {
+            self.state = SetupState::InstallConfirm;
+        }
+    }
+
+    pub fn start_installs(&mut self) {
+        self.state = SetupState::Installing;
+        self.install_queue.start_next();
+    }
+
+    /// Takes in install output; checks the tools again once the queue ends.
+    pub async fn poll_installs(&mut self) {
+        match self.install_queue.poll() {
+            QueueUpdate::Unchanged => {}
+            QueueUpdate::Progress => self.dirty = true,
+            QueueUpdate::Finished => self.refresh_plugin_health().await,
+        }
+    }
+
+    /// Back to the plugin health page from the progress view, cancelling
+    /// what is still running or queued.
+    pub async fn leave_installs(&mut self) {
+        self.install_queue.cancel();
+        self.install_queue.clear_finished();
+        self.state = SetupState::PluginHealth;
+        self.refresh_plugin_health().await;
+    }
+
+    /// Opens the settings page of the plugin under the cursor.
+    pub fn open_plugin_settings(&mut self) {
+        let Some(plugin) = self.plugins.get(self.plugin_cursor) else {
+            return;
+        };
+        let settings = self.config.plugin_settings(plugin.name());
+        self.plugin_settings = Some(PluginSettingsDraft::new(plugin.name(), settings));
+        self.plugin_settings_field = PluginSettingsField::DefaultFlags;
+        self.plugin_message = None;
+        self.state = SetupState::PluginSettings;
+    }
+
+    /// Puts the page's settings in the config; settings left at their
+    /// defaults drop the plugin's table. Returns the plugin's name.
+    pub fn apply_plugin_settings(&mut self) -> Option<String> {
+        let draft = self.plugin_settings.take()?;
+        let settings = draft.to_settings();
+        if settings.is_default() {
+            self.config.plugins.remove(&draft.plugin);
+        } else {
+            self.config.plugins.insert(draft.plugin.clone(), settings);
+        }
+        self.plugins = builtin_plugins(&self.config);
+        self.state = SetupState::PluginHealth;
+        Some(draft.plugin)
+    }
+
+    /// `apply_plugin_settings`, then writes config.toml right away: they
+    /// don't depend on the provider steps.
+    pub async fn save_plugin_settings(&mut self) {
+        let Some(plugin) = self.apply_plugin_settings() else {
+            return;
+        };
+        self.plugin_message = Some(match self.config.save().await {
+            Ok(()) => {
+                self.settings_saved = true;
+                format!("Saved the {} settings.", plugin)
+            }
+            Err(e) => format!("Couldn't save the {} settings: {}", plugin, e),
+        });
+    }
+
+    /// Opens the safety page on the config's rules.
+    pub fn open_safety(&mut self) {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        self.safety = Some(SafetyDraft::new(&self.config, &self.plugins, cwd));
+        self.state = SetupState::Safety;
+    }
+
+    /// Puts the page's rules in the config and writes config.toml right
+    /// away, like plugin settings. Invalid patterns keep it from saving.
+    pub async fn save_safety(&mut self) {
+        let Some(draft) = self.safety.as_mut() else {
+            return;
+        };
+        let policy = draft.to_policy();
+        if let Some(idx) =
+            (0..draft.policy.blocked_patterns.len()).find(|idx| draft.pattern_error(*idx).is_some())
+        {
+            draft.message = Some(format!("Pattern {} is not a valid regex.", idx + 1));
+            return;
+        }
+        draft.policy = policy.clone();
+        self.config.safety = policy;
+        self.config.confirm_timeout.minutes = draft.confirm_minutes;
+        draft.message = Some(match self.config.save().await {
+            Ok(()) => {
+                self.settings_saved = true;
+                self.issues
+                    .retain(|issue| issue.field != ConfigField::Section("safety".to_string()));
+                "Saved the safety rules.".to_string()
+            }
+            Err(e) => format!("Couldn't save the safety rules: {}", e),
+        });
+    }
+
+    /// Opens the import step with the keys found on this machine that
+    /// aren't configured yet. Each provider without a key has its first
+    /// find ticked. Returns whether anything was found.
+    pub fn open_key_import(&mut self) -> bool {
+        self.open_key_import_from(&KeySources::system())
+    }
+
+    pub fn open_key_import_from(&mut self, sources: &KeySources) -> bool {
+        let mut ticked = Vec::new();
+        self.found_keys = find_keys(sources)
+            .into_iter()
+            .filter(|found| {
+                !self
+                    .providers
+                    .iter()
+                    .any(|p| p.kind == found.kind && p.api_key.trim() == found.api_key)
+            })
+            .map(|found| {
+                let has_key = self
+                    .providers
+                    .iter()
+                    .any(|p| p.kind == found.kind && p.has_key());
+                let tick = !has_key && !ticked.contains(&found.kind);
+                if tick {
+                    ticked.push(found.kind);
+                }
+                (found, tick)
+            })
+            .collect();
+        self.found_key_cursor = 0;
+        self.state = SetupState::KeyImport;
+        !self.found_keys.is_empty()
+    }
+
+    /// Ticks or unticks the key under the cursor; ticking it unticks any
+    /// other key for the same provider.
+    pub fn toggle_found_key(&mut self) {
+        let Some((found, ticked)) = self.found_keys.get(self.found_key_cursor).cloned() else {
+            return;
+        };
+        for (idx, (other, tick)) in self.found_keys.iter_mut().enumerate() {
+            if idx == self.found_key_cursor {
+                *tick = !ticked;
+            } else if other.kind == found.kind && !ticked {
+                *tick = false;
+            }
+        }
+    }
+
+    /// Sets the ticked keys on their providers and enables them.
+    pub fn import_found_keys(&mut self) {
+        for (found, _) in self.found_keys.iter().filter(|(_, ticked)| *ticked) {
+            if let Some(provider) = self.providers.iter_mut().find(|p| p.kind == found.kind) {
+                provider.api_key = found.api_key.clone();
+                provider.enabled = true;
+            }
+        }
+        self.found_keys.clear();
+        self.found_key_cursor = 0;
+    }
+
     pub fn provider_selection_len(&self) -> usize {
         self.providers.len()
     }
@@ -250,13 +752,27 @@ impl SetupApp {
 
         self.guided_provider_order = order;
         self.guided_provider_pos = 0;
+        self.skipped_providers.clear();
         self.config_provider_idx = self.current_guided_provider_idx();
+        self.config_field = self.first_config_field();
         self.provider_model_cursor = 0;
         self.state = SetupState::ProviderConfig;
         Ok(())
     }
 
+    /// Providers without an API key start on the proxy field.
+    fn first_config_field(&self) -> ProviderConfigField {
+        match self.config_provider_idx {
+            Some(idx) if !self.providers[idx].requires_api_key() => ProviderConfigField::Proxy,
+            _ => ProviderConfigField::ApiKey,
+        }
+    }
+
     pub fn reset_guided_flow(&mut self) {
+        if let Some(fetch) = self.model_fetch.take() {
+            fetch.task.abort();
+        }
+        self.skipped_providers.clear();
         self.guided_provider_order.clear();
         self.guided_provider_pos = 0;
         self.config_provider_idx = None;
@@ -267,6 +783,7 @@ impl SetupApp {
         if self.guided_provider_pos + 1 < self.guided_provider_order.len() {
             self.guided_provider_pos += 1;
             self.config_provider_idx = self.current_guided_provider_idx();
+            self.config_field = self.first_config_field();
             self.state = SetupState::ProviderConfig;
         } else {
             self.guided_provider_pos = 0;
@@ -284,6 +801,11 @@ impl SetupApp {
             self.state = SetupState::FetchingProviderModels;
         } else if self.ensure_at_least_one_route() {
             self.state = SetupState::ModelOrderSelection;
+        } else if !self.skipped_providers.is_empty() {
+            self.state = SetupState::Error(
+                "No active models: the skipped providers have none selected. Retry them or select models for another provider."
+                    .to_string(),
+            );
         } else {
             self.state = SetupState::Error(
                 "No active models. Select at least one model for an enabled provider.".to_string(),
@@ -374,13 +896,112 @@ impl SetupApp {
         true
     }
 
-    pub async fn fetch_models_for_current_provider(&mut self) -> Result<()> {
+    /// Takes the current provider's cached model list, or starts fetching
+    /// it in the background; `poll_model_fetch` picks up the outcome.
+    pub fn start_model_fetch(&mut self) -> Result<()> {
         let provider_idx = self
             .config_provider_idx
             .ok_or_else(|| anyhow!("No provider selected for model fetch"))?;
 
         let provider_cfg = self.providers[provider_idx].to_provider_config_for_fetch();
-        let primary = self.providers[provider_idx]
+        let primary = self.primary_model(provider_idx, &provider_cfg);
+
+        // Cached lists are reused until REFRESH.
+        let cached = ModelCache::load().get(&provider_cfg).cloned();
+        let refresh = std::mem::take(&mut self.refresh_models);
+        self.model_fetch_failure = None;
+        if let Some(cached) = cached.clone().filter(|_| !refresh) {
+            self.apply_models(provider_idx, Some(cached));
+            self.state = SetupState::ProviderModelSelection;
+            return Ok(());
+        }
+        let client =
+            LlmClient::with_routes(vec![provider_cfg.clone()], Vec::new(), primary, Vec::new());
+        self.model_fetch = Some(ModelFetch {
+            provider_idx,
+            provider_cfg,
+            cached,
+            task: tokio::spawn(async move { client.list_models().await }),
+        });
+        Ok(())
+    }
+
+    /// Moves on once the fetch finished: to the model list, or to the
+    /// failure page with `model_fetch_failure`, which changes nothing until
+    /// `use_fallback_models`.
+    pub async fn poll_model_fetch(&mut self) {
+        if !self
+            .model_fetch
+            .as_ref()
+            .is_some_and(|f| f.task.is_finished())
+        {
+            return;
+        }
+        let Some(fetch) = self.model_fetch.take() else {
+            return;
+        };
+        self.dirty = true;
+        let provider_idx = fetch.provider_idx;
+        let listed = match fetch.task.await {
+            Ok(listed) => listed,
+            Err(e) => Err(anyhow!("Model fetch stopped: {}", e)),
+        };
+        if self.providers[provider_idx].kind == ProviderKind::Ollama {
+            self.providers[provider_idx].runtime_ready = Some(listed.is_ok());
+        }
+        match listed {
+            Ok(models) => {
+                let mut cache = ModelCache::load();
+                let entry = cache.insert(&fetch.provider_cfg, models).clone();
+                if let Err(e) = cache.save() {
+                    tracing::warn!(error = %e, "failed to save the model list cache");
+                }
+                self.apply_models(provider_idx, Some(entry));
+                self.state = SetupState::ProviderModelSelection;
+            }
+            Err(e) => {
+                tracing::warn!(error = %format!("{:#}", e), "model list fetch failed");
+                self.model_fetch_failure = Some(ModelFetchFailure {
+                    details: e
+                        .chain()
+                        .find_map(|cause| cause.downcast_ref::<ModelListError>())
+                        .cloned(),
+                    message: format!("{:#}", e),
+                    cached: fetch.cached,
+                });
+                self.state = SetupState::ModelFetchFailed;
+            }
+        }
+    }
+
+    /// Stops a running fetch; Esc on the fetching step.
+    pub fn cancel_model_fetch(&mut self) {
+        if let Some(fetch) = self.model_fetch.take() {
+            fetch.task.abort();
+        }
+        self.model_fetch_failure = None;
+        self.state = SetupState::ProviderConfig;
+    }
+
+    /// Passes over the current provider while its models are fetched or
+    /// after the fetch failed, and goes on with the next one. Its settings
+    /// and selected models stay as they were.
+    pub fn skip_provider(&mut self) {
+        if let Some(fetch) = self.model_fetch.take() {
+            fetch.task.abort();
+        }
+        self.model_fetch_failure = None;
+        if let Some(idx) = self.config_provider_idx {
+            if !self.skipped_providers.contains(&idx) {
+                self.skipped_providers.push(idx);
+            }
+        }
+        self.update_model_order_from_active();
+        self.advance_provider_models();
+    }
+
+    fn primary_model(&self, provider_idx: usize, provider_cfg: &ProviderConfig) -> String {
+        self.providers[provider_idx]
             .active_models
             .first()
             .cloned()
@@ -391,24 +1012,81 @@ impl SetupApp {
                     .cloned()
             })
             .or_else(|| provider_cfg.models.first().cloned())
-            .unwrap_or_else(|| "gemini-2.5-flash-lite".to_string());
+            .unwrap_or_else(|| "gemini-2.5-flash-lite".to_string())
+    }
 
+    /// Asks the current provider's gateway for a device code and waits for
+    /// it in the background; `poll_sign_in` picks up the outcome.
+    pub async fn start_sign_in(&mut self) {
+        let Some(provider_idx) = self.config_provider_idx else {
+            return;
+        };
+        let provider_cfg = self.providers[provider_idx].to_provider_config_for_fetch();
+        let primary = self.primary_model(provider_idx, &provider_cfg);
         let client = LlmClient::with_routes(vec![provider_cfg], Vec::new(), primary, Vec::new());
-
-        let discovered_models = client.list_models().await;
-        let discovered_models = match discovered_models {
-            Ok(models) => {
-                if self.providers[provider_idx].kind == ProviderKind::Ollama {
-                    self.providers[provider_idx].runtime_ready = Some(true);
-                }
-                models
+        match client.start_sign_in().await {
+            Ok(code) => {
+                let waiting = code.clone();
+                let task = tokio::spawn(async move { client.finish_sign_in(&waiting).await });
+                self.sign_in = Some(SignIn {
+                    provider_idx,
+                    code,
+                    task,
+                });
+                self.state = SetupState::SigningIn;
             }
-            Err(_) => {
-                if self.providers[provider_idx].kind == ProviderKind::Ollama {
-                    self.providers[provider_idx].runtime_ready = Some(false);
-                }
-                self.providers[provider_idx].kind.default_models()
+            Err(e) => {
+                self.state = SetupState::Error(format!("Sign-in failed: {:#}", e));
             }
+        }
+    }
+
+    /// Moves on once the sign-in finished: to the models on success, to
+    /// the error otherwise.
+    pub async fn poll_sign_in(&mut self) {
+        if !self.sign_in.as_ref().is_some_and(|s| s.task.is_finished()) {
+            return;
+        }
+        let Some(sign_in) = self.sign_in.take() else {
+            return;
+        };
+        self.dirty = true;
+        match sign_in.task.await {
+            Ok(Ok(())) => {
+                self.providers[sign_in.provider_idx].signed_in = true;
+                self.advance_provider_config();
+            }
+            Ok(Err(e)) => self.state = SetupState::Error(format!("{:#}", e)),
+            Err(e) => self.state = SetupState::Error(format!("Sign-in failed: {}", e)),
+        }
+    }
+
+    /// Back to the provider config step, giving up on the code.
+    pub fn cancel_sign_in(&mut self) {
+        if let Some(sign_in) = self.sign_in.take() {
+            sign_in.task.abort();
+        }
+        self.state = SetupState::ProviderConfig;
+    }
+
+    /// Passes over a failed fetch with the cached list, or the built-in one
+    /// when nothing is cached.
+    pub fn use_fallback_models(&mut self) {
+        let Some(provider_idx) = self.config_provider_idx else {
+            return;
+        };
+        let cached = self
+            .model_fetch_failure
+            .take()
+            .and_then(|failure| failure.cached);
+        self.apply_models(provider_idx, cached);
+    }
+
+    fn apply_models(&mut self, provider_idx: usize, fetched: Option<CachedModels>) {
+        self.providers[provider_idx].models_age = fetched.as_ref().map(CachedModels::age);
+        let discovered_models = match fetched {
+            Some(fetched) => fetched.models,
+            None => self.providers[provider_idx].kind.default_models(),
         };
 
         let mut available_models = dedup_models(discovered_models);
@@ -432,7 +1110,6 @@ impl SetupApp {
         self.providers[provider_idx].available_models = available_models;
         self.providers[provider_idx].active_models = dedup_models(active_models);
         self.provider_model_cursor = 0;
-        Ok(())
     }
 
     pub fn toggle_model_selection(&mut self) {
@@ -485,6 +1162,65 @@ impl SetupApp {
         }
     }
 
+    /// Router-step twins of the moves above; reordering by hand keeps the
+    /// order from then on.
+    pub fn move_router_order_up(&mut self) {
+        if self.router_order_cursor > 0 && self.router_order_cursor < self.router_order.len() {
+            self.router_order
+                .swap(self.router_order_cursor, self.router_order_cursor - 1);
+            self.router_order_cursor -= 1;
+            self.router_order_custom = true;
+        }
+    }
+
+    pub fn move_router_order_down(&mut self) {
+        if self.router_order_cursor + 1 < self.router_order.len() {
+            self.router_order
+                .swap(self.router_order_cursor, self.router_order_cursor + 1);
+            self.router_order_cursor += 1;
+            self.router_order_custom = true;
+        }
+    }
+
+    /// Routes with the same order as the executor, as setup used to save.
+    pub fn mirror_model_order(&mut self) {
+        self.router_order = self.model_order.clone();
+        self.router_order_custom = true;
+        self.router_order_cursor = 0;
+    }
+
+    /// Drops the hand-made router order for the suggested one.
+    pub fn reset_router_order(&mut self) {
+        self.router_order_custom = false;
+        self.router_order_cursor = 0;
+        self.sync_router_order();
+    }
+
+    /// Brings the router order in line with the active models. A custom
+    /// order keeps its ranking and gains newly activated models at the
+    /// end; otherwise it is the suggestion: lightweight models first.
+    pub fn sync_router_order(&mut self) {
+        if self.router_order_custom {
+            let mut merged: Vec<ModelRouteDraft> = self
+                .router_order
+                .iter()
+                .filter(|route| self.model_order.contains(route))
+                .cloned()
+                .collect();
+            for route in &self.model_order {
+                if !merged.contains(route) {
+                    merged.push(route.clone());
+                }
+            }
+            self.router_order = merged;
+        } else {
+            self.router_order = suggested_router_order(&self.model_order, &self.providers);
+        }
+        if self.router_order_cursor >= self.router_order.len() {
+            self.router_order_cursor = self.router_order.len().saturating_sub(1);
+        }
+    }
+
     pub fn ensure_at_least_one_route(&mut self) -> bool {
         self.update_model_order_from_active() && !self.model_order.is_empty()
     }
@@ -497,41 +1233,29 @@ impl SetupApp {
             .iter()
             .map(|p| p.to_provider_config())
             .collect();
-        self.config.providers = providers_cfg.clone();
-
-        self.config.api_keys.gemini = providers_cfg
-            .iter()
-            .find(|p| matches!(p.kind, ProviderKind::Gemini))
-            .and_then(|p| p.api_key.clone());
-        self.config.api_keys.deepseek = providers_cfg
-            .iter()
-            .find(|p| matches!(p.kind, ProviderKind::Deepseek))
-            .and_then(|p| p.api_key.clone());
-        self.config.api_keys.base_url = None;
-
-        let routes: Vec<ModelRoute> = self
-            .model_order
-            .iter()
-            .filter_map(|r| {
-                self.providers.get(r.provider_idx).map(|p| ModelRoute {
-                    provider: p.kind,
-                    model: r.model.clone(),
-                })
-            })
-            .collect();
+        self.config.providers = providers_cfg;
 
-        self.config.models.router_routes = routes.clone();
-        self.config.models.executor_routes = routes.clone();
+        self.sync_router_order();
+        let routes = model_routes(&self.model_order, &self.providers);
+        let router_routes = model_routes(&self.router_order, &self.providers);
 
         if let Some(primary) = routes.first() {
-            self.config.models.router_model = primary.model.clone();
             self.config.models.executor_model = primary.model.clone();
         }
-
-        let fallback_models =
+        if let Some(primary) = router_routes.first() {
+            self.config.models.router_model = primary.model.clone();
+        }
+        self.config.models.executor_fallback_models =
             dedup_models(routes.iter().skip(1).map(|r| r.model.clone()).collect());
-        self.config.models.router_fallback_models = fallback_models.clone();
-        self.config.models.executor_fallback_models = fallback_models;
+        self.config.models.router_fallback_models = dedup_models(
+            router_routes
+                .iter()
+                .skip(1)
+                .map(|r| r.model.clone())
+                .collect(),
+        );
+        self.config.models.executor_routes = routes;
+        self.config.models.router_routes = router_routes;
 
         let theme_id = self.available_themes[self.selected_theme_idx].0;
         self.config.theme = theme_id.to_string();
@@ -566,6 +1290,9 @@ pub fn build_provider_entries(config: &Config) -> Vec<SetupProviderEntry> {
                 base.base_url = existing_provider.base_url.clone();
                 base.auth = existing_provider.auth;
                 base.models = dedup_models(existing_provider.models.clone());
+                base.proxy = existing_provider.proxy.clone();
+                base.ca_cert = existing_provider.ca_cert.clone();
+                base.oauth = existing_provider.oauth.clone();
                 enabled = existing_provider.enabled;
             }
 
@@ -589,6 +1316,12 @@ pub fn build_provider_entries(config: &Config) -> Vec<SetupProviderEntry> {
                 available_models: dedup_models(available_models),
                 active_models: dedup_models(active_models),
                 runtime_ready: None,
+                proxy: base.proxy.unwrap_or_default(),
+                ca_cert: base.ca_cert.unwrap_or_default(),
+                signed_in: base.auth == ProviderAuth::OAuth
+                    && base.oauth.as_ref().is_some_and(is_signed_in),
+                oauth: base.oauth,
+                models_age: None,
             }
         })
         .collect::<Vec<_>>();
@@ -633,6 +1366,53 @@ pub fn dedup_models(models: Vec<String>) -> Vec<String> {
     out
 }
 
+/// Config routes as drafts, skipping blank models and providers setup does
+/// not list.
+fn route_drafts(routes: &[ModelRoute], providers: &[SetupProviderEntry]) -> Vec<ModelRouteDraft> {
+    routes
+        .iter()
+        .filter(|route| !route.model.trim().is_empty())
+        .filter_map(|route| {
+            let provider_idx = providers.iter().position(|p| p.kind == route.provider)?;
+            Some(ModelRouteDraft {
+                provider_idx,
+                model: route.model.clone(),
+            })
+        })
+        .collect()
+}
+
+fn model_routes(order: &[ModelRouteDraft], providers: &[SetupProviderEntry]) -> Vec<ModelRoute> {
+    order
+        .iter()
+        .filter_map(|r| {
+            providers.get(r.provider_idx).map(|p| ModelRoute {
+                provider: p.kind,
+                model: r.model.clone(),
+            })
+        })
+        .collect()
+}
+
+/// The routes of `order` ranked for routing: local models first, then the
+/// small hosted tiers, then the rest. Ties go by provider and model name,
+/// so the suggestion doesn't depend on how the executor list was arranged.
+pub fn suggested_router_order(
+    order: &[ModelRouteDraft],
+    providers: &[SetupProviderEntry],
+) -> Vec<ModelRouteDraft> {
+    let mut suggested = order.to_vec();
+    suggested.sort_by_cached_key(|draft| {
+        let tier = match model_routes(std::slice::from_ref(draft), providers).first() {
+            Some(route) if route.provider == ProviderKind::Ollama => 0,
+            Some(route) if route.is_lightweight() => 1,
+            _ => 2,
+        };
+        (tier, draft.provider_idx, draft.model.clone())
+    });
+    suggested
+}
+
 pub fn model_route_display(route: &ModelRouteDraft, providers: &[SetupProviderEntry]) -> String {
     let provider_name = providers
         .get(route.provider_idx)
@@ -644,11 +1424,16 @@ pub fn model_route_display(route: &ModelRouteDraft, providers: &[SetupProviderEn
 #[cfg(test)]
 mod tests {
     use super::*;
+    use dexter_core::ConflictPolicy;
 
     #[test]
-    fn setup_transition_model_order_to_theme() {
+    fn setup_transition_model_order_to_router_order() {
         assert_eq!(
             SetupState::on_model_order_enter(true),
+            SetupState::RouterOrderSelection
+        );
+        assert_eq!(
+            SetupState::on_router_order_enter(),
             SetupState::ThemeSelection
         );
     }
@@ -662,4 +1447,215 @@ mod tests {
     fn setup_transition_confirm_to_saving() {
         assert_eq!(SetupState::on_confirm_enter(), SetupState::Saving);
     }
+
+    #[test]
+    fn issues_focus_the_step_that_fixes_them() {
+        let (config, issues) = dexter_core::config::parse_lenient(
+            "[[providers]]\nkind = \"groq\"\napi_key = \"k\"\nbase_url = \"groq.com\"\n",
+        );
+        let mut app = SetupApp::new(config, false);
+        app.focus_issues(issues);
+        assert_eq!(app.state, SetupState::ProviderSelection);
+        let groq = &app.providers[app.selected_provider_idx];
+        assert_eq!(groq.kind, ProviderKind::Groq);
+        assert_eq!(groq.base_url, ProviderKind::Groq.default_base_url());
+
+        let (config, issues) = dexter_core::config::parse_lenient("theme = \"neon\"");
+        let mut app = SetupApp::new(config, false);
+        app.focus_issues(issues);
+        assert_eq!(app.state, SetupState::ThemeSelection);
+    }
+
+    #[test]
+    fn router_routes_default_to_lightweight_models_and_save_apart() {
+        let (config, _) = dexter_core::config::parse_lenient(
+            r#"
+[[providers]]
+kind = "anthropic"
+api_key = "k"
+models = ["claude-sonnet-4-5"]
+
+[[providers]]
+kind = "ollama"
+models = ["llama3.2"]
+
+[models]
+executor_routes = [
+    { provider = "anthropic", model = "claude-sonnet-4-5" },
+    { provider = "ollama", model = "llama3.2" },
+]
+"#,
+        );
+        let mut app = SetupApp::new(config.clone(), false);
+        let models = |order: &[ModelRouteDraft]| -> Vec<String> {
+            order.iter().map(|r| r.model.clone()).collect()
+        };
+        assert_eq!(models(&app.model_order), ["claude-sonnet-4-5", "llama3.2"]);
+        assert_eq!(models(&app.router_order), ["llama3.2", "claude-sonnet-4-5"]);
+
+        app.mirror_model_order();
+        assert!(app.router_order_custom);
+        assert_eq!(app.router_order, app.model_order);
+        app.reset_router_order();
+        assert_eq!(models(&app.router_order), ["llama3.2", "claude-sonnet-4-5"]);
+
+        // The suggestion is the same whatever order the executor list is in.
+        app.model_order.reverse();
+        let anthropic = app
+            .providers
+            .iter()
+            .position(|p| p.kind == ProviderKind::Anthropic)
+            .unwrap();
+        app.providers[anthropic]
+            .active_models
+            .push("claude-haiku-4-5".to_string());
+        app.update_model_order_from_active();
+        app.sync_router_order();
+        assert_eq!(
+            models(&app.router_order),
+            ["llama3.2", "claude-haiku-4-5", "claude-sonnet-4-5"]
+        );
+        app.model_order.reverse();
+        app.sync_router_order();
+        assert_eq!(
+            models(&app.router_order),
+            ["llama3.2", "claude-haiku-4-5", "claude-sonnet-4-5"]
+        );
+
+        let mut saved = config;
+        saved.models.router_routes = vec![ModelRoute {
+            provider: ProviderKind::Anthropic,
+            model: "claude-sonnet-4-5".to_string(),
+        }];
+        let app = SetupApp::new(saved, false);
+        assert!(app.router_order_custom);
+        // A custom order keeps its ranking and gains the other active models.
+        assert_eq!(models(&app.router_order), ["claude-sonnet-4-5", "llama3.2"]);
+    }
+
+    #[test]
+    fn plugin_settings_pages_edit_the_plugins_table() {
+        let (config, _) = dexter_core::config::parse_lenient(
+            "[plugins.ffmpeg]\ndefault_flags = \"-hide_banner\"\n",
+        );
+        let mut app = SetupApp::new(config, false);
+        app.plugin_cursor = app
+            .plugins
+            .iter()
+            .position(|p| p.name() == "ffmpeg")
+            .unwrap();
+        app.open_plugin_settings();
+        assert_eq!(app.state, SetupState::PluginSettings);
+        let draft = app.plugin_settings.as_mut().unwrap();
+        assert_eq!(draft.default_flags, "-hide_banner");
+        draft
+            .text_mut(PluginSettingsField::OutputDir)
+            .unwrap()
+            .push_str(" exports ");
+        draft.never_overwrite = true;
+        assert_eq!(app.apply_plugin_settings().as_deref(), Some("ffmpeg"));
+        assert_eq!(app.state, SetupState::PluginHealth);
+        let ffmpeg = app.plugins.iter().find(|p| p.name() == "ffmpeg").unwrap();
+        assert_eq!(ffmpeg.settings().output_dir.as_deref(), Some("exports"));
+        assert_eq!(
+            ConflictPolicy::Overwrite.for_plugin(ffmpeg.settings()),
+            ConflictPolicy::AutoRename
+        );
+        app.config.output_conflicts = ConflictPolicy::Overwrite;
+        assert_eq!(
+            app.config.conflict_policy_for("ffmpeg"),
+            ConflictPolicy::AutoRename
+        );
+        assert_eq!(
+            app.config.conflict_policy_for("pandoc"),
+            ConflictPolicy::Overwrite
+        );
+
+        app.open_plugin_settings();
+        let draft = app.plugin_settings.as_mut().unwrap();
+        draft.default_flags.clear();
+        draft.output_dir.clear();
+        draft.never_overwrite = false;
+        app.apply_plugin_settings();
+        assert!(app.config.plugins.is_empty());
+    }
+
+    #[test]
+    fn skipped_providers_keep_their_settings_and_the_flow_goes_on() {
+        let (config, _) = dexter_core::config::parse_lenient(
+            r#"
+[[providers]]
+kind = "anthropic"
+api_key = "k"
+models = ["claude-sonnet-4-5"]
+
+[[providers]]
+kind = "ollama"
+models = ["llama3.2"]
+"#,
+        );
+        let mut app = SetupApp::new(config, false);
+        app.start_guided_flow().unwrap();
+        let order = app.guided_provider_order.clone();
+        assert_eq!(order.len(), 2);
+        app.advance_provider_config();
+        app.advance_provider_config();
+        assert_eq!(app.state, SetupState::FetchingProviderModels);
+        assert_eq!(app.config_provider_idx, Some(order[0]));
+
+        app.skip_provider();
+        assert_eq!(app.state, SetupState::FetchingProviderModels);
+        assert_eq!(app.config_provider_idx, Some(order[1]));
+        app.state = SetupState::ModelFetchFailed;
+        app.skip_provider();
+        assert_eq!(app.state, SetupState::ModelOrderSelection);
+        assert_eq!(app.skipped_providers, order);
+        assert_eq!(app.model_order.len(), 2);
+        assert!(app
+            .providers
+            .iter()
+            .any(|p| p.kind == ProviderKind::Anthropic && p.api_key == "k"));
+
+        app.reset_guided_flow();
+        assert!(app.skipped_providers.is_empty());
+    }
+
+    #[test]
+    fn imports_ticked_keys_for_providers_without_one() {
+        let (config, _) = dexter_core::config::parse_lenient(
+            "[[providers]]\nkind = \"openai\"\napi_key = \"sk-old\"\n",
+        );
+        let mut app = SetupApp::new(config, false);
+        let env = |name: &str| match name {
+            "OPENAI_API_KEY" => Some("sk-new".to_string()),
+            "GROQ_API_KEY" => Some("gsk".to_string()),
+            "ANTHROPIC_API_KEY" => Some(String::new()),
+            _ => None,
+        };
+        let found = app.open_key_import_from(&KeySources {
+            env: &env,
+            home: None,
+            config_dir: None,
+        });
+        assert!(found);
+        assert_eq!(app.state, SetupState::KeyImport);
+        let ticked: Vec<(ProviderKind, bool)> = app
+            .found_keys
+            .iter()
+            .map(|(key, tick)| (key.kind, *tick))
+            .collect();
+        assert_eq!(
+            ticked,
+            [(ProviderKind::OpenAI, false), (ProviderKind::Groq, true)]
+        );
+
+        app.toggle_found_key();
+        app.import_found_keys();
+        let key = |kind| {
+            let provider = app.providers.iter().find(|p| p.kind == kind).unwrap();
+            (provider.api_key.clone(), provider.enabled)
+        };
+        assert_eq!(key(ProviderKind::OpenAI), ("sk-new".to_string(), true));
+        assert_eq!(key(ProviderKind::Groq), ("gsk".to_string(), true));
+    }
 }
@@ -1,12 +1,18 @@
+use dexter_core::workspace::WORKSPACE_FILE;
+use dexter_core::SafetyGuard;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Cell, Paragraph, Row, Table, Wrap},
+    widgets::{Borders, Cell, Paragraph, Row, Table, Wrap},
     Frame,
 };
 
-use crate::setup::state::{model_route_display, SetupApp, SetupState};
+use crate::setup::install::InstallStatus;
+use crate::setup::safety::SafetyRow;
+use crate::setup::state::{
+    model_route_display, PluginSettingsField, ProviderConfigField, SetupApp, SetupState,
+};
 
 pub fn mask_api_key(raw: &str) -> String {
     let value = raw.trim();
@@ -29,9 +35,66 @@ pub fn mask_api_key(raw: &str) -> String {
     format!("{}{}", "*".repeat(len - 4), suffix)
 }
 
+fn config_field_line(app: &SetupApp, field: ProviderConfigField, value: String) -> Line<'static> {
+    field_line(app, app.config_field == field, value)
+}
+
+fn field_line(app: &SetupApp, focused: bool, value: String) -> Line<'static> {
+    let value = if value.is_empty() && focused {
+        "_".to_string()
+    } else {
+        value
+    };
+    Line::from(vec![
+        Span::styled(
+            if focused { "> " } else { "  " },
+            app.theme.input_prompt_style,
+        ),
+        Span::styled(
+            value,
+            if focused {
+                app.theme.input_cursor_style
+            } else {
+                app.theme.input_text_style
+            },
+        ),
+    ])
+}
+
+const MAX_ISSUE_LINES: usize = 4;
+
+fn render_issues_banner(f: &mut Frame, app: &SetupApp, area: Rect) {
+    let mut lines: Vec<Line> = app
+        .issues
+        .iter()
+        .take(MAX_ISSUE_LINES)
+        .map(|issue| Line::from(Span::styled(format!("! {}", issue), app.theme.error_style)))
+        .collect();
+    let hidden = app.issues.len().saturating_sub(MAX_ISSUE_LINES);
+    if hidden > 0 {
+        if let Some(last) = lines.last_mut() {
+            *last = Line::from(Span::styled(
+                format!("! ...and {} more", hidden + 1),
+                app.theme.error_style,
+            ));
+        }
+    }
+    let banner = Paragraph::new(lines)
+        .block(
+            app.theme
+                .block()
+                .borders(Borders::ALL)
+                .border_style(app.theme.error_style)
+                .title(" CONFIG PROBLEMS // SAVE TO FIX "),
+        )
+        .wrap(Wrap { trim: true })
+        .style(app.theme.base_style);
+    f.render_widget(banner, area);
+}
+
 pub fn setup_ui(f: &mut Frame, app: &SetupApp) {
     // Fill the full frame so theme background also applies to top/bottom gutters.
-    f.render_widget(Block::default().style(app.theme.base_style), f.area());
+    f.render_widget(app.theme.block().style(app.theme.base_style), f.area());
 
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -47,13 +110,25 @@ pub fn setup_ui(f: &mut Frame, app: &SetupApp) {
         app.theme.header_title_style,
     ))
     .block(
-        Block::default()
+        app.theme
+            .block()
             .borders(Borders::ALL)
             .border_style(app.theme.border_style),
     )
     .style(app.theme.base_style);
     f.render_widget(header, chunks[0]);
 
+    let mut chunks = chunks.to_vec();
+    if !app.issues.is_empty() {
+        let shown = app.issues.len().min(MAX_ISSUE_LINES);
+        let areas = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(shown as u16 + 2), Constraint::Min(1)])
+            .split(chunks[1]);
+        render_issues_banner(f, app, areas[0]);
+        chunks[1] = areas[1];
+    }
+
     if app.state == SetupState::ProviderSelection {
         render_setup_provider_table(f, app, chunks[1]);
         return;
@@ -62,7 +137,10 @@ pub fn setup_ui(f: &mut Frame, app: &SetupApp) {
         render_setup_models_table(f, app, chunks[1]);
         return;
     }
-    if app.state == SetupState::ModelOrderSelection {
+    if matches!(
+        app.state,
+        SetupState::ModelOrderSelection | SetupState::RouterOrderSelection
+    ) {
         render_setup_model_order_table(f, app, chunks[1]);
         return;
     }
@@ -70,6 +148,18 @@ pub fn setup_ui(f: &mut Frame, app: &SetupApp) {
         render_setup_confirm_table(f, app, chunks[1]);
         return;
     }
+    if app.state == SetupState::PluginHealth {
+        render_plugin_health_table(f, app, chunks[1]);
+        return;
+    }
+    if app.state == SetupState::Installing {
+        render_install_progress(f, app, chunks[1]);
+        return;
+    }
+    if app.state == SetupState::Safety {
+        render_safety_page(f, app, chunks[1]);
+        return;
+    }
 
     let content_text = match &app.state {
         SetupState::Welcome => vec![
@@ -89,13 +179,22 @@ pub fn setup_ui(f: &mut Frame, app: &SetupApp) {
                 app.theme.input_cursor_style,
             )),
         ],
+        SetupState::KeyImport => key_import_lines(app),
+        SetupState::InstallConfirm => install_confirm_lines(app),
+        SetupState::PluginSettings => plugin_settings_lines(app),
         SetupState::ProviderSelection => vec![],
         SetupState::ProviderConfig => {
             if let Some(provider_idx) = app.config_provider_idx {
                 let provider = &app.providers[provider_idx];
                 let total = app.guided_provider_order.len().max(1);
                 let current = app.guided_provider_pos + 1;
-                let key_display = if provider.requires_api_key() {
+                let key_display = if provider.uses_oauth() {
+                    if provider.signed_in {
+                        "Signed in".to_string()
+                    } else {
+                        "Not signed in".to_string()
+                    }
+                } else if provider.requires_api_key() {
                     if provider.api_key.is_empty() {
                         "_".to_string()
                     } else {
@@ -104,6 +203,13 @@ pub fn setup_ui(f: &mut Frame, app: &SetupApp) {
                 } else {
                     "N/A".to_string()
                 };
+                let keys = match (provider.uses_oauth(), provider.signed_in) {
+                    (true, false) => "TAB: Next Field  ENTER: Sign In  ESC: Back to Step 1",
+                    (true, true) => {
+                        "TAB: Next Field  ENTER: Save & Next Provider  F2: Sign In Again  ESC: Back to Step 1"
+                    }
+                    _ => "TAB: Next Field  ENTER: Save & Next Provider  ESC: Back to Step 1",
+                };
                 vec![
                     Line::from(Span::styled(
                         format!("STEP 2: PROVIDERS CONFIG ({}/{})", current, total),
@@ -114,20 +220,21 @@ pub fn setup_ui(f: &mut Frame, app: &SetupApp) {
                     Line::from(format!("Runtime Enabled: {}", provider.enabled)),
                     Line::from(format!("Base URL: {}", provider.base_url)),
                     Line::from(""),
-                    Line::from(if provider.requires_api_key() {
+                    Line::from(if provider.uses_oauth() {
+                        "Sign-in (device code, tokens refresh on their own):"
+                    } else if provider.requires_api_key() {
                         "API Key (editable):"
                     } else {
                         "API Key not required for this provider"
                     }),
-                    Line::from(vec![
-                        Span::styled("> ", app.theme.input_prompt_style),
-                        Span::styled(key_display, app.theme.input_text_style),
-                    ]),
+                    config_field_line(app, ProviderConfigField::ApiKey, key_display),
                     Line::from(""),
-                    Line::from(Span::styled(
-                        "ENTER: Save & Next Provider  ESC: Back to Step 1",
-                        app.theme.header_subtitle_style,
-                    )),
+                    Line::from("Proxy (http://, socks5://, `system`, empty = direct):"),
+                    config_field_line(app, ProviderConfigField::Proxy, provider.proxy.clone()),
+                    Line::from("CA certificate PEM path (optional):"),
+                    config_field_line(app, ProviderConfigField::CaCert, provider.ca_cert.clone()),
+                    Line::from(""),
+                    Line::from(Span::styled(keys, app.theme.header_subtitle_style)),
                 ]
             } else {
                 vec![
@@ -139,6 +246,7 @@ pub fn setup_ui(f: &mut Frame, app: &SetupApp) {
                 ]
             }
         }
+        SetupState::SigningIn => sign_in_lines(app),
         SetupState::FetchingProviderModels => {
             let provider_name = app
                 .config_provider_idx
@@ -156,8 +264,14 @@ pub fn setup_ui(f: &mut Frame, app: &SetupApp) {
                 Line::from("Connecting and discovering latest models..."),
                 Line::from(""),
                 Line::from(Span::styled("[PLEASE WAIT]", app.theme.input_cursor_style)),
+                Line::from(""),
+                Line::from(Span::styled(
+                    "S: Skip This Provider (Settings Kept)  ESC: Back to Provider Config",
+                    app.theme.header_subtitle_style,
+                )),
             ]
         }
+        SetupState::ModelFetchFailed => model_fetch_failure_lines(app),
         SetupState::ProviderModelSelection => {
             if let Some(provider_idx) = app.config_provider_idx {
                 let provider = &app.providers[provider_idx];
@@ -230,11 +344,11 @@ pub fn setup_ui(f: &mut Frame, app: &SetupApp) {
         SetupState::ModelOrderSelection => {
             let mut lines = vec![
                 Line::from(Span::styled(
-                    "STEP 4: ALL MODELS CONFIRMATION / FALLBACK ORDER",
+                    "STEP 4: COMMAND MODELS / FALLBACK ORDER",
                     app.theme.header_title_style,
                 )),
                 Line::from(""),
-                Line::from("Primary model = top item. Fallbacks follow in order."),
+                Line::from("These models write commands. Primary = top item; fallbacks follow."),
                 Line::from("Use U/K to move up, D/J to move down."),
                 Line::from(""),
             ];
@@ -266,7 +380,7 @@ pub fn setup_ui(f: &mut Frame, app: &SetupApp) {
 
             lines.push(Line::from(""));
             lines.push(Line::from(Span::styled(
-                "ENTER: Next Step (Theme)  ESC: Back to Step 1",
+                "ENTER: Next Step (Routing)  ESC: Back to Step 1",
                 app.theme.header_subtitle_style,
             )));
             lines
@@ -274,7 +388,7 @@ pub fn setup_ui(f: &mut Frame, app: &SetupApp) {
         SetupState::ThemeSelection => {
             let mut lines = vec![
                 Line::from(Span::styled(
-                    "STEP 5: SELECT THEME",
+                    "STEP 6: SELECT THEME",
                     app.theme.header_title_style,
                 )),
                 Line::from(""),
@@ -288,11 +402,7 @@ pub fn setup_ui(f: &mut Frame, app: &SetupApp) {
                 } else {
                     app.theme.header_subtitle_style
                 };
-                let marker = if i == app.selected_theme_idx {
-                    "◆"
-                } else {
-                    "◇"
-                };
+                let marker = app.theme.marker(i == app.selected_theme_idx);
                 lines.push(Line::from(Span::styled(
                     format!("  {} {}", marker, display_name),
                     style,
@@ -306,7 +416,11 @@ pub fn setup_ui(f: &mut Frame, app: &SetupApp) {
             )));
             lines
         }
-        SetupState::Confirm => vec![],
+        SetupState::RouterOrderSelection
+        | SetupState::Confirm
+        | SetupState::PluginHealth
+        | SetupState::Installing
+        | SetupState::Safety => vec![],
         SetupState::Saving => vec![
             Line::from(""),
             Line::from(Span::styled(
@@ -327,7 +441,8 @@ pub fn setup_ui(f: &mut Frame, app: &SetupApp) {
 
     let content = Paragraph::new(content_text)
         .block(
-            Block::default()
+            app.theme
+                .block()
                 .borders(Borders::ALL)
                 .border_style(app.theme.border_style)
                 .title(" SETUP WIZARD "),
@@ -337,8 +452,491 @@ pub fn setup_ui(f: &mut Frame, app: &SetupApp) {
     f.render_widget(content, chunks[1]);
 }
 
+/// The keys found for import, ticked or not, with where each came from.
+fn key_import_lines(app: &SetupApp) -> Vec<Line<'static>> {
+    let mut lines = vec![
+        Line::from(Span::styled(
+            "IMPORT EXISTING API KEYS",
+            app.theme.header_title_style,
+        )),
+        Line::from(""),
+    ];
+    if app.found_keys.is_empty() {
+        lines.push(Line::from(
+            "No API keys found in provider environment variables or the llm, aider and Continue configs.",
+        ));
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "ENTER/ESC: Back to Step 1",
+            app.theme.header_subtitle_style,
+        )));
+        return lines;
+    }
+    lines.push(Line::from(
+        "Found on this machine. Ticked keys are set on their provider.",
+    ));
+    lines.push(Line::from(""));
+    for (i, (found, ticked)) in app.found_keys.iter().enumerate() {
+        let is_cursor = i == app.found_key_cursor;
+        let replaces = app
+            .providers
+            .iter()
+            .any(|p| p.kind == found.kind && p.has_key());
+        lines.push(Line::from(Span::styled(
+            format!(
+                "{}[{}] {:<12} {}{}",
+                if is_cursor { "> " } else { "  " },
+                if *ticked { "x" } else { " " },
+                found.kind.display_name(),
+                mask_api_key(&found.api_key),
+                if replaces {
+                    "  (replaces current key)"
+                } else {
+                    ""
+                }
+            ),
+            if is_cursor {
+                app.theme.proposal_cmd_style
+            } else {
+                app.theme.header_subtitle_style
+            },
+        )));
+        lines.push(Line::from(format!("      from {}", found.source)));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "SPACE: Toggle  ENTER: Import Ticked  ESC: Skip",
+        app.theme.header_subtitle_style,
+    )));
+    lines
+}
+
+/// The settings page of one plugin, with the field being edited marked.
+fn plugin_settings_lines(app: &SetupApp) -> Vec<Line<'static>> {
+    let Some(draft) = &app.plugin_settings else {
+        return Vec::new();
+    };
+    let field = app.plugin_settings_field;
+    let toggle_focused = field == PluginSettingsField::NeverOverwrite;
+    let mut prompt_note = Vec::new();
+    if draft.prompt_override.is_some() || !draft.prompt_append.is_empty() {
+        prompt_note.push(Line::from(""));
+        prompt_note.push(Line::from(Span::styled(
+            format!(
+                "Prompt from config.toml: {} extra instruction(s){}",
+                draft.prompt_append.len(),
+                if draft.prompt_override.is_some() {
+                    ", own prompt replaced"
+                } else {
+                    ""
+                }
+            ),
+            app.theme.header_subtitle_style,
+        )));
+    }
+    let mut lines = vec![
+        Line::from(Span::styled(
+            format!("PLUGIN SETTINGS: {}", draft.plugin.to_uppercase()),
+            app.theme.header_title_style,
+        )),
+        Line::from(""),
+        Line::from("Default flags, added to every command unless the request says otherwise:"),
+        field_line(
+            app,
+            field == PluginSettingsField::DefaultFlags,
+            draft.default_flags.clone(),
+        ),
+        Line::from("Output directory (empty = where the files are; a project's output_dir wins):"),
+        field_line(
+            app,
+            field == PluginSettingsField::OutputDir,
+            draft.output_dir.clone(),
+        ),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled(
+                if toggle_focused { "> " } else { "  " },
+                app.theme.input_prompt_style,
+            ),
+            Span::styled(
+                format!(
+                    "[{}] Never overwrite: rename around existing outputs",
+                    if draft.never_overwrite { "x" } else { " " }
+                ),
+                if toggle_focused {
+                    app.theme.input_cursor_style
+                } else {
+                    app.theme.input_text_style
+                },
+            ),
+        ]),
+    ];
+    lines.extend(prompt_note);
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "TAB: Next Field  SPACE: Toggle  ENTER: Save  ESC: Back",
+        app.theme.header_subtitle_style,
+    )));
+    lines
+}
+
+/// The safety page, scrolled so the cursor and the test verdict show.
+fn render_safety_page(f: &mut Frame, app: &SetupApp, area: Rect) {
+    let block = app
+        .theme
+        .block()
+        .borders(Borders::ALL)
+        .border_style(app.theme.border_style)
+        .title(" SAFETY RULES ");
+    let Some(draft) = &app.safety else {
+        f.render_widget(block, area);
+        return;
+    };
+    let current = draft.current();
+    let mut cursor_line = 0;
+    let mut lines: Vec<Line> = Vec::new();
+    let heading = |text: &'static str| Line::from(Span::styled(text, app.theme.header_title_style));
+    let note = |text: String| Line::from(Span::styled(text, app.theme.header_subtitle_style));
+    let mut row = |lines: &mut Vec<Line<'static>>, focused: bool, text: String| {
+        if focused {
+            cursor_line = lines.len();
+        }
+        lines.push(field_line(app, focused, text));
+    };
+
+    lines.push(note(
+        "Commands are checked before every preview and again before they run.".to_string(),
+    ));
+    lines.push(Line::from(""));
+    lines.push(heading("DANGER PATTERNS"));
+    lines.push(note(
+        "Regexes a command must not match. Built-in and project ones always apply.".to_string(),
+    ));
+    let builtin: Vec<String> = SafetyGuard::default()
+        .patterns()
+        .map(str::to_string)
+        .collect();
+    for chunk in builtin.chunks(3) {
+        lines.push(note(format!("  built-in  {}", chunk.join("   "))));
+    }
+    match (&draft.project.error, &draft.project.root) {
+        (Some(error), _) => lines.push(Line::from(Span::styled(
+            format!("  project   {}", error),
+            app.theme.error_style,
+        ))),
+        (None, Some(root)) if !draft.project.patterns.is_empty() => {
+            for pattern in &draft.project.patterns {
+                lines.push(note(format!(
+                    "  project   {}   ({})",
+                    pattern,
+                    root.join(WORKSPACE_FILE).display()
+                )));
+            }
+        }
+        _ => {}
+    }
+    for (idx, pattern) in draft.policy.blocked_patterns.iter().enumerate() {
+        row(
+            &mut lines,
+            current == SafetyRow::Pattern(idx),
+            pattern.clone(),
+        );
+        if let Some(error) = draft.pattern_error(idx) {
+            lines.push(Line::from(Span::styled(
+                format!("    ! {}", error),
+                app.theme.error_style,
+            )));
+        }
+    }
+    let adding = current == SafetyRow::Pattern(draft.policy.blocked_patterns.len());
+    row(
+        &mut lines,
+        adding,
+        "+ type to add a pattern for every project".to_string(),
+    );
+
+    lines.push(Line::from(""));
+    lines.push(heading("PATH CONTAINMENT"));
+    row(
+        &mut lines,
+        current == SafetyRow::ContainPaths,
+        format!(
+            "[{}] Block paths outside the working directory (absolute, ~/ or ../)",
+            if draft.policy.contain_paths { "x" } else { " " }
+        ),
+    );
+
+    lines.push(Line::from(""));
+    lines.push(heading("PLUGIN POLICIES"));
+    lines.push(note(
+        "allow: as usual   confirm: never runs on its own   block: previews only".to_string(),
+    ));
+    let width = draft.plugins.iter().map(String::len).max().unwrap_or(0);
+    for (idx, plugin) in draft.plugins.iter().enumerate() {
+        row(
+            &mut lines,
+            current == SafetyRow::Plugin(idx),
+            format!(
+                "{:width$}  < {} >",
+                plugin,
+                draft.policy.plugin_policy(plugin).label(),
+                width = width
+            ),
+        );
+    }
+
+    lines.push(Line::from(""));
+    lines.push(heading("CONFIRMATION"));
+    row(
+        &mut lines,
+        current == SafetyRow::ConfirmTimeout,
+        match draft.confirm_minutes {
+            0 => "< wait forever > for a preview to be confirmed".to_string(),
+            minutes => format!(
+                "< {} min > without a key, then cancel the preview (or run an auto_run recipe)",
+                minutes
+            ),
+        },
+    );
+
+    lines.push(Line::from(""));
+    lines.push(heading("TEST A COMMAND"));
+    row(&mut lines, current == SafetyRow::Test, draft.sample.clone());
+    match draft.verdict(&app.plugins) {
+        Some(Ok(verdict)) => lines.push(Line::from(Span::styled(
+            format!("  {}", verdict),
+            app.theme.success_style,
+        ))),
+        Some(Err(reason)) => lines.push(Line::from(Span::styled(
+            format!("  {}", reason),
+            app.theme.error_style,
+        ))),
+        None => lines.push(note(
+            "  Type a command to check it against the rules above.".to_string(),
+        )),
+    }
+    if let Some(message) = &draft.message {
+        lines.push(Line::from(""));
+        lines.push(note(message.clone()));
+    }
+    lines.push(Line::from(""));
+    lines.push(note(
+        "UP/DOWN: Move  SPACE/LEFT/RIGHT: Change  BACKSPACE on empty: Remove  ENTER: Save  ESC: Back"
+            .to_string(),
+    ));
+
+    let height = block.inner(area).height as usize;
+    // Keeps two lines below the cursor in view, for the test verdict.
+    let scroll = (cursor_line + 3).saturating_sub(height);
+    let page = Paragraph::new(lines)
+        .block(block)
+        .style(app.theme.base_style)
+        .scroll((scroll as u16, 0));
+    f.render_widget(page, area);
+}
+
+/// The queued installs with the commands they will run.
+fn install_confirm_lines(app: &SetupApp) -> Vec<Line<'static>> {
+    let mut lines = vec![
+        Line::from(Span::styled(
+            "INSTALL EXTERNAL TOOLS",
+            app.theme.header_title_style,
+        )),
+        Line::from(""),
+        Line::from("These commands will run one after another:"),
+        Line::from(""),
+    ];
+    let mut sudo = false;
+    for job in app.install_queue.pending() {
+        sudo |= job
+            .plan
+            .argv
+            .first()
+            .is_some_and(|program| program == "sudo");
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!(
+                    "  {:<12} {:<8} ",
+                    job.plan.plugin,
+                    if job.plan.update { "update" } else { "install" }
+                ),
+                app.theme.header_subtitle_style,
+            ),
+            Span::styled(job.plan.command_line(), app.theme.proposal_cmd_style),
+        ]));
+    }
+    if sudo {
+        lines.push(Line::from(""));
+        lines.push(Line::from(
+            "sudo -n fails instead of asking for a password. If it does, run the command in a terminal.",
+        ));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "ENTER: Run  ESC: Back",
+        app.theme.header_subtitle_style,
+    )));
+    lines
+}
+
+/// Each queued install's status, then as much of their output as fits.
+fn render_install_progress(f: &mut Frame, app: &SetupApp, area: Rect) {
+    let block = app
+        .theme
+        .block()
+        .borders(Borders::ALL)
+        .border_style(app.theme.border_style)
+        .title(" INSTALLING ");
+    f.render_widget(&block, area);
+    let inner = block.inner(area);
+    let queue = &app.install_queue;
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length((queue.jobs.len() as u16).saturating_add(1)),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .split(inner);
+
+    let jobs: Vec<Line> = queue
+        .jobs
+        .iter()
+        .map(|job| {
+            let (status, style) = match &job.status {
+                InstallStatus::Queued => ("QUEUED".to_string(), app.theme.header_subtitle_style),
+                InstallStatus::Running => ("RUNNING".to_string(), app.theme.input_cursor_style),
+                InstallStatus::Done => ("DONE".to_string(), app.theme.proposal_cmd_style),
+                InstallStatus::Failed(e) => (
+                    format!("FAILED: {}", e.lines().next().unwrap_or_default()),
+                    app.theme.error_style,
+                ),
+                InstallStatus::Cancelled => {
+                    ("CANCELLED".to_string(), app.theme.header_subtitle_style)
+                }
+            };
+            Line::from(Span::styled(
+                format!("{:<12} {}", job.plan.plugin, status),
+                style,
+            ))
+        })
+        .collect();
+    f.render_widget(Paragraph::new(jobs), layout[0]);
+
+    let shown = layout[1].height as usize;
+    let output: Vec<Line> = queue.output[queue.output.len().saturating_sub(shown)..]
+        .iter()
+        .map(|line| Line::from(line.clone()))
+        .collect();
+    f.render_widget(
+        Paragraph::new(output).style(app.theme.input_text_style),
+        layout[1],
+    );
+
+    let footer = if queue.is_running() {
+        "ESC: Cancel"
+    } else {
+        "ENTER/ESC: Back to plugin health"
+    };
+    f.render_widget(
+        Paragraph::new(footer).style(app.theme.header_subtitle_style),
+        layout[2],
+    );
+}
+
+/// The device code and where to enter it, while the sign-in waits.
+fn sign_in_lines(app: &SetupApp) -> Vec<Line<'static>> {
+    let mut lines = vec![
+        Line::from(Span::styled(
+            "STEP 2: SIGN IN",
+            app.theme.header_title_style,
+        )),
+        Line::from(""),
+    ];
+    let Some(sign_in) = &app.sign_in else {
+        return lines;
+    };
+    let code = &sign_in.code;
+    lines.push(Line::from(format!(
+        "Provider: {}",
+        app.providers[sign_in.provider_idx].name()
+    )));
+    lines.push(Line::from(""));
+    lines.push(Line::from("Open this page in a browser:"));
+    lines.push(Line::from(Span::styled(
+        code.verification_uri_complete
+            .clone()
+            .unwrap_or_else(|| code.verification_uri.clone()),
+        app.theme.proposal_cmd_style,
+    )));
+    lines.push(Line::from("and enter the code:"));
+    lines.push(Line::from(Span::styled(
+        code.user_code.clone(),
+        app.theme.input_cursor_style,
+    )));
+    lines.push(Line::from(""));
+    lines.push(Line::from(format!(
+        "Waiting for the sign-in (the code is valid for {} min)...",
+        code.expires_in.div_ceil(60)
+    )));
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "ESC: Cancel",
+        app.theme.header_subtitle_style,
+    )));
+    lines
+}
+
+/// Status, the provider's answer and a hint for a failed model fetch.
+fn model_fetch_failure_lines(app: &SetupApp) -> Vec<Line<'static>> {
+    let provider_name = app
+        .config_provider_idx
+        .and_then(|idx| app.providers.get(idx))
+        .map(|p| p.name().to_string())
+        .unwrap_or_else(|| "Provider".to_string());
+    let mut lines = vec![
+        Line::from(Span::styled(
+            "STEP 3: MODEL DISCOVERY FAILED",
+            Style::default().fg(Color::Red),
+        )),
+        Line::from(""),
+        Line::from(format!("Provider: {}", provider_name)),
+    ];
+    let Some(failure) = &app.model_fetch_failure else {
+        return lines;
+    };
+    match &failure.details {
+        Some(details) => {
+            lines.push(Line::from(format!("Status: {}", details.status_line())));
+            lines.push(Line::from(format!("URL: {}", details.url)));
+            lines.push(Line::from(format!("Response: {}", details.body)));
+            if let Some(hint) = details.hint() {
+                lines.push(Line::from(""));
+                lines.push(Line::from(Span::styled(
+                    format!("Hint: {}", hint),
+                    app.theme.input_cursor_style,
+                )));
+            }
+        }
+        None => lines.push(Line::from(failure.message.clone())),
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        if failure.cached.is_some() {
+            "R: Retry  ENTER: Use Cached List  S: Skip Provider  ESC: Back to Provider Config"
+        } else {
+            "R: Retry  ENTER: Use Built-in List  S: Skip Provider  ESC: Back to Provider Config"
+        },
+        app.theme.header_subtitle_style,
+    )));
+    lines
+}
+
 fn render_setup_provider_table(f: &mut Frame, app: &SetupApp, area: Rect) {
-    let block = Block::default()
+    let block = app
+        .theme
+        .block()
         .borders(Borders::ALL)
         .border_style(app.theme.border_style)
         .title(" SETUP WIZARD ");
@@ -350,7 +948,7 @@ fn render_setup_provider_table(f: &mut Frame, app: &SetupApp, area: Rect) {
     let layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(if compact { 8 } else { 7 }),
+            Constraint::Length(if compact { 10 } else { 9 }),
             Constraint::Length(1),
             Constraint::Length((app.providers.len() as u16).saturating_add(2)),
             Constraint::Min(1),
@@ -374,6 +972,9 @@ fn render_setup_provider_table(f: &mut Frame, app: &SetupApp, area: Rect) {
             "ENTER: Continue to providers config"
         }),
         Line::from("OFF providers keep model selections saved but not active at runtime."),
+        Line::from("P: Plugin health (installed tools, versions, INSTALL/UPDATE)"),
+        Line::from("I: Import API keys from the environment, llm, aider or Continue"),
+        Line::from("S: Safety rules (danger patterns, path containment, plugin policies)"),
     ];
     let intro_para = Paragraph::new(intro)
         .style(app.theme.header_subtitle_style)
@@ -399,11 +1000,7 @@ fn render_setup_provider_table(f: &mut Frame, app: &SetupApp, area: Rect) {
         .enumerate()
         .map(|(idx, provider)| {
             let is_cursor = idx == app.selected_provider_idx;
-            let toggle = if provider.enabled {
-                "◉ ON"
-            } else {
-                "○ OFF"
-            };
+            let toggle = app.theme.toggle(provider.enabled);
             let setup_state = if provider.requires_api_key() {
                 if provider.has_key() {
                     "SET"
@@ -474,8 +1071,140 @@ fn render_setup_provider_table(f: &mut Frame, app: &SetupApp, area: Rect) {
     f.render_widget(table, layout[2]);
 }
 
+fn render_plugin_health_table(f: &mut Frame, app: &SetupApp, area: Rect) {
+    let block = app
+        .theme
+        .block()
+        .borders(Borders::ALL)
+        .border_style(app.theme.border_style)
+        .title(" PLUGIN HEALTH ");
+    f.render_widget(&block, area);
+    let inner = block.inner(area);
+    let compact = inner.width < 98;
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length((app.plugin_rows.len() as u16).saturating_add(1)),
+            Constraint::Length(1),
+            Constraint::Min(1),
+        ])
+        .split(inner);
+
+    let intro = vec![
+        Line::from(Span::styled(
+            "PLUGINS AND THEIR EXTERNAL TOOLS",
+            app.theme.header_title_style,
+        )),
+        Line::from(""),
+        Line::from(
+            "SPACE: Queue  ENTER: Install/update queued or selected  S: Settings  R: Re-check  ESC: Back to Step 1",
+        ),
+    ];
+    f.render_widget(
+        Paragraph::new(intro).style(app.theme.header_subtitle_style),
+        layout[0],
+    );
+
+    let mut header = vec![
+        Cell::from("  "),
+        Cell::from("PLUGIN"),
+        Cell::from("STATUS"),
+        Cell::from("VERSION"),
+    ];
+    if !compact {
+        header.push(Cell::from("PATH"));
+    }
+    header.push(Cell::from("LAST OK (UTC)"));
+    header.push(Cell::from("ACTION"));
+    let header = Row::new(header).style(app.theme.footer_text_style.add_modifier(Modifier::BOLD));
+
+    let rows: Vec<Row> = app
+        .plugin_rows
+        .iter()
+        .enumerate()
+        .map(|(idx, row)| {
+            let is_cursor = idx == app.plugin_cursor;
+            let (status, action) = if row.health.installed {
+                ("INSTALLED", "UPDATE")
+            } else {
+                ("MISSING", "INSTALL")
+            };
+            let action = if app.install_queue.contains(&row.name) {
+                "QUEUED"
+            } else {
+                action
+            };
+            let mut cells = vec![
+                Cell::from(if is_cursor { "> " } else { "  " }),
+                Cell::from(row.name.clone()),
+                Cell::from(status),
+                Cell::from(
+                    row.health
+                        .version
+                        .clone()
+                        .unwrap_or_else(|| "-".to_string()),
+                ),
+            ];
+            if !compact {
+                cells.push(Cell::from(
+                    row.health
+                        .path
+                        .as_ref()
+                        .map(|p| p.display().to_string())
+                        .unwrap_or_else(|| format!("{} not in $PATH", row.health.binary)),
+                ));
+            }
+            cells.push(Cell::from(
+                row.last_success
+                    .clone()
+                    .unwrap_or_else(|| "never".to_string()),
+            ));
+            cells.push(Cell::from(action));
+            let style = if is_cursor {
+                app.theme.proposal_cmd_style
+            } else if row.health.installed {
+                app.theme.header_subtitle_style
+            } else {
+                app.theme.error_style
+            };
+            Row::new(cells).style(style)
+        })
+        .collect();
+
+    let mut widths = vec![
+        Constraint::Length(2),
+        Constraint::Length(10),
+        Constraint::Length(10),
+        Constraint::Min(16),
+    ];
+    if !compact {
+        widths.push(Constraint::Min(20));
+    }
+    widths.push(Constraint::Length(16));
+    widths.push(Constraint::Length(8));
+    let table = Table::new(rows, widths)
+        .header(header)
+        .column_spacing(1)
+        .style(app.theme.base_style);
+    f.render_widget(table, layout[1]);
+
+    if let Some(message) = &app.plugin_message {
+        let lines: Vec<Line> = message.lines().map(|l| Line::from(l.to_string())).collect();
+        f.render_widget(
+            Paragraph::new(lines)
+                .style(app.theme.input_text_style)
+                .wrap(Wrap { trim: false }),
+            layout[3],
+        );
+    }
+}
+
 fn render_setup_models_table(f: &mut Frame, app: &SetupApp, area: Rect) {
-    let block = Block::default()
+    let block = app
+        .theme
+        .block()
         .borders(Borders::ALL)
         .border_style(app.theme.border_style)
         .title(" SETUP WIZARD ");
@@ -517,6 +1246,10 @@ fn render_setup_models_table(f: &mut Frame, app: &SetupApp, area: Rect) {
         } else {
             "Select one or more models to activate for fallback."
         }),
+        Line::from(match &provider.models_age {
+            Some(age) => format!("Model list: fetched {}", age),
+            None => "Model list: built-in (could not fetch)".to_string(),
+        }),
     ];
     let intro_para = Paragraph::new(intro)
         .style(app.theme.header_subtitle_style)
@@ -537,7 +1270,7 @@ fn render_setup_models_table(f: &mut Frame, app: &SetupApp, area: Rect) {
         .map(|(idx, model)| {
             let is_cursor = idx == app.provider_model_cursor;
             let selected = provider.active_models.iter().any(|m| m == model);
-            let toggle = if selected { "◉ ON" } else { "○ OFF" };
+            let toggle = app.theme.toggle(selected);
             Row::new(vec![
                 Cell::from(if is_cursor { "> " } else { "  " }),
                 Cell::from(toggle),
@@ -557,7 +1290,7 @@ fn render_setup_models_table(f: &mut Frame, app: &SetupApp, area: Rect) {
     rows.push(
         Row::new(vec![
             Cell::from(if select_all_cursor { "> " } else { "  " }),
-            Cell::from(if all_selected { "◉ ON" } else { "○ OFF" }),
+            Cell::from(app.theme.toggle(all_selected)),
             Cell::from("Select All"),
         ])
         .style(if select_all_cursor {
@@ -593,9 +1326,9 @@ fn render_setup_models_table(f: &mut Frame, app: &SetupApp, area: Rect) {
     f.render_widget(table, layout[2]);
 
     let help = Paragraph::new(if very_narrow {
-        "SPACE: Toggle / Select All   ENTER: Save & Next"
+        "SPACE: Toggle / Select All   R: Refresh   ENTER: Save & Next"
     } else {
-        "SPACE: Toggle model / Select All   ENTER: Save & Next"
+        "SPACE: Toggle model / Select All   R: Refresh list   ENTER: Save & Next"
     })
     .style(app.theme.header_subtitle_style)
     .wrap(Wrap { trim: true });
@@ -603,7 +1336,9 @@ fn render_setup_models_table(f: &mut Frame, app: &SetupApp, area: Rect) {
 }
 
 fn render_setup_model_order_table(f: &mut Frame, app: &SetupApp, area: Rect) {
-    let block = Block::default()
+    let block = app
+        .theme
+        .block()
         .borders(Borders::ALL)
         .border_style(app.theme.border_style)
         .title(" SETUP WIZARD ");
@@ -612,10 +1347,16 @@ fn render_setup_model_order_table(f: &mut Frame, app: &SetupApp, area: Rect) {
     let compact = inner.width < 98;
     let very_narrow = inner.width < 78;
 
-    let table_height = if app.model_order.is_empty() {
+    let routing = app.state == SetupState::RouterOrderSelection;
+    let (order, cursor) = if routing {
+        (&app.router_order, app.router_order_cursor)
+    } else {
+        (&app.model_order, app.model_order_cursor)
+    };
+    let table_height = if order.is_empty() {
         3
     } else {
-        (app.model_order.len() as u16).saturating_add(2)
+        (order.len() as u16).saturating_add(2)
     };
     let layout = Layout::default()
         .direction(Direction::Vertical)
@@ -627,19 +1368,51 @@ fn render_setup_model_order_table(f: &mut Frame, app: &SetupApp, area: Rect) {
         ])
         .split(inner);
 
-    let intro = vec![
-        Line::from(Span::styled(
-            "STEP 4: ALL MODELS CONFIRMATION / FALLBACK ORDER",
-            app.theme.header_title_style,
-        )),
-        Line::from(""),
-        Line::from("Primary model = top item. Fallbacks follow in order."),
-        Line::from(if very_narrow {
-            "U/K: move up   D/J: move down"
-        } else {
-            "Use U/K to move up, D/J to move down."
-        }),
-    ];
+    let mut intro = if routing {
+        vec![
+            Line::from(Span::styled(
+                "STEP 5: ROUTING MODELS",
+                app.theme.header_title_style,
+            )),
+            Line::from(""),
+            Line::from("These models pick the tool. Small, fast models come first by default."),
+            Line::from(if very_narrow {
+                "U/K D/J: move   M: same as step 4   R: suggested"
+            } else {
+                "U/K: move up   D/J: move down   M: same order as step 4   R: suggested order"
+            }),
+        ]
+    } else {
+        vec![
+            Line::from(Span::styled(
+                "STEP 4: COMMAND MODELS / FALLBACK ORDER",
+                app.theme.header_title_style,
+            )),
+            Line::from(""),
+            Line::from("These models write commands. Primary = top item; fallbacks follow."),
+            Line::from(if very_narrow {
+                "U/K: move up   D/J: move down"
+            } else {
+                "Use U/K to move up, D/J to move down."
+            }),
+        ]
+    };
+    if !routing && !app.skipped_providers.is_empty() {
+        let skipped: Vec<&str> = app
+            .skipped_providers
+            .iter()
+            .filter_map(|idx| app.providers.get(*idx))
+            .map(|p| p.name())
+            .collect();
+        intro.push(Line::from(""));
+        intro.push(Line::from(Span::styled(
+            format!(
+                "Skipped: {} (settings and selected models kept)",
+                skipped.join(", ")
+            ),
+            app.theme.input_cursor_style,
+        )));
+    }
     let intro_para = Paragraph::new(intro)
         .style(app.theme.header_subtitle_style)
         .wrap(Wrap { trim: true });
@@ -653,7 +1426,7 @@ fn render_setup_model_order_table(f: &mut Frame, app: &SetupApp, area: Rect) {
     ])
     .style(app.theme.footer_text_style.add_modifier(Modifier::BOLD));
 
-    let rows = if app.model_order.is_empty() {
+    let rows = if order.is_empty() {
         vec![Row::new(vec![
             Cell::from("  "),
             Cell::from("--"),
@@ -662,11 +1435,11 @@ fn render_setup_model_order_table(f: &mut Frame, app: &SetupApp, area: Rect) {
         ])
         .style(Style::default().fg(Color::Red))]
     } else {
-        app.model_order
+        order
             .iter()
             .enumerate()
             .map(|(idx, route)| {
-                let is_cursor = idx == app.model_order_cursor;
+                let is_cursor = idx == cursor;
                 let provider = app
                     .providers
                     .get(route.provider_idx)
@@ -715,14 +1488,20 @@ fn render_setup_model_order_table(f: &mut Frame, app: &SetupApp, area: Rect) {
         .style(app.theme.base_style);
     f.render_widget(table, layout[2]);
 
-    let help = Paragraph::new("ENTER: Next Step (Theme)   ESC: Back to Step 1")
-        .style(app.theme.header_subtitle_style)
-        .wrap(Wrap { trim: true });
+    let help = Paragraph::new(if routing {
+        "ENTER: Next Step (Theme)   ESC: Back to Step 4"
+    } else {
+        "ENTER: Next Step (Routing)   ESC: Back to Step 1"
+    })
+    .style(app.theme.header_subtitle_style)
+    .wrap(Wrap { trim: true });
     f.render_widget(help, layout[3]);
 }
 
 fn render_setup_confirm_table(f: &mut Frame, app: &SetupApp, area: Rect) {
-    let block = Block::default()
+    let block = app
+        .theme
+        .block()
         .borders(Borders::ALL)
         .border_style(app.theme.border_style)
         .title(" SETUP WIZARD ");
@@ -736,7 +1515,7 @@ fn render_setup_confirm_table(f: &mut Frame, app: &SetupApp, area: Rect) {
         .constraints([
             Constraint::Length(if compact { 5 } else { 4 }),
             Constraint::Length(1),
-            Constraint::Length(5),
+            Constraint::Length(6),
             Constraint::Length(if very_narrow { 8 } else { 5 }),
             Constraint::Min(6),
             Constraint::Length(3),
@@ -745,7 +1524,7 @@ fn render_setup_confirm_table(f: &mut Frame, app: &SetupApp, area: Rect) {
 
     let intro = vec![
         Line::from(Span::styled(
-            "STEP 6: CONFIRM SETTINGS",
+            "STEP 7: CONFIRM SETTINGS",
             app.theme.header_title_style,
         )),
         Line::from(""),
@@ -761,6 +1540,11 @@ fn render_setup_confirm_table(f: &mut Frame, app: &SetupApp, area: Rect) {
         .first()
         .map(|r| model_route_display(r, &app.providers))
         .unwrap_or_else(|| "Unknown".to_string());
+    let router = app
+        .router_order
+        .first()
+        .map(|r| model_route_display(r, &app.providers))
+        .unwrap_or_else(|| "Unknown".to_string());
     let summary_header = Row::new(vec![Cell::from("FIELD"), Cell::from("VALUE")])
         .style(app.theme.footer_text_style.add_modifier(Modifier::BOLD));
     let summary_rows = vec![
@@ -771,6 +1555,8 @@ fn render_setup_confirm_table(f: &mut Frame, app: &SetupApp, area: Rect) {
         .style(app.theme.header_subtitle_style),
         Row::new(vec![Cell::from("PRIMARY"), Cell::from(primary)])
             .style(app.theme.header_subtitle_style),
+        Row::new(vec![Cell::from("ROUTER"), Cell::from(router)])
+            .style(app.theme.header_subtitle_style),
         Row::new(vec![
             Cell::from("ROUTES"),
             Cell::from(app.model_order.len().to_string()),
@@ -789,10 +1575,10 @@ fn render_setup_confirm_table(f: &mut Frame, app: &SetupApp, area: Rect) {
         app.theme.footer_text_style.add_modifier(Modifier::BOLD),
     ))];
     if enabled.is_empty() {
-        enabled_lines.push(Line::from("  ◇ (NONE)"));
+        enabled_lines.push(Line::from(format!("  {} (NONE)", app.theme.marker(false))));
     } else {
         for name in enabled {
-            enabled_lines.push(Line::from(format!("  ◆ {}", name)));
+            enabled_lines.push(Line::from(format!("  {} {}", app.theme.marker(true), name)));
         }
     }
     let enabled_para = Paragraph::new(enabled_lines)
@@ -0,0 +1,22 @@
+use anyhow::Result;
+use dexter_core::sync::{self, SyncPrefer};
+use dexter_core::Config;
+
+use crate::cli::SyncSide;
+
+pub async fn run_sync(prefer: Option<SyncSide>, dry_run: bool) -> Result<()> {
+    let config = Config::load().await?;
+    let mut policy = config.sync.clone();
+    if let Some(side) = prefer {
+        policy.prefer = match side {
+            SyncSide::Local => SyncPrefer::Local,
+            SyncSide::Remote => SyncPrefer::Remote,
+        };
+    }
+    let report = sync::sync(&config, &policy, dry_run).await?;
+    println!("{}", report.summary());
+    if dry_run {
+        println!("Dry run: nothing was written.");
+    }
+    Ok(())
+}
@@ -1,4 +1,24 @@
 use ratatui::style::{Color, Modifier, Style};
+use ratatui::symbols::{border, scrollbar};
+use ratatui::widgets::Block;
+
+const ASCII_BORDER: border::Set = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+const ASCII_SCROLLBAR: scrollbar::Set = scrollbar::Set {
+    track: "|",
+    thumb: "#",
+    begin: "^",
+    end: "v",
+};
 
 #[derive(Debug, Clone)]
 pub struct Theme {
@@ -15,6 +35,12 @@ pub struct Theme {
     pub input_cursor_style: Style,
     // Proposal/Command
     pub proposal_cmd_style: Style,
+    // Command syntax highlighting (patched over proposal_cmd_style)
+    pub syntax_program_style: Style,
+    pub syntax_flag_style: Style,
+    pub syntax_string_style: Style,
+    pub syntax_path_style: Style,
+    pub syntax_operator_style: Style,
     // Status/Processing
     pub processing_spinner_style: Style,
     pub processing_text_style: Style,
@@ -31,7 +57,10 @@ pub struct Theme {
     pub history_selected_style: Style,
     // Alerts/Errors
     pub error_style: Style,
+    pub warning_style: Style,
     pub success_style: Style,
+    // `plain_ui`: ASCII glyphs and no animation
+    pub plain: bool,
 }
 
 impl Theme {
@@ -52,6 +81,56 @@ impl Theme {
         }
     }
 
+    pub fn with_plain(mut self, plain: bool) -> Self {
+        self.plain = plain;
+        if plain {
+            self.input_cursor_style = self
+                .input_cursor_style
+                .remove_modifier(Modifier::RAPID_BLINK);
+        }
+        self
+    }
+
+    /// A block drawn with the theme's border glyphs.
+    pub fn block<'a>(&self) -> Block<'a> {
+        Block::default().border_set(if self.plain {
+            ASCII_BORDER
+        } else {
+            border::PLAIN
+        })
+    }
+
+    pub fn scrollbar_symbols(&self) -> scrollbar::Set<'static> {
+        if self.plain {
+            ASCII_SCROLLBAR
+        } else {
+            scrollbar::VERTICAL
+        }
+    }
+
+    pub fn toggle(&self, on: bool) -> &'static str {
+        match (on, self.plain) {
+            (true, false) => "◉ ON",
+            (false, false) => "○ OFF",
+            (true, true) => "[x] ON",
+            (false, true) => "[ ] OFF",
+        }
+    }
+
+    pub fn marker(&self, selected: bool) -> &'static str {
+        match (selected, self.plain) {
+            (true, false) => "◆",
+            (false, false) => "◇",
+            (true, true) => "*",
+            (false, true) => "-",
+        }
+    }
+
+    /// Whether the spinner, conveyor belt and cursor blink should move.
+    pub fn animated(&self) -> bool {
+        !self.plain
+    }
+
     pub fn retro() -> Self {
         let amber = Color::Rgb(255, 176, 0);
         let amber_dim = Color::Rgb(150, 110, 0);
@@ -73,6 +152,15 @@ impl Theme {
                 .add_modifier(Modifier::RAPID_BLINK),
 
             proposal_cmd_style: Style::default().bg(amber).fg(bg),
+            syntax_program_style: Style::default().add_modifier(Modifier::BOLD),
+            syntax_flag_style: Style::default().fg(Color::Rgb(110, 40, 0)),
+            syntax_string_style: Style::default()
+                .fg(Color::Rgb(0, 80, 40))
+                .add_modifier(Modifier::ITALIC),
+            syntax_path_style: Style::default().add_modifier(Modifier::UNDERLINED),
+            syntax_operator_style: Style::default()
+                .fg(Color::Rgb(170, 0, 0))
+                .add_modifier(Modifier::BOLD),
 
             processing_spinner_style: Style::default().fg(amber).add_modifier(Modifier::BOLD),
             processing_text_style: Style::default().fg(amber),
@@ -94,7 +182,11 @@ impl Theme {
                 .add_modifier(Modifier::BOLD),
 
             error_style: Style::default().fg(red_alert),
+            warning_style: Style::default()
+                .fg(Color::Rgb(255, 120, 0))
+                .add_modifier(Modifier::BOLD),
             success_style: Style::default().fg(bg).bg(amber),
+            plain: false,
         }
     }
 
@@ -119,6 +211,15 @@ impl Theme {
                 .add_modifier(Modifier::RAPID_BLINK),
 
             proposal_cmd_style: Style::default().bg(accent).fg(Color::White),
+            syntax_program_style: Style::default().add_modifier(Modifier::BOLD),
+            syntax_flag_style: Style::default().fg(Color::Rgb(255, 230, 120)),
+            syntax_string_style: Style::default()
+                .fg(Color::Rgb(170, 255, 170))
+                .add_modifier(Modifier::ITALIC),
+            syntax_path_style: Style::default().add_modifier(Modifier::UNDERLINED),
+            syntax_operator_style: Style::default()
+                .fg(Color::Rgb(255, 150, 150))
+                .add_modifier(Modifier::BOLD),
 
             processing_spinner_style: Style::default().fg(accent).add_modifier(Modifier::BOLD),
             processing_text_style: Style::default().fg(accent),
@@ -140,7 +241,9 @@ impl Theme {
                 .add_modifier(Modifier::BOLD),
 
             error_style: Style::default().fg(red_alert),
+            warning_style: Style::default().fg(Color::Rgb(170, 90, 0)),
             success_style: Style::default().fg(Color::White).bg(accent),
+            plain: false,
         }
     }
 
@@ -165,6 +268,15 @@ impl Theme {
                 .add_modifier(Modifier::RAPID_BLINK),
 
             proposal_cmd_style: Style::default().bg(amber).fg(bg),
+            syntax_program_style: Style::default().add_modifier(Modifier::BOLD),
+            syntax_flag_style: Style::default().fg(Color::Rgb(110, 40, 0)),
+            syntax_string_style: Style::default()
+                .fg(Color::Rgb(0, 80, 40))
+                .add_modifier(Modifier::ITALIC),
+            syntax_path_style: Style::default().add_modifier(Modifier::UNDERLINED),
+            syntax_operator_style: Style::default()
+                .fg(Color::Rgb(170, 0, 0))
+                .add_modifier(Modifier::BOLD),
 
             processing_spinner_style: Style::default().fg(amber).add_modifier(Modifier::BOLD),
             processing_text_style: Style::default().fg(amber),
@@ -186,7 +298,37 @@ impl Theme {
                 .add_modifier(Modifier::BOLD),
 
             error_style: Style::default().fg(red_alert),
+            warning_style: Style::default().fg(Color::Rgb(255, 140, 50)),
             success_style: Style::default().fg(bg).bg(amber),
+            plain: false,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_theme_uses_ascii_glyphs_and_a_steady_cursor() {
+        let theme = Theme::retro().with_plain(true);
+        assert!(!theme.animated());
+        assert!(!theme
+            .input_cursor_style
+            .add_modifier
+            .contains(Modifier::RAPID_BLINK));
+        let glyphs = [
+            theme.toggle(true),
+            theme.toggle(false),
+            theme.marker(true),
+            theme.marker(false),
+            theme.scrollbar_symbols().thumb,
+            theme.scrollbar_symbols().track,
+        ];
+        assert!(glyphs.iter().all(|glyph| glyph.is_ascii()));
+
+        let fancy = Theme::retro();
+        assert!(fancy.animated());
+        assert_eq!(fancy.toggle(true), "◉ ON");
+    }
+}
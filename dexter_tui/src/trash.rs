@@ -0,0 +1,48 @@
+use anyhow::{anyhow, Result};
+use dexter_core::{trash, Config};
+
+use crate::cli::TrashAction;
+
+pub async fn run_trash(action: Option<TrashAction>) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    match action.unwrap_or(TrashAction::List) {
+        TrashAction::List => {
+            let batches = trash::list(&cwd)?;
+            if batches.is_empty() {
+                println!("Nothing is staged for this directory.");
+            }
+            for batch in batches {
+                println!("{}", batch.summary());
+            }
+        }
+        TrashAction::Restore { id } => {
+            let batches = trash::list(&cwd)?;
+            let batch = match &id {
+                Some(id) => batches.iter().find(|b| &b.id == id),
+                None => batches.first(),
+            }
+            .ok_or_else(|| match &id {
+                Some(id) => anyhow!("No staging batch {} for this directory", id),
+                None => anyhow!("Nothing is staged for this directory"),
+            })?;
+            let report = batch.restore()?;
+            println!("Restored {} file(s) from {}.", report.restored, batch.id);
+            for kept in &report.kept {
+                eprintln!("Kept: {}", kept);
+            }
+            if !report.kept.is_empty() {
+                eprintln!("Undo the rename first (`f2 -u -x`), then restore again.");
+            }
+        }
+        TrashAction::Purge { all } => {
+            let keep_days = if all {
+                None
+            } else {
+                Some(Config::load().await?.trash.keep_days)
+            };
+            let removed = trash::purge(&cwd, keep_days)?;
+            println!("Removed {} staging batch(es).", removed);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,168 @@
+use ratatui::{
+    style::Modifier,
+    text::{Line, Span},
+};
+
+use crate::theme::Theme;
+
+/// A word of the new command, or one the old command had.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordChange<S> {
+    Kept(S),
+    Added(S),
+    Removed(S),
+}
+
+impl WordChange<&str> {
+    fn into_owned(self) -> WordChange<String> {
+        match self {
+            WordChange::Kept(word) => WordChange::Kept(word.to_string()),
+            WordChange::Added(word) => WordChange::Added(word.to_string()),
+            WordChange::Removed(word) => WordChange::Removed(word.to_string()),
+        }
+    }
+}
+
+/// `word_diff` of a replaced command against the one on screen, worked
+/// out once per pair rather than on every frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandDiff {
+    old: String,
+    new: String,
+    changes: Vec<WordChange<String>>,
+}
+
+impl CommandDiff {
+    pub fn new(old: &str, new: &str) -> Self {
+        Self {
+            old: old.to_string(),
+            new: new.to_string(),
+            changes: word_diff(old, new)
+                .into_iter()
+                .map(WordChange::into_owned)
+                .collect(),
+        }
+    }
+
+    pub fn is_for(&self, old: &str, new: &str) -> bool {
+        self.old == old && self.new == new
+    }
+}
+
+/// The words of `new` against those of `old`, by longest common
+/// subsequence; where both changed, removals come before additions.
+pub fn word_diff<'a>(old: &'a str, new: &'a str) -> Vec<WordChange<&'a str>> {
+    let old: Vec<&str> = old.split_whitespace().collect();
+    let new: Vec<&str> = new.split_whitespace().collect();
+    // common[i][j]: length of the common subsequence of old[i..] and new[j..].
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut changes = Vec::with_capacity(old.len().max(new.len()));
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            changes.push(WordChange::Kept(old[i]));
+            i += 1;
+            j += 1;
+        } else if j == new.len() || (i < old.len() && common[i + 1][j] >= common[i][j + 1]) {
+            changes.push(WordChange::Removed(old[i]));
+            i += 1;
+        } else {
+            changes.push(WordChange::Added(new[j]));
+            j += 1;
+        }
+    }
+    changes
+}
+
+/// The `CHANGED FROM PREVIOUS` lines of the confirmation view: the new
+/// command with removed words struck through and added ones highlighted,
+/// or marked `[-old-]{+new+}` in plain mode.
+pub fn render_command_diff(diff: &CommandDiff, theme: &Theme) -> Vec<Line<'static>> {
+    let changes = &diff.changes;
+    let added = changes
+        .iter()
+        .filter(|change| matches!(change, WordChange::Added(_)))
+        .count();
+    let removed = changes
+        .iter()
+        .filter(|change| matches!(change, WordChange::Removed(_)))
+        .count();
+    let mut spans = vec![Span::raw("  ")];
+    for (idx, change) in changes.iter().enumerate() {
+        if idx > 0 {
+            spans.push(Span::raw(" "));
+        }
+        spans.push(match (change, theme.plain) {
+            (WordChange::Kept(word), _) => Span::styled(word.to_string(), theme.proposal_cmd_style),
+            (WordChange::Added(word), true) => {
+                Span::styled(format!("{{+{}+}}", word), theme.diff_added_style)
+            }
+            (WordChange::Removed(word), true) => {
+                Span::styled(format!("[-{}-]", word), theme.diff_removed_style)
+            }
+            (WordChange::Added(word), false) => Span::styled(
+                word.to_string(),
+                theme.diff_added_style.add_modifier(Modifier::BOLD),
+            ),
+            (WordChange::Removed(word), false) => Span::styled(
+                word.to_string(),
+                theme.diff_removed_style.add_modifier(Modifier::CROSSED_OUT),
+            ),
+        });
+    }
+    vec![
+        Line::from(Span::styled(
+            format!(
+                "CHANGED FROM PREVIOUS: {} word(s) added, {} removed",
+                added, removed
+            ),
+            theme.diff_header_style,
+        )),
+        Line::from(spans),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use WordChange::*;
+
+    #[test]
+    fn refinements_show_as_word_changes() {
+        assert_eq!(
+            word_diff(
+                "ffmpeg -i a.mp4 -crf 28 out.mp4",
+                "ffmpeg -i a.mp4 -crf 23 -preset slow out.mp4"
+            ),
+            [
+                Kept("ffmpeg"),
+                Kept("-i"),
+                Kept("a.mp4"),
+                Kept("-crf"),
+                Removed("28"),
+                Added("23"),
+                Added("-preset"),
+                Added("slow"),
+                Kept("out.mp4"),
+            ]
+        );
+        assert_eq!(
+            word_diff("f2  -f a", "f2 -f a"),
+            [Kept("f2"), Kept("-f"), Kept("a")]
+        );
+        assert_eq!(word_diff("", "qpdf"), [Added("qpdf")]);
+        assert_eq!(
+            word_diff("qpdf --check", "qpdf"),
+            [Kept("qpdf"), Removed("--check")]
+        );
+    }
+}
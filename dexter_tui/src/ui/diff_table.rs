@@ -0,0 +1,295 @@
+use dexter_plugins::{DiffItem, PreviewContent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    text::Line,
+    widgets::{
+        Cell, Paragraph, Row, Scrollbar, ScrollbarOrientation, ScrollbarState, Table, TableState,
+    },
+    Frame,
+};
+
+use crate::app::state::{App, AppState};
+use crate::ui::main_view::scrollbar_thumb_style;
+
+/// Characters `[` / `]` move the name columns by.
+pub const HSCROLL_STEP: u16 = 8;
+const NUM_WIDTH: u16 = 4;
+const STATUS_WIDTH: u16 = 12;
+/// Rows kept for the table even when the text above it is long.
+const MIN_TABLE_ROWS: u16 = 5;
+/// Rows per page; longer lists are paged with `<` / `>` so only one page
+/// is laid out per frame.
+pub const PAGE_SIZE: usize = 100;
+
+/// What the rename list does overall, for the line above the table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiffSummary {
+    pub renames: usize,
+    /// Entries without an original name: files the command creates.
+    pub adds: usize,
+    /// Entries without a new name: files the command removes.
+    pub removals: usize,
+    /// Entries with a status other than `ok`.
+    pub conflicts: usize,
+}
+
+impl DiffSummary {
+    pub fn of(diffs: &[DiffItem]) -> Self {
+        let mut summary = Self::default();
+        for diff in diffs {
+            let status = diff.status.as_deref().unwrap_or("");
+            if diff.new.is_empty() && !diff.original.is_empty() {
+                summary.removals += 1;
+            } else if !status.is_empty() && !status.eq_ignore_ascii_case("ok") {
+                summary.conflicts += 1;
+            } else if diff.original.is_empty() {
+                summary.adds += 1;
+            } else if diff.original != diff.new {
+                summary.renames += 1;
+            }
+        }
+        summary
+    }
+
+    pub fn label(&self) -> String {
+        let mut label = format!(
+            "{} rename(s), {} add(s), {} conflict(s)",
+            self.renames, self.adds, self.conflicts
+        );
+        if self.removals > 0 {
+            label.push_str(&format!(", {} removal(s)", self.removals));
+        }
+        label
+    }
+}
+
+pub fn page_count(diffs: &[DiffItem]) -> usize {
+    diffs.len().div_ceil(PAGE_SIZE).max(1)
+}
+
+/// The rename list drawn as a table under the preview text, in the
+/// confirmation and edit views.
+pub fn shown_diffs(app: &App) -> Option<&[DiffItem]> {
+    if app.show_debug || app.show_queue || app.show_schedule {
+        return None;
+    }
+    if !matches!(
+        app.state,
+        AppState::AwaitingConfirmation | AppState::EditingCommand
+    ) {
+        return None;
+    }
+    match &app.dry_run_output {
+        Some(PreviewContent::DiffList(diffs)) if !diffs.is_empty() => Some(diffs.as_slice()),
+        _ => None,
+    }
+}
+
+/// Renders `text` at the top of `area` and the table below it, one page of
+/// rows at a time. The output scroll moves the rows of the page; returns
+/// the max scroll, the clamped scroll and the scrollbar area like the plain
+/// text pane does.
+pub fn render(
+    f: &mut Frame,
+    app: &App,
+    area: Rect,
+    text: Vec<Line>,
+    diffs: &[DiffItem],
+) -> (u16, u16, Option<Rect>) {
+    let text_height = (text.len() as u16).min(area.height.saturating_sub(MIN_TABLE_ROWS));
+    let parts = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(text_height), Constraint::Min(1)])
+        .split(area);
+    f.render_widget(Paragraph::new(text).style(app.theme.base_style), parts[0]);
+
+    let pages = page_count(diffs);
+    let page = app.diff_page.min(pages - 1);
+    let rows = sorted(diffs, app.diff_sort_by_status);
+    let page_rows = &rows[page * PAGE_SIZE..((page + 1) * PAGE_SIZE).min(rows.len())];
+
+    let mut table_area = parts[1];
+    if pages > 1 && table_area.height > 1 {
+        table_area.height -= 1;
+        let indicator = Rect {
+            y: table_area.y + table_area.height,
+            height: 1,
+            ..table_area
+        };
+        let first = page * PAGE_SIZE + 1;
+        f.render_widget(
+            Paragraph::new(format!(
+                "PAGE {}/{}  rows {}-{} of {}  ,/.=Page  g/G=First/last",
+                page + 1,
+                pages,
+                first,
+                first + page_rows.len() - 1,
+                diffs.len()
+            ))
+            .style(app.theme.header_subtitle_style),
+            indicator,
+        );
+    }
+    let visible_rows = table_area.height.saturating_sub(1);
+    let max_scroll = (page_rows.len() as u16).saturating_sub(visible_rows);
+    let scroll = app.output_scroll.min(max_scroll);
+    let show_scrollbar = max_scroll > 0 && table_area.width > 1;
+    let mut body = table_area;
+    if show_scrollbar {
+        body.width -= 1;
+    }
+
+    let name_width = body.width.saturating_sub(NUM_WIDTH + STATUS_WIDTH + 3) / 2;
+    let mark = if app.theme.plain { '~' } else { '…' };
+    let hscroll = app.diff_hscroll as usize;
+    let theme = &app.theme;
+    let rows = page_rows.iter().map(|&(index, diff)| {
+        Row::new([
+            Cell::from(format!("{}", index + 1)).style(theme.diff_header_style),
+            Cell::from(clip(
+                diff.status.as_deref().unwrap_or("-"),
+                0,
+                STATUS_WIDTH,
+                mark,
+            ))
+            .style(theme.proposal_cmd_style),
+            Cell::from(clip(&diff.original, hscroll, name_width, mark))
+                .style(theme.diff_removed_style),
+            Cell::from(clip(&diff.new, hscroll, name_width, mark)).style(theme.diff_added_style),
+        ])
+    });
+    let status_header = if app.diff_sort_by_status {
+        "STATUS v"
+    } else {
+        "STATUS"
+    };
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(NUM_WIDTH),
+            Constraint::Length(STATUS_WIDTH),
+            Constraint::Length(name_width),
+            Constraint::Length(name_width),
+        ],
+    )
+    .header(Row::new(["#", status_header, "OLD NAME", "NEW NAME"]).style(theme.diff_header_style))
+    .column_spacing(1)
+    .style(theme.base_style);
+    let mut state = TableState::default().with_offset(scroll as usize);
+    f.render_stateful_widget(table, body, &mut state);
+
+    let scrollbar_rect = show_scrollbar.then(|| Rect {
+        x: table_area.x + table_area.width - 1,
+        width: 1,
+        ..table_area
+    });
+    if let Some(rect) = scrollbar_rect {
+        let mut scrollbar_state = ScrollbarState::new(page_rows.len()).position(scroll as usize);
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .symbols(theme.scrollbar_symbols())
+            .thumb_style(scrollbar_thumb_style(app))
+            .track_style(theme.base_style);
+        f.render_stateful_widget(scrollbar, rect, &mut scrollbar_state);
+    }
+    (max_scroll, scroll, scrollbar_rect)
+}
+
+/// Longest name, so `]` stops once every name has scrolled out.
+pub fn max_hscroll(diffs: &[DiffItem]) -> u16 {
+    diffs
+        .iter()
+        .flat_map(|d| [&d.original, &d.new])
+        .map(|name| name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .saturating_sub(1) as u16
+}
+
+/// Rows with their position in the tool's output. Sorting by status puts
+/// anything other than a plain `ok` first, keeping the original order
+/// within each status.
+fn sorted(diffs: &[DiffItem], by_status: bool) -> Vec<(usize, &DiffItem)> {
+    let mut rows: Vec<(usize, &DiffItem)> = diffs.iter().enumerate().collect();
+    if by_status {
+        rows.sort_by_key(|(_, diff)| {
+            let status = diff.status.as_deref().unwrap_or("").to_lowercase();
+            (matches!(status.as_str(), "" | "ok"), status)
+        });
+    }
+    rows
+}
+
+/// `text` from char `skip` on, cut to `width`; `mark` replaces the edge
+/// characters where text is hidden.
+fn clip(text: &str, skip: usize, width: u16, mark: char) -> String {
+    let width = width as usize;
+    if width == 0 {
+        return String::new();
+    }
+    let mut visible: Vec<char> = text.chars().skip(skip).collect();
+    if skip > 0 && !visible.is_empty() {
+        visible[0] = mark;
+    }
+    if visible.len() > width {
+        visible.truncate(width - 1);
+        visible.push(mark);
+    }
+    visible.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(original: &str, new: &str, status: Option<&str>) -> DiffItem {
+        DiffItem {
+            original: original.to_string(),
+            new: new.to_string(),
+            status: status.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn rows_sort_by_status_and_names_clip_with_scroll() {
+        let diffs = vec![
+            diff("a.jpg", "1.jpg", Some("ok")),
+            diff("b.jpg", "1.jpg", Some("overwriting")),
+            diff("c.jpg", "3.jpg", None),
+            diff("d.jpg", "1.jpg", Some("conflict")),
+        ];
+        let order = |by_status| -> Vec<usize> {
+            sorted(&diffs, by_status)
+                .iter()
+                .map(|(index, _)| *index)
+                .collect()
+        };
+        assert_eq!(order(false), [0, 1, 2, 3]);
+        assert_eq!(order(true), [3, 1, 2, 0]);
+
+        assert_eq!(clip("holiday-photo.jpg", 0, 20, '…'), "holiday-photo.jpg");
+        assert_eq!(clip("holiday-photo.jpg", 0, 8, '…'), "holiday…");
+        assert_eq!(clip("holiday-photo.jpg", 8, 20, '~'), "~hoto.jpg");
+        assert_eq!(clip("holiday-photo.jpg", 4, 6, '~'), "~ay-p~");
+        assert_eq!(clip("a.jpg", 9, 6, '~'), "");
+        assert_eq!(max_hscroll(&diffs), 4);
+
+        let summary = DiffSummary::of(&[
+            diff("a.jpg", "b.jpg", Some("ok")),
+            diff("", "new.jpg", None),
+            diff("same.jpg", "same.jpg", Some("ok")),
+            diff("b.jpg", "a.jpg", Some("overwriting")),
+            diff("empty", "", Some("remove")),
+        ]);
+        assert_eq!(
+            summary,
+            DiffSummary {
+                renames: 1,
+                adds: 1,
+                removals: 1,
+                conflicts: 1
+            }
+        );
+        assert_eq!(page_count(&[]), 1);
+        assert_eq!(page_count(&vec![diffs[0].clone(); PAGE_SIZE + 1]), 2);
+    }
+}
@@ -0,0 +1,231 @@
+use ratatui::style::Style;
+
+use crate::theme::Theme;
+
+/// Coarse token classes for the command preview. This is deliberately not a
+/// shell parser: it only needs to be good enough to make flags, quoted values
+/// and file arguments stand out in long generated command lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellToken {
+    Plain,
+    Program,
+    Flag,
+    String,
+    Path,
+    Operator,
+}
+
+impl ShellToken {
+    pub fn style(self, base: Style, theme: &Theme) -> Style {
+        match self {
+            ShellToken::Plain => base,
+            ShellToken::Program => base.patch(theme.syntax_program_style),
+            ShellToken::Flag => base.patch(theme.syntax_flag_style),
+            ShellToken::String => base.patch(theme.syntax_string_style),
+            ShellToken::Path => base.patch(theme.syntax_path_style),
+            ShellToken::Operator => base.patch(theme.syntax_operator_style),
+        }
+    }
+}
+
+/// Classifies every char of `text` (char-indexed, newlines included) so the
+/// caller can split lines and overlay a cursor without re-tokenizing.
+pub fn classify_shell_chars(text: &str) -> Vec<ShellToken> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = vec![ShellToken::Plain; chars.len()];
+    let mut expect_program = true;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let ch = chars[i];
+        if ch.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if let Some(len) = operator_len(&chars[i..]) {
+            out[i..i + len].fill(ShellToken::Operator);
+            i += len;
+            expect_program = !matches!(ch, '>' | '<');
+            continue;
+        }
+
+        // Scan one word, marking quoted segments as strings as we go.
+        let start = i;
+        let mut quoted = Vec::new();
+        while i < chars.len() {
+            let c = chars[i];
+            if c.is_whitespace() || operator_len(&chars[i..]).is_some() {
+                break;
+            }
+            if c == '\\' {
+                i = (i + 2).min(chars.len());
+                continue;
+            }
+            if c == '\'' || c == '"' {
+                let quote_start = i;
+                i += 1;
+                while i < chars.len() && chars[i] != c {
+                    if c == '"' && chars[i] == '\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                i = (i + 1).min(chars.len());
+                quoted.push(quote_start..i);
+                continue;
+            }
+            i += 1;
+        }
+
+        let word: String = chars[start..i].iter().collect();
+        let kind = if expect_program {
+            ShellToken::Program
+        } else {
+            classify_word(&word)
+        };
+        match kind {
+            ShellToken::Flag => {
+                // `--title=value`: only the flag name gets flag styling.
+                let name_len = word.find('=').map_or(word.chars().count(), |byte| {
+                    word[..byte].chars().count() + 1
+                });
+                out[start..start + name_len].fill(ShellToken::Flag);
+                let value: String = chars[start + name_len..i].iter().collect();
+                if looks_like_path(&value) {
+                    out[start + name_len..i].fill(ShellToken::Path);
+                }
+            }
+            other => out[start..i].fill(other),
+        }
+        for range in quoted {
+            out[range].fill(ShellToken::String);
+        }
+        expect_program = false;
+    }
+
+    out
+}
+
+fn operator_len(rest: &[char]) -> Option<usize> {
+    match rest {
+        ['&', '&', ..] | ['|', '|', ..] | ['>', '>', ..] | ['$', '(', ..] => Some(2),
+        ['|' | ';' | '&' | '>' | '<' | '`', ..] => Some(1),
+        _ => None,
+    }
+}
+
+fn classify_word(word: &str) -> ShellToken {
+    let unquoted = word.trim_matches(|c| c == '"' || c == '\'');
+    if word.starts_with('-') && word.len() > 1 && !is_number(word) {
+        ShellToken::Flag
+    } else if looks_like_path(unquoted) {
+        ShellToken::Path
+    } else {
+        ShellToken::Plain
+    }
+}
+
+fn looks_like_path(word: &str) -> bool {
+    let word = word.trim_matches(|c| c == '"' || c == '\'');
+    if word.is_empty() || word.contains("://") {
+        return false;
+    }
+    if word.contains('/') || word.starts_with('~') || word == "." || word == ".." {
+        return true;
+    }
+    match word.rsplit_once('.') {
+        Some((stem, ext)) => {
+            !stem.is_empty()
+                && (1..=5).contains(&ext.len())
+                && ext.chars().all(|c| c.is_ascii_alphanumeric())
+                && !ext.chars().all(|c| c.is_ascii_digit())
+        }
+        None => false,
+    }
+}
+
+fn is_number(word: &str) -> bool {
+    word.trim_start_matches('-')
+        .chars()
+        .all(|c| c.is_ascii_digit() || c == '.')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Groups consecutive chars of the same class, never across whitespace.
+    fn runs(text: &str) -> Vec<(String, ShellToken)> {
+        let kinds = classify_shell_chars(text);
+        let mut out: Vec<(String, ShellToken)> = Vec::new();
+        let mut after_space = true;
+        for (ch, kind) in text.chars().zip(kinds) {
+            if ch.is_whitespace() {
+                after_space = true;
+                continue;
+            }
+            match out.last_mut() {
+                Some((run, last)) if *last == kind && !after_space => run.push(ch),
+                _ => out.push((ch.to_string(), kind)),
+            }
+            after_space = false;
+        }
+        out
+    }
+
+    fn run(text: &str, kind: ShellToken) -> (String, ShellToken) {
+        (text.to_string(), kind)
+    }
+
+    #[test]
+    fn classifies_ffmpeg_command() {
+        assert_eq!(
+            runs("ffmpeg -i in.mov -c:v libx264 -crf 23 -ss -5 out/clip.mp4"),
+            vec![
+                run("ffmpeg", ShellToken::Program),
+                run("-i", ShellToken::Flag),
+                run("in.mov", ShellToken::Path),
+                run("-c:v", ShellToken::Flag),
+                run("libx264", ShellToken::Plain),
+                run("-crf", ShellToken::Flag),
+                run("23", ShellToken::Plain),
+                run("-ss", ShellToken::Flag),
+                run("-5", ShellToken::Plain),
+                run("out/clip.mp4", ShellToken::Path),
+            ]
+        );
+    }
+
+    #[test]
+    fn quoted_values_and_flag_assignments() {
+        assert_eq!(
+            runs(r#"pandoc --output=report.pdf --metadata "title=My \"Doc\"" notes.md"#),
+            vec![
+                run("pandoc", ShellToken::Program),
+                run("--output=", ShellToken::Flag),
+                run("report.pdf", ShellToken::Path),
+                run("--metadata", ShellToken::Flag),
+                run(r#""title=My"#, ShellToken::String),
+                run(r#"\"Doc\"""#, ShellToken::String),
+                run("notes.md", ShellToken::Path),
+            ]
+        );
+    }
+
+    #[test]
+    fn operators_reset_program_position() {
+        assert_eq!(
+            runs("ls a.txt | wc -l > count.txt"),
+            vec![
+                run("ls", ShellToken::Program),
+                run("a.txt", ShellToken::Path),
+                run("|", ShellToken::Operator),
+                run("wc", ShellToken::Program),
+                run("-l", ShellToken::Flag),
+                run(">", ShellToken::Operator),
+                run("count.txt", ShellToken::Path),
+            ]
+        );
+    }
+}
@@ -2,18 +2,34 @@ use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap},
+    widgets::{Borders, Clear, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap},
     Frame,
 };
 
-use dexter_core::Config;
+use dexter_core::budget::format_tokens;
+use dexter_core::{
+    Config, ConflictPolicy, ExecutionReport, FailureHint, HistoryEntry, Invocation, JobLane,
+    OutputLayout, RunOutcome, ScopeSummary,
+};
 use dexter_plugins::PreviewContent;
+use std::time::Instant;
 
-use crate::app::editor::split_line_at_char;
+use crate::app::editor::{next_grapheme, split_line_at_grapheme};
+use crate::app::help::help_sections;
+use crate::app::log::{LogEntry, LogFilter, Severity};
+use crate::app::model_picker::{picker_entries, route_label};
+use crate::app::queue::JobStatus;
 use crate::app::state::{App, AppState, FocusArea, FooterAction, FooterButton};
+use crate::app::subtasks::SubTaskStatus;
+use crate::app::telemetry;
 use crate::theme::Theme;
+use crate::ui::command_diff::render_command_diff;
+use crate::ui::diff_table::{self, DiffSummary};
+use crate::ui::highlight::{classify_shell_chars, ShellToken};
+use crate::ui::thumbnail::{ThumbnailPayload, THUMBNAIL_COLS, THUMBNAIL_ROWS};
 
 pub fn ui(f: &mut Frame, app: &mut App) {
+    app.refresh_command_diff();
     let area = f.area();
     let compact_width = area.width < 100;
     let very_narrow_width = area.width < 80;
@@ -26,7 +42,7 @@ pub fn ui(f: &mut Frame, app: &mut App) {
     let border_style = app.theme.border_style;
 
     // --- SECTION 1: TITLE (HEADER) ---
-    let header_text = if very_narrow_width {
+    let mut header_text = if very_narrow_width {
         Line::from(vec![
             Span::styled(" DEXTER ", app.theme.header_title_style),
             Span::styled(" // AI CLI ", app.theme.header_subtitle_style),
@@ -40,15 +56,33 @@ pub fn ui(f: &mut Frame, app: &mut App) {
             ),
         ])
     };
+    if let Some(remote) = app.active_remote() {
+        header_text.push_span(Span::styled(
+            format!(" @ {} ", remote.destination()),
+            app.theme.header_title_style,
+        ));
+    }
+    if let Some(alert) = &app.budget_alert {
+        header_text.push_span(Span::styled(
+            if very_narrow_width {
+                " ! BUDGET ".to_string()
+            } else {
+                format!(" ! OVER BUDGET: {} ", alert.label())
+            },
+            app.theme.error_style,
+        ));
+    }
 
     let header = Paragraph::new(header_text).style(block_style).block(
-        Block::default()
+        app.theme
+            .block()
             .borders(Borders::ALL)
             .border_style(border_style)
-            .title(if very_narrow_width {
-                " STATUS: ONLINE "
-            } else {
-                " SYSTEM STATUS: ONLINE "
+            .title(match (very_narrow_width, app.config.offline) {
+                (true, false) => " STATUS: ONLINE ",
+                (true, true) => " STATUS: OFFLINE ",
+                (false, false) => " SYSTEM STATUS: ONLINE ",
+                (false, true) => " SYSTEM STATUS: OFFLINE (LOCAL MODELS) ",
             }),
     );
     f.render_widget(header, main_layout[0]);
@@ -56,8 +90,7 @@ pub fn ui(f: &mut Frame, app: &mut App) {
     // --- SECTION 2: PROPOSAL (OR INPUT/INTENT) ---
     let (proposal_title, proposal_content) = match app.state {
         AppState::Input => {
-            let cursor_visible =
-                app.focus == FocusArea::Proposal && (app.tick_count / 8).is_multiple_of(2);
+            let cursor_visible = cursor_blink_on(app);
             let mut lines = vec![Line::from("")];
             lines.extend(render_multiline_prompt(
                 &app.input,
@@ -70,7 +103,7 @@ pub fn ui(f: &mut Frame, app: &mut App) {
             ));
             (" USER INPUT ", lines)
         }
-        AppState::History => {
+        AppState::History | AppState::TemplatePicker => {
             let mut lines = vec![Line::from("")];
             lines.extend(render_multiline_prompt(
                 &app.input,
@@ -85,18 +118,19 @@ pub fn ui(f: &mut Frame, app: &mut App) {
         }
         AppState::EditingCommand => {
             let mut lines = vec![Line::from("")];
-            let cmd_cursor = app
+            let mut cmd_cursor = app
                 .theme
                 .proposal_cmd_style
-                .add_modifier(Modifier::REVERSED)
-                .add_modifier(Modifier::RAPID_BLINK);
-            let cursor_visible =
-                app.focus == FocusArea::Proposal && (app.tick_count / 8).is_multiple_of(2);
-            lines.extend(render_multiline_prompt(
+                .add_modifier(Modifier::REVERSED);
+            if app.theme.animated() {
+                cmd_cursor = cmd_cursor.add_modifier(Modifier::RAPID_BLINK);
+            }
+            let cursor_visible = cursor_blink_on(app);
+            lines.extend(render_command_prompt(
                 &app.command_draft,
                 Span::styled(" > ", app.theme.header_subtitle_style),
                 Span::styled("   ", app.theme.header_subtitle_style),
-                app.theme.proposal_cmd_style,
+                &app.theme,
                 Some(cmd_cursor),
                 cursor_visible,
                 Some(app.command_cursor),
@@ -107,6 +141,7 @@ pub fn ui(f: &mut Frame, app: &mut App) {
         | AppState::Generating
         | AppState::PendingRouting
         | AppState::PendingGeneration
+        | AppState::ConfirmingBudget
         | AppState::Clarifying => (" USER INTENT ", {
             let mut lines = vec![Line::from("")];
             lines.extend(render_multiline_prompt(
@@ -120,15 +155,93 @@ pub fn ui(f: &mut Frame, app: &mut App) {
             ));
             lines
         }),
+        AppState::Executing => {
+            let cursor_visible = cursor_blink_on(app);
+            let mut lines = vec![Line::from("")];
+            if let Some(cmd) = &app.generated_command {
+                lines.extend(render_command_prompt(
+                    cmd,
+                    Span::styled(" $ ", app.theme.header_subtitle_style),
+                    Span::styled("   ", app.theme.header_subtitle_style),
+                    &app.theme,
+                    None,
+                    false,
+                    None,
+                ));
+            }
+            lines.extend(render_multiline_prompt(
+                &app.queue_input,
+                Span::styled(" + ", app.theme.input_prompt_style),
+                Span::styled("   ", app.theme.input_prompt_style),
+                app.theme.input_text_style,
+                Some(app.theme.input_cursor_style),
+                cursor_visible,
+                Some(app.queue_cursor),
+            ));
+            (" RUNNING // QUEUE NEXT INTENT (ENTER) ", lines)
+        }
+        AppState::AwaitingConfirmation if app.schedule_input.is_some() => {
+            let cursor_visible = cursor_blink_on(app);
+            let mut lines = vec![Line::from("")];
+            if let Some(cmd) = &app.generated_command {
+                lines.extend(render_command_prompt(
+                    cmd,
+                    Span::styled(" > ", app.theme.header_subtitle_style),
+                    Span::styled("   ", app.theme.header_subtitle_style),
+                    &app.theme,
+                    None,
+                    false,
+                    None,
+                ));
+            }
+            lines.extend(render_multiline_prompt(
+                app.schedule_input.as_deref().unwrap_or_default(),
+                Span::styled(" @ ", app.theme.input_prompt_style),
+                Span::styled("   ", app.theme.input_prompt_style),
+                app.theme.input_text_style,
+                Some(app.theme.input_cursor_style),
+                cursor_visible,
+                Some(app.schedule_cursor),
+            ));
+            (
+                " RUN LATER // WHEN? (2am, 14:30, +2h, 2026-01-31 02:00) ",
+                lines,
+            )
+        }
+        AppState::Error(_) if app.override_input.is_some() => {
+            let cursor_visible = cursor_blink_on(app);
+            let mut lines = vec![Line::from("")];
+            if let Some(cmd) = &app.generated_command {
+                lines.extend(render_command_prompt(
+                    cmd,
+                    Span::styled(" > ", app.theme.header_subtitle_style),
+                    Span::styled("   ", app.theme.header_subtitle_style),
+                    &app.theme,
+                    None,
+                    false,
+                    None,
+                ));
+            }
+            lines.extend(render_multiline_prompt(
+                app.override_input.as_deref().unwrap_or_default(),
+                Span::styled(" ! ", app.theme.input_prompt_style),
+                Span::styled("   ", app.theme.input_prompt_style),
+                app.theme.input_text_style,
+                Some(app.theme.input_cursor_style),
+                cursor_visible,
+                Some(app.override_cursor),
+            ));
+            (" OVERRIDE // WHY IS THIS COMMAND SAFE? ", lines)
+        }
         _ => {
             if let Some(cmd) = &app.generated_command {
                 (" PROPOSAL ", {
                     let mut lines = vec![Line::from("")];
-                    lines.extend(render_multiline_prompt(
+                    lines.extend(render_command_prompt(
                         cmd,
                         Span::styled(" > ", app.theme.header_subtitle_style),
                         Span::styled("   ", app.theme.header_subtitle_style),
-                        app.theme.proposal_cmd_style,
+                        &app.theme,
                         None,
                         false,
                         None,
@@ -156,10 +269,11 @@ pub fn ui(f: &mut Frame, app: &mut App) {
     let proposal_block = Paragraph::new(proposal_content)
         .wrap(Wrap { trim: false })
         .block(
-            Block::default()
+            app.theme
+                .block()
                 .borders(Borders::ALL)
                 .border_style(border_style)
-                .title(Span::styled(proposal_title, app.theme.header_title_style)),
+                .title(proposal_title_line(app, proposal_title)),
         );
     f.render_widget(proposal_block, main_layout[1]);
     app.proposal_rect = Some(Rect {
@@ -174,23 +288,54 @@ pub fn ui(f: &mut Frame, app: &mut App) {
 
     // --- SECTION 4: OUTPUT (PREVIEW / LOGS / STATUS) + OPTIONAL SCROLLBAR ---
     let output_title = output_title(app);
-    let output_block = Block::default()
+    let output_block = app
+        .theme
+        .block()
         .borders(Borders::ALL)
         .border_style(border_style)
         .title(Span::styled(output_title, app.theme.header_title_style));
     f.render_widget(&output_block, main_layout[3]);
 
-    let inner = output_block.inner(main_layout[3]);
-    let output_viewport_height = main_layout[3].height.saturating_sub(2);
-    let output_inner_width = main_layout[3].width.saturating_sub(2);
+    let mut inner = output_block.inner(main_layout[3]);
+    if let Some((primary, log_area, log_border)) = split_output_area(app, inner) {
+        render_log_pane(f, app, log_area, log_border);
+        inner = primary;
+    }
+    app.thumbnail_rect = None;
+    if let Some(text_area) = render_thumbnail(f, app, inner) {
+        inner = text_area;
+    }
+    let output_viewport_height = inner.height;
+    let output_inner_width = inner.width;
     app.output_text_width = output_inner_width.saturating_sub(1);
+    app.output_viewport_height = output_viewport_height;
+
+    if keeps_scrollback(app) {
+        let mut scrollback = std::mem::take(&mut app.scrollback);
+        scrollback.track(output_title, &build_output_lines(app), &app.theme);
+        app.scrollback = scrollback;
+    }
+    // Scrolled back, the archived lines sit above the live view, which
+    // then starts at its top.
+    let earlier: Vec<Line> = app.scrollback.visible().cloned().collect();
+    let scrolled_back = earlier.len();
+
+    let diffs = diff_table::shown_diffs(app).filter(|_| scrolled_back == 0);
 
-    let (max_scroll, clamped_scroll, scrollbar_rect) = {
-        let output_content = build_output_lines(app);
-        let output_line_count = output_content.len() as u16;
-        let max_scroll = output_line_count.saturating_sub(output_viewport_height);
-        let clamped_scroll = app.output_scroll.min(max_scroll);
-        let show_scrollbar = max_scroll > 0 && inner.width > 1 && inner.height > 0;
+    let (max_scroll, clamped_scroll, scrollbar_rect) = if let Some(diffs) = diffs {
+        diff_table::render(f, app, inner, build_output_lines(app), diffs)
+    } else {
+        let live_content = build_output_lines(app);
+        let live_line_count = live_content.len() as u16;
+        let output_content: Vec<Line> = earlier.into_iter().chain(live_content).collect();
+        let max_scroll = live_line_count.saturating_sub(output_viewport_height);
+        let clamped_scroll = if scrolled_back > 0 {
+            0
+        } else {
+            app.output_scroll.min(max_scroll)
+        };
+        let show_scrollbar =
+            (max_scroll > 0 || !app.scrollback.is_empty()) && inner.width > 1 && inner.height > 0;
         let scrollbar_rect = if show_scrollbar {
             Some(Rect {
                 x: inner.x + inner.width - 1,
@@ -214,11 +359,13 @@ pub fn ui(f: &mut Frame, app: &mut App) {
         f.render_widget(output_para, text_area);
 
         if show_scrollbar {
-            let content_len = output_line_count.max(1) as usize;
-            let mut scrollbar_state =
-                ScrollbarState::new(content_len).position(clamped_scroll as usize);
+            let archived = app.scrollback.len();
+            let content_len = (archived + live_line_count as usize).max(1);
+            let position = archived - scrolled_back + clamped_scroll as usize;
+            let mut scrollbar_state = ScrollbarState::new(content_len).position(position);
             let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
-                .thumb_style(app.theme.border_style)
+                .symbols(app.theme.scrollbar_symbols())
+                .thumb_style(scrollbar_thumb_style(app))
                 .track_style(app.theme.base_style);
             f.render_stateful_widget(scrollbar, inner, &mut scrollbar_state);
         }
@@ -233,7 +380,9 @@ pub fn ui(f: &mut Frame, app: &mut App) {
     // --- SECTION 5: FOOTER (MODE/MODEL/PROVIDER) ---
     let state_name = format!("{:?}", app.state).to_uppercase();
     let provider_name = get_provider_name(&app.config);
-    let footer_block = Block::default()
+    let footer_block = app
+        .theme
+        .block()
         .borders(Borders::TOP)
         .border_style(border_style);
     f.render_widget(&footer_block, main_layout[4]);
@@ -245,6 +394,11 @@ pub fn ui(f: &mut Frame, app: &mut App) {
         " [SETTINGS] "
     };
     let settings_width = settings_label.len() as u16;
+    let settings_style = if app.focus == FocusArea::SettingsButton {
+        app.theme.footer_selected_style
+    } else {
+        app.theme.footer_key_style
+    };
 
     if compact_width && footer_inner.height >= 2 {
         let footer_rows = Layout::default()
@@ -277,22 +431,25 @@ pub fn ui(f: &mut Frame, app: &mut App) {
             let info = Paragraph::new(vec![line1]).style(block_style);
             f.render_widget(info, top_split[0]);
 
-            let settings_button = Paragraph::new(settings_label).style(app.theme.footer_key_style);
+            let settings_button = Paragraph::new(settings_label).style(settings_style);
             f.render_widget(settings_button, top_split[1]);
             app.settings_button_rect = Some(top_split[1]);
         } else {
-            let settings_button = Paragraph::new(settings_label).style(app.theme.footer_key_style);
+            let settings_button = Paragraph::new(settings_label).style(settings_style);
             f.render_widget(settings_button, top);
             app.settings_button_rect = Some(top);
         }
 
-        let line2 = Line::from(vec![
+        let mut line2 = Line::from(vec![
             Span::styled(" MODEL: ", app.theme.footer_text_style),
             Span::styled(
                 &app.config.models.executor_model,
                 app.theme.footer_highlight_style,
             ),
         ]);
+        line2.spans.extend(override_badge(app));
+        line2.spans.extend(read_only_badge(app));
+        line2.spans.extend(update_badge(app));
         let model_info = Paragraph::new(vec![line2]).style(block_style);
         f.render_widget(model_info, bottom);
     } else if footer_inner.width > settings_width {
@@ -301,7 +458,7 @@ pub fn ui(f: &mut Frame, app: &mut App) {
             .constraints([Constraint::Min(1), Constraint::Length(settings_width)])
             .split(footer_inner);
 
-        let line1 = Line::from(vec![
+        let mut line1 = Line::from(vec![
             Span::styled(" MODE: ", app.theme.footer_text_style),
             Span::styled(&state_name, app.theme.footer_highlight_style),
             Span::styled("  MODEL: ", app.theme.footer_text_style),
@@ -312,10 +469,13 @@ pub fn ui(f: &mut Frame, app: &mut App) {
             Span::styled("  PROVIDER: ", app.theme.footer_text_style),
             Span::styled(&provider_name, app.theme.footer_highlight_style),
         ]);
+        line1.spans.extend(override_badge(app));
+        line1.spans.extend(read_only_badge(app));
+        line1.spans.extend(update_badge(app));
         let info = Paragraph::new(vec![line1]).style(block_style);
         f.render_widget(info, footer_layout[0]);
 
-        let settings_button = Paragraph::new(settings_label).style(app.theme.footer_key_style);
+        let settings_button = Paragraph::new(settings_label).style(settings_style);
         f.render_widget(settings_button, footer_layout[1]);
         app.settings_button_rect = Some(footer_layout[1]);
     } else {
@@ -333,6 +493,647 @@ pub fn ui(f: &mut Frame, app: &mut App) {
         let info = Paragraph::new(vec![line1]).style(block_style);
         f.render_widget(info, footer_inner);
     }
+
+    render_mentions(f, app, area);
+    render_model_picker(f, app, area);
+    render_bookmark_picker(f, app, area);
+    render_palette(f, app, area);
+    render_search(f, app, area);
+    render_output_viewer(f, app, area);
+    render_help(f, app, area);
+}
+
+/// VIEW FULL OUTPUT: the saved output over the whole screen, unwrapped,
+/// with the file it came from at the bottom.
+fn render_output_viewer(f: &mut Frame, app: &mut App, area: Rect) {
+    let theme = &app.theme;
+    let Some(viewer) = &mut app.output_viewer else {
+        return;
+    };
+    let block = theme
+        .block()
+        .borders(Borders::ALL)
+        .border_style(theme.border_style)
+        .title(Span::styled(
+            format!(" FULL OUTPUT // {} LINES ", viewer.lines.len()),
+            theme.header_title_style,
+        ));
+    let inner = block.inner(area);
+    viewer.rows = inner.height.saturating_sub(1) as usize;
+    viewer.scroll = viewer.scroll.min(viewer.max_scroll());
+    let mut lines: Vec<Line> = viewer
+        .lines
+        .iter()
+        .skip(viewer.scroll)
+        .take(viewer.rows)
+        .map(|line| Line::from(Span::styled(line.as_str(), theme.header_subtitle_style)))
+        .collect();
+    lines.resize(viewer.rows, Line::from(""));
+    let hint = format!(
+        " {}-{}  UP/DOWN PGUP/PGDN HOME/END=SCROLL  ESC=CLOSE  {}",
+        (viewer.scroll + 1).min(viewer.lines.len()),
+        (viewer.scroll + viewer.rows).min(viewer.lines.len()),
+        viewer.path.display()
+    );
+    lines.push(Line::from(Span::styled(
+        truncate_with_ellipsis(&hint, inner.width as usize),
+        theme.footer_text_style,
+    )));
+
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(lines).style(theme.base_style).block(block),
+        area,
+    );
+    if viewer.max_scroll() > 0 && inner.height > 1 {
+        let track = Rect {
+            height: inner.height - 1,
+            ..inner
+        };
+        let mut state = ScrollbarState::new(viewer.max_scroll() + 1).position(viewer.scroll);
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .symbols(theme.scrollbar_symbols())
+            .thumb_style(theme.footer_highlight_style)
+            .track_style(theme.base_style);
+        f.render_stateful_widget(scrollbar, track, &mut state);
+    }
+}
+
+/// The `?` overlay: the current state's actions and keys, centered.
+fn render_help(f: &mut Frame, app: &App, area: Rect) {
+    if !app.show_help {
+        return;
+    }
+    let sections = help_sections(app);
+    let key_width = sections
+        .iter()
+        .flat_map(|(_, entries)| entries.iter().map(|entry| entry.key.len()))
+        .max()
+        .unwrap_or(0);
+    let width = area.width.saturating_sub(4).min(64);
+    let inner_width = width.saturating_sub(2) as usize;
+    let mut lines = Vec::new();
+    for (title, entries) in &sections {
+        if !lines.is_empty() {
+            lines.push(Line::default());
+        }
+        lines.push(Line::from(Span::styled(
+            format!(" {}", title),
+            app.theme.header_title_style,
+        )));
+        for entry in entries {
+            let label_width = inner_width.saturating_sub(key_width + 4);
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!(" {:>key_width$}  ", entry.key),
+                    app.theme.footer_key_style,
+                ),
+                Span::styled(
+                    truncate_with_ellipsis(&entry.label, label_width),
+                    app.theme.header_subtitle_style,
+                ),
+            ]));
+        }
+    }
+    let height = (lines.len() as u16 + 2).min(area.height.saturating_sub(2));
+    if width < 30 || height < 4 {
+        return;
+    }
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+
+    f.render_widget(Clear, popup);
+    let block = app
+        .theme
+        .block()
+        .borders(Borders::ALL)
+        .border_style(app.theme.border_style)
+        .title(Span::styled(" KEYS (?) ", app.theme.header_title_style));
+    f.render_widget(
+        Paragraph::new(lines)
+            .style(app.theme.base_style)
+            .block(block),
+        popup,
+    );
+}
+
+/// The `@` file list, under the input it completes.
+fn render_mentions(f: &mut Frame, app: &App, area: Rect) {
+    let matches = app.mention_matches();
+    let Some(input) = app.proposal_rect.filter(|_| !matches.is_empty()) else {
+        return;
+    };
+    let selected = app.mention_selection(&matches);
+    let width = input.width.saturating_sub(4).min(60);
+    let y = input.y + input.height;
+    let height = (matches.len() as u16 + 2).min(area.bottom().saturating_sub(y));
+    if width < 20 || height < 3 {
+        return;
+    }
+    let popup = Rect {
+        x: input.x + 2,
+        y,
+        width,
+        height,
+    };
+
+    let inner_width = width.saturating_sub(2) as usize;
+    let lines: Vec<Line> = matches
+        .iter()
+        .enumerate()
+        .take(height.saturating_sub(2) as usize)
+        .map(|(idx, name)| {
+            let style = if idx == selected {
+                app.theme.history_selected_style
+            } else {
+                app.theme.header_subtitle_style
+            };
+            Line::from(Span::styled(
+                format!(
+                    " {}",
+                    truncate_with_ellipsis(name, inner_width.saturating_sub(2))
+                ),
+                style,
+            ))
+        })
+        .collect();
+
+    f.render_widget(Clear, popup);
+    let block = app
+        .theme
+        .block()
+        .borders(Borders::ALL)
+        .border_style(app.theme.border_style)
+        .title(Span::styled(" FILES (TAB) ", app.theme.header_title_style));
+    f.render_widget(
+        Paragraph::new(lines)
+            .style(app.theme.base_style)
+            .block(block),
+        popup,
+    );
+}
+
+/// The Ctrl+G model picker, placed like the palette.
+fn render_model_picker(f: &mut Frame, app: &App, area: Rect) {
+    let Some(picker) = &app.model_picker else {
+        return;
+    };
+    let entries = picker_entries(&app.config);
+    let width = area.width.saturating_sub(4).min(60);
+    let height = (entries.len() as u16 + 2).min(area.height.saturating_sub(4));
+    if width < 20 || height < 3 {
+        return;
+    }
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + 3,
+        width,
+        height,
+    };
+
+    let inner_width = width.saturating_sub(2) as usize;
+    let rows = height.saturating_sub(2) as usize;
+    let first = picker.selected.saturating_sub(rows.saturating_sub(1));
+    let lines: Vec<Line> = entries
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(rows)
+        .map(|(idx, entry)| {
+            let label = match entry {
+                Some(route) => route_label(route),
+                None => "Configured routing".to_string(),
+            };
+            let marker = app
+                .theme
+                .marker(entry.as_ref() == app.model_override.as_ref());
+            let row = format!(
+                " {} {}",
+                marker,
+                truncate_with_ellipsis(&label, inner_width.saturating_sub(4))
+            );
+            let style = if idx == picker.selected {
+                app.theme.history_selected_style
+            } else {
+                app.theme.header_subtitle_style
+            };
+            Line::from(Span::styled(row, style))
+        })
+        .collect();
+
+    f.render_widget(Clear, popup);
+    let block = app
+        .theme
+        .block()
+        .borders(Borders::ALL)
+        .border_style(app.theme.border_style)
+        .title(Span::styled(
+            " NEXT GENERATION (CTRL+G) ",
+            app.theme.header_title_style,
+        ));
+    f.render_widget(
+        Paragraph::new(lines)
+            .style(app.theme.base_style)
+            .block(block),
+        popup,
+    );
+}
+
+/// The Ctrl+B directory switcher, placed like the model picker: each
+/// bookmark with its path, the working directory's marked, and a line of
+/// keys below.
+fn render_bookmark_picker(f: &mut Frame, app: &App, area: Rect) {
+    let Some(picker) = &app.bookmark_picker else {
+        return;
+    };
+    let bookmarks = &app.config.bookmarks;
+    let width = area.width.saturating_sub(4).min(72);
+    let height = (bookmarks.len().max(1) as u16 + 3).min(area.height.saturating_sub(4));
+    if width < 20 || height < 4 {
+        return;
+    }
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + 3,
+        width,
+        height,
+    };
+
+    let inner_width = width.saturating_sub(2) as usize;
+    let rows = height.saturating_sub(3) as usize;
+    let first = picker.selected.saturating_sub(rows.saturating_sub(1));
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let name_width = bookmarks
+        .iter()
+        .map(|bookmark| bookmark.name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .min(inner_width / 3);
+    let mut lines: Vec<Line> = bookmarks
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(rows)
+        .map(|(idx, bookmark)| {
+            let marker = app.theme.marker(bookmark.dir() == cwd);
+            let name = truncate_with_ellipsis(&bookmark.name, name_width);
+            let row = format!(
+                " {} {:<width$}  {}",
+                marker,
+                name,
+                bookmark.path,
+                width = name_width
+            );
+            let style = if idx == picker.selected {
+                app.theme.history_selected_style
+            } else {
+                app.theme.header_subtitle_style
+            };
+            Line::from(Span::styled(
+                truncate_with_ellipsis(&row, inner_width),
+                style,
+            ))
+        })
+        .collect();
+    if bookmarks.is_empty() {
+        lines.push(Line::from(Span::styled(
+            " No bookmarks yet.",
+            app.theme.header_subtitle_style,
+        )));
+    }
+    lines.push(Line::from(Span::styled(
+        truncate_with_ellipsis(
+            " ENTER=SWITCH  A=BOOKMARK THIS FOLDER  D=REMOVE  ESC=CLOSE",
+            inner_width,
+        ),
+        app.theme.footer_text_style,
+    )));
+
+    f.render_widget(Clear, popup);
+    let block = app
+        .theme
+        .block()
+        .borders(Borders::ALL)
+        .border_style(app.theme.border_style)
+        .title(Span::styled(
+            " BOOKMARKS (CTRL+B) ",
+            app.theme.header_title_style,
+        ));
+    f.render_widget(
+        Paragraph::new(lines)
+            .style(app.theme.base_style)
+            .block(block),
+        popup,
+    );
+}
+
+/// The Ctrl+K palette, over the top of the screen below the header.
+fn render_palette(f: &mut Frame, app: &App, area: Rect) {
+    let Some(palette) = &app.palette else {
+        return;
+    };
+    let matches = palette.matches(app);
+    let width = area.width.saturating_sub(4).min(60);
+    let height = (matches.len().max(1) as u16 + 3).min(area.height.saturating_sub(4));
+    if width < 20 || height < 4 {
+        return;
+    }
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + 3,
+        width,
+        height,
+    };
+
+    let mut lines = vec![Line::from(vec![
+        Span::styled(" > ", app.theme.input_prompt_style),
+        Span::styled(palette.query.as_str(), app.theme.input_text_style),
+        Span::styled(" ", app.theme.input_cursor_style),
+    ])];
+    let inner_width = width.saturating_sub(2) as usize;
+    let rows = height.saturating_sub(3) as usize;
+    if matches.is_empty() {
+        lines.push(Line::from(Span::styled(
+            " No matching action.",
+            app.theme.header_subtitle_style,
+        )));
+    }
+    let first = palette.selected.saturating_sub(rows.saturating_sub(1));
+    for (idx, entry) in matches.iter().enumerate().skip(first).take(rows) {
+        let key = entry.key.unwrap_or_default();
+        let label_width = inner_width.saturating_sub(key.len() + 3);
+        let row = format!(
+            " {:<label_width$} {} ",
+            truncate_with_ellipsis(&entry.label, label_width),
+            key
+        );
+        let style = if idx == palette.selected {
+            app.theme.history_selected_style
+        } else {
+            app.theme.header_subtitle_style
+        };
+        lines.push(Line::from(Span::styled(row, style)));
+    }
+
+    f.render_widget(Clear, popup);
+    let block = app
+        .theme
+        .block()
+        .borders(Borders::ALL)
+        .border_style(app.theme.border_style)
+        .title(Span::styled(
+            " ACTIONS (CTRL+K) ",
+            app.theme.header_title_style,
+        ));
+    f.render_widget(
+        Paragraph::new(lines)
+            .style(app.theme.base_style)
+            .block(block),
+        popup,
+    );
+}
+
+/// The Ctrl+F search: the query, then the ranked hits, or the log lines
+/// around the hit that was opened.
+fn render_search(f: &mut Frame, app: &App, area: Rect) {
+    let Some(search) = &app.search else {
+        return;
+    };
+    let width = area.width.saturating_sub(4).min(100);
+    let height = area.height.saturating_sub(6).min(24);
+    if width < 30 || height < 5 {
+        return;
+    }
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + 3,
+        width,
+        height,
+    };
+    let inner_width = width.saturating_sub(2) as usize;
+    let rows = height.saturating_sub(4) as usize;
+
+    let (title, hint, mut lines) = match &search.excerpt {
+        Some(excerpt) => {
+            let first = excerpt.hit.saturating_sub(rows / 2);
+            let lines = excerpt
+                .lines
+                .iter()
+                .enumerate()
+                .skip(first)
+                .take(rows + 1)
+                .map(|(idx, line)| {
+                    let style = if idx == excerpt.hit {
+                        app.theme.history_selected_style
+                    } else {
+                        app.theme.header_subtitle_style
+                    };
+                    Line::from(Span::styled(
+                        format!(" {}", truncate_with_ellipsis(line, inner_width - 1)),
+                        style,
+                    ))
+                })
+                .collect();
+            (
+                format!(" LOG {} ", excerpt.title),
+                "ESC=BACK".to_string(),
+                lines,
+            )
+        }
+        None => {
+            let matches = search.matches();
+            let mut lines = vec![Line::from(vec![
+                Span::styled(" > ", app.theme.input_prompt_style),
+                Span::styled(search.query.as_str(), app.theme.input_text_style),
+                Span::styled(" ", app.theme.input_cursor_style),
+            ])];
+            if matches.is_empty() {
+                lines.push(Line::from(Span::styled(
+                    " Nothing in history, templates or the log matches.",
+                    app.theme.header_subtitle_style,
+                )));
+            }
+            let first = search.selected.saturating_sub(rows.saturating_sub(1));
+            for (idx, hit) in matches.iter().enumerate().skip(first).take(rows) {
+                let detail_width = (inner_width / 3).min(hit.detail.chars().count());
+                let text_width = inner_width.saturating_sub(detail_width + 11);
+                let row = format!(
+                    " {:<7} {:<text_width$} {}",
+                    hit.kind(),
+                    truncate_with_ellipsis(&hit.text, text_width),
+                    truncate_with_ellipsis(&hit.detail, detail_width)
+                );
+                let style = if idx == search.selected {
+                    app.theme.history_selected_style
+                } else {
+                    app.theme.header_subtitle_style
+                };
+                lines.push(Line::from(Span::styled(row, style)));
+            }
+            let hint = match search.selected_hit() {
+                Some(hit) => format!("{}  ESC=CLOSE", hit.action_label()),
+                None => "ESC=CLOSE".to_string(),
+            };
+            (format!(" SEARCH {} MATCH(ES) ", matches.len()), hint, lines)
+        }
+    };
+    while lines.len() < rows + 1 {
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from(Span::styled(
+        format!(" {}", hint),
+        app.theme.footer_text_style,
+    )));
+
+    f.render_widget(Clear, popup);
+    let block = app
+        .theme
+        .block()
+        .borders(Borders::ALL)
+        .border_style(app.theme.border_style)
+        .title(Span::styled(title, app.theme.header_title_style));
+    f.render_widget(
+        Paragraph::new(lines)
+            .style(app.theme.base_style)
+            .block(block),
+        popup,
+    );
+}
+
+/// Splits the output pane for the side-by-side and stacked layouts. The
+/// input screen and the debug, queue and schedule panels keep the whole
+/// pane, since they already show logs or don't need them. Side by side
+/// falls back to stacked on narrow terminals.
+fn split_output_area(app: &App, area: Rect) -> Option<(Rect, Rect, Borders)> {
+    if app.output_layout == OutputLayout::Single
+        || app.state == AppState::Input
+        || app.show_debug
+        || app.show_queue
+        || app.show_schedule
+    {
+        return None;
+    }
+    let side_by_side = app.output_layout == OutputLayout::SideBySide && area.width >= 100;
+    let (direction, log_size, log_border) = if side_by_side {
+        (
+            Direction::Horizontal,
+            Constraint::Percentage(40),
+            Borders::LEFT,
+        )
+    } else {
+        (
+            Direction::Vertical,
+            Constraint::Length((area.height * 2 / 5).clamp(3, 10)),
+            Borders::TOP,
+        )
+    };
+    if (!side_by_side && area.height < 8) || area.width < 20 {
+        return None;
+    }
+    let parts = Layout::default()
+        .direction(direction)
+        .constraints([Constraint::Min(1), log_size])
+        .split(area);
+    Some((parts[0], parts[1], log_border))
+}
+
+/// The newest log lines that fit in `area`, oldest first.
+fn render_log_pane(f: &mut Frame, app: &App, area: Rect, border: Borders) {
+    let block = app
+        .theme
+        .block()
+        .borders(border)
+        .border_style(app.theme.border_style)
+        .title(Span::styled(
+            match app.log_filter {
+                LogFilter::All => " LIVE LOG ",
+                LogFilter::ErrorsOnly => " LIVE LOG // ERRORS ONLY ",
+            },
+            app.theme.header_title_style,
+        ));
+    let visible = block.inner(area).height as usize;
+    let mut lines: Vec<Line> = app
+        .visible_logs()
+        .rev()
+        .take(visible)
+        .map(|log| log_line(log, &app.theme))
+        .collect();
+    lines.reverse();
+    let pane = Paragraph::new(lines)
+        .style(app.theme.base_style)
+        .block(block);
+    f.render_widget(pane, area);
+}
+
+/// Draws the confirmation thumbnail on the right of the output pane and
+/// returns the area left for text. Graphics protocols only get an empty box
+/// here; the runtime paints the image into `app.thumbnail_rect` after the
+/// frame is flushed.
+fn render_thumbnail(f: &mut Frame, app: &mut App, area: Rect) -> Option<Rect> {
+    // Graphics would be painted over the palette.
+    if app.state != AppState::AwaitingConfirmation
+        || app.show_debug
+        || app.palette.is_some()
+        || app.search.is_some()
+        || app.output_viewer.is_some()
+        || app.show_help
+    {
+        return None;
+    }
+    let thumbnail = app.thumbnail.as_ref()?;
+    let box_width = THUMBNAIL_COLS + 2;
+    if area.width < box_width + 40 || area.height < THUMBNAIL_ROWS + 2 {
+        return None;
+    }
+
+    let split = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(1), Constraint::Length(box_width)])
+        .split(area);
+    let name = thumbnail
+        .source
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let title: String = format!(" {} ", name)
+        .chars()
+        .take(box_width.saturating_sub(2) as usize)
+        .collect();
+    let block = app
+        .theme
+        .block()
+        .borders(Borders::ALL)
+        .border_style(app.theme.border_style)
+        .title(Span::styled(title, app.theme.header_subtitle_style));
+    let box_area = Rect {
+        height: THUMBNAIL_ROWS + 2,
+        ..split[1]
+    };
+    let image_area = block.inner(box_area);
+    f.render_widget(block, box_area);
+
+    match &thumbnail.payload {
+        ThumbnailPayload::Ascii(rows) => {
+            let lines: Vec<Line> = rows
+                .iter()
+                .map(|row| Line::from(Span::styled(row.as_str(), app.theme.header_title_style)))
+                .collect();
+            f.render_widget(
+                Paragraph::new(lines).style(app.theme.base_style),
+                image_area,
+            );
+        }
+        ThumbnailPayload::Graphics(_) => app.thumbnail_rect = Some(image_area),
+    }
+
+    Some(Rect {
+        width: split[0].width.saturating_sub(1),
+        ..split[0]
+    })
 }
 
 fn main_layout_constraints(area: Rect) -> [Constraint; 5] {
@@ -370,6 +1171,15 @@ fn compact_button_label(label: &str) -> String {
     }
 }
 
+/// The thumb takes the buttons' focus look while the arrow keys move it.
+pub(crate) fn scrollbar_thumb_style(app: &App) -> Style {
+    if app.focus == FocusArea::Scrollbar {
+        app.theme.footer_selected_style
+    } else {
+        app.theme.border_style
+    }
+}
+
 fn button_row_width(buttons: &[(FooterAction, String)]) -> u16 {
     let mut width = 0u16;
     for (idx, (_, label)) in buttons.iter().enumerate() {
@@ -402,7 +1212,7 @@ fn render_button_row(f: &mut Frame, app: &mut App, area: Rect) {
     if area.width == history_width {
         render_button_bar(f, app, Rect { width: 0, ..area });
         let mut history_style = app.theme.footer_key_style;
-        if app.state == AppState::History {
+        if app.state == AppState::History || app.focus == FocusArea::HistoryButton {
             history_style = app.theme.footer_selected_style;
         }
         let history_button = Paragraph::new(history_label).style(history_style);
@@ -428,7 +1238,7 @@ fn render_button_row(f: &mut Frame, app: &mut App, area: Rect) {
     render_button_bar(f, app, left_area);
 
     let mut history_style = app.theme.footer_key_style;
-    if app.state == AppState::History {
+    if app.state == AppState::History || app.focus == FocusArea::HistoryButton {
         history_style = app.theme.footer_selected_style;
     }
     let history_button = Paragraph::new(history_label).style(history_style);
@@ -514,12 +1324,78 @@ fn render_button_bar(f: &mut Frame, app: &mut App, area: Rect) {
     }
 }
 
+/// The proposal title, followed by a tab per task when the input was
+/// split: the one on screen highlighted, each marked with how it stands.
+fn proposal_title_line<'a>(app: &App, title: &'a str) -> Line<'a> {
+    let mut spans = vec![Span::styled(title, app.theme.header_title_style)];
+    let Some(subtasks) = &app.subtasks else {
+        return Line::from(spans);
+    };
+    if matches!(
+        app.state,
+        AppState::Input | AppState::History | AppState::TemplatePicker
+    ) {
+        return Line::from(spans);
+    }
+    for (idx, tab) in subtasks.tabs.iter().enumerate() {
+        let mark = match &tab.status {
+            SubTaskStatus::Preparing => " ...",
+            SubTaskStatus::Ready { .. } => "",
+            SubTaskStatus::Failed(_) => " failed",
+            SubTaskStatus::Ran { success: true, .. } => " done",
+            SubTaskStatus::Ran { success: false, .. } => " failed",
+        };
+        let style = if subtasks.active == Some(idx) {
+            app.theme.history_selected_style
+        } else {
+            app.theme.header_subtitle_style
+        };
+        spans.push(Span::styled(
+            format!(
+                " {} {}{} ",
+                idx + 1,
+                truncate_with_ellipsis(&tab.intent, 20),
+                mark
+            ),
+            style,
+        ));
+        spans.push(Span::raw(" "));
+    }
+    spans.push(Span::styled("< > ", app.theme.header_subtitle_style));
+    Line::from(spans)
+}
+
+/// Shown while `--read-only` is on.
+fn read_only_badge(app: &App) -> Option<Span<'static>> {
+    app.read_only
+        .then(|| Span::styled("  READ-ONLY ", app.theme.warning_style))
+}
+
+/// The startup check found a newer release.
+fn update_badge(app: &App) -> Option<Span<'static>> {
+    let release = app.update_release.as_ref()?;
+    Some(Span::styled(
+        format!("  NEW VERSION: {} ", release.version()),
+        app.theme.warning_style,
+    ))
+}
+
+/// `NEXT: PROVIDER / model` while a Ctrl+G pick waits for the next
+/// generation.
+fn override_badge(app: &App) -> Option<Span<'static>> {
+    let route = app.model_override.as_ref()?;
+    Some(Span::styled(
+        format!("  NEXT: {} ", route_label(route)),
+        app.theme.footer_key_style,
+    ))
+}
+
 fn get_provider_name(config: &Config) -> String {
     if let Some(primary_route) = config.models.executor_routes.first() {
         return primary_route.provider.display_name().to_string();
     }
 
-    let providers = config.configured_providers();
+    let providers = config.llm_providers();
     if let Some(provider) = providers
         .iter()
         .find(|p| p.models.iter().any(|m| m == &config.models.executor_model))
@@ -532,19 +1408,64 @@ fn get_provider_name(config: &Config) -> String {
     }
 }
 
-fn footer_buttons_for_state(app: &App) -> Vec<(FooterAction, String)> {
+pub(crate) fn footer_buttons_for_state(app: &App) -> Vec<(FooterAction, String)> {
+    let mut buttons = state_buttons(app);
+    if app.read_only {
+        buttons.retain(|(action, _)| !action.runs_commands());
+    }
+    buttons
+}
+
+fn state_buttons(app: &App) -> Vec<(FooterAction, String)> {
     match &app.state {
-        AppState::Input => vec![
-            (FooterAction::Submit, "SUBMIT".to_string()),
-            (FooterAction::ClearInput, "CLEAR".to_string()),
-            (
-                FooterAction::ToggleDebug,
-                if app.show_debug {
-                    "DEBUG:ON".to_string()
-                } else {
-                    "DEBUG:OFF".to_string()
-                },
-            ),
+        AppState::Input if !app.interrupted_runs.is_empty() => vec![
+            (FooterAction::ResumeInterrupted, "RESUME".to_string()),
+            (FooterAction::RollBackInterrupted, "ROLL BACK".to_string()),
+            (FooterAction::DismissInterrupted, "DISMISS".to_string()),
+            (FooterAction::Quit, "QUIT".to_string()),
+        ],
+        AppState::Input => {
+            let mut buttons = vec![
+                (FooterAction::Submit, "SUBMIT".to_string()),
+                (FooterAction::ClearInput, "CLEAR".to_string()),
+                (FooterAction::OpenTemplates, "TEMPLATES".to_string()),
+                (
+                    FooterAction::ToggleDebug,
+                    if app.show_debug {
+                        "DEBUG:ON".to_string()
+                    } else {
+                        "DEBUG:OFF".to_string()
+                    },
+                ),
+            ];
+            if app.show_debug {
+                if let Some(level) = telemetry::verbosity() {
+                    buttons.push((
+                        FooterAction::CycleLogLevel,
+                        format!("LOG:{}", level.to_string().to_uppercase()),
+                    ));
+                }
+            }
+            if !app.config.remotes.is_empty() {
+                buttons.push((
+                    FooterAction::CycleTarget,
+                    format!("TARGET:{}", app.execution_target_label().to_uppercase()),
+                ));
+            }
+            push_queue_buttons(app, &mut buttons);
+            push_schedule_buttons(app, &mut buttons);
+            buttons.push((FooterAction::Quit, "QUIT".to_string()));
+            buttons
+        }
+        AppState::Executing => {
+            let mut buttons = Vec::new();
+            push_queue_buttons(app, &mut buttons);
+            buttons.push((FooterAction::Quit, "QUIT".to_string()));
+            buttons
+        }
+        AppState::TemplatePicker => vec![
+            (FooterAction::ApplyTemplate, "USE".to_string()),
+            (FooterAction::CloseTemplates, "BACK".to_string()),
             (FooterAction::Quit, "QUIT".to_string()),
         ],
         AppState::History => vec![
@@ -560,12 +1481,66 @@ fn footer_buttons_for_state(app: &App) -> Vec<(FooterAction, String)> {
             (FooterAction::CloseHistory, "BACK".to_string()),
             (FooterAction::Quit, "QUIT".to_string()),
         ],
-        AppState::AwaitingConfirmation => vec![
-            (FooterAction::Execute, "EXECUTE".to_string()),
-            (FooterAction::BackToInput, "BACK".to_string()),
+        AppState::AwaitingConfirmation if !app.output_conflicts.is_empty() => vec![
+            (
+                FooterAction::ResolveConflict(ConflictPolicy::Overwrite),
+                "OVERWRITE".to_string(),
+            ),
+            (
+                FooterAction::ResolveConflict(ConflictPolicy::AutoRename),
+                "RENAME".to_string(),
+            ),
+            (
+                FooterAction::ResolveConflict(ConflictPolicy::Skip),
+                "SKIP".to_string(),
+            ),
+            (FooterAction::BackToInput, "BACK".to_string()),
+            (FooterAction::EditCommand, "EDIT CMD".to_string()),
+            (FooterAction::Quit, "QUIT".to_string()),
+        ],
+        AppState::AwaitingConfirmation if app.schedule_input.is_some() => vec![
+            (FooterAction::ConfirmRunLater, "SCHEDULE".to_string()),
+            (FooterAction::CancelRunLater, "CANCEL".to_string()),
+            (FooterAction::Quit, "QUIT".to_string()),
+        ],
+        AppState::AwaitingConfirmation if app.context_change.is_some() => vec![
+            (FooterAction::RefreshPreview, "REFRESH PREVIEW".to_string()),
+            (FooterAction::BackToInput, "BACK".to_string()),
+            (FooterAction::EditCommand, "EDIT CMD".to_string()),
+            (FooterAction::Quit, "QUIT".to_string()),
+        ],
+        AppState::AwaitingConfirmation if app.metered_hold => vec![
+            (FooterAction::Execute, "RUN ON METERED".to_string()),
+            (FooterAction::BackToInput, "BACK".to_string()),
+            (FooterAction::EditCommand, "EDIT CMD".to_string()),
+            (FooterAction::Quit, "QUIT".to_string()),
+        ],
+        AppState::AwaitingConfirmation if app.collision_hold.is_some() => vec![
+            (FooterAction::Execute, "RUN UNCHECKED".to_string()),
+            (FooterAction::BackToInput, "BACK".to_string()),
+            (FooterAction::EditCommand, "EDIT CMD".to_string()),
+            (FooterAction::Quit, "QUIT".to_string()),
+        ],
+        AppState::AwaitingConfirmation if !app.files_in_use.is_empty() => vec![
+            (FooterAction::Execute, "RUN ANYWAY".to_string()),
+            (FooterAction::BackToInput, "BACK".to_string()),
+            (FooterAction::EditCommand, "EDIT CMD".to_string()),
+            (FooterAction::Quit, "QUIT".to_string()),
+        ],
+        AppState::AwaitingConfirmation if app.delete_hold => vec![
+            (FooterAction::BackToInput, "BACK".to_string()),
+            (FooterAction::ConfirmDelete, "CONFIRM DELETE".to_string()),
+            (FooterAction::EditCommand, "EDIT CMD".to_string()),
+            (FooterAction::Quit, "QUIT".to_string()),
+        ],
+        AppState::AwaitingConfirmation => vec![
+            (FooterAction::Execute, "EXECUTE".to_string()),
+            (FooterAction::RunLater, "RUN LATER".to_string()),
+            (FooterAction::BackToInput, "BACK".to_string()),
             (FooterAction::EditCommand, "EDIT CMD".to_string()),
             (FooterAction::EditInput, "EDIT INPUT".to_string()),
             (FooterAction::Regenerate, "REGEN".to_string()),
+            command_pin_button(app),
             (FooterAction::Quit, "QUIT".to_string()),
         ],
         AppState::EditingCommand => vec![
@@ -573,16 +1548,71 @@ fn footer_buttons_for_state(app: &App) -> Vec<(FooterAction, String)> {
             (FooterAction::CancelEditCommand, "BACK".to_string()),
             (FooterAction::Quit, "QUIT".to_string()),
         ],
-        AppState::Finished(_) | AppState::Error(_) => vec![
-            (FooterAction::Retry, "RETRY".to_string()),
-            (FooterAction::ResetToInput, "BACK".to_string()),
+        AppState::Error(_) if app.override_input.is_some() => vec![
+            (FooterAction::ConfirmOverride, "OVERRIDE".to_string()),
+            (FooterAction::CancelOverride, "CANCEL".to_string()),
+        ],
+        AppState::Error(_) if app.safety_block.is_some() => {
+            let show_rule = app
+                .safety_block
+                .as_ref()
+                .is_some_and(|block| block.show_rule);
+            let mut buttons = vec![
+                (
+                    FooterAction::ToggleSafetyRule,
+                    if show_rule { "HIDE RULE" } else { "RULE" }.to_string(),
+                ),
+                (FooterAction::EditCommand, "EDIT CMD".to_string()),
+                (FooterAction::RegenerateWithinRule, "SAFE REGEN".to_string()),
+            ];
+            if app.can_override() {
+                buttons.push((FooterAction::OverrideSafety, "OVERRIDE".to_string()));
+            }
+            buttons.push((FooterAction::ResetToInput, "BACK".to_string()));
+            buttons.push((FooterAction::Quit, "QUIT".to_string()));
+            buttons
+        }
+        AppState::Finished(_) | AppState::Error(_) => {
+            let mut buttons = vec![
+                (FooterAction::Retry, "RETRY".to_string()),
+                (FooterAction::ResetToInput, "BACK".to_string()),
+            ];
+            if app.failure_install.is_some() && matches!(app.state, AppState::Error(_)) {
+                buttons.push((FooterAction::InstallMissing, "INSTALL".to_string()));
+            }
+            if app.generated_command.is_some() {
+                buttons.push(command_pin_button(app));
+            }
+            buttons.extend(app.result_actions());
+            push_queue_buttons(app, &mut buttons);
+            push_schedule_buttons(app, &mut buttons);
+            buttons.push((FooterAction::Quit, "QUIT".to_string()));
+            buttons
+        }
+        AppState::ConfirmingBudget => vec![
+            (FooterAction::SendOverBudget, "SEND ANYWAY".to_string()),
+            (FooterAction::BackToInput, "BACK".to_string()),
             (FooterAction::Quit, "QUIT".to_string()),
         ],
         AppState::Clarifying => {
             let mut buttons = Vec::new();
             if let Some(payload) = &app.clarify {
+                let multiple = payload.allows_multiple();
                 for (i, opt) in payload.options.iter().enumerate() {
-                    buttons.push((FooterAction::ClarifySelect(i), opt.label.clone()));
+                    let label = if multiple && opt.combinable {
+                        let mark = if payload.selected[i] { "x" } else { " " };
+                        format!("[{}] {}", mark, opt.label)
+                    } else {
+                        opt.label.clone()
+                    };
+                    buttons.push((FooterAction::ClarifySelect(i), label));
+                }
+                let picked = payload.selected.iter().filter(|on| **on).count();
+                if picked > 0 {
+                    buttons.push((
+                        FooterAction::ClarifyRunSelected,
+                        format!("RUN {} SELECTED", picked),
+                    ));
                 }
             }
             buttons.push((FooterAction::BackToInput, "BACK".to_string()));
@@ -593,6 +1623,44 @@ fn footer_buttons_for_state(app: &App) -> Vec<(FooterAction, String)> {
     }
 }
 
+fn command_pin_button(app: &App) -> (FooterAction, String) {
+    let label = if app.current_command_is_pinned() {
+        "UNPIN"
+    } else {
+        "PIN"
+    };
+    (FooterAction::ToggleCommandPin, label.to_string())
+}
+
+fn push_queue_buttons(app: &App, buttons: &mut Vec<(FooterAction, String)>) {
+    let pending = app.jobs.pending_count();
+    if pending > 0 && app.state != AppState::Executing {
+        buttons.push((FooterAction::StartNextJob, "NEXT JOB".to_string()));
+    }
+    if pending > 0 && app.show_queue {
+        buttons.push((FooterAction::CancelQueued, "DROP QUEUED".to_string()));
+    }
+    if !app.jobs.jobs().is_empty() {
+        buttons.push((FooterAction::ToggleQueue, format!("QUEUE:{}", pending)));
+    }
+}
+
+fn push_schedule_buttons(app: &App, buttons: &mut Vec<(FooterAction, String)>) {
+    if app.scheduled_jobs.is_empty() {
+        return;
+    }
+    if app.show_schedule {
+        if app.scheduled_jobs.len() > 1 {
+            buttons.push((FooterAction::CycleScheduled, "SELECT".to_string()));
+        }
+        buttons.push((FooterAction::CancelScheduled, "UNSCHEDULE".to_string()));
+    }
+    buttons.push((
+        FooterAction::ToggleSchedule,
+        format!("SCHEDULED:{}", app.scheduled_jobs.len()),
+    ));
+}
+
 // --- HELPER RENDERERS ---
 
 fn render_multiline_prompt<'a>(
@@ -621,7 +1689,7 @@ fn render_multiline_prompt<'a>(
 
         let line_len = line.chars().count();
         if cursor_pending && remaining <= line_len {
-            let (before, current, after) = split_line_at_char(line, remaining);
+            let (before, current, after) = split_line_at_grapheme(line, remaining);
             let mut spans = vec![prefix];
 
             if !before.is_empty() {
@@ -629,16 +1697,15 @@ fn render_multiline_prompt<'a>(
             }
 
             match current {
-                Some(ch) => {
-                    let ch_str = ch.to_string();
+                Some(cluster) => {
                     if cursor_visible {
                         if let Some(cursor_style) = cursor_style {
-                            spans.push(Span::styled(ch_str, cursor_style));
+                            spans.push(Span::styled(cluster, cursor_style));
                         } else {
-                            spans.push(Span::styled(ch_str, text_style));
+                            spans.push(Span::styled(cluster, text_style));
                         }
                     } else {
-                        spans.push(Span::styled(ch_str, text_style));
+                        spans.push(Span::styled(cluster, text_style));
                     }
                 }
                 None => {
@@ -665,14 +1732,86 @@ fn render_multiline_prompt<'a>(
     out
 }
 
+/// Like `render_multiline_prompt`, but styles each char by its shell token
+/// class so flags, quoted values, paths and operators are easy to scan.
+fn render_command_prompt<'a>(
+    text: &str,
+    first_prefix: Span<'a>,
+    continuation_prefix: Span<'a>,
+    theme: &Theme,
+    cursor_style: Option<Style>,
+    cursor_visible: bool,
+    cursor_pos: Option<usize>,
+) -> Vec<Line<'a>> {
+    let base = theme.proposal_cmd_style;
+    let kinds = classify_shell_chars(text);
+    let cursor_style = cursor_style.filter(|_| cursor_visible);
+    // The whole cluster under the cursor is highlighted.
+    let cursor_chars = cursor_pos.map(|pos| pos..next_grapheme(text, pos));
+    let mut out = Vec::new();
+    let mut offset = 0;
+
+    for (idx, line) in text.split('\n').enumerate() {
+        let prefix = if idx == 0 {
+            first_prefix.clone()
+        } else {
+            continuation_prefix.clone()
+        };
+        let mut spans = vec![prefix];
+        let mut run = String::new();
+        let mut run_style = base;
+        let line_len = line.chars().count();
+
+        for (col, ch) in line.chars().enumerate() {
+            let mut style = kinds
+                .get(offset + col)
+                .copied()
+                .unwrap_or(ShellToken::Plain)
+                .style(base, theme);
+            if cursor_chars
+                .as_ref()
+                .is_some_and(|chars| chars.contains(&(offset + col)))
+            {
+                if let Some(cursor_style) = cursor_style {
+                    style = cursor_style;
+                }
+            }
+            if style != run_style && !run.is_empty() {
+                spans.push(Span::styled(std::mem::take(&mut run), run_style));
+            }
+            run_style = style;
+            run.push(ch);
+        }
+        if !run.is_empty() {
+            spans.push(Span::styled(run, run_style));
+        }
+        if cursor_pos == Some(offset + line_len) {
+            if let Some(cursor_style) = cursor_style {
+                spans.push(Span::styled(" ", cursor_style));
+            }
+        }
+
+        out.push(Line::from(spans));
+        offset += line_len + 1;
+    }
+    out
+}
+
 fn output_title(app: &App) -> &'static str {
     if app.show_debug {
         return " DEBUG_SYSTEM_INTERNAL ";
     }
+    if app.show_queue {
+        return " JOB QUEUE ";
+    }
+    if app.show_schedule {
+        return " SCHEDULED JOBS ";
+    }
 
     match &app.state {
         AppState::Input => " SYSTEM STATUS & LOGS ",
         AppState::History => " HISTORY ",
+        AppState::TemplatePicker => " INTENT TEMPLATES ",
         AppState::Routing
         | AppState::Generating
         | AppState::Executing
@@ -681,21 +1820,36 @@ fn output_title(app: &App) -> &'static str {
         | AppState::PendingGeneration
         | AppState::PendingDryRun => " PROCESSING ",
         AppState::Clarifying => " CLARIFICATION ",
+        AppState::ConfirmingBudget => " TOKEN BUDGET ",
         AppState::AwaitingConfirmation => " PREVIEW / CONFIRMATION ",
         AppState::EditingCommand => " EDIT COMMAND ",
         AppState::Finished(_) => " EXECUTION RESULTS ",
+        AppState::Error(_) if app.safety_block.is_some() => " SAFETY BLOCK ",
         AppState::Error(_) => " SYSTEM FAILURE ",
     }
 }
 
+/// Views worth scrolling back to; overlays and pickers are left out.
+fn keeps_scrollback(app: &App) -> bool {
+    let picker = matches!(app.state, AppState::History | AppState::TemplatePicker);
+    !(app.show_debug || app.show_queue || app.show_schedule || picker)
+}
+
 fn build_output_lines<'a>(app: &'a App) -> Vec<Line<'a>> {
     if app.show_debug {
         return render_debug(app, &app.theme);
     }
+    if app.show_queue {
+        return render_queue_view(app, &app.theme);
+    }
+    if app.show_schedule {
+        return render_schedule_view(app, &app.theme);
+    }
 
     match &app.state {
         AppState::Input => render_input_view(app, &app.theme),
         AppState::History => render_history_view(app, &app.theme),
+        AppState::TemplatePicker => render_template_picker_view(app, &app.theme),
         AppState::Routing
         | AppState::Generating
         | AppState::Executing
@@ -704,12 +1858,19 @@ fn build_output_lines<'a>(app: &'a App) -> Vec<Line<'a>> {
         | AppState::PendingGeneration
         | AppState::PendingDryRun => render_processing_view(app, &app.theme),
         AppState::Clarifying => render_clarify_view(app, &app.theme),
+        AppState::ConfirmingBudget => render_budget_view(app, &app.theme),
         AppState::AwaitingConfirmation => render_preview_view(app, &app.theme),
         AppState::EditingCommand => render_edit_command_view(app, &app.theme),
-        AppState::Finished(out) => {
-            render_finished_view(out, app.selected_plugin.as_deref(), &app.theme)
+        AppState::Finished(out) => render_finished_view(
+            out,
+            app.selected_plugin.as_deref(),
+            app.execution_report.as_ref(),
+            &app.theme,
+        ),
+        AppState::Error(e) if app.safety_block.is_some() => {
+            render_safety_block_view(app, e, &app.theme)
         }
-        AppState::Error(e) => render_error_view(e, &app.theme),
+        AppState::Error(e) => render_error_view(e, app.failure_hint, &app.theme),
     }
 }
 
@@ -722,7 +1883,20 @@ fn render_debug<'a>(app: &'a App, theme: &Theme) -> Vec<Line<'a>> {
         Line::from(vec![
             Span::styled(" CWD: ", theme.header_subtitle_style),
             Span::styled(
-                format!("{}", std::env::current_dir().unwrap_or_default().display()),
+                match app.active_remote() {
+                    Some(remote) => format!("{}:{}", remote.destination(), remote.workdir()),
+                    None => format!("{}", std::env::current_dir().unwrap_or_default().display()),
+                },
+                theme.header_title_style,
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled(" LOG: ", theme.header_subtitle_style),
+            Span::styled(
+                match (telemetry::log_path(), telemetry::verbosity()) {
+                    (Some(path), Some(level)) => format!("{} [{}]", path, level),
+                    _ => "(file logging unavailable)".to_string(),
+                },
                 theme.header_title_style,
             ),
         ]),
@@ -742,6 +1916,125 @@ fn render_debug<'a>(app: &'a App, theme: &Theme) -> Vec<Line<'a>> {
             theme.error_style,
         )));
     }
+
+    lines.push(Line::from(""));
+    lines.extend(render_route_explanation(app, theme));
+    lines.push(Line::from(""));
+    lines.extend(render_prompt_overrides(app, theme));
+    lines
+}
+
+/// The `prompt_append` and `prompt_override` entries the executor prompt
+/// is built with: the routed plugin's, or every plugin's before routing.
+fn render_prompt_overrides<'a>(app: &'a App, theme: &Theme) -> Vec<Line<'a>> {
+    let mut lines = vec![Line::from(Span::styled(
+        " PROMPT OVERRIDES:",
+        theme.header_subtitle_style,
+    ))];
+    let plugins = app.config.plugins.iter().filter(|(name, settings)| {
+        app.selected_plugin
+            .as_deref()
+            .is_none_or(|selected| selected == name.as_str())
+            && (settings.prompt_override.is_some() || settings.prompt_fragments().next().is_some())
+    });
+    for (name, settings) in plugins {
+        if let Some(template) = &settings.prompt_override {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {}: ", name), theme.header_subtitle_style),
+                Span::styled("OWN PROMPT REPLACED", theme.warning_style),
+            ]));
+            for line in template.lines() {
+                lines.push(Line::from(Span::styled(
+                    format!("    | {}", line),
+                    theme.header_title_style,
+                )));
+            }
+        }
+        for fragment in settings.prompt_fragments() {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {} + ", name), theme.header_subtitle_style),
+                Span::styled(fragment, theme.header_title_style),
+            ]));
+        }
+    }
+    if lines.len() == 1 {
+        lines.push(Line::from(Span::styled(
+            "  (None in config.toml)",
+            theme.header_subtitle_style,
+        )));
+    }
+    lines
+}
+
+fn render_route_explanation<'a>(app: &'a App, theme: &Theme) -> Vec<Line<'a>> {
+    let mut lines = vec![Line::from(Span::styled(
+        " ROUTING:",
+        theme.header_subtitle_style,
+    ))];
+    let Some(explanation) = &app.route_explanation else {
+        lines.push(Line::from(Span::styled(
+            "  (No routing decision yet)",
+            theme.header_subtitle_style,
+        )));
+        return lines;
+    };
+
+    lines.push(Line::from(vec![
+        Span::styled("  SOURCE: ", theme.header_subtitle_style),
+        Span::styled(
+            if explanation.rule_based {
+                "RULE PRECHECK"
+            } else if explanation.cached {
+                "CACHED ROUTE"
+            } else {
+                "ROUTER MODEL"
+            },
+            theme.header_title_style,
+        ),
+    ]));
+    lines.push(Line::from(vec![
+        Span::styled("  DECISION: ", theme.header_subtitle_style),
+        Span::styled(explanation.decision.as_str(), theme.header_title_style),
+    ]));
+    if !explanation.reasoning.is_empty() {
+        lines.push(Line::from(vec![
+            Span::styled("  REASONING: ", theme.header_subtitle_style),
+            Span::styled(explanation.reasoning.as_str(), theme.header_title_style),
+        ]));
+    }
+
+    if explanation.scores.is_empty() {
+        if !explanation.rule_based {
+            lines.push(Line::from(Span::styled(
+                "  (Model returned no per-plugin scores)",
+                theme.header_subtitle_style,
+            )));
+        }
+        return lines;
+    }
+
+    const BAR_WIDTH: usize = 10;
+    let chosen = explanation.chosen_plugin.as_deref();
+    for score in &explanation.scores {
+        let filled = (score.score * BAR_WIDTH as f32).round() as usize;
+        let bar = format!(
+            "{}{}",
+            "#".repeat(filled.min(BAR_WIDTH)),
+            ".".repeat(BAR_WIDTH - filled.min(BAR_WIDTH))
+        );
+        let style = if chosen == Some(score.plugin.as_str()) {
+            theme.header_title_style
+        } else {
+            theme.header_subtitle_style
+        };
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("  {:<10} [{}] {:.2} ", score.plugin, bar, score.score),
+                style,
+            ),
+            Span::styled(score.reason.as_str(), theme.header_subtitle_style),
+        ]));
+    }
     lines
 }
 
@@ -762,12 +2055,25 @@ fn render_input_view<'a>(app: &'a App, theme: &Theme) -> Vec<Line<'a>> {
         )),
         Line::from(""),
     ]);
+    if !app.session.is_empty() {
+        text.push(Line::from(Span::styled(
+            format!(
+                " SESSION: follow-ups can refer to the last {} run(s). Ctrl+N forgets them.",
+                app.session.len()
+            ),
+            theme.header_subtitle_style,
+        )));
+        text.push(Line::from(""));
+    }
 
     if let Some(ctx) = &app.current_context {
         text.push(Line::from(vec![
             Span::styled(" CWD_CONTEXT: ", theme.header_subtitle_style),
             Span::styled(
-                format!("{}", std::env::current_dir().unwrap_or_default().display()),
+                match app.active_remote() {
+                    Some(remote) => format!("{}:{}", remote.destination(), remote.workdir()),
+                    None => format!("{}", std::env::current_dir().unwrap_or_default().display()),
+                },
                 theme.header_subtitle_style,
             ),
         ]));
@@ -800,30 +2106,42 @@ fn render_input_view<'a>(app: &'a App, theme: &Theme) -> Vec<Line<'a>> {
         text.push(Line::from(""));
     }
 
-    if !app.logs.is_empty() {
+    if app.visible_logs().next().is_some() {
         text.push(Line::from(Span::styled(
-            "--- SYSTEM LOGS ---",
+            match app.log_filter {
+                LogFilter::All => "--- SYSTEM LOGS ---",
+                LogFilter::ErrorsOnly => "--- SYSTEM LOGS (ERRORS ONLY) ---",
+            },
             theme.header_subtitle_style,
         )));
-        for log in app.logs.iter().rev().take(5) {
-            text.push(Line::from(Span::styled(
-                format!(":: {}", log),
-                theme.header_subtitle_style,
-            )));
+        for log in app.visible_logs().rev().take(5) {
+            text.push(log_line(log, theme));
         }
     }
     text
 }
 
+fn log_line(log: &LogEntry, theme: &Theme) -> Line<'static> {
+    let style = match log.severity {
+        Severity::Info => theme.header_subtitle_style,
+        Severity::Warning => theme.warning_style,
+        Severity::Error => theme.error_style,
+    };
+    Line::from(Span::styled(format!(":: {}", log.line()), style))
+}
+
 fn render_history_view<'a>(app: &'a App, theme: &Theme) -> Vec<Line<'a>> {
     let mut lines = vec![
         Line::from(""),
         Line::from(Span::styled(
-            "Command history sorted by pin and execution time.",
+            format!(
+                "Command history sorted by pin and execution time; showing {}.",
+                app.history_filter.label()
+            ),
             theme.header_subtitle_style,
         )),
         Line::from(Span::styled(
-            "Up/Down/PageUp/PageDown/Home/End: Move  X/Run: Execute  P: Pin/Unpin  Esc: Back",
+            "Up/Down/PageUp/PageDown/Home/End: Move  X/Run: Execute  P: Pin/Unpin  F: Failures  Esc: Back",
             theme.header_subtitle_style,
         )),
         Line::from(""),
@@ -838,16 +2156,36 @@ fn render_history_view<'a>(app: &'a App, theme: &Theme) -> Vec<Line<'a>> {
     }
 
     let text_width = app.output_text_width.max(24) as usize;
+    // Rows off screen stay blank: only the viewport's are formatted. The
+    // scroll is clamped after this frame is built, and the selected row's
+    // details add up to three lines, hence the margin.
+    let height = app.output_viewport_height as usize;
+    let total = lines.len() + app.history_items.len() + 3;
+    let top = (app.output_scroll as usize)
+        .min(total.saturating_sub(height))
+        .saturating_sub(3);
+    let shown = top..top + height + 3;
     for (idx, item) in app.history_items.iter().enumerate() {
+        if idx != app.history_selected && !shown.contains(&lines.len()) {
+            lines.push(Line::default());
+            continue;
+        }
         let pin_label = if item.pinned_at.is_some() {
             "[PIN]"
         } else {
             "[   ]"
         };
-        let row = format!(
-            "{} {} [{}] {}",
-            pin_label, item.entry.timestamp, item.entry.plugin, item.entry.command
+        let mut row = format!(
+            "{} {} [{}] {} {}",
+            pin_label,
+            item.entry.timestamp,
+            item.entry.plugin,
+            format_run_outcome(item.entry.outcome.as_ref()),
+            item.entry.command
         );
+        if let Some(intent) = &item.intent {
+            row.push_str(&format!("  <- {}", intent));
+        }
         let clipped = truncate_with_ellipsis(&row, text_width);
         let style = if idx == app.history_selected {
             theme.history_selected_style
@@ -855,8 +2193,227 @@ fn render_history_view<'a>(app: &'a App, theme: &Theme) -> Vec<Line<'a>> {
             theme.header_subtitle_style
         };
         lines.push(Line::from(Span::styled(clipped, style)));
+        if idx == app.history_selected {
+            lines.extend(history_details(&item.entry, text_width, theme));
+        }
+    }
+
+    lines
+}
+
+/// The selected run's environment, under its row: a command that worked
+/// last month may only work with the tool version it ran with then.
+fn history_details<'a>(entry: &HistoryEntry, width: usize, theme: &Theme) -> Vec<Line<'a>> {
+    let detail = |label: &str, value: &str| {
+        Line::from(vec![
+            Span::styled(format!("      {:<8}", label), theme.header_subtitle_style),
+            Span::styled(
+                truncate_with_ellipsis(value, width.saturating_sub(14)),
+                theme.header_title_style,
+            ),
+        ])
+    };
+    let Some(environment) = &entry.environment else {
+        return vec![detail("ran on", "not recorded (remote or older run)")];
+    };
+    vec![
+        detail(
+            "tool",
+            environment
+                .tool_version
+                .as_deref()
+                .unwrap_or("version unknown"),
+        ),
+        detail(
+            "ran on",
+            &format!("{}, dexter {}", environment.os, environment.dexter_version),
+        ),
+    ]
+}
+
+/// Fixed-width `STATUS  TIME  SIZE` columns for a history row.
+fn format_run_outcome(outcome: Option<&RunOutcome>) -> String {
+    let Some(outcome) = outcome else {
+        return format!("{:<7} {:>6} {:>6}", "-", "-", "-");
+    };
+    let status = match (outcome.success, outcome.exit_code) {
+        (true, _) => "ok".to_string(),
+        (false, Some(code)) => format!("exit {}", code),
+        (false, None) => "failed".to_string(),
+    };
+    format!(
+        "{:<7} {:>6} {:>6}",
+        status,
+        format_duration_ms(outcome.duration_ms),
+        format_byte_size(outcome.output_bytes)
+    )
+}
+
+fn format_duration_ms(ms: u64) -> String {
+    match ms {
+        0..=999 => format!("{}ms", ms),
+        1000..=59_999 => format!("{:.1}s", ms as f64 / 1000.0),
+        _ => format!("{}m{:02}s", ms / 60_000, (ms / 1000) % 60),
+    }
+}
+
+fn format_byte_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["K", "M", "G", "T"];
+    if bytes < 1024 {
+        return format!("{}B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1}{}", size, UNITS[unit])
+}
+
+/// `3 files affected, 1.2G in -> ~1.2G out`; the output half only when it
+/// can be estimated.
+fn format_scope(scope: &ScopeSummary) -> String {
+    let mut label = format!(
+        "{} file{} affected, {} in",
+        scope.files,
+        if scope.files == 1 { "" } else { "s" },
+        format_byte_size(scope.input_bytes)
+    );
+    if let Some(output) = scope.output_bytes {
+        label.push_str(&format!(" -> ~{} out", format_byte_size(output)));
+    }
+    label
+}
+
+fn render_queue_view<'a>(app: &'a App, theme: &Theme) -> Vec<Line<'a>> {
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            "Jobs run one at a time; the next pending job starts when the current one finishes.",
+            theme.header_subtitle_style,
+        )),
+        Line::from(Span::styled(
+            "[NVENC] and other encoder jobs never share their encoder; `dexter batch` runs [CPU] jobs in parallel.",
+            theme.header_subtitle_style,
+        )),
+        Line::from(Span::styled(
+            "Enter (while running): Queue intent  Ctrl+Q: Close  Next Job: Start after a failure",
+            theme.header_subtitle_style,
+        )),
+        Line::from(""),
+    ];
+
+    if app.jobs.jobs().is_empty() {
+        lines.push(Line::from(Span::styled(
+            "(No jobs yet)",
+            theme.header_subtitle_style,
+        )));
+        return lines;
+    }
+
+    let text_width = app.output_text_width.max(24) as usize;
+    for job in app.jobs.jobs() {
+        let mut row = format!("#{:<3} [{}] {}", job.id, job.status.label(), job.intent);
+        if let Some(cmd) = &job.command {
+            row.push_str(&format!("  [{}] -> {}", JobLane::of(cmd).name(), cmd));
+        }
+        let style = match &job.status {
+            JobStatus::Running(_) => theme.history_selected_style,
+            JobStatus::Failed(_) => theme.error_style,
+            _ => theme.header_subtitle_style,
+        };
+        lines.push(Line::from(Span::styled(
+            truncate_with_ellipsis(&row, text_width),
+            style,
+        )));
+        if let JobStatus::Failed(err) = &job.status {
+            let first = err.lines().next().unwrap_or_default();
+            lines.push(Line::from(Span::styled(
+                truncate_with_ellipsis(&format!("      {}", first), text_width),
+                theme.error_style,
+            )));
+        }
+    }
+    lines
+}
+
+fn render_schedule_view<'a>(app: &'a App, theme: &Theme) -> Vec<Line<'a>> {
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            "Jobs start on time while `dexter daemon` runs; otherwise at the next launch.",
+            theme.header_subtitle_style,
+        )),
+        Line::from(Span::styled(
+            "Select: Pick a job  Unschedule: Cancel it",
+            theme.header_subtitle_style,
+        )),
+        Line::from(""),
+    ];
+
+    if app.scheduled_jobs.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "(Nothing scheduled)",
+            theme.header_subtitle_style,
+        )));
+        return lines;
+    }
+
+    let text_width = app.output_text_width.max(24) as usize;
+    for (idx, job) in app.scheduled_jobs.iter().enumerate() {
+        let selected = idx == app.schedule_selected;
+        let row = format!(
+            "{} {} [{}] {}",
+            if selected { ">" } else { " " },
+            job.run_at_label(),
+            job.plugin,
+            job.command
+        );
+        let style = if selected {
+            theme.history_selected_style
+        } else {
+            theme.header_subtitle_style
+        };
+        lines.push(Line::from(Span::styled(
+            truncate_with_ellipsis(&row, text_width),
+            style,
+        )));
+        lines.push(Line::from(Span::styled(
+            truncate_with_ellipsis(&format!("      in {}", job.cwd.display()), text_width),
+            theme.header_subtitle_style,
+        )));
     }
+    lines
+}
+
+fn render_template_picker_view<'a>(app: &'a App, theme: &Theme) -> Vec<Line<'a>> {
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            "Pick a template; {placeholders} become tab-stops in the input.",
+            theme.header_subtitle_style,
+        )),
+        Line::from(Span::styled(
+            "Up/Down: Move  Enter/Use: Insert  Esc: Back",
+            theme.header_subtitle_style,
+        )),
+        Line::from(""),
+    ];
 
+    let text_width = app.output_text_width.max(24) as usize;
+    for (idx, template) in app.intent_templates().iter().enumerate() {
+        let row = format!("{:<18} {}", template.name, template.text);
+        let style = if idx == app.template_selected {
+            theme.history_selected_style
+        } else {
+            theme.header_subtitle_style
+        };
+        lines.push(Line::from(Span::styled(
+            truncate_with_ellipsis(&row, text_width),
+            style,
+        )));
+    }
     lines
 }
 
@@ -871,15 +2428,33 @@ fn render_clarify_view<'a>(app: &'a App, theme: &Theme) -> Vec<Line<'a>> {
             &payload.question,
             theme.header_subtitle_style,
         )));
-        lines.push(Line::from(Span::styled(
-            "Choose one option below to continue.",
-            theme.header_subtitle_style,
-        )));
+        let hint = if payload.allows_multiple() {
+            // The digit keys reach the options shown, the first nine.
+            let keys = match payload.options.len().min(9) {
+                1 => "1".to_string(),
+                last => format!("1-{}", last),
+            };
+            format!(
+                "Press {} to choose an option or toggle a [ ] one, then Tab to RUN SELECTED and press Enter to run them in order.",
+                keys
+            )
+        } else {
+            "Choose one option below to continue.".to_string()
+        };
+        lines.push(Line::from(Span::styled(hint, theme.header_subtitle_style)));
         lines.push(Line::from(""));
 
-        for opt in payload.options.iter() {
+        for (i, opt) in payload.options.iter().enumerate() {
+            let marker = match (
+                payload.allows_multiple() && opt.combinable,
+                payload.selected[i],
+            ) {
+                (true, true) => format!("OPTION {} [x]: ", i + 1),
+                (true, false) => format!("OPTION {} [ ]: ", i + 1),
+                (false, _) => format!("OPTION {}: ", i + 1),
+            };
             lines.push(Line::from(vec![
-                Span::styled("OPTION: ", theme.header_subtitle_style),
+                Span::styled(marker, theme.header_subtitle_style),
                 Span::styled(&opt.label, theme.header_title_style),
             ]));
             lines.push(Line::from(Span::styled(
@@ -913,10 +2488,17 @@ fn truncate_with_ellipsis(input: &str, max_chars: usize) -> String {
     format!("{}...", prefix)
 }
 
+/// The cursor blinks while the proposal pane has focus, unless `plain_ui`
+/// holds it steady.
+fn cursor_blink_on(app: &App) -> bool {
+    app.focus == FocusArea::Proposal
+        && (!app.theme.animated() || (app.tick_count / 8).is_multiple_of(2))
+}
+
 fn render_processing_view<'a>(app: &'a App, theme: &Theme) -> Vec<Line<'a>> {
     let spinner = ["|", "/", "-", "\\"];
     let idx = (app.tick_count as usize / 2) % spinner.len();
-    let char = spinner[idx];
+    let char = if theme.animated() { spinner[idx] } else { "*" };
 
     let action = match app.state {
         AppState::Routing | AppState::PendingRouting => "CALCULATING ROUTE",
@@ -926,16 +2508,35 @@ fn render_processing_view<'a>(app: &'a App, theme: &Theme) -> Vec<Line<'a>> {
         _ => "PROCESSING",
     };
 
+    let progress_message = app
+        .progress
+        .as_ref()
+        .map(|prog| prog.message.trim())
+        .filter(|msg| !msg.is_empty());
+    if !theme.animated() {
+        let mut lines = vec![
+            Line::from(""),
+            Line::from(vec![
+                Span::styled(format!(" {} ", char), theme.processing_spinner_style),
+                Span::styled(format!("{}...", action), theme.processing_text_style),
+            ]),
+            Line::from(Span::styled(
+                progress_message.unwrap_or("Working.").to_string(),
+                theme.header_subtitle_style,
+            )),
+            Line::from(""),
+        ];
+        lines.extend(prompt_estimate_line(app, theme));
+        return lines;
+    }
+
     let belt_w = app.output_text_width.max(10) as usize;
     let belt = token_conveyor_belt_line(app.tick_count, belt_w);
 
-    let quip = if let Some(prog) = &app.progress {
-        let msg = prog.message.trim();
-        if !msg.is_empty() {
-            msg.to_string()
-        } else {
-            processing_quip(app.tick_count, belt.bounce_count)
-        }
+    let quip = if let Some(msg) = progress_message {
+        msg.to_string()
+    } else if app.progress.is_some() {
+        processing_quip(app.tick_count, belt.bounce_count)
     } else if belt.dropped {
         // Guaranteed to line up: we compute drop + message in the same frame.
         match (app.tick_count / 10) % 3 {
@@ -956,7 +2557,7 @@ fn render_processing_view<'a>(app: &'a App, theme: &Theme) -> Vec<Line<'a>> {
         String::new()
     };
 
-    vec![
+    let mut lines = vec![
         Line::from(""),
         Line::from(vec![
             Span::styled(format!(" {} ", char), theme.processing_spinner_style),
@@ -966,7 +2567,65 @@ fn render_processing_view<'a>(app: &'a App, theme: &Theme) -> Vec<Line<'a>> {
         Line::from(Span::styled(drop_line, theme.header_subtitle_style)),
         Line::from(Span::styled(quip, theme.header_subtitle_style)),
         Line::from(""),
-    ]
+    ];
+    lines.extend(prompt_estimate_line(app, theme));
+    lines
+}
+
+/// The size and cost estimate of the prompt being sent, while one is.
+fn prompt_estimate_line<'a>(app: &App, theme: &Theme) -> Option<Line<'a>> {
+    let (stage, estimate) = app.prompt_estimate.as_ref()?;
+    matches!(app.state, AppState::Routing | AppState::Generating).then(|| {
+        Line::from(Span::styled(
+            format!(" PROMPT: {} {}", stage.label(), estimate.label()),
+            theme.header_subtitle_style,
+        ))
+    })
+}
+
+fn render_budget_view<'a>(app: &'a App, theme: &Theme) -> Vec<Line<'a>> {
+    let mut lines = vec![Line::from("")];
+    let Some((stage, estimate)) = &app.prompt_estimate else {
+        return lines;
+    };
+    lines.push(Line::from(Span::styled(
+        format!(
+            " The {} prompt is larger than your token budget.",
+            stage.label()
+        ),
+        theme.error_style.add_modifier(Modifier::BOLD),
+    )));
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled(" ESTIMATE: ", theme.header_subtitle_style),
+        Span::styled(estimate.label(), theme.header_title_style),
+    ]));
+    lines.push(Line::from(vec![
+        Span::styled(" BUDGET:   ", theme.header_subtitle_style),
+        Span::styled(
+            format!(
+                "{} tokens (token_budget.warn_tokens)",
+                format_tokens(app.config.token_budget.warn_tokens)
+            ),
+            theme.header_title_style,
+        ),
+    ]));
+    lines.push(Line::from(""));
+    if let Some(context) = &app.current_context {
+        lines.push(Line::from(Span::styled(
+            format!(
+                " The directory context lists {} entries{}. Running Dexter from a narrower directory shrinks it.",
+                context.files.len(),
+                if context.truncated { " (scan truncated)" } else { "" }
+            ),
+            theme.header_subtitle_style,
+        )));
+    }
+    lines.push(Line::from(Span::styled(
+        " [y] SEND ANYWAY   [Esc] BACK",
+        theme.header_subtitle_style,
+    )));
+    lines
 }
 
 struct BeltRender {
@@ -1093,11 +2752,155 @@ fn processing_quip(tick: u64, bounce_count: u64) -> String {
 
 fn render_preview_view<'a>(app: &'a App, theme: &Theme) -> Vec<Line<'a>> {
     let mut lines = vec![Line::from("")];
+    if let Some(note) = &app.repair_note {
+        lines.push(Line::from(Span::styled(
+            note.as_str(),
+            theme.error_style.add_modifier(Modifier::BOLD),
+        )));
+        lines.push(Line::from(""));
+    }
+    let overridden = app
+        .generated_command
+        .as_deref()
+        .is_some_and(|cmd| app.overrides(cmd));
+    if let Some(safety_override) = app.safety_override.as_ref().filter(|_| overridden) {
+        lines.push(Line::from(Span::styled(
+            format!("SAFETY OVERRIDE: {}", safety_override.reason),
+            theme.error_style.add_modifier(Modifier::BOLD),
+        )));
+        lines.push(Line::from(Span::styled(
+            format!("Justification: {}", safety_override.justification),
+            theme.header_subtitle_style,
+        )));
+        lines.push(Line::from(""));
+    }
+    if app.critic_rx.is_some() {
+        lines.push(Line::from(Span::styled(
+            "CRITIC: reviewing the command...",
+            theme.header_subtitle_style,
+        )));
+        lines.push(Line::from(""));
+    } else if !app.critic_issues.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "CRITIC WARNINGS:",
+            theme.error_style.add_modifier(Modifier::BOLD),
+        )));
+        for issue in &app.critic_issues {
+            lines.push(Line::from(Span::styled(
+                format!("  ! {}", issue),
+                theme.error_style,
+            )));
+        }
+        lines.push(Line::from(""));
+    }
+    if let Some(diff) = &app.command_diff {
+        lines.extend(render_command_diff(diff, theme));
+        lines.push(Line::from(""));
+    }
+    if let Some(scope) = &app.preview_scope {
+        lines.push(Line::from(vec![
+            Span::styled("SCOPE: ", theme.header_subtitle_style),
+            Span::styled(format_scope(scope), theme.header_title_style),
+        ]));
+        lines.push(Line::from(""));
+    }
+    match app.invocation() {
+        Some(Ok(invocation)) => {
+            lines.extend(render_invocation(&invocation, app.show_invocation, theme));
+            lines.push(Line::from(""));
+        }
+        Some(Err(e)) => {
+            lines.push(Line::from(Span::styled(
+                format!("RUNS AS: unknown ({})", e),
+                theme.error_style,
+            )));
+            lines.push(Line::from(""));
+        }
+        None => {}
+    }
     if let Some(preview) = &app.dry_run_output {
-        lines.extend(render_preview_content(preview, theme));
+        lines.extend(render_preview_content(preview, &app.stream_keep, theme));
     }
 
     lines.push(Line::from(""));
+    if !app.output_conflicts.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "OUTPUT FILES ALREADY EXIST:",
+            theme.error_style.add_modifier(Modifier::BOLD),
+        )));
+        for conflict in &app.output_conflicts {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {} ", conflict.path), theme.error_style),
+                Span::styled(
+                    format!("(rename would use {})", conflict.suggested),
+                    theme.header_subtitle_style,
+                ),
+            ]));
+        }
+        lines.push(Line::from(Span::styled(
+            "O=Overwrite  A=Auto-rename  S=Skip  N=Back",
+            theme.input_prompt_style,
+        )));
+        return lines;
+    }
+    if let Some(change) = &app.context_change {
+        lines.push(Line::from(Span::styled(
+            format!(
+                "CONTEXT CHANGED ({}): refresh the preview before running? [F]",
+                change.label()
+            ),
+            theme.error_style.add_modifier(Modifier::BOLD),
+        )));
+    }
+    if app.metered_hold {
+        lines.push(Line::from(Span::styled(
+            "METERED CONNECTION: this download is held by `metered.action = \"block\"`.",
+            theme.error_style.add_modifier(Modifier::BOLD),
+        )));
+    }
+    if let Some(error) = &app.collision_hold {
+        lines.push(Line::from(Span::styled(
+            format!(
+                "COLLISION CHECK FAILED ({}): the rename may clash. Run it unchecked?",
+                error
+            ),
+            theme.error_style.add_modifier(Modifier::BOLD),
+        )));
+    }
+    if app.delete_hold {
+        lines.push(Line::from(Span::styled(
+            "REMOVES FILES: everything listed below goes. Press D (CONFIRM DELETE) to run it.",
+            theme.error_style.add_modifier(Modifier::BOLD),
+        )));
+    }
+    if !app.files_in_use.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "FILES OPEN IN ANOTHER PROCESS: close them first, or run anyway.",
+            theme.error_style.add_modifier(Modifier::BOLD),
+        )));
+        for file in &app.files_in_use {
+            let holders = if file.processes.is_empty() {
+                "in use".to_string()
+            } else {
+                file.processes.join(", ")
+            };
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {} ", file.path.display()), theme.error_style),
+                Span::styled(format!("({})", holders), theme.header_subtitle_style),
+            ]));
+        }
+    }
+    if let Some(deadline) = app.confirm_deadline() {
+        let left = deadline.saturating_duration_since(Instant::now()).as_secs();
+        let left = format!("{}:{:02}", left / 60, left % 60);
+        lines.push(Line::from(Span::styled(
+            match app.auto_run_recipe() {
+                Some(recipe) => format!("RUNS IN {} (auto-run recipe \"{}\")", left, recipe),
+                None => format!("CANCELS IN {} without confirmation", left),
+            },
+            theme.header_subtitle_style,
+        )));
+    }
     lines.push(Line::from(vec![
         Span::styled("CONFIRM EXECUTION? [", theme.input_prompt_style),
         Span::styled("Y", theme.input_prompt_style),
@@ -1106,14 +2909,73 @@ fn render_preview_view<'a>(app: &'a App, theme: &Theme) -> Vec<Line<'a>> {
         Span::styled("]", theme.input_prompt_style),
     ]));
 
+    let keys = if matches!(app.dry_run_output, Some(PreviewContent::StreamSelection(_))) {
+        "M=Edit Cmd  E=Edit Input  R=Regenerate  L=Run Later  I=Invocation  1-9=Toggle Stream  Up/Down=Scroll  Ctrl+L=Layout"
+    } else {
+        "M=Edit Cmd  E=Edit Input  R=Regenerate  L=Run Later  I=Invocation  Up/Down=Scroll  Ctrl+L=Layout"
+    };
     lines.push(Line::from(vec![
         Span::styled("KEYS: ", theme.header_subtitle_style),
+        Span::styled(keys, theme.header_subtitle_style),
+    ]));
+
+    lines
+}
+
+/// The process the command starts. Collapsed it is one line, flagged when
+/// it differs from the command shown; expanded it lists every argument.
+fn render_invocation(invocation: &Invocation, expanded: bool, theme: &Theme) -> Vec<Line<'static>> {
+    let changed = if invocation.changes.is_empty() {
+        String::new()
+    } else {
+        format!(" ({} change(s))", invocation.changes.len())
+    };
+    let marker = if expanded { "[-]" } else { "[+]" };
+    let mut lines = vec![Line::from(vec![
         Span::styled(
-            "M=Edit Cmd  E=Edit Input  R=Regenerate  Up/Down=Scroll",
-            theme.header_subtitle_style,
+            format!("{} RUNS AS{}: ", marker, changed),
+            if changed.is_empty() {
+                theme.header_subtitle_style
+            } else {
+                theme.input_prompt_style
+            },
         ),
+        Span::styled(invocation.command_line(), theme.header_title_style),
+    ])];
+    if !expanded {
+        return lines;
+    }
+    for change in &invocation.changes {
+        lines.push(Line::from(Span::styled(
+            format!("    * {}", change),
+            theme.input_prompt_style,
+        )));
+    }
+    for (idx, arg) in invocation.argv.iter().enumerate() {
+        lines.push(Line::from(vec![
+            Span::styled(format!("    argv[{}] ", idx), theme.header_subtitle_style),
+            Span::styled(format!("{:?}", arg), theme.header_title_style),
+        ]));
+    }
+    if let Some(retry) = invocation.retry_line() {
+        lines.push(Line::from(vec![
+            Span::styled("    retry ", theme.header_subtitle_style),
+            Span::styled(retry, theme.header_title_style),
+        ]));
+    }
+    lines.push(Line::from(vec![
+        Span::styled("    cwd ", theme.header_subtitle_style),
+        Span::styled(invocation.cwd.clone(), theme.header_title_style),
+    ]));
+    let env = if invocation.env.is_empty() {
+        "inherited unchanged".to_string()
+    } else {
+        invocation.env.join(", ")
+    };
+    lines.push(Line::from(vec![
+        Span::styled("    env ", theme.header_subtitle_style),
+        Span::styled(env, theme.header_title_style),
     ]));
-
     lines
 }
 
@@ -1132,7 +2994,7 @@ fn render_edit_command_view<'a>(app: &'a App, theme: &Theme) -> Vec<Line<'a>> {
     ];
 
     if let Some(preview) = &app.dry_run_output {
-        lines.extend(render_preview_content(preview, theme));
+        lines.extend(render_preview_content(preview, &app.stream_keep, theme));
     } else {
         lines.push(Line::from(Span::styled(
             "(No preview available yet.)",
@@ -1143,7 +3005,11 @@ fn render_edit_command_view<'a>(app: &'a App, theme: &Theme) -> Vec<Line<'a>> {
     lines
 }
 
-fn render_preview_content<'a>(preview: &'a PreviewContent, theme: &Theme) -> Vec<Line<'a>> {
+fn render_preview_content<'a>(
+    preview: &'a PreviewContent,
+    stream_keep: &[bool],
+    theme: &Theme,
+) -> Vec<Line<'a>> {
     let mut lines = Vec::new();
     match preview {
         PreviewContent::Text(t) => {
@@ -1158,31 +3024,118 @@ fn render_preview_content<'a>(preview: &'a PreviewContent, theme: &Theme) -> Vec
                     theme.header_subtitle_style,
                 )));
             } else {
-                for (i, diff) in diffs.iter().enumerate() {
-                    let mut header_spans = vec![Span::styled(
-                        format!("FILE [{:02}]: ", i + 1),
-                        theme.diff_header_style,
-                    )];
-
-                    if let Some(status) = &diff.status {
-                        let status_style = theme.proposal_cmd_style;
-                        header_spans.push(Span::styled(
-                            format!(" [{}] ", status.to_uppercase()),
-                            status_style,
-                        ));
+                // The rows themselves are drawn as a table under this text.
+                let summary = DiffSummary::of(diffs);
+                let heading = if summary.removals == diffs.len() {
+                    "REMOVALS"
+                } else {
+                    "RENAMES"
+                };
+                lines.push(Line::from(Span::styled(
+                    format!("{}: {} file(s): {}", heading, diffs.len(), summary.label()),
+                    theme.diff_header_style,
+                )));
+                lines.push(Line::from(Span::styled(
+                    "T=Sort by status  [/]=Scroll names",
+                    theme.header_subtitle_style,
+                )));
+            }
+        }
+        PreviewContent::StreamSelection(selection) => {
+            for line in selection.summary.lines() {
+                lines.push(Line::from(Span::styled(line, theme.processing_text_style)));
+            }
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                format!(
+                    "STREAMS IN {} (press 1-9 to keep/drop; -map flags are rewritten):",
+                    selection.input
+                ),
+                theme.diff_header_style,
+            )));
+            for (i, stream) in selection.streams.iter().enumerate() {
+                let kept = stream_keep
+                    .get(i)
+                    .or(selection.selected.get(i))
+                    .copied()
+                    .unwrap_or(false);
+                let key = if i < 9 {
+                    format!("{}", i + 1)
+                } else {
+                    " ".to_string()
+                };
+                let (mark, style) = if kept {
+                    ("[x]", theme.diff_added_style)
+                } else {
+                    ("[ ]", theme.diff_removed_style)
+                };
+                lines.push(Line::from(vec![
+                    Span::styled(format!("  {} {} ", key, mark), style),
+                    Span::styled(stream.describe(), style),
+                ]));
+            }
+        }
+        PreviewContent::Table {
+            title,
+            headers,
+            rows,
+        } => {
+            lines.push(Line::from(Span::styled(
+                title.as_str(),
+                theme.diff_header_style,
+            )));
+            let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+            for row in rows {
+                for (i, cell) in row.iter().enumerate() {
+                    let len = cell.chars().count();
+                    match widths.get_mut(i) {
+                        Some(width) => *width = (*width).max(len),
+                        None => widths.push(len),
                     }
-
-                    lines.push(Line::from(header_spans));
-                    lines.push(Line::from(vec![
-                        Span::styled("  OLD: ", theme.diff_removed_style),
-                        Span::styled(&diff.original, theme.diff_removed_style),
-                    ]));
+                }
+            }
+            let align = |cells: &[String]| {
+                let padded: Vec<String> = cells
+                    .iter()
+                    .zip(&widths)
+                    .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                    .collect();
+                format!("  {}", padded.join("  ").trim_end())
+            };
+            lines.push(Line::from(Span::styled(
+                align(headers),
+                theme.header_subtitle_style,
+            )));
+            for row in rows {
+                lines.push(Line::from(Span::styled(
+                    align(row),
+                    theme.processing_text_style,
+                )));
+            }
+        }
+        PreviewContent::KeyValue(sections) => {
+            for (i, section) in sections.iter().enumerate() {
+                if i > 0 {
+                    lines.push(Line::from(""));
+                }
+                lines.push(Line::from(Span::styled(
+                    section.title.as_str(),
+                    theme.diff_header_style,
+                )));
+                for (key, value) in &section.entries {
                     lines.push(Line::from(vec![
-                        Span::styled("  NEW: ", theme.diff_added_style),
-                        Span::styled(&diff.new, theme.diff_added_style),
+                        Span::styled(format!("  {}: ", key), theme.header_subtitle_style),
+                        Span::styled(value.as_str(), theme.processing_text_style),
                     ]));
+                }
+            }
+        }
+        PreviewContent::Sections(parts) => {
+            for (i, part) in parts.iter().enumerate() {
+                if i > 0 {
                     lines.push(Line::from(""));
                 }
+                lines.extend(render_preview_content(part, stream_keep, theme));
             }
         }
     }
@@ -1193,6 +3146,7 @@ fn render_preview_content<'a>(preview: &'a PreviewContent, theme: &Theme) -> Vec
 fn render_finished_view<'a>(
     output: &'a str,
     plugin_name: Option<&'a str>,
+    report: Option<&'a ExecutionReport>,
     theme: &Theme,
 ) -> Vec<Line<'a>> {
     let mut lines = vec![
@@ -1230,6 +3184,10 @@ fn render_finished_view<'a>(
         }
     }
 
+    if let Some(report) = report {
+        lines.extend(render_output_checks(report, theme));
+    }
+
     lines.push(Line::from(""));
     lines.push(Line::from(Span::styled(
         "[PRESS ENTER TO RESET]",
@@ -1238,7 +3196,122 @@ fn render_finished_view<'a>(
     lines
 }
 
-fn render_error_view<'a>(err: &'a str, theme: &Theme) -> Vec<Line<'a>> {
+/// Where deleted files went, then the verified outputs: size and
+/// checksum, or what looks wrong.
+fn render_output_checks<'a>(report: &'a ExecutionReport, theme: &Theme) -> Vec<Line<'a>> {
+    let mut lines = Vec::new();
+    if let Some(trashed) = &report.trashed {
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            Span::styled("Moved to Trash: ", theme.header_subtitle_style),
+            Span::styled(
+                format!("{} file(s) in {}", trashed.files.len(), trashed.location),
+                theme.success_style,
+            ),
+        ]));
+    }
+    if report.outputs.is_empty() {
+        return lines;
+    }
+    let suspicious = report.suspicious().count();
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled("Output Checks: ", theme.header_subtitle_style),
+        if suspicious == 0 {
+            Span::styled("ALL OK", theme.success_style)
+        } else {
+            Span::styled(
+                format!("{} SUSPICIOUS", suspicious),
+                theme.error_style.add_modifier(Modifier::BOLD),
+            )
+        },
+    ]));
+    for check in &report.outputs {
+        let detail = match (&check.problem, &check.sha256) {
+            (Some(problem), _) => Span::styled(format!("  !! {}", problem), theme.error_style),
+            (None, Some(sha256)) => Span::styled(
+                format!("  {} bytes  sha256 {}", check.size, sha256),
+                theme.header_subtitle_style,
+            ),
+            (None, None) => Span::styled("  directory", theme.header_subtitle_style),
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!(" {}", check.path), theme.header_title_style),
+            detail,
+        ]));
+    }
+    lines
+}
+
+/// A command a safety rule refused, with the ways on from it.
+fn render_safety_block_view<'a>(app: &'a App, err: &'a str, theme: &Theme) -> Vec<Line<'a>> {
+    let Some(block) = &app.safety_block else {
+        return Vec::new();
+    };
+    let mut lines = vec![
+        Line::from(Span::styled(
+            "!!! COMMAND BLOCKED !!!",
+            theme.error_style.add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+    ];
+    for line in err.lines() {
+        lines.push(Line::from(Span::styled(line, theme.error_style)));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled("BLOCKED: ", theme.header_subtitle_style),
+        Span::styled(block.command.as_str(), theme.base_style),
+    ]));
+    if block.show_rule {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "VIOLATED RULE",
+            theme.input_prompt_style.add_modifier(Modifier::BOLD),
+        )));
+        for line in app.safety_rule_lines() {
+            lines.push(Line::from(Span::styled(line, theme.base_style)));
+        }
+    }
+    lines.push(Line::from(""));
+    if app.override_input.is_some() {
+        let destination = if app.config.audit.system_log {
+            "audit.log and the system log"
+        } else {
+            "audit.log"
+        };
+        lines.push(Line::from(Span::styled(
+            format!(
+                "The reason is written to {} with the command, which is then previewed as usual.",
+                destination
+            ),
+            theme.header_subtitle_style,
+        )));
+        return lines;
+    }
+    let mut options = "[V] RULE  [M] EDIT THE COMMAND  [S] REGENERATE WITHIN THE RULE".to_string();
+    if app.can_override() {
+        options.push_str("  [O] OVERRIDE");
+    }
+    options.push_str("  [ENTER] BACK");
+    lines.push(Line::from(Span::styled(
+        options,
+        theme.header_subtitle_style,
+    )));
+    if block.overridable && !app.config.safety.allow_override {
+        lines.push(Line::from(Span::styled(
+            "Set safety.allow_override = true to be able to run it anyway.",
+            theme.header_subtitle_style,
+        )));
+    }
+    lines
+}
+
+fn render_error_view<'a>(
+    err: &'a str,
+    hint: Option<&'static FailureHint>,
+    theme: &Theme,
+) -> Vec<Line<'a>> {
     let mut lines = vec![
         Line::from(Span::styled(
             "!!! SYSTEM FAILURE !!!",
@@ -1249,6 +3322,23 @@ fn render_error_view<'a>(err: &'a str, theme: &Theme) -> Vec<Line<'a>> {
     for line in err.lines() {
         lines.push(Line::from(Span::styled(line, theme.error_style)));
     }
+    if let Some(hint) = hint {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            format!("LIKELY CAUSE: {}", hint.problem),
+            theme.input_prompt_style.add_modifier(Modifier::BOLD),
+        )));
+        lines.push(Line::from(Span::styled(
+            format!("FIX: {}", hint.fix),
+            theme.base_style,
+        )));
+        if let Some(argv) = hint.install {
+            lines.push(Line::from(Span::styled(
+                format!("INSTALL: {}", argv.join(" ")),
+                theme.header_subtitle_style,
+            )));
+        }
+    }
     lines.push(Line::from(""));
     lines.push(Line::from(Span::styled(
         "[PRESS ENTER TO ACKNOWLEDGE]",
@@ -1 +1,7 @@
+pub mod command_diff;
+pub mod diff_table;
+pub mod highlight;
 pub mod main_view;
+#[cfg(test)]
+pub mod snapshot;
+pub mod thumbnail;
@@ -0,0 +1,219 @@
+//! Renders the TUI into an in-memory buffer and compares it with the text
+//! snapshots under `src/ui/snapshots/`, one per view and terminal size.
+//!
+//! A missing or changed snapshot fails the test and leaves a `.snap.new`
+//! next to where it belongs; rerun with `DEXTER_UPDATE_SNAPSHOTS=1` to
+//! accept the new rendering, and commit the `.snap` files.
+
+use ratatui::backend::TestBackend;
+use ratatui::{Frame, Terminal};
+use std::path::PathBuf;
+
+/// Narrow, standard and wide terminals, as (width, height).
+pub const SNAPSHOT_SIZES: [(u16, u16); 3] = [(60, 20), (80, 24), (120, 36)];
+
+/// What `draw` puts on a `width` x `height` terminal, one line per row with
+/// trailing blanks trimmed. Styles are ignored.
+pub fn render_to_string(width: u16, height: u16, draw: impl FnOnce(&mut Frame)) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("test terminal");
+    terminal.draw(draw).expect("draw into test buffer");
+    let buffer = terminal.backend().buffer();
+    buffer
+        .content
+        .chunks(width as usize)
+        .map(|row| {
+            let line: String = row.iter().map(|cell| cell.symbol()).collect();
+            format!("{}\n", line.trim_end())
+        })
+        .collect()
+}
+
+/// Compares `rendered` with the stored snapshot `name`.
+pub fn assert_snapshot(name: &str, rendered: &str) {
+    let path = snapshot_dir().join(format!("{}.snap", name));
+    let update = std::env::var_os("DEXTER_UPDATE_SNAPSHOTS").is_some();
+    let stored = std::fs::read_to_string(&path).ok();
+    if stored.as_deref() == Some(rendered) {
+        let _ = std::fs::remove_file(path.with_extension("snap.new"));
+        return;
+    }
+    std::fs::create_dir_all(snapshot_dir()).expect("create snapshot dir");
+    if update {
+        std::fs::write(&path, rendered).expect("write snapshot");
+        let _ = std::fs::remove_file(path.with_extension("snap.new"));
+        return;
+    }
+    let new_path = path.with_extension("snap.new");
+    std::fs::write(&new_path, rendered).expect("write new snapshot");
+    panic!(
+        "snapshot {} is {}; compare it with {} and rerun with \
+         DEXTER_UPDATE_SNAPSHOTS=1 to accept",
+        path.display(),
+        if stored.is_some() {
+            "out of date"
+        } else {
+            "missing"
+        },
+        new_path.display()
+    );
+}
+
+fn snapshot_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/ui/snapshots")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::palette::Palette;
+    use crate::app::state::{App, AppState, ClarifyPayload};
+    use crate::setup::state::{SetupApp, SetupState};
+    use crate::setup::view::setup_ui;
+    use crate::ui::main_view::ui;
+    use chrono::{Local, TimeZone};
+    use dexter_core::{ClarifyOption, Config};
+    use dexter_plugins::{DiffItem, PreviewContent};
+
+    fn snapshot_config() -> Config {
+        Config {
+            // `auto` asks the terminal for its background.
+            theme: "dark".to_string(),
+            ..Config::default()
+        }
+    }
+
+    /// An app on `state` with fixed contents and none of the machine's
+    /// history, schedules or interrupted runs.
+    fn app_in(state: AppState) -> App {
+        let mut app = App::new(snapshot_config());
+        app.logs.clear();
+        app.scheduled_jobs.clear();
+        app.interrupted_runs.clear();
+        app.budget_alert = None;
+        app.input = "rename the holiday photos by capture date".to_string();
+        app.selected_plugin = Some("f2".to_string());
+        let command = "f2 -f '^IMG_' -r '{x.cdt.YYYY}-{x.cdt.MM}-{x.cdt.DD}_'".to_string();
+        app.command_draft = command.clone();
+        app.generated_command = Some(command);
+        app.dry_run_output = Some(PreviewContent::DiffList(vec![
+            DiffItem {
+                original: "IMG_0001.jpg".to_string(),
+                new: "2024-07-01_0001.jpg".to_string(),
+                status: Some("ok".to_string()),
+            },
+            DiffItem {
+                original: "IMG_0002.jpg".to_string(),
+                new: "2024-07-02_0002.jpg".to_string(),
+                status: Some("ok".to_string()),
+            },
+        ]));
+        app.push_log("Routed to f2.");
+        // Log lines carry their time; pin it so the snapshots don't drift.
+        for entry in &mut app.logs {
+            entry.at = Local.with_ymd_and_hms(2024, 7, 1, 9, 30, 0).unwrap();
+        }
+        if state == AppState::Clarifying {
+            app.clarify = Some(ClarifyPayload::new(
+                "Rename or convert the photos?".to_string(),
+                vec![
+                    ClarifyOption {
+                        id: "1".to_string(),
+                        label: "Rename".to_string(),
+                        detail: "Prefix each name with its capture date".to_string(),
+                        resolved_intent: "rename the photos by capture date".to_string(),
+                        combinable: false,
+                    },
+                    ClarifyOption {
+                        id: "2".to_string(),
+                        label: "Convert".to_string(),
+                        detail: "Convert them to WebP".to_string(),
+                        resolved_intent: "convert the photos to webp".to_string(),
+                        combinable: false,
+                    },
+                ],
+            ));
+        }
+        app.state = state;
+        app
+    }
+
+    fn all_states() -> Vec<(&'static str, AppState)> {
+        vec![
+            ("input", AppState::Input),
+            ("history", AppState::History),
+            ("routing", AppState::Routing),
+            ("generating", AppState::Generating),
+            ("awaiting_confirmation", AppState::AwaitingConfirmation),
+            ("editing_command", AppState::EditingCommand),
+            ("executing", AppState::Executing),
+            (
+                "finished",
+                AppState::Finished("Renamed 2 files.".to_string()),
+            ),
+            (
+                "error",
+                AppState::Error("f2 error: exit status 1".to_string()),
+            ),
+            ("clarifying", AppState::Clarifying),
+            ("pending_routing", AppState::PendingRouting),
+            ("pending_generation", AppState::PendingGeneration),
+            ("pending_dry_run", AppState::PendingDryRun),
+            ("dry_running", AppState::DryRunning),
+            ("template_picker", AppState::TemplatePicker),
+            ("confirming_budget", AppState::ConfirmingBudget),
+        ]
+    }
+
+    #[test]
+    fn main_view_snapshots() {
+        for (name, state) in all_states() {
+            for (width, height) in SNAPSHOT_SIZES {
+                let mut app = app_in(state.clone());
+                let rendered = render_to_string(width, height, |f| ui(f, &mut app));
+                assert_snapshot(&format!("main_{}_{}x{}", name, width, height), &rendered);
+            }
+        }
+        for (width, height) in SNAPSHOT_SIZES {
+            let mut app = app_in(AppState::AwaitingConfirmation);
+            app.palette = Some(Palette {
+                query: "ed".to_string(),
+                selected: 1,
+            });
+            let rendered = render_to_string(width, height, |f| ui(f, &mut app));
+            assert_snapshot(&format!("main_palette_{}x{}", width, height), &rendered);
+        }
+    }
+
+    #[test]
+    fn setup_view_snapshots() {
+        let states = [
+            ("welcome", SetupState::Welcome),
+            ("provider_selection", SetupState::ProviderSelection),
+            ("provider_config", SetupState::ProviderConfig),
+            ("model_order", SetupState::ModelOrderSelection),
+            ("router_order", SetupState::RouterOrderSelection),
+            ("theme", SetupState::ThemeSelection),
+            ("confirm", SetupState::Confirm),
+            (
+                "error",
+                SetupState::Error("No active models in routing order.".to_string()),
+            ),
+        ];
+        for (name, state) in states {
+            for (width, height) in SNAPSHOT_SIZES {
+                let mut app = SetupApp::new(snapshot_config(), true);
+                app.state = state.clone();
+                let rendered = render_to_string(width, height, |f| setup_ui(f, &app));
+                assert_snapshot(&format!("setup_{}_{}x{}", name, width, height), &rendered);
+            }
+        }
+    }
+
+    #[test]
+    fn render_to_string_trims_each_row() {
+        let rendered = render_to_string(6, 2, |f| {
+            f.render_widget(ratatui::widgets::Paragraph::new("hi"), f.area())
+        });
+        assert_eq!(rendered, "hi\n\n");
+    }
+}
@@ -0,0 +1,396 @@
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use dexter_core::ThumbnailMode;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command;
+
+/// Size of the thumbnail box inside the confirmation view, in cells.
+pub const THUMBNAIL_COLS: u16 = 30;
+pub const THUMBNAIL_ROWS: u16 = 10;
+
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "webp", "gif", "bmp", "tif", "tiff", "heic", "avif",
+];
+const VIDEO_EXTENSIONS: &[&str] = &[
+    "mp4", "mkv", "mov", "avi", "webm", "m4v", "wmv", "flv", "mpg", "mpeg", "ts",
+];
+const ASCII_RAMP: &[u8] = b" .:-=+*#%@";
+// Kitty limits each graphics escape to 4096 bytes of payload.
+const KITTY_CHUNK: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphicsProtocol {
+    Kitty,
+    Iterm2,
+    Sixel,
+    Ascii,
+}
+
+impl GraphicsProtocol {
+    pub fn detect(mode: ThumbnailMode) -> Option<Self> {
+        match mode {
+            ThumbnailMode::Off => None,
+            ThumbnailMode::Ascii => Some(Self::Ascii),
+            ThumbnailMode::Auto => Some(Self::from_env(|key| std::env::var(key).ok())),
+        }
+    }
+
+    fn from_env(var: impl Fn(&str) -> Option<String>) -> Self {
+        let term = var("TERM").unwrap_or_default();
+        let program = var("TERM_PROGRAM").unwrap_or_default();
+        if var("KITTY_WINDOW_ID").is_some() || term.contains("kitty") || program == "ghostty" {
+            Self::Kitty
+        } else if matches!(program.as_str(), "iTerm.app" | "WezTerm") {
+            Self::Iterm2
+        } else if term.contains("sixel") || term.starts_with("foot") || term.starts_with("mlterm") {
+            Self::Sixel
+        } else {
+            Self::Ascii
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ThumbnailPayload {
+    /// Ready-to-write escape sequence; the caller positions the cursor.
+    Graphics(String),
+    Ascii(Vec<String>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Thumbnail {
+    pub source: PathBuf,
+    pub payload: ThumbnailPayload,
+}
+
+impl Thumbnail {
+    pub fn is_graphics(&self) -> bool {
+        matches!(self.payload, ThumbnailPayload::Graphics(_))
+    }
+}
+
+/// First media file the command reads, resolved against `cwd`. ffmpeg's
+/// first `-i` wins; otherwise the first existing image or video argument.
+pub fn thumbnail_source(plugin: &str, cmd: &str, cwd: &Path) -> Option<PathBuf> {
+    if !matches!(plugin, "ffmpeg" | "libvips") {
+        return None;
+    }
+    let argv = shell_words::split(cmd).ok()?;
+    let existing_media = |arg: &String| {
+        let path = cwd.join(arg);
+        (is_media_path(&path) && path.is_file()).then_some(path)
+    };
+
+    if plugin == "ffmpeg" {
+        return argv
+            .windows(2)
+            .find(|pair| pair[0] == "-i")
+            .and_then(|pair| existing_media(&pair[1]));
+    }
+    argv.iter().skip(1).find_map(existing_media)
+}
+
+/// Renders a frame of `source` with ffmpeg for the given protocol.
+/// `cell_px` is the terminal cell size in pixels, used for pixel protocols.
+pub async fn load_thumbnail(
+    source: PathBuf,
+    protocol: GraphicsProtocol,
+    cell_px: (u16, u16),
+) -> Result<Thumbnail> {
+    let (cols, rows) = (THUMBNAIL_COLS, THUMBNAIL_ROWS);
+    let payload = match protocol {
+        GraphicsProtocol::Ascii => {
+            // Cells are roughly twice as tall as wide: squash before fitting.
+            let filter = format!(
+                "scale=iw:ih/2,{}",
+                fit_filter(cols as u32, rows as u32, "black")
+            );
+            let gray = render_frame(&source, &filter, "gray", "rawvideo").await?;
+            ThumbnailPayload::Ascii(ascii_art(&gray, cols as usize, rows as usize)?)
+        }
+        GraphicsProtocol::Sixel => {
+            let (w, h) = pixel_size(cols, rows, cell_px);
+            let rgb =
+                render_frame(&source, &fit_filter(w, h, "black"), "rgb24", "rawvideo").await?;
+            ThumbnailPayload::Graphics(sixel_sequence(&rgb, w as usize, h as usize)?)
+        }
+        GraphicsProtocol::Kitty | GraphicsProtocol::Iterm2 => {
+            let (w, h) = pixel_size(cols, rows, cell_px);
+            let filter = format!("format=rgba,{}", fit_filter(w, h, "0x00000000"));
+            let png = render_frame(&source, &filter, "rgba", "image2pipe").await?;
+            ThumbnailPayload::Graphics(if protocol == GraphicsProtocol::Kitty {
+                kitty_sequence(&png, cols, rows)
+            } else {
+                iterm2_sequence(&png, cols, rows)
+            })
+        }
+    };
+
+    Ok(Thumbnail { source, payload })
+}
+
+/// Removes every kitty image placed by us; other protocols are cleared by
+/// repainting the cells they occupied.
+pub fn kitty_clear_sequence() -> &'static str {
+    "\x1b_Ga=d,q=2\x1b\\"
+}
+
+fn is_media_path(path: &Path) -> bool {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    IMAGE_EXTENSIONS.contains(&ext.as_str()) || VIDEO_EXTENSIONS.contains(&ext.as_str())
+}
+
+fn is_video_path(path: &Path) -> bool {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    VIDEO_EXTENSIONS.contains(&ext.as_str())
+}
+
+fn pixel_size(cols: u16, rows: u16, cell_px: (u16, u16)) -> (u32, u32) {
+    let (cw, ch) = if cell_px.0 == 0 || cell_px.1 == 0 {
+        (10, 20)
+    } else {
+        cell_px
+    };
+    (cols as u32 * cw as u32, rows as u32 * ch as u32)
+}
+
+/// Scales into `w`x`h` keeping aspect ratio, then pads to exactly that size so
+/// raw output has known dimensions and kitty does not stretch the image.
+fn fit_filter(w: u32, h: u32, pad_color: &str) -> String {
+    format!(
+        "scale={w}:{h}:force_original_aspect_ratio=decrease,\
+         pad={w}:{h}:(ow-iw)/2:(oh-ih)/2:color={pad_color}"
+    )
+}
+
+async fn render_frame(source: &Path, filter: &str, pix_fmt: &str, format: &str) -> Result<Vec<u8>> {
+    // Skip the first second of videos, which is often a black fade-in; retry
+    // from the start for clips shorter than that.
+    let seeks: &[Option<&str>] = if is_video_path(source) {
+        &[Some("1"), None]
+    } else {
+        &[None]
+    };
+
+    dexter_core::replay::refuse_spawn("ffmpeg")?;
+    for seek in seeks {
+        let mut cmd = Command::new("ffmpeg");
+        cmd.args(["-v", "error", "-nostdin"]);
+        if let Some(seek) = seek {
+            cmd.args(["-ss", seek]);
+        }
+        cmd.arg("-i")
+            .arg(source)
+            .args(["-frames:v", "1", "-vf", filter, "-pix_fmt", pix_fmt])
+            .args(["-f", format]);
+        if format == "image2pipe" {
+            cmd.args(["-c:v", "png"]);
+        }
+        let output = cmd
+            .arg("-")
+            .stdin(Stdio::null())
+            .output()
+            .await
+            .context("Failed to run ffmpeg for thumbnail")?;
+        if output.status.success() && !output.stdout.is_empty() {
+            return Ok(output.stdout);
+        }
+        if seek.is_none() {
+            return Err(anyhow!(
+                "ffmpeg could not decode a frame: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+    }
+    Err(anyhow!("ffmpeg could not decode a frame"))
+}
+
+fn ascii_art(gray: &[u8], cols: usize, rows: usize) -> Result<Vec<String>> {
+    if gray.len() < cols * rows {
+        return Err(anyhow!("Thumbnail frame is truncated"));
+    }
+    Ok(gray
+        .chunks(cols)
+        .take(rows)
+        .map(|row| {
+            row.iter()
+                .map(|&v| ASCII_RAMP[v as usize * (ASCII_RAMP.len() - 1) / 255] as char)
+                .collect()
+        })
+        .collect())
+}
+
+fn kitty_sequence(png: &[u8], cols: u16, rows: u16) -> String {
+    let data = base64::engine::general_purpose::STANDARD.encode(png);
+    let chunks: Vec<&[u8]> = data.as_bytes().chunks(KITTY_CHUNK).collect();
+    let mut out = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        let chunk = std::str::from_utf8(chunk).unwrap_or_default();
+        if i == 0 {
+            // q=2 suppresses replies that would otherwise arrive as key input.
+            let _ = write!(
+                out,
+                "\x1b_Ga=T,f=100,q=2,C=1,c={cols},r={rows},m={more};{chunk}\x1b\\"
+            );
+        } else {
+            let _ = write!(out, "\x1b_Gm={more};{chunk}\x1b\\");
+        }
+    }
+    out
+}
+
+fn iterm2_sequence(png: &[u8], cols: u16, rows: u16) -> String {
+    format!(
+        "\x1b]1337;File=inline=1;size={};width={cols};height={rows};preserveAspectRatio=1:{}\x07",
+        png.len(),
+        base64::engine::general_purpose::STANDARD.encode(png)
+    )
+}
+
+/// Encodes rgb24 pixels as sixel using a fixed 6x6x6 color cube, which keeps
+/// the encoder trivial and is plenty for a confirmation thumbnail.
+fn sixel_sequence(rgb: &[u8], width: usize, height: usize) -> Result<String> {
+    if width == 0 || height == 0 || rgb.len() < width * height * 3 {
+        return Err(anyhow!("Thumbnail frame is truncated"));
+    }
+    let level = |v: u8| (v as usize * 5 + 127) / 255;
+    let indices: Vec<usize> = rgb
+        .chunks(3)
+        .take(width * height)
+        .map(|px| level(px[0]) * 36 + level(px[1]) * 6 + level(px[2]))
+        .collect();
+
+    let mut out = format!("\x1bP0;1q\"1;1;{width};{height}");
+    for i in 0..216 {
+        let pct = |l: usize| l * 100 / 5;
+        let _ = write!(
+            out,
+            "#{i};2;{};{};{}",
+            pct(i / 36),
+            pct(i / 6 % 6),
+            pct(i % 6)
+        );
+    }
+
+    for band in (0..height).step_by(6) {
+        let band_rows = (height - band).min(6);
+        let mut used: Vec<usize> = (0..band_rows)
+            .flat_map(|dy| &indices[(band + dy) * width..(band + dy + 1) * width])
+            .copied()
+            .collect();
+        used.sort_unstable();
+        used.dedup();
+
+        for (n, color) in used.iter().enumerate() {
+            if n > 0 {
+                out.push('$');
+            }
+            let _ = write!(out, "#{color}");
+            let mut run: Option<(char, usize)> = None;
+            for x in 0..width {
+                let bits = (0..band_rows)
+                    .filter(|dy| indices[(band + dy) * width + x] == *color)
+                    .fold(0u8, |acc, dy| acc | (1 << dy));
+                let ch = (63 + bits) as char;
+                run = match run {
+                    Some((c, len)) if c == ch => Some((c, len + 1)),
+                    Some(prev) => {
+                        push_sixel_run(&mut out, prev);
+                        Some((ch, 1))
+                    }
+                    None => Some((ch, 1)),
+                };
+            }
+            if let Some(prev) = run {
+                push_sixel_run(&mut out, prev);
+            }
+        }
+        out.push('-');
+    }
+    out.push_str("\x1b\\");
+    Ok(out)
+}
+
+fn push_sixel_run(out: &mut String, (ch, len): (char, usize)) {
+    if len > 3 {
+        let _ = write!(out, "!{len}{ch}");
+    } else {
+        out.extend(std::iter::repeat_n(ch, len));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn detects_protocol_from_environment() {
+        let detect = |pairs: &[(&str, &str)]| {
+            let env: HashMap<String, String> = pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            GraphicsProtocol::from_env(|key| env.get(key).cloned())
+        };
+        assert_eq!(detect(&[("TERM", "xterm-kitty")]), GraphicsProtocol::Kitty);
+        assert_eq!(
+            detect(&[("TERM_PROGRAM", "iTerm.app")]),
+            GraphicsProtocol::Iterm2
+        );
+        assert_eq!(detect(&[("TERM", "foot")]), GraphicsProtocol::Sixel);
+        assert_eq!(
+            detect(&[("TERM", "xterm-256color")]),
+            GraphicsProtocol::Ascii
+        );
+    }
+
+    #[test]
+    fn picks_ffmpeg_input_and_skips_missing_files() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        std::fs::File::create(dir.path().join("clip one.mov"))?;
+        std::fs::File::create(dir.path().join("photo.JPG"))?;
+
+        assert_eq!(
+            thumbnail_source("ffmpeg", "ffmpeg -y -i 'clip one.mov' out.mp4", dir.path()),
+            Some(dir.path().join("clip one.mov"))
+        );
+        assert_eq!(
+            thumbnail_source(
+                "libvips",
+                "vips resize missing.png photo.JPG 0.5",
+                dir.path()
+            ),
+            Some(dir.path().join("photo.JPG"))
+        );
+        assert_eq!(
+            thumbnail_source("pandoc", "pandoc photo.JPG -o a.pdf", dir.path()),
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn sixel_encodes_bands_with_run_lengths() {
+        let red = [255u8, 0, 0].repeat(8 * 7);
+        let sixel = sixel_sequence(&red, 8, 7).unwrap();
+        assert!(sixel.starts_with("\x1bP0;1q\"1;1;8;7"));
+        // Top band covers six rows, the second band only the seventh.
+        assert!(sixel.ends_with("#180!8~-#180!8@-\x1b\\"));
+    }
+
+    #[test]
+    fn ascii_art_maps_brightness_to_ramp() {
+        let art = ascii_art(&[0, 255, 128, 64], 2, 2).unwrap();
+        assert_eq!(art, vec![" @".to_string(), "=:".to_string()]);
+    }
+}
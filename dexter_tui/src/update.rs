@@ -0,0 +1,35 @@
+use anyhow::Result;
+use dexter_core::update;
+
+const CURRENT: &str = env!("CARGO_PKG_VERSION");
+
+/// `dexter update`: replaces this binary with the latest release, or with
+/// `check_only` just says whether there is one.
+pub async fn run_update(check_only: bool) -> Result<()> {
+    let release = update::latest_release(CURRENT).await?;
+    if !release.is_newer_than(CURRENT) {
+        println!("Dexter {} is up to date.", CURRENT);
+        return Ok(());
+    }
+    println!(
+        "Dexter {} is available (installed: {}): {}",
+        release.version(),
+        CURRENT,
+        release.html_url
+    );
+    if check_only {
+        return Ok(());
+    }
+    if release.platform_asset().is_none() {
+        println!("No prebuilt binary for this platform. Install it with:");
+        println!("  {}", release.install_command());
+        return Ok(());
+    }
+    let path = update::self_update(&release, CURRENT).await?;
+    println!(
+        "Installed Dexter {} to {}.",
+        release.version(),
+        path.display()
+    );
+    Ok(())
+}